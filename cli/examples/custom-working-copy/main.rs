@@ -42,6 +42,7 @@ use jj_lib::working_copy::ResetError;
 use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::SnapshotOptions;
 use jj_lib::working_copy::SnapshotStats;
+use jj_lib::working_copy::TrackingPatterns;
 use jj_lib::working_copy::WorkingCopy;
 use jj_lib::working_copy::WorkingCopyFactory;
 use jj_lib::working_copy::WorkingCopyStateError;
@@ -121,6 +122,7 @@ impl ConflictsWorkingCopy {
         state_path: PathBuf,
         operation_id: OperationId,
         workspace_id: WorkspaceId,
+        user_settings: &UserSettings,
     ) -> Result<Self, WorkingCopyStateError> {
         let inner = LocalWorkingCopy::init(
             store,
@@ -128,15 +130,22 @@ impl ConflictsWorkingCopy {
             state_path,
             operation_id,
             workspace_id,
-        )?;
+        )?
+        .with_lock_manager(user_settings.lock_manager());
         Ok(ConflictsWorkingCopy {
             inner: Box::new(inner),
             working_copy_path,
         })
     }
 
-    fn load(store: Arc<Store>, working_copy_path: PathBuf, state_path: PathBuf) -> Self {
-        let inner = LocalWorkingCopy::load(store, working_copy_path.clone(), state_path);
+    fn load(
+        store: Arc<Store>,
+        working_copy_path: PathBuf,
+        state_path: PathBuf,
+        user_settings: &UserSettings,
+    ) -> Self {
+        let inner = LocalWorkingCopy::load(store, working_copy_path.clone(), state_path)
+            .with_lock_manager(user_settings.lock_manager());
         ConflictsWorkingCopy {
             inner: Box::new(inner),
             working_copy_path,
@@ -169,6 +178,10 @@ impl WorkingCopy for ConflictsWorkingCopy {
         self.inner.sparse_patterns()
     }
 
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        self.inner.tracking_patterns()
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let inner = self.inner.start_mutation()?;
         Ok(Box::new(LockedConflictsWorkingCopy {
@@ -188,6 +201,7 @@ impl WorkingCopyFactory for ConflictsWorkingCopyFactory {
         state_path: PathBuf,
         operation_id: OperationId,
         workspace_id: WorkspaceId,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
         Ok(Box::new(ConflictsWorkingCopy::init(
             store,
@@ -195,6 +209,7 @@ impl WorkingCopyFactory for ConflictsWorkingCopyFactory {
             state_path,
             operation_id,
             workspace_id,
+            user_settings,
         )?))
     }
 
@@ -203,11 +218,13 @@ impl WorkingCopyFactory for ConflictsWorkingCopyFactory {
         store: Arc<Store>,
         working_copy_path: PathBuf,
         state_path: PathBuf,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
         Ok(Box::new(ConflictsWorkingCopy::load(
             store,
             working_copy_path,
             state_path,
+            user_settings,
         )))
     }
 }
@@ -287,6 +304,18 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.set_sparse_patterns(new_sparse_patterns, options)
     }
 
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        self.inner.tracking_patterns()
+    }
+
+    fn track(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        self.inner.track(paths)
+    }
+
+    fn untrack(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        self.inner.untrack(paths)
+    }
+
     fn finish(
         self: Box<Self>,
         operation_id: OperationId,
@@ -64,11 +64,15 @@ use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
 use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::eol::EolSettings;
 use jj_lib::fileset;
 use jj_lib::fileset::FilesetDiagnostics;
 use jj_lib::fileset::FilesetExpression;
+use jj_lib::git_lfs::LfsSettings;
 use jj_lib::gitignore::GitIgnoreError;
 use jj_lib::gitignore::GitIgnoreFile;
+use jj_lib::hooks::HookKind;
+use jj_lib::hooks::Hooks;
 use jj_lib::id_prefix::IdPrefixContext;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::MergedTreeValue;
@@ -110,14 +114,17 @@ use jj_lib::revset::RevsetWorkspaceContext;
 use jj_lib::revset::SymbolResolverExtension;
 use jj_lib::revset::UserRevsetExpression;
 use jj_lib::rewrite::restore_tree;
+use jj_lib::rewrite::RebaseError;
 use jj_lib::settings::HumanByteSize;
 use jj_lib::settings::UserSettings;
 use jj_lib::str_util::StringPattern;
 use jj_lib::transaction::Transaction;
 use jj_lib::view::View;
 use jj_lib::working_copy;
+use jj_lib::working_copy::auto_tracking_matcher;
 use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::NewFilesMode;
 use jj_lib::working_copy::SnapshotOptions;
 use jj_lib::working_copy::SnapshotStats;
 use jj_lib::working_copy::UntrackedReason;
@@ -137,12 +144,14 @@ use tracing::instrument;
 use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::prelude::*;
 
+use crate::command_error::checkout_error;
 use crate::command_error::cli_error;
 use crate::command_error::config_error_with_message;
 use crate::command_error::handle_command_result;
 use crate::command_error::internal_error;
 use crate::command_error::internal_error_with_message;
 use crate::command_error::print_parse_diagnostics;
+use crate::command_error::update_stale_error;
 use crate::command_error::user_error;
 use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
@@ -150,6 +159,8 @@ use crate::commit_templater::CommitTemplateLanguage;
 use crate::commit_templater::CommitTemplateLanguageExtension;
 use crate::complete;
 use crate::config::config_from_environment;
+use crate::config::config_layer_warnings;
+use crate::config::known_config_table;
 use crate::config::parse_config_args;
 use crate::config::ConfigArgKind;
 use crate::config::ConfigEnv;
@@ -443,7 +454,7 @@ impl CommandHelper {
                 // auto-update-stale, so let's do that now. We need to do it up here, not at a
                 // lower level (e.g. inside snapshot_working_copy()) to avoid recursive locking
                 // of the working copy.
-                self.recover_stale_working_copy(ui)?
+                self.recover_stale_working_copy(ui, false)?
             }
         };
 
@@ -465,6 +476,14 @@ impl CommandHelper {
         WorkspaceCommandHelper::new(ui, workspace, repo, env, self.is_at_head_operation())
     }
 
+    pub fn store_factories(&self) -> &StoreFactories {
+        &self.data.store_factories
+    }
+
+    pub fn working_copy_factories(&self) -> &WorkingCopyFactories {
+        &self.data.working_copy_factories
+    }
+
     pub fn get_working_copy_factory(&self) -> Result<&dyn WorkingCopyFactory, CommandError> {
         let loader = self.workspace_loader()?;
 
@@ -510,12 +529,16 @@ impl CommandHelper {
             .map_err(|err| map_workspace_load_error(err, None))
     }
 
+    /// Unless `force` is set, changes in the stale working copy are
+    /// snapshotted before it's updated. Otherwise they're discarded.
+    ///
     /// Note that unless you have a good reason not to do so, you should always
     /// call [`print_snapshot_stats`] with the [`SnapshotStats`] returned by
     /// this function to present possible untracked files to the user.
     pub fn recover_stale_working_copy(
         &self,
         ui: &Ui,
+        force: bool,
     ) -> Result<(WorkspaceCommandHelper, SnapshotStats), CommandError> {
         let workspace = self.load_workspace()?;
         let op_id = workspace.working_copy().operation_id();
@@ -529,41 +552,39 @@ impl CommandHelper {
                 // operation, then merge the divergent operations. The wc_commit_id of the
                 // merged repo wouldn't change because the old one wins, but it's probably
                 // fine if we picked the new wc_commit_id.
-                let stats = workspace_command
-                    .maybe_snapshot_impl(ui)
-                    .map_err(|err| err.into_command_error())?;
-
-                let wc_commit_id = workspace_command.get_wc_commit_id().unwrap();
-                let repo = workspace_command.repo().clone();
-                let stale_wc_commit = repo.store().get_commit(wc_commit_id)?;
+                let stats = if force {
+                    SnapshotStats::default()
+                } else {
+                    workspace_command
+                        .maybe_snapshot_impl(ui)
+                        .map_err(|err| err.into_command_error())?
+                };
 
                 let mut workspace_command = self.workspace_helper_no_snapshot(ui)?;
-                let checkout_options = workspace_command.checkout_options();
+                let checkout_options = CheckoutOptions {
+                    force,
+                    ..workspace_command.checkout_options()
+                };
 
                 let repo = workspace_command.repo().clone();
-                let (mut locked_ws, desired_wc_commit) =
-                    workspace_command.unchecked_start_working_copy_mutation()?;
-                match WorkingCopyFreshness::check_stale(
-                    locked_ws.locked_wc(),
-                    &desired_wc_commit,
-                    &repo,
-                )? {
-                    WorkingCopyFreshness::Fresh | WorkingCopyFreshness::Updated(_) => {
+                workspace_command.check_working_copy_writable()?;
+                let desired_wc_commit = match workspace_command.get_wc_commit_id() {
+                    Some(wc_commit_id) => repo.store().get_commit(wc_commit_id)?,
+                    None => return Err(user_error("Nothing checked out in this workspace")),
+                };
+                match workspace_command
+                    .workspace
+                    .update_stale(&repo, &checkout_options)
+                    .map_err(|err| {
+                        update_stale_error(err, workspace_command.env.path_converter())
+                    })? {
+                    None => {
                         writeln!(
                             ui.status(),
                             "Attempted recovery, but the working copy is not stale"
                         )?;
                     }
-                    WorkingCopyFreshness::WorkingCopyStale
-                    | WorkingCopyFreshness::SiblingOperation => {
-                        let stats = update_stale_working_copy(
-                            locked_ws,
-                            repo.op_id().clone(),
-                            &stale_wc_commit,
-                            &desired_wc_commit,
-                            &checkout_options,
-                        )?;
-
+                    Some(stats) => {
                         // TODO: Share this code with new/checkout somehow.
                         if let Some(mut formatter) = ui.status_formatter() {
                             write!(formatter, "Working copy now at: ")?;
@@ -660,7 +681,7 @@ impl CommandHelper {
                         }
                     }
                     Ok(tx
-                        .write("reconcile divergent operations")
+                        .write("reconcile divergent operations")?
                         .leave_unpublished()
                         .operation()
                         .clone())
@@ -784,6 +805,8 @@ pub struct WorkspaceCommandEnvironment {
     immutable_heads_expression: Rc<UserRevsetExpression>,
     short_prefixes_expression: Option<Rc<UserRevsetExpression>>,
     conflict_marker_style: ConflictMarkerStyle,
+    eol_settings: EolSettings,
+    lfs_settings: LfsSettings,
 }
 
 impl WorkspaceCommandEnvironment {
@@ -806,6 +829,8 @@ impl WorkspaceCommandEnvironment {
             immutable_heads_expression: RevsetExpression::root(),
             short_prefixes_expression: None,
             conflict_marker_style: settings.get("ui.conflict-marker-style")?,
+            eol_settings: EolSettings::from_settings(settings)?,
+            lfs_settings: LfsSettings::from_settings(settings)?,
         };
         env.immutable_heads_expression = env.load_immutable_heads_expression(ui)?;
         env.short_prefixes_expression = env.load_short_prefixes_expression(ui)?;
@@ -825,13 +850,13 @@ impl WorkspaceCommandEnvironment {
             path_converter: &self.path_converter,
             workspace_id: &self.workspace_id,
         };
-        let now = if let Some(timestamp) = self.settings.commit_timestamp() {
-            chrono::Local
-                .timestamp_millis_opt(timestamp.timestamp.0)
-                .unwrap()
-        } else {
-            chrono::Local::now()
-        };
+        let timestamp = self
+            .settings
+            .commit_timestamp()
+            .unwrap_or_else(|| self.settings.clock().now());
+        let now = chrono::Local
+            .timestamp_millis_opt(timestamp.timestamp.0)
+            .unwrap();
         RevsetParseContext::new(
             &self.revset_aliases_map,
             self.settings.user_email(),
@@ -868,6 +893,16 @@ impl WorkspaceCommandEnvironment {
         self.conflict_marker_style
     }
 
+    /// User-configured line ending conversion of working-copy files
+    pub fn eol_settings(&self) -> &EolSettings {
+        &self.eol_settings
+    }
+
+    /// User-configured Git LFS conversion of working-copy files
+    pub fn lfs_settings(&self) -> &LfsSettings {
+        &self.lfs_settings
+    }
+
     fn load_immutable_heads_expression(
         &self,
         ui: &Ui,
@@ -1003,6 +1038,16 @@ impl WorkspaceCommandEnvironment {
     }
 }
 
+/// Format of the output of commands that support `--output`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON, using the same schema as the `json()` template function
+    Json,
+}
+
 /// Provides utilities for writing a command that works on a [`Workspace`]
 /// (which most commands do).
 pub struct WorkspaceCommandHelper {
@@ -1014,6 +1059,9 @@ pub struct WorkspaceCommandHelper {
     op_summary_template_text: String,
     may_update_working_copy: bool,
     working_copy_shared_with_git: bool,
+    // Tree recorded by the snapshot operation created by this command, to be
+    // linked from the operation of the command itself.
+    snapshot_tree_id: Option<MergedTreeId>,
 }
 
 enum SnapshotWorkingCopyError {
@@ -1062,6 +1110,7 @@ impl WorkspaceCommandHelper {
             op_summary_template_text,
             may_update_working_copy,
             working_copy_shared_with_git,
+            snapshot_tree_id: None,
         };
         // Parse commit_summary template early to report error before starting
         // mutable operation.
@@ -1243,9 +1292,13 @@ impl WorkspaceCommandHelper {
         &self.env
     }
 
-    pub fn checkout_options(&self) -> CheckoutOptions {
+    pub fn checkout_options(&self) -> CheckoutOptions<'static> {
         CheckoutOptions {
             conflict_marker_style: self.env.conflict_marker_style(),
+            progress: None,
+            eol_settings: self.env.eol_settings().clone(),
+            lfs_settings: self.env.lfs_settings().clone(),
+            force: false,
         }
     }
 
@@ -1365,42 +1418,26 @@ to the current parents may contain changes from multiple commits.
         Ok(FilesetExpression::union_all(expressions))
     }
 
+    pub fn new_files_mode(&self) -> Result<NewFilesMode, ConfigGetError> {
+        NewFilesMode::from_settings(self.settings())
+    }
+
     pub fn auto_tracking_matcher(&self, ui: &Ui) -> Result<Box<dyn Matcher>, CommandError> {
         let mut diagnostics = FilesetDiagnostics::new();
-        let pattern = self.settings().get_string("snapshot.auto-track")?;
-        let expression = fileset::parse(
-            &mut diagnostics,
-            &pattern,
-            &RepoPathUiConverter::Fs {
-                cwd: "".into(),
-                base: "".into(),
-            },
-        )?;
+        let matcher = auto_tracking_matcher(self.settings(), &mut diagnostics)?;
         print_parse_diagnostics(ui, "In `snapshot.auto-track`", &diagnostics)?;
-        Ok(expression.to_matcher())
+        Ok(matcher)
     }
 
     pub fn snapshot_options_with_start_tracking_matcher<'a>(
         &self,
         start_tracking_matcher: &'a dyn Matcher,
     ) -> Result<SnapshotOptions<'a>, CommandError> {
-        let base_ignores = self.base_ignores()?;
-        let fsmonitor_settings = self.settings().fsmonitor_settings()?;
-        let HumanByteSize(mut max_new_file_size) = self
-            .settings()
-            .get_value_with("snapshot.max-new-file-size", TryInto::try_into)?;
-        if max_new_file_size == 0 {
-            max_new_file_size = u64::MAX;
-        }
-        let conflict_marker_style = self.env.conflict_marker_style();
-        Ok(SnapshotOptions {
-            base_ignores,
-            fsmonitor_settings,
-            progress: None,
+        Ok(SnapshotOptions::from_settings(
+            self.settings(),
+            self.base_ignores()?,
             start_tracking_matcher,
-            max_new_file_size,
-            conflict_marker_style,
-        })
+        )?)
     }
 
     pub(crate) fn path_converter(&self) -> &RepoPathUiConverter {
@@ -1415,44 +1452,7 @@ to the current parents may contain changes from multiple commits.
     #[cfg(feature = "git")]
     #[instrument(skip_all)]
     pub fn base_ignores(&self) -> Result<Arc<GitIgnoreFile>, GitIgnoreError> {
-        let get_excludes_file_path = |config: &gix::config::File| -> Option<PathBuf> {
-            // TODO: maybe use path() and interpolate(), which can process non-utf-8
-            // path on Unix.
-            if let Some(value) = config.string("core.excludesFile") {
-                let path = str::from_utf8(&value)
-                    .ok()
-                    .map(jj_lib::file_util::expand_home_path)?;
-                // The configured path is usually absolute, but if it's relative,
-                // the "git" command would read the file at the work-tree directory.
-                Some(self.workspace_root().join(path))
-            } else {
-                xdg_config_home().ok().map(|x| x.join("git").join("ignore"))
-            }
-        };
-
-        fn xdg_config_home() -> Result<PathBuf, std::env::VarError> {
-            if let Ok(x) = std::env::var("XDG_CONFIG_HOME") {
-                if !x.is_empty() {
-                    return Ok(PathBuf::from(x));
-                }
-            }
-            std::env::var("HOME").map(|x| Path::new(&x).join(".config"))
-        }
-
-        let mut git_ignores = GitIgnoreFile::empty();
-        if let Ok(git_backend) = jj_lib::git::get_git_backend(self.repo().store()) {
-            let git_repo = git_backend.git_repo();
-            if let Some(excludes_file_path) = get_excludes_file_path(&git_repo.config_snapshot()) {
-                git_ignores = git_ignores.chain_with_file("", excludes_file_path)?;
-            }
-            git_ignores = git_ignores
-                .chain_with_file("", git_backend.git_repo_path().join("info").join("exclude"))?;
-        } else if let Ok(git_config) = gix::config::File::from_globals() {
-            if let Some(excludes_file_path) = get_excludes_file_path(&git_config) {
-                git_ignores = git_ignores.chain_with_file("", excludes_file_path)?;
-            }
-        }
-        Ok(git_ignores)
+        jj_lib::git::base_ignores(self.repo().store(), self.workspace_root())
     }
 
     /// Creates textual diff renderer of the specified `formats`.
@@ -1961,6 +1961,8 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             let mut tx =
                 start_repo_transaction(&self.user_repo.repo, self.env.command.string_args());
             tx.set_is_snapshot(true);
+            tx.set_working_copy_tree_id(&new_tree_id);
+            self.snapshot_tree_id = Some(new_tree_id.clone());
             let mut_repo = tx.repo_mut();
             let commit = mut_repo
                 .rewrite_commit(&wc_commit)
@@ -1989,6 +1991,9 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
                 crate::git_util::print_failed_git_export(ui, &refs)
                     .map_err(snapshot_command_error)?;
             }
+            #[cfg(feature = "git")]
+            mirror_git_refs(ui, tx.base_repo().settings(), tx.repo())
+                .map_err(snapshot_command_error)?;
 
             let repo = tx
                 .commit("snapshot working copy")
@@ -2015,6 +2020,16 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             maybe_old_commit,
             new_commit,
             &checkout_options,
+            self.env.path_converter(),
+        )?;
+        self.run_hook(
+            ui,
+            HookKind::PostCheckout,
+            &serde_json::json!({
+                "workspace_id": self.workspace_id().as_str(),
+                "old_commit_id": maybe_old_commit.map(|commit| commit.id().hex()),
+                "new_commit_id": new_commit.id().hex(),
+            }),
         )?;
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
@@ -2047,8 +2062,32 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
         Ok(())
     }
 
+    /// Runs the `kind` of repo-local hook, if any, with the `payload` on its
+    /// stdin. Failures of hooks which can't abort the action are reported as
+    /// warnings.
+    pub fn run_hook(
+        &self,
+        ui: &Ui,
+        kind: HookKind,
+        payload: &serde_json::Value,
+    ) -> Result<(), CommandError> {
+        let hooks = Hooks::new(self.workspace_root(), self.settings())?;
+        match hooks.run(kind, payload) {
+            Ok(()) => Ok(()),
+            Err(err) if !kind.can_abort() => {
+                writeln!(ui.warning_default(), "{err}")?;
+                Ok(())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
     pub fn start_transaction(&mut self) -> WorkspaceCommandTransaction {
-        let tx = start_repo_transaction(self.repo(), self.env.command.string_args());
+        let mut tx = start_repo_transaction(self.repo(), self.env.command.string_args());
+        if let Some(tree_id) = self.snapshot_tree_id.take() {
+            tx.set_working_copy_tree_id(&tree_id);
+            tx.set_includes_snapshot(true);
+        }
         let id_prefix_context = mem::take(&mut self.user_repo.id_prefix_context);
         WorkspaceCommandTransaction {
             helper: self,
@@ -2103,6 +2142,19 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             .map(|commit_id| tx.repo().store().get_commit(commit_id))
             .transpose()?;
 
+        // The hook runs before refs are exported so that a rejected operation
+        // doesn't update the Git repo.
+        let description = description.into();
+        self.run_hook(
+            ui,
+            HookKind::PreCommitOperation,
+            &serde_json::json!({
+                "workspace_id": self.workspace_id().as_str(),
+                "base_operation_id": old_repo.op_id().hex(),
+                "description": description,
+            }),
+        )?;
+
         #[cfg(feature = "git")]
         if self.working_copy_shared_with_git {
             if let Some(wc_commit) = &maybe_new_wc_commit {
@@ -2111,6 +2163,8 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             let refs = jj_lib::git::export_refs(tx.repo_mut())?;
             crate::git_util::print_failed_git_export(ui, &refs)?;
         }
+        #[cfg(feature = "git")]
+        mirror_git_refs(ui, tx.base_repo().settings(), tx.repo())?;
 
         self.user_repo = ReadonlyUserRepo::new(tx.commit(description)?);
 
@@ -2410,6 +2464,19 @@ impl WorkspaceCommandTransaction<'_> {
         self.tx.repo_mut().edit(workspace_id, commit)
     }
 
+    /// Replaces the view, keeping working-copy changes made since
+    /// `since_view`. See
+    /// [`Transaction::restore_view_preserving_working_copies`].
+    pub fn restore_view_preserving_working_copies(
+        &mut self,
+        view: jj_lib::op_store::View,
+        since_view: &jj_lib::op_store::View,
+    ) -> Result<(), RebaseError> {
+        self.id_prefix_context.take(); // invalidate
+        self.tx
+            .restore_view_preserving_working_copies(view, since_view)
+    }
+
     pub fn format_commit_summary(&self, commit: &Commit) -> String {
         let mut output = Vec::new();
         self.write_commit_summary(&mut PlainTextFormatter::new(&mut output), commit)
@@ -2537,6 +2604,12 @@ jj git init --colocate",
 
 pub fn start_repo_transaction(repo: &Arc<ReadonlyRepo>, string_args: &[String]) -> Transaction {
     let mut tx = repo.start_transaction();
+    tx.set_tag("args".to_string(), format_args_tag(string_args));
+    tx
+}
+
+/// Formats the command-line arguments as recorded in the "args" operation tag.
+pub fn format_args_tag(string_args: &[String]) -> String {
     // TODO: Either do better shell-escaping here or store the values in some list
     // type (which we currently don't have).
     let shell_escape = |arg: &String| {
@@ -2561,34 +2634,7 @@ pub fn start_repo_transaction(repo: &Arc<ReadonlyRepo>, string_args: &[String])
     };
     let mut quoted_strings = vec!["jj".to_string()];
     quoted_strings.extend(string_args.iter().skip(1).map(shell_escape));
-    tx.set_tag("args".to_string(), quoted_strings.join(" "));
-    tx
-}
-
-fn update_stale_working_copy(
-    mut locked_ws: LockedWorkspace,
-    op_id: OperationId,
-    stale_commit: &Commit,
-    new_commit: &Commit,
-    options: &CheckoutOptions,
-) -> Result<CheckoutStats, CommandError> {
-    // The same check as start_working_copy_mutation(), but with the stale
-    // working-copy commit.
-    if stale_commit.tree_id() != locked_ws.locked_wc().old_tree_id() {
-        return Err(user_error("Concurrent working copy operation. Try again."));
-    }
-    let stats = locked_ws
-        .locked_wc()
-        .check_out(new_commit, options)
-        .map_err(|err| {
-            internal_error_with_message(
-                format!("Failed to check out commit {}", new_commit.id().hex()),
-                err,
-            )
-        })?;
-    locked_ws.finish(op_id)?;
-
-    Ok(stats)
+    quoted_strings.join(" ")
 }
 
 #[instrument(skip_all)]
@@ -2851,12 +2897,26 @@ pub fn print_trackable_remote_bookmarks(ui: &Ui, view: &View) -> io::Result<()>
     Ok(())
 }
 
+/// Mirrors bookmarks and working-copy commits to `refs/jj/` in the backing Git
+/// repo if `git.mirror-refs` is enabled.
+#[cfg(feature = "git")]
+fn mirror_git_refs(ui: &Ui, settings: &UserSettings, repo: &dyn Repo) -> Result<(), CommandError> {
+    if !settings.git_settings()?.mirror_refs || jj_lib::git::get_git_backend(repo.store()).is_err()
+    {
+        return Ok(());
+    }
+    let refs = jj_lib::git::mirror_refs(repo)?;
+    crate::git_util::print_failed_git_export(ui, &refs)?;
+    Ok(())
+}
+
 pub fn update_working_copy(
     repo: &Arc<ReadonlyRepo>,
     workspace: &mut Workspace,
     old_commit: Option<&Commit>,
     new_commit: &Commit,
     options: &CheckoutOptions,
+    path_converter: &RepoPathUiConverter,
 ) -> Result<CheckoutStats, CommandError> {
     let old_tree_id = old_commit.map(|commit| commit.tree_id().clone());
     // TODO: CheckoutError::ConcurrentCheckout should probably just result in a
@@ -2868,12 +2928,7 @@ pub fn update_working_copy(
             new_commit,
             options,
         )
-        .map_err(|err| {
-            internal_error_with_message(
-                format!("Failed to check out commit {}", new_commit.id().hex()),
-                err,
-            )
-        })?;
+        .map_err(|err| checkout_error(new_commit.id(), err, path_converter))?;
     Ok(stats)
 }
 
@@ -3889,6 +3944,25 @@ impl CliRunner {
         for desc in &last_config_migration_descriptions {
             writeln!(ui.warning_default(), "Deprecated config: {desc}")?;
         }
+        // `jj config` can be used to manage arbitrary variables.
+        if matches.subcommand_name() != Some("config") {
+            let known_config = known_config_table(
+                config
+                    .layers_for(ConfigSource::Default)
+                    .iter()
+                    .map(AsRef::as_ref),
+            );
+            // Conditional tables are resolved into separate layers sharing the
+            // same source file.
+            let warnings = [ConfigSource::User, ConfigSource::Repo]
+                .into_iter()
+                .flat_map(|source| config.layers_for(source))
+                .flat_map(|layer| config_layer_warnings(layer, &known_config))
+                .unique();
+            for warning in warnings {
+                writeln!(ui.warning_default(), "{warning}")?;
+            }
+        }
 
         if args.global_args.repository.is_some() {
             warn_if_args_mismatch(ui, &self.app, &config, &string_args)?;
@@ -24,6 +24,7 @@ use std::sync::Arc;
 use itertools::Itertools as _;
 use jj_lib::absorb::AbsorbError;
 use jj_lib::backend::BackendError;
+use jj_lib::backend::CommitId;
 use jj_lib::config::ConfigFileSaveError;
 use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigLoadError;
@@ -33,6 +34,8 @@ use jj_lib::fileset::FilePatternParseError;
 use jj_lib::fileset::FilesetParseError;
 use jj_lib::fileset::FilesetParseErrorKind;
 use jj_lib::gitignore::GitIgnoreError;
+use jj_lib::hooks::HookError;
+use jj_lib::object_id::ObjectId;
 use jj_lib::op_heads_store::OpHeadResolutionError;
 use jj_lib::op_heads_store::OpHeadsStoreError;
 use jj_lib::op_store::OpStoreError;
@@ -43,17 +46,24 @@ use jj_lib::repo::EditCommitError;
 use jj_lib::repo::RepoLoaderError;
 use jj_lib::repo::RewriteRootCommit;
 use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::repo_path::UiPathParseError;
+use jj_lib::resolution_cache::ResolutionCacheError;
 use jj_lib::revset;
 use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetParseError;
 use jj_lib::revset::RevsetParseErrorKind;
 use jj_lib::revset::RevsetResolutionError;
+use jj_lib::rewrite::RebaseError;
 use jj_lib::str_util::StringPatternParseError;
+use jj_lib::transaction::TransactionCommitError;
 use jj_lib::view::RenameWorkspaceError;
+use jj_lib::working_copy::AutoTrackingError;
+use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::RecoverWorkspaceError;
 use jj_lib::working_copy::ResetError;
 use jj_lib::working_copy::SnapshotError;
+use jj_lib::working_copy::UpdateStaleError;
 use jj_lib::working_copy::WorkingCopyStateError;
 use jj_lib::workspace::WorkspaceInitError;
 use thiserror::Error;
@@ -322,12 +332,39 @@ impl From<BackendError> for CommandError {
     }
 }
 
+impl From<RebaseError> for CommandError {
+    fn from(err: RebaseError) -> Self {
+        match err {
+            RebaseError::Cancelled(_) | RebaseError::Cycle(_) => user_error(err),
+            RebaseError::Backend(err) => err.into(),
+        }
+    }
+}
+
+impl From<ResolutionCacheError> for CommandError {
+    fn from(err: ResolutionCacheError) -> Self {
+        match err {
+            ResolutionCacheError::Backend(err) => err.into(),
+            ResolutionCacheError::Path(err) => err.into(),
+        }
+    }
+}
+
 impl From<OpHeadsStoreError> for CommandError {
     fn from(err: OpHeadsStoreError) -> Self {
         internal_error_with_message("Unexpected error from operation heads store", err)
     }
 }
 
+impl From<TransactionCommitError> for CommandError {
+    fn from(err: TransactionCommitError) -> Self {
+        match err {
+            TransactionCommitError::Backend(err) => err.into(),
+            TransactionCommitError::OpHeadsStore(err) => err.into(),
+        }
+    }
+}
+
 impl From<WorkspaceInitError> for CommandError {
     fn from(err: WorkspaceInitError) -> Self {
         match err {
@@ -346,6 +383,9 @@ impl From<WorkspaceInitError> for CommandError {
             WorkspaceInitError::OpHeadsStore(err) => {
                 user_error_with_message("Failed to record initial operation", err)
             }
+            WorkspaceInitError::TransactionCommit(err) => {
+                user_error_with_message("Failed to record initial operation", err)
+            }
             WorkspaceInitError::Backend(err) => {
                 user_error_with_message("Failed to access the repository", err)
             }
@@ -487,6 +527,7 @@ mod git {
     use jj_lib::git::GitPushError;
     use jj_lib::git::GitRemoteManagementError;
     use jj_lib::git::UnexpectedGitBackendError;
+    use jj_lib::workspace::GitCloneError;
 
     use super::*;
 
@@ -571,6 +612,12 @@ jj currently does not support partial clones. To use jj with this repository, tr
                 GitPushError::InternalGitError(err) => map_git2_error(err),
                 GitPushError::Subprocess(_) => user_error(err),
                 GitPushError::UnexpectedBackend(_) => user_error(err),
+                GitPushError::NoSuchChange(_) => user_error(err),
+                GitPushError::DivergentChange(_) => user_error(err),
+                GitPushError::ConflictedRemoteBookmark(_) => user_error_with_hint(
+                    err,
+                    "Run `jj git fetch` to update the conflicted remote bookmark.",
+                ),
             }
         }
     }
@@ -587,6 +634,30 @@ jj currently does not support partial clones. To use jj with this repository, tr
         }
     }
 
+    impl From<GitCloneError> for CommandError {
+        fn from(err: GitCloneError) -> Self {
+            match err {
+                GitCloneError::DestinationNotEmpty(_) => {
+                    user_error("Destination path exists and is not an empty directory")
+                }
+                GitCloneError::Path(err) => err.into(),
+                GitCloneError::WorkspaceInit(err) => err.into(),
+                GitCloneError::WorkspaceLoad(_) => internal_error(err),
+                GitCloneError::RepoLoader(err) => err.into(),
+                GitCloneError::Config(err) => err.into(),
+                GitCloneError::RemoteManagement(err) => err.into(),
+                GitCloneError::FetchPrepare(err) => err.into(),
+                GitCloneError::Fetch(err) => err.into(),
+                GitCloneError::Import(err) => err.into(),
+                GitCloneError::Backend(err) => err.into(),
+                GitCloneError::Rebase(err) => err.into(),
+                GitCloneError::CheckOutCommit(err) => err.into(),
+                GitCloneError::TransactionCommit(err) => err.into(),
+                GitCloneError::Checkout(_) => internal_error(err),
+            }
+        }
+    }
+
     fn map_git2_error(err: git2::Error) -> CommandError {
         if err.class() == git2::ErrorClass::Ssh {
             let hint = if err.code() == git2::ErrorCode::Certificate
@@ -623,11 +694,20 @@ impl From<FilesetParseError> for CommandError {
     }
 }
 
+impl From<AutoTrackingError> for CommandError {
+    fn from(err: AutoTrackingError) -> Self {
+        match err {
+            AutoTrackingError::Config(err) => err.into(),
+            AutoTrackingError::Parse(err) => err.into(),
+        }
+    }
+}
+
 impl From<RecoverWorkspaceError> for CommandError {
     fn from(err: RecoverWorkspaceError) -> Self {
         match err {
             RecoverWorkspaceError::Backend(err) => err.into(),
-            RecoverWorkspaceError::OpHeadsStore(err) => err.into(),
+            RecoverWorkspaceError::TransactionCommit(err) => err.into(),
             RecoverWorkspaceError::Reset(err) => err.into(),
             RecoverWorkspaceError::RewriteRootCommit(err) => err.into(),
             err @ RecoverWorkspaceError::WorkspaceMissingWorkingCopy(_) => user_error(err),
@@ -635,6 +715,53 @@ impl From<RecoverWorkspaceError> for CommandError {
     }
 }
 
+/// Converts an error from updating a stale working copy. Paths are formatted
+/// for display by `path_converter`.
+pub fn update_stale_error(
+    err: UpdateStaleError,
+    path_converter: &RepoPathUiConverter,
+) -> CommandError {
+    match err {
+        UpdateStaleError::OpStore(err) => err.into(),
+        UpdateStaleError::RepoLoader(err) => err.into(),
+        UpdateStaleError::Backend(err) => err.into(),
+        UpdateStaleError::WorkingCopyState(err) => err.into(),
+        UpdateStaleError::ConcurrentOperation => {
+            user_error("Concurrent working copy operation. Try again.")
+        }
+        UpdateStaleError::Checkout { commit_id, source } => {
+            checkout_error(&commit_id, source, path_converter)
+        }
+        err @ UpdateStaleError::WorkspaceMissingWorkingCopy(_) => user_error(err),
+    }
+}
+
+/// Converts an error from checking out `commit_id` into the working copy.
+/// Paths are formatted for display by `path_converter`.
+pub fn checkout_error(
+    commit_id: &CommitId,
+    err: CheckoutError,
+    path_converter: &RepoPathUiConverter,
+) -> CommandError {
+    match err {
+        CheckoutError::ChangedFiles { paths } => user_error_with_hint(
+            format!(
+                "Refusing to overwrite files changed since the last snapshot: {}",
+                paths
+                    .iter()
+                    .map(|path| path_converter.format_file_path(path))
+                    .join(", ")
+            ),
+            "Run `jj workspace update-stale` to snapshot the changes and update the working copy, \
+             or `jj workspace update-stale --force` to discard them.",
+        ),
+        err => internal_error_with_message(
+            format!("Failed to check out commit {}", commit_id.hex()),
+            err,
+        ),
+    }
+}
+
 impl From<RevsetParseError> for CommandError {
     fn from(err: RevsetParseError) -> Self {
         let hint = revset_parse_error_hint(&err);
@@ -700,6 +827,12 @@ impl From<GitIgnoreError> for CommandError {
     }
 }
 
+impl From<HookError> for CommandError {
+    fn from(err: HookError) -> Self {
+        user_error(err)
+    }
+}
+
 impl From<ParseBulkEditMessageError> for CommandError {
     fn from(err: ParseBulkEditMessageError) -> Self {
         user_error(err)
@@ -777,6 +910,7 @@ fn file_pattern_parse_error_hint(err: &FilePatternParseError) -> Option<String>
         }
         FilePatternParseError::RelativePath(_) => None,
         FilePatternParseError::GlobPattern(_) => None,
+        FilePatternParseError::RegexPattern(_) => None,
     }
 }
 
@@ -16,7 +16,7 @@ use bstr::ByteVec as _;
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::object_id::ObjectId;
-use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::rewrite::back_out_commit;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -101,22 +101,13 @@ pub(crate) fn cmd_backout(
             .collect_vec()
     };
     let mut tx = workspace_command.start_transaction();
-    let mut new_base_tree = merge_commit_trees(tx.repo(), &parents)?;
-
     for (commit_to_back_out, new_commit_description) in
         commits_to_back_out_with_new_commit_descriptions
     {
-        let old_base_tree = commit_to_back_out.parent_tree(tx.repo())?;
-        let old_tree = commit_to_back_out.tree()?;
-        let new_tree = new_base_tree.merge(&old_tree, &old_base_tree)?;
-        let new_parent_ids = parents.iter().map(|commit| commit.id().clone()).collect();
-        let new_commit = tx
-            .repo_mut()
-            .new_commit(new_parent_ids, new_tree.id())
+        let new_commit = back_out_commit(tx.repo_mut(), &commit_to_back_out, &parents)?
             .set_description(new_commit_description)
             .write()?;
         parents = vec![new_commit];
-        new_base_tree = new_tree;
     }
     tx.finish(ui, transaction_description)?;
 
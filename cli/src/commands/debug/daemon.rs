@@ -0,0 +1,138 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(unix)]
+use std::collections::HashMap;
+use std::fmt::Debug;
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::io::Write as _;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+#[cfg(unix)]
+use jj_lib::daemon;
+#[cfg(unix)]
+use jj_lib::daemon::Daemon;
+#[cfg(unix)]
+use jj_lib::daemon::DaemonOptions;
+#[cfg(unix)]
+use jj_lib::fileset::FilesetDiagnostics;
+#[cfg(all(unix, feature = "git"))]
+use jj_lib::repo::Repo as _;
+#[cfg(unix)]
+use jj_lib::working_copy::auto_tracking_matcher;
+#[cfg(unix)]
+use jj_lib::working_copy::SnapshotOptions;
+
+#[cfg(unix)]
+use crate::cli_util::format_args_tag;
+use crate::cli_util::CommandHelper;
+#[cfg(unix)]
+use crate::command_error::internal_error_with_message;
+#[cfg(unix)]
+use crate::command_error::print_parse_diagnostics;
+use crate::command_error::user_error;
+#[cfg(all(unix, feature = "watchman"))]
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Serve snapshot and status requests for the workspace over a Unix socket
+///
+/// The daemon keeps running until a client asks it to shut down.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugDaemonArgs {}
+
+#[cfg(unix)]
+pub fn cmd_debug_daemon(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &DebugDaemonArgs,
+) -> Result<(), CommandError> {
+    let workspace = command.load_workspace()?;
+    let op_head = command.resolve_operation(ui, workspace.repo_loader())?;
+    let repo = workspace.repo_loader().load_at(&op_head)?;
+    let settings = command.settings();
+
+    let mut diagnostics = FilesetDiagnostics::new();
+    let start_tracking_matcher = auto_tracking_matcher(settings, &mut diagnostics)?;
+    print_parse_diagnostics(ui, "In `snapshot.auto-track`", &diagnostics)?;
+    #[cfg(feature = "git")]
+    let base_ignores = jj_lib::git::base_ignores(repo.store(), workspace.workspace_root())?;
+    #[cfg(not(feature = "git"))]
+    let base_ignores = jj_lib::gitignore::GitIgnoreFile::empty();
+    let SnapshotOptions {
+        base_ignores,
+        fsmonitor_settings,
+        max_new_file_size,
+        conflict_marker_style,
+        path_normalization,
+        eol_settings,
+        lfs_settings,
+        ..
+    } = SnapshotOptions::from_settings(settings, base_ignores, start_tracking_matcher.as_ref())?;
+    let options = DaemonOptions {
+        base_ignores,
+        fsmonitor_settings,
+        start_tracking_matcher,
+        max_new_file_size,
+        conflict_marker_style,
+        path_normalization,
+        eol_settings,
+        lfs_settings,
+        op_tags: HashMap::from([("args".to_owned(), format_args_tag(command.string_args()))]),
+        client_timeout: daemon::DEFAULT_CLIENT_TIMEOUT,
+    };
+
+    let socket_path = daemon::socket_path(workspace.workspace_root())?;
+    let listener = match UnixListener::bind(&socket_path) {
+        Ok(listener) => listener,
+        Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+            // The socket of a daemon which didn't shut down cleanly is left
+            // behind. Nothing accepts connections on it anymore.
+            if UnixStream::connect(&socket_path).is_ok() {
+                return Err(user_error(format!(
+                    "A daemon is already serving this workspace at {}",
+                    socket_path.display()
+                )));
+            }
+            std::fs::remove_file(&socket_path)?;
+            UnixListener::bind(&socket_path)?
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut daemon = Daemon::new(workspace, repo, options);
+    #[cfg(feature = "watchman")]
+    daemon
+        .watch_working_copy()
+        .map_err(|err| user_error_with_message("Failed to subscribe to Watchman", err))?;
+    writeln!(ui.status(), "Listening on {}", socket_path.display())?;
+    let result = daemon.serve(&listener);
+    std::fs::remove_file(&socket_path)?;
+    result.map_err(|err| internal_error_with_message("The daemon failed", err))
+}
+
+#[cfg(not(unix))]
+pub fn cmd_debug_daemon(
+    _ui: &mut Ui,
+    _command: &CommandHelper,
+    _args: &DebugDaemonArgs,
+) -> Result<(), CommandError> {
+    Err(user_error("The daemon is only supported on Unix"))
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod copy_detection;
+mod daemon;
 mod fileset;
 mod index;
 mod init_local;
@@ -34,6 +35,8 @@ use jj_lib::local_working_copy::LocalWorkingCopy;
 
 use self::copy_detection::cmd_debug_copy_detection;
 use self::copy_detection::CopyDetectionArgs;
+use self::daemon::cmd_debug_daemon;
+use self::daemon::DebugDaemonArgs;
 use self::fileset::cmd_debug_fileset;
 use self::fileset::DebugFilesetArgs;
 use self::index::cmd_debug_index;
@@ -68,6 +71,7 @@ use crate::ui::Ui;
 #[command(hide = true)]
 pub enum DebugCommand {
     CopyDetection(CopyDetectionArgs),
+    Daemon(DebugDaemonArgs),
     Fileset(DebugFilesetArgs),
     Index(DebugIndexArgs),
     InitLocal(DebugInitLocalArgs),
@@ -91,6 +95,7 @@ pub fn cmd_debug(
 ) -> Result<(), CommandError> {
     match subcommand {
         DebugCommand::CopyDetection(args) => cmd_debug_copy_detection(ui, command, args),
+        DebugCommand::Daemon(args) => cmd_debug_daemon(ui, command, args),
         DebugCommand::Fileset(args) => cmd_debug_fileset(ui, command, args),
         DebugCommand::Index(args) => cmd_debug_index(ui, command, args),
         DebugCommand::InitLocal(args) => cmd_debug_init_local(ui, command, args),
@@ -13,21 +13,39 @@
 // limitations under the License.
 
 use std::fmt::Debug;
+use std::io::Write as _;
 
+use crate::cli_util::print_snapshot_stats;
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
 /// Trigger a snapshot in the op log
 #[derive(clap::Args, Clone, Debug)]
-pub struct DebugSnapshotArgs {}
+pub struct DebugSnapshotArgs {
+    /// Print how many files were found unchanged by their recorded metadata
+    /// and how many had to be read
+    #[arg(long)]
+    stats: bool,
+}
 
 pub fn cmd_debug_snapshot(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &DebugSnapshotArgs,
+    args: &DebugSnapshotArgs,
 ) -> Result<(), CommandError> {
     // workspace helper will snapshot as needed
-    command.workspace_helper(ui)?;
+    let (workspace_command, stats) = command.workspace_helper_with_stats(ui)?;
+    print_snapshot_stats(ui, &stats, workspace_command.env().path_converter())?;
+    if args.stats {
+        let file_cache = &stats.file_cache;
+        writeln!(ui.stdout(), "File cache hits: {}", file_cache.hits)?;
+        writeln!(ui.stdout(), "File cache misses: {}", file_cache.misses)?;
+        writeln!(
+            ui.stdout(),
+            "Racily clean files: {}",
+            file_cache.racily_clean
+        )?;
+    }
     Ok(())
 }
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write as _;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use indexmap::IndexSet;
@@ -24,9 +26,11 @@ use tracing::instrument;
 use crate::cli_util::print_unmatched_explicit_paths;
 use crate::cli_util::short_commit_hash;
 use crate::cli_util::CommandHelper;
+use crate::cli_util::OutputFormat;
 use crate::cli_util::RevisionArg;
 use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
+use crate::commit_templater::collect_tree_diff_entries;
 use crate::complete;
 use crate::diff_util::get_copy_records;
 use crate::diff_util::DiffFormatArgs;
@@ -93,6 +97,12 @@ pub(crate) struct DiffArgs {
     paths: Vec<String>,
     #[command(flatten)]
     format: DiffFormatArgs,
+    /// Output format
+    ///
+    /// With `json`, the changed files are printed as an array of objects.
+    /// Requires `--summary`.
+    #[arg(long, value_enum, default_value_t, requires_if("json", "summary"))]
+    output: OutputFormat,
 }
 
 #[instrument(skip_all)]
@@ -162,17 +172,25 @@ pub(crate) fn cmd_diff(
         }
     }
 
-    let diff_renderer = workspace_command.diff_renderer_for(&args.format)?;
-    ui.request_pager();
-    diff_renderer.show_diff(
-        ui,
-        ui.stdout_formatter().as_mut(),
-        &from_tree,
-        &to_tree,
-        &matcher,
-        &copy_records,
-        ui.term_width(),
-    )?;
+    match args.output {
+        OutputFormat::Text => {
+            let diff_renderer = workspace_command.diff_renderer_for(&args.format)?;
+            ui.request_pager();
+            diff_renderer.show_diff(
+                ui,
+                ui.stdout_formatter().as_mut(),
+                &from_tree,
+                &to_tree,
+                &matcher,
+                &copy_records,
+                ui.term_width(),
+            )?;
+        }
+        OutputFormat::Json => {
+            let entries = collect_tree_diff_entries(&from_tree, &to_tree, &matcher, &copy_records)?;
+            writeln!(ui.stdout(), "{}", serde_json::json!(entries))?;
+        }
+    }
     print_unmatched_explicit_paths(
         ui,
         &workspace_command,
@@ -18,6 +18,7 @@ use std::io::Write;
 use indoc::writedoc;
 use itertools::Itertools;
 use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::working_copy::NewFilesMode;
 use jj_lib::working_copy::SnapshotStats;
 use jj_lib::working_copy::UntrackedReason;
 use tracing::instrument;
@@ -36,6 +37,10 @@ use crate::ui::Ui;
 /// `snapshot.auto-track` (e.g. to `"none()"` or `"glob:**/*.rs"`). Files that
 /// don't match the pattern can be manually tracked using this command. The
 /// default pattern is `all()` and this command has no effect.
+///
+/// If `snapshot.new-files` is set to `"leave-untracked"`, new files are never
+/// tracked automatically. Paths passed to this command are remembered, so new
+/// files created under them later will be tracked too.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileTrackArgs {
     /// Paths to track
@@ -50,13 +55,20 @@ pub(crate) fn cmd_file_track(
     args: &FileTrackArgs,
 ) -> Result<(), CommandError> {
     let (mut workspace_command, auto_stats) = command.workspace_helper_with_stats(ui)?;
-    let matcher = workspace_command
-        .parse_file_patterns(ui, &args.paths)?
-        .to_matcher();
+    let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
+    let matcher = fileset_expression.to_matcher();
     let options = workspace_command.snapshot_options_with_start_tracking_matcher(&matcher)?;
+    let new_files_mode = workspace_command.new_files_mode()?;
 
     let mut tx = workspace_command.start_transaction().into_inner();
     let (mut locked_ws, _wc_commit) = workspace_command.start_working_copy_mutation()?;
+    if new_files_mode == NewFilesMode::LeaveUntracked {
+        let paths = fileset_expression
+            .explicit_paths()
+            .map(|path| path.to_owned())
+            .collect_vec();
+        locked_ws.locked_wc().track(&paths)?;
+    }
     let (_tree_id, track_stats) = locked_ws.locked_wc().snapshot(&options)?;
     let num_rebased = tx.repo_mut().rebase_descendants()?;
     if num_rebased > 0 {
@@ -19,6 +19,7 @@ use itertools::Itertools;
 use jj_lib::merge::Merge;
 use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::repo::Repo;
+use jj_lib::working_copy::NewFilesMode;
 use tracing::instrument;
 
 use crate::cli_util::print_snapshot_stats;
@@ -34,7 +35,9 @@ pub(crate) struct FileUntrackArgs {
     /// Paths to untrack. They must already be ignored.
     ///
     /// The paths could be ignored via a .gitignore or .git/info/exclude (in
-    /// colocated repos).
+    /// colocated repos). If `snapshot.new-files` is set to
+    /// `"leave-untracked"`, the paths don't need to be ignored. They stay
+    /// untracked until tracked again by `jj file track`.
     #[arg(
         required = true,
         value_name = "FILESETS",
@@ -52,9 +55,9 @@ pub(crate) fn cmd_file_untrack(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let store = workspace_command.repo().store().clone();
-    let matcher = workspace_command
-        .parse_file_patterns(ui, &args.paths)?
-        .to_matcher();
+    let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
+    let matcher = fileset_expression.to_matcher();
+    let new_files_mode = workspace_command.new_files_mode()?;
     let auto_tracking_matcher = workspace_command.auto_tracking_matcher(ui)?;
     let options =
         workspace_command.snapshot_options_with_start_tracking_matcher(&auto_tracking_matcher)?;
@@ -73,6 +76,15 @@ pub(crate) fn cmd_file_untrack(
         .rewrite_commit(&wc_commit)
         .set_tree_id(new_tree_id)
         .write()?;
+    if new_files_mode == NewFilesMode::LeaveUntracked {
+        // Remember the paths so that they aren't tracked again with their
+        // parent directories.
+        let paths = fileset_expression
+            .explicit_paths()
+            .map(|path| path.to_owned())
+            .collect_vec();
+        locked_ws.locked_wc().untrack(&paths)?;
+    }
     // Reset the working copy to the new commit
     locked_ws.locked_wc().reset(&new_commit)?;
     // Commit the working copy again so we can inform the user if paths couldn't be
@@ -12,25 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fs;
-use std::io;
+use std::collections::HashMap;
 use std::io::Write;
 use std::num::NonZeroU32;
-use std::path::Path;
 
-use jj_lib::git;
-use jj_lib::git::GitFetch;
+use jj_lib::file_util::normalize_path;
 use jj_lib::refs::RemoteRefSymbol;
 use jj_lib::repo::Repo;
-use jj_lib::str_util::StringPattern;
+use jj_lib::workspace::GitCloneOptions;
 use jj_lib::workspace::Workspace;
 
 use super::write_repository_level_trunk_alias;
+use crate::cli_util::format_args_tag;
 use crate::cli_util::CommandHelper;
-use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::cli_error;
 use crate::command_error::user_error;
-use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::commands::git::maybe_add_gitignore;
 use crate::git_util::absolute_git_url;
@@ -73,14 +69,6 @@ fn clone_destination_for_source(source: &str) -> Option<&str> {
         .map(|(_, name)| name)
 }
 
-fn is_empty_dir(path: &Path) -> bool {
-    if let Ok(mut entries) = path.read_dir() {
-        entries.next().is_none()
-    } else {
-        false
-    }
-}
-
 pub fn cmd_git_clone(
     ui: &mut Ui,
     command: &CommandHelper,
@@ -96,55 +84,48 @@ pub fn cmd_git_clone(
         .as_deref()
         .or_else(|| clone_destination_for_source(&source))
         .ok_or_else(|| user_error("No destination specified and wasn't able to guess it"))?;
-    let wc_path = command.cwd().join(wc_path_str);
-
-    let wc_path_existed = wc_path.exists();
-    if wc_path_existed && !is_empty_dir(&wc_path) {
-        return Err(user_error(
-            "Destination path exists and is not an empty directory",
-        ));
-    }
+    let wc_path = normalize_path(&command.cwd().join(wc_path_str));
+    let settings = command.settings_for_new_workspace(&wc_path)?;
 
-    // will create a tree dir in case if was deleted after last check
-    fs::create_dir_all(&wc_path)
-        .map_err(|err| user_error_with_message(format!("Failed to create {wc_path_str}"), err))?;
-
-    // Canonicalize because fs::remove_dir_all() doesn't seem to like e.g.
-    // `/some/path/.`
-    let canonical_wc_path = dunce::canonicalize(&wc_path)
-        .map_err(|err| user_error_with_message(format!("Failed to create {wc_path_str}"), err))?;
-
-    let clone_result = (|| -> Result<_, CommandError> {
-        let workspace_command = init_workspace(ui, command, &canonical_wc_path, args.colocate)?;
-        let mut workspace_command =
-            configure_remote(ui, command, workspace_command, remote_name, &source)?;
-        let default_branch = fetch_new_remote(ui, &mut workspace_command, remote_name, args.depth)?;
-        Ok((workspace_command, default_branch))
-    })();
-    if clone_result.is_err() {
-        let clean_up_dirs = || -> io::Result<()> {
-            fs::remove_dir_all(canonical_wc_path.join(".jj"))?;
-            if args.colocate {
-                fs::remove_dir_all(canonical_wc_path.join(".git"))?;
-            }
-            if !wc_path_existed {
-                fs::remove_dir(&canonical_wc_path)?;
-            }
-            Ok(())
-        };
-        if let Err(err) = clean_up_dirs() {
-            writeln!(
-                ui.warning_default(),
-                "Failed to clean up {}: {}",
-                canonical_wc_path.display(),
-                err
-            )
-            .ok();
-        }
+    writeln!(
+        ui.status(),
+        r#"Fetching into new repo in "{}""#,
+        wc_path.display()
+    )?;
+    let options = GitCloneOptions {
+        remote_name,
+        colocate: args.colocate,
+        depth: args.depth,
+        // Checked out below, so that the working copy is updated and reported
+        // like by any other command.
+        check_out_default_branch: false,
+        op_tags: HashMap::from([("args".to_owned(), format_args_tag(command.string_args()))]),
+    };
+    let (workspace, repo, stats) = with_remote_git_callbacks(ui, |cb| {
+        Workspace::clone_git(
+            &source,
+            &wc_path,
+            &settings,
+            command.store_factories(),
+            command.working_copy_factories(),
+            &options,
+            cb,
+        )
+    })?;
+    let mut workspace_command = command.for_workable_repo(ui, workspace, repo)?;
+    maybe_add_gitignore(&workspace_command)?;
+    print_git_import_stats(
+        ui,
+        workspace_command.repo().as_ref(),
+        &stats.import_stats,
+        true,
+    )?;
+    // Nothing is recorded if there was nothing to fetch.
+    if stats.import_stats.changed_remote_refs.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
     }
 
-    let (mut workspace_command, default_branch) = clone_result?;
-    if let Some(name) = &default_branch {
+    if let Some(name) = &stats.default_branch {
         let default_symbol = RemoteRefSymbol {
             name,
             remote: remote_name,
@@ -167,66 +148,3 @@ pub fn cmd_git_clone(
     }
     Ok(())
 }
-
-fn init_workspace(
-    ui: &Ui,
-    command: &CommandHelper,
-    wc_path: &Path,
-    colocate: bool,
-) -> Result<WorkspaceCommandHelper, CommandError> {
-    let settings = command.settings_for_new_workspace(wc_path)?;
-    let (workspace, repo) = if colocate {
-        Workspace::init_colocated_git(&settings, wc_path)?
-    } else {
-        Workspace::init_internal_git(&settings, wc_path)?
-    };
-    let workspace_command = command.for_workable_repo(ui, workspace, repo)?;
-    maybe_add_gitignore(&workspace_command)?;
-    Ok(workspace_command)
-}
-
-fn configure_remote(
-    ui: &Ui,
-    command: &CommandHelper,
-    workspace_command: WorkspaceCommandHelper,
-    remote_name: &str,
-    source: &str,
-) -> Result<WorkspaceCommandHelper, CommandError> {
-    git::add_remote(workspace_command.repo().store(), remote_name, source)?;
-    // Reload workspace to apply new remote configuration to
-    // gix::ThreadSafeRepository behind the store.
-    let workspace = command.load_workspace_at(
-        workspace_command.workspace_root(),
-        workspace_command.settings(),
-    )?;
-    let op = workspace
-        .repo_loader()
-        .load_operation(workspace_command.repo().op_id())?;
-    let repo = workspace.repo_loader().load_at(&op)?;
-    command.for_workable_repo(ui, workspace, repo)
-}
-
-fn fetch_new_remote(
-    ui: &Ui,
-    workspace_command: &mut WorkspaceCommandHelper,
-    remote_name: &str,
-    depth: Option<NonZeroU32>,
-) -> Result<Option<String>, CommandError> {
-    writeln!(
-        ui.status(),
-        r#"Fetching into new repo in "{}""#,
-        workspace_command.workspace_root().display()
-    )?;
-    let git_settings = workspace_command.settings().git_settings()?;
-    let mut fetch_tx = workspace_command.start_transaction();
-    let mut git_fetch = GitFetch::new(fetch_tx.repo_mut(), &git_settings)?;
-    with_remote_git_callbacks(ui, |cb| {
-        git_fetch.fetch(remote_name, &[StringPattern::everything()], cb, depth)
-    })?;
-    let default_branch =
-        with_remote_git_callbacks(ui, |cb| git_fetch.get_default_branch(remote_name, cb))?;
-    let import_stats = git_fetch.import_refs()?;
-    print_git_import_stats(ui, fetch_tx.repo(), &import_stats, true)?;
-    fetch_tx.finish(ui, "fetch from git remote into empty repo")?;
-    Ok(default_branch)
-}
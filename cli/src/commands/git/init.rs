@@ -164,27 +164,22 @@ fn do_init(
             maybe_add_gitignore(&workspace_command)?;
         }
         GitInitMode::External(git_repo_path) => {
-            let (workspace, repo) =
+            let (mut workspace, repo) =
                 Workspace::init_external_git(&settings, workspace_root, git_repo_path)?;
-            // Import refs first so all the reachable commits are indexed in
-            // chronological order.
-            let colocated = is_colocated_git_workspace(&workspace, &repo);
-            let repo = init_git_refs(ui, repo, command.string_args(), colocated)?;
-            let mut workspace_command = command.for_workable_repo(ui, workspace, repo)?;
+            let mut workspace_command = if is_colocated_git_workspace(&workspace, &repo) {
+                // Import refs first so all the reachable commits are indexed in
+                // chronological order.
+                let repo =
+                    init_colocated_git_refs(ui, &mut workspace, repo, command.string_args())?;
+                command.for_workable_repo(ui, workspace, repo)?
+            } else {
+                let mut workspace_command = command.for_workable_repo(ui, workspace, repo)?;
+                init_external_git_refs(ui, &mut workspace_command)?;
+                workspace_command
+            };
             maybe_add_gitignore(&workspace_command)?;
             workspace_command.maybe_snapshot(ui)?;
             maybe_set_repository_level_trunk_alias(ui, &workspace_command)?;
-            if !workspace_command.working_copy_shared_with_git() {
-                let mut tx = workspace_command.start_transaction();
-                jj_lib::git::import_head(tx.repo_mut())?;
-                if let Some(git_head_id) = tx.repo().view().git_head().as_normal().cloned() {
-                    let git_head_commit = tx.repo().store().get_commit(&git_head_id)?;
-                    tx.check_out(&git_head_commit)?;
-                }
-                if tx.repo().has_changes() {
-                    tx.finish(ui, "import git head")?;
-                }
-            }
             print_trackable_remote_bookmarks(ui, workspace_command.repo().view())?;
         }
         GitInitMode::Internal => {
@@ -194,16 +189,46 @@ fn do_init(
     Ok(())
 }
 
-/// Imports branches and tags from the underlying Git repo, exports changes if
-/// the repo is colocated.
+/// Imports branches, tags, and the HEAD from the underlying Git repo, and
+/// checks out the Git HEAD in the same operation.
+fn init_external_git_refs(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+) -> Result<(), CommandError> {
+    let mut git_settings = workspace_command.settings().git_settings()?;
+    let mut tx = workspace_command.start_transaction();
+    // There should be no old refs to abandon, but enforce it.
+    git_settings.abandon_unreachable_commits = false;
+    let stats = git::import_refs(tx.repo_mut(), &git_settings)?;
+    print_git_import_stats(ui, tx.repo(), &stats, false)?;
+    git::import_head(tx.repo_mut())?;
+    if !tx.repo().has_changes() {
+        return Ok(());
+    }
+    if let Some(git_head_id) = tx.repo().view().git_head().as_normal().cloned() {
+        let git_head_commit = tx.repo().store().get_commit(&git_head_id)?;
+        tx.check_out(&git_head_commit)?;
+    }
+    writeln!(
+        ui.status(),
+        "Done importing changes from the underlying Git repo."
+    )?;
+    tx.finish(ui, "import git refs")?;
+    Ok(())
+}
+
+/// Imports branches, tags, and the HEAD from the colocated Git repo, and
+/// exports changes back to it.
 ///
 /// This is similar to `WorkspaceCommandHelper::import_git_refs()`, but never
-/// moves the Git HEAD to the working copy parent.
-fn init_git_refs(
-    ui: &mut Ui,
+/// moves the Git HEAD to the working copy parent. The working-copy state is
+/// reset to the Git HEAD in the same operation. The working-copy files are
+/// left alone since they are already managed by Git.
+fn init_colocated_git_refs(
+    ui: &Ui,
+    workspace: &mut Workspace,
     repo: Arc<ReadonlyRepo>,
     string_args: &[String],
-    colocated: bool,
 ) -> Result<Arc<ReadonlyRepo>, CommandError> {
     let mut git_settings = repo.settings().git_settings()?;
     let mut tx = start_repo_transaction(&repo, string_args);
@@ -211,16 +236,27 @@ fn init_git_refs(
     git_settings.abandon_unreachable_commits = false;
     let stats = git::import_refs(tx.repo_mut(), &git_settings)?;
     print_git_import_stats(ui, tx.repo(), &stats, false)?;
+    git::import_head(tx.repo_mut())?;
     if !tx.repo().has_changes() {
         return Ok(repo);
     }
-    if colocated {
-        // If git.auto-local-bookmark = true, local bookmarks could be created for
-        // the imported remote branches.
-        let failed_refs = git::export_refs(tx.repo_mut())?;
-        print_failed_git_export(ui, &failed_refs)?;
-    }
-    let repo = tx.commit("import git refs")?;
+    // If git.auto-local-bookmark = true, local bookmarks could be created for
+    // the imported remote branches.
+    let failed_refs = git::export_refs(tx.repo_mut())?;
+    print_failed_git_export(ui, &failed_refs)?;
+    let repo = if let Some(git_head_id) = tx.repo().view().git_head().as_normal().cloned() {
+        let git_head_commit = tx.repo().store().get_commit(&git_head_id)?;
+        tx.repo_mut()
+            .check_out(workspace.workspace_id().clone(), &git_head_commit)?;
+        tx.repo_mut().rebase_descendants()?;
+        let mut locked_ws = workspace.start_working_copy_mutation()?;
+        locked_ws.locked_wc().reset(&git_head_commit)?;
+        let repo = tx.commit("import git refs")?;
+        locked_ws.finish(repo.op_id().clone())?;
+        repo
+    } else {
+        tx.commit("import git refs")?
+    };
     writeln!(
         ui.status(),
         "Done importing changes from the underlying Git repo."
@@ -28,6 +28,7 @@ use jj_lib::commit::CommitIteratorExt as _;
 use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::git;
 use jj_lib::git::GitBranchPushTargets;
+use jj_lib::hooks::HookKind;
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::RefTarget;
 use jj_lib::refs::classify_bookmark_push_action;
@@ -246,7 +247,7 @@ pub fn cmd_git_push(
         let mut seen_bookmarks: HashSet<&str> = HashSet::new();
 
         // Process --change bookmarks first because matching bookmarks can be moved.
-        let bookmark_prefix = tx.settings().get_string("git.push-bookmark-prefix")?;
+        let bookmark_prefix = tx.settings().git_settings()?.push_bookmark_prefix;
         let change_bookmark_names =
             update_change_bookmarks(ui, &mut tx, &args.change, &bookmark_prefix)?;
         let change_bookmarks = change_bookmark_names.iter().map(|bookmark_name| {
@@ -369,6 +370,25 @@ pub fn cmd_git_push(
         return Ok(());
     }
 
+    let bookmarks_payload: Vec<_> = bookmark_updates
+        .iter()
+        .map(|(name, update)| {
+            serde_json::json!({
+                "name": name,
+                "old_target": update.old_target.as_ref().map(|id| id.hex()),
+                "new_target": update.new_target.as_ref().map(|id| id.hex()),
+            })
+        })
+        .collect();
+    tx.base_workspace_helper().run_hook(
+        ui,
+        HookKind::PrePush,
+        &serde_json::json!({
+            "remote": remote,
+            "bookmarks": bookmarks_payload,
+        }),
+    )?;
+
     let targets = GitBranchPushTargets {
         branch_updates: bookmark_updates,
     };
@@ -698,21 +718,10 @@ fn update_change_bookmarks(
         .try_collect()?;
 
     for commit in all_commits {
-        let workspace_command = tx.base_workspace_helper();
         let short_change_id = short_change_hash(commit.change_id());
-        let mut bookmark_name = format!("{bookmark_prefix}{}", commit.change_id().hex());
+        let bookmark_name =
+            git::change_bookmark_name(tx.base_repo().as_ref(), bookmark_prefix, commit.change_id());
         let view = tx.base_repo().view();
-        if view.get_local_bookmark(&bookmark_name).is_absent() {
-            // A local bookmark with the full change ID doesn't exist already, so use the
-            // short ID if it's not ambiguous (which it shouldn't be most of the time).
-            if workspace_command
-                .resolve_single_rev(ui, &RevisionArg::from(short_change_id.clone()))
-                .is_ok()
-            {
-                // Short change ID is not ambiguous, so update the bookmark name to use it.
-                bookmark_name = format!("{bookmark_prefix}{short_change_id}");
-            };
-        }
         if view.get_local_bookmark(&bookmark_name).is_absent() {
             writeln!(
                 ui.status(),
@@ -792,6 +801,13 @@ fn find_bookmarks_targeted_by_revisions<'a>(
     }
     for rev_arg in revisions {
         let mut expression = workspace_command.parse_revset(ui, rev_arg)?;
+        let anonymous_heads: Vec<_> = expression
+            .evaluate_to_commit_ids()?
+            .filter_map_ok(|id| {
+                let view = workspace_command.repo().view();
+                view.anonymous_head_name(&id).map(str::to_owned)
+            })
+            .try_collect()?;
         expression.intersect_with(&RevsetExpression::bookmarks(StringPattern::everything()));
         let mut commit_ids = expression.evaluate_to_commit_ids()?.peekable();
         if commit_ids.peek().is_none() {
@@ -799,6 +815,12 @@ fn find_bookmarks_targeted_by_revisions<'a>(
                 ui.warning_default(),
                 "No bookmarks point to the specified revisions: {rev_arg}"
             )?;
+            for name in &anonymous_heads {
+                writeln!(
+                    ui.hint_default(),
+                    "Run `jj bookmark create {name} -r {name}` to push the anonymous head {name}."
+                )?;
+            }
         }
         for commit_id in commit_ids {
             revision_commit_ids.insert(commit_id?);
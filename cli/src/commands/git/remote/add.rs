@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use jj_lib::git;
-use jj_lib::repo::Repo;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
@@ -37,8 +36,9 @@ pub fn cmd_git_remote_add(
     command: &CommandHelper,
     args: &GitRemoteAddArgs,
 ) -> Result<(), CommandError> {
-    let workspace_command = command.workspace_helper(ui)?;
+    let mut workspace_command = command.workspace_helper(ui)?;
     let url = absolute_git_url(command.cwd(), &args.url)?;
-    git::add_remote(workspace_command.repo().store(), &args.remote, &url)?;
-    Ok(())
+    let mut tx = workspace_command.start_transaction();
+    git::add_remote(tx.repo_mut(), &args.remote, &url)?;
+    tx.finish(ui, format!("add git remote {}", &args.remote))
 }
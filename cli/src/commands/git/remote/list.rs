@@ -32,7 +32,14 @@ pub fn cmd_git_remote_list(
     _args: &GitRemoteListArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
-    let git_repo = git::get_git_repo(workspace_command.repo().store())?;
+    let repo = workspace_command.repo();
+    let Ok(git_repo) = git::get_git_repo(repo.store()) else {
+        // Without a backing Git repo, remotes are only stored in the view.
+        for (remote_name, config) in repo.view().remote_configs() {
+            writeln!(ui.stdout(), "{remote_name} {}", config.url)?;
+        }
+        return Ok(());
+    };
     for remote_name in git_repo.remote_names() {
         let remote = match git_repo.try_find_remote(&*remote_name) {
             Some(Ok(remote)) => remote,
@@ -37,6 +37,8 @@ use crate::ui::Ui;
 /// Manage Git remotes
 ///
 /// The Git repo will be a bare git repo stored inside the `.jj/` directory.
+/// Remotes added by jj are also recorded in the repo itself, so repos that
+/// aren't backed by Git can have remotes too.
 #[derive(Subcommand, Clone, Debug)]
 pub enum RemoteCommand {
     Add(GitRemoteAddArgs),
@@ -14,7 +14,6 @@
 
 use clap_complete::ArgValueCandidates;
 use jj_lib::git;
-use jj_lib::repo::Repo;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
@@ -40,8 +39,13 @@ pub fn cmd_git_remote_set_url(
     command: &CommandHelper,
     args: &GitRemoteSetUrlArgs,
 ) -> Result<(), CommandError> {
-    let workspace_command = command.workspace_helper(ui)?;
+    let mut workspace_command = command.workspace_helper(ui)?;
     let url = absolute_git_url(command.cwd(), &args.url)?;
-    git::set_remote_url(workspace_command.repo().store(), &args.remote, &url)?;
-    Ok(())
+    let mut tx = workspace_command.start_transaction();
+    git::set_remote_url(tx.repo_mut(), &args.remote, &url)?;
+    if tx.repo().has_changes() {
+        tx.finish(ui, format!("set url of git remote {}", &args.remote))
+    } else {
+        Ok(()) // Do not print "Nothing changed."
+    }
 }
@@ -29,13 +29,11 @@ use jj_lib::matchers::EverythingMatcher;
 use jj_lib::op_store::RefTarget;
 use jj_lib::op_store::RemoteRef;
 use jj_lib::op_store::RemoteRefState;
-use jj_lib::refs::diff_named_commit_ids;
-use jj_lib::refs::diff_named_ref_targets;
-use jj_lib::refs::diff_named_remote_refs;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
 use jj_lib::revset;
 use jj_lib::revset::RevsetIteratorExt as _;
+use jj_lib::view;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
@@ -278,11 +276,8 @@ pub fn show_op_diff(
         }
     }
 
-    let changed_working_copies = diff_named_commit_ids(
-        from_repo.view().wc_commit_ids(),
-        to_repo.view().wc_commit_ids(),
-    )
-    .collect_vec();
+    let view_diff = view::diff(from_repo.view(), to_repo.view());
+    let changed_working_copies = view_diff.wc_commits;
     if !changed_working_copies.is_empty() {
         writeln!(formatter)?;
         for (name, (from_commit, to_commit)) in changed_working_copies {
@@ -312,11 +307,7 @@ pub fn show_op_diff(
         }
     }
 
-    let changed_local_bookmarks = diff_named_ref_targets(
-        from_repo.view().local_bookmarks(),
-        to_repo.view().local_bookmarks(),
-    )
-    .collect_vec();
+    let changed_local_bookmarks = view_diff.local_bookmarks;
     if !changed_local_bookmarks.is_empty() {
         writeln!(formatter)?;
         with_content_format.write(formatter, |formatter| {
@@ -345,8 +336,7 @@ pub fn show_op_diff(
         }
     }
 
-    let changed_tags =
-        diff_named_ref_targets(from_repo.view().tags(), to_repo.view().tags()).collect_vec();
+    let changed_tags = view_diff.tags;
     if !changed_tags.is_empty() {
         writeln!(formatter)?;
         with_content_format.write(formatter, |formatter| writeln!(formatter, "Changed tags:"))?;
@@ -374,14 +364,13 @@ pub fn show_op_diff(
         writeln!(formatter)?;
     }
 
-    let changed_remote_bookmarks = diff_named_remote_refs(
-        from_repo.view().all_remote_bookmarks(),
-        to_repo.view().all_remote_bookmarks(),
-    )
-    // Skip updates to the local git repo, since they should typically be covered in
-    // local branches.
-    .filter(|(symbol, _)| !jj_lib::git::is_special_git_remote(symbol.remote))
-    .collect_vec();
+    let changed_remote_bookmarks = view_diff
+        .remote_bookmarks
+        .into_iter()
+        // Skip updates to the local git repo, since they should typically be covered in
+        // local branches.
+        .filter(|(symbol, _)| !jj_lib::git::is_special_git_remote(symbol.remote))
+        .collect_vec();
     if !changed_remote_bookmarks.is_empty() {
         writeln!(formatter)?;
         with_content_format.write(formatter, |formatter| {
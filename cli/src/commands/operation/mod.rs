@@ -104,5 +104,8 @@ fn view_with_desired_portions_restored(
         git_refs: current_view.git_refs.clone(),
         git_head: current_view.git_head.clone(),
         wc_commit_ids: repo_source.wc_commit_ids.clone(),
+        anonymous_heads: repo_source.anonymous_heads.clone(),
+        bisect_state: repo_source.bisect_state.clone(),
+        remotes: remote_source.remotes.clone(),
     }
 }
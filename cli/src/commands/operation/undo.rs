@@ -31,7 +31,9 @@ use crate::ui::Ui;
 /// Create a new operation that undoes an earlier operation
 ///
 /// This undoes an individual operation by applying the inverse of the
-/// operation.
+/// operation. Changes made in the working copy since the operation are kept on
+/// top of the restored working-copy commit, unless `--restore-working-copy` is
+/// passed.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationUndoArgs {
     /// The operation to undo
@@ -45,6 +47,10 @@ pub struct OperationUndoArgs {
     /// This option is EXPERIMENTAL.
     #[arg(long, value_enum, default_values_t = DEFAULT_UNDO_WHAT)]
     what: Vec<UndoWhatToRestore>,
+
+    /// Also revert changes made in the working copy since the operation
+    #[arg(long)]
+    restore_working_copy: bool,
 }
 
 fn is_undo(op: &Operation, parent_op: &Operation) -> Result<bool, OpStoreError> {
@@ -76,12 +82,36 @@ pub fn cmd_op_undo(
     let bad_repo = repo_loader.load_at(&bad_op)?;
     let parent_repo = repo_loader.load_at(&parent_op)?;
     tx.repo_mut().merge(&bad_repo, &parent_repo)?;
-    let new_view = view_with_desired_portions_restored(
+    let mut new_view = view_with_desired_portions_restored(
         tx.repo().view().store_view(),
         tx.base_repo().view().store_view(),
         &args.what,
     );
-    tx.repo_mut().set_view(new_view);
+    if args.what.contains(&UndoWhatToRestore::Repo) {
+        // The view merge keeps the current working-copy commit if it has
+        // changed since the undone operation. If it's still the same change,
+        // restore the working-copy commit from before the operation instead.
+        let store = tx.base_repo().store().clone();
+        for (workspace_id, wc_commit_id) in &mut new_view.wc_commit_ids {
+            let bad_wc_commit_id = bad_repo.view().get_wc_commit_id(workspace_id);
+            let parent_wc_commit_id = parent_repo.view().get_wc_commit_id(workspace_id);
+            let (Some(bad_id), Some(parent_id)) = (bad_wc_commit_id, parent_wc_commit_id) else {
+                continue;
+            };
+            if bad_id == parent_id || wc_commit_id == parent_id {
+                continue;
+            }
+            if store.get_commit(wc_commit_id)?.change_id() == store.get_commit(bad_id)?.change_id()
+            {
+                *wc_commit_id = parent_id.clone();
+            }
+        }
+    }
+    if args.restore_working_copy {
+        tx.repo_mut().set_view(new_view);
+    } else {
+        tx.restore_view_preserving_working_copies(new_view, bad_repo.view().store_view())?;
+    }
     if let Some(mut formatter) = ui.status_formatter() {
         write!(formatter, "Undid operation: ")?;
         let template = tx.base_workspace_helper().operation_summary_template();
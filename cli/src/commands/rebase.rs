@@ -27,6 +27,7 @@ use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetIteratorExt;
 use jj_lib::rewrite::move_commits;
+use jj_lib::rewrite::ConflictBehaviour;
 use jj_lib::rewrite::EmptyBehaviour;
 use jj_lib::rewrite::MoveCommitsStats;
 use jj_lib::rewrite::MoveCommitsTarget;
@@ -380,10 +381,14 @@ pub(crate) fn cmd_rebase(
             true => EmptyBehaviour::AbandonNewlyEmpty,
             false => EmptyBehaviour::Keep,
         },
+        conflicts: ConflictBehaviour::Keep,
         rewrite_refs: RewriteRefsOptions {
             delete_abandoned_bookmarks: false,
         },
         simplify_ancestor_merge: false,
+        cancellation: Default::default(),
+        descendants_filter: None,
+        description: None,
     };
     let mut workspace_command = command.workspace_helper(ui)?;
     if !args.revisions.is_empty() {
@@ -653,6 +658,7 @@ fn print_move_commits_stats(ui: &Ui, stats: &MoveCommitsStats) -> std::io::Resul
         num_rebased_targets,
         num_rebased_descendants,
         num_skipped_rebases,
+        num_skipped_conflicts,
         num_abandoned,
     } = stats;
     if num_skipped_rebases > 0 {
@@ -661,6 +667,12 @@ fn print_move_commits_stats(ui: &Ui, stats: &MoveCommitsStats) -> std::io::Resul
             "Skipped rebase of {num_skipped_rebases} commits that were already in place"
         )?;
     }
+    if num_skipped_conflicts > 0 {
+        writeln!(
+            formatter,
+            "Skipped rebase of {num_skipped_conflicts} commits that would have become conflicted"
+        )?;
+    }
     if num_rebased_targets > 0 {
         writeln!(
             formatter,
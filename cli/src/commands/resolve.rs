@@ -15,7 +15,9 @@
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools;
+use jj_lib::attributes::FileAttributes;
 use jj_lib::object_id::ObjectId;
+use jj_lib::repo_path::RepoPath;
 use tracing::instrument;
 
 use crate::cli_util::print_conflicted_paths;
@@ -24,6 +26,7 @@ use crate::cli_util::RevisionArg;
 use crate::command_error::cli_error;
 use crate::command_error::CommandError;
 use crate::complete;
+use crate::merge_tools::MergeToolPartialResolutionError;
 use crate::ui::Ui;
 
 /// Resolve conflicted files with an external merge tool
@@ -107,10 +110,55 @@ pub(crate) fn cmd_resolve(
         .map(|(path, _)| path.as_ref())
         .collect_vec();
     workspace_command.check_rewritable([commit.id()])?;
-    let merge_editor = workspace_command.merge_editor(ui, args.tool.as_deref())?;
+    // Without --tool, files may select their merge tool by the `merge-tool`
+    // attribute. Each tool is run on its files in turn.
+    let file_attributes = FileAttributes::from_settings(workspace_command.settings())?;
+    let mut paths_by_tool: Vec<(Option<&str>, Vec<&RepoPath>)> = vec![];
+    for &path in &repo_paths {
+        let tool = args
+            .tool
+            .as_deref()
+            .or_else(|| file_attributes.merge_tool(path));
+        match paths_by_tool.iter_mut().find(|(other, _)| *other == tool) {
+            Some((_, paths)) => paths.push(path),
+            None => paths_by_tool.push((tool, vec![path])),
+        }
+    }
+    let merge_editors: Vec<_> = paths_by_tool
+        .into_iter()
+        .map(|(tool, paths)| {
+            Ok::<_, CommandError>((workspace_command.merge_editor(ui, tool)?, paths))
+        })
+        .try_collect()?;
     let mut tx = workspace_command.start_transaction();
-    let (new_tree_id, partial_resolution_error) =
-        merge_editor.edit_files(ui, &tree, &repo_paths)?;
+    let mut new_tree = tree.clone();
+    let mut resolved_count = 0;
+    let mut partial_resolution_error = None;
+    for (merge_editor, paths) in &merge_editors {
+        match merge_editor.edit_files(ui, &new_tree, paths) {
+            Ok((tree_id, err)) => {
+                new_tree = tree.store().get_root_tree(&tree_id)?;
+                if let Some(err) = err {
+                    partial_resolution_error = Some(MergeToolPartialResolutionError {
+                        source: err.source,
+                        resolved_count: resolved_count + err.resolved_count,
+                    });
+                    break;
+                }
+                resolved_count += paths.len();
+            }
+            Err(err) if resolved_count == 0 => return Err(err.into()),
+            Err(err) => {
+                // Keep the files resolved by the previous tools
+                partial_resolution_error = Some(MergeToolPartialResolutionError {
+                    source: err,
+                    resolved_count,
+                });
+                break;
+            }
+        }
+    }
+    let new_tree_id = new_tree.id();
     let new_commit = tx
         .repo_mut()
         .rewrite_commit(&commit)
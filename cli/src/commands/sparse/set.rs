@@ -28,6 +28,9 @@ use crate::ui::Ui;
 /// For example, if all you need is the `README.md` and the `lib/`
 /// directory, use `jj sparse set --clear --add README.md --add lib`.
 /// If you no longer need the `lib` directory, use `jj sparse set --remove lib`.
+///
+/// To use the same patterns as another workspace, use
+/// `jj sparse set --clone-from <path to other workspace>`.
 #[derive(clap::Args, Clone, Debug)]
 pub struct SparseSetArgs {
     /// Patterns to add to the working copy
@@ -48,6 +51,15 @@ pub struct SparseSetArgs {
     /// Include no files in the working copy (combine with --add)
     #[arg(long)]
     clear: bool,
+    /// Replace the patterns with those of the workspace at this path (combine
+    /// with --add)
+    #[arg(
+        long,
+        value_name = "WORKSPACE_PATH",
+        conflicts_with_all = ["clear", "remove"],
+        value_hint = clap::ValueHint::DirPath,
+    )]
+    clone_from: Option<String>,
 }
 
 #[instrument(skip_all)]
@@ -57,9 +69,20 @@ pub fn cmd_sparse_set(
     args: &SparseSetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
+    let cloned_patterns = args
+        .clone_from
+        .as_ref()
+        .map(|path| -> Result<_, CommandError> {
+            let other_workspace =
+                command.load_workspace_at(&command.cwd().join(path), command.settings())?;
+            Ok(other_workspace.working_copy().sparse_patterns()?.to_vec())
+        })
+        .transpose()?;
     update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
         let mut new_patterns = HashSet::new();
-        if !args.clear {
+        if let Some(cloned_patterns) = cloned_patterns {
+            new_patterns.extend(cloned_patterns);
+        } else if !args.clear {
             new_patterns.extend(old_patterns.iter().cloned());
             for path in &args.remove {
                 new_patterns.remove(path);
@@ -19,7 +19,10 @@ use jj_lib::commit::Commit;
 use jj_lib::matchers::Matcher;
 use jj_lib::object_id::ObjectId;
 use jj_lib::repo::Repo;
+use jj_lib::rewrite::split_commit;
 use jj_lib::rewrite::CommitWithSelection;
+use jj_lib::rewrite::SplitCommits;
+use jj_lib::rewrite::SplitOptions;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -144,95 +147,58 @@ pub(crate) fn cmd_split(
     // Prompt the user to select the changes they want for the first commit.
     let target = select_diff(ui, &tx, &target_commit, &matcher, &diff_selector)?;
 
+    let legacy_bookmark_behavior = tx.settings().get_bool("split.legacy-bookmark-behavior")?;
+    let options = SplitOptions {
+        parallel,
+        // With the legacy behavior, bookmarks pointing to the target commit
+        // are moved to the second commit.
+        move_bookmarks_to_second: legacy_bookmark_behavior,
+    };
+    let mut split = split_commit(tx.repo_mut(), target, &options);
+
     // Create the first commit, which includes the changes selected by the user.
-    let first_commit = {
-        let mut commit_builder = tx.repo_mut().rewrite_commit(&target.commit).detach();
-        commit_builder.set_tree_id(target.selected_tree.id());
-        if commit_builder.description().is_empty() {
-            commit_builder.set_description(tx.settings().get_string("ui.default-description")?);
-        }
-        let temp_commit = commit_builder.write_hidden()?;
+    let commit_builder = split.commit_builder();
+    if commit_builder.description().is_empty() {
+        commit_builder.set_description(tx.settings().get_string("ui.default-description")?);
+    }
+    let temp_commit = commit_builder.write_hidden()?;
+    let template = description_template(
+        ui,
+        &tx,
+        "Enter a description for the first commit.",
+        &temp_commit,
+    )?;
+    let description = edit_description(&text_editor, &template)?;
+    split.commit_builder().set_description(description);
+    let mut split = split.write(tx.repo_mut())?;
+
+    // Create the second commit, which includes everything the user didn't
+    // select.
+    let description = if target_commit.description().is_empty() {
+        // If there was no description before, don't ask for one for the
+        // second commit.
+        "".to_string()
+    } else {
+        let temp_commit = split.commit_builder().write_hidden()?;
         let template = description_template(
             ui,
             &tx,
-            "Enter a description for the first commit.",
+            "Enter a description for the second commit.",
             &temp_commit,
         )?;
-        let description = edit_description(&text_editor, &template)?;
-        commit_builder.set_description(description);
-        commit_builder.write(tx.repo_mut())?
-    };
-
-    // Create the second commit, which includes everything the user didn't
-    // select.
-    let second_commit = {
-        let target_tree = target.commit.tree()?;
-        let new_tree = if args.parallel {
-            // Merge the original commit tree with its parent using the tree
-            // containing the user selected changes as the base for the merge.
-            // This results in a tree with the changes the user didn't select.
-            target_tree.merge(&target.selected_tree, &target.parent_tree)?
-        } else {
-            target_tree
-        };
-        let parents = if parallel {
-            target.commit.parent_ids().to_vec()
-        } else {
-            vec![first_commit.id().clone()]
-        };
-        let mut commit_builder = tx.repo_mut().rewrite_commit(&target.commit).detach();
-        commit_builder
-            .set_parents(parents)
-            .set_tree_id(new_tree.id())
-            // Generate a new change id so that the commit being split doesn't
-            // become divergent.
-            .generate_new_change_id();
-        let description = if target.commit.description().is_empty() {
-            // If there was no description before, don't ask for one for the
-            // second commit.
-            "".to_string()
-        } else {
-            let temp_commit = commit_builder.write_hidden()?;
-            let template = description_template(
-                ui,
-                &tx,
-                "Enter a description for the second commit.",
-                &temp_commit,
-            )?;
-            edit_description(&text_editor, &template)?
-        };
-        commit_builder.set_description(description);
-        commit_builder.write(tx.repo_mut())?
+        edit_description(&text_editor, &template)?
     };
+    split.commit_builder().set_description(description);
+    let SplitCommits {
+        first_commit,
+        second_commit,
+        num_rebased,
+    } = split.write(tx.repo_mut())?;
 
-    let legacy_bookmark_behavior = tx.settings().get_bool("split.legacy-bookmark-behavior")?;
-    if legacy_bookmark_behavior {
-        // Mark the commit being split as rewritten to the second commit. This
-        // moves any bookmarks pointing to the target commit to the second
-        // commit.
-        tx.repo_mut()
-            .set_rewritten_commit(target.commit.id().clone(), second_commit.id().clone());
-    }
-    let mut num_rebased = 0;
-    tx.repo_mut()
-        .transform_descendants(vec![target.commit.id().clone()], |mut rewriter| {
-            num_rebased += 1;
-            if parallel && legacy_bookmark_behavior {
-                // The old_parent is the second commit due to the rewrite above.
-                rewriter
-                    .replace_parent(second_commit.id(), [first_commit.id(), second_commit.id()]);
-            } else if parallel {
-                rewriter.replace_parent(first_commit.id(), [first_commit.id(), second_commit.id()]);
-            } else {
-                rewriter.replace_parent(first_commit.id(), [second_commit.id()]);
-            }
-            rewriter.rebase()?.write()?;
-            Ok(())
-        })?;
     // Move the working copy commit (@) to the second commit for any workspaces
     // where the target commit is the working copy commit.
     for (workspace_id, working_copy_commit) in tx.base_repo().clone().view().wc_commit_ids() {
-        if working_copy_commit == target.commit.id() {
+        if working_copy_commit == target_commit.id() {
             tx.repo_mut().edit(workspace_id.clone(), &second_commit)?;
         }
     }
@@ -247,7 +213,7 @@ pub(crate) fn cmd_split(
         tx.write_commit_summary(formatter.as_mut(), &second_commit)?;
         writeln!(formatter)?;
     }
-    tx.finish(ui, format!("split commit {}", target.commit.id().hex()))?;
+    tx.finish(ui, format!("split commit {}", target_commit.id().hex()))?;
     Ok(())
 }
 
@@ -13,18 +13,24 @@
 // limitations under the License.
 
 use std::io;
+use std::io::Write as _;
 
 use itertools::Itertools;
+use jj_lib::commit::Commit;
 use jj_lib::copies::CopyRecords;
+use jj_lib::matchers::Matcher;
 use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetFilterPredicate;
+use jj_lib::working_copy::SnapshotStats;
 use tracing::instrument;
 
 use crate::cli_util::print_conflicted_paths;
 use crate::cli_util::print_snapshot_stats;
 use crate::cli_util::CommandHelper;
+use crate::cli_util::OutputFormat;
 use crate::command_error::CommandError;
+use crate::commit_templater::collect_tree_diff_entries;
 use crate::diff_util::get_copy_records;
 use crate::diff_util::DiffFormat;
 use crate::ui::Ui;
@@ -35,8 +41,12 @@ use crate::ui::Ui;
 ///
 ///  * The working copy commit and its (first) parent, and a summary of the
 ///    changes between them
+///  * A warning if the working-copy change is [divergent]
 ///  * [Conflicted bookmarks]
 ///
+/// [divergent]:
+///     https://jj-vcs.github.io/jj/latest/glossary/#divergent-change
+///
 /// [Conflicted bookmarks]:
 ///     https://jj-vcs.github.io/jj/latest/bookmarks/#conflicts
 #[derive(clap::Args, Clone, Debug)]
@@ -45,6 +55,13 @@ pub(crate) struct StatusArgs {
     /// Restrict the status display to these paths
     #[arg(value_name = "FILESETS", value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Output format
+    ///
+    /// With `json`, a single object is printed with the working-copy commit,
+    /// its parents, the changes between them, the untracked and conflicted
+    /// paths, and the conflicted bookmarks.
+    #[arg(long, value_enum, default_value_t)]
+    output: OutputFormat,
 }
 
 #[instrument(skip_all)]
@@ -67,6 +84,16 @@ pub(crate) fn cmd_status(
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
+    if args.output == OutputFormat::Json {
+        let value = status_to_json(
+            repo.as_ref(),
+            maybe_wc_commit.as_ref(),
+            &matcher,
+            &snapshot_stats,
+        )?;
+        writeln!(ui.stdout(), "{value}")?;
+        return Ok(());
+    }
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
@@ -123,6 +150,27 @@ pub(crate) fn cmd_status(
             writeln!(formatter)?;
         }
 
+        let divergent_ids = repo
+            .resolve_change_id(wc_commit.change_id())
+            .unwrap_or_default();
+        if divergent_ids.len() > 1 {
+            writeln!(
+                formatter.labeled("warning").with_heading("Warning: "),
+                "The working-copy change is divergent:"
+            )?;
+            for commit_id in &divergent_ids {
+                let commit = repo.store().get_commit(commit_id)?;
+                write!(formatter, "  ")?;
+                template.format(&commit, formatter)?;
+                writeln!(formatter)?;
+            }
+            writeln!(
+                formatter.labeled("hint").with_heading("Hint: "),
+                "Use `jj abandon` to discard the unwanted versions, or `jj squash` to combine \
+                 them."
+            )?;
+        }
+
         if wc_commit.has_conflict()? {
             // TODO: Conflicts should also be filtered by the `matcher`. See the related
             // TODO on `MergedTree::conflicts()`.
@@ -210,3 +258,54 @@ pub(crate) fn cmd_status(
 
     Ok(())
 }
+
+fn status_to_json(
+    repo: &dyn Repo,
+    maybe_wc_commit: Option<&Commit>,
+    matcher: &dyn Matcher,
+    snapshot_stats: &SnapshotStats,
+) -> Result<serde_json::Value, CommandError> {
+    let mut parents = vec![];
+    let mut changes = vec![];
+    let mut conflicts = vec![];
+    if let Some(wc_commit) = maybe_wc_commit {
+        parents = wc_commit.parents().try_collect()?;
+        let mut copy_records = CopyRecords::default();
+        for parent in wc_commit.parent_ids() {
+            let records = get_copy_records(repo.store(), parent, wc_commit.id(), matcher)?;
+            copy_records.add_records(records)?;
+        }
+        changes = collect_tree_diff_entries(
+            &wc_commit.parent_tree(repo)?,
+            &wc_commit.tree()?,
+            matcher,
+            &copy_records,
+        )?;
+        conflicts = wc_commit
+            .tree()?
+            .conflicts()
+            .map(|(path, _)| path)
+            .collect();
+    }
+    let conflicted_bookmarks = repo
+        .view()
+        .local_bookmarks()
+        .filter(|(_, target)| target.has_conflict())
+        .map(|(bookmark_name, _)| bookmark_name.to_owned())
+        .collect_vec();
+    let conflicted_remote_bookmarks = repo
+        .view()
+        .all_remote_bookmarks()
+        .filter(|(_, remote_ref)| remote_ref.target.has_conflict())
+        .map(|(symbol, _)| symbol.to_string())
+        .collect_vec();
+    Ok(serde_json::json!({
+        "working_copy": maybe_wc_commit,
+        "parents": parents,
+        "changes": changes,
+        "untracked_paths": snapshot_stats.untracked_paths.keys().collect_vec(),
+        "conflicted_paths": conflicts,
+        "conflicted_bookmarks": conflicted_bookmarks,
+        "conflicted_remote_bookmarks": conflicted_remote_bookmarks,
+    }))
+}
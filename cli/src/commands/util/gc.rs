@@ -65,5 +65,8 @@ pub fn cmd_util_gc(
     repo.op_store()
         .gc(slice::from_ref(repo.op_id()), keep_newer)?;
     repo.store().gc(repo.index(), keep_newer)?;
+    if let Some(cache) = repo.resolution_cache() {
+        cache.gc(repo.store())?;
+    }
     Ok(())
 }
@@ -26,15 +26,19 @@ use crate::ui::Ui;
 /// [stale working copy documentation]:
 ///     https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy
 #[derive(clap::Args, Clone, Debug)]
-pub struct WorkspaceUpdateStaleArgs {}
+pub struct WorkspaceUpdateStaleArgs {
+    /// Discard changes in the working copy instead of snapshotting them
+    #[arg(long)]
+    force: bool,
+}
 
 #[instrument(skip_all)]
 pub fn cmd_workspace_update_stale(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &WorkspaceUpdateStaleArgs,
+    args: &WorkspaceUpdateStaleArgs,
 ) -> Result<(), CommandError> {
-    let (workspace_command, stats) = command.recover_stale_working_copy(ui)?;
+    let (workspace_command, stats) = command.recover_stale_working_copy(ui, args.force)?;
     print_snapshot_stats(ui, &stats, workspace_command.env().path_converter())?;
 
     Ok(())
@@ -330,6 +330,23 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(self, diagnostics, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::Trailer(property) => {
+                let table = &self.build_fn_table.trailer_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                build(self, diagnostics, build_ctx, property, function)
+            }
+            CommitTemplatePropertyKind::TrailerList(property) => {
+                // TODO: migrate to table?
+                template_builder::build_formattable_list_method(
+                    self,
+                    diagnostics,
+                    build_ctx,
+                    property,
+                    function,
+                    Self::wrap_trailer,
+                    Self::wrap_trailer_list,
+                )
+            }
         }
     }
 }
@@ -454,6 +471,18 @@ impl<'repo> CommitTemplateLanguage<'repo> {
     ) -> CommitTemplatePropertyKind<'repo> {
         CommitTemplatePropertyKind::AnnotationLine(Box::new(property))
     }
+
+    pub fn wrap_trailer(
+        property: impl TemplateProperty<Output = Trailer> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::Trailer(Box::new(property))
+    }
+
+    pub fn wrap_trailer_list(
+        property: impl TemplateProperty<Output = Vec<Trailer>> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::TrailerList(Box::new(property))
+    }
 }
 
 pub enum CommitTemplatePropertyKind<'repo> {
@@ -477,6 +506,8 @@ pub enum CommitTemplatePropertyKind<'repo> {
         Box<dyn TemplateProperty<Output = Option<CryptographicSignature>> + 'repo>,
     ),
     AnnotationLine(Box<dyn TemplateProperty<Output = AnnotationLine> + 'repo>),
+    Trailer(Box<dyn TemplateProperty<Output = Trailer> + 'repo>),
+    TrailerList(Box<dyn TemplateProperty<Output = Vec<Trailer>> + 'repo>),
 }
 
 impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
@@ -502,6 +533,8 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
                 "Option<CryptographicSignature>"
             }
             CommitTemplatePropertyKind::AnnotationLine(_) => "AnnotationLine",
+            CommitTemplatePropertyKind::Trailer(_) => "Trailer",
+            CommitTemplatePropertyKind::TrailerList(_) => "List<Trailer>",
         }
     }
 
@@ -541,6 +574,10 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
                 Some(Box::new(property.map(|sig| sig.is_some())))
             }
             CommitTemplatePropertyKind::AnnotationLine(_) => None,
+            CommitTemplatePropertyKind::Trailer(_) => None,
+            CommitTemplatePropertyKind::TrailerList(property) => {
+                Some(Box::new(property.map(|l| !l.is_empty())))
+            }
         }
     }
 
@@ -585,6 +622,8 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::DiffStats(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::CryptographicSignatureOpt(_) => None,
             CommitTemplatePropertyKind::AnnotationLine(_) => None,
+            CommitTemplatePropertyKind::Trailer(property) => Some(property.into_template()),
+            CommitTemplatePropertyKind::TrailerList(property) => Some(property.into_template()),
         }
     }
 
@@ -611,6 +650,8 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             (CommitTemplatePropertyKind::DiffStats(_), _) => None,
             (CommitTemplatePropertyKind::CryptographicSignatureOpt(_), _) => None,
             (CommitTemplatePropertyKind::AnnotationLine(_), _) => None,
+            (CommitTemplatePropertyKind::Trailer(_), _) => None,
+            (CommitTemplatePropertyKind::TrailerList(_), _) => None,
         }
     }
 
@@ -640,6 +681,48 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             (CommitTemplatePropertyKind::DiffStats(_), _) => None,
             (CommitTemplatePropertyKind::CryptographicSignatureOpt(_), _) => None,
             (CommitTemplatePropertyKind::AnnotationLine(_), _) => None,
+            (CommitTemplatePropertyKind::Trailer(_), _) => None,
+            (CommitTemplatePropertyKind::TrailerList(_), _) => None,
+        }
+    }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'repo>> {
+        match self {
+            CommitTemplatePropertyKind::Core(property) => property.try_into_json(),
+            CommitTemplatePropertyKind::Commit(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitOpt(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitList(property) => Some(property.into_json()),
+            // serde doesn't implement Serialize for Rc<T> by default
+            CommitTemplatePropertyKind::RefName(property) => {
+                let json = property.and_then(|name| Ok(serde_json::to_value(&*name)?));
+                Some(Box::new(json))
+            }
+            CommitTemplatePropertyKind::RefNameOpt(property) => {
+                let json = property.and_then(|opt| Ok(serde_json::to_value(opt.as_deref())?));
+                Some(Box::new(json))
+            }
+            CommitTemplatePropertyKind::RefNameList(property) => {
+                let json = property.and_then(|names| {
+                    let names = names.iter().map(|name| &**name).collect_vec();
+                    Ok(serde_json::to_value(names)?)
+                });
+                Some(Box::new(json))
+            }
+            CommitTemplatePropertyKind::RepoPath(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::RepoPathOpt(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitOrChangeId(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::ShortestIdPrefix(_) => None,
+            CommitTemplatePropertyKind::TreeDiff(_) => None,
+            CommitTemplatePropertyKind::TreeDiffEntry(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::TreeDiffEntryList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::TreeEntry(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::DiffStats(_) => None,
+            CommitTemplatePropertyKind::CryptographicSignatureOpt(_) => None,
+            CommitTemplatePropertyKind::AnnotationLine(_) => None,
+            CommitTemplatePropertyKind::Trailer(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::TrailerList(property) => Some(property.into_json()),
         }
     }
 }
@@ -663,6 +746,7 @@ pub struct CommitTemplateBuildFnTable<'repo> {
     pub cryptographic_signature_methods:
         CommitTemplateBuildMethodFnMap<'repo, CryptographicSignature>,
     pub annotation_line_methods: CommitTemplateBuildMethodFnMap<'repo, AnnotationLine>,
+    pub trailer_methods: CommitTemplateBuildMethodFnMap<'repo, Trailer>,
 }
 
 impl<'repo> CommitTemplateBuildFnTable<'repo> {
@@ -681,6 +765,7 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             diff_stats_methods: builtin_diff_stats_methods(),
             cryptographic_signature_methods: builtin_cryptographic_signature_methods(),
             annotation_line_methods: builtin_annotation_line_methods(),
+            trailer_methods: builtin_trailer_methods(),
         }
     }
 
@@ -698,6 +783,7 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             diff_stats_methods: HashMap::new(),
             cryptographic_signature_methods: HashMap::new(),
             annotation_line_methods: HashMap::new(),
+            trailer_methods: HashMap::new(),
         }
     }
 
@@ -715,6 +801,7 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             diff_stats_methods,
             cryptographic_signature_methods,
             annotation_line_methods,
+            trailer_methods,
         } = extension;
 
         self.core.merge(core);
@@ -738,6 +825,7 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             cryptographic_signature_methods,
         );
         merge_fn_map(&mut self.annotation_line_methods, annotation_line_methods);
+        merge_fn_map(&mut self.trailer_methods, trailer_methods);
     }
 }
 
@@ -796,6 +884,40 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
             Ok(L::wrap_string(out_property))
         },
     );
+    map.insert(
+        "subject",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|commit| commit.subject().to_owned());
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map.insert(
+        "body",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.map(|commit| text_util::complete_newline(commit.body()));
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map.insert(
+        "trailers",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|commit| {
+                commit
+                    .trailers()
+                    .into_iter()
+                    .map(|(key, value)| Trailer {
+                        key: key.to_owned(),
+                        value: value.to_owned(),
+                    })
+                    .collect()
+            });
+            Ok(L::wrap_trailer_list(out_property))
+        },
+    );
     map.insert(
         "change_id",
         |_language, _diagnostics, _build_ctx, self_property, function| {
@@ -1343,6 +1465,24 @@ impl Template for Vec<Rc<RefName>> {
     }
 }
 
+impl serde::Serialize for RefName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+        let mut state = serializer.serialize_struct("RefName", 6)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("remote", &self.remote)?;
+        state.serialize_field("present", &self.is_present())?;
+        state.serialize_field("conflict", &self.has_conflict())?;
+        state.serialize_field("removed_targets", &self.target.removed_ids().collect_vec())?;
+        state.serialize_field("added_targets", &self.target.added_ids().collect_vec())?;
+        state.serialize_field("tracked", &self.is_tracked())?;
+        state.end()
+    }
+}
+
 fn builtin_ref_name_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Rc<RefName>> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -1584,6 +1724,15 @@ impl Template for CommitOrChangeId {
     }
 }
 
+impl serde::Serialize for CommitOrChangeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.hex())
+    }
+}
+
 fn builtin_commit_or_change_id_methods<'repo>(
 ) -> CommitTemplateBuildMethodFnMap<'repo, CommitOrChangeId> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
@@ -1950,6 +2099,21 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
     map
 }
 
+/// Collects the entries of the diff between two trees, which serialize the
+/// same way as the `TreeDiffEntry` template type.
+pub fn collect_tree_diff_entries(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &CopyRecords,
+) -> BackendResult<Vec<TreeDiffEntry>> {
+    from_tree
+        .diff_stream_with_copies(to_tree, matcher, copy_records)
+        .map(TreeDiffEntry::from_backend_entry_with_copies)
+        .try_collect()
+        .block_on()
+}
+
 /// [`MergedTree`] diff entry.
 #[derive(Clone, Debug)]
 pub struct TreeDiffEntry {
@@ -1992,6 +2156,20 @@ impl TreeDiffEntry {
     }
 }
 
+impl serde::Serialize for TreeDiffEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+        let mut state = serializer.serialize_struct("TreeDiffEntry", 3)?;
+        state.serialize_field("status", self.status_label())?;
+        state.serialize_field("source", &self.clone().into_source_entry())?;
+        state.serialize_field("target", &self.clone().into_target_entry())?;
+        state.end()
+    }
+}
+
 fn builtin_tree_diff_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, TreeDiffEntry>
 {
     type L<'repo> = CommitTemplateLanguage<'repo>;
@@ -2041,6 +2219,22 @@ pub struct TreeEntry {
     pub value: MergedTreeValue,
 }
 
+impl serde::Serialize for TreeEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+        let mut state = serializer.serialize_struct("TreeEntry", 4)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("conflict", &!self.value.is_resolved())?;
+        state.serialize_field("file_type", describe_file_type(&self.value))?;
+        let executable = is_executable_file(&self.value).unwrap_or_default();
+        state.serialize_field("executable", &executable)?;
+        state.end()
+    }
+}
+
 fn builtin_tree_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, TreeEntry> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -2263,3 +2457,44 @@ pub fn builtin_annotation_line_methods<'repo>(
     );
     map
 }
+
+/// Git-style `Key: value` trailer at the end of a commit description.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+impl Template for Trailer {
+    fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
+        write!(formatter, "{}: {}", self.key, self.value)
+    }
+}
+
+impl Template for Vec<Trailer> {
+    fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
+        templater::format_joined(formatter, self, "\n")
+    }
+}
+
+fn builtin_trailer_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Trailer> {
+    type L<'repo> = CommitTemplateLanguage<'repo>;
+    let mut map = CommitTemplateBuildMethodFnMap::<Trailer>::new();
+    map.insert(
+        "key",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|trailer| trailer.key);
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map.insert(
+        "value",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|trailer| trailer.value);
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map
+}
@@ -24,12 +24,15 @@ use std::process::Command;
 use itertools::Itertools;
 use jj_lib::config::ConfigFile;
 use jj_lib::config::ConfigGetError;
+use jj_lib::config::ConfigItem;
 use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigLoadError;
 use jj_lib::config::ConfigMigrationRule;
 use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigResolutionContext;
 use jj_lib::config::ConfigSource;
+use jj_lib::config::ConfigTable;
+use jj_lib::config::ConfigTableLike;
 use jj_lib::config::ConfigValue;
 use jj_lib::config::StackedConfig;
 use regex::Captures;
@@ -628,6 +631,207 @@ pub fn default_config_migrations() -> Vec<ConfigMigrationRule> {
     ]
 }
 
+/// Builds table of the declared config variables from [`CONFIG_SCHEMA`] and
+/// the `default_layers`.
+///
+/// Leaf items are arrays of the expected TOML type names. An empty array
+/// means that any type is allowed. Tables which have defaults but aren't
+/// described in the schema are free-form.
+pub fn known_config_table<'a>(
+    default_layers: impl IntoIterator<Item = &'a ConfigLayer>,
+) -> ConfigTable {
+    let mut known = ConfigTable::new();
+    for layer in default_layers {
+        merge_known_tables(&mut known, known_table_from_defaults(layer.data.as_table()));
+    }
+    let schema: serde_json::Value =
+        serde_json::from_str(CONFIG_SCHEMA).expect("config schema should be valid JSON");
+    let ConfigItem::Table(schema_known) = known_item_from_schema(&schema, &schema) else {
+        panic!("config schema should describe a table");
+    };
+    for (key, item) in known.iter_mut() {
+        if !schema_known.contains_key(&key) {
+            *item = ConfigItem::Value(ConfigValue::Array(Default::default()));
+        }
+    }
+    merge_known_tables(&mut known, schema_known);
+    known
+}
+
+fn known_table_from_defaults(table: &ConfigTableLike) -> ConfigTable {
+    table
+        .iter()
+        .map(|(key, item)| {
+            let known_item = match item.as_table_like() {
+                Some(table) => ConfigItem::Table(known_table_from_defaults(table)),
+                None => ConfigItem::Value(ConfigValue::Array(Default::default())),
+            };
+            (key, known_item)
+        })
+        .collect()
+}
+
+fn merge_known_tables(lower: &mut ConfigTable, upper: ConfigTable) {
+    for (key, upper_item) in upper {
+        match (lower.get_mut(&key), upper_item) {
+            (Some(ConfigItem::Table(lower_table)), ConfigItem::Table(upper_table)) => {
+                merge_known_tables(lower_table, upper_table);
+            }
+            (_, upper_item) => {
+                lower.insert(&key, upper_item);
+            }
+        }
+    }
+}
+
+fn known_item_from_schema(schema: &serde_json::Value, root: &serde_json::Value) -> ConfigItem {
+    if let Some(schema) = resolve_schema_ref(schema, root) {
+        return known_item_from_schema(schema, root);
+    }
+    match &schema["properties"] {
+        serde_json::Value::Object(properties) if schema.get("additionalProperties").is_none() => {
+            let mut table = ConfigTable::new();
+            for (key, property) in properties {
+                table.insert(key, known_item_from_schema(property, root));
+            }
+            ConfigItem::Table(table)
+        }
+        _ => {
+            let types = schema_type_names(schema, root).unwrap_or_default();
+            ConfigItem::Value(types.into_iter().collect())
+        }
+    }
+}
+
+/// Returns TOML type names the `schema` accepts, or `None` if the schema
+/// doesn't restrict the type.
+fn schema_type_names(
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+) -> Option<BTreeSet<&'static str>> {
+    if let Some(schema) = resolve_schema_ref(schema, root) {
+        return schema_type_names(schema, root);
+    }
+    let to_toml_names = |name: &str| -> &'static [&'static str] {
+        match name {
+            "string" => &["string"],
+            "boolean" => &["boolean"],
+            "integer" => &["integer"],
+            "number" => &["integer", "float"],
+            "array" => &["array"],
+            "object" => &["table"],
+            _ => &[],
+        }
+    };
+    if let Some(name) = schema["type"].as_str() {
+        return Some(to_toml_names(name).iter().copied().collect());
+    } else if let Some(names) = schema["type"].as_array() {
+        let names = names.iter().filter_map(|name| name.as_str());
+        return Some(names.flat_map(to_toml_names).copied().collect());
+    } else if let Some(values) = schema["enum"].as_array() {
+        let names = values.iter().map(|value| match value {
+            serde_json::Value::Bool(_) => "boolean",
+            serde_json::Value::Number(n) if n.is_f64() => "float",
+            serde_json::Value::Number(_) => "integer",
+            serde_json::Value::String(_) => "string",
+            serde_json::Value::Array(_) => "array",
+            serde_json::Value::Object(_) | serde_json::Value::Null => "table",
+        });
+        return Some(names.collect());
+    }
+    let alternatives = schema["oneOf"].as_array().or(schema["anyOf"].as_array())?;
+    alternatives
+        .iter()
+        .map(|alternative| schema_type_names(alternative, root))
+        .reduce(|acc, names| Some(acc?.into_iter().chain(names?).collect()))?
+}
+
+fn resolve_schema_ref<'a>(
+    schema: &serde_json::Value,
+    root: &'a serde_json::Value,
+) -> Option<&'a serde_json::Value> {
+    let pointer = schema["$ref"].as_str()?.strip_prefix('#')?;
+    root.pointer(pointer)
+}
+
+/// Checks user and repo config layers against the `known` table built by
+/// [`known_config_table()`], and returns warning messages.
+pub fn config_layer_warnings(layer: &ConfigLayer, known: &ConfigTable) -> Vec<String> {
+    let location = |name: &ConfigNamePathBuf| {
+        let path = layer.path.as_ref().map_or_else(
+            || format!("{} config", layer.source),
+            |path| path.display().to_string(),
+        );
+        match layer.source_line(name) {
+            Some(line) => format!("{path}:{line}"),
+            None => path,
+        }
+    };
+    let mut warnings = layer
+        .unknown_names(known)
+        .iter()
+        .map(|name| format!("Unknown config variable {name} in {}", location(name)))
+        .collect_vec();
+    let mut mismatches = Vec::new();
+    collect_type_mismatches(
+        layer.data.as_table(),
+        known,
+        &ConfigNamePathBuf::root(),
+        &mut mismatches,
+    );
+    warnings.extend(mismatches.iter().map(|(name, expected, actual)| {
+        format!(
+            "Config variable {name} in {} should be {}, but is {actual}",
+            location(name),
+            expected.iter().join(" or ")
+        )
+    }));
+    warnings
+}
+
+type TypeMismatch<'a> = (ConfigNamePathBuf, Vec<&'a str>, &'static str);
+
+fn collect_type_mismatches<'a>(
+    table: &ConfigTableLike,
+    known: &'a ConfigTableLike,
+    prefix: &ConfigNamePathBuf,
+    mismatches: &mut Vec<TypeMismatch<'a>>,
+) {
+    for (key, item) in table.iter() {
+        let mut name = prefix.clone();
+        name.push(key);
+        match (known.get(key), item.as_table_like()) {
+            (Some(ConfigItem::Table(known_table)), Some(table)) => {
+                collect_type_mismatches(table, known_table, &name, mismatches);
+            }
+            (Some(ConfigItem::Table(_)), None) => {
+                mismatches.push((name, vec!["table"], toml_type_name(item)));
+            }
+            (Some(ConfigItem::Value(ConfigValue::Array(types))), _) => {
+                let types = types.iter().filter_map(|ty| ty.as_str()).collect_vec();
+                let actual = toml_type_name(item);
+                if !types.is_empty() && !types.contains(&actual) {
+                    mismatches.push((name, types, actual));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn toml_type_name(item: &ConfigItem) -> &'static str {
+    match item {
+        ConfigItem::None => "none",
+        ConfigItem::Value(ConfigValue::String(_)) => "string",
+        ConfigItem::Value(ConfigValue::Integer(_)) => "integer",
+        ConfigItem::Value(ConfigValue::Float(_)) => "float",
+        ConfigItem::Value(ConfigValue::Boolean(_)) => "boolean",
+        ConfigItem::Value(ConfigValue::Datetime(_)) => "datetime",
+        ConfigItem::Value(ConfigValue::Array(_)) | ConfigItem::ArrayOfTables(_) => "array",
+        ConfigItem::Value(ConfigValue::InlineTable(_)) | ConfigItem::Table(_) => "table",
+    }
+}
+
 /// Command name and arguments specified by config.
 #[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize)]
 #[serde(untagged)]
@@ -917,6 +1121,34 @@ mod tests {
         assert_eq!(args, ["-nw"].as_ref());
     }
 
+    #[test]
+    fn test_config_layer_warnings() {
+        let known = known_config_table(&default_config_layers());
+        let layer = ConfigLayer::parse(
+            ConfigSource::User,
+            indoc! {"
+                ui.color = 'always'
+                ui.quiet = true
+                ui.unknown = 1
+                snapshot.max-new-file-size = 1024
+                git.auto-local-bookmark = 'yes'
+                aliases.l = ['log']
+                templates.anything = 'x'
+                revset-aliases = 'not a table'
+                unknown.key = 1
+            "},
+        )
+        .unwrap();
+        insta::assert_debug_snapshot!(config_layer_warnings(&layer, &known), @r#"
+        [
+            "Unknown config variable ui.unknown in user config",
+            "Unknown config variable unknown in user config",
+            "Config variable git.auto-local-bookmark in user config should be boolean, but is string",
+            "Config variable revset-aliases in user config should be table, but is string",
+        ]
+        "#);
+    }
+
     #[test]
     fn test_resolved_config_values_empty() {
         let config = StackedConfig::empty();
@@ -12,14 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::borrow::Borrow;
 use std::cmp::max;
 use std::io;
 use std::iter;
-use std::mem;
 use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use bstr::BStr;
 use futures::executor::block_on_stream;
@@ -27,6 +26,7 @@ use futures::stream::BoxStream;
 use futures::StreamExt as _;
 use futures::TryStreamExt as _;
 use itertools::Itertools;
+use jj_lib::attributes::FileAttributes;
 use jj_lib::backend::BackendError;
 use jj_lib::backend::BackendResult;
 use jj_lib::backend::CommitId;
@@ -44,21 +44,28 @@ use jj_lib::copies::CopiesTreeDiffEntry;
 use jj_lib::copies::CopiesTreeDiffEntryPath;
 use jj_lib::copies::CopyOperation;
 use jj_lib::copies::CopyRecords;
-use jj_lib::diff::find_line_ranges;
-use jj_lib::diff::CompareBytesExactly;
-use jj_lib::diff::CompareBytesIgnoreAllWhitespace;
-use jj_lib::diff::CompareBytesIgnoreWhitespaceAmount;
 use jj_lib::diff::Diff;
 use jj_lib::diff::DiffHunk;
 use jj_lib::diff::DiffHunkContentVec;
 use jj_lib::diff::DiffHunkKind;
+use jj_lib::diff_presentation::diff_by_line;
+use jj_lib::diff_presentation::file_content_for_diff;
+use jj_lib::diff_presentation::unified::git_diff_file_paths;
+use jj_lib::diff_presentation::unified::git_diff_part;
+use jj_lib::diff_presentation::unified::unified_diff_hunks;
+use jj_lib::diff_presentation::unified::unzip_diff_hunks_to_lines;
+use jj_lib::diff_presentation::unified::write_git_diff_header;
+use jj_lib::diff_presentation::unified::DiffLineType;
+use jj_lib::diff_presentation::unified::DiffTokenType;
+use jj_lib::diff_presentation::unified::UnifiedDiffError;
+use jj_lib::diff_presentation::FileContent;
+pub use jj_lib::diff_presentation::LineCompareMode;
 use jj_lib::files::DiffLineHunkSide;
 use jj_lib::files::DiffLineIterator;
 use jj_lib::files::DiffLineNumber;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
-use jj_lib::object_id::ObjectId;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::InvalidRepoPathError;
 use jj_lib::repo_path::RepoPath;
@@ -283,6 +290,21 @@ pub enum DiffRenderError {
     Io(#[from] io::Error),
 }
 
+impl From<UnifiedDiffError> for DiffRenderError {
+    fn from(err: UnifiedDiffError) -> Self {
+        match err {
+            UnifiedDiffError::Backend(err) => DiffRenderError::Backend(err),
+            UnifiedDiffError::AccessDenied { path, source } => {
+                DiffRenderError::AccessDenied { path, source }
+            }
+            UnifiedDiffError::Io(err) => DiffRenderError::Io(err),
+            err @ UnifiedDiffError::BothSidesAbsent { .. } => {
+                DiffRenderError::Io(io::Error::other(err))
+            }
+        }
+    }
+}
+
 /// Configuration and environment to render textual diff.
 pub struct DiffRenderer<'a> {
     repo: &'a dyn Repo,
@@ -512,38 +534,6 @@ impl LineDiffOptions {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub enum LineCompareMode {
-    /// Compares lines literally.
-    #[default]
-    Exact,
-    /// Compares lines ignoring any whitespace occurrences.
-    IgnoreAllSpace,
-    /// Compares lines ignoring changes in whitespace amount.
-    IgnoreSpaceChange,
-}
-
-fn diff_by_line<'input, T: AsRef<[u8]> + ?Sized + 'input>(
-    inputs: impl IntoIterator<Item = &'input T>,
-    options: &LineDiffOptions,
-) -> Diff<'input> {
-    // TODO: If we add --ignore-blank-lines, its tokenizer will have to attach
-    // blank lines to the preceding range. Maybe it can also be implemented as a
-    // post-process (similar to refine_changed_regions()) that expands unchanged
-    // regions across blank lines.
-    match options.compare_mode {
-        LineCompareMode::Exact => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesExactly)
-        }
-        LineCompareMode::IgnoreAllSpace => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreAllWhitespace)
-        }
-        LineCompareMode::IgnoreSpaceChange => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreWhitespaceAmount)
-        }
-    }
-}
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ColorWordsDiffOptions {
     /// Number of context lines to show.
@@ -552,6 +542,8 @@ pub struct ColorWordsDiffOptions {
     pub line_diff: LineDiffOptions,
     /// Maximum number of removed/added word alternation to inline.
     pub max_inline_alternation: Option<usize>,
+    /// Per-path overrides of binary file detection.
+    pub file_attributes: Arc<FileAttributes>,
 }
 
 impl ColorWordsDiffOptions {
@@ -571,6 +563,7 @@ impl ColorWordsDiffOptions {
             context: settings.get("diff.color-words.context")?,
             line_diff: LineDiffOptions::default(),
             max_inline_alternation,
+            file_attributes: Arc::new(FileAttributes::from_settings(settings)?),
         })
     }
 
@@ -588,7 +581,7 @@ fn show_color_words_diff_hunks(
     right: &[u8],
     options: &ColorWordsDiffOptions,
 ) -> io::Result<()> {
-    let line_diff = diff_by_line([left, right], &options.line_diff);
+    let line_diff = diff_by_line([left, right], options.line_diff.compare_mode);
     let mut line_number = DiffLineNumber { left: 1, right: 1 };
     // Matching entries shouldn't appear consecutively in diff of two inputs.
     // However, if the inputs have conflicts, there may be a hunk that can be
@@ -850,43 +843,6 @@ fn split_diff_hunks_by_matching_newline<'a, 'b>(
     })
 }
 
-struct FileContent {
-    /// false if this file is likely text; true if it is likely binary.
-    is_binary: bool,
-    contents: Vec<u8>,
-}
-
-impl FileContent {
-    fn empty() -> Self {
-        Self {
-            is_binary: false,
-            contents: vec![],
-        }
-    }
-
-    pub(crate) fn is_empty(&self) -> bool {
-        self.contents.is_empty()
-    }
-}
-
-fn file_content_for_diff(reader: &mut dyn io::Read) -> io::Result<FileContent> {
-    // If this is a binary file, don't show the full contents.
-    // Determine whether it's binary by whether the first 8k bytes contain a null
-    // character; this is the same heuristic used by git as of writing: https://github.com/git/git/blob/eea0e59ffbed6e33d171ace5be13cde9faa41639/xdiff-interface.c#L192-L198
-    const PEEK_SIZE: usize = 8000;
-    // TODO: currently we look at the whole file, even though for binary files we
-    // only need to know the file size. To change that we'd have to extend all
-    // the data backends to support getting the length.
-    let mut contents = vec![];
-    reader.read_to_end(&mut contents)?;
-
-    let start = &contents[..PEEK_SIZE.min(contents.len())];
-    Ok(FileContent {
-        is_binary: start.contains(&b'\0'),
-        contents,
-    })
-}
-
 fn diff_content(
     path: &RepoPath,
     value: MaterializedTreeValue,
@@ -932,6 +888,14 @@ fn diff_content(
     }
 }
 
+/// Overrides the detected binary-ness of `content` by the file attributes of
+/// `path`.
+fn apply_file_attributes(content: &mut FileContent, path: &RepoPath, attributes: &FileAttributes) {
+    if let Some(is_binary) = attributes.binary_for_contents(path, &content.contents) {
+        content.is_binary = is_binary;
+    }
+}
+
 fn basic_diff_file_type(value: &MaterializedTreeValue) -> &'static str {
     match value {
         MaterializedTreeValue::Absent => {
@@ -995,7 +959,9 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Added {description} {right_ui_path}:"
                 )?;
-                let right_content = diff_content(right_path, right_value, conflict_marker_style)?;
+                let mut right_content =
+                    diff_content(right_path, right_value, conflict_marker_style)?;
+                apply_file_attributes(&mut right_content, right_path, &options.file_attributes);
                 if right_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if right_content.is_binary {
@@ -1057,8 +1023,11 @@ pub fn show_color_words_diff(
                         )
                     }
                 };
-                let left_content = diff_content(left_path, left_value, conflict_marker_style)?;
-                let right_content = diff_content(right_path, right_value, conflict_marker_style)?;
+                let mut left_content = diff_content(left_path, left_value, conflict_marker_style)?;
+                let mut right_content =
+                    diff_content(right_path, right_value, conflict_marker_style)?;
+                apply_file_attributes(&mut left_content, left_path, &options.file_attributes);
+                apply_file_attributes(&mut right_content, right_path, &options.file_attributes);
                 if left_path == right_path {
                     writeln!(
                         formatter.labeled("header"),
@@ -1086,7 +1055,8 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Removed {description} {right_ui_path}:"
                 )?;
-                let left_content = diff_content(left_path, left_value, conflict_marker_style)?;
+                let mut left_content = diff_content(left_path, left_value, conflict_marker_style)?;
+                apply_file_attributes(&mut left_content, left_path, &options.file_attributes);
                 if left_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if left_content.is_binary {
@@ -1172,99 +1142,14 @@ pub fn show_file_by_file_diff(
     .block_on()
 }
 
-struct GitDiffPart {
-    /// Octal mode string or `None` if the file is absent.
-    mode: Option<&'static str>,
-    hash: String,
-    content: FileContent,
-}
-
-fn git_diff_part(
-    path: &RepoPath,
-    value: MaterializedTreeValue,
-    conflict_marker_style: ConflictMarkerStyle,
-) -> Result<GitDiffPart, DiffRenderError> {
-    const DUMMY_HASH: &str = "0000000000";
-    let mode;
-    let mut hash;
-    let content;
-    match value {
-        MaterializedTreeValue::Absent => {
-            return Ok(GitDiffPart {
-                mode: None,
-                hash: DUMMY_HASH.to_owned(),
-                content: FileContent::empty(),
-            });
-        }
-        MaterializedTreeValue::AccessDenied(err) => {
-            return Err(DiffRenderError::AccessDenied {
-                path: path.as_internal_file_string().to_owned(),
-                source: err,
-            });
-        }
-        MaterializedTreeValue::File {
-            id,
-            executable,
-            mut reader,
-        } => {
-            mode = if executable { "100755" } else { "100644" };
-            hash = id.hex();
-            content = file_content_for_diff(&mut reader)?;
-        }
-        MaterializedTreeValue::Symlink { id, target } => {
-            mode = "120000";
-            hash = id.hex();
-            content = FileContent {
-                // Unix file paths can't contain null bytes.
-                is_binary: false,
-                contents: target.into_bytes(),
-            };
-        }
-        MaterializedTreeValue::GitSubmodule(id) => {
-            // TODO: What should we actually do here?
-            mode = "040000";
-            hash = id.hex();
-            content = FileContent::empty();
-        }
-        MaterializedTreeValue::FileConflict {
-            id: _,
-            contents,
-            executable,
-        } => {
-            mode = if executable { "100755" } else { "100644" };
-            hash = DUMMY_HASH.to_owned();
-            content = FileContent {
-                is_binary: false, // TODO: are we sure this is never binary?
-                contents: materialize_merge_result_to_bytes(&contents, conflict_marker_style)
-                    .into(),
-            };
-        }
-        MaterializedTreeValue::OtherConflict { id } => {
-            mode = "100644";
-            hash = DUMMY_HASH.to_owned();
-            content = FileContent {
-                is_binary: false,
-                contents: id.describe().into_bytes(),
-            };
-        }
-        MaterializedTreeValue::Tree(_) => {
-            panic!("Unexpected tree in diff at path {path:?}");
-        }
-    }
-    hash.truncate(10);
-    Ok(GitDiffPart {
-        mode: Some(mode),
-        hash,
-        content,
-    })
-}
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UnifiedDiffOptions {
     /// Number of context lines to show.
     pub context: usize,
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
+    /// Per-path overrides of binary file detection.
+    pub file_attributes: Arc<FileAttributes>,
 }
 
 impl UnifiedDiffOptions {
@@ -1272,6 +1157,7 @@ impl UnifiedDiffOptions {
         Ok(UnifiedDiffOptions {
             context: settings.get("diff.git.context")?,
             line_diff: LineDiffOptions::default(),
+            file_attributes: Arc::new(FileAttributes::from_settings(settings)?),
         })
     }
 
@@ -1283,170 +1169,6 @@ impl UnifiedDiffOptions {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum DiffLineType {
-    Context,
-    Removed,
-    Added,
-}
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum DiffTokenType {
-    Matching,
-    Different,
-}
-
-type DiffTokenVec<'content> = Vec<(DiffTokenType, &'content [u8])>;
-
-struct UnifiedDiffHunk<'content> {
-    left_line_range: Range<usize>,
-    right_line_range: Range<usize>,
-    lines: Vec<(DiffLineType, DiffTokenVec<'content>)>,
-}
-
-impl<'content> UnifiedDiffHunk<'content> {
-    fn extend_context_lines(&mut self, lines: impl IntoIterator<Item = &'content [u8]>) {
-        let old_len = self.lines.len();
-        self.lines.extend(lines.into_iter().map(|line| {
-            let tokens = vec![(DiffTokenType::Matching, line)];
-            (DiffLineType::Context, tokens)
-        }));
-        self.left_line_range.end += self.lines.len() - old_len;
-        self.right_line_range.end += self.lines.len() - old_len;
-    }
-
-    fn extend_removed_lines(&mut self, lines: impl IntoIterator<Item = DiffTokenVec<'content>>) {
-        let old_len = self.lines.len();
-        self.lines
-            .extend(lines.into_iter().map(|line| (DiffLineType::Removed, line)));
-        self.left_line_range.end += self.lines.len() - old_len;
-    }
-
-    fn extend_added_lines(&mut self, lines: impl IntoIterator<Item = DiffTokenVec<'content>>) {
-        let old_len = self.lines.len();
-        self.lines
-            .extend(lines.into_iter().map(|line| (DiffLineType::Added, line)));
-        self.right_line_range.end += self.lines.len() - old_len;
-    }
-}
-
-fn unified_diff_hunks<'content>(
-    left_content: &'content [u8],
-    right_content: &'content [u8],
-    options: &UnifiedDiffOptions,
-) -> Vec<UnifiedDiffHunk<'content>> {
-    let mut hunks = vec![];
-    let mut current_hunk = UnifiedDiffHunk {
-        left_line_range: 0..0,
-        right_line_range: 0..0,
-        lines: vec![],
-    };
-    let diff = diff_by_line([left_content, right_content], &options.line_diff);
-    let mut diff_hunks = diff.hunks().peekable();
-    while let Some(hunk) = diff_hunks.next() {
-        match hunk.kind {
-            DiffHunkKind::Matching => {
-                // Just use the right (i.e. new) content. We could count the
-                // number of skipped lines separately, but the number of the
-                // context lines should match the displayed content.
-                let [_, right] = hunk.contents[..].try_into().unwrap();
-                let mut lines = right.split_inclusive(|b| *b == b'\n').fuse();
-                if !current_hunk.lines.is_empty() {
-                    // The previous hunk line should be either removed/added.
-                    current_hunk.extend_context_lines(lines.by_ref().take(options.context));
-                }
-                let before_lines = if diff_hunks.peek().is_some() {
-                    lines.by_ref().rev().take(options.context).collect()
-                } else {
-                    vec![] // No more hunks
-                };
-                let num_skip_lines = lines.count();
-                if num_skip_lines > 0 {
-                    let left_start = current_hunk.left_line_range.end + num_skip_lines;
-                    let right_start = current_hunk.right_line_range.end + num_skip_lines;
-                    if !current_hunk.lines.is_empty() {
-                        hunks.push(current_hunk);
-                    }
-                    current_hunk = UnifiedDiffHunk {
-                        left_line_range: left_start..left_start,
-                        right_line_range: right_start..right_start,
-                        lines: vec![],
-                    };
-                }
-                // The next hunk should be of DiffHunk::Different type if any.
-                current_hunk.extend_context_lines(before_lines.into_iter().rev());
-            }
-            DiffHunkKind::Different => {
-                let (left_lines, right_lines) =
-                    unzip_diff_hunks_to_lines(Diff::by_word(hunk.contents).hunks());
-                current_hunk.extend_removed_lines(left_lines);
-                current_hunk.extend_added_lines(right_lines);
-            }
-        }
-    }
-    if !current_hunk.lines.is_empty() {
-        hunks.push(current_hunk);
-    }
-    hunks
-}
-
-/// Splits `(left, right)` hunk pairs into `(left_lines, right_lines)`.
-fn unzip_diff_hunks_to_lines<'content, I>(
-    diff_hunks: I,
-) -> (Vec<DiffTokenVec<'content>>, Vec<DiffTokenVec<'content>>)
-where
-    I: IntoIterator,
-    I::Item: Borrow<DiffHunk<'content>>,
-{
-    let mut left_lines: Vec<DiffTokenVec<'content>> = vec![];
-    let mut right_lines: Vec<DiffTokenVec<'content>> = vec![];
-    let mut left_tokens: DiffTokenVec<'content> = vec![];
-    let mut right_tokens: DiffTokenVec<'content> = vec![];
-
-    for hunk in diff_hunks {
-        let hunk = hunk.borrow();
-        match hunk.kind {
-            DiffHunkKind::Matching => {
-                // TODO: add support for unmatched contexts
-                debug_assert!(hunk.contents.iter().all_equal());
-                for token in hunk.contents[0].split_inclusive(|b| *b == b'\n') {
-                    left_tokens.push((DiffTokenType::Matching, token));
-                    right_tokens.push((DiffTokenType::Matching, token));
-                    if token.ends_with(b"\n") {
-                        left_lines.push(mem::take(&mut left_tokens));
-                        right_lines.push(mem::take(&mut right_tokens));
-                    }
-                }
-            }
-            DiffHunkKind::Different => {
-                let [left, right] = hunk.contents[..]
-                    .try_into()
-                    .expect("hunk should have exactly two inputs");
-                for token in left.split_inclusive(|b| *b == b'\n') {
-                    left_tokens.push((DiffTokenType::Different, token));
-                    if token.ends_with(b"\n") {
-                        left_lines.push(mem::take(&mut left_tokens));
-                    }
-                }
-                for token in right.split_inclusive(|b| *b == b'\n') {
-                    right_tokens.push((DiffTokenType::Different, token));
-                    if token.ends_with(b"\n") {
-                        right_lines.push(mem::take(&mut right_tokens));
-                    }
-                }
-            }
-        }
-    }
-
-    if !left_tokens.is_empty() {
-        left_lines.push(left_tokens);
-    }
-    if !right_tokens.is_empty() {
-        right_lines.push(right_tokens);
-    }
-    (left_lines, right_lines)
-}
-
 fn show_unified_diff_hunks(
     formatter: &mut dyn Formatter,
     left_content: &[u8],
@@ -1468,7 +1190,13 @@ fn show_unified_diff_hunks(
         }
     }
 
-    for hunk in unified_diff_hunks(left_content, right_content, options) {
+    let hunks = unified_diff_hunks(
+        left_content,
+        right_content,
+        options.context,
+        options.line_diff.compare_mode,
+    );
+    for hunk in hunks {
         writeln!(
             formatter.labeled("hunk_header"),
             "@@ -{},{} +{},{} @@",
@@ -1523,66 +1251,26 @@ pub fn show_git_diff(
         while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
             let left_path = path.source();
             let right_path = path.target();
-            let left_path_string = left_path.as_internal_file_string();
-            let right_path_string = right_path.as_internal_file_string();
             let (left_value, right_value) = values?;
 
-            let left_part = git_diff_part(left_path, left_value, conflict_marker_style)?;
-            let right_part = git_diff_part(right_path, right_value, conflict_marker_style)?;
-
-            formatter.with_label("file_header", |formatter| {
-                writeln!(
-                    formatter,
-                    "diff --git a/{left_path_string} b/{right_path_string}"
-                )?;
-                let left_hash = &left_part.hash;
-                let right_hash = &right_part.hash;
-                match (left_part.mode, right_part.mode) {
-                    (None, Some(right_mode)) => {
-                        writeln!(formatter, "new file mode {right_mode}")?;
-                        writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                    }
-                    (Some(left_mode), None) => {
-                        writeln!(formatter, "deleted file mode {left_mode}")?;
-                        writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                    }
-                    (Some(left_mode), Some(right_mode)) => {
-                        if let Some(op) = path.copy_operation() {
-                            let operation = match op {
-                                CopyOperation::Copy => "copy",
-                                CopyOperation::Rename => "rename",
-                            };
-                            // TODO: include similarity index?
-                            writeln!(formatter, "{operation} from {left_path_string}")?;
-                            writeln!(formatter, "{operation} to {right_path_string}")?;
-                        }
-                        if left_mode != right_mode {
-                            writeln!(formatter, "old mode {left_mode}")?;
-                            writeln!(formatter, "new mode {right_mode}")?;
-                            if left_hash != right_hash {
-                                writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                            }
-                        } else if left_hash != right_hash {
-                            writeln!(formatter, "index {left_hash}..{right_hash} {left_mode}")?;
-                        }
-                    }
-                    (None, None) => panic!("either left or right part should be present"),
-                }
-                Ok::<(), DiffRenderError>(())
+            let mut left_part = git_diff_part(left_path, left_value, conflict_marker_style)?;
+            let mut right_part = git_diff_part(right_path, right_value, conflict_marker_style)?;
+            apply_file_attributes(&mut left_part.content, left_path, &options.file_attributes);
+            apply_file_attributes(
+                &mut right_part.content,
+                right_path,
+                &options.file_attributes,
+            );
+
+            formatter.with_label("file_header", |mut formatter| {
+                write_git_diff_header(&mut formatter, &path, &left_part, &right_part, false)
             })?;
 
             if left_part.content.contents == right_part.content.contents {
                 continue; // no content hunks
             }
 
-            let left_path = match left_part.mode {
-                Some(_) => format!("a/{left_path_string}"),
-                None => "/dev/null".to_owned(),
-            };
-            let right_path = match right_part.mode {
-                Some(_) => format!("b/{right_path_string}"),
-                None => "/dev/null".to_owned(),
-            };
+            let (left_path, right_path) = git_diff_file_paths(&path, &left_part, &right_part);
             if left_part.content.is_binary || right_part.content.is_binary {
                 // TODO: add option to emit Git binary diff
                 writeln!(
@@ -1722,7 +1410,7 @@ fn get_diff_stat_entry(
     // many `0x0a` characters are in an image.
     let diff = diff_by_line(
         [&left_content.contents, &right_content.contents],
-        &options.line_diff,
+        options.line_diff.compare_mode,
     );
     let mut added = 0;
     let mut removed = 0;
@@ -9,7 +9,9 @@ use std::sync::Arc;
 use futures::StreamExt;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::eol::EolSettings;
 use jj_lib::fsmonitor::FsmonitorSettings;
+use jj_lib::git_lfs::LfsSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::TreeState;
 use jj_lib::local_working_copy::TreeStateError;
@@ -17,6 +19,7 @@ use jj_lib::matchers::EverythingMatcher;
 use jj_lib::matchers::Matcher;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::merged_tree::TreeDiffEntry;
+use jj_lib::repo_path::PathNormalization;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::store::Store;
 use jj_lib::working_copy::CheckoutError;
@@ -299,6 +302,9 @@ diff editing in mind and be a little inaccurate.
             start_tracking_matcher: &EverythingMatcher,
             max_new_file_size: u64::MAX,
             conflict_marker_style,
+            path_normalization: PathNormalization::default(),
+            eol_settings: EolSettings::default(),
+            lfs_settings: LfsSettings::default(),
         })?;
         Ok(output_tree_state.current_tree_id().clone())
     }
@@ -11,9 +11,11 @@ use itertools::Itertools;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::conflicts;
 use jj_lib::conflicts::choose_materialized_conflict_marker_len;
-use jj_lib::conflicts::materialize_merge_result_to_bytes_with_marker_len;
+use jj_lib::conflicts::materialize_file_conflict_to_bytes_with_marker_len;
 use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::conflicts::MIN_CONFLICT_MARKER_LEN;
+use jj_lib::eol::EolSettings;
+use jj_lib::git_lfs::LfsSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::Merge;
@@ -203,7 +205,8 @@ fn run_mergetool_external_single_file(
         MIN_CONFLICT_MARKER_LEN
     };
     let initial_output_content = if editor.merge_tool_edits_conflict_markers {
-        materialize_merge_result_to_bytes_with_marker_len(
+        materialize_file_conflict_to_bytes_with_marker_len(
+            &file_merge.clone().simplify(),
             content,
             conflict_marker_style,
             conflict_marker_len,
@@ -372,6 +375,10 @@ pub fn edit_diff_external(
         .unwrap_or(default_conflict_marker_style);
     let options = CheckoutOptions {
         conflict_marker_style,
+        progress: None,
+        eol_settings: EolSettings::default(),
+        lfs_settings: LfsSettings::default(),
+        force: false,
     };
 
     let got_output_field = find_all_variables(&editor.edit_args).contains(&"output");
@@ -420,6 +427,10 @@ pub fn generate_diff(
         .unwrap_or(default_conflict_marker_style);
     let options = CheckoutOptions {
         conflict_marker_style,
+        progress: None,
+        eol_settings: EolSettings::default(),
+        lfs_settings: LfsSettings::default(),
+        force: false,
     };
     let store = left_tree.store();
     let diff_wc = check_out_trees(store, left_tree, right_tree, matcher, None, &options)?;
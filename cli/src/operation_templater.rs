@@ -220,6 +220,14 @@ impl IntoTemplateProperty<'static> for OperationTemplatePropertyKind {
             (OperationTemplatePropertyKind::OperationId(_), _) => None,
         }
     }
+
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value>>> {
+        match self {
+            OperationTemplatePropertyKind::Core(property) => property.try_into_json(),
+            OperationTemplatePropertyKind::Operation(property) => Some(property.into_json()),
+            OperationTemplatePropertyKind::OperationId(property) => Some(property.into_json()),
+        }
+    }
 }
 
 /// Table of functions that translate method call node of self type `T`.
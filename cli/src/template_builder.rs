@@ -186,6 +186,9 @@ pub trait IntoTemplateProperty<'a> {
     /// Transforms into a property that will evaluate to an [`Ordering`].
     fn try_into_cmp(self, other: Self)
         -> Option<Box<dyn TemplateProperty<Output = Ordering> + 'a>>;
+
+    /// Transforms into a property that will evaluate to a JSON value.
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>>;
 }
 
 pub enum CoreTemplatePropertyKind<'a> {
@@ -360,6 +363,31 @@ impl<'a> IntoTemplateProperty<'a> for CoreTemplatePropertyKind<'a> {
             (CoreTemplatePropertyKind::ListTemplate(_), _) => None,
         }
     }
+
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>> {
+        match self {
+            CoreTemplatePropertyKind::String(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::StringList(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Boolean(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Integer(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::IntegerOpt(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::ConfigValue(property) => {
+                // toml_edit::Value doesn't implement Serialize, but it can be
+                // deserialized into any serde data type.
+                let json = property.and_then(|value| {
+                    Ok(serde_json::Value::deserialize(value.into_deserializer())?)
+                });
+                Some(Box::new(json))
+            }
+            CoreTemplatePropertyKind::Signature(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Email(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::SizeHint(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Timestamp(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::TimestampRange(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Template(_) => None,
+            CoreTemplatePropertyKind::ListTemplate(_) => None,
+        }
+    }
 }
 
 /// Function that translates global function call node.
@@ -634,6 +662,12 @@ impl<'a, P: IntoTemplateProperty<'a>> Expression<P> {
     ) -> Option<Box<dyn TemplateProperty<Output = Ordering> + 'a>> {
         self.property.try_into_cmp(other.property)
     }
+
+    pub fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>> {
+        self.property.try_into_json()
+    }
 }
 
 pub struct BuildContext<'i, P> {
@@ -1582,6 +1616,19 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
         let content = expect_plain_text_expression(language, diagnostics, build_ctx, content_node)?;
         Ok(L::wrap_string(content))
     });
+    map.insert("json", |language, diagnostics, build_ctx, function| {
+        let [value_node] = function.expect_exact_arguments()?;
+        let value = expect_expression_of_type(
+            language,
+            diagnostics,
+            build_ctx,
+            value_node,
+            "Serialize",
+            |expression| expression.try_into_json(),
+        )?;
+        let out_property = value.and_then(|value| Ok(serde_json::to_string(&value)?));
+        Ok(L::wrap_string(out_property))
+    });
     map.insert("if", |language, diagnostics, build_ctx, function| {
         let ([condition_node, true_node], [false_node]) = function.expect_arguments()?;
         let condition =
@@ -3184,6 +3231,44 @@ mod tests {
         insta::assert_snapshot!(env.render_ok("stringify(label('error', 'text'))"), @"text");
     }
 
+    #[test]
+    fn test_json_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_keyword("none_i64", || L::wrap_integer_opt(Literal(None)));
+        env.add_keyword("string_list", || {
+            L::wrap_string_list(Literal(vec!["foo".to_owned(), "\"bar\"".to_owned()]))
+        });
+        env.add_keyword("config_list", || {
+            L::wrap_config_value(Literal(ConfigValue::from_iter(["foo", "bar"])))
+        });
+        env.add_keyword("author", || {
+            L::wrap_signature(Literal(new_signature("Test User", "test.user@example.com")))
+        });
+        env.add_keyword("t0", || L::wrap_timestamp(Literal(new_timestamp(0, 0))));
+        env.add_keyword("bad_string", || L::wrap_string(new_error_property("Bad")));
+
+        insta::assert_snapshot!(env.render_ok(r#"json("a\nb")"#), @r#""a\nb""#);
+        insta::assert_snapshot!(env.render_ok("json(42)"), @"42");
+        insta::assert_snapshot!(env.render_ok("json(true)"), @"true");
+        insta::assert_snapshot!(env.render_ok("json(none_i64)"), @"null");
+        insta::assert_snapshot!(env.render_ok("json(string_list)"), @r#"["foo","\"bar\""]"#);
+        insta::assert_snapshot!(env.render_ok("json(config_list)"), @r#"["foo","bar"]"#);
+        insta::assert_snapshot!(
+            env.render_ok("json(author)"),
+            @r#"{"email":"test.user@example.com","name":"Test User","timestamp":"1970-01-01T00:00:00+00:00"}"#);
+        insta::assert_snapshot!(env.render_ok("json(t0)"), @r#""1970-01-01T00:00:00+00:00""#);
+        insta::assert_snapshot!(env.render_ok("json(bad_string)"), @"<Error: Bad>");
+
+        insta::assert_snapshot!(env.parse_err(r#"json(label("x", "y"))"#), @r#"
+         --> 1:6
+          |
+        1 | json(label("x", "y"))
+          |      ^-------------^
+          |
+          = Expected expression of type `Serialize`, but actual type is `Template`
+        "#);
+    }
+
     #[test]
     fn test_coalesce_function() {
         let mut env = TestTemplateEnv::new();
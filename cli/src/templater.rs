@@ -105,7 +105,8 @@ impl Template for Signature {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(transparent)]
 pub struct Email(pub String);
 
 impl From<String> for Email {
@@ -152,7 +153,7 @@ impl Template for Timestamp {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
 pub struct TimestampRange {
     // Could be aliased to Range<Timestamp> if needed.
     pub start: Timestamp,
@@ -435,6 +436,15 @@ pub trait TemplatePropertyExt: TemplateProperty {
     {
         Box::new(FormattablePropertyTemplate::new(self))
     }
+
+    /// Converts this property into a property of JSON value.
+    fn into_json<'a>(self) -> Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>
+    where
+        Self: Sized + 'a,
+        Self::Output: serde::Serialize,
+    {
+        Box::new(self.and_then(|value| Ok(serde_json::to_value(value)?)))
+    }
 }
 
 impl<P: TemplateProperty + ?Sized> TemplatePropertyExt for P {}
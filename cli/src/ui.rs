@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::VecDeque;
 use std::env;
 use std::error;
 use std::fmt;
 use std::io;
 use std::io::IsTerminal as _;
+use std::io::Read as _;
 use std::io::Stderr;
 use std::io::StderrLock;
 use std::io::Stdout;
@@ -27,6 +29,10 @@ use std::mem;
 use std::process::Child;
 use std::process::ChildStdin;
 use std::process::Stdio;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::thread;
 use std::thread::JoinHandle;
 
@@ -60,6 +66,7 @@ enum UiOutput {
         err_wr: PipeWriter,
         pager_thread: JoinHandle<streampager::Result<()>>,
     },
+    Captured(CapturedIo),
     Null,
 }
 
@@ -149,15 +156,106 @@ impl UiOutput {
                     }
                 }
             }
+            UiOutput::Captured(_) => {}
             UiOutput::Null => {}
         }
     }
 }
 
+/// In-memory stdout/stderr buffers and scripted prompt responses of a [`Ui`]
+/// created by [`Ui::with_captured_io()`].
+///
+/// This allows commands to be driven without a terminal, from tests or from
+/// another frontend. Cloned handles share the same buffers.
+#[derive(Clone, Debug, Default)]
+pub struct CapturedIo {
+    inner: Arc<CapturedIoInner>,
+}
+
+#[derive(Debug, Default)]
+struct CapturedIoInner {
+    stdout: Mutex<Vec<u8>>,
+    stderr: Mutex<Vec<u8>>,
+    // Threads copying the stderr of child processes into the stderr buffer.
+    child_stderr_readers: Mutex<Vec<JoinHandle<()>>>,
+    responses: Mutex<VecDeque<String>>,
+    pager_requested: AtomicBool,
+}
+
+impl CapturedIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a line of input to be returned by the next prompt.
+    pub fn push_response(&self, response: impl Into<String>) {
+        let mut responses = self.inner.responses.lock().unwrap();
+        responses.push_back(response.into());
+    }
+
+    /// Takes the bytes written to stdout so far.
+    pub fn take_stdout(&self) -> Vec<u8> {
+        mem::take(&mut *self.inner.stdout.lock().unwrap())
+    }
+
+    /// Takes the bytes written to stderr so far, including the stderr of child
+    /// processes. Waits for the child processes to close their stderr.
+    pub fn take_stderr(&self) -> Vec<u8> {
+        let readers = mem::take(&mut *self.inner.child_stderr_readers.lock().unwrap());
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        mem::take(&mut *self.inner.stderr.lock().unwrap())
+    }
+
+    /// Whether the command asked for its output to be paginated.
+    pub fn pager_requested(&self) -> bool {
+        self.inner.pager_requested.load(Ordering::Relaxed)
+    }
+
+    fn has_response(&self) -> bool {
+        !self.inner.responses.lock().unwrap().is_empty()
+    }
+
+    fn pop_response(&self) -> Option<String> {
+        self.inner.responses.lock().unwrap().pop_front()
+    }
+
+    /// Creates a pipe for the stderr of a child process, whose output is
+    /// appended to the stderr buffer.
+    fn child_stderr(&self) -> io::Result<PipeWriter> {
+        let (mut reader, writer) = os_pipe::pipe()?;
+        let inner = self.inner.clone();
+        let handle = thread::spawn(move || {
+            let mut buf = [0; 8192];
+            // The loop ends once all handles to the write end are closed.
+            while let Ok(n @ 1..) = reader.read(&mut buf) {
+                inner.stderr.lock().unwrap().extend_from_slice(&buf[..n]);
+            }
+        });
+        self.inner.child_stderr_readers.lock().unwrap().push(handle);
+        Ok(writer)
+    }
+}
+
+/// Writer appending to one of the [`CapturedIo`] buffers.
+pub struct CapturedWriter<'a>(&'a Mutex<Vec<u8>>);
+
+impl Write for CapturedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 pub enum UiStdout<'a> {
     Terminal(StdoutLock<'static>),
     Paged(&'a ChildStdin),
     Builtin(&'a PipeWriter),
+    Captured(CapturedWriter<'a>),
     Null(io::Sink),
 }
 
@@ -165,6 +263,7 @@ pub enum UiStderr<'a> {
     Terminal(StderrLock<'static>),
     Paged(&'a ChildStdin),
     Builtin(&'a PipeWriter),
+    Captured(CapturedWriter<'a>),
     Null(io::Sink),
 }
 
@@ -174,6 +273,7 @@ macro_rules! for_outputs {
             $ty::Terminal($pat) => $expr,
             $ty::Paged($pat) => $expr,
             $ty::Builtin($pat) => $expr,
+            $ty::Captured($pat) => $expr,
             $ty::Null($pat) => $expr,
         }
     };
@@ -238,9 +338,8 @@ impl fmt::Display for ColorChoice {
 
 fn prepare_formatter_factory(
     config: &StackedConfig,
-    stdout: &Stdout,
+    terminal: bool,
 ) -> Result<FormatterFactory, ConfigGetError> {
-    let terminal = stdout.is_terminal();
     let (color, debug) = match config.get("ui.color")? {
         ColorChoice::Always => (true, false),
         ColorChoice::Never => (false, false),
@@ -347,7 +446,7 @@ impl Ui {
     }
 
     pub fn with_config(config: &StackedConfig) -> Result<Ui, CommandError> {
-        let formatter_factory = prepare_formatter_factory(config, &io::stdout())?;
+        let formatter_factory = prepare_formatter_factory(config, io::stdout().is_terminal())?;
         Ok(Ui {
             quiet: config.get("ui.quiet")?,
             formatter_factory,
@@ -357,17 +456,44 @@ impl Ui {
         })
     }
 
+    /// Creates a `Ui` which writes to in-memory buffers and reads prompt
+    /// responses from the returned [`CapturedIo`] instead of the terminal.
+    ///
+    /// The output is never paginated, and isn't considered a terminal when
+    /// `ui.color = "auto"`.
+    pub fn with_captured_io(config: &StackedConfig) -> Result<(Ui, CapturedIo), CommandError> {
+        let io = CapturedIo::new();
+        let ui = Ui {
+            quiet: config.get("ui.quiet")?,
+            formatter_factory: prepare_formatter_factory(config, false)?,
+            pager: PagerConfig::from_config(config)?,
+            progress_indicator: false,
+            output: UiOutput::Captured(io.clone()),
+        };
+        Ok((ui, io))
+    }
+
     pub fn reset(&mut self, config: &StackedConfig) -> Result<(), CommandError> {
         self.quiet = config.get("ui.quiet")?;
         self.pager = PagerConfig::from_config(config)?;
         self.progress_indicator = config.get("ui.progress-indicator")?;
-        self.formatter_factory = prepare_formatter_factory(config, &io::stdout())?;
+        let terminal = match &self.output {
+            UiOutput::Captured(_) => false,
+            _ => io::stdout().is_terminal(),
+        };
+        self.formatter_factory = prepare_formatter_factory(config, terminal)?;
         Ok(())
     }
 
     /// Switches the output to use the pager, if allowed.
     #[instrument(skip_all)]
     pub fn request_pager(&mut self) {
+        if let UiOutput::Captured(io) = &self.output {
+            if !matches!(self.pager, PagerConfig::Disabled) {
+                io.inner.pager_requested.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
         if !matches!(&self.output, UiOutput::Terminal { stdout, .. } if stdout.is_terminal()) {
             return;
         }
@@ -426,6 +552,7 @@ impl Ui {
             UiOutput::Terminal { stdout, .. } => UiStdout::Terminal(stdout.lock()),
             UiOutput::Paged { child_stdin, .. } => UiStdout::Paged(child_stdin),
             UiOutput::BuiltinPaged { out_wr, .. } => UiStdout::Builtin(out_wr),
+            UiOutput::Captured(io) => UiStdout::Captured(CapturedWriter(&io.inner.stdout)),
             UiOutput::Null => UiStdout::Null(io::sink()),
         }
     }
@@ -444,6 +571,7 @@ impl Ui {
             UiOutput::Terminal { stderr, .. } => UiStderr::Terminal(stderr.lock()),
             UiOutput::Paged { child_stdin, .. } => UiStderr::Paged(child_stdin),
             UiOutput::BuiltinPaged { err_wr, .. } => UiStderr::Builtin(err_wr),
+            UiOutput::Captured(io) => UiStderr::Captured(CapturedWriter(&io.inner.stderr)),
             UiOutput::Null => UiStderr::Null(io::sink()),
         }
     }
@@ -459,6 +587,7 @@ impl Ui {
             UiOutput::Terminal { .. } => Ok(Stdio::inherit()),
             UiOutput::Paged { child_stdin, .. } => Ok(duplicate_child_stdin(child_stdin)?.into()),
             UiOutput::BuiltinPaged { err_wr, .. } => Ok(err_wr.try_clone()?.into()),
+            UiOutput::Captured(io) => Ok(io.child_stderr()?.into()),
             UiOutput::Null => Ok(Stdio::null()),
         }
     }
@@ -470,6 +599,7 @@ impl Ui {
             UiOutput::Terminal { stderr, .. } => self.progress_indicator && stderr.is_terminal(),
             UiOutput::Paged { .. } => false,
             UiOutput::BuiltinPaged { .. } => false,
+            UiOutput::Captured(_) => false,
             UiOutput::Null => false,
         }
     }
@@ -553,6 +683,9 @@ impl Ui {
     /// Waits for the pager exits.
     #[instrument(skip_all)]
     pub fn finalize_pager(&mut self) {
+        if let UiOutput::Captured(_) = &self.output {
+            return;
+        }
         let old_output = mem::replace(&mut self.output, UiOutput::new_terminal());
         old_output.finalize(self);
     }
@@ -564,7 +697,28 @@ impl Ui {
                 .unwrap_or(false)
     }
 
+    /// Whether prompts can read input, either from the terminal or from the
+    /// remaining scripted responses of a captured `Ui`.
+    fn can_read_input(&self) -> bool {
+        match &self.output {
+            UiOutput::Captured(io) => io.has_response(),
+            _ => Self::can_prompt(),
+        }
+    }
+
     pub fn prompt(&self, prompt: &str) -> io::Result<String> {
+        if let UiOutput::Captured(io) = &self.output {
+            // Echo the response as if it were typed in the terminal.
+            let response = io.pop_response();
+            writeln!(
+                self.stderr(),
+                "{prompt}: {}",
+                response.as_deref().unwrap_or("")
+            )?;
+            return response.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "Prompt cancelled by EOF")
+            });
+        }
         if !Self::can_prompt() {
             return Err(io::Error::new(
                 io::ErrorKind::Unsupported,
@@ -596,7 +750,7 @@ impl Ui {
         choices: &[impl AsRef<str>],
         default: Option<&str>,
     ) -> io::Result<String> {
-        if !Self::can_prompt() {
+        if !self.can_read_input() {
             if let Some(default) = default {
                 // Choose the default automatically without waiting.
                 writeln!(self.stderr(), "{prompt}: {default}")?;
@@ -637,6 +791,12 @@ impl Ui {
     }
 
     pub fn prompt_password(&self, prompt: &str) -> io::Result<String> {
+        if let UiOutput::Captured(io) = &self.output {
+            writeln!(self.stderr(), "{prompt}: ")?;
+            return io.pop_response().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "Prompt cancelled by EOF")
+            });
+        }
         if !io::stdout().is_terminal() {
             return Err(io::Error::new(
                 io::ErrorKind::Unsupported,
@@ -735,3 +895,110 @@ fn term_width() -> Option<u16> {
         crossterm::terminal::size().ok().map(|(cols, _)| cols)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use jj_lib::config::ConfigLayer;
+    use jj_lib::config::ConfigSource;
+
+    use super::*;
+
+    fn captured_ui(text: &str) -> (Ui, CapturedIo) {
+        let mut config = StackedConfig::with_defaults();
+        config.extend_layers(crate::config::default_config_layers());
+        config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+        Ui::with_captured_io(&config).unwrap()
+    }
+
+    #[test]
+    fn test_captured_output() {
+        let (mut ui, io) = captured_ui("");
+        writeln!(ui.stdout(), "out").unwrap();
+        writeln!(ui.status(), "status").unwrap();
+        writeln!(ui.warning_default(), "careful").unwrap();
+        ui.request_pager();
+        writeln!(ui.stdout_formatter().labeled("error"), "labeled").unwrap();
+        ui.finalize_pager();
+        assert_eq!(io.take_stdout(), b"out\nlabeled\n");
+        assert_eq!(io.take_stderr(), b"status\nWarning: careful\n");
+        assert!(io.pager_requested());
+
+        // Buffers are drained
+        assert_eq!(io.take_stdout(), b"");
+        assert_eq!(io.take_stderr(), b"");
+
+        let (mut ui, io) = captured_ui(
+            r#"
+            ui.quiet = true
+            ui.paginate = "never"
+            ui.color = "debug"
+            "#,
+        );
+        writeln!(ui.status(), "status").unwrap();
+        ui.request_pager();
+        writeln!(ui.stdout_formatter().labeled("error"), "labeled").unwrap();
+        assert_eq!(
+            io.take_stdout(),
+            b"\x1b[1m\x1b[39m<<error::labeled>>\x1b[0m\n"
+        );
+        assert_eq!(io.take_stderr(), b"");
+        assert!(!io.pager_requested());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_captured_child_stderr() {
+        let (ui, io) = captured_ui("");
+        writeln!(ui.stderr(), "before").unwrap();
+        let status = std::process::Command::new("sh")
+            .args(["-c", "echo child >&2"])
+            .stderr(ui.stderr_for_child().unwrap())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(io.take_stderr(), b"before\nchild\n");
+    }
+
+    #[test]
+    fn test_captured_prompt() {
+        let (ui, io) = captured_ui("");
+        io.push_response("hello");
+        io.push_response("maybe");
+        io.push_response("y");
+        io.push_response("");
+        io.push_response("secret");
+        assert_eq!(ui.prompt("Name").unwrap(), "hello");
+        assert!(ui.prompt_yes_no("Continue?", None).unwrap());
+        assert!(!ui.prompt_yes_no("Again?", Some(false)).unwrap());
+        assert_eq!(ui.prompt_password("Password").unwrap(), "secret");
+        assert_eq!(
+            ui.prompt("Name").unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            String::from_utf8(io.take_stderr()).unwrap(),
+            "Name: hello\nContinue? (yn): maybe\nunrecognized response\nContinue? (yn): y\nAgain? \
+             (yN): \nPassword: \nName: \n"
+        );
+    }
+
+    #[test]
+    fn test_captured_prompt_default_without_response() {
+        let (ui, io) = captured_ui("");
+        assert!(ui.prompt_yes_no("Continue?", Some(true)).unwrap());
+        assert_eq!(
+            ui.prompt_choice("Pick", &["a", "b"], Some("b")).unwrap(),
+            "b"
+        );
+        assert_eq!(
+            ui.prompt_choice("Pick", &["a", "b"], None)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            String::from_utf8(io.take_stderr()).unwrap(),
+            "Continue? (Yn): Y\nPick: b\nPick: \n"
+        );
+    }
+}
@@ -207,6 +207,9 @@ fn test_advance_bookmarks_overrides(make_commit: CommitFn) {
     ○  bookmarks{test_bookmark} desc: first
     ◆  bookmarks{} desc:
     [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0002.toml:1
+    [EOF]
     ");
     }
     make_commit(&work_dir, "second");
@@ -217,6 +220,9 @@ fn test_advance_bookmarks_overrides(make_commit: CommitFn) {
     ○  bookmarks{test_bookmark} desc: first
     ◆  bookmarks{} desc:
     [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0002.toml:1
+    [EOF]
     ");
     }
 
@@ -237,6 +243,10 @@ fn test_advance_bookmarks_overrides(make_commit: CommitFn) {
     ○  bookmarks{test_bookmark} desc: first
     ◆  bookmarks{} desc:
     [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0002.toml:1
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0003.toml:1
+    [EOF]
     ");
     }
 
@@ -257,6 +267,10 @@ fn test_advance_bookmarks_overrides(make_commit: CommitFn) {
     ○  bookmarks{} desc: first
     ◆  bookmarks{} desc:
     [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0002.toml:1
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0003.toml:1
+    [EOF]
     ");
     }
     make_commit(&work_dir, "fourth");
@@ -269,6 +283,10 @@ fn test_advance_bookmarks_overrides(make_commit: CommitFn) {
     ○  bookmarks{} desc: first
     ◆  bookmarks{} desc:
     [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0002.toml:1
+    Warning: Unknown config variable experimental-advance-bookmarks in $TEST_ENV/config/config0003.toml:1
+    [EOF]
     ");
     }
 }
@@ -242,6 +242,61 @@ fn test_mine_is_true_when_author_is_user() {
     ");
 }
 
+#[test]
+fn test_log_description_parts() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir
+        .run_jj([
+            "new",
+            "-m",
+            "second\n\nbody\n\nFixes: #123\nSigned-off-by: Test User <test.user@example.com>",
+        ])
+        .success();
+
+    let template = r#"
+    separate("\n",
+      "subject: " ++ subject,
+      "body: " ++ body,
+      "trailers: " ++ trailers.map(|t| t.key() ++ "=" ++ t.value()).join(","),
+      "list: " ++ trailers.len(),
+    ) ++ "\n"
+    "#;
+    let output = work_dir.run_jj(["log", "-r~root()", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    @  subject: second
+    │  body: body
+    │
+    │  Fixes: #123
+    │  Signed-off-by: Test User <test.user@example.com>
+    │
+    │  trailers: Fixes=#123,Signed-off-by=Test User <test.user@example.com>
+    │  list: 2
+    ○  subject: first
+    │  body:
+    ~  trailers:
+       list: 0
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r",
+        "description_contains_trailer('signed-off-by')",
+        "-T",
+        "trailers ++ \"\\n\"",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    Fixes: #123
+    Signed-off-by: Test User <test.user@example.com>
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_log_default() {
     let test_env = TestEnvironment::default();
@@ -1609,3 +1664,63 @@ fn test_signature_templates() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_json_templates() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file1", "a\n");
+    work_dir.write_file("file2", "b\n");
+    work_dir
+        .run_jj([
+            "commit",
+            "-m",
+            "first\n\nSigned-off-by: Test User <test.user@example.com>",
+        ])
+        .success();
+    work_dir.remove_file("file1");
+    work_dir.write_file("file2", "c\n");
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "main"])
+        .success();
+
+    let output = work_dir.run_jj(["log", "--no-graph", "-T", r#"json(self) ++ "\n""#]);
+    insta::assert_snapshot!(output, @r#"
+    {"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:09+07:00"},"change_id":"rlvkpnrzqnoowoytxnquwvuryrwnrmlp","commit_id":"5675d2c0de8f2b86133c7c751deaeeb95e8614be","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:09+07:00"},"description":"","parents":["216c62208833fa0f2476a05038fdb4bb6b4a58c4"]}
+    {"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"change_id":"qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu","commit_id":"216c62208833fa0f2476a05038fdb4bb6b4a58c4","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"description":"first\n\nSigned-off-by: Test User <test.user@example.com>\n","parents":["0000000000000000000000000000000000000000"]}
+    {"author":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"change_id":"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz","commit_id":"0000000000000000000000000000000000000000","committer":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"description":"","parents":[]}
+    [EOF]
+    "#);
+
+    let template = indoc! {r#"
+        json(bookmarks) ++ "\n"
+        ++ json(self.diff().files()) ++ "\n"
+        ++ json(trailers) ++ "\n"
+    "#};
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@|@-", "-T", template]);
+    insta::assert_snapshot!(output, @r#"
+    [{"added_targets":["5675d2c0de8f2b86133c7c751deaeeb95e8614be"],"conflict":false,"name":"main","present":true,"remote":null,"removed_targets":[],"tracked":false}]
+    [{"source":{"conflict":false,"executable":false,"file_type":"file","path":"file1"},"status":"removed","target":{"conflict":false,"executable":false,"file_type":"","path":"file1"}},{"source":{"conflict":false,"executable":false,"file_type":"file","path":"file2"},"status":"modified","target":{"conflict":false,"executable":false,"file_type":"file","path":"file2"}}]
+    []
+    []
+    [{"source":{"conflict":false,"executable":false,"file_type":"","path":"file1"},"status":"added","target":{"conflict":false,"executable":false,"file_type":"file","path":"file1"}},{"source":{"conflict":false,"executable":false,"file_type":"","path":"file2"},"status":"added","target":{"conflict":false,"executable":false,"file_type":"file","path":"file2"}}]
+    [{"key":"Signed-off-by","value":"Test User <test.user@example.com>"}]
+    [EOF]
+    "#);
+
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", "json(self.diff())"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse template: Expected expression of type `Serialize`, but actual type is `TreeDiff`
+    Caused by:  --> 1:6
+      |
+    1 | json(self.diff())
+      |      ^---------^
+      |
+      = Expected expression of type `Serialize`, but actual type is `TreeDiff`
+    [EOF]
+    [exit status: 1]
+    ");
+}
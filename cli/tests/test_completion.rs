@@ -652,7 +652,11 @@ fn test_config() {
 
     let output = test_env.run_jj_in(dir, ["--", "jj", "config", "get", "c"]);
     insta::assert_snapshot!(output, @r"
+    commit-trailers.change-id	Whether to add a Gerrit Change-Id trailer derived from the change id
+    commit-trailers.signed-off-by	Whether to add a Signed-off-by trailer with the committer name and email
     core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
+    core.lock-mode	How lock files are acquired. Use `nfs` if the repository is stored on a network file system
+    core.lock-timeout	How many seconds to wait for a lock held by another process before failing. Waits forever if unset
     core.watchman.register-snapshot-trigger	Whether to use triggers to monitor for changes in the background.
     [EOF]
     ");
@@ -660,8 +664,13 @@ fn test_config() {
     let output = test_env.run_jj_in(dir, ["--", "jj", "config", "list", "c"]);
     insta::assert_snapshot!(output, @r"
     colors	Mapping from jj formatter labels to colors
+    commit-trailers	Trailers added to non-empty commit descriptions whenever a commit is written
+    commit-trailers.change-id	Whether to add a Gerrit Change-Id trailer derived from the change id
+    commit-trailers.signed-off-by	Whether to add a Signed-off-by trailer with the committer name and email
     core
     core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
+    core.lock-mode	How lock files are acquired. Use `nfs` if the repository is stored on a network file system
+    core.lock-timeout	How many seconds to wait for a lock held by another process before failing. Waits forever if unset
     core.watchman
     core.watchman.register-snapshot-trigger	Whether to use triggers to monitor for changes in the background.
     [EOF]
@@ -669,7 +678,11 @@ fn test_config() {
 
     let output = test_env.run_jj_in(dir, ["--", "jj", "log", "--config", "c"]);
     insta::assert_snapshot!(output, @r"
+    commit-trailers.change-id=	Whether to add a Gerrit Change-Id trailer derived from the change id
+    commit-trailers.signed-off-by=	Whether to add a Signed-off-by trailer with the committer name and email
     core.fsmonitor=	Whether to use an external filesystem monitor, useful for large repos
+    core.lock-mode=	How lock files are acquired. Use `nfs` if the repository is stored on a network file system
+    core.lock-timeout=	How many seconds to wait for a lock held by another process before failing. Waits forever if unset
     core.watchman.register-snapshot-trigger=	Whether to use triggers to monitor for changes in the background.
     [EOF]
     ");
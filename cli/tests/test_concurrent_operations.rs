@@ -65,6 +65,40 @@ fn test_concurrent_operation_divergence() {
     ");
 }
 
+#[test]
+fn test_concurrent_operation_divergence_nfs_lock_mode() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"core.lock-mode = "nfs""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m", "message 1"]).success();
+    work_dir
+        .run_jj(["describe", "-m", "message 2", "--at-op", "@-"])
+        .success();
+
+    let output = work_dir.run_jj(["log", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    @  message 1
+    │ ○  message 2
+    ├─╯
+    ◆
+    [EOF]
+    ------- stderr -------
+    Concurrent modification detected, resolving automatically.
+    [EOF]
+    ");
+
+    // No lock files are left behind
+    let lock_paths = [
+        work_dir.root().join(".jj/working_copy/working_copy.lock"),
+        work_dir.root().join(".jj/repo/op_heads/heads/lock"),
+    ];
+    for path in lock_paths {
+        assert!(!path.exists(), "{path:?}");
+    }
+}
+
 #[test]
 fn test_concurrent_operations_auto_rebase() {
     let test_env = TestEnvironment::default();
@@ -75,10 +109,10 @@ fn test_concurrent_operations_auto_rebase() {
     work_dir.run_jj(["describe", "-m", "initial"]).success();
     let output = work_dir.run_jj(["op", "log"]);
     insta::assert_snapshot!(output, @r"
-    @  c62ace5c0522 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    @  4f3136861568 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  describe commit 4e8f9d2be039994f589b4e57ac5e9488703e604d
     │  args: jj describe -m initial
-    ○  82d32fc68fc3 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    ○  d4c27682160a test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  snapshot working copy
     │  args: jj describe -m initial
     ○  eac759b9ab75 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
@@ -190,16 +224,16 @@ fn test_concurrent_snapshot_wc_reloadable() {
     let template = r#"id ++ "\n" ++ description ++ "\n" ++ tags"#;
     let output = work_dir.run_jj(["op", "log", "-T", template]);
     insta::assert_snapshot!(output, @r"
-    @  ec6bf266624bbaed55833a34ae62fa95c0e9efa651b94eb28846972da645845052dcdc8580332a5628849f23f48b9e99fc728dc3fb13106df8d0666d746f8b85
+    @  b278a9cbcd4613447f0388cbc4f7c801006d632a45d13f1bda7e74a58215eda473e856d6b925cd64f242de3d33be461eecb935a8186606ff020bcb18c9168290
     │  commit 554d22b2c43c1c47e279430197363e8daabe2fd6
     │  args: jj commit -m 'new child1'
-    ○  23858df860b789e8176a73c0eb21804e3f1848f26d68b70d234c004d08980c41499b6669042bca20fbc2543c437222a084c7cd473e91c7a9a095a02bf38544ab
+    ○  ba505af998b195ec0967b4a77b38bac94f460acbda6ce055b73e7a07e8a8baa5c7850f547859896354c8f97d48f6f68b4417bddb43fc2c5f3f7e9f5f6e8360ba
     │  snapshot working copy
     │  args: jj commit -m 'new child1'
-    ○  e1db5fa988fc66e5cc0491b00c53fb93e25e730341c850cb42e1e0db0c76d2b4065005787563301b1d292c104f381918897f7deabeb92d2532f42ce75d3fe588
+    ○  bc4d6b521bcd4fca60b20d7b767ba79a1f885986a9cb4064c15c814e51aa135de0c4122830ecb88d543925d05497a44b1231f12c4d8f8e1072944f80f28007cc
     │  commit de71e09289762a65f80bb1c3dae2a949df6bcde7
     │  args: jj commit -m initial
-    ○  7de878155a459b7751097222132c935f9dcbb8f69a72b0f3a9036345a963010a553dc7c92964220128679ead72b087ca3aaf4ab9e20a221d1ffa4f9e92a32193
+    ○  9d16e1d34c241484107b4ab8126a647dd086cf7163e211ad1a7548087ca8cbb89d27b5b950317bd71dc41216e8d04a56ea5c046c792b014b90cd4e87dc52ee16
     │  snapshot working copy
     │  args: jj commit -m initial
     ○  eac759b9ab75793fd3da96e60939fb48f2cd2b2a9c1f13ffe723cf620f3005b8d3e7e923634a07ea39513e4f2f360c87b9ad5d331cf90d7a844864b83b72eba1
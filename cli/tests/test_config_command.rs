@@ -1435,6 +1435,38 @@ fn test_config_author_change_warning_root_env() {
     insta::assert_snapshot!(output, @"");
 }
 
+#[test]
+fn test_config_unknown_and_mistyped_variables() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(indoc! {r#"
+        [ui]
+        colour = "always"
+
+        [git]
+        auto-local-bookmark = "yes"
+
+        [aliases]
+        my-log = ["log"]
+
+        [templates]
+        my-template = "description"
+    "#});
+    work_dir.write_file(".jj/repo/config.toml", "unknown-table.key = 1\n");
+
+    let output = work_dir.run_jj(["root"]);
+    insta::assert_snapshot!(output, @r"
+    $TEST_ENV/repo
+    [EOF]
+    ------- stderr -------
+    Warning: Unknown config variable ui.colour in $TEST_ENV/config/config0002.toml:2
+    Warning: Config variable git.auto-local-bookmark in $TEST_ENV/config/config0002.toml:5 should be boolean, but is string
+    Warning: Unknown config variable unknown-table in $TEST_ENV/repo/.jj/repo/config.toml:1
+    [EOF]
+    ");
+}
+
 fn find_stdout_lines(keyname_pattern: &str, stdout: &str) -> String {
     let key_line_re = Regex::new(&format!(r"(?m)^{keyname_pattern} = .*\n")).unwrap();
     key_line_re.find_iter(stdout).map(|m| m.as_str()).collect()
@@ -46,7 +46,7 @@ fn test_debug_fileset() {
       |
       = Invalid file pattern
     2: Path ".." is not in the repo "."
-    3: Invalid component ".." in repo-relative path "../"
+    3: Path "../" points outside of the repo root
     [EOF]
     [exit status: 1]
     "#);
@@ -231,6 +231,43 @@ fn test_debug_tree() {
     );
 }
 
+#[test]
+fn test_debug_snapshot_stats() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file1", "contents 1");
+    work_dir.write_file("file2", "contents 2");
+
+    // New files have to be read
+    let output = work_dir.run_jj(["debug", "snapshot", "--stats"]);
+    assert_snapshot!(output, @r"
+    File cache hits: 0
+    File cache misses: 2
+    Racily clean files: 0
+    [EOF]
+    ");
+
+    // Unchanged files are found by their recorded metadata
+    let output = work_dir.run_jj(["debug", "snapshot", "--stats"]);
+    assert_snapshot!(output, @r"
+    File cache hits: 2
+    File cache misses: 0
+    Racily clean files: 0
+    [EOF]
+    ");
+
+    // Only the modified file is read again
+    work_dir.write_file("file2", "modified contents 2");
+    let output = work_dir.run_jj(["debug", "snapshot", "--stats"]);
+    assert_snapshot!(output, @r"
+    File cache hits: 1
+    File cache misses: 1
+    Racily clean files: 0
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_debug_operation_id() {
     let test_env = TestEnvironment::default();
@@ -243,6 +280,80 @@ fn test_debug_operation_id() {
     ");
 }
 
+#[test]
+fn test_debug_operation_snapshot_metadata() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "contents");
+    work_dir.run_jj(["describe", "-m", "add file"]).success();
+
+    // The snapshot operation and the operation of the command that triggered it
+    // both record the snapshotted tree
+    let show_metadata = |op: &str| {
+        let output = work_dir.run_jj(["debug", "operation", "--display", "operation", op]);
+        output.normalize_stdout_with(|text| {
+            let start = text.find("        description:").unwrap();
+            text[start..].to_owned()
+        })
+    };
+    assert_snapshot!(show_metadata("@-"), @r#"
+            description: "snapshot working copy",
+            hostname: "host.example.com",
+            username: "test-username",
+            is_snapshot: true,
+            tags: {
+                "args": "jj describe -m 'add file'",
+            },
+            working_copy_tree_ids: Some(
+                Resolved(
+                    TreeId(
+                        "5bfa85fe39384c975a4d1558a6811e2b47492603",
+                    ),
+                ),
+            ),
+            includes_snapshot: false,
+            stopped_rebase: None,
+        },
+    }
+    [EOF]
+    "#);
+    assert_snapshot!(show_metadata("@"), @r#"
+            description: "describe commit 4e8f9d2be039994f589b4e57ac5e9488703e604d",
+            hostname: "host.example.com",
+            username: "test-username",
+            is_snapshot: false,
+            tags: {
+                "args": "jj describe -m 'add file'",
+            },
+            working_copy_tree_ids: Some(
+                Resolved(
+                    TreeId(
+                        "5bfa85fe39384c975a4d1558a6811e2b47492603",
+                    ),
+                ),
+            ),
+            includes_snapshot: true,
+            stopped_rebase: None,
+        },
+    }
+    [EOF]
+    "#);
+    assert_snapshot!(show_metadata("@--"), @r#"
+            description: "add workspace 'default'",
+            hostname: "host.example.com",
+            username: "test-username",
+            is_snapshot: false,
+            tags: {},
+            working_copy_tree_ids: None,
+            includes_snapshot: false,
+            stopped_rebase: None,
+        },
+    }
+    [EOF]
+    "#);
+}
+
 fn filter_index_stats(output: CommandOutput) -> CommandOutput {
     let regex = Regex::new(r"    Name: [0-9a-z]+").unwrap();
     output.normalize_stdout_with(|text| regex.replace_all(&text, "    Name: [hash]").into_owned())
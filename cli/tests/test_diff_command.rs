@@ -538,6 +538,49 @@ fn test_diff_name_only() {
     ");
 }
 
+#[test]
+fn test_diff_summary_json() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("deleted"), "d").unwrap();
+    std::fs::write(repo_path.join("modified"), "m").unwrap();
+    std::fs::write(repo_path.join("renamed"), "r").unwrap();
+    test_env.run_jj_in(&repo_path, ["new"]).success();
+    std::fs::remove_file(repo_path.join("deleted")).unwrap();
+    std::fs::write(repo_path.join("modified"), "mod").unwrap();
+    std::fs::rename(repo_path.join("renamed"), repo_path.join("renamed-target")).unwrap();
+    let output = test_env.run_jj_in(&repo_path, ["diff", "--summary", "--output", "json"]);
+    insta::assert_snapshot!(output, @r#"
+    [{"source":{"conflict":false,"executable":false,"file_type":"file","path":"deleted"},"status":"removed","target":{"conflict":false,"executable":false,"file_type":"","path":"deleted"}},{"source":{"conflict":false,"executable":false,"file_type":"file","path":"modified"},"status":"modified","target":{"conflict":false,"executable":false,"file_type":"file","path":"modified"}},{"source":{"conflict":false,"executable":false,"file_type":"file","path":"renamed"},"status":"renamed","target":{"conflict":false,"executable":false,"file_type":"file","path":"renamed-target"}}]
+    [EOF]
+    "#);
+
+    // Only the summary can be printed as JSON
+    let output = test_env.run_jj_in(&repo_path, ["diff", "--output", "json"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the following required arguments were not provided:
+      --summary
+
+    Usage: jj diff --summary --output <OUTPUT> [FILESETS]...
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+    let output = test_env.run_jj_in(&repo_path, ["diff", "--output", "text"]);
+    insta::assert_snapshot!(output, @r"
+    Removed regular file deleted:
+       1     : d
+    Modified regular file modified:
+       1    1: mmod
+    Modified regular file renamed-target (renamed => renamed-target):
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_diff_bad_args() {
     let test_env = TestEnvironment::default();
@@ -2846,6 +2889,52 @@ fn test_diff_binary() {
     ");
 }
 
+#[test]
+fn test_diff_binary_attribute() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config(indoc! {r#"
+        [attributes."*.svg"]
+        binary = true
+        [attributes."*.bin"]
+        lfs = true
+    "#});
+
+    std::fs::write(repo_path.join("file.svg"), "<svg>\n").unwrap();
+    std::fs::write(
+        repo_path.join("file.bin"),
+        indoc! {"
+            version https://git-lfs.github.com/spec/v1
+            oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+            size 12345
+        "},
+    )
+    .unwrap();
+
+    let output = test_env.run_jj_in(&repo_path, ["diff"]);
+    insta::assert_snapshot!(output, @r"
+    Added regular file file.bin:
+        (binary)
+    Added regular file file.svg:
+        (binary)
+    [EOF]
+    ");
+
+    let output = test_env.run_jj_in(&repo_path, ["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.bin b/file.bin
+    new file mode 100644
+    index 0000000000..60c8d8ab2a
+    Binary files /dev/null and b/file.bin differ
+    diff --git a/file.svg b/file.svg
+    new file mode 100644
+    index 0000000000..802032b68e
+    Binary files /dev/null and b/file.svg differ
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_diff_revisions() {
     let test_env = TestEnvironment::default();
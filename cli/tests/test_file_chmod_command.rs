@@ -203,7 +203,7 @@ fn test_chmod_file_dir_deletion_conflicts() {
     "#);
     let output = test_env.run_jj_in(&repo_path, ["file", "show", "-r=file_dir", "file"]);
     insta::assert_snapshot!(output, @r"
-    Conflict:
+    Conflict between a file and a directory:
       Removing file with id df967b96a579e45a18b8251732d16804b2e56a55
       Adding file with id 78981922613b2afb6025042ff6bd878ac1994e85
       Adding tree with id 133bb38fc4e4bf6b551f1f04db7e48f04cac2877
@@ -199,6 +199,55 @@ fn test_auto_track() {
     ");
 }
 
+#[test]
+fn test_leave_new_files_untracked() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"snapshot.new-files = "leave-untracked""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("file1"), "initial").unwrap();
+    std::fs::write(repo_path.join("dir").join("file2"), "initial").unwrap();
+
+    // New files aren't tracked
+    let output = test_env.run_jj_in(&repo_path, ["file", "list"]);
+    insta::assert_snapshot!(output, @"");
+
+    // Tracked directories are remembered
+    let output = test_env.run_jj_in(&repo_path, ["file", "track", "dir"]);
+    insta::assert_snapshot!(output, @"");
+    std::fs::write(repo_path.join("dir").join("file3"), "initial").unwrap();
+    let output = test_env.run_jj_in(&repo_path, ["file", "list"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    dir/file2
+    dir/file3
+    [EOF]
+    ");
+
+    // Untracked paths don't need to be ignored, and aren't tracked again with
+    // their parent directory
+    let output = test_env.run_jj_in(&repo_path, ["file", "untrack", "dir/file2"]);
+    insta::assert_snapshot!(output, @"");
+    std::fs::write(repo_path.join("dir").join("file2"), "modified").unwrap();
+    let output = test_env.run_jj_in(&repo_path, ["file", "list"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    dir/file3
+    [EOF]
+    ");
+
+    // Paths can be tracked again
+    let output = test_env.run_jj_in(&repo_path, ["file", "track", "dir/file2", "file1"]);
+    insta::assert_snapshot!(output, @"");
+    let output = test_env.run_jj_in(&repo_path, ["file", "list"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    dir/file2
+    dir/file3
+    file1
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_track_ignored() {
     let test_env = TestEnvironment::default();
@@ -140,48 +140,52 @@ fn test_git_clone(subprocess: bool) {
     // Failed clone (if attempted) shouldn't remove the existing workspace
     let output = test_env.run_jj_in(".", ["git", "clone", "bad", "clone"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
     assert!(test_env.env_root().join("clone").join(".jj").exists());
 
     // Try cloning into an existing workspace
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "clone"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Try cloning into an existing file
     std::fs::write(test_env.env_root().join("file"), "contents").unwrap();
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "file"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/file"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Try cloning into non-empty, non-workspace directory
     std::fs::remove_dir_all(test_env.env_root().join("clone").join(".jj")).unwrap();
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "clone"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Clone into a nested path
@@ -401,12 +405,13 @@ fn test_git_clone_colocate(subprocess: bool) {
     // Failed clone (if attempted) shouldn't remove the existing workspace
     let output = test_env.run_jj_in(".", ["git", "clone", "--colocate", "bad", "clone"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
     assert!(test_env.env_root().join("clone").join(".git").exists());
     assert!(test_env.env_root().join("clone").join(".jj").exists());
@@ -414,36 +419,39 @@ fn test_git_clone_colocate(subprocess: bool) {
     // Try cloning into an existing workspace
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "clone", "--colocate"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Try cloning into an existing file
     std::fs::write(test_env.env_root().join("file"), "contents").unwrap();
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "file", "--colocate"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/file"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Try cloning into non-empty, non-workspace directory
     std::fs::remove_dir_all(test_env.env_root().join("clone").join(".jj")).unwrap();
     let output = test_env.run_jj_in(".", ["git", "clone", "source", "clone", "--colocate"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/clone"
     Error: Destination path exists and is not an empty directory
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 
     // Clone into a nested path
@@ -765,12 +773,13 @@ fn test_git_clone_with_remote_named_git(subprocess: bool) {
 
     let output = test_env.run_jj_in(".", ["git", "clone", "--remote=git", "source", "dest"]);
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/dest"
     Error: Git remote named 'git' is reserved for local Git repository
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 }
 
@@ -789,12 +798,13 @@ fn test_git_clone_with_remote_with_slashes(subprocess: bool) {
         ["git", "clone", "--remote=slash/origin", "source", "dest"],
     );
     insta::allow_duplicates! {
-    insta::assert_snapshot!(output, @r"
+    insta::assert_snapshot!(output, @r#"
     ------- stderr -------
+    Fetching into new repo in "$TEST_ENV/dest"
     Error: Git remotes with slashes are incompatible with jj: slash/origin
     [EOF]
     [exit status: 1]
-    ");
+    "#);
     }
 }
 
@@ -1023,6 +1033,7 @@ fn test_git_clone_invalid_immutable_heads(subprocess: bool) {
     ------- stderr -------
     Fetching into new repo in "$TEST_ENV/clone"
     bookmark: main@origin [new] untracked
+    Setting the revset alias `trunk()` to `main@origin`
     Config error: Invalid `revset-aliases.immutable_heads()`
     Caused by: Revision `unknown` doesn't exist
     For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
@@ -1065,7 +1076,7 @@ fn test_git_clone_malformed(subprocess: bool) {
     insta::allow_duplicates! {
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Error: The working copy is stale (not updated since operation 57e024eb3edf).
+    Error: The working copy is stale (not updated since operation eac759b9ab75).
     Hint: Run `jj workspace update-stale` to update it.
     See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy for more information.
     [EOF]
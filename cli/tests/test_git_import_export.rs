@@ -308,6 +308,38 @@ fn test_git_import_move_export_with_default_undo() {
     ");
 }
 
+#[test]
+fn test_git_mirror_refs() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+    let git_repo = git::open(repo_path.join(".jj/repo/store/git"));
+    test_env.add_config("git.mirror-refs = true");
+
+    test_env
+        .run_jj_in(&repo_path, ["bookmark", "create", "-r@", "main"])
+        .success();
+    insta::assert_snapshot!(get_mirrored_git_refs(&git_repo), @r"
+    refs/jj/bookmarks/main 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    refs/jj/workspaces/default 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ");
+    // Bookmarks aren't exported to refs/heads/
+    insta::assert_debug_snapshot!(get_git_repo_refs(&git_repo), @"[]");
+
+    // Snapshots are mirrored too
+    std::fs::write(repo_path.join("file"), "contents").unwrap();
+    test_env.run_jj_in(&repo_path, ["new"]).success();
+    insta::assert_snapshot!(get_mirrored_git_refs(&git_repo), @r"
+    refs/jj/bookmarks/main 81b46e2c7fdcd28ca37744d426a20c7305dcc450
+    refs/jj/workspaces/default e405300ac5857c881589b409fa0e3b8372cb99f0
+    ");
+
+    test_env
+        .run_jj_in(&repo_path, ["bookmark", "delete", "main"])
+        .success();
+    insta::assert_snapshot!(get_mirrored_git_refs(&git_repo), @"refs/jj/workspaces/default e405300ac5857c881589b409fa0e3b8372cb99f0");
+}
+
 #[must_use]
 fn get_bookmark_output(test_env: &TestEnvironment, repo_path: &Path) -> CommandOutput {
     test_env.run_jj_in(repo_path, ["bookmark", "list", "--all-remotes"])
@@ -338,3 +370,14 @@ fn get_git_repo_refs(git_repo: &gix::Repository) -> Vec<(bstr::BString, CommitId
     refs.sort();
     refs
 }
+
+fn get_mirrored_git_refs(git_repo: &gix::Repository) -> String {
+    let git_references = git_repo.references().unwrap();
+    git_references
+        .prefixed("refs/jj/bookmarks/")
+        .unwrap()
+        .chain(git_references.prefixed("refs/jj/workspaces/").unwrap())
+        .map_ok(|git_ref| format!("{} {}\n", git_ref.name().as_bstr(), git_ref.id()))
+        .try_collect()
+        .unwrap()
+}
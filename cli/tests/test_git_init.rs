@@ -173,6 +173,20 @@ fn test_git_init_external(bare: bool) {
         [EOF]
         ");
     }
+
+    // Refs and HEAD are imported in a single operation
+    let output = test_env.run_jj_in(
+        &workspace_root,
+        ["op", "log", "--no-graph", "-T", r#"description ++ "\n""#],
+    );
+    insta::allow_duplicates! {
+        insta::assert_snapshot!(output, @r"
+        import git refs
+        add workspace 'default'
+
+        [EOF]
+        ");
+    }
 }
 
 #[test_case(false; "full")]
@@ -217,10 +231,10 @@ fn test_git_init_external_import_trunk(bare: bool) {
     insta::assert_snapshot!(output, @r#"
     ------- stderr -------
     Done importing changes from the underlying Git repo.
-    Setting the revset alias `trunk()` to `trunk@origin`
     Working copy now at: sqpuoqvx 0bd37cef (empty) (no description set)
     Parent commit      : nntyzxmz e80a42cc my-bookmark trunk@origin | My commit message
     Added 1 files, modified 0 files, removed 0 files
+    Setting the revset alias `trunk()` to `trunk@origin`
     Initialized repo in "repo"
     [EOF]
     "#);
@@ -368,6 +382,20 @@ fn test_git_init_colocated_via_git_repo_path() {
     ◆  000000000000
     [EOF]
     ");
+
+    // Refs and HEAD are imported in a single operation
+    let output = test_env.run_jj_in(
+        &workspace_root,
+        ["op", "log", "--no-graph", "-T", r#"description ++ "\n""#],
+    );
+    insta::assert_snapshot!(output, @r"
+    new empty commit
+    snapshot working copy
+    import git refs
+    add workspace 'default'
+
+    [EOF]
+    ");
 }
 
 #[test]
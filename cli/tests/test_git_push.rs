@@ -1181,6 +1181,55 @@ fn test_git_push_revisions(subprocess: bool) {
     }
 }
 
+#[test]
+fn test_git_push_revisions_anonymous_head() {
+    let (test_env, workspace_root) = set_up();
+    test_env
+        .run_jj_in(&workspace_root, ["new", "root()", "-m", "foo"])
+        .success();
+    test_env
+        .run_jj_in(&workspace_root, ["new", "bookmark1"])
+        .success();
+
+    // Pushing an anonymous head proposes a bookmark name
+    let output = test_env.run_jj_in(
+        &workspace_root,
+        ["git", "push", "--allow-new", "-r=description(foo)"],
+    );
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Warning: No bookmarks point to the specified revisions: description(foo)
+    Hint: Run `jj bookmark create head-vruxwmqv -r head-vruxwmqv` to push the anonymous head head-vruxwmqv.
+    Nothing changed.
+    [EOF]
+    ");
+
+    // The name of the anonymous head resolves as a revision
+    test_env
+        .run_jj_in(
+            &workspace_root,
+            ["bookmark", "create", "head-vruxwmqv", "-r", "head-vruxwmqv"],
+        )
+        .success();
+    let output = test_env.run_jj_in(
+        &workspace_root,
+        [
+            "git",
+            "push",
+            "--allow-new",
+            "-r=description(foo)",
+            "--dry-run",
+        ],
+    );
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Changes to push to origin:
+      Add bookmark head-vruxwmqv to 7283b790a895
+    Dry-run requested, not pushing.
+    [EOF]
+    ");
+}
+
 #[test_case(false; "use git2 for remote calls")]
 #[test_case(true; "spawn a git subprocess for remote calls")]
 fn test_git_push_mixed(subprocess: bool) {
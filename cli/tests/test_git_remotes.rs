@@ -91,6 +91,79 @@ fn test_git_remotes() {
     "#);
 }
 
+#[test]
+fn test_git_remotes_without_git_backend() {
+    let test_env = TestEnvironment::default();
+
+    test_env
+        .run_jj_in(".", ["debug", "init-local", "repo"])
+        .success();
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env
+        .run_jj_in(
+            &repo_path,
+            ["git", "remote", "add", "foo", "http://example.com/repo/foo"],
+        )
+        .success();
+    test_env
+        .run_jj_in(
+            &repo_path,
+            ["git", "remote", "add", "bar", "http://example.com/repo/bar"],
+        )
+        .success();
+    let output = test_env.run_jj_in(&repo_path, ["git", "remote", "list"]);
+    insta::assert_snapshot!(output, @r"
+    bar http://example.com/repo/bar
+    foo http://example.com/repo/foo
+    [EOF]
+    ");
+    test_env
+        .run_jj_in(&repo_path, ["git", "remote", "rename", "foo", "baz"])
+        .success();
+    test_env
+        .run_jj_in(
+            &repo_path,
+            [
+                "git",
+                "remote",
+                "set-url",
+                "bar",
+                "http://example.com/repo/qux",
+            ],
+        )
+        .success();
+    test_env
+        .run_jj_in(&repo_path, ["git", "remote", "remove", "baz"])
+        .success();
+    let output = test_env.run_jj_in(&repo_path, ["git", "remote", "remove", "foo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No git remote named 'foo'
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = test_env.run_jj_in(&repo_path, ["git", "remote", "list"]);
+    insta::assert_snapshot!(output, @r"
+    bar http://example.com/repo/qux
+    [EOF]
+    ");
+    let output = test_env.run_jj_in(
+        &repo_path,
+        ["op", "log", "--no-graph", "-T", r#"description ++ "\n""#],
+    );
+    insta::assert_snapshot!(output, @r"
+    remove git remote baz
+    set url of git remote bar
+    rename git remote foo to baz
+    add git remote bar
+    add git remote foo
+    add workspace 'default'
+
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_git_remote_add() {
     let test_env = TestEnvironment::default();
@@ -289,7 +289,7 @@ fn test_bad_path() {
       |
       = Invalid file pattern
     2: Path "../out" is not in the repo "."
-    3: Invalid component ".." in repo-relative path "../out"
+    3: Path "../out" points outside of the repo root
     [EOF]
     [exit status: 1]
     "#);
@@ -307,7 +307,7 @@ fn test_bad_path() {
       |
       = Invalid file pattern
     2: Path "../.." is not in the repo "../"
-    3: Invalid component ".." in repo-relative path "../"
+    3: Path "../" points outside of the repo root
     [EOF]
     [exit status: 1]
     "#);
@@ -325,7 +325,7 @@ fn test_bad_path() {
       |
       = Invalid file pattern
     2: Path "out" is not in the repo "repo"
-    3: Invalid component ".." in repo-relative path "../out"
+    3: Path "../out" points outside of the repo root
     Hint: Consider using root:"out" to specify repo-relative path
     [EOF]
     [exit status: 1]
@@ -553,7 +553,7 @@ fn test_color_ui_messages() {
     [39m  |[39m
     [39m  = Invalid file pattern[39m
     [1m[39m2: [0m[39mPath ".." is not in the repo "."[39m
-    [1m[39m3: [0m[39mInvalid component ".." in repo-relative path "../"[39m
+    [1m[39m3: [0m[39mPath "../" points outside of the repo root[39m
     [EOF]
     [exit status: 1]
     "#);
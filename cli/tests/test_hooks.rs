@@ -0,0 +1,140 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt as _;
+
+use testutils::git;
+
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+fn write_hook(work_dir: &TestWorkDir, name: &str, script: &str) {
+    let path = work_dir.root().join(".jj").join("hooks").join(name);
+    work_dir.write_file(&path, format!("#!/bin/sh\n{script}"));
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn test_hooks_disabled_by_default() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    write_hook(&work_dir, "pre-commit-operation", "exit 1\n");
+
+    let output = work_dir.run_jj(["describe", "-m", "first"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy now at: qpvuntsm fa15625b (empty) first
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_pre_commit_operation_hook() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config("hooks.enabled = true");
+    write_hook(
+        &work_dir,
+        "pre-commit-operation",
+        "echo \"checking operation\"\ntest ! -e .jj/block\n",
+    );
+
+    // The hook output is printed to stderr
+    let output = work_dir.run_jj(["describe", "-m", "first"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    checking operation
+    Working copy now at: qpvuntsm fa15625b (empty) first
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+
+    // A failing hook aborts the operation
+    work_dir.write_file(".jj/block", "");
+    let output = work_dir.run_jj(["describe", "-m", "second"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    checking operation
+    Error: Hook $TEST_ENV/repo/.jj/hooks/pre-commit-operation failed with exit status: 1
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    first
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_pre_commit_operation_hook_failure_keeps_git_refs() {
+    let test_env = TestEnvironment::default();
+    test_env
+        .run_jj_in(".", ["git", "init", "--colocate", "repo"])
+        .success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config("hooks.enabled = true");
+    test_env.add_config("git.mirror-refs = true");
+    write_hook(&work_dir, "pre-commit-operation", "exit 1\n");
+
+    let git_repo = git::open(work_dir.root());
+    let old_head_id = git_repo.head_id().ok();
+    let output = work_dir.run_jj(["bookmark", "create", "-r@", "foo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Created 1 bookmarks pointing to qpvuntsm 230dd059 foo | (empty) (no description set)
+    Error: Hook $TEST_ENV/repo/.jj/hooks/pre-commit-operation failed with exit status: 1
+    [EOF]
+    [exit status: 1]
+    ");
+    let git_repo = git::open(work_dir.root());
+    assert_eq!(git_repo.head_id().ok(), old_head_id);
+    assert!(git_repo
+        .try_find_reference("refs/heads/foo")
+        .unwrap()
+        .is_none());
+    assert!(git_repo
+        .try_find_reference("refs/jj/bookmarks/foo")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_post_checkout_hook_failure_is_warning() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config("hooks.enabled = true");
+    write_hook(&work_dir, "post-checkout", "echo \"checked out\"\nexit 1\n");
+
+    let output = work_dir.run_jj(["new", "-m", "second"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    checked out
+    Warning: Hook $TEST_ENV/repo/.jj/hooks/post-checkout failed with exit status: 1
+    Working copy now at: rlvkpnrz 58331aff (empty) second
+    Parent commit      : qpvuntsm 230dd059 (empty) (no description set)
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    second
+    [EOF]
+    ");
+}
@@ -276,7 +276,7 @@ fn test_op_log_reversed() {
     ○ │  d009cfc04993 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     ├─╯  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
     │    args: jj describe -m 'description 0'
-    @  e4538ffdc13d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    @  6c7e9b9f779f test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
        reconcile divergent operations
        args: jj op log --reversed
     [EOF]
@@ -297,7 +297,7 @@ fn test_op_log_reversed() {
     d009cfc04993 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
     args: jj describe -m 'description 0'
-    e4538ffdc13d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    6c7e9b9f779f test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     reconcile divergent operations
     args: jj op log --reversed
     [EOF]
@@ -312,7 +312,7 @@ fn test_op_log_reversed() {
     │ ○  d009cfc04993 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     ├─╯  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
     │    args: jj describe -m 'description 0'
-    @  e4538ffdc13d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    @  6c7e9b9f779f test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
        reconcile divergent operations
        args: jj op log --reversed
     [EOF]
@@ -327,7 +327,7 @@ fn test_op_log_reversed() {
     d009cfc04993 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
     args: jj describe -m 'description 0'
-    e4538ffdc13d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    6c7e9b9f779f test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     reconcile divergent operations
     args: jj op log --reversed
     [EOF]
@@ -381,6 +381,12 @@ fn test_op_log_template() {
     [EOF]
     ");
 
+    insta::assert_snapshot!(render(r#"json(self) ++ "\n""#), @r#"
+    @  {"id":"eac759b9ab75793fd3da96e60939fb48f2cd2b2a9c1f13ffe723cf620f3005b8d3e7e923634a07ea39513e4f2f360c87b9ad5d331cf90d7a844864b83b72eba1","metadata":{"description":"add workspace 'default'","end_time":"2001-02-03T04:05:07+07:00","hostname":"host.example.com","includes_snapshot":false,"is_snapshot":false,"start_time":"2001-02-03T04:05:07+07:00","tags":{},"username":"test-username","working_copy_tree_ids":null},"parents":["00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"],"view_id":"7ce6213e47aabdc4443a832ac68f40235bd5bd7372b18a4189538a7e8ac79cefe28cc8451a8a466ac4bbd8e01b2cb9dac04ad9414e016a5438c158ce2f982216"}
+    ○  {"id":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","metadata":{"description":"","end_time":"1970-01-01T00:00:00+00:00","hostname":"","includes_snapshot":false,"is_snapshot":false,"start_time":"1970-01-01T00:00:00+00:00","tags":{},"username":"","working_copy_tree_ids":null},"parents":[],"view_id":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"}
+    [EOF]
+    "#);
+
     // Negative length shouldn't cause panic.
     insta::assert_snapshot!(render(r#"id.short(-1) ++ "|""#), @r"
     @  <Error: out of range integral type conversion attempted>|
@@ -473,7 +479,7 @@ fn test_op_log_word_wrap() {
 
     // ui.log-word-wrap option works
     insta::assert_snapshot!(render(&["op", "log"], 40, false), @r"
-    @  b7cd3d0069f6 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    @  2644edcdaa62 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  snapshot working copy
     │  args: jj debug snapshot
     ○  eac759b9ab75 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
@@ -482,7 +488,7 @@ fn test_op_log_word_wrap() {
     [EOF]
     ");
     insta::assert_snapshot!(render(&["op", "log"], 40, true), @r"
-    @  b7cd3d0069f6
+    @  2644edcdaa62
     │  test-username@host.example.com
     │  2001-02-03 04:05:08.000 +07:00 -
     │  2001-02-03 04:05:08.000 +07:00
@@ -499,7 +505,7 @@ fn test_op_log_word_wrap() {
 
     // Nested graph should be wrapped
     insta::assert_snapshot!(render(&["op", "log", "--op-diff"], 40, true), @r"
-    @  b7cd3d0069f6
+    @  2644edcdaa62
     │  test-username@host.example.com
     │  2001-02-03 04:05:08.000 +07:00 -
     │  2001-02-03 04:05:08.000 +07:00
@@ -537,7 +543,7 @@ fn test_op_log_word_wrap() {
 
     // Nested diff stat shouldn't exceed the terminal width
     insta::assert_snapshot!(render(&["op", "log", "-n1", "--stat"], 40, true), @r"
-    @  b7cd3d0069f6
+    @  2644edcdaa62
     │  test-username@host.example.com
     │  2001-02-03 04:05:08.000 +07:00 -
     │  2001-02-03 04:05:08.000 +07:00
@@ -560,7 +566,7 @@ fn test_op_log_word_wrap() {
     [EOF]
     ");
     insta::assert_snapshot!(render(&["op", "log", "-n1", "--no-graph", "--stat"], 40, true), @r"
-    b7cd3d0069f6
+    2644edcdaa62
     test-username@host.example.com
     2001-02-03 04:05:08.000 +07:00 -
     2001-02-03 04:05:08.000 +07:00
@@ -901,7 +907,7 @@ fn test_op_abandon_multiple_heads() {
 
     let output = test_env.run_jj_in(&repo_path, ["op", "log"]);
     insta::assert_snapshot!(output, @r"
-    @    7e65e7e27e34 test-username@host.example.com 2001-02-03 04:05:17.000 +07:00 - 2001-02-03 04:05:17.000 +07:00
+    @    3b51f63c4695 test-username@host.example.com 2001-02-03 04:05:17.000 +07:00 - 2001-02-03 04:05:17.000 +07:00
     ├─╮  reconcile divergent operations
     │ │  args: jj op log
     ○ │  0508a30825ed test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
@@ -1098,7 +1104,7 @@ fn test_op_summary_diff_template() {
     let output = test_env.run_jj_in(&repo_path, ["op", "undo", "--color=always"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: [38;5;4mac20a4ff4791[39m ([38;5;6m2001-02-03 08:05:08[39m) new empty commit
+    Undid operation: [38;5;4m33225fa10188[39m ([38;5;6m2001-02-03 08:05:08[39m) new empty commit
     [EOF]
     ");
     let output = test_env.run_jj_in(
@@ -1115,7 +1121,7 @@ fn test_op_summary_diff_template() {
     );
     insta::assert_snapshot!(output, @r"
     From operation: [38;5;4m000000000000[39m [38;5;2mroot()[39m
-      To operation: [38;5;4me3792fce5b1f[39m ([38;5;6m2001-02-03 08:05:09[39m) undo operation ac20a4ff47914da9a2e43677b94455b86383bfb9227374d6531ecee85b9ff9230eeb96416a24bb27e7477aa18d50c01810e97c6a008b5c584224650846f4c05b
+      To operation: [38;5;4mae9795ee3f23[39m ([38;5;6m2001-02-03 08:05:09[39m) undo operation 33225fa10188c3e5b085a111792fe050bb282e4f6222149a5b5593f6191dce47855fbcc1ebd5efc498b2fb9409899925dfca1f6aa0425c523c41a025bbfe2660
 
     Changed commits:
     ○  [38;5;2m+[39m [1m[38;5;5mq[0m[38;5;8mpvuntsm[39m [1m[38;5;4m2[0m[38;5;8m30dd059[39m [38;5;2m(empty)[39m [38;5;2m(no description set)[39m
@@ -1133,7 +1139,7 @@ fn test_op_summary_diff_template() {
     let output = test_env.run_jj_in(&repo_path, ["op", "undo", "--color=debug"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: [38;5;4m<<operation id short::2301f6e6ec31>>[39m<<operation:: (>>[38;5;6m<<operation time end local format::2001-02-03 08:05:11>>[39m<<operation::) >><<operation description first_line::new empty commit>>
+    Undid operation: [38;5;4m<<operation id short::5ffe11af2b00>>[39m<<operation:: (>>[38;5;6m<<operation time end local format::2001-02-03 08:05:11>>[39m<<operation::) >><<operation description first_line::new empty commit>>
     [EOF]
     ");
     let output = test_env.run_jj_in(
@@ -1150,7 +1156,7 @@ fn test_op_summary_diff_template() {
     );
     insta::assert_snapshot!(output, @r"
     From operation: [38;5;4m<<operation id short::000000000000>>[39m<<operation:: >>[38;5;2m<<operation root::root()>>[39m
-      To operation: [38;5;4m<<operation id short::d208ae1b4e3c>>[39m<<operation:: (>>[38;5;6m<<operation time end local format::2001-02-03 08:05:12>>[39m<<operation::) >><<operation description first_line::undo operation 2301f6e6ec31931a9b0a594742d6035a44c05250d1707f7f8678e888b11a98773ef07bf0e8008a5bccddf7114da4a35d1a1b1f7efa37c1e6c80d6bdb8f0d7a90>>
+      To operation: [38;5;4m<<operation id short::d0fd3d3d122b>>[39m<<operation:: (>>[38;5;6m<<operation time end local format::2001-02-03 08:05:12>>[39m<<operation::) >><<operation description first_line::undo operation 5ffe11af2b008d5b8fc28ead5bc8aeb7194b808ad709e66c8a228040208157a12e86dd356a110cbdc2b9777fdff187ea9f1f0410b6bc76398d9d0806dcd9b14f>>
 
     Changed commits:
     ○  [38;5;2m<<diff added::+>>[39m [1m[38;5;5m<<change_id shortest prefix::q>>[0m[38;5;8m<<change_id shortest rest::pvuntsm>>[39m [1m[38;5;4m<<commit_id shortest prefix::2>>[0m[38;5;8m<<commit_id shortest rest::30dd059>>[39m [38;5;2m<<empty::(empty)>>[39m [38;5;2m<<empty description placeholder::(no description set)>>[39m
@@ -1588,7 +1594,7 @@ fn test_op_diff() {
     let output = test_env.run_jj_in(&repo_path, ["op", "diff"]);
     insta::assert_snapshot!(output, @r"
     From operation: 9969a6088fd3 (2001-02-03 08:05:32) delete bookmark bookmark-2
-      To operation: ff305cfe0aca (2001-02-03 08:05:34) push all tracked bookmarks to git remote origin
+      To operation: b20d9ced1e48 (2001-02-03 08:05:34) push all tracked bookmarks to git remote origin
 
     Changed commits:
     ○  + oupztwtk fe3ad088 (empty) (no description set)
@@ -1626,7 +1632,7 @@ fn test_op_diff_patch() {
     let output = test_env.run_jj_in(&repo_path, ["op", "diff", "--op", "@-", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
     From operation: eac759b9ab75 (2001-02-03 08:05:07) add workspace 'default'
-      To operation: 187a5a9d8a22 (2001-02-03 08:05:08) snapshot working copy
+      To operation: 2e935d59f9e6 (2001-02-03 08:05:08) snapshot working copy
 
     Changed commits:
     ○  + qpvuntsm 6b1027d2 (no description set)
@@ -1646,8 +1652,8 @@ fn test_op_diff_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "diff", "--op", "@", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    From operation: 187a5a9d8a22 (2001-02-03 08:05:08) snapshot working copy
-      To operation: a7e535e73c4b (2001-02-03 08:05:08) new empty commit
+    From operation: 2e935d59f9e6 (2001-02-03 08:05:08) snapshot working copy
+      To operation: 14940d16ef6f (2001-02-03 08:05:08) new empty commit
 
     Changed commits:
     ○  + rlvkpnrz 56950632 (empty) (no description set)
@@ -1669,8 +1675,8 @@ fn test_op_diff_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "diff", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    From operation: 15c3c5d0baf0 (2001-02-03 08:05:11) snapshot working copy
-      To operation: 894c12d90345 (2001-02-03 08:05:11) squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
+    From operation: 133721655181 (2001-02-03 08:05:11) snapshot working copy
+      To operation: 69140a0ef37d (2001-02-03 08:05:11) squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
 
     Changed commits:
     ○  + mzvwutvl 9f4fb57f (empty) (no description set)
@@ -1709,8 +1715,8 @@ fn test_op_diff_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "diff", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    From operation: 894c12d90345 (2001-02-03 08:05:11) squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
-      To operation: e5505aa79d31 (2001-02-03 08:05:13) abandon commit 9f4fb57fba25a7b47ce5980a5d9a4766778331e8
+    From operation: 69140a0ef37d (2001-02-03 08:05:11) squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
+      To operation: a4f5ab03165a (2001-02-03 08:05:13) abandon commit 9f4fb57fba25a7b47ce5980a5d9a4766778331e8
 
     Changed commits:
     ○  + yqosqzyt 33f321c4 (empty) (no description set)
@@ -1759,19 +1765,19 @@ fn test_op_diff_sibling() {
 
     let output = test_env.run_jj_in(&repo_path, ["op", "log"]);
     insta::assert_snapshot!(output, @r"
-    @    779ecb7ea7f0 test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
+    @    73c2f78a0dde test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
     ├─╮  reconcile divergent operations
     │ │  args: jj op log
-    ○ │  d700dc16fded test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    ○ │  d763e4b2c446 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     │ │  new empty commit
     │ │  args: jj new 'all:@-+' -mA
-    ○ │  b47de32023e1 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    ○ │  4f3ed6aa51e2 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     │ │  snapshot working copy
     │ │  args: jj new 'all:@-+' -mA
-    ○ │  8a31868f615d test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    ○ │  b0a8752bf01d test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
     │ │  new empty commit
     │ │  args: jj new 'root()' -mA.2
-    ○ │  2cd33ddecde8 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    ○ │  d8d7cb0f634e test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
     │ │  snapshot working copy
     │ │  args: jj new 'root()' -mA.2
     ○ │  d86c1ae55c48 test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00
@@ -1796,8 +1802,8 @@ fn test_op_diff_sibling() {
         .success();
     let (head_op_id, p1_op_id, _, _, _, _, p2_op_id) =
         output.stdout.raw().lines().next_tuple().unwrap();
-    insta::assert_snapshot!(head_op_id, @"779ecb7ea7f0");
-    insta::assert_snapshot!(p1_op_id, @"d700dc16fded");
+    insta::assert_snapshot!(head_op_id, @"73c2f78a0dde");
+    insta::assert_snapshot!(p1_op_id, @"d763e4b2c446");
     insta::assert_snapshot!(p2_op_id, @"13b143e1f4f9");
 
     // Diff between p1 and p2 operations should work no matter if p2 is chosen
@@ -1817,7 +1823,7 @@ fn test_op_diff_sibling() {
         ],
     );
     insta::assert_snapshot!(output, @r"
-    From operation: d700dc16fded (2001-02-03 08:05:11) new empty commit
+    From operation: d763e4b2c446 (2001-02-03 08:05:11) new empty commit
       To operation: 13b143e1f4f9 (2001-02-03 08:05:12) describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
 
     Changed commits:
@@ -1850,7 +1856,7 @@ fn test_op_diff_sibling() {
     );
     insta::assert_snapshot!(output, @r"
     From operation: 13b143e1f4f9 (2001-02-03 08:05:12) describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
-      To operation: d700dc16fded (2001-02-03 08:05:11) new empty commit
+      To operation: d763e4b2c446 (2001-02-03 08:05:11) new empty commit
 
     Changed commits:
     ○    + mzvwutvl 270db3d9 (empty) A
@@ -1896,7 +1902,7 @@ fn test_op_diff_word_wrap() {
     // ui.log-word-wrap option works, and diff stat respects content width
     insta::assert_snapshot!(render(&["op", "diff", "--from=@---", "--stat"], 40, true), @r"
     From operation: eac759b9ab75 (2001-02-03 08:05:07) add workspace 'default'
-      To operation: e1a943dc0fe5 (2001-02-03 08:05:08) snapshot working copy
+      To operation: d3711cd70bf7 (2001-02-03 08:05:08) snapshot working copy
 
     Changed commits:
     ○  + sqpuoqvx 7581c520 (no description
@@ -1951,7 +1957,7 @@ fn test_op_diff_word_wrap() {
     insta::assert_snapshot!(
         render(&["op", "diff", "--from=@---", "--config", config], 10, true), @r"
     From operation: eac759b9ab75 (2001-02-03 08:05:07) add workspace 'default'
-      To operation: e1a943dc0fe5 (2001-02-03 08:05:08) snapshot working copy
+      To operation: d3711cd70bf7 (2001-02-03 08:05:08) snapshot working copy
 
     Changed
     commits:
@@ -2336,7 +2342,7 @@ fn test_op_show() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "show"]);
     insta::assert_snapshot!(output, @r"
-    2a7821341a99 test-username@host.example.com 2001-02-03 04:05:30.000 +07:00 - 2001-02-03 04:05:30.000 +07:00
+    2aa7c4a6a93b test-username@host.example.com 2001-02-03 04:05:30.000 +07:00 - 2001-02-03 04:05:30.000 +07:00
     push all tracked bookmarks to git remote origin
     args: jj git push --tracked
 
@@ -2375,7 +2381,7 @@ fn test_op_show_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "show", "@-", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    187a5a9d8a22 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    2e935d59f9e6 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     snapshot working copy
     args: jj new
 
@@ -2397,7 +2403,7 @@ fn test_op_show_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "show", "@", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    a7e535e73c4b test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    14940d16ef6f test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     new empty commit
     args: jj new
 
@@ -2421,7 +2427,7 @@ fn test_op_show_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "show", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    894c12d90345 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    69140a0ef37d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
     args: jj squash
 
@@ -2462,7 +2468,7 @@ fn test_op_show_patch() {
     ");
     let output = test_env.run_jj_in(&repo_path, ["op", "show", "-p", "--git"]);
     insta::assert_snapshot!(output, @r"
-    e5505aa79d31 test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
+    a4f5ab03165a test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
     abandon commit 9f4fb57fba25a7b47ce5980a5d9a4766778331e8
     args: jj abandon
 
@@ -2479,7 +2485,7 @@ fn test_op_show_patch() {
     // Try again with "op log".
     let output = test_env.run_jj_in(&repo_path, ["op", "log", "--git"]);
     insta::assert_snapshot!(output, @r"
-    @  e5505aa79d31 test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
+    @  a4f5ab03165a test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
     │  abandon commit 9f4fb57fba25a7b47ce5980a5d9a4766778331e8
     │  args: jj abandon
     │
@@ -2490,7 +2496,7 @@ fn test_op_show_patch() {
     │  Changed working copy default@:
     │  + yqosqzyt 33f321c4 (empty) (no description set)
     │  - mzvwutvl hidden 9f4fb57f (empty) (no description set)
-    ○  894c12d90345 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    ○  69140a0ef37d test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     │  squash commits into 6b1027d2770cd0a39c468e525e52bf8c47e1464a
     │  args: jj squash
     │
@@ -2517,7 +2523,7 @@ fn test_op_show_patch() {
     │  Changed working copy default@:
     │  + mzvwutvl 9f4fb57f (empty) (no description set)
     │  - rlvkpnrz hidden 1d7f8f94 (no description set)
-    ○  15c3c5d0baf0 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    ○  133721655181 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
     │  snapshot working copy
     │  args: jj squash
     │
@@ -2535,7 +2541,7 @@ fn test_op_show_patch() {
     │  Changed working copy default@:
     │  + rlvkpnrz 1d7f8f94 (no description set)
     │  - rlvkpnrz hidden 56950632 (empty) (no description set)
-    ○  a7e535e73c4b test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    ○  14940d16ef6f test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  new empty commit
     │  args: jj new
     │
@@ -2545,7 +2551,7 @@ fn test_op_show_patch() {
     │  Changed working copy default@:
     │  + rlvkpnrz 56950632 (empty) (no description set)
     │  - qpvuntsm 6b1027d2 (no description set)
-    ○  187a5a9d8a22 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    ○  2e935d59f9e6 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  snapshot working copy
     │  args: jj new
     │
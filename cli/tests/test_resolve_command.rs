@@ -940,10 +940,10 @@ fn test_edit_delete_conflict_input_files() {
     std::fs::read_to_string(repo_path.join("file")).unwrap()
         , @r"
     <<<<<<< Conflict 1 of 1
-    +++++++ Contents of side #1
-    a
-    %%%%%%% Changes from base to side #2
+    %%%%%%% Changes from base to side #1
     -base
+    +a
+    +++++++ Contents of side #2 (file deleted)
     >>>>>>> Conflict 1 of 1 ends
     ");
 
@@ -997,7 +997,7 @@ fn test_file_vs_dir() {
     Hint: Using default editor ':builtin'; run `jj config set --user ui.merge-editor :builtin` to disable this message.
     Error: Failed to resolve conflicts
     Caused by: Only conflicts that involve normal files (not symlinks, not executable, etc.) are supported. Conflict summary for "file":
-    Conflict:
+    Conflict between a file and a directory:
       Removing file with id df967b96a579e45a18b8251732d16804b2e56a55
       Adding file with id 78981922613b2afb6025042ff6bd878ac1994e85
       Adding tree with id 133bb38fc4e4bf6b551f1f04db7e48f04cac2877
@@ -1067,7 +1067,7 @@ fn test_description_with_dir_and_deletion() {
     Hint: Using default editor ':builtin'; run `jj config set --user ui.merge-editor :builtin` to disable this message.
     Error: Failed to resolve conflicts
     Caused by: Only conflicts that involve normal files (not symlinks, not executable, etc.) are supported. Conflict summary for "file":
-    Conflict:
+    Conflict between a file and a directory:
       Removing file with id df967b96a579e45a18b8251732d16804b2e56a55
       Removing file with id df967b96a579e45a18b8251732d16804b2e56a55
       Adding file with id 61780798228d17af2d34fce4cfbdf35556832472
@@ -1228,6 +1228,89 @@ fn test_resolve_conflicts_with_executable() {
     ");
 }
 
+#[test]
+fn test_resolve_with_merge_tool_attribute() {
+    let mut test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit_with_files(
+        &test_env.work_dir(&repo_path),
+        "base",
+        &[],
+        &[("file.txt", "base\n"), ("file.svg", "base\n")],
+    );
+    create_commit_with_files(
+        &test_env.work_dir(&repo_path),
+        "a",
+        &["base"],
+        &[("file.txt", "a\n"), ("file.svg", "a\n")],
+    );
+    create_commit_with_files(
+        &test_env.work_dir(&repo_path),
+        "b",
+        &["base"],
+        &[("file.txt", "b\n"), ("file.svg", "b\n")],
+    );
+    create_commit_with_files(&test_env.work_dir(&repo_path), "conflict", &["a", "b"], &[]);
+    let editor_script = test_env.set_up_fake_editor();
+    test_env.add_config(indoc! {r#"
+        ui.merge-editor = "failing-tool"
+        [merge-tools.failing-tool]
+        program = "false"
+        merge-args = ["$output"]
+
+        [attributes."*.svg"]
+        merge-tool = "fake-editor"
+    "#});
+
+    // The tool selected by the attribute resolves file.svg, and the default
+    // tool fails to resolve file.txt
+    std::fs::write(&editor_script, "write\nresolution\n").unwrap();
+    let output = test_env.run_jj_in(&repo_path, ["resolve"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Resolving conflicts in: file.svg
+    Resolving conflicts in: file.txt
+    Working copy now at: vruxwmqv 4bace78e conflict | (conflict) conflict
+    Parent commit      : zsuskuln e8277034 a | a
+    Parent commit      : royxmykx b577349d b | b
+    Added 0 files, modified 1 files, removed 0 files
+    Warning: There are unresolved conflicts at these paths:
+    file.txt    2-sided conflict
+    New conflicts appeared in these commits:
+      vruxwmqv 4bace78e conflict | (conflict) conflict
+    Hint: To resolve the conflicts, start by updating to it:
+      jj new vruxwmqv
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you may want to inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    Error: Stopped due to error after resolving 1 conflicts
+    Caused by: Tool exited with exit status: 1 (run with --debug to see the exact invocation)
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(test_env.run_jj_in(&repo_path, ["resolve", "--list"]), @r"
+    file.txt    2-sided conflict
+    [EOF]
+    ");
+
+    // --tool applies to all files
+    test_env.run_jj_in(&repo_path, ["undo"]).success();
+    std::fs::write(&editor_script, "write\nresolution\n").unwrap();
+    let output = test_env.run_jj_in(&repo_path, ["resolve", "--tool=fake-editor"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Resolving conflicts in: file.svg
+    Resolving conflicts in: file.txt
+    Working copy now at: vruxwmqv 3477797c conflict | conflict
+    Parent commit      : zsuskuln e8277034 a | a
+    Parent commit      : royxmykx b577349d b | b
+    Added 0 files, modified 2 files, removed 0 files
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_resolve_long_conflict_markers() {
     let mut test_env = TestEnvironment::default();
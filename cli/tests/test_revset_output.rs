@@ -235,7 +235,7 @@ fn test_bad_function_call() {
       |
       = Invalid file pattern
     3: Path "../out" is not in the repo "."
-    4: Invalid component ".." in repo-relative path "../out"
+    4: Path "../out" points outside of the repo root
     [EOF]
     [exit status: 1]
     "#);
@@ -190,6 +190,66 @@ fn test_sparse_manage_patterns() {
     ");
 }
 
+#[test]
+fn test_sparse_set_clone_from() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_path = test_env.env_root().join("main");
+    std::fs::write(main_path.join("file1"), "contents").unwrap();
+    std::fs::write(main_path.join("file2"), "contents").unwrap();
+    std::fs::write(main_path.join("file3"), "contents").unwrap();
+    test_env
+        .run_jj_in(&main_path, ["workspace", "add", "../secondary"])
+        .success();
+    let secondary_path = test_env.env_root().join("secondary");
+    test_env
+        .run_jj_in(&secondary_path, ["sparse", "set", "--clear", "--add=file1"])
+        .success();
+
+    // Copies the other workspace's patterns, replacing the current ones
+    let output = test_env.run_jj_in(
+        &main_path,
+        ["sparse", "set", "--clone-from=../secondary", "--add=file3"],
+    );
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    let output = test_env.run_jj_in(&main_path, ["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    file1
+    file3
+    [EOF]
+    ");
+    assert!(!main_path.join("file2").exists());
+
+    // Can't be combined with --clear
+    let output = test_env.run_jj_in(
+        &main_path,
+        ["sparse", "set", "--clone-from=../secondary", "--clear"],
+    );
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the argument '--clone-from <WORKSPACE_PATH>' cannot be used with '--clear'
+
+    Usage: jj sparse set --clone-from <WORKSPACE_PATH>
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+
+    // Not a workspace
+    let output = test_env.run_jj_in(&main_path, ["sparse", "set", "--clone-from=.."]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Error: There is no jj repo in "$TEST_ENV/main/.."
+    [EOF]
+    [exit status: 1]
+    "#);
+}
+
 #[test]
 fn test_sparse_editor_avoids_unc() {
     use std::path::PathBuf;
@@ -81,6 +81,35 @@ fn test_status_merge() {
 }
 
 // See https://github.com/jj-vcs/jj/issues/2051.
+#[test]
+fn test_status_divergent_working_copy() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env
+        .run_jj_in(&repo_path, ["describe", "-m", "first"])
+        .success();
+    test_env
+        .run_jj_in(&repo_path, ["describe", "-m", "second", "--at-op", "@-"])
+        .success();
+
+    let output = test_env.run_jj_in(&repo_path, ["status"]);
+    insta::assert_snapshot!(output, @r"
+    The working copy has no changes.
+    Working copy : qpvuntsm?? fa15625b (empty) first
+    Parent commit: zzzzzzzz 00000000 (empty) (no description set)
+    Warning: The working-copy change is divergent:
+      qpvuntsm?? fa15625b (empty) first
+      qpvuntsm?? 79c0a4ca (empty) second
+    Hint: Use `jj abandon` to discard the unwanted versions, or `jj squash` to combine them.
+    [EOF]
+    ------- stderr -------
+    Concurrent modification detected, resolving automatically.
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_status_ignored_gitignore() {
     let test_env = TestEnvironment::default();
@@ -106,6 +135,23 @@ fn test_status_ignored_gitignore() {
     ");
 }
 
+#[test]
+fn test_status_json() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config(r#"snapshot.auto-track = "file_1""#);
+
+    std::fs::write(repo_path.join("file_1"), "file_1").unwrap();
+    std::fs::write(repo_path.join("file_2"), "file_2").unwrap();
+
+    let output = test_env.run_jj_in(&repo_path, ["status", "--output", "json"]);
+    insta::assert_snapshot!(output, @r#"
+    {"changes":[{"source":{"conflict":false,"executable":false,"file_type":"","path":"file_1"},"status":"added","target":{"conflict":false,"executable":false,"file_type":"file","path":"file_1"}}],"conflicted_bookmarks":[],"conflicted_paths":[],"conflicted_remote_bookmarks":[],"parents":[{"author":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"change_id":"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz","commit_id":"0000000000000000000000000000000000000000","committer":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"description":"","parents":[]}],"untracked_paths":["file_2"],"working_copy":{"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"change_id":"qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu","commit_id":"d5e151b63d8f45c68ae5e21fcd27ced794a14e4c","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"description":"","parents":["0000000000000000000000000000000000000000"]}}
+    [EOF]
+    "#);
+}
+
 #[test]
 fn test_status_filtered() {
     let test_env = TestEnvironment::default();
@@ -547,6 +547,80 @@ fn test_shows_no_warning_when_undoing_a_specific_undo_change() {
     ");
 }
 
+#[test]
+fn test_undo_preserves_working_copy_changes() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    // Rebase the working-copy commit onto the wrong parent, and edit some files
+    // afterwards
+    work_dir.run_jj(["describe", "-m", "a"]).success();
+    work_dir.write_file("a", "a\n");
+    work_dir.run_jj(["new", "root()", "-m", "b"]).success();
+    work_dir.write_file("b", "b\n");
+    work_dir
+        .run_jj(["new", "description(a)", "-m", "wc"])
+        .success();
+    work_dir.write_file("wc", "wc\n");
+    work_dir
+        .run_jj(["rebase", "-r", "@", "-d", "description(b)"])
+        .success();
+    let rebase_op_id = work_dir.current_operation_id();
+    work_dir.write_file("new", "new\n");
+
+    // Undoing the rebase keeps the new file
+    let output = work_dir.run_jj(["undo", &rebase_op_id]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Undid operation: f789f4be65bb (2001-02-03 08:05:11) rebase commit 18e4d26adbb5dc74054efb2f531a257ce12f9d85
+    Working copy now at: zsuskuln 05c71dec wc
+    Parent commit      : qpvuntsm 2443ea76 a
+    Added 1 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "-T", "description", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    @  wc
+    │  A new
+    │  A wc
+    ○  a
+    │  A a
+    │ ○  b
+    ├─╯  A b
+    ◆
+    [EOF]
+    ");
+    assert_eq!(work_dir.read_file("new"), "new\n");
+
+    // The changes made since can be reverted as well
+    work_dir.run_jj(["undo"]).success();
+    let output = work_dir.run_jj(["undo", &rebase_op_id, "--restore-working-copy"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Undid operation: f789f4be65bb (2001-02-03 08:05:11) rebase commit 18e4d26adbb5dc74054efb2f531a257ce12f9d85
+    Working copy now at: zsuskuln?? 18e4d26a wc
+    Parent commit      : qpvuntsm 2443ea76 a
+    Added 1 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "-T", "description", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    @  wc
+    │  A wc
+    ○  a
+    │  A a
+    │ ○  wc
+    │ │  A new
+    │ │  A wc
+    │ ○  b
+    ├─╯  A b
+    ◆
+    [EOF]
+    ");
+    assert!(!work_dir.root().join("new").exists());
+}
+
 #[must_use]
 fn get_bookmark_output(test_env: &TestEnvironment, repo_path: &Path) -> CommandOutput {
     // --quiet to suppress deleted bookmarks hint
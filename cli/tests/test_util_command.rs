@@ -100,7 +100,7 @@ fn test_gc_operation_log() {
     let output = test_env.run_jj_in(&repo_path, ["debug", "operation", &op_to_remove]);
     insta::assert_snapshot!(output, @r#"
     ------- stderr -------
-    Error: No operation ID matching "bda58b425f645d895ce92608576509b4fcc0c96dbc5f18717a817f09a530117dff0c2054a28781b4c7f1fdbf5a726c89ebd8666fe54dc9f3cc52ca9596110418"
+    Error: No operation ID matching "6310dc33c1255ae9514cd12c750e95751c23cc470370b8f20f7884fe12b1e1dc0a6d5f5a7a75461c1e83300829a2b77e7553d4d49e132d75955dfd2b4d49e319"
     [EOF]
     [exit status: 1]
     "#);
@@ -591,7 +591,7 @@ fn test_workspaces_conflicting_edits() {
     let output = test_env.run_jj_in(&secondary_path, ["st"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Error: The working copy is stale (not updated since operation c81af45155a2).
+    Error: The working copy is stale (not updated since operation d29f29d0b895).
     Hint: Run `jj workspace update-stale` to update it.
     See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy for more information.
     [EOF]
@@ -601,7 +601,7 @@ fn test_workspaces_conflicting_edits() {
     let output = test_env.run_jj_in(&secondary_path, ["log"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Error: The working copy is stale (not updated since operation c81af45155a2).
+    Error: The working copy is stale (not updated since operation d29f29d0b895).
     Hint: Run `jj workspace update-stale` to update it.
     See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy for more information.
     [EOF]
@@ -644,6 +644,49 @@ fn test_workspaces_conflicting_edits() {
     ");
 }
 
+/// Test discarding changes in a stale working copy
+#[test]
+fn test_workspaces_update_stale_force() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_path = test_env.env_root().join("main");
+    let secondary_path = test_env.env_root().join("secondary");
+
+    std::fs::write(main_path.join("file"), "contents\n").unwrap();
+    test_env.run_jj_in(&main_path, ["new"]).success();
+
+    test_env
+        .run_jj_in(&main_path, ["workspace", "add", "../secondary"])
+        .success();
+
+    // Make changes in both working copies, and rewrite the secondary
+    // workspace's working-copy commit from the main workspace
+    std::fs::write(main_path.join("file"), "changed in main\n").unwrap();
+    std::fs::write(secondary_path.join("file"), "changed in second\n").unwrap();
+    test_env.run_jj_in(&main_path, ["squash"]).success();
+
+    // The changes in the secondary workspace are discarded, so no divergent
+    // commit is created
+    let output = test_env.run_jj_in(&secondary_path, ["workspace", "update-stale", "--force"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy now at: pmmvwywv f42c7be0 (empty) (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    Updated working copy to fresh commit f42c7be07e2b
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &secondary_path), @r"
+    @  f42c7be07e2b secondary@
+    │ ○  9f9a75fd0fb4 default@
+    ├─╯
+    ○  709a45307e19
+    ◆  000000000000
+    [EOF]
+    ");
+    let contents = std::fs::read_to_string(secondary_path.join("file")).unwrap();
+    assert_eq!(contents, "changed in main\n");
+}
+
 /// Test a clean working copy that gets rewritten from another workspace
 #[test]
 fn test_workspaces_updated_by_other() {
@@ -691,7 +734,7 @@ fn test_workspaces_updated_by_other() {
     let output = test_env.run_jj_in(&secondary_path, ["st"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Error: The working copy is stale (not updated since operation c81af45155a2).
+    Error: The working copy is stale (not updated since operation d29f29d0b895).
     Hint: Run `jj workspace update-stale` to update it.
     See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy for more information.
     [EOF]
@@ -845,13 +888,13 @@ fn test_workspaces_current_op_discarded_by_other(automatic: bool) {
     );
     insta::allow_duplicates! {
         insta::assert_snapshot!(output, @r"
-        @  64d9b429d9 abandon commit dc638a7f20571df2c846c84d1469b9fcd0edafc0
-        ○  129f2dca87 create initial working-copy commit in workspace secondary
-        ○  1516a7f851 add workspace 'secondary'
-        ○  19bf99b2b1 new empty commit
-        ○  38c9c18632 snapshot working copy
-        ○  5e4f01399f new empty commit
-        ○  299bc7a187 snapshot working copy
+        @  69eda5d3e1 abandon commit dc638a7f20571df2c846c84d1469b9fcd0edafc0
+        ○  9d3f0323c6 create initial working-copy commit in workspace secondary
+        ○  d2c748553e add workspace 'secondary'
+        ○  d61b6ce76e new empty commit
+        ○  39c7915d7d snapshot working copy
+        ○  6cf12c7bba new empty commit
+        ○  8208f2e2ab snapshot working copy
         ○  eac759b9ab add workspace 'default'
         ○  0000000000
         [EOF]
@@ -891,7 +934,7 @@ fn test_workspaces_current_op_discarded_by_other(automatic: bool) {
         Parent commit: rzvqmyuk 3df3bf89 (empty) (no description set)
         [EOF]
         ------- stderr -------
-        Failed to read working copy's current operation; attempting recovery. Error message from read attempt: Object 129f2dca870b954e2966fba35893bb47a5bc6358db6e8c4065cee91d2d49073efc3e055b9b81269a13c443d964abb18e83d25de73db2376ff434c876c59976ac of type operation not found
+        Failed to read working copy's current operation; attempting recovery. Error message from read attempt: Object 9d3f0323c6404a99c2ec9fe800883ba0e0c781572d383961b4a28c8d88ef41b2ea1e62a6e2f3c64f58d78c47e7fea7db620d02cb3ebb6a2e6ea39034f0549270 of type operation not found
         Created and checked out recovery commit 8ed0355c5d31
         [EOF]
         ");
@@ -909,7 +952,7 @@ fn test_workspaces_current_op_discarded_by_other(automatic: bool) {
         let output = test_env.run_jj_in(&secondary_path, ["workspace", "update-stale"]);
         insta::assert_snapshot!(output, @r"
         ------- stderr -------
-        Failed to read working copy's current operation; attempting recovery. Error message from read attempt: Object 129f2dca870b954e2966fba35893bb47a5bc6358db6e8c4065cee91d2d49073efc3e055b9b81269a13c443d964abb18e83d25de73db2376ff434c876c59976ac of type operation not found
+        Failed to read working copy's current operation; attempting recovery. Error message from read attempt: Object 9d3f0323c6404a99c2ec9fe800883ba0e0c781572d383961b4a28c8d88ef41b2ea1e62a6e2f3c64f58d78c47e7fea7db620d02cb3ebb6a2e6ea39034f0549270 of type operation not found
         Created and checked out recovery commit 8ed0355c5d31
         [EOF]
         ");
@@ -1149,7 +1192,7 @@ fn test_workspaces_forget_multi_transaction() {
     // the op log should have multiple workspaces forgotten in a single tx
     let output = test_env.run_jj_in(&main_path, ["op", "log", "--limit", "1"]);
     insta::assert_snapshot!(output, @r"
-    @  60b2b5a71a84 test-username@host.example.com 2001-02-03 04:05:12.000 +07:00 - 2001-02-03 04:05:12.000 +07:00
+    @  2d92e8de1d78 test-username@host.example.com 2001-02-03 04:05:12.000 +07:00 - 2001-02-03 04:05:12.000 +07:00
     │  forget workspaces second, third
     │  args: jj workspace forget second third
     [EOF]
@@ -1342,7 +1385,7 @@ fn test_debug_snapshot() {
         .success();
     let output = test_env.run_jj_in(&repo_path, ["op", "log"]);
     insta::assert_snapshot!(output, @r"
-    @  c55ebc67e3db test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    @  248c8f4e5889 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  snapshot working copy
     │  args: jj debug snapshot
     ○  eac759b9ab75 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
@@ -1355,10 +1398,10 @@ fn test_debug_snapshot() {
         .success();
     let output = test_env.run_jj_in(&repo_path, ["op", "log"]);
     insta::assert_snapshot!(output, @r"
-    @  c9a40b951848 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    @  1c0724abe6a5 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
     │  describe commit 4e8f9d2be039994f589b4e57ac5e9488703e604d
     │  args: jj describe -m initial
-    ○  c55ebc67e3db test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    ○  248c8f4e5889 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
     │  snapshot working copy
     │  args: jj debug snapshot
     ○  eac759b9ab75 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
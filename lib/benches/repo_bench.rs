@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BatchSize;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use jj_lib::commit::Commit;
+use jj_lib::default_index::DefaultIndexStore;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::revset::ResolvedRevsetExpression;
+use jj_lib::revset::RevsetExpression;
+use testutils::create_tree;
+use testutils::CommitGraphBuilder;
+use testutils::TestRepo;
+
+/// Creates a repo with `num_commits` commits in a single operation. The
+/// commits form two interleaved lines of history which are merged into each
+/// other every 10 commits. Returns the commits in topological order.
+fn create_synthetic_repo(num_commits: usize) -> (TestRepo, Arc<ReadonlyRepo>, Vec<Commit>) {
+    let test_repo = TestRepo::init();
+    let mut tx = test_repo.repo.start_transaction();
+    let mut graph_builder = CommitGraphBuilder::new(tx.repo_mut());
+    let mut commits: Vec<Commit> = Vec::with_capacity(num_commits);
+    for i in 0..num_commits {
+        let commit = match i {
+            0 => graph_builder.initial_commit(),
+            1 => graph_builder.commit_with_parents(&[&commits[0]]),
+            _ if i % 10 == 0 => {
+                graph_builder.commit_with_parents(&[&commits[i - 2], &commits[i - 1]])
+            }
+            _ => graph_builder.commit_with_parents(&[&commits[i - 2]]),
+        };
+        commits.push(commit);
+    }
+    let repo = tx.commit("create synthetic repo").unwrap();
+    (test_repo, repo, commits)
+}
+
+fn label(count: usize) -> String {
+    format!("{}k", count / 1000)
+}
+
+fn bench_index_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_index_build");
+    group.sample_size(10);
+    for count in [1000, 10000] {
+        let (_test_repo, repo, _commits) = create_synthetic_repo(count);
+        let index_store: &DefaultIndexStore = repo.index_store().as_any().downcast_ref().unwrap();
+        group.bench_function(BenchmarkId::from_parameter(label(count)), |b| {
+            b.iter_batched(
+                || index_store.reinit().unwrap(),
+                |()| {
+                    index_store
+                        .build_index_at_operation(repo.operation(), repo.store())
+                        .unwrap()
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+}
+
+fn bench_ancestor_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_ancestor_queries");
+    for count in [1000, 10000] {
+        let (_test_repo, repo, commits) = create_synthetic_repo(count);
+        let index = repo.index();
+        let first = commits[0].id();
+        let head1 = commits[count - 1].id();
+        let head2 = commits[count - 2].id();
+        group.bench_function(BenchmarkId::new("is_ancestor", label(count)), |b| {
+            b.iter(|| index.is_ancestor(first, head1));
+        });
+        group.bench_function(BenchmarkId::new("common_ancestors", label(count)), |b| {
+            b.iter(|| {
+                index.common_ancestors(std::slice::from_ref(head1), std::slice::from_ref(head2))
+            });
+        });
+    }
+}
+
+fn bench_revset_evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_revset_evaluation");
+    for count in [1000, 10000] {
+        let (_test_repo, repo, commits) = create_synthetic_repo(count);
+        let head = ResolvedRevsetExpression::commits(vec![commits[count - 1].id().clone()]);
+        let middle = ResolvedRevsetExpression::commits(vec![commits[count / 2].id().clone()]);
+        let expressions = [
+            ("ancestors", head.ancestors()),
+            ("descendants", middle.descendants()),
+            ("range", middle.range(&head)),
+            ("heads_all", RevsetExpression::all().heads()),
+            ("roots_all", RevsetExpression::all().roots()),
+        ];
+        for (name, expression) in expressions {
+            group.bench_function(BenchmarkId::new(name, label(count)), |b| {
+                b.iter(|| {
+                    let revset = expression.clone().evaluate(repo.as_ref()).unwrap();
+                    revset.iter().count()
+                });
+            });
+        }
+    }
+}
+
+fn bench_tree_merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_tree_merge");
+    group.sample_size(10);
+    for count in [1000, 10000] {
+        let test_repo = TestRepo::init();
+        let repo = &test_repo.repo;
+        let paths = (0..count)
+            .map(|i| RepoPathBuf::from_internal_string(format!("dir{}/file{i}", i / 100)))
+            .collect::<Vec<_>>();
+        // Each side modifies a different third of the files, so the merge is
+        // clean.
+        let write_side = |modified: usize, contents: &str| {
+            let path_contents = paths
+                .iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let contents = if i % 3 == modified { contents } else { "base" };
+                    (path.as_ref(), contents)
+                })
+                .collect::<Vec<_>>();
+            create_tree(repo, &path_contents)
+        };
+        let base_tree = write_side(usize::MAX, "");
+        let tree1 = write_side(0, "side 1");
+        let tree2 = write_side(1, "side 2");
+        group.bench_function(BenchmarkId::from_parameter(label(count)), |b| {
+            b.iter(|| tree1.merge(&base_tree, &tree2).unwrap());
+        });
+    }
+}
+
+fn bench_rebase_descendants(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_rebase_descendants");
+    group.sample_size(10);
+    for count in [1000, 10000] {
+        let (_test_repo, repo, commits) = create_synthetic_repo(count);
+        group.bench_function(BenchmarkId::from_parameter(label(count)), |b| {
+            b.iter_batched(
+                || {
+                    let mut tx = repo.start_transaction();
+                    tx.repo_mut()
+                        .rewrite_commit(&commits[0])
+                        .set_description("rewritten")
+                        .write()
+                        .unwrap();
+                    tx
+                },
+                |mut tx| tx.repo_mut().rebase_descendants().unwrap(),
+                BatchSize::PerIteration,
+            );
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_index_build,
+    bench_ancestor_queries,
+    bench_revset_evaluation,
+    bench_tree_merge,
+    bench_rebase_descendants,
+);
+criterion_main!(benches);
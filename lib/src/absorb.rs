@@ -48,6 +48,7 @@ use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedRevsetExpression;
 use crate::revset::RevsetEvaluationError;
+use crate::rewrite::RebaseError;
 
 /// The source commit to absorb into its ancestry.
 #[derive(Clone, Debug)]
@@ -288,7 +289,7 @@ pub fn absorb_hunks(
     repo: &mut MutableRepo,
     source: &AbsorbSource,
     mut selected_trees: HashMap<CommitId, MergedTreeBuilder>,
-) -> BackendResult<AbsorbStats> {
+) -> Result<AbsorbStats, RebaseError> {
     let store = repo.store().clone();
     let mut rewritten_source = None;
     let mut rewritten_destinations = Vec::new();
@@ -36,6 +36,7 @@ use crate::conflicts::materialize_merge_result_to_bytes;
 use crate::conflicts::materialize_tree_value;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MaterializedTreeValue;
+use crate::copies::find_rename_sources;
 use crate::diff::Diff;
 use crate::diff::DiffHunkKind;
 use crate::fileset::FilesetExpression;
@@ -191,20 +192,30 @@ fn compute_file_annotation(
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     source.fill_line_map();
     let text = source.text.clone();
-    let line_map = process_commits(repo, starting_commit_id, source, domain, file_path)?;
+    let mut line_map = vec![None; source.line_map.len()];
+    process_commits(
+        repo,
+        starting_commit_id,
+        source,
+        domain,
+        file_path,
+        &mut line_map,
+    )?;
     Ok(FileAnnotation { line_map, text })
 }
 
 /// Starting at the starting commit, compute changes at that commit relative to
-/// it's direct parents, updating the mappings as we go. We return the final
-/// original line map that represents where each line of the original came from.
+/// it's direct parents, updating the mappings as we go. The
+/// `original_line_map` is updated to represent where each line of the original
+/// came from.
 fn process_commits(
     repo: &dyn Repo,
     starting_commit_id: &CommitId,
     starting_source: Source,
     domain: &Rc<ResolvedRevsetExpression>,
     file_name: &RepoPath,
-) -> Result<OriginalLineMap, RevsetEvaluationError> {
+    original_line_map: &mut OriginalLineMap,
+) -> Result<(), RevsetEvaluationError> {
     let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(file_name.to_owned()));
     // TODO: If the domain isn't a contiguous range, changes masked out by it
     // might not be caught by the closest ancestor revision. For example,
@@ -216,15 +227,15 @@ fn process_commits(
         .union(&domain.intersection(&ancestors).filtered(predicate))
         .evaluate(repo)?;
 
-    let mut original_line_map = vec![None; starting_source.line_map.len()];
     let mut commit_source_map = HashMap::from([(starting_commit_id.clone(), starting_source)]);
 
     for node in revset.iter_graph() {
         let (commit_id, edge_list) = node?;
         process_commit(
             repo,
+            domain,
             file_name,
-            &mut original_line_map,
+            original_line_map,
             &mut commit_source_map,
             &commit_id,
             &edge_list,
@@ -234,24 +245,30 @@ fn process_commits(
             break;
         }
     }
-    Ok(original_line_map)
+    Ok(())
 }
 
 /// For a given commit, for each parent, we compare the version in the parent
 /// tree with the current version, updating the mappings for any lines in
 /// common. If the parent doesn't have the file, we skip it.
+///
+/// If none of the direct parents have the file, but it was renamed from
+/// another path, the lines in common with the renamed file are traced from
+/// that parent with the old path instead.
 fn process_commit(
     repo: &dyn Repo,
+    domain: &Rc<ResolvedRevsetExpression>,
     file_name: &RepoPath,
     original_line_map: &mut OriginalLineMap,
     commit_source_map: &mut CommitSourceMap,
     current_commit_id: &CommitId,
     edges: &[GraphEdge<CommitId>],
-) -> Result<(), BackendError> {
+) -> Result<(), RevsetEvaluationError> {
     let Some(mut current_source) = commit_source_map.remove(current_commit_id) else {
         return Ok(());
     };
 
+    let mut any_parent_has_file = false;
     for parent_edge in edges {
         let parent_commit_id = &parent_edge.target;
         let parent_source = match commit_source_map.entry(parent_commit_id.clone()) {
@@ -262,38 +279,8 @@ fn process_commit(
             }
         };
 
-        // For two versions of the same file, for all the lines in common,
-        // overwrite the new mapping in the results for the new commit. Let's
-        // say I have a file in commit A and commit B. We know that according to
-        // local line_map, in commit A, line 3 corresponds to line 7 of the
-        // original file. Now, line 3 in Commit A corresponds to line 6 in
-        // commit B. Then, we update local line_map to say that "Commit B line 6
-        // goes to line 7 of the original file". We repeat this for all lines in
-        // common in the two commits.
-        let mut current_lines = current_source.line_map.iter().copied().peekable();
-        let mut new_current_line_map = Vec::new();
-        let mut new_parent_line_map = Vec::new();
-        copy_same_lines_with(
-            &current_source.text,
-            &parent_source.text,
-            |current_start, parent_start, count| {
-                new_current_line_map
-                    .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
-                while let Some((current, original)) =
-                    current_lines.next_if(|&(cur, _)| cur < current_start + count)
-                {
-                    let parent = parent_start + (current - current_start);
-                    new_parent_line_map.push((parent, original));
-                }
-            },
-        );
-        new_current_line_map.extend(current_lines);
-        current_source.line_map = new_current_line_map;
-        parent_source.line_map = if parent_source.line_map.is_empty() {
-            new_parent_line_map
-        } else {
-            itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
-        };
+        any_parent_has_file |= !parent_source.text.is_empty();
+        copy_lines_to_parent(&mut current_source, parent_source);
         // If an omitted parent had the file, leave these lines unresolved.
         // TODO: These unresolved lines could be copied to the original_line_map
         // as Err(commit_id) or something instead of None; or the farthest ancestor
@@ -303,6 +290,26 @@ fn process_commit(
         }
     }
 
+    // Copy records are only looked up if the file appears to be added by this
+    // commit. find_rename_sources() then checks the direct parents.
+    if !any_parent_has_file && !current_source.line_map.is_empty() {
+        let commit = repo.store().get_commit(current_commit_id)?;
+        for (parent_commit, source_path) in find_rename_sources(&commit, file_name)? {
+            let mut parent_source = Source::load(&parent_commit, &source_path)?;
+            copy_lines_to_parent(&mut current_source, &mut parent_source);
+            if !parent_source.line_map.is_empty() {
+                process_commits(
+                    repo,
+                    parent_commit.id(),
+                    parent_source,
+                    domain,
+                    &source_path,
+                    original_line_map,
+                )?;
+            }
+        }
+    }
+
     // Once we've looked at all parents of a commit, any leftover lines must be
     // original to the current commit, so we save this information in
     // original_line_map.
@@ -313,6 +320,40 @@ fn process_commit(
     Ok(())
 }
 
+/// For two versions of the same file, for all the lines in common, overwrite
+/// the new mapping in the results for the new commit. Let's say I have a file
+/// in commit A and commit B. We know that according to local line_map, in
+/// commit A, line 3 corresponds to line 7 of the original file. Now, line 3 in
+/// Commit A corresponds to line 6 in commit B. Then, we update local line_map
+/// to say that "Commit B line 6 goes to line 7 of the original file". We
+/// repeat this for all lines in common in the two commits.
+fn copy_lines_to_parent(current_source: &mut Source, parent_source: &mut Source) {
+    let mut current_lines = current_source.line_map.iter().copied().peekable();
+    let mut new_current_line_map = Vec::new();
+    let mut new_parent_line_map = Vec::new();
+    copy_same_lines_with(
+        &current_source.text,
+        &parent_source.text,
+        |current_start, parent_start, count| {
+            new_current_line_map
+                .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
+            while let Some((current, original)) =
+                current_lines.next_if(|&(cur, _)| cur < current_start + count)
+            {
+                let parent = parent_start + (current - current_start);
+                new_parent_line_map.push((parent, original));
+            }
+        },
+    );
+    new_current_line_map.extend(current_lines);
+    current_source.line_map = new_current_line_map;
+    parent_source.line_map = if parent_source.line_map.is_empty() {
+        new_parent_line_map
+    } else {
+        itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
+    };
+}
+
 /// For two files, calls `copy(current_start, parent_start, count)` for each
 /// range of contiguous lines in common (e.g. line 8-10 maps to line 9-11.)
 fn copy_same_lines_with(
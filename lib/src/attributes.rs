@@ -0,0 +1,276 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-path file attributes, similar to `.gitattributes`.
+//!
+//! Attributes are configured in the `[attributes]` table, which maps glob
+//! patterns to tables of attribute values:
+//!
+//! ```toml
+//! [attributes."*.png"]
+//! binary = true
+//! [attributes."*.bat"]
+//! eol = "input-output"
+//! ```
+
+use serde::Deserialize;
+
+use crate::config::ConfigGetError;
+use crate::eol::EolConversionMode;
+use crate::git_lfs::LfsPointer;
+use crate::repo_path::RepoPath;
+use crate::settings::UserSettings;
+
+/// Attribute values set by a pattern. Unset attributes fall through to the
+/// next matching pattern.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AttributeValues {
+    /// Whether the file is shown as binary (or as text) in diffs, instead of
+    /// detecting it from the contents.
+    pub binary: Option<bool>,
+    /// Line ending conversion between the working copy and the store.
+    pub eol: Option<EolConversionMode>,
+    /// Name of the merge tool used to resolve conflicts in the file.
+    pub merge_tool: Option<String>,
+    /// Whether the file is stored as a Git LFS pointer.
+    pub lfs: Option<bool>,
+}
+
+/// Attributes of files, looked up by path.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileAttributes {
+    rules: Vec<(glob::Pattern, AttributeValues)>,
+}
+
+impl FileAttributes {
+    /// Creates attributes which don't set anything for any path.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Reads the `[attributes]` table. Patterns from higher-precedence config
+    /// layers take precedence.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let table_name = "attributes";
+        let mut attributes = FileAttributes::empty();
+        for pattern in settings.table_keys(table_name) {
+            let values = settings.get([table_name, pattern])?;
+            let pattern = glob::Pattern::new(pattern).map_err(|err| ConfigGetError::Type {
+                name: format!("{table_name}.{pattern}"),
+                error: err.into(),
+                source_path: None,
+            })?;
+            attributes.rules.push((pattern, values));
+        }
+        Ok(attributes)
+    }
+
+    /// Adds `values` for paths matching the glob `pattern`. Rules added first
+    /// take precedence.
+    pub fn with_rule(
+        mut self,
+        pattern: &str,
+        values: AttributeValues,
+    ) -> Result<Self, glob::PatternError> {
+        self.rules.push((glob::Pattern::new(pattern)?, values));
+        Ok(self)
+    }
+
+    /// Iterates over the values set for `path`, in order of precedence.
+    ///
+    /// A pattern without a `/` is matched against the file name, like in
+    /// `.gitattributes`. Otherwise it's matched against the whole path.
+    fn matching_values<'a: 'b, 'b>(
+        &'a self,
+        path: &'b RepoPath,
+    ) -> impl Iterator<Item = &'a AttributeValues> + 'b {
+        let path = path.as_internal_file_string();
+        let file_name = path.rsplit_once('/').map_or(path, |(_, name)| name);
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.rules
+            .iter()
+            .filter(move |(pattern, _)| {
+                if pattern.as_str().contains('/') {
+                    pattern.matches_with(path, options)
+                } else {
+                    pattern.matches_with(file_name, options)
+                }
+            })
+            .map(|(_, values)| values)
+    }
+
+    /// Returns all attributes of `path`. For each attribute, the first
+    /// matching pattern which sets it wins.
+    pub fn values_for(&self, path: &RepoPath) -> AttributeValues {
+        let mut merged = AttributeValues::default();
+        for values in self.matching_values(path) {
+            let AttributeValues {
+                binary,
+                eol,
+                merge_tool,
+                lfs,
+            } = values;
+            merged.binary = merged.binary.or(*binary);
+            merged.eol = merged.eol.or(*eol);
+            merged.merge_tool = merged.merge_tool.or_else(|| merge_tool.clone());
+            merged.lfs = merged.lfs.or(*lfs);
+        }
+        merged
+    }
+
+    /// Whether `path` should be treated as binary in diffs, or `None` to
+    /// detect it from the contents.
+    pub fn binary(&self, path: &RepoPath) -> Option<bool> {
+        self.matching_values(path).find_map(|values| values.binary)
+    }
+
+    /// Whether `path` with `contents` should be treated as binary in diffs,
+    /// or `None` to detect it from the contents. Git LFS pointers are treated
+    /// as binary unless the `binary` attribute is set.
+    pub fn binary_for_contents(&self, path: &RepoPath, contents: &[u8]) -> Option<bool> {
+        self.binary(path)
+            .or_else(|| (self.lfs(path) && is_lfs_pointer(contents)).then_some(true))
+    }
+
+    /// The line ending conversion mode of `path`, or `None` to use the
+    /// default.
+    pub fn eol(&self, path: &RepoPath) -> Option<EolConversionMode> {
+        self.matching_values(path).find_map(|values| values.eol)
+    }
+
+    /// The merge tool to resolve conflicts in `path` with, or `None` to use
+    /// the default.
+    pub fn merge_tool(&self, path: &RepoPath) -> Option<&str> {
+        self.matching_values(path)
+            .find_map(|values| values.merge_tool.as_deref())
+    }
+
+    /// Whether `path` is stored as a Git LFS pointer.
+    pub fn lfs(&self, path: &RepoPath) -> bool {
+        self.matching_values(path)
+            .find_map(|values| values.lfs)
+            .unwrap_or(false)
+    }
+}
+
+fn is_lfs_pointer(contents: &[u8]) -> bool {
+    LfsPointer::parse(contents).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    #[test]
+    fn test_values_for() {
+        let attributes = FileAttributes::empty()
+            .with_rule(
+                "assets/*.svg",
+                AttributeValues {
+                    binary: Some(false),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .with_rule(
+                "*.svg",
+                AttributeValues {
+                    binary: Some(true),
+                    merge_tool: Some("svg-merge".to_owned()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .with_rule(
+                "*",
+                AttributeValues {
+                    eol: Some(EolConversionMode::Input),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let path = repo_path("assets/logo.svg");
+        assert_eq!(attributes.binary(path), Some(false));
+        assert_eq!(attributes.merge_tool(path), Some("svg-merge"));
+        assert_eq!(attributes.eol(path), Some(EolConversionMode::Input));
+        assert!(!attributes.lfs(path));
+        assert_eq!(
+            attributes.values_for(path),
+            AttributeValues {
+                binary: Some(false),
+                eol: Some(EolConversionMode::Input),
+                merge_tool: Some("svg-merge".to_owned()),
+                lfs: None,
+            }
+        );
+
+        // Patterns without a slash match the file name in any directory
+        let path = repo_path("assets/sub/logo.svg");
+        assert_eq!(attributes.binary(path), Some(true));
+        let path = repo_path("file.txt");
+        assert_eq!(attributes.binary(path), None);
+        assert_eq!(attributes.merge_tool(path), None);
+        assert_eq!(attributes.eol(path), Some(EolConversionMode::Input));
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_binary_for_contents() {
+        let lfs = |lfs| AttributeValues {
+            lfs: Some(lfs),
+            ..Default::default()
+        };
+        let attributes = FileAttributes::empty()
+            .with_rule(
+                "text.bin",
+                AttributeValues {
+                    binary: Some(false),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .with_rule("*.bin", lfs(true))
+            .unwrap()
+            .with_rule("*.txt", lfs(false))
+            .unwrap();
+        let pointer = b"version https://git-lfs.github.com/spec/v1\n\
+            oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+            size 12345\n";
+        assert_eq!(
+            attributes.binary_for_contents(repo_path("file.bin"), pointer),
+            Some(true)
+        );
+        assert_eq!(
+            attributes.binary_for_contents(repo_path("file.bin"), b"not a pointer\n"),
+            None
+        );
+        assert_eq!(
+            attributes.binary_for_contents(repo_path("text.bin"), pointer),
+            Some(false)
+        );
+        assert_eq!(
+            attributes.binary_for_contents(repo_path("file.txt"), pointer),
+            None
+        );
+    }
+}
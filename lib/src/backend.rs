@@ -82,10 +82,44 @@ impl Timestamp {
             tz_offset: datetime.offset().local_minus_utc() / 60,
         }
     }
+
+    /// Converts to a date-time in the recorded time zone. Returns `None` if
+    /// the timestamp or time zone offset is out of range.
+    pub fn to_datetime(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        let offset = chrono::FixedOffset::east_opt(self.tz_offset.checked_mul(60)?)?;
+        let utc = chrono::DateTime::from_timestamp_millis(self.timestamp.0)?;
+        Some(utc.with_timezone(&offset))
+    }
+}
+
+/// Serializes as an RFC 3339 date-time string.
+impl serde::Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let datetime = self
+            .to_datetime()
+            .ok_or_else(|| serde::ser::Error::custom("timestamp out of range"))?;
+        serializer.serialize_str(&datetime.to_rfc3339())
+    }
+}
+
+/// Deserializes from an RFC 3339 date-time string.
+impl<'de> serde::Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let text = String::deserialize(deserializer)?;
+        let datetime =
+            chrono::DateTime::parse_from_rfc3339(&text).map_err(serde::de::Error::custom)?;
+        Ok(Timestamp::from_datetime(datetime))
+    }
 }
 
 /// Represents a [`Commit`] signature.
-#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+#[derive(ContentHash, Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct Signature {
     pub name: String,
     pub email: String,
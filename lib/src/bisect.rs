@@ -0,0 +1,209 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bisection of a range of commits to find the first bad commit.
+
+use std::rc::Rc;
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::commit::Commit;
+use crate::op_store::BisectState;
+use crate::repo::MutableRepo;
+use crate::repo::Repo as _;
+use crate::revset::ResolvedRevsetExpression;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+
+/// Error that may occur during bisection.
+#[derive(Debug, Error)]
+pub enum BisectionError {
+    /// No commit in the input range can be the first bad commit, e.g. because
+    /// a commit marked as good is a descendant of a commit marked as bad.
+    #[error("The commits marked as good and bad contradict each other")]
+    ContradictoryMarks,
+    /// Failed to evaluate the candidates.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// Failed to load a commit.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// Next step of a bisection.
+#[derive(Clone, Debug)]
+pub enum BisectionResult {
+    /// The commit should be tested and marked as good, bad, or skipped.
+    Evaluate(Commit),
+    /// The first bad commits were found.
+    Found(Vec<Commit>),
+    /// No untested commit is left, but the first bad commit can't be
+    /// determined because some candidates were skipped. Contains the
+    /// remaining candidates.
+    Indeterminate(Vec<Commit>),
+}
+
+/// Finds the first bad commit in a range of commits.
+///
+/// A commit is assumed to be bad if any of its ancestors is bad. Until a
+/// commit is marked as bad, the heads of the input range are assumed to be
+/// bad.
+///
+/// The marks are recorded in the view, so a bisection can be continued by a
+/// later transaction.
+pub struct Bisector<'repo> {
+    repo: &'repo mut MutableRepo,
+    input_range: Rc<ResolvedRevsetExpression>,
+}
+
+impl<'repo> Bisector<'repo> {
+    /// Creates a bisector over the commits in `input_range`, starting from
+    /// the marks recorded in the view of `repo`.
+    pub fn new(repo: &'repo mut MutableRepo, input_range: Rc<ResolvedRevsetExpression>) -> Self {
+        Bisector { repo, input_range }
+    }
+
+    fn state(&self) -> &BisectState {
+        self.repo.view().bisect_state()
+    }
+
+    fn update_state(&mut self, f: impl FnOnce(&mut BisectState)) {
+        let mut state = self.state().clone();
+        f(&mut state);
+        self.repo.set_bisect_state(state);
+    }
+
+    /// Records that the commit doesn't have the problem.
+    pub fn mark_good(&mut self, id: CommitId) {
+        self.update_state(|state| state.good_commits.push(id));
+    }
+
+    /// Records that the commit has the problem.
+    pub fn mark_bad(&mut self, id: CommitId) {
+        self.update_state(|state| state.bad_commits.push(id));
+    }
+
+    /// Records that the commit couldn't be tested.
+    pub fn mark_skipped(&mut self, id: CommitId) {
+        self.update_state(|state| {
+            if !state.skipped_commits.contains(&id) {
+                state.skipped_commits.push(id);
+            }
+        });
+    }
+
+    /// Forgets all marks, which ends the bisection.
+    pub fn reset(&mut self) {
+        self.repo.set_bisect_state(BisectState::default());
+    }
+
+    /// Commits marked as good.
+    pub fn good_commits(&self) -> &[CommitId] {
+        &self.state().good_commits
+    }
+
+    /// Commits marked as bad.
+    pub fn bad_commits(&self) -> &[CommitId] {
+        &self.state().bad_commits
+    }
+
+    /// Commits in the input range which may be the first bad commit, in
+    /// reverse topological order.
+    fn candidates(&self) -> Result<Vec<CommitId>, RevsetEvaluationError> {
+        let state = self.state();
+        // The first bad commit is an ancestor of all bad commits.
+        let bad_ancestors = if state.bad_commits.is_empty() {
+            self.input_range.heads().ancestors()
+        } else {
+            state
+                .bad_commits
+                .iter()
+                .map(|id| RevsetExpression::commit(id.clone()).ancestors())
+                .reduce(|acc, expr| acc.intersection(&expr))
+                .unwrap()
+        };
+        let good_ancestors = RevsetExpression::commits(state.good_commits.clone()).ancestors();
+        let expression = self
+            .input_range
+            .intersection(&bad_ancestors)
+            .minus(&good_ancestors);
+        let revset = expression.evaluate(&*self.repo)?;
+        let ids = revset.iter().try_collect()?;
+        Ok(ids)
+    }
+
+    /// Determines the next commit to test, or the result of the bisection.
+    ///
+    /// The commit to test is chosen so that about half of the candidates are
+    /// its ancestors, which means either answer eliminates about half of
+    /// them.
+    pub fn next_step(&self) -> Result<BisectionResult, BisectionError> {
+        let candidates = self.candidates()?;
+        if candidates.is_empty() {
+            return Err(BisectionError::ContradictoryMarks);
+        }
+        let state = self.state();
+        let store = self.repo.store();
+        let load = |ids: &[CommitId]| -> Result<Vec<Commit>, BackendError> {
+            ids.iter().map(|id| store.get_commit(id)).try_collect()
+        };
+        let untested = candidates
+            .iter()
+            .filter(|id| !state.bad_commits.contains(id) && !state.skipped_commits.contains(id))
+            .collect_vec();
+        let candidates_expression = RevsetExpression::commits(candidates.clone());
+        if untested.is_empty() {
+            let roots = candidates_expression
+                .roots()
+                .evaluate(&*self.repo)?
+                .iter()
+                .try_collect::<_, Vec<_>, _>()?;
+            return if roots.iter().all(|id| state.bad_commits.contains(id)) {
+                Ok(BisectionResult::Found(load(&roots)?))
+            } else {
+                Ok(BisectionResult::Indeterminate(load(&candidates)?))
+            };
+        }
+        let ancestor_counts: Vec<(&CommitId, usize)> = untested
+            .into_iter()
+            .map(|id| Ok((id, self.count_ancestors(id, &candidates_expression)?)))
+            .try_collect::<_, _, RevsetEvaluationError>()?;
+        let num_candidates = candidates.len();
+        let (best_id, _) = ancestor_counts
+            .into_iter()
+            .max_by_key(|&(_, count)| {
+                // Prefer the older commit if both halves are equally large.
+                (count.min(num_candidates - count), std::cmp::Reverse(count))
+            })
+            .unwrap();
+        Ok(BisectionResult::Evaluate(store.get_commit(best_id)?))
+    }
+
+    /// Returns the number of ancestors (inclusive) of the commit `id` among
+    /// the `candidates`.
+    fn count_ancestors(
+        &self,
+        id: &CommitId,
+        candidates: &Rc<ResolvedRevsetExpression>,
+    ) -> Result<usize, RevsetEvaluationError> {
+        let revset = RevsetExpression::commit(id.clone())
+            .ancestors()
+            .intersection(candidates)
+            .evaluate(&*self.repo)?;
+        revset.iter().process_results(|ids| ids.count())
+    }
+}
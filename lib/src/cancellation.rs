@@ -0,0 +1,81 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cooperative cancellation of long-running operations.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+/// Error returned by an operation which was cancelled through a
+/// [`CancellationToken`].
+#[derive(Clone, Copy, Debug, Error)]
+#[error("Operation was cancelled")]
+pub struct Cancelled;
+
+/// Handle that can be used to request cancellation of an operation from
+/// another thread.
+///
+/// Clones share the same state, so cancelling one clone cancels all of them.
+/// Operations check the token at points where stopping leaves no partial
+/// state behind (e.g. between commits when rebasing descendants), and return
+/// [`Cancelled`] once cancellation has been requested.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that hasn't been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of the operations observing this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`Cancelled`] if cancellation has been requested.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_shared_between_clones() {
+        let token = CancellationToken::new();
+        let other = token.clone();
+        assert!(!other.is_cancelled());
+        assert!(other.check().is_ok());
+        token.cancel();
+        assert!(other.is_cancelled());
+        assert!(matches!(other.check(), Err(Cancelled)));
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Source of the current time for new commits and operations.
+
+use std::fmt::Debug;
+
+use crate::backend::Timestamp;
+
+/// Provides the current time.
+///
+/// The clock is injected through
+/// [`UserSettings`](crate::settings::UserSettings) so that tests and
+/// reproducible builds can produce byte-identical commits and operations.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Timestamp;
+}
+
+/// Clock that reads the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// Clock that always returns the same time.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock {
+    timestamp: Timestamp,
+}
+
+impl FixedClock {
+    /// Creates a clock that is stopped at `timestamp`.
+    pub fn new(timestamp: Timestamp) -> Self {
+        FixedClock { timestamp }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.timestamp
+    }
+}
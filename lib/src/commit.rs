@@ -36,6 +36,7 @@ use crate::rewrite::merge_commit_trees;
 use crate::signing::SignResult;
 use crate::signing::Verification;
 use crate::store::Store;
+use crate::trailer;
 
 #[derive(Clone)]
 pub struct Commit {
@@ -76,6 +77,25 @@ impl Hash for Commit {
     }
 }
 
+/// Serializes the commit metadata. The tree and other backend-specific data
+/// are omitted.
+impl serde::Serialize for Commit {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+        let mut state = serializer.serialize_struct("Commit", 6)?;
+        state.serialize_field("commit_id", self.id())?;
+        state.serialize_field("parents", self.parent_ids())?;
+        state.serialize_field("change_id", self.change_id())?;
+        state.serialize_field("description", self.description())?;
+        state.serialize_field("author", self.author())?;
+        state.serialize_field("committer", self.committer())?;
+        state.end()
+    }
+}
+
 impl Commit {
     pub fn new(store: Arc<Store>, id: CommitId, data: Arc<backend::Commit>) -> Self {
         Commit { store, id, data }
@@ -149,6 +169,26 @@ impl Commit {
         &self.data.description
     }
 
+    /// Returns the first line of the description.
+    pub fn subject(&self) -> &str {
+        self.description().lines().next().unwrap_or_default()
+    }
+
+    /// Returns the description after the subject line and the blank lines
+    /// following it. Trailers are included.
+    pub fn body(&self) -> &str {
+        match self.description().split_once('\n') {
+            Some((_, rest)) => rest.trim_start_matches(['\r', '\n']),
+            None => "",
+        }
+    }
+
+    /// Returns the `(key, value)` pairs of the trailers at the end of the
+    /// description.
+    pub fn trailers(&self) -> Vec<(&str, &str)> {
+        trailer::parse_trailers(self.description())
+    }
+
     pub fn author(&self) -> &Signature {
         &self.data.author
     }
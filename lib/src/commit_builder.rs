@@ -14,6 +14,7 @@
 
 #![allow(missing_docs)]
 
+use std::mem;
 use std::sync::Arc;
 
 use pollster::FutureExt;
@@ -29,10 +30,13 @@ use crate::commit::Commit;
 use crate::repo::MutableRepo;
 use crate::repo::Repo;
 use crate::settings::JJRng;
+use crate::settings::RewriteTimestamps;
 use crate::settings::SignSettings;
+use crate::settings::TrailerSettings;
 use crate::settings::UserSettings;
 use crate::signing::SignBehavior;
 use crate::store::Store;
+use crate::trailer;
 
 #[must_use]
 pub struct CommitBuilder<'repo> {
@@ -163,6 +167,7 @@ pub struct DetachedCommitBuilder {
     commit: backend::Commit,
     rewrite_source: Option<Commit>,
     sign_settings: SignSettings,
+    trailer_settings: TrailerSettings,
 }
 
 impl DetachedCommitBuilder {
@@ -194,6 +199,7 @@ impl DetachedCommitBuilder {
             commit,
             rewrite_source: None,
             sign_settings: settings.sign_settings(),
+            trailer_settings: settings.trailer_settings(),
         }
     }
 
@@ -206,6 +212,7 @@ impl DetachedCommitBuilder {
         let store = repo.store().clone();
         let mut commit = predecessor.store_commit().clone();
         commit.predecessors = vec![predecessor.id().clone()];
+        let committer_timestamp = commit.committer.timestamp;
         commit.committer = settings.signature();
         // If the user had not configured a name and email before but now they have,
         // update the author fields with the new information.
@@ -220,14 +227,25 @@ impl DetachedCommitBuilder {
             commit.author.email.clone_from(&commit.committer.email);
         }
 
-        // Reset author timestamp on discardable commits if the author is the
-        // committer. While it's unlikely we'll have somebody else's commit
-        // with no description in our repo, we'd like to be extra safe.
-        if commit.author.name == commit.committer.name
-            && commit.author.email == commit.committer.email
-            && predecessor.is_discardable(repo).unwrap_or_default()
-        {
-            commit.author.timestamp = commit.committer.timestamp;
+        match settings.rewrite_timestamps() {
+            RewriteTimestamps::RefreshCommitter => {
+                // Reset author timestamp on discardable commits if the author
+                // is the committer. While it's unlikely we'll have somebody
+                // else's commit with no description in our repo, we'd like to
+                // be extra safe.
+                if commit.author.name == commit.committer.name
+                    && commit.author.email == commit.committer.email
+                    && predecessor.is_discardable(repo).unwrap_or_default()
+                {
+                    commit.author.timestamp = commit.committer.timestamp;
+                }
+            }
+            RewriteTimestamps::Keep => {
+                commit.committer.timestamp = committer_timestamp;
+            }
+            RewriteTimestamps::ResetAuthor => {
+                commit.author.timestamp = commit.committer.timestamp;
+            }
         }
 
         DetachedCommitBuilder {
@@ -236,6 +254,7 @@ impl DetachedCommitBuilder {
             rng: settings.get_rng(),
             rewrite_source: Some(predecessor.clone()),
             sign_settings: settings.sign_settings(),
+            trailer_settings: settings.trailer_settings(),
         }
     }
 
@@ -346,12 +365,28 @@ impl DetachedCommitBuilder {
         self
     }
 
+    /// Returns true if the description is new or has been edited. Trailers
+    /// are only added to such descriptions, not to the ones carried over by
+    /// rewrites such as rebases.
+    fn is_description_edited(&self) -> bool {
+        self.rewrite_source
+            .as_ref()
+            .is_none_or(|source| source.description() != self.commit.description)
+    }
+
     /// Writes new commit and makes it visible in the `mut_repo`.
     pub fn write(self, mut_repo: &mut MutableRepo) -> BackendResult<Commit> {
-        let commit = write_to_store(&self.store, self.commit, &self.sign_settings)?;
+        let add_trailers = self.is_description_edited();
+        let commit = write_to_store(
+            &self.store,
+            self.commit,
+            &self.sign_settings,
+            add_trailers.then_some(&self.trailer_settings),
+        )?;
         mut_repo.add_head(&commit)?;
         if let Some(rewrite_source) = self.rewrite_source {
             if rewrite_source.change_id() == commit.change_id() {
+                mut_repo.add_resolved_conflicts(&rewrite_source, &commit);
                 mut_repo.set_rewritten_commit(rewrite_source.id().clone(), commit.id().clone());
             }
         }
@@ -363,7 +398,13 @@ impl DetachedCommitBuilder {
     /// This does not consume the builder, so you can reuse the current
     /// configuration to create another commit later.
     pub fn write_hidden(&self) -> BackendResult<Commit> {
-        write_to_store(&self.store, self.commit.clone(), &self.sign_settings)
+        write_to_store(
+            &self.store,
+            self.commit.clone(),
+            &self.sign_settings,
+            self.is_description_edited()
+                .then_some(&self.trailer_settings),
+        )
     }
 
     /// Records the old commit as abandoned in the `mut_repo`.
@@ -387,7 +428,12 @@ fn write_to_store(
     store: &Arc<Store>,
     mut commit: backend::Commit,
     sign_settings: &SignSettings,
+    trailer_settings: Option<&TrailerSettings>,
 ) -> BackendResult<Commit> {
+    if let Some(trailer_settings) = trailer_settings {
+        let description = mem::take(&mut commit.description);
+        commit.description = add_trailers(description, &commit, trailer_settings);
+    }
     let should_sign = store.signer().can_sign() && sign_settings.should_sign(&commit);
     let sign_fn = |data: &[u8]| store.signer().sign(data, sign_settings.key.as_deref());
 
@@ -400,3 +446,25 @@ fn write_to_store(
         .write_commit(commit, should_sign.then_some(&mut &sign_fn))
         .block_on()
 }
+
+/// Adds the configured trailers to a non-empty `description` of the `commit`.
+fn add_trailers(
+    mut description: String,
+    commit: &backend::Commit,
+    trailer_settings: &TrailerSettings,
+) -> String {
+    if trailer_settings.signed_off_by {
+        let value = trailer::signed_off_by_value(&commit.committer);
+        description = trailer::append_trailer(&description, "Signed-off-by", &value);
+    }
+    // Keep the Change-Id of commits imported from Gerrit.
+    if trailer_settings.change_id
+        && !trailer::parse_trailers(&description)
+            .iter()
+            .any(|(key, _)| *key == "Change-Id")
+    {
+        let value = trailer::gerrit_change_id_value(&commit.change_id);
+        description = trailer::append_trailer(&description, "Change-Id", &value);
+    }
+    description
+}
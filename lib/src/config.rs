@@ -500,6 +500,68 @@ impl ConfigLayer {
         ensure_table(self.data.as_table_mut(), &name.0)
             .map_err(|keys| would_overwrite_table(keys.join(".")))
     }
+
+    /// Returns names of the variables in this layer which aren't declared in
+    /// the `known` table.
+    ///
+    /// Sub tables are checked recursively if the `known` table has a
+    /// (non-inline) table at the same path. Any other known item, such as `{}`,
+    /// matches all values at the path. The latter can be used to declare
+    /// free-form tables like `aliases`. Conditional tables should be resolved
+    /// beforehand.
+    pub fn unknown_names(&self, known: &ConfigTableLike) -> Vec<ConfigNamePathBuf> {
+        let mut unknown_names = Vec::new();
+        collect_unknown_names(
+            self.data.as_table(),
+            known,
+            &ConfigNamePathBuf::root(),
+            &mut unknown_names,
+        );
+        unknown_names
+    }
+
+    /// Returns the 1-based line number where the variable `name` is defined in
+    /// the source file of this layer.
+    ///
+    /// Since the parsed document doesn't retain spans, the source file is read
+    /// again. Returns `None` if this layer wasn't loaded from a file, or if the
+    /// variable isn't found in the file (e.g. because it was migrated.)
+    pub fn source_line(&self, name: impl ToConfigNamePath) -> Option<usize> {
+        let name = name.into_name_path();
+        let text = fs::read_to_string(self.path.as_ref()?).ok()?;
+        let doc = ImDocument::parse(text).ok()?;
+        let mut table: Option<&ConfigTableLike> = Some(doc.as_table());
+        let mut span = None;
+        for key in name.borrow().components() {
+            let (key, item) = table?.get_key_value(key)?;
+            span = key.span();
+            table = item.as_table_like();
+        }
+        let offset = span?.start;
+        Some(doc.raw()[..offset].matches('\n').count() + 1)
+    }
+}
+
+fn collect_unknown_names(
+    table: &ConfigTableLike,
+    known: &ConfigTableLike,
+    prefix: &ConfigNamePathBuf,
+    unknown_names: &mut Vec<ConfigNamePathBuf>,
+) {
+    for (key, item) in table.iter() {
+        let mut name = prefix.clone();
+        name.push(key);
+        match known.get(key) {
+            Some(ConfigItem::Table(known_table)) => {
+                // Type mismatch will be reported when the value is looked up.
+                if let Some(table) = item.as_table_like() {
+                    collect_unknown_names(table, known_table, &name, unknown_names);
+                }
+            }
+            Some(_) => {}
+            None => unknown_names.push(name),
+        }
+    }
 }
 
 /// Looks up item from the `root_item`. Returns `Some(item)` if an item found at
@@ -902,6 +964,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::tests::new_temp_dir;
 
     #[test]
     fn test_config_layer_set_value() {
@@ -1047,6 +1110,77 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn test_config_layer_unknown_names() {
+        let known = ConfigLayer::parse(
+            ConfigSource::Default,
+            indoc! {"
+                aliases = {}
+                [foo]
+                bar = 1
+                [foo.baz]
+                qux = 'a'
+            "},
+        )
+        .unwrap();
+        let layer = ConfigLayer::parse(
+            ConfigSource::User,
+            indoc! {"
+                unknown = 0
+                aliases.a = ['b']
+                aliases.c.d = 1
+                foo.bar = 'wrong type'
+                foo.unknown = 2
+                foo.baz.qux = 'b'
+                foo.baz.unknown = { a = 1 }
+                [foo.unknown-table]
+                a = 1
+            "},
+        )
+        .unwrap();
+        let unknown_names = layer.unknown_names(known.data.as_table());
+        assert_eq!(
+            unknown_names
+                .iter()
+                .map(|name| name.to_string())
+                .collect_vec(),
+            [
+                "unknown",
+                "foo.unknown",
+                "foo.baz.unknown",
+                "foo.unknown-table"
+            ]
+        );
+        assert!(known.unknown_names(known.data.as_table()).is_empty());
+    }
+
+    #[test]
+    fn test_config_layer_source_line() {
+        let temp_dir = new_temp_dir();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            indoc! {"
+                foo.bar = 1
+
+                [baz]
+                qux = 'a'
+                quux.a = 2
+            "},
+        )
+        .unwrap();
+        let layer = ConfigLayer::load_from_file(ConfigSource::User, path).unwrap();
+        assert_eq!(layer.source_line("foo.bar"), Some(1));
+        assert_eq!(layer.source_line("baz"), Some(3));
+        assert_eq!(layer.source_line("baz.qux"), Some(4));
+        assert_eq!(layer.source_line("baz.quux.a"), Some(5));
+        assert_eq!(layer.source_line("baz.unknown"), None);
+        assert_eq!(layer.source_line("foo.bar.a"), None);
+
+        let layer = ConfigLayer::parse(ConfigSource::User, "foo = 1").unwrap();
+        assert_eq!(layer.source_line("foo"), None);
+    }
+
     #[test]
     fn test_stacked_config_layer_order() {
         let empty_data = || DocumentMut::new();
@@ -14,10 +14,12 @@
 
 #![allow(missing_docs)]
 
+use std::fmt::Write as _;
 use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::iter::zip;
+use std::sync::Arc;
 
 use bstr::BString;
 use bstr::ByteSlice;
@@ -28,6 +30,7 @@ use futures::StreamExt;
 use futures::TryStreamExt;
 use itertools::Itertools;
 use pollster::FutureExt;
+use thiserror::Error;
 
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
@@ -43,9 +46,11 @@ use crate::diff::DiffHunk;
 use crate::diff::DiffHunkKind;
 use crate::files;
 use crate::files::MergeResult;
+use crate::merge::ConflictKind;
 use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
 use crate::repo_path::RepoPath;
 use crate::store::Store;
 
@@ -67,6 +72,9 @@ const ADD_NO_EOL_COMMENT: &str = " (removes terminating newline)";
 /// Comment for missing terminating newline in the "remove" side of a diff.
 const REMOVE_NO_EOL_COMMENT: &str = " (adds terminating newline)";
 
+/// Comment for a side of a conflict which deleted the file.
+const DELETED_COMMENT: &str = " (file deleted)";
+
 fn write_diff_hunks(hunks: &[DiffHunk], file: &mut dyn Write) -> io::Result<()> {
     for hunk in hunks {
         match hunk.kind {
@@ -226,6 +234,39 @@ async fn materialize_tree_value_no_access_denied(
     }
 }
 
+/// Describes a conflict which can't be materialized with conflict markers.
+///
+/// In addition to [`Merge::describe()`], this lists the files in the
+/// directories of a [`ConflictKind::FileDirectory`] conflict, since they would
+/// otherwise be hidden by the conflict.
+pub fn describe_conflict(
+    store: &Arc<Store>,
+    path: &RepoPath,
+    conflict: &MergedTreeValue,
+) -> BackendResult<String> {
+    let mut description = conflict.describe();
+    if conflict.conflict_kind() != Some(ConflictKind::FileDirectory) {
+        return Ok(description);
+    }
+    for term in conflict.adds().flatten().unique() {
+        let TreeValue::Tree(id) = term else {
+            continue;
+        };
+        let tree = MergedTree::resolved(store.get_tree(path.to_owned(), id)?);
+        writeln!(description, "Files in tree with id {id}:").unwrap();
+        for (file_path, value) in tree.entries() {
+            value?;
+            writeln!(description, "  {}", file_path.as_internal_file_string()).unwrap();
+        }
+    }
+    writeln!(
+        description,
+        "Replace this file with the file or directory to keep to resolve the conflict."
+    )
+    .unwrap();
+    Ok(description)
+}
+
 /// Describes what style should be used when materializing conflicts.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -348,7 +389,13 @@ pub fn materialize_merge_result<T: AsRef<[u8]>>(
         MergeResult::Resolved(content) => output.write_all(content),
         MergeResult::Conflict(hunks) => {
             let conflict_marker_len = choose_materialized_conflict_marker_len(single_hunk);
-            materialize_conflict_hunks(hunks, conflict_marker_style, conflict_marker_len, output)
+            materialize_conflict_hunks(
+                hunks,
+                conflict_marker_style,
+                conflict_marker_len,
+                &[],
+                output,
+            )
         }
     }
 }
@@ -362,9 +409,13 @@ pub fn materialize_merge_result_with_marker_len<T: AsRef<[u8]>>(
     let merge_result = files::merge(single_hunk);
     match &merge_result {
         MergeResult::Resolved(content) => output.write_all(content),
-        MergeResult::Conflict(hunks) => {
-            materialize_conflict_hunks(hunks, conflict_marker_style, conflict_marker_len, output)
-        }
+        MergeResult::Conflict(hunks) => materialize_conflict_hunks(
+            hunks,
+            conflict_marker_style,
+            conflict_marker_len,
+            &[],
+            output,
+        ),
     }
 }
 
@@ -382,6 +433,7 @@ pub fn materialize_merge_result_to_bytes<T: AsRef<[u8]>>(
                 &hunks,
                 conflict_marker_style,
                 conflict_marker_len,
+                &[],
                 &mut output,
             )
             .expect("writing to an in-memory buffer should never fail");
@@ -404,6 +456,35 @@ pub fn materialize_merge_result_to_bytes_with_marker_len<T: AsRef<[u8]>>(
                 &hunks,
                 conflict_marker_style,
                 conflict_marker_len,
+                &[],
+                &mut output,
+            )
+            .expect("writing to an in-memory buffer should never fail");
+            output.into()
+        }
+    }
+}
+
+/// Materializes the contents of a file conflict. Unlike
+/// [`materialize_merge_result_to_bytes_with_marker_len()`], the sides which
+/// deleted the file (as opposed to emptying it) are labeled as such.
+pub fn materialize_file_conflict_to_bytes_with_marker_len(
+    file_ids: &Merge<Option<FileId>>,
+    contents: &Merge<BString>,
+    conflict_marker_style: ConflictMarkerStyle,
+    conflict_marker_len: usize,
+) -> BString {
+    let merge_result = files::merge(contents);
+    match merge_result {
+        MergeResult::Resolved(content) => content,
+        MergeResult::Conflict(hunks) => {
+            let deleted_sides = file_ids.adds().map(Option::is_none).collect_vec();
+            let mut output = Vec::new();
+            materialize_conflict_hunks(
+                &hunks,
+                conflict_marker_style,
+                conflict_marker_len,
+                &deleted_sides,
                 &mut output,
             )
             .expect("writing to an in-memory buffer should never fail");
@@ -416,6 +497,7 @@ fn materialize_conflict_hunks(
     hunks: &[Merge<BString>],
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    deleted_sides: &[bool],
     output: &mut dyn Write,
 ) -> io::Result<()> {
     let num_conflicts = hunks
@@ -439,6 +521,7 @@ fn materialize_conflict_hunks(
                         right,
                         &conflict_info,
                         conflict_marker_len,
+                        deleted_sides,
                         output,
                     )?;
                 }
@@ -448,6 +531,7 @@ fn materialize_conflict_hunks(
                         &conflict_info,
                         conflict_marker_style,
                         conflict_marker_len,
+                        deleted_sides,
                         output,
                     )?;
                 }
@@ -463,13 +547,17 @@ fn materialize_git_style_conflict(
     right: &[u8],
     conflict_info: &str,
     conflict_marker_len: usize,
+    deleted_sides: &[bool],
     output: &mut dyn Write,
 ) -> io::Result<()> {
     write_conflict_marker(
         output,
         ConflictMarkerLineChar::ConflictStart,
         conflict_marker_len,
-        &format!("Side #1 ({conflict_info})"),
+        &format!(
+            "Side #1{} ({conflict_info})",
+            maybe_deleted_comment(deleted_sides, 0)
+        ),
     )?;
     write_and_ensure_newline(output, left)?;
 
@@ -494,7 +582,10 @@ fn materialize_git_style_conflict(
         output,
         ConflictMarkerLineChar::ConflictEnd,
         conflict_marker_len,
-        &format!("Side #2 ({conflict_info} ends)"),
+        &format!(
+            "Side #2{} ({conflict_info} ends)",
+            maybe_deleted_comment(deleted_sides, 1)
+        ),
     )?;
 
     Ok(())
@@ -505,8 +596,11 @@ fn materialize_jj_style_conflict(
     conflict_info: &str,
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    deleted_sides: &[bool],
     output: &mut dyn Write,
 ) -> io::Result<()> {
+    let is_deleted = |add_index: usize| deleted_sides.get(add_index).copied().unwrap_or(false);
+
     // Write a positive snapshot (side) of a conflict
     let write_side = |add_index: usize, data: &[u8], output: &mut dyn Write| {
         write_conflict_marker(
@@ -514,8 +608,9 @@ fn materialize_jj_style_conflict(
             ConflictMarkerLineChar::Add,
             conflict_marker_len,
             &format!(
-                "Contents of side #{}{}",
+                "Contents of side #{}{}{}",
                 add_index + 1,
+                maybe_deleted_comment(deleted_sides, add_index),
                 maybe_no_eol_comment(data)
             ),
         )?;
@@ -591,11 +686,35 @@ fn materialize_jj_style_conflict(
             continue;
         }
 
+        // A side which deleted the file is always emitted as a snapshot, since a
+        // diff would look like all lines were removed.
+        if is_deleted(add_index) {
+            write_side(add_index, right1, output)?;
+            match hunk
+                .get_add(add_index + 1)
+                .filter(|_| !is_deleted(add_index + 1))
+            {
+                Some(right2) => {
+                    let diff2 = Diff::by_line([&left, &right2]).hunks().collect_vec();
+                    write_diff(&base_str, add_index + 1, &diff2, output)?;
+                    add_index += 2;
+                }
+                None => {
+                    write_base(&base_str, left, output)?;
+                    add_index += 1;
+                }
+            }
+            continue;
+        }
+
         let diff1 = Diff::by_line([&left, &right1]).hunks().collect_vec();
         // Check if the diff against the next positive term is better. Since we want to
         // preserve the order of the terms, we don't match against any later positive
         // terms.
-        if let Some(right2) = hunk.get_add(add_index + 1) {
+        if let Some(right2) = hunk
+            .get_add(add_index + 1)
+            .filter(|_| !is_deleted(add_index + 1))
+        {
             let diff2 = Diff::by_line([&left, &right2]).hunks().collect_vec();
             if diff_size(&diff2) < diff_size(&diff1) {
                 // If the next positive term is a better match, emit the current positive term
@@ -624,6 +743,14 @@ fn materialize_jj_style_conflict(
     Ok(())
 }
 
+fn maybe_deleted_comment(deleted_sides: &[bool], add_index: usize) -> &'static str {
+    if deleted_sides.get(add_index).copied().unwrap_or(false) {
+        DELETED_COMMENT
+    } else {
+        ""
+    }
+}
+
 fn maybe_no_eol_comment(slice: &[u8]) -> &'static str {
     if has_no_eol(slice) {
         NO_EOL_COMMENT
@@ -684,6 +811,117 @@ pub fn materialized_diff_stream<'a>(
         .buffered((store.concurrency() / 2).max(1))
 }
 
+/// A conflicted region of a file, as returned by [`parse()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// The contents of each term of the conflict in this region.
+    pub contents: Merge<BString>,
+}
+
+impl ConflictHunk {
+    /// The number of sides of the conflict.
+    pub fn num_sides(&self) -> usize {
+        self.contents.num_sides()
+    }
+
+    /// The contents of the side at `index` (0-based), if any.
+    pub fn side(&self, index: usize) -> Option<&BString> {
+        self.contents.get_add(index)
+    }
+}
+
+/// How to resolve a single hunk with [`resolve_hunks()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HunkResolution {
+    /// Leave the hunk conflicted.
+    Unresolved,
+    /// Take the contents of the side at the given index (0-based).
+    Side(usize),
+    /// Replace the hunk with the given contents.
+    Contents(BString),
+}
+
+/// Error from [`resolve_hunks()`].
+#[derive(Debug, Error)]
+pub enum ResolveHunksError {
+    /// The number of resolutions doesn't match the number of conflicted hunks.
+    #[error("Expected {expected} hunk resolutions, got {actual}")]
+    WrongNumberOfResolutions { expected: usize, actual: usize },
+    /// A resolution refers to a side which doesn't exist.
+    #[error("Hunk {hunk} has no side #{}", side + 1)]
+    NoSuchSide { hunk: usize, side: usize },
+}
+
+/// Returns the conflicted hunks of a file conflict, in order.
+///
+/// The regions which merge cleanly are not included. Use [`resolve_hunks()`]
+/// to resolve the returned hunks individually.
+pub fn parse<T: AsRef<[u8]>>(file_conflict: &Merge<T>) -> Vec<ConflictHunk> {
+    match files::merge(file_conflict) {
+        MergeResult::Resolved(_) => vec![],
+        MergeResult::Conflict(hunks) => hunks
+            .into_iter()
+            .filter(|hunk| !hunk.is_resolved())
+            .map(|contents| ConflictHunk { contents })
+            .collect(),
+    }
+}
+
+/// Resolves the conflicted hunks of a file conflict, as returned by
+/// [`parse()`], with one resolution per hunk.
+///
+/// Returns the new contents of each term of the conflict. If all hunks are
+/// resolved, the result is resolved.
+pub fn resolve_hunks<T: AsRef<[u8]>>(
+    file_conflict: &Merge<T>,
+    resolutions: &[HunkResolution],
+) -> Result<Merge<BString>, ResolveHunksError> {
+    let hunks = match files::merge(file_conflict) {
+        MergeResult::Resolved(content) => vec![Merge::resolved(content)],
+        MergeResult::Conflict(hunks) => hunks,
+    };
+    let num_conflicts = hunks.iter().filter(|hunk| !hunk.is_resolved()).count();
+    if resolutions.len() != num_conflicts {
+        return Err(ResolveHunksError::WrongNumberOfResolutions {
+            expected: num_conflicts,
+            actual: resolutions.len(),
+        });
+    }
+    let mut contents = file_conflict.map(|_| BString::default());
+    let mut resolutions = resolutions.iter().enumerate();
+    for hunk in hunks {
+        let resolved = if let Some(slice) = hunk.as_resolved() {
+            Some(slice)
+        } else {
+            let (index, resolution) = resolutions.next().unwrap();
+            match resolution {
+                HunkResolution::Unresolved => None,
+                HunkResolution::Side(side) => {
+                    let slice = hunk.get_add(*side).ok_or(ResolveHunksError::NoSuchSide {
+                        hunk: index,
+                        side: *side,
+                    })?;
+                    Some(slice)
+                }
+                HunkResolution::Contents(slice) => Some(slice),
+            }
+        };
+        match resolved {
+            Some(slice) => {
+                for content in contents.iter_mut() {
+                    content.extend_from_slice(slice);
+                }
+            }
+            None => {
+                for (content, slice) in zip(contents.iter_mut(), hunk.iter()) {
+                    content.extend_from_slice(slice);
+                }
+            }
+        }
+    }
+    Ok(contents.simplify())
+}
+
 /// Parses conflict markers from a slice.
 ///
 /// Returns `None` if there were no valid conflict markers. The caller
@@ -909,15 +1147,13 @@ pub async fn update_from_content(
     // store. This is also a way of making sure that unchanged tree/file
     // conflicts (for example) are not converted to regular files in the working
     // copy.
-    let mut old_content = Vec::with_capacity(content.len());
     let merge_hunk = extract_as_single_hunk(&simplified_file_ids, store, path).await?;
-    materialize_merge_result_with_marker_len(
+    let old_content = materialize_file_conflict_to_bytes_with_marker_len(
+        &simplified_file_ids,
         &merge_hunk,
         conflict_marker_style,
         conflict_marker_len,
-        &mut old_content,
-    )
-    .unwrap();
+    );
     if content == old_content {
         return Ok(file_ids.clone());
     }
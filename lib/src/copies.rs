@@ -21,15 +21,93 @@ use std::task::Context;
 use std::task::Poll;
 
 use futures::Stream;
+use futures::StreamExt as _;
+use futures::TryStreamExt as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
 
+use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::CopyRecord;
+use crate::backend::FileId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::matchers::EverythingMatcher;
 use crate::merge::MergedTreeValue;
 use crate::merged_tree::MergedTree;
+use crate::merged_tree::TreeDiffEntry;
 use crate::merged_tree::TreeDiffStream;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 
+/// If the file at `path` doesn't exist in any parent of the `commit`, returns
+/// the parents and the paths the file was renamed from, as reported by the
+/// backend's copy records.
+pub fn find_rename_sources(
+    commit: &Commit,
+    path: &RepoPath,
+) -> BackendResult<Vec<(Commit, RepoPathBuf)>> {
+    let store = commit.store();
+    let parents: Vec<Commit> = commit.parents().try_collect()?;
+    for parent in &parents {
+        if parent.tree()?.path_value(path)?.is_present() {
+            return Ok(vec![]);
+        }
+    }
+    let paths = [path.to_owned()];
+    let mut sources = Vec::new();
+    for parent in parents {
+        let records: Vec<CopyRecord> = store
+            .get_copy_records(Some(&paths), parent.id(), commit.id())?
+            .try_collect()
+            .block_on()?;
+        if let Some(record) = records.into_iter().find(|record| record.target == paths[0]) {
+            sources.push((parent, record.source));
+        }
+    }
+    Ok(sources)
+}
+
+/// Finds the files which were renamed without changing their contents between
+/// `base` and `side`. Returns pairs of source and target paths.
+///
+/// Only renames whose contents uniquely identify both the source and the target
+/// are returned. Conflicted paths are ignored.
+pub fn find_exact_renames(
+    base: &MergedTree,
+    side: &MergedTree,
+) -> BackendResult<Vec<(RepoPathBuf, RepoPathBuf)>> {
+    let mut removed: HashMap<FileId, Vec<RepoPathBuf>> = HashMap::new();
+    let mut added: HashMap<FileId, Vec<RepoPathBuf>> = HashMap::new();
+    let mut diff_stream = base.diff_stream(side, &EverythingMatcher);
+    async {
+        while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+            let (before, after) = values?;
+            match (before.as_normal(), after.as_normal()) {
+                (Some(TreeValue::File { id, .. }), None) if after.is_absent() => {
+                    removed.entry(id.clone()).or_default().push(path);
+                }
+                (None, Some(TreeValue::File { id, .. })) if before.is_absent() => {
+                    added.entry(id.clone()).or_default().push(path);
+                }
+                _ => {}
+            }
+        }
+        Ok::<(), BackendError>(())
+    }
+    .block_on()?;
+    let renames = removed
+        .into_iter()
+        .filter_map(|(id, sources)| {
+            let [source] = sources.try_into().ok()?;
+            let [target] = added.remove(&id)?.try_into().ok()?;
+            Some((source, target))
+        })
+        .sorted()
+        .collect();
+    Ok(renames)
+}
+
 /// A collection of CopyRecords.
 #[derive(Default, Debug)]
 pub struct CopyRecords {
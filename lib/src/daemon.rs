@@ -0,0 +1,540 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A long-lived process which snapshots a workspace on behalf of other
+//! processes.
+//!
+//! Loading a repo and snapshotting a huge working copy from scratch is slow.
+//! A [`Daemon`] keeps a workspace and its repo loaded, including the warm
+//! object and index caches, and answers requests from short-lived clients
+//! over a Unix socket. Snapshots go through the configured fsmonitor, so the
+//! daemon only has to look at the files which changed since its last request.
+//! With Watchman, the daemon can also hold a subscription to the working copy
+//! and skip snapshots altogether while no files changed.
+//!
+//! Requests are served one at a time. Each request is a line of JSON, which is
+//! answered by a line of JSON. A client which doesn't send a request or read
+//! the response within the client timeout is disconnected, so it can't keep
+//! other clients waiting.
+
+use std::collections::HashMap;
+use std::fs::DirBuilder;
+use std::io;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::fs::DirBuilderExt as _;
+use std::os::unix::fs::MetadataExt as _;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use futures::StreamExt as _;
+use pollster::FutureExt as _;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::commit::Commit;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::eol::EolSettings;
+#[cfg(feature = "watchman")]
+use crate::fsmonitor::watchman;
+use crate::fsmonitor::FsmonitorSettings;
+use crate::git_lfs::LfsSettings;
+use crate::gitignore::GitIgnoreFile;
+use crate::matchers::EverythingMatcher;
+use crate::matchers::Matcher;
+use crate::object_id::ObjectId as _;
+use crate::op_store::OperationId;
+use crate::op_store::WorkspaceId;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo::RepoLoaderError;
+use crate::repo_path::PathNormalization;
+use crate::working_copy::SnapshotOptions;
+use crate::working_copy::WorkspaceSnapshotError;
+use crate::workspace::Workspace;
+
+/// Longest path which fits in the `sun_path` of a Unix socket address. It's
+/// 108 bytes on Linux and 104 bytes on macOS and the BSDs, including the
+/// terminating NUL.
+const MAX_SOCKET_PATH_LEN: usize = 103;
+
+/// How long a daemon waits for a client by default before disconnecting it.
+pub const DEFAULT_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the path of the socket a daemon for the workspace at
+/// `workspace_root` listens on.
+///
+/// The socket is in the `.jj` directory, unless that path is too long for a
+/// socket address. The socket is then named after a hash of `workspace_root`
+/// in `$XDG_RUNTIME_DIR`, or in a directory private to the current user in
+/// the temporary directory if that isn't set. The private directory is
+/// created if needed.
+pub fn socket_path(workspace_root: &Path) -> io::Result<PathBuf> {
+    let path = workspace_root.join(".jj").join("daemon.sock");
+    if path.as_os_str().len() <= MAX_SOCKET_PATH_LEN {
+        return Ok(path);
+    }
+    let runtime_dir = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => private_temp_dir()?,
+    };
+    let hash = Blake2b512::digest(workspace_root.as_os_str().as_bytes());
+    Ok(runtime_dir.join(format!("jj-daemon-{}.sock", hex::encode(&hash[..16]))))
+}
+
+/// Creates or opens the directory `jj-<uid>` in the temporary directory.
+///
+/// The temporary directory is shared with other users, who could otherwise
+/// bind the socket first and answer the requests. The directory is rejected
+/// unless it's owned by the current user and inaccessible to anyone else.
+fn private_temp_dir() -> io::Result<PathBuf> {
+    let uid = rustix::process::getuid().as_raw();
+    let dir = std::env::temp_dir().join(format!("jj-{uid}"));
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+    let metadata = dir.symlink_metadata()?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a directory private to the current user",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(dir)
+}
+
+/// A request sent to a daemon.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DaemonRequest {
+    /// Snapshot the working copy.
+    Snapshot,
+    /// Snapshot the working copy and list the paths changed in the
+    /// working-copy commit.
+    Status,
+    /// Stop serving requests.
+    Shutdown,
+}
+
+/// A daemon's answer to a `DaemonRequest`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DaemonResponse {
+    /// Answer to `DaemonRequest::Snapshot`.
+    Snapshot(SnapshotResponse),
+    /// Answer to `DaemonRequest::Status`.
+    Status(StatusResponse),
+    /// Answer to `DaemonRequest::Shutdown`.
+    Shutdown,
+    /// The request failed.
+    Error {
+        /// Description of the error.
+        message: String,
+    },
+}
+
+/// The state of the repo after a snapshot.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SnapshotResponse {
+    /// Hex id of the operation the working copy is at.
+    pub operation_id: String,
+    /// Hex id of the working-copy commit.
+    pub wc_commit_id: String,
+    /// Whether the snapshot created a new operation.
+    pub changed: bool,
+}
+
+/// The state of the working-copy commit after a snapshot.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StatusResponse {
+    /// Hex id of the operation the working copy is at.
+    pub operation_id: String,
+    /// Hex id of the working-copy commit.
+    pub wc_commit_id: String,
+    /// Paths changed in the working-copy commit compared to its parents, in
+    /// internal format.
+    pub changed_paths: Vec<String>,
+}
+
+/// An error while serving or sending a daemon request.
+#[derive(Debug, Error)]
+pub enum DaemonError {
+    /// Error communicating over the socket.
+    #[error("Failed to communicate with the daemon")]
+    Io(#[from] io::Error),
+    /// A message couldn't be encoded or decoded.
+    #[error("Invalid daemon message")]
+    Message(#[from] serde_json::Error),
+    /// The daemon answered with an error.
+    #[error("Daemon error: {0}")]
+    Remote(String),
+    /// The daemon answered with a response of the wrong type.
+    #[error("Unexpected daemon response")]
+    UnexpectedResponse,
+    /// Error loading the repo.
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+    /// Backend error.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Error snapshotting the working copy into the working-copy commit.
+    #[error(transparent)]
+    WorkspaceSnapshot(#[from] WorkspaceSnapshotError),
+    /// Working copy commit is missing.
+    #[error("\"{0:?}\" doesn't have a working-copy commit")]
+    WorkspaceMissingWorkingCopy(WorkspaceId),
+    /// Error subscribing to changes of the working copy.
+    #[cfg(feature = "watchman")]
+    #[error(transparent)]
+    Watchman(#[from] watchman::Error),
+}
+
+/// Settings used by a daemon to snapshot the working copy. These correspond
+/// to the fields of `SnapshotOptions`, but are owned by the daemon.
+pub struct DaemonOptions {
+    /// See `SnapshotOptions::base_ignores`.
+    pub base_ignores: Arc<GitIgnoreFile>,
+    /// See `SnapshotOptions::fsmonitor_settings`.
+    pub fsmonitor_settings: FsmonitorSettings,
+    /// See `SnapshotOptions::start_tracking_matcher`.
+    pub start_tracking_matcher: Box<dyn Matcher>,
+    /// See `SnapshotOptions::max_new_file_size`.
+    pub max_new_file_size: u64,
+    /// See `SnapshotOptions::conflict_marker_style`.
+    pub conflict_marker_style: ConflictMarkerStyle,
+    /// See `SnapshotOptions::path_normalization`.
+    pub path_normalization: PathNormalization,
+    /// See `SnapshotOptions::eol_settings`.
+    pub eol_settings: EolSettings,
+    /// See `SnapshotOptions::lfs_settings`.
+    pub lfs_settings: LfsSettings,
+    /// Tags recorded on the operations created by snapshots.
+    pub op_tags: HashMap<String, String>,
+    /// How long to wait for a client to send a request or read a response
+    /// before disconnecting it.
+    pub client_timeout: Duration,
+}
+
+impl DaemonOptions {
+    /// Create an instance for use in tests.
+    pub fn empty_for_test() -> Self {
+        let options = SnapshotOptions::empty_for_test();
+        DaemonOptions {
+            base_ignores: options.base_ignores,
+            fsmonitor_settings: options.fsmonitor_settings,
+            start_tracking_matcher: Box::new(EverythingMatcher),
+            max_new_file_size: options.max_new_file_size,
+            conflict_marker_style: options.conflict_marker_style,
+            path_normalization: options.path_normalization,
+            eol_settings: options.eol_settings,
+            lfs_settings: options.lfs_settings,
+            op_tags: HashMap::new(),
+            client_timeout: DEFAULT_CLIENT_TIMEOUT,
+        }
+    }
+
+    fn snapshot_options(&self) -> SnapshotOptions<'_> {
+        SnapshotOptions {
+            base_ignores: self.base_ignores.clone(),
+            fsmonitor_settings: self.fsmonitor_settings.clone(),
+            progress: None,
+            start_tracking_matcher: self.start_tracking_matcher.as_ref(),
+            max_new_file_size: self.max_new_file_size,
+            conflict_marker_style: self.conflict_marker_style,
+            path_normalization: self.path_normalization,
+            eol_settings: self.eol_settings.clone(),
+            lfs_settings: self.lfs_settings.clone(),
+        }
+    }
+}
+
+/// Watchman subscription to changes of the working copy.
+#[cfg(feature = "watchman")]
+struct WorkingCopyWatcher {
+    fsmonitor: watchman::Fsmonitor,
+    subscription: watchman::Subscription,
+    // The Watchman client runs on this runtime, so it must be dropped last.
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "watchman")]
+impl WorkingCopyWatcher {
+    fn take_changes(&mut self) -> Result<bool, watchman::Error> {
+        self.runtime
+            .block_on(self.fsmonitor.take_changes(&mut self.subscription))
+    }
+}
+
+/// Keeps a workspace loaded and snapshots it on request.
+pub struct Daemon {
+    workspace: Workspace,
+    repo: Arc<ReadonlyRepo>,
+    options: DaemonOptions,
+    #[cfg(feature = "watchman")]
+    watcher: Option<WorkingCopyWatcher>,
+    // Operation of the last snapshot, which can be reused while the repo and
+    // the working copy are unchanged.
+    last_snapshot_op_id: Option<OperationId>,
+}
+
+impl Daemon {
+    /// Creates a daemon for `workspace`, which was loaded with `repo`.
+    pub fn new(workspace: Workspace, repo: Arc<ReadonlyRepo>, options: DaemonOptions) -> Self {
+        Daemon {
+            workspace,
+            repo,
+            options,
+            #[cfg(feature = "watchman")]
+            watcher: None,
+            last_snapshot_op_id: None,
+        }
+    }
+
+    /// Subscribes to changes of the working copy if Watchman is the
+    /// configured fsmonitor. Requests are then answered without snapshotting
+    /// while no files changed and no other process updated the repo.
+    ///
+    /// Does nothing with other fsmonitors.
+    #[cfg(feature = "watchman")]
+    pub fn watch_working_copy(&mut self) -> Result<(), DaemonError> {
+        let FsmonitorSettings::Watchman(config) = &self.options.fsmonitor_settings else {
+            return Ok(());
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (fsmonitor, subscription) = runtime.block_on(async {
+            let fsmonitor =
+                watchman::Fsmonitor::init(self.workspace.workspace_root(), config).await?;
+            let subscription = fsmonitor.subscribe().await?;
+            Ok::<_, watchman::Error>((fsmonitor, subscription))
+        })?;
+        self.watcher = Some(WorkingCopyWatcher {
+            fsmonitor,
+            subscription,
+            runtime,
+        });
+        // Changes made before the subscription may not have been snapshotted.
+        self.last_snapshot_op_id = None;
+        Ok(())
+    }
+
+    /// The repo as of the last request.
+    pub fn repo(&self) -> &Arc<ReadonlyRepo> {
+        &self.repo
+    }
+
+    /// Serves requests from clients connecting to `listener` until a client
+    /// sends `DaemonRequest::Shutdown`.
+    ///
+    /// Errors communicating with a client, including timeouts, only end the
+    /// connection to that client.
+    pub fn serve(&mut self, listener: &UnixListener) -> Result<(), DaemonError> {
+        for stream in listener.incoming() {
+            match self.serve_connection(stream?) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(err) => tracing::warn!(?err, "failed to serve daemon client"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Serves requests on `stream` until the client disconnects. Returns true
+    /// if the client asked to shut down.
+    fn serve_connection(&mut self, stream: UnixStream) -> Result<bool, DaemonError> {
+        stream.set_read_timeout(Some(self.options.client_timeout))?;
+        stream.set_write_timeout(Some(self.options.client_timeout))?;
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => self.handle_request(&request),
+                Err(err) => DaemonResponse::Error {
+                    message: format!("Invalid request: {err}"),
+                },
+            };
+            write_message(&mut writer, &response)?;
+            if response == DaemonResponse::Shutdown {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Handles a single request.
+    pub fn handle_request(&mut self, request: &DaemonRequest) -> DaemonResponse {
+        let result = match request {
+            DaemonRequest::Snapshot => self.snapshot().map(DaemonResponse::Snapshot),
+            DaemonRequest::Status => self.status().map(DaemonResponse::Status),
+            DaemonRequest::Shutdown => Ok(DaemonResponse::Shutdown),
+        };
+        result.unwrap_or_else(|err| DaemonResponse::Error {
+            message: err.to_string(),
+        })
+    }
+
+    fn snapshot(&mut self) -> Result<SnapshotResponse, DaemonError> {
+        let old_op_id = self.repo.op_id().clone();
+        let wc_commit = self.snapshot_wc_commit()?;
+        Ok(SnapshotResponse {
+            operation_id: self.repo.op_id().hex(),
+            wc_commit_id: wc_commit.id().hex(),
+            changed: *self.repo.op_id() != old_op_id,
+        })
+    }
+
+    fn status(&mut self) -> Result<StatusResponse, DaemonError> {
+        let wc_commit = self.snapshot_wc_commit()?;
+        let parent_tree = wc_commit.parent_tree(self.repo.as_ref())?;
+        let tree = wc_commit.tree()?;
+        let mut changed_paths = vec![];
+        let mut diff_stream = parent_tree.diff_stream(&tree, &EverythingMatcher);
+        while let Some(entry) = diff_stream.next().block_on() {
+            entry.values?;
+            changed_paths.push(entry.path.as_internal_file_string().to_owned());
+        }
+        Ok(StatusResponse {
+            operation_id: self.repo.op_id().hex(),
+            wc_commit_id: wc_commit.id().hex(),
+            changed_paths,
+        })
+    }
+
+    /// Returns whether the working copy needs to be snapshotted again at the
+    /// current operation. Without a Watchman subscription, it always does.
+    #[cfg(feature = "watchman")]
+    fn needs_snapshot(&mut self) -> bool {
+        let Some(watcher) = &mut self.watcher else {
+            return true;
+        };
+        // Changes are taken even if the snapshot is needed anyway, so they
+        // aren't reported again by the next request.
+        match watcher.take_changes() {
+            Ok(changed) => changed || self.last_snapshot_op_id.as_ref() != Some(self.repo.op_id()),
+            Err(err) => {
+                tracing::warn!(
+                    ?err,
+                    "Watchman subscription failed; snapshotting every request"
+                );
+                self.watcher = None;
+                true
+            }
+        }
+    }
+
+    #[cfg(not(feature = "watchman"))]
+    fn needs_snapshot(&mut self) -> bool {
+        true
+    }
+
+    /// Snapshots the working copy at the head operation, committing a new
+    /// operation if it changed. Returns the resulting working-copy commit.
+    fn snapshot_wc_commit(&mut self) -> Result<Commit, DaemonError> {
+        // Other processes may have committed operations since the last request.
+        self.repo = self.repo.reload_at_head()?;
+        if !self.needs_snapshot() {
+            let workspace_id = self.workspace.workspace_id();
+            let wc_commit_id = self
+                .repo
+                .view()
+                .get_wc_commit_id(workspace_id)
+                .ok_or_else(|| DaemonError::WorkspaceMissingWorkingCopy(workspace_id.clone()))?;
+            return Ok(self.repo.store().get_commit(wc_commit_id)?);
+        }
+        self.last_snapshot_op_id = None;
+        let (repo, wc_commit, _stats) = self.workspace.snapshot(
+            self.repo.clone(),
+            &self.options.snapshot_options(),
+            &self.options.op_tags,
+        )?;
+        self.repo = repo;
+        self.last_snapshot_op_id = Some(self.repo.op_id().clone());
+        Ok(wc_commit)
+    }
+}
+
+fn write_message(writer: &mut impl io::Write, message: &impl Serialize) -> Result<(), DaemonError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A connection to a daemon.
+pub struct DaemonClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl DaemonClient {
+    /// Connects to the daemon listening on `socket_path`.
+    pub fn connect(socket_path: &Path) -> Result<Self, DaemonError> {
+        let writer = UnixStream::connect(socket_path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(DaemonClient { reader, writer })
+    }
+
+    /// Sends a request and waits for the response.
+    pub fn request(&mut self, request: &DaemonRequest) -> Result<DaemonResponse, DaemonError> {
+        write_message(&mut self.writer, request)?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        match serde_json::from_str(&line)? {
+            DaemonResponse::Error { message } => Err(DaemonError::Remote(message)),
+            response => Ok(response),
+        }
+    }
+
+    /// Asks the daemon to snapshot the working copy.
+    pub fn snapshot(&mut self) -> Result<SnapshotResponse, DaemonError> {
+        match self.request(&DaemonRequest::Snapshot)? {
+            DaemonResponse::Snapshot(response) => Ok(response),
+            _ => Err(DaemonError::UnexpectedResponse),
+        }
+    }
+
+    /// Asks the daemon to snapshot the working copy and list the changed
+    /// paths.
+    pub fn status(&mut self) -> Result<StatusResponse, DaemonError> {
+        match self.request(&DaemonRequest::Status)? {
+            DaemonResponse::Status(response) => Ok(response),
+            _ => Err(DaemonError::UnexpectedResponse),
+        }
+    }
+
+    /// Asks the daemon to stop serving requests.
+    pub fn shutdown(&mut self) -> Result<(), DaemonError> {
+        match self.request(&DaemonRequest::Shutdown)? {
+            DaemonResponse::Shutdown => Ok(()),
+            _ => Err(DaemonError::UnexpectedResponse),
+        }
+    }
+}
@@ -293,6 +293,11 @@ impl CompositeIndex {
         ancestor_pos: IndexPosition,
         descendant_pos: IndexPosition,
     ) -> bool {
+        // Entries are topologically ordered, so descendants are always indexed
+        // after their ancestors.
+        if ancestor_pos > descendant_pos {
+            return false;
+        }
         let ancestor_generation = self.entry_by_pos(ancestor_pos).generation_number();
         let mut work = vec![descendant_pos];
         let mut visited = HashSet::new();
@@ -307,7 +312,12 @@ impl CompositeIndex {
             if descendant_entry.generation_number() <= ancestor_generation {
                 continue;
             }
-            work.extend(descendant_entry.parent_positions());
+            work.extend(
+                descendant_entry
+                    .parent_positions()
+                    .into_iter()
+                    .filter(|&pos| pos >= ancestor_pos),
+            );
         }
         false
     }
@@ -45,6 +45,7 @@ pub use self::store::DefaultIndexStoreInitError;
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::sync::Arc;
 
     use itertools::Itertools;
@@ -61,6 +62,7 @@ mod tests {
     use crate::default_index::entry::LocalPosition;
     use crate::default_index::entry::SmallLocalPositionsVec;
     use crate::index::Index;
+    use crate::lock::LockMode;
     use crate::object_id::HexPrefix;
     use crate::object_id::ObjectId;
     use crate::object_id::PrefixResolution;
@@ -84,7 +86,9 @@ mod tests {
         let temp_dir = new_temp_dir();
         let mutable_segment = MutableIndexSegment::full(3, 16);
         let index_segment: Box<DynIndexSegment> = if on_disk {
-            let saved_index = mutable_segment.save_in(temp_dir.path()).unwrap();
+            let saved_index = mutable_segment
+                .save_in(temp_dir.path(), LockMode::Native)
+                .unwrap();
             Box::new(Arc::try_unwrap(saved_index).unwrap())
         } else {
             Box::new(mutable_segment)
@@ -115,7 +119,9 @@ mod tests {
         let change_id0 = new_change_id();
         mutable_segment.add_commit_data(id_0.clone(), change_id0.clone(), &[]);
         let index_segment: Box<DynIndexSegment> = if on_disk {
-            let saved_index = mutable_segment.save_in(temp_dir.path()).unwrap();
+            let saved_index = mutable_segment
+                .save_in(temp_dir.path(), LockMode::Native)
+                .unwrap();
             Box::new(Arc::try_unwrap(saved_index).unwrap())
         } else {
             Box::new(mutable_segment)
@@ -186,7 +192,9 @@ mod tests {
         // If testing incremental indexing, write the first three commits to one file
         // now and build the remainder as another segment on top.
         if incremental {
-            let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+            let initial_file = mutable_segment
+                .save_in(temp_dir.path(), LockMode::Native)
+                .unwrap();
             mutable_segment = MutableIndexSegment::incremental(initial_file);
         }
 
@@ -200,7 +208,9 @@ mod tests {
         mutable_segment.add_commit_data(id_4.clone(), change_id4, &[id_1.clone()]);
         mutable_segment.add_commit_data(id_5.clone(), change_id5, &[id_4.clone(), id_2.clone()]);
         let index_segment: Box<DynIndexSegment> = if on_disk {
-            let saved_index = mutable_segment.save_in(temp_dir.path()).unwrap();
+            let saved_index = mutable_segment
+                .save_in(temp_dir.path(), LockMode::Native)
+                .unwrap();
             Box::new(Arc::try_unwrap(saved_index).unwrap())
         } else {
             Box::new(mutable_segment)
@@ -312,7 +322,9 @@ mod tests {
             &[id_1, id_2, id_3, id_4, id_5],
         );
         let index_segment: Box<DynIndexSegment> = if on_disk {
-            let saved_index = mutable_segment.save_in(temp_dir.path()).unwrap();
+            let saved_index = mutable_segment
+                .save_in(temp_dir.path(), LockMode::Native)
+                .unwrap();
             Box::new(Arc::try_unwrap(saved_index).unwrap())
         } else {
             Box::new(mutable_segment)
@@ -358,7 +370,9 @@ mod tests {
         mutable_segment.add_commit_data(id_2.clone(), new_change_id(), &[]);
 
         // Write the first three commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file);
 
         let id_3 = CommitId::from_hex("055444");
@@ -430,7 +444,9 @@ mod tests {
         mutable_segment.add_commit_data(id_2.clone(), new_change_id(), &[]);
 
         // Write the first three commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file.clone());
 
         let id_3 = CommitId::from_hex("055444");
@@ -558,7 +574,9 @@ mod tests {
         mutable_segment.add_commit_data(id_2.clone(), new_change_id(), &[]);
 
         // Write the first three commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file);
 
         let id_3 = CommitId::from_hex("055444");
@@ -625,7 +643,9 @@ mod tests {
         mutable_segment.add_commit_data(new_commit_id(), id_2.clone(), &[]);
 
         // Write these commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file.clone());
 
         mutable_segment.add_commit_data(new_commit_id(), id_3.clone(), &[]);
@@ -789,7 +809,9 @@ mod tests {
         mutable_segment.add_commit_data(new_commit_id(), id_2.clone(), &[]);
 
         // Write these commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file.clone());
 
         mutable_segment.add_commit_data(new_commit_id(), id_3.clone(), &[]);
@@ -935,7 +957,9 @@ mod tests {
         mutable_segment.add_commit_data(new_commit_id(), id_2.clone(), &[]);
 
         // Write these commits to one file and build the remainder on top.
-        let initial_file = mutable_segment.save_in(temp_dir.path()).unwrap();
+        let initial_file = mutable_segment
+            .save_in(temp_dir.path(), LockMode::Native)
+            .unwrap();
         mutable_segment = MutableIndexSegment::incremental(initial_file.clone());
 
         mutable_segment.add_commit_data(new_commit_id(), id_3.clone(), &[]);
@@ -1222,4 +1246,82 @@ mod tests {
             vec![id_3.clone(), id_5.clone()]
         );
     }
+
+    #[test_case(false; "single segment")]
+    #[test_case(true; "incremental")]
+    fn test_is_ancestor_and_heads_merge_heavy(incremental: bool) {
+        let temp_dir = new_temp_dir();
+        let mut new_commit_id = commit_id_generator();
+        let mut new_change_id = change_id_generator();
+        // Deterministic pseudo-random numbers in 0..n
+        let mut seed = 1_u64;
+        let mut random = move |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+
+        // Most commits are merges of up to 3 earlier commits, so walks from a
+        // descendant reach many parents indexed before the ancestor, which
+        // are pruned.
+        let mut index = DefaultMutableIndex::full(16, 16);
+        let mut ids: Vec<CommitId> = vec![];
+        let mut ancestors: Vec<HashSet<usize>> = vec![];
+        for i in 0..64 {
+            if incremental && i == 32 {
+                let initial_file = index
+                    .squash_and_save_in(temp_dir.path(), LockMode::Native)
+                    .unwrap();
+                index = DefaultMutableIndex::incremental(initial_file);
+            }
+            let num_parents = (1 + random(3)).min(i);
+            let parents: Vec<usize> = (0..num_parents)
+                .map(|_| random(i))
+                .sorted()
+                .dedup()
+                .collect();
+            let mut commit_ancestors = HashSet::from([i]);
+            for &parent in &parents {
+                commit_ancestors.extend(&ancestors[parent]);
+            }
+            let id = new_commit_id();
+            let parent_ids = parents.iter().map(|&pos| ids[pos].clone()).collect_vec();
+            index.add_commit_data(id.clone(), new_change_id(), &parent_ids);
+            ids.push(id);
+            ancestors.push(commit_ancestors);
+        }
+
+        for (descendant, descendant_ancestors) in ancestors.iter().enumerate() {
+            for ancestor in 0..ids.len() {
+                assert_eq!(
+                    index.is_ancestor(&ids[ancestor], &ids[descendant]),
+                    descendant_ancestors.contains(&ancestor),
+                    "is_ancestor({ancestor}, {descendant})"
+                );
+            }
+        }
+
+        for _ in 0..32 {
+            let candidates = (0..1 + random(8))
+                .map(|_| random(ids.len()))
+                .sorted()
+                .dedup()
+                .collect_vec();
+            let expected_heads = candidates
+                .iter()
+                .filter(|&&pos| {
+                    candidates
+                        .iter()
+                        .all(|&other| other == pos || !ancestors[other].contains(&pos))
+                })
+                .map(|&pos| ids[pos].clone())
+                .sorted()
+                .collect_vec();
+            let heads = index
+                .heads(&mut candidates.iter().map(|&pos| &ids[pos]))
+                .unwrap();
+            assert_eq!(heads.into_iter().sorted().collect_vec(), expected_heads);
+        }
+    }
 }
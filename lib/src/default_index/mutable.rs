@@ -54,6 +54,7 @@ use crate::index::Index;
 use crate::index::IndexError;
 use crate::index::MutableIndex;
 use crate::index::ReadonlyIndex;
+use crate::lock::LockMode;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId;
 use crate::object_id::PrefixResolution;
@@ -348,7 +349,11 @@ impl MutableIndexSegment {
         squashed
     }
 
-    pub(super) fn save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
+    pub(super) fn save_in(
+        self,
+        dir: &Path,
+        lock_mode: LockMode,
+    ) -> io::Result<Arc<ReadonlyIndexSegment>> {
         if self.num_local_commits() == 0 && self.parent_file.is_some() {
             return Ok(self.parent_file.unwrap());
         }
@@ -366,7 +371,7 @@ impl MutableIndexSegment {
         let mut temp_file = NamedTempFile::new_in(dir)?;
         let file = temp_file.as_file_mut();
         file.write_all(&buf)?;
-        persist_content_addressed_temp_file(temp_file, index_file_path)?;
+        persist_content_addressed_temp_file(temp_file, index_file_path, lock_mode)?;
 
         Ok(ReadonlyIndexSegment::load_with_parent_file(
             &mut &buf[local_entries_offset..],
@@ -479,8 +484,12 @@ impl DefaultMutableIndex {
         self.0.add_commit_data(commit_id, change_id, parent_ids);
     }
 
-    pub(super) fn squash_and_save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
-        self.0.maybe_squash_with_ancestors().save_in(dir)
+    pub(super) fn squash_and_save_in(
+        self,
+        dir: &Path,
+        lock_mode: LockMode,
+    ) -> io::Result<Arc<ReadonlyIndexSegment>> {
+        self.0.maybe_squash_with_ancestors().save_in(dir, lock_mode)
     }
 }
 
@@ -1168,7 +1168,21 @@ fn build_predicate_fn(
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
-                Ok(pattern.matches(commit.description().lines().next().unwrap_or_default()))
+                Ok(pattern.matches(commit.subject()))
+            })
+        }
+        RevsetFilterPredicate::DescriptionTrailer { key, value } => {
+            let key = key.clone();
+            let value = value.clone();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                Ok(commit
+                    .trailers()
+                    .iter()
+                    .any(|(trailer_key, trailer_value)| {
+                        trailer_key.eq_ignore_ascii_case(&key) && value.matches(trailer_value)
+                    }))
             })
         }
         RevsetFilterPredicate::AuthorName(pattern) => {
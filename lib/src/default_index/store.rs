@@ -46,6 +46,7 @@ use crate::index::IndexStore;
 use crate::index::IndexWriteError;
 use crate::index::MutableIndex;
 use crate::index::ReadonlyIndex;
+use crate::lock::LockManager;
 use crate::object_id::ObjectId;
 use crate::op_store::OpStoreError;
 use crate::op_store::OperationId;
@@ -91,6 +92,7 @@ pub enum DefaultIndexStoreError {
 #[derive(Debug)]
 pub struct DefaultIndexStore {
     dir: PathBuf,
+    lock_manager: LockManager,
 }
 
 impl DefaultIndexStore {
@@ -101,6 +103,7 @@ impl DefaultIndexStore {
     pub fn init(dir: &Path) -> Result<Self, DefaultIndexStoreInitError> {
         let store = DefaultIndexStore {
             dir: dir.to_owned(),
+            lock_manager: LockManager::new(),
         };
         store.ensure_base_dirs()?;
         Ok(store)
@@ -109,9 +112,16 @@ impl DefaultIndexStore {
     pub fn load(dir: &Path) -> DefaultIndexStore {
         DefaultIndexStore {
             dir: dir.to_owned(),
+            lock_manager: LockManager::new(),
         }
     }
 
+    /// Sets the lock manager whose mode decides how index files are written.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
     pub fn reinit(&self) -> Result<(), DefaultIndexStoreInitError> {
         // Create base directories in case the store was initialized by old jj.
         self.ensure_base_dirs()?;
@@ -196,20 +206,26 @@ impl DefaultIndexStore {
             .map(|commit_id| (commit_id.clone(), operation.id().clone()))
             .collect();
         let mut parent_op_id: Option<OperationId> = None;
-        for op in dag_walk::dfs_ok(
+        let ops: Vec<Operation> = dag_walk::dfs_ok(
             [Ok(operation.clone())],
             |op: &Operation| op.id().clone(),
-            |op: &Operation| op.parents().collect_vec(),
-        ) {
-            let op = op?;
-            // Pick the latest existing ancestor operation as the parent
-            // segment. Perhaps, breadth-first search is more appropriate here,
-            // but that wouldn't matter in practice as the operation log is
-            // mostly linear.
-            if parent_op_id.is_none() && operations_dir.join(op.id().hex()).is_file() {
-                parent_op_id = Some(op.id().clone());
-            }
-            // TODO: no need to walk ancestors of the parent_op_id operation
+            |op: &Operation| {
+                // Pick the latest existing ancestor operation as the parent
+                // segment. Perhaps, breadth-first search is more appropriate
+                // here, but that wouldn't matter in practice as the operation
+                // log is mostly linear.
+                if parent_op_id.is_none() && operations_dir.join(op.id().hex()).is_file() {
+                    parent_op_id = Some(op.id().clone());
+                    // Commits referenced by the ancestors are already indexed
+                    // in the parent segment.
+                    vec![]
+                } else {
+                    op.parents().collect_vec()
+                }
+            },
+        )
+        .try_collect()?;
+        for op in &ops {
             for commit_id in op.view()?.all_referenced_commit_ids() {
                 if visited_heads.insert(commit_id.clone()) {
                     historical_heads.push((commit_id.clone(), op.id().clone()));
@@ -289,7 +305,7 @@ impl DefaultIndexStore {
         op_id: &OperationId,
     ) -> Result<Arc<ReadonlyIndexSegment>, DefaultIndexStoreError> {
         let index_segment = mutable_index
-            .squash_and_save_in(&self.segments_dir())
+            .squash_and_save_in(&self.segments_dir(), self.lock_manager.mode())
             .map_err(DefaultIndexStoreError::SaveIndex)?;
         self.associate_file_with_operation(&index_segment, op_id)
             .map_err(|source| DefaultIndexStoreError::AssociateIndex {
@@ -309,7 +325,11 @@ impl DefaultIndexStore {
         let mut temp_file = NamedTempFile::new_in(&dir)?;
         let file = temp_file.as_file_mut();
         file.write_all(index.name().as_bytes())?;
-        persist_content_addressed_temp_file(temp_file, dir.join(op_id.hex()))?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            dir.join(op_id.hex()),
+            self.lock_manager.mode(),
+        )?;
         Ok(())
     }
 }
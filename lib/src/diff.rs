@@ -29,6 +29,12 @@ use itertools::Itertools;
 use smallvec::smallvec;
 use smallvec::SmallVec;
 
+use crate::backend::BackendResult;
+use crate::commit::Commit;
+use crate::merged_tree::MergedTree;
+use crate::repo::Repo;
+use crate::rewrite::rebase_to_dest_parent;
+
 pub fn find_line_ranges(text: &[u8]) -> Vec<Range<usize>> {
     text.split_inclusive(|b| *b == b'\n')
         .scan(0, |total, line| {
@@ -1023,6 +1029,22 @@ pub fn diff<'a, T: AsRef<[u8]> + ?Sized + 'a>(
     diff.hunks().collect()
 }
 
+/// Returns the trees to compare to see what changed between two versions of
+/// a change, such as before and after it was amended and rebased.
+///
+/// The changes in `old_commit` are rebased onto the parents of `new_commit`
+/// first, so that differences between the parents aren't included. The
+/// rebased tree may contain conflicts if the changes don't apply cleanly.
+pub fn interdiff(
+    repo: &dyn Repo,
+    old_commit: &Commit,
+    new_commit: &Commit,
+) -> BackendResult<(MergedTree, MergedTree)> {
+    let old_tree = rebase_to_dest_parent(repo, slice::from_ref(old_commit), new_commit)?;
+    let new_tree = new_commit.tree()?;
+    Ok((old_tree, new_tree))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
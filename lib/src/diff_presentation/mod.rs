@@ -0,0 +1,101 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Utilities to present file diffs to the user.
+
+#![allow(missing_docs)]
+
+use std::io;
+
+use crate::diff::find_line_ranges;
+use crate::diff::CompareBytesExactly;
+use crate::diff::CompareBytesIgnoreAllWhitespace;
+use crate::diff::CompareBytesIgnoreWhitespaceAmount;
+use crate::diff::Diff;
+
+pub mod unified;
+
+/// File contents prepared for diffing.
+#[derive(Clone, Debug)]
+pub struct FileContent {
+    /// false if this file is likely text; true if it is likely binary.
+    pub is_binary: bool,
+    pub contents: Vec<u8>,
+}
+
+impl FileContent {
+    pub fn empty() -> Self {
+        Self {
+            is_binary: false,
+            contents: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+}
+
+/// Reads the contents of a file, and detects whether it's binary.
+pub fn file_content_for_diff(reader: &mut dyn io::Read) -> io::Result<FileContent> {
+    // If this is a binary file, don't show the full contents.
+    // Determine whether it's binary by whether the first 8k bytes contain a null
+    // character; this is the same heuristic used by git as of writing: https://github.com/git/git/blob/eea0e59ffbed6e33d171ace5be13cde9faa41639/xdiff-interface.c#L192-L198
+    const PEEK_SIZE: usize = 8000;
+    // TODO: currently we look at the whole file, even though for binary files we
+    // only need to know the file size. To change that we'd have to extend all
+    // the data backends to support getting the length.
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+
+    let start = &contents[..PEEK_SIZE.min(contents.len())];
+    Ok(FileContent {
+        is_binary: start.contains(&b'\0'),
+        contents,
+    })
+}
+
+/// How equivalence of lines is tested.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineCompareMode {
+    /// Compares lines literally.
+    #[default]
+    Exact,
+    /// Compares lines ignoring any whitespace occurrences.
+    IgnoreAllSpace,
+    /// Compares lines ignoring changes in whitespace amount.
+    IgnoreSpaceChange,
+}
+
+/// Diffs `inputs` line by line, comparing lines by `compare_mode`.
+pub fn diff_by_line<'input, T: AsRef<[u8]> + ?Sized + 'input>(
+    inputs: impl IntoIterator<Item = &'input T>,
+    compare_mode: LineCompareMode,
+) -> Diff<'input> {
+    // TODO: If we add --ignore-blank-lines, its tokenizer will have to attach
+    // blank lines to the preceding range. Maybe it can also be implemented as a
+    // post-process (similar to refine_changed_regions()) that expands unchanged
+    // regions across blank lines.
+    match compare_mode {
+        LineCompareMode::Exact => {
+            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesExactly)
+        }
+        LineCompareMode::IgnoreAllSpace => {
+            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreAllWhitespace)
+        }
+        LineCompareMode::IgnoreSpaceChange => {
+            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreWhitespaceAmount)
+        }
+    }
+}
@@ -0,0 +1,446 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Unified and Git-style diffs.
+
+#![allow(missing_docs)]
+
+use std::borrow::Borrow;
+use std::io;
+use std::io::Write as _;
+use std::mem;
+use std::ops::Range;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use super::diff_by_line;
+use super::file_content_for_diff;
+use super::FileContent;
+use super::LineCompareMode;
+use crate::backend::BackendError;
+use crate::conflicts::materialize_merge_result_to_bytes;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::conflicts::MaterializedTreeValue;
+use crate::copies::CopiesTreeDiffEntryPath;
+use crate::copies::CopyOperation;
+use crate::diff::Diff;
+use crate::diff::DiffHunk;
+use crate::diff::DiffHunkKind;
+use crate::object_id::ObjectId as _;
+use crate::repo_path::RepoPath;
+
+/// Error while preparing a Git-style diff.
+#[derive(Debug, Error)]
+pub enum UnifiedDiffError {
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error("Access denied to {path}")]
+    AccessDenied {
+        path: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Neither side of the diff of {path} is present")]
+    BothSidesAbsent { path: String },
+}
+
+/// One side of a Git-style diff of a file.
+pub struct GitDiffPart {
+    /// Octal mode string or `None` if the file is absent.
+    pub mode: Option<&'static str>,
+    /// Full hex of the object id, or zeros if there is no object.
+    pub hash: String,
+    pub content: FileContent,
+}
+
+/// Prepares the materialized `value` at `path` for Git-style diffing.
+pub fn git_diff_part(
+    path: &RepoPath,
+    value: MaterializedTreeValue,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> Result<GitDiffPart, UnifiedDiffError> {
+    const DUMMY_HASH: &str = "0000000000000000000000000000000000000000";
+    let mode;
+    let hash;
+    let content;
+    match value {
+        MaterializedTreeValue::Absent => {
+            return Ok(GitDiffPart {
+                mode: None,
+                hash: DUMMY_HASH.to_owned(),
+                content: FileContent::empty(),
+            });
+        }
+        MaterializedTreeValue::AccessDenied(err) => {
+            return Err(UnifiedDiffError::AccessDenied {
+                path: path.as_internal_file_string().to_owned(),
+                source: err,
+            });
+        }
+        MaterializedTreeValue::File {
+            id,
+            executable,
+            mut reader,
+        } => {
+            mode = if executable { "100755" } else { "100644" };
+            hash = id.hex();
+            content = file_content_for_diff(&mut reader)?;
+        }
+        MaterializedTreeValue::Symlink { id, target } => {
+            mode = "120000";
+            hash = id.hex();
+            content = FileContent {
+                // Unix file paths can't contain null bytes.
+                is_binary: false,
+                contents: target.into_bytes(),
+            };
+        }
+        MaterializedTreeValue::GitSubmodule(id) => {
+            // TODO: What should we actually do here?
+            mode = "040000";
+            hash = id.hex();
+            content = FileContent::empty();
+        }
+        MaterializedTreeValue::FileConflict {
+            id: _,
+            contents,
+            executable,
+        } => {
+            mode = if executable { "100755" } else { "100644" };
+            hash = DUMMY_HASH.to_owned();
+            content = FileContent {
+                is_binary: false, // TODO: are we sure this is never binary?
+                contents: materialize_merge_result_to_bytes(&contents, conflict_marker_style)
+                    .into(),
+            };
+        }
+        MaterializedTreeValue::OtherConflict { id } => {
+            mode = "100644";
+            hash = DUMMY_HASH.to_owned();
+            content = FileContent {
+                is_binary: false,
+                contents: id.describe().into_bytes(),
+            };
+        }
+        MaterializedTreeValue::Tree(_) => {
+            panic!("Unexpected tree in diff at path {path:?}");
+        }
+    }
+    Ok(GitDiffPart {
+        mode: Some(mode),
+        hash,
+        content,
+    })
+}
+
+/// Writes the `diff --git` line and the extended header lines of a Git-style
+/// diff of a file. Object ids are abbreviated unless `full_index` is set.
+pub fn write_git_diff_header(
+    out: &mut dyn io::Write,
+    path: &CopiesTreeDiffEntryPath,
+    left_part: &GitDiffPart,
+    right_part: &GitDiffPart,
+    full_index: bool,
+) -> Result<(), UnifiedDiffError> {
+    let left_path_string = path.source().as_internal_file_string();
+    let right_path_string = path.target().as_internal_file_string();
+    let abbreviate = |hash: &str| -> String {
+        if full_index {
+            hash.to_owned()
+        } else {
+            hash.chars().take(10).collect()
+        }
+    };
+    let left_hash = abbreviate(&left_part.hash);
+    let right_hash = abbreviate(&right_part.hash);
+    writeln!(out, "diff --git a/{left_path_string} b/{right_path_string}")?;
+    match (left_part.mode, right_part.mode) {
+        (None, Some(right_mode)) => {
+            writeln!(out, "new file mode {right_mode}")?;
+            writeln!(out, "index {left_hash}..{right_hash}")?;
+        }
+        (Some(left_mode), None) => {
+            writeln!(out, "deleted file mode {left_mode}")?;
+            writeln!(out, "index {left_hash}..{right_hash}")?;
+        }
+        (Some(left_mode), Some(right_mode)) => {
+            if let Some(op) = path.copy_operation() {
+                let operation = match op {
+                    CopyOperation::Copy => "copy",
+                    CopyOperation::Rename => "rename",
+                };
+                // TODO: include similarity index?
+                writeln!(out, "{operation} from {left_path_string}")?;
+                writeln!(out, "{operation} to {right_path_string}")?;
+            }
+            if left_mode != right_mode {
+                writeln!(out, "old mode {left_mode}")?;
+                writeln!(out, "new mode {right_mode}")?;
+                if left_hash != right_hash {
+                    writeln!(out, "index {left_hash}..{right_hash}")?;
+                }
+            } else if left_hash != right_hash {
+                writeln!(out, "index {left_hash}..{right_hash} {left_mode}")?;
+            }
+        }
+        (None, None) => {
+            return Err(UnifiedDiffError::BothSidesAbsent {
+                path: right_path_string.to_owned(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the paths of the `---` and `+++` lines of a Git-style diff of a
+/// file, which are `/dev/null` for an absent side.
+pub fn git_diff_file_paths(
+    path: &CopiesTreeDiffEntryPath,
+    left_part: &GitDiffPart,
+    right_part: &GitDiffPart,
+) -> (String, String) {
+    let left_path = match left_part.mode {
+        Some(_) => format!("a/{}", path.source().as_internal_file_string()),
+        None => "/dev/null".to_owned(),
+    };
+    let right_path = match right_part.mode {
+        Some(_) => format!("b/{}", path.target().as_internal_file_string()),
+        None => "/dev/null".to_owned(),
+    };
+    (left_path, right_path)
+}
+
+/// Writes a `GIT binary patch` which replaces the `left` contents with the
+/// `right` contents, followed by the reverse patch, as `git diff --binary`
+/// does.
+pub fn write_git_binary_patch(
+    out: &mut dyn io::Write,
+    left: &[u8],
+    right: &[u8],
+) -> io::Result<()> {
+    writeln!(out, "GIT binary patch")?;
+    write_git_binary_literal(out, right)?;
+    write_git_binary_literal(out, left)?;
+    Ok(())
+}
+
+fn write_git_binary_literal(out: &mut dyn io::Write, data: &[u8]) -> io::Result<()> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    writeln!(out, "literal {}", data.len())?;
+    for chunk in compressed.chunks(52) {
+        let len_char = match chunk.len() {
+            len @ 1..=26 => b'A' + (len - 1) as u8,
+            len => b'a' + (len - 27) as u8,
+        };
+        out.write_all(&[len_char])?;
+        out.write_all(&encode_base85(chunk))?;
+        writeln!(out)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Encodes `data` in the base85 alphabet of Git binary patches. The last group
+/// of 4 bytes is padded with zeros.
+fn encode_base85(data: &[u8]) -> Vec<u8> {
+    const ALPHABET: &[u8] =
+        b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(bytes);
+        let mut digits = [0; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        encoded.extend(digits);
+    }
+    encoded
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiffLineType {
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiffTokenType {
+    Matching,
+    Different,
+}
+
+pub type DiffTokenVec<'content> = Vec<(DiffTokenType, &'content [u8])>;
+
+/// Lines of a unified diff hunk and the line ranges they cover.
+pub struct UnifiedDiffHunk<'content> {
+    pub left_line_range: Range<usize>,
+    pub right_line_range: Range<usize>,
+    pub lines: Vec<(DiffLineType, DiffTokenVec<'content>)>,
+}
+
+impl<'content> UnifiedDiffHunk<'content> {
+    fn extend_context_lines(&mut self, lines: impl IntoIterator<Item = &'content [u8]>) {
+        let old_len = self.lines.len();
+        self.lines.extend(lines.into_iter().map(|line| {
+            let tokens = vec![(DiffTokenType::Matching, line)];
+            (DiffLineType::Context, tokens)
+        }));
+        self.left_line_range.end += self.lines.len() - old_len;
+        self.right_line_range.end += self.lines.len() - old_len;
+    }
+
+    fn extend_removed_lines(&mut self, lines: impl IntoIterator<Item = DiffTokenVec<'content>>) {
+        let old_len = self.lines.len();
+        self.lines
+            .extend(lines.into_iter().map(|line| (DiffLineType::Removed, line)));
+        self.left_line_range.end += self.lines.len() - old_len;
+    }
+
+    fn extend_added_lines(&mut self, lines: impl IntoIterator<Item = DiffTokenVec<'content>>) {
+        let old_len = self.lines.len();
+        self.lines
+            .extend(lines.into_iter().map(|line| (DiffLineType::Added, line)));
+        self.right_line_range.end += self.lines.len() - old_len;
+    }
+}
+
+/// Splits the diff of `left_content` and `right_content` into hunks with
+/// `context` lines of context.
+pub fn unified_diff_hunks<'content>(
+    left_content: &'content [u8],
+    right_content: &'content [u8],
+    context: usize,
+    compare_mode: LineCompareMode,
+) -> Vec<UnifiedDiffHunk<'content>> {
+    let mut hunks = vec![];
+    let mut current_hunk = UnifiedDiffHunk {
+        left_line_range: 0..0,
+        right_line_range: 0..0,
+        lines: vec![],
+    };
+    let diff = diff_by_line([left_content, right_content], compare_mode);
+    let mut diff_hunks = diff.hunks().peekable();
+    while let Some(hunk) = diff_hunks.next() {
+        match hunk.kind {
+            DiffHunkKind::Matching => {
+                // Just use the right (i.e. new) content. We could count the
+                // number of skipped lines separately, but the number of the
+                // context lines should match the displayed content.
+                let [_, right] = hunk.contents[..].try_into().unwrap();
+                let mut lines = right.split_inclusive(|b| *b == b'\n').fuse();
+                if !current_hunk.lines.is_empty() {
+                    // The previous hunk line should be either removed/added.
+                    current_hunk.extend_context_lines(lines.by_ref().take(context));
+                }
+                let before_lines = if diff_hunks.peek().is_some() {
+                    lines.by_ref().rev().take(context).collect()
+                } else {
+                    vec![] // No more hunks
+                };
+                let num_skip_lines = lines.count();
+                if num_skip_lines > 0 {
+                    let left_start = current_hunk.left_line_range.end + num_skip_lines;
+                    let right_start = current_hunk.right_line_range.end + num_skip_lines;
+                    if !current_hunk.lines.is_empty() {
+                        hunks.push(current_hunk);
+                    }
+                    current_hunk = UnifiedDiffHunk {
+                        left_line_range: left_start..left_start,
+                        right_line_range: right_start..right_start,
+                        lines: vec![],
+                    };
+                }
+                // The next hunk should be of DiffHunk::Different type if any.
+                current_hunk.extend_context_lines(before_lines.into_iter().rev());
+            }
+            DiffHunkKind::Different => {
+                let (left_lines, right_lines) =
+                    unzip_diff_hunks_to_lines(Diff::by_word(hunk.contents).hunks());
+                current_hunk.extend_removed_lines(left_lines);
+                current_hunk.extend_added_lines(right_lines);
+            }
+        }
+    }
+    if !current_hunk.lines.is_empty() {
+        hunks.push(current_hunk);
+    }
+    hunks
+}
+
+/// Splits `(left, right)` hunk pairs into `(left_lines, right_lines)`.
+pub fn unzip_diff_hunks_to_lines<'content, I>(
+    diff_hunks: I,
+) -> (Vec<DiffTokenVec<'content>>, Vec<DiffTokenVec<'content>>)
+where
+    I: IntoIterator,
+    I::Item: Borrow<DiffHunk<'content>>,
+{
+    let mut left_lines: Vec<DiffTokenVec<'content>> = vec![];
+    let mut right_lines: Vec<DiffTokenVec<'content>> = vec![];
+    let mut left_tokens: DiffTokenVec<'content> = vec![];
+    let mut right_tokens: DiffTokenVec<'content> = vec![];
+
+    for hunk in diff_hunks {
+        let hunk = hunk.borrow();
+        match hunk.kind {
+            DiffHunkKind::Matching => {
+                // TODO: add support for unmatched contexts
+                debug_assert!(hunk.contents.iter().all_equal());
+                for token in hunk.contents[0].split_inclusive(|b| *b == b'\n') {
+                    left_tokens.push((DiffTokenType::Matching, token));
+                    right_tokens.push((DiffTokenType::Matching, token));
+                    if token.ends_with(b"\n") {
+                        left_lines.push(mem::take(&mut left_tokens));
+                        right_lines.push(mem::take(&mut right_tokens));
+                    }
+                }
+            }
+            DiffHunkKind::Different => {
+                let [left, right] = hunk.contents[..]
+                    .try_into()
+                    .expect("hunk should have exactly two inputs");
+                for token in left.split_inclusive(|b| *b == b'\n') {
+                    left_tokens.push((DiffTokenType::Different, token));
+                    if token.ends_with(b"\n") {
+                        left_lines.push(mem::take(&mut left_tokens));
+                    }
+                }
+                for token in right.split_inclusive(|b| *b == b'\n') {
+                    right_tokens.push((DiffTokenType::Different, token));
+                    if token.ends_with(b"\n") {
+                        right_lines.push(mem::take(&mut right_tokens));
+                    }
+                }
+            }
+        }
+    }
+
+    if !left_tokens.is_empty() {
+        left_lines.push(left_tokens);
+    }
+    if !right_tokens.is_empty() {
+        right_lines.push(right_tokens);
+    }
+    (left_lines, right_lines)
+}
@@ -0,0 +1,193 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Line ending conversion between the working copy and the store.
+
+use std::borrow::Cow;
+
+use bstr::ByteSlice as _;
+use serde::Deserialize;
+
+use crate::attributes::FileAttributes;
+use crate::config::ConfigGetError;
+use crate::repo_path::RepoPath;
+use crate::settings::UserSettings;
+
+/// How line endings are converted between the working copy and the store.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EolConversionMode {
+    /// Files are stored as they are on disk.
+    #[default]
+    None,
+    /// CRLF line endings are converted to LF when snapshotting. Files are
+    /// checked out as stored.
+    Input,
+    /// CRLF line endings are converted to LF when snapshotting, and LF line
+    /// endings are converted to CRLF when checking out.
+    InputOutput,
+}
+
+impl EolConversionMode {
+    /// Converts the contents of a file on disk to the contents to store.
+    pub fn convert_input(self, contents: &[u8]) -> Cow<'_, [u8]> {
+        match self {
+            Self::None => Cow::Borrowed(contents),
+            Self::Input | Self::InputOutput => {
+                if is_binary(contents) || !contents.contains_str(b"\r\n") {
+                    Cow::Borrowed(contents)
+                } else {
+                    Cow::Owned(contents.replace(b"\r\n", b"\n"))
+                }
+            }
+        }
+    }
+
+    /// Converts stored contents to the contents to write to disk.
+    pub fn convert_output(self, contents: &[u8]) -> Cow<'_, [u8]> {
+        match self {
+            Self::None | Self::Input => Cow::Borrowed(contents),
+            Self::InputOutput => {
+                if is_binary(contents) || !contents.contains(&b'\n') {
+                    return Cow::Borrowed(contents);
+                }
+                let mut converted = Vec::with_capacity(contents.len() + contents.len() / 16);
+                let mut prev = None;
+                for &byte in contents {
+                    if byte == b'\n' && prev != Some(b'\r') {
+                        converted.push(b'\r');
+                    }
+                    converted.push(byte);
+                    prev = Some(byte);
+                }
+                Cow::Owned(converted)
+            }
+        }
+    }
+}
+
+/// Files which contain a NUL byte within the first 8000 bytes are considered
+/// binary, like Git does, and their line endings are never converted.
+fn is_binary(contents: &[u8]) -> bool {
+    contents[..contents.len().min(8000)].contains(&0)
+}
+
+/// Line ending conversion settings of a working copy, with per-path
+/// overrides from the `eol` file attribute.
+#[derive(Clone, Debug, Default)]
+pub struct EolSettings {
+    default_mode: EolConversionMode,
+    attributes: FileAttributes,
+}
+
+impl EolSettings {
+    /// Creates settings which use `default_mode` for all paths.
+    pub fn new(default_mode: EolConversionMode) -> Self {
+        EolSettings {
+            default_mode,
+            attributes: FileAttributes::empty(),
+        }
+    }
+
+    /// Reads `working-copy.eol-conversion` and the `eol` attribute of the
+    /// `[attributes]` table.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(EolSettings {
+            default_mode: settings.get("working-copy.eol-conversion")?,
+            attributes: FileAttributes::from_settings(settings)?,
+        })
+    }
+
+    /// Uses the `eol` attribute of `attributes` to override the default
+    /// mode.
+    pub fn with_attributes(mut self, attributes: FileAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Returns the conversion mode for `path`.
+    pub fn mode_for(&self, path: &RepoPath) -> EolConversionMode {
+        self.attributes.eol(path).unwrap_or(self.default_mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::AttributeValues;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    #[test]
+    fn test_convert_input() {
+        let mode = EolConversionMode::Input;
+        assert_eq!(mode.convert_input(b"a\r\nb\r\n"), &b"a\nb\n"[..]);
+        assert_eq!(mode.convert_input(b"a\nb\r\nc\r"), &b"a\nb\nc\r"[..]);
+        assert_eq!(mode.convert_input(b"a\r\n\0"), &b"a\r\n\0"[..]);
+        assert_eq!(
+            EolConversionMode::None.convert_input(b"a\r\n"),
+            &b"a\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_convert_output() {
+        let mode = EolConversionMode::InputOutput;
+        assert_eq!(mode.convert_output(b"a\nb\n"), &b"a\r\nb\r\n"[..]);
+        assert_eq!(mode.convert_output(b"a\r\nb\n"), &b"a\r\nb\r\n"[..]);
+        assert_eq!(mode.convert_output(b"\na"), &b"\r\na"[..]);
+        assert_eq!(mode.convert_output(b"a\n\0"), &b"a\n\0"[..]);
+        assert_eq!(EolConversionMode::Input.convert_output(b"a\n"), &b"a\n"[..]);
+    }
+
+    #[test]
+    fn test_mode_for() {
+        let eol = |mode| AttributeValues {
+            eol: Some(mode),
+            ..Default::default()
+        };
+        let attributes = FileAttributes::empty()
+            .with_rule("*.sh", eol(EolConversionMode::None))
+            .unwrap()
+            .with_rule("scripts/*.bat", eol(EolConversionMode::Input))
+            .unwrap()
+            .with_rule("*.bat", eol(EolConversionMode::None))
+            .unwrap()
+            .with_rule("*.png", AttributeValues::default())
+            .unwrap();
+        let settings = EolSettings::new(EolConversionMode::InputOutput).with_attributes(attributes);
+        assert_eq!(
+            settings.mode_for(repo_path("file.txt")),
+            EolConversionMode::InputOutput
+        );
+        assert_eq!(
+            settings.mode_for(repo_path("image.png")),
+            EolConversionMode::InputOutput
+        );
+        assert_eq!(
+            settings.mode_for(repo_path("dir/build.sh")),
+            EolConversionMode::None
+        );
+        assert_eq!(
+            settings.mode_for(repo_path("scripts/run.bat")),
+            EolConversionMode::Input
+        );
+        assert_eq!(
+            settings.mode_for(repo_path("scripts/sub/run.bat")),
+            EolConversionMode::None
+        );
+    }
+}
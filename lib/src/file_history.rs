@@ -0,0 +1,75 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! History of a single file across renames.
+
+use std::collections::HashSet;
+
+use crate::backend::CommitId;
+use crate::commit::Commit;
+use crate::copies::find_rename_sources;
+use crate::fileset::FilesetExpression;
+use crate::repo::Repo;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+use crate::revset::RevsetFilterPredicate;
+
+/// Commit which changed the file, and the path of the file at that commit.
+#[derive(Clone, Debug)]
+pub struct FileHistoryEntry {
+    /// Commit which changed the file.
+    pub commit: Commit,
+    /// Path of the file at the commit.
+    pub path: RepoPathBuf,
+}
+
+/// Returns the ancestors of `from_commit` (inclusive) which changed the file
+/// at `path`, following renames reported by the backend's copy records.
+///
+/// Commits are found by the `files()` revset predicate, which skips subtrees
+/// with unchanged ids. The commits with the same path are in reverse
+/// topological order, and followed by the commits before the file was
+/// renamed.
+pub fn file_history(
+    repo: &dyn Repo,
+    from_commit: &Commit,
+    path: &RepoPath,
+) -> Result<Vec<FileHistoryEntry>, RevsetEvaluationError> {
+    let mut entries = Vec::new();
+    let mut visited: HashSet<(CommitId, RepoPathBuf)> = HashSet::new();
+    let mut to_visit = vec![(from_commit.id().clone(), path.to_owned())];
+    while let Some((start_id, path)) = to_visit.pop() {
+        if !visited.insert((start_id.clone(), path.clone())) {
+            continue;
+        }
+        let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(path.clone()));
+        let revset = RevsetExpression::commit(start_id)
+            .ancestors()
+            .filtered(predicate)
+            .evaluate(repo)?;
+        for commit_id in revset.iter() {
+            let commit = repo.store().get_commit(&commit_id?)?;
+            for (parent, source_path) in find_rename_sources(&commit, &path)? {
+                to_visit.push((parent.id().clone(), source_path));
+            }
+            entries.push(FileHistoryEntry {
+                commit,
+                path: path.clone(),
+            });
+        }
+    }
+    Ok(entries)
+}
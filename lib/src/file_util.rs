@@ -26,6 +26,7 @@ use tempfile::PersistError;
 use thiserror::Error;
 
 pub use self::platform::*;
+use crate::lock::LockMode;
 
 #[derive(Debug, Error)]
 #[error("Cannot access {path}")]
@@ -132,15 +133,17 @@ pub fn normalize_path(path: &Path) -> PathBuf {
 }
 
 /// Like `NamedTempFile::persist()`, but doesn't try to overwrite the existing
-/// target on Windows.
+/// target on Windows, or in the NFS `lock_mode`.
 pub fn persist_content_addressed_temp_file<P: AsRef<Path>>(
     temp_file: NamedTempFile,
     new_path: P,
+    lock_mode: LockMode,
 ) -> io::Result<File> {
-    if cfg!(windows) {
+    if cfg!(windows) || lock_mode == LockMode::Nfs {
         // On Windows, overwriting file can fail if the file is opened without
-        // FILE_SHARE_DELETE for example. We don't need to take a risk if the
-        // file already exists.
+        // FILE_SHARE_DELETE for example. On NFS, replacing a file which another
+        // client has open may leave that client with a stale file handle. We
+        // don't need to take a risk if the file already exists.
         match temp_file.persist_noclobber(&new_path) {
             Ok(file) => Ok(file),
             Err(PersistError { error, file: _ }) => {
@@ -237,12 +240,14 @@ mod tests {
         let target = temp_dir.path().join("file");
         let mut temp_file = NamedTempFile::new_in(&temp_dir).unwrap();
         temp_file.write_all(b"contents").unwrap();
-        assert!(persist_content_addressed_temp_file(temp_file, target).is_ok());
+        assert!(persist_content_addressed_temp_file(temp_file, target, LockMode::Native).is_ok());
     }
 
-    #[test_case(false ; "existing file open")]
-    #[test_case(true ; "existing file closed")]
-    fn test_persist_target_exists(existing_file_closed: bool) {
+    #[test_case(false, LockMode::Native ; "existing file open")]
+    #[test_case(true, LockMode::Native ; "existing file closed")]
+    #[test_case(false, LockMode::Nfs ; "existing file open nfs")]
+    #[test_case(true, LockMode::Nfs ; "existing file closed nfs")]
+    fn test_persist_target_exists(existing_file_closed: bool, lock_mode: LockMode) {
         let temp_dir = new_temp_dir();
         let target = temp_dir.path().join("file");
         let mut temp_file = NamedTempFile::new_in(&temp_dir).unwrap();
@@ -254,6 +259,6 @@ mod tests {
             drop(file);
         }
 
-        assert!(persist_content_addressed_temp_file(temp_file, &target).is_ok());
+        assert!(persist_content_addressed_temp_file(temp_file, &target, lock_mode).is_ok());
     }
 }
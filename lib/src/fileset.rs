@@ -37,6 +37,7 @@ use crate::fileset_parser::UnaryOp;
 use crate::matchers::DifferenceMatcher;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::FileGlobsMatcher;
+use crate::matchers::FileRegexMatcher;
 use crate::matchers::FilesMatcher;
 use crate::matchers::IntersectionMatcher;
 use crate::matchers::Matcher;
@@ -64,6 +65,9 @@ pub enum FilePatternParseError {
     /// Failed to parse glob pattern.
     #[error(transparent)]
     GlobPattern(#[from] glob::PatternError),
+    /// Failed to parse regular expression.
+    #[error(transparent)]
+    RegexPattern(#[from] regex::Error),
 }
 
 /// Basic pattern to match `RepoPath`.
@@ -80,6 +84,16 @@ pub enum FilePattern {
         /// Glob pattern relative to `dir`.
         pattern: glob::Pattern,
     },
+    /// Matches file (or exact) path with case-insensitive glob pattern.
+    FileGlobI {
+        /// Prefix directory path where the `pattern` will be evaluated. This
+        /// path is matched case-sensitively.
+        dir: RepoPathBuf,
+        /// Glob pattern relative to `dir`.
+        pattern: glob::Pattern,
+    },
+    /// Matches workspace-relative file path with regular expression.
+    FileRegex(regex::Regex),
     // TODO: add more patterns:
     // - FilesInPath: files in directory, non-recursively?
     // - NameGlob or SuffixGlob: file name with glob?
@@ -105,14 +119,18 @@ impl FilePattern {
         // * string pattern syntax (+ case sensitivity?)
         //   * path: literal path (default) (default anchor: prefix)
         //   * glob: glob pattern (default anchor: file)
-        //   * regex?
+        //   * regex: regular expression (root only, unanchored)
+        // * "-i" suffix: case-insensitive
         match kind {
             "cwd" => Self::cwd_prefix_path(path_converter, input),
             "cwd-file" | "file" => Self::cwd_file_path(path_converter, input),
             "cwd-glob" | "glob" => Self::cwd_file_glob(path_converter, input),
+            "cwd-glob-i" | "glob-i" => Self::cwd_file_glob_i(path_converter, input),
             "root" => Self::root_prefix_path(input),
             "root-file" => Self::root_file_path(input),
             "root-glob" => Self::root_file_glob(input),
+            "root-glob-i" => Self::root_file_glob_i(input),
+            "root-regex" => Self::root_file_regex(input),
             _ => Err(FilePatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -145,6 +163,18 @@ impl FilePattern {
         Self::file_glob_at(dir, pattern)
     }
 
+    /// Pattern that matches cwd-relative file path glob case-insensitively.
+    pub fn cwd_file_glob_i(
+        path_converter: &RepoPathUiConverter,
+        input: impl AsRef<str>,
+    ) -> Result<Self, FilePatternParseError> {
+        // Literal directory names are matched case-insensitively, too, so only
+        // the leading "." and ".." components are resolved.
+        let (dir, pattern) = split_parent_path(input.as_ref());
+        let dir = path_converter.parse_file_path(dir)?;
+        Self::file_glob_i_at(dir, pattern)
+    }
+
     /// Pattern that matches workspace-relative file (or exact) path.
     pub fn root_file_path(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
         // TODO: Let caller pass in converter for root-relative paths too
@@ -165,6 +195,19 @@ impl FilePattern {
         Self::file_glob_at(dir, pattern)
     }
 
+    /// Pattern that matches workspace-relative file path glob
+    /// case-insensitively.
+    pub fn root_file_glob_i(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
+        Self::file_glob_i_at(RepoPathBuf::root(), input.as_ref())
+    }
+
+    /// Pattern that matches workspace-relative file path with regular
+    /// expression.
+    pub fn root_file_regex(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
+        let regex = regex::Regex::new(input.as_ref())?;
+        Ok(FilePattern::FileRegex(regex))
+    }
+
     fn file_glob_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
         if input.is_empty() {
             return Ok(FilePattern::FilePath(dir));
@@ -175,6 +218,15 @@ impl FilePattern {
         Ok(FilePattern::FileGlob { dir, pattern })
     }
 
+    fn file_glob_i_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
+        if input.is_empty() {
+            return Ok(FilePattern::FilePath(dir));
+        }
+        let normalized = RepoPathBuf::from_relative_path(input)?;
+        let pattern = glob::Pattern::new(normalized.as_internal_file_string())?;
+        Ok(FilePattern::FileGlobI { dir, pattern })
+    }
+
     /// Returns path if this pattern represents a literal path in a workspace.
     /// Returns `None` if this is a glob pattern for example.
     pub fn as_path(&self) -> Option<&RepoPath> {
@@ -182,10 +234,25 @@ impl FilePattern {
             FilePattern::FilePath(path) => Some(path),
             FilePattern::PrefixPath(path) => Some(path),
             FilePattern::FileGlob { .. } => None,
+            FilePattern::FileGlobI { .. } => None,
+            FilePattern::FileRegex(_) => None,
         }
     }
 }
 
+/// Splits `input` path into leading "." and ".." components and the rest.
+fn split_parent_path(input: &str) -> (&str, &str) {
+    let prefix_len = input
+        .split_inclusive(path::is_separator)
+        .take_while(|component| {
+            let name = component.trim_end_matches(path::is_separator);
+            name == "." || name == ".."
+        })
+        .map(|component| component.len())
+        .sum();
+    input.split_at(prefix_len)
+}
+
 /// Splits `input` path into literal directory path and glob pattern.
 fn split_glob_path(input: &str) -> (&str, &str) {
     const GLOB_CHARS: &[char] = &['?', '*', '[', ']']; // see glob::Pattern::escape()
@@ -317,6 +384,8 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
     let mut file_paths = Vec::new();
     let mut prefix_paths = Vec::new();
     let mut file_globs = Vec::new();
+    let mut file_globs_i = Vec::new();
+    let mut file_regexes = Vec::new();
     let mut matchers: Vec<Option<Box<dyn Matcher>>> = Vec::new();
     for expr in expressions {
         let matcher: Box<dyn Matcher> = match expr {
@@ -330,6 +399,10 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
                     FilePattern::FileGlob { dir, pattern } => {
                         file_globs.push((dir, pattern.clone()));
                     }
+                    FilePattern::FileGlobI { dir, pattern } => {
+                        file_globs_i.push((dir, pattern.clone()));
+                    }
+                    FilePattern::FileRegex(regex) => file_regexes.push(regex.clone()),
                 }
                 continue;
             }
@@ -358,6 +431,14 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
     if !file_globs.is_empty() {
         matchers.push(Some(Box::new(FileGlobsMatcher::new(file_globs))));
     }
+    if !file_globs_i.is_empty() {
+        matchers.push(Some(Box::new(FileGlobsMatcher::new_case_insensitive(
+            file_globs_i,
+        ))));
+    }
+    if !file_regexes.is_empty() {
+        matchers.push(Some(Box::new(FileRegexMatcher::new(file_regexes))));
+    }
     union_all_matchers(&mut matchers)
 }
 
@@ -739,6 +820,54 @@ mod tests {
         assert!(parse(r#"root-glob:"/*""#).is_err());
     }
 
+    #[test]
+    fn test_parse_glob_i_and_regex_pattern() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter);
+
+        // Literal directories are part of the case-insensitive pattern
+        insta::assert_debug_snapshot!(
+            parse(r#"glob-i:"../Foo/*.RS""#).unwrap(), @r#"
+        Pattern(
+            FileGlobI {
+                dir: "",
+                pattern: Pattern {
+                    original: "Foo/*.RS",
+                    tokens: _,
+                    is_recursive: false,
+                },
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse(r#"cwd-glob-i:"foo""#).unwrap(), @r#"
+        Pattern(
+            FileGlobI {
+                dir: "cur",
+                pattern: Pattern {
+                    original: "foo",
+                    tokens: _,
+                    is_recursive: false,
+                },
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse(r#"root-glob-i:"""#).unwrap(),
+            @r#"Pattern(FilePath(""))"#);
+        assert!(parse(r#"root-glob-i:"../*""#).is_err());
+
+        insta::assert_debug_snapshot!(
+            parse(r#"root-regex:"\\.rs$""#).unwrap(),
+            @r#"Pattern(FileRegex(Regex("\\.rs$")))"#);
+        assert!(parse(r#"root-regex:"(""#).is_err());
+    }
+
     #[test]
     fn test_parse_function() {
         let settings = insta_settings();
@@ -885,6 +1014,7 @@ mod tests {
                     is_recursive: false,
                 },
             ] {},
+            case_sensitive: true,
         }
         "#);
 
@@ -909,10 +1039,37 @@ mod tests {
                     ] {},
                 },
             },
+            case_sensitive: true,
         }
         "#);
     }
 
+    #[test]
+    fn test_build_matcher_glob_i_and_regex_pattern() {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| {
+            parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter)
+                .unwrap()
+                .to_matcher()
+        };
+        let repo_path = RepoPath::from_internal_string;
+
+        let m = parse(r#"glob-i:"src/**/*.rs""#);
+        assert!(m.matches(repo_path("src/lib.rs")));
+        assert!(m.matches(repo_path("SRC/Foo/LIB.RS")));
+        assert!(!m.matches(repo_path("lib/src/lib.rs")));
+
+        let m = parse(r#"root-regex:"^src/.*\\.rs$" ~ glob:"src/tests/*""#);
+        assert!(m.matches(repo_path("src/lib.rs")));
+        assert!(m.matches(repo_path("src/foo/bar.rs")));
+        assert!(!m.matches(repo_path("src/tests/foo.rs")));
+        assert!(!m.matches(repo_path("src/lib.rs.orig")));
+        assert!(!m.matches(repo_path("lib/src/lib.rs")));
+    }
+
     #[test]
     fn test_build_matcher_union_patterns_of_same_kind() {
         let settings = insta_settings();
@@ -0,0 +1,187 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integrity checks of the objects stored in a repo.
+
+use std::collections::HashSet;
+use std::io;
+
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::op_store::OpStoreError;
+use crate::op_store::OperationId;
+use crate::op_store::ViewId;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo_path::RepoPathBuf;
+use crate::view::View;
+
+/// Problem found while verifying a repo.
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    /// An operation or view couldn't be read.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// A commit, tree, file, symlink, or conflict couldn't be read.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// Result of [`ReadonlyRepo::verify()`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Number of operations that were read.
+    pub num_operations: usize,
+    /// Number of views that were read.
+    pub num_views: usize,
+    /// Number of commits that were read.
+    pub num_commits: usize,
+    /// Number of trees that were read.
+    pub num_trees: usize,
+    /// Number of files, symlinks, and conflicts that were read.
+    pub num_files: usize,
+    /// Missing or corrupt objects.
+    pub errors: Vec<VerifyError>,
+}
+
+impl VerifyReport {
+    /// Returns true if no problem was found.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Walks all operations reachable from the repo's operation, and all
+/// commits, trees, and files reachable from their views.
+///
+/// Objects which can't be read are recorded in the report, and their
+/// dependents aren't visited.
+pub(crate) fn verify(repo: &ReadonlyRepo) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let commit_ids = verify_operations(repo, &mut report);
+    verify_commits(repo, commit_ids, &mut report);
+    report
+}
+
+fn verify_operations(repo: &ReadonlyRepo, report: &mut VerifyReport) -> Vec<CommitId> {
+    let op_store = repo.op_store();
+    let mut visited_ops: HashSet<OperationId> = HashSet::new();
+    let mut visited_views: HashSet<ViewId> = HashSet::new();
+    let mut commit_ids = Vec::new();
+    let mut to_visit = vec![repo.op_id().clone()];
+    while let Some(op_id) = to_visit.pop() {
+        if !visited_ops.insert(op_id.clone()) {
+            continue;
+        }
+        let op = match op_store.read_operation(&op_id) {
+            Ok(op) => op,
+            Err(err) => {
+                report.errors.push(err.into());
+                continue;
+            }
+        };
+        report.num_operations += 1;
+        to_visit.extend(op.parents.iter().cloned());
+        if !visited_views.insert(op.view_id.clone()) {
+            continue;
+        }
+        match op_store.read_view(&op.view_id) {
+            Ok(view) => {
+                report.num_views += 1;
+                let view = View::new(view);
+                commit_ids.extend(view.all_referenced_commit_ids().cloned());
+            }
+            Err(err) => report.errors.push(err.into()),
+        }
+    }
+    commit_ids
+}
+
+fn verify_commits(repo: &ReadonlyRepo, commit_ids: Vec<CommitId>, report: &mut VerifyReport) {
+    let store = repo.store();
+    let mut visited_commits: HashSet<CommitId> = HashSet::new();
+    let mut visited_trees: HashSet<TreeId> = HashSet::new();
+    let mut visited_files: HashSet<FileId> = HashSet::new();
+    let mut to_visit = commit_ids;
+    while let Some(commit_id) = to_visit.pop() {
+        if !visited_commits.insert(commit_id.clone()) {
+            continue;
+        }
+        let commit = match store.get_commit(&commit_id) {
+            Ok(commit) => commit,
+            Err(err) => {
+                report.errors.push(err.into());
+                continue;
+            }
+        };
+        report.num_commits += 1;
+        to_visit.extend(commit.parent_ids().iter().cloned());
+        let tree_ids = match commit.tree_id() {
+            MergedTreeId::Legacy(tree_id) => vec![tree_id.clone()],
+            MergedTreeId::Merge(tree_ids) => tree_ids.iter().cloned().collect(),
+        };
+        for tree_id in tree_ids {
+            let mut to_visit_trees = vec![(RepoPathBuf::root(), tree_id)];
+            while let Some((dir, tree_id)) = to_visit_trees.pop() {
+                if !visited_trees.insert(tree_id.clone()) {
+                    continue;
+                }
+                let tree = match store.get_tree(dir.clone(), &tree_id) {
+                    Ok(tree) => tree,
+                    Err(err) => {
+                        report.errors.push(err.into());
+                        continue;
+                    }
+                };
+                report.num_trees += 1;
+                for entry in tree.entries_non_recursive() {
+                    let path = dir.join(entry.name());
+                    let result = match entry.value() {
+                        TreeValue::File { id, .. } => {
+                            if !visited_files.insert(id.clone()) {
+                                continue;
+                            }
+                            store.read_file(&path, id).and_then(|mut reader| {
+                                io::copy(&mut reader, &mut io::sink())
+                                    .map(|_| ())
+                                    .map_err(|err| BackendError::ReadFile {
+                                        path: path.clone(),
+                                        id: id.clone(),
+                                        source: err.into(),
+                                    })
+                            })
+                        }
+                        TreeValue::Symlink(id) => store.read_symlink(&path, id).map(|_| ()),
+                        TreeValue::Conflict(id) => store.read_conflict(&path, id).map(|_| ()),
+                        TreeValue::Tree(id) => {
+                            to_visit_trees.push((path, id.clone()));
+                            continue;
+                        }
+                        TreeValue::GitSubmodule(_) => continue,
+                    };
+                    match result {
+                        Ok(()) => report.num_files += 1,
+                        Err(err) => report.errors.push(err.into()),
+                    }
+                }
+            }
+        }
+    }
+}
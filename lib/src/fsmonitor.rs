@@ -85,7 +85,10 @@ pub mod watchman {
     use std::path::Path;
     use std::path::PathBuf;
 
+    use futures::FutureExt as _;
     use itertools::Itertools;
+    use serde::Deserialize;
+    use serde::Serialize;
     use thiserror::Error;
     use tracing::info;
     use tracing::instrument;
@@ -95,7 +98,10 @@ pub mod watchman {
     use watchman_client::prelude::NameOnly;
     use watchman_client::prelude::QueryRequestCommon;
     use watchman_client::prelude::QueryResult;
+    use watchman_client::prelude::SubscribeRequest;
+    use watchman_client::prelude::SyncTimeout;
     use watchman_client::prelude::TriggerRequest;
+    use watchman_client::SubscriptionData;
 
     /// Represents an instance in time from the perspective of the filesystem
     /// monitor.
@@ -163,6 +169,30 @@ pub mod watchman {
 
         #[error("Failed to register Watchman trigger")]
         WatchmanTriggerError(#[source] watchman_client::Error),
+
+        #[error("Failed to subscribe to Watchman")]
+        WatchmanSubscribeError(#[source] watchman_client::Error),
+    }
+
+    /// Subscription to changes of the files in the working copy, created by
+    /// [`Fsmonitor::subscribe()`].
+    pub struct Subscription(watchman_client::Subscription<NameOnly>);
+
+    #[derive(Debug, Serialize)]
+    struct FlushSubscriptionsRequest(&'static str, PathBuf, FlushSubscriptionsRequestParams);
+
+    #[derive(Debug, Serialize)]
+    struct FlushSubscriptionsRequestParams {
+        sync_timeout: SyncTimeout,
+        subscriptions: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct FlushSubscriptionsResponse {
+        #[serde(default)]
+        synced: Vec<String>,
+        #[serde(default)]
+        no_sync_needed: Vec<String>,
     }
 
     /// Handle to the underlying Watchman instance.
@@ -276,6 +306,62 @@ pub mod watchman {
                 .any(|t| t.name == "jj-background-monitor"))
         }
 
+        /// Subscribe to changes of the files in the working copy.
+        #[instrument(skip(self))]
+        pub async fn subscribe(&self) -> Result<Subscription, Error> {
+            info!("Subscribing to Watchman changes...");
+            let (subscription, _response) = self
+                .client
+                .subscribe(
+                    &self.resolved_root,
+                    SubscribeRequest {
+                        expression: Some(self.build_exclude_expr()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(Error::WatchmanSubscribeError)?;
+            Ok(Subscription(subscription))
+        }
+
+        /// Return whether files may have changed since the previous call, or
+        /// since the subscription was created.
+        ///
+        /// Changes made before this is called are always reported. If Watchman
+        /// can't tell whether the subscription is up to date, for example
+        /// while a version control operation defers its updates, this returns
+        /// `true`.
+        #[instrument(skip_all)]
+        pub async fn take_changes(&self, subscription: &mut Subscription) -> Result<bool, Error> {
+            let Subscription(subscription) = subscription;
+            let name = subscription.name().to_owned();
+            // Updates for the changes made so far are delivered before the
+            // response to the flush.
+            let response: FlushSubscriptionsResponse = self
+                .client
+                .generic_request(FlushSubscriptionsRequest(
+                    "flush-subscriptions",
+                    self.resolved_root.project_root().to_owned(),
+                    FlushSubscriptionsRequestParams {
+                        sync_timeout: SyncTimeout::Default,
+                        subscriptions: vec![name.clone()],
+                    },
+                ))
+                .await
+                .map_err(Error::WatchmanSubscribeError)?;
+            let mut changed =
+                !response.synced.contains(&name) && !response.no_sync_needed.contains(&name);
+            while let Some(data) = subscription.next().now_or_never() {
+                match data.map_err(Error::WatchmanSubscribeError)? {
+                    SubscriptionData::FilesChanged(_) | SubscriptionData::Canceled => {
+                        changed = true;
+                    }
+                    SubscriptionData::StateEnter { .. } | SubscriptionData::StateLeave { .. } => {}
+                }
+            }
+            Ok(changed)
+        }
+
         /// Register trigger for changed files.
         #[instrument(skip(self))]
         async fn register_trigger(&self) -> Result<(), Error> {
@@ -23,8 +23,10 @@ use std::default::Default;
 use std::fmt;
 use std::fs::File;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::path::PathBuf;
 use std::str;
+use std::sync::Arc;
 
 use bstr::BStr;
 use bstr::BString;
@@ -33,6 +35,7 @@ use thiserror::Error;
 
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
+use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::backend::TreeValue;
 use crate::commit::Commit;
@@ -41,11 +44,14 @@ use crate::file_util::PathError;
 use crate::git_backend::GitBackend;
 use crate::git_subprocess::GitSubprocessContext;
 use crate::git_subprocess::GitSubprocessError;
+use crate::gitignore::GitIgnoreError;
+use crate::gitignore::GitIgnoreFile;
 use crate::index::Index;
 use crate::merged_tree::MergedTree;
 use crate::object_id::ObjectId;
 use crate::op_store::RefTarget;
 use crate::op_store::RefTargetOptionExt;
+use crate::op_store::RemoteConfig;
 use crate::op_store::RemoteRef;
 use crate::op_store::RemoteRefState;
 use crate::refs;
@@ -279,6 +285,53 @@ pub fn get_git_backend(store: &Store) -> Result<&GitBackend, UnexpectedGitBacken
         .ok_or(UnexpectedGitBackendError)
 }
 
+/// Returns the Git ignores which apply to the workspace in addition to its
+/// `.gitignore` files: the user's `core.excludesFile` (or
+/// `$XDG_CONFIG_HOME/git/ignore`), and the backing repo's `info/exclude`.
+pub fn base_ignores(
+    store: &Store,
+    workspace_root: &Path,
+) -> Result<Arc<GitIgnoreFile>, GitIgnoreError> {
+    let get_excludes_file_path = |config: &gix::config::File| -> Option<PathBuf> {
+        // TODO: maybe use path() and interpolate(), which can process non-utf-8
+        // path on Unix.
+        if let Some(value) = config.string("core.excludesFile") {
+            let path = str::from_utf8(&value)
+                .ok()
+                .map(crate::file_util::expand_home_path)?;
+            // The configured path is usually absolute, but if it's relative,
+            // the "git" command would read the file at the work-tree directory.
+            Some(workspace_root.join(path))
+        } else {
+            xdg_config_home().ok().map(|x| x.join("git").join("ignore"))
+        }
+    };
+
+    fn xdg_config_home() -> Result<PathBuf, std::env::VarError> {
+        if let Ok(x) = std::env::var("XDG_CONFIG_HOME") {
+            if !x.is_empty() {
+                return Ok(PathBuf::from(x));
+            }
+        }
+        std::env::var("HOME").map(|x| Path::new(&x).join(".config"))
+    }
+
+    let mut git_ignores = GitIgnoreFile::empty();
+    if let Ok(git_backend) = get_git_backend(store) {
+        let git_repo = git_backend.git_repo();
+        if let Some(excludes_file_path) = get_excludes_file_path(&git_repo.config_snapshot()) {
+            git_ignores = git_ignores.chain_with_file("", excludes_file_path)?;
+        }
+        git_ignores = git_ignores
+            .chain_with_file("", git_backend.git_repo_path().join("info").join("exclude"))?;
+    } else if let Ok(git_config) = gix::config::File::from_globals() {
+        if let Some(excludes_file_path) = get_excludes_file_path(&git_config) {
+            git_ignores = git_ignores.chain_with_file("", excludes_file_path)?;
+        }
+    }
+    Ok(git_ignores)
+}
+
 /// Returns new thread-local instance to access to the underlying Git repo.
 pub fn get_git_repo(store: &Store) -> Result<gix::Repository, UnexpectedGitBackendError> {
     get_git_backend(store).map(|backend| backend.git_repo())
@@ -918,6 +971,100 @@ pub fn export_some_refs(
     Ok(failed_branches)
 }
 
+/// Namespace under which `mirror_refs()` mirrors the local bookmarks.
+pub const MIRRORED_BOOKMARK_REF_PREFIX: &str = "refs/jj/bookmarks/";
+/// Namespace under which `mirror_refs()` mirrors the working-copy commits.
+pub const MIRRORED_WORKSPACE_REF_PREFIX: &str = "refs/jj/workspaces/";
+
+/// Mirrors the local bookmarks and the working-copy commits in the view to
+/// refs under `refs/jj/` in the backing Git repo, so Git-based tools can see
+/// the jj-managed history without an explicit export or push. Returns a list
+/// of bookmarks that failed to be mirrored.
+///
+/// Unlike the refs written by `export_refs()`, the mirrored refs are owned by
+/// jj. They're overwritten or deleted regardless of changes made to them in
+/// Git, and they're never imported back. Conflicted bookmarks aren't mirrored.
+pub fn mirror_refs(repo: &dyn Repo) -> Result<Vec<FailedRefExport>, GitExportError> {
+    let git_repo = get_git_repo(repo.store())?;
+    let root_commit_id = repo.store().root_commit_id();
+    let mut failed_bookmarks = vec![];
+    let mut new_refs = BTreeMap::new();
+    for (name, target) in repo.view().local_bookmarks() {
+        let Some(id) = target.as_normal() else {
+            continue;
+        };
+        let ref_name = RefName::LocalBranch(name.to_owned());
+        if id == root_commit_id {
+            failed_bookmarks.push(FailedRefExport {
+                name: ref_name,
+                reason: FailedRefExportReason::OnRootCommit,
+            });
+        } else if let Ok(git_ref_name) =
+            gix::refs::FullName::try_from(format!("{MIRRORED_BOOKMARK_REF_PREFIX}{name}"))
+        {
+            new_refs.insert(
+                git_ref_name,
+                gix::ObjectId::from_bytes_or_panic(id.as_bytes()),
+            );
+        } else {
+            failed_bookmarks.push(FailedRefExport {
+                name: ref_name,
+                reason: FailedRefExportReason::InvalidGitName,
+            });
+        }
+    }
+    for (workspace_id, id) in repo.view().wc_commit_ids() {
+        let git_ref_name = format!("{MIRRORED_WORKSPACE_REF_PREFIX}{}", workspace_id.as_str());
+        match gix::refs::FullName::try_from(git_ref_name) {
+            Ok(git_ref_name) if id != root_commit_id => {
+                new_refs.insert(
+                    git_ref_name,
+                    gix::ObjectId::from_bytes_or_panic(id.as_bytes()),
+                );
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!(?err, ?workspace_id, "cannot mirror working-copy commit"),
+        }
+    }
+
+    let git_references = git_repo.references().map_err(GitExportError::from_git)?;
+    let mut edits = vec![];
+    for prefix in [MIRRORED_BOOKMARK_REF_PREFIX, MIRRORED_WORKSPACE_REF_PREFIX] {
+        for git_ref in git_references
+            .prefixed(prefix)
+            .map_err(GitExportError::from_git)?
+        {
+            let git_ref = git_ref.map_err(GitExportError::from_git)?;
+            match new_refs.get(git_ref.name()) {
+                Some(oid) if git_ref.target().try_id() == Some(oid.as_ref()) => {
+                    new_refs.remove(git_ref.name());
+                }
+                Some(_) => {}
+                None => edits.push(remove_ref(git_ref)),
+            }
+        }
+    }
+    for (git_ref_name, oid) in new_refs {
+        edits.push(gix::refs::transaction::RefEdit {
+            change: gix::refs::transaction::Change::Update {
+                log: gix::refs::transaction::LogChange {
+                    message: "mirror from jj".into(),
+                    ..Default::default()
+                },
+                expected: gix::refs::transaction::PreviousValue::Any,
+                new: gix::refs::Target::Object(oid),
+            },
+            name: git_ref_name,
+            deref: false,
+        });
+    }
+    git_repo
+        .edit_references(edits)
+        .map_err(GitExportError::from_git)?;
+    failed_bookmarks.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(failed_bookmarks)
+}
+
 fn copy_exportable_local_branches_to_remote_view(
     mut_repo: &mut MutableRepo,
     remote: &str,
@@ -1584,15 +1731,49 @@ pub fn get_all_remote_names(store: &Store) -> Result<Vec<String>, UnexpectedGitB
     Ok(names)
 }
 
+/// Adds a remote to the repo view. If the repo is backed by a Git repo, the
+/// remote is also added to its config.
 pub fn add_remote(
-    store: &Store,
+    mut_repo: &mut MutableRepo,
     remote_name: &str,
     url: &str,
 ) -> Result<(), GitRemoteManagementError> {
-    let git_repo = get_git_repo(store)?;
+    validate_remote_name(remote_name)?;
+
+    if mut_repo.view().get_remote_config(remote_name).is_some() {
+        return Err(GitRemoteManagementError::RemoteAlreadyExists(
+            remote_name.to_owned(),
+        ));
+    }
+    if let Ok(git_repo) = get_git_repo(mut_repo.store()) {
+        add_git_remote(&git_repo, remote_name, url)?;
+    }
+
+    mut_repo.set_remote_config(
+        remote_name,
+        RemoteConfig {
+            url: url.to_owned(),
+        },
+    );
+    Ok(())
+}
 
+/// Adds a remote to the config of the backing Git repo without recording it
+/// in the view, e.g. for the origin of a clone.
+pub(crate) fn add_git_remote_config(
+    store: &Store,
+    remote_name: &str,
+    url: &str,
+) -> Result<(), GitRemoteManagementError> {
     validate_remote_name(remote_name)?;
+    add_git_remote(&get_git_repo(store)?, remote_name, url)
+}
 
+fn add_git_remote(
+    git_repo: &gix::Repository,
+    remote_name: &str,
+    url: &str,
+) -> Result<(), GitRemoteManagementError> {
     if git_repo.try_find_remote(remote_name).is_some() {
         return Err(GitRemoteManagementError::RemoteAlreadyExists(
             remote_name.to_owned(),
@@ -1617,30 +1798,42 @@ pub fn add_remote(
     Ok(())
 }
 
+/// Removes a remote and its bookmarks from the repo view, and from the config
+/// and refs of the backing Git repo if there is one.
 pub fn remove_remote(
     mut_repo: &mut MutableRepo,
     remote_name: &str,
 ) -> Result<(), GitRemoteManagementError> {
-    let mut git_repo = get_git_repo(mut_repo.store())?;
-
-    if git_repo.try_find_remote(remote_name).is_none() {
+    let mut found = mut_repo.view().get_remote_config(remote_name).is_some();
+    if let Ok(mut git_repo) = get_git_repo(mut_repo.store()) {
+        if git_repo.try_find_remote(remote_name).is_some() {
+            remove_git_remote(&mut git_repo, remote_name)?;
+            found = true;
+        }
+    }
+    if !found {
         return Err(GitRemoteManagementError::NoSuchRemote(
             remote_name.to_owned(),
         ));
-    };
+    }
+
+    if remote_name != REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+        remove_remote_refs(mut_repo, remote_name);
+    }
 
+    Ok(())
+}
+
+fn remove_git_remote(
+    git_repo: &mut gix::Repository,
+    remote_name: &str,
+) -> Result<(), GitRemoteManagementError> {
     let mut config = git_repo.config_snapshot().clone();
     remove_remote_git_branch_config_sections(&mut config, remote_name)?;
     remove_remote_git_config_sections(&mut config, remote_name)?;
     save_git_config(&config).map_err(GitRemoteManagementError::GitConfigSaveError)?;
 
-    remove_remote_git_refs(&mut git_repo, remote_name)
-        .map_err(GitRemoteManagementError::from_git)?;
-
-    if remote_name != REMOTE_NAME_FOR_LOCAL_GIT_REPO {
-        remove_remote_refs(mut_repo, remote_name);
-    }
-
+    remove_remote_git_refs(git_repo, remote_name).map_err(GitRemoteManagementError::from_git)?;
     Ok(())
 }
 
@@ -1672,15 +1865,46 @@ fn remove_remote_refs(mut_repo: &mut MutableRepo, remote_name: &str) {
     }
 }
 
+/// Renames a remote and its bookmarks in the repo view, and in the config and
+/// refs of the backing Git repo if there is one.
 pub fn rename_remote(
     mut_repo: &mut MutableRepo,
     old_remote_name: &str,
     new_remote_name: &str,
 ) -> Result<(), GitRemoteManagementError> {
-    let mut git_repo = get_git_repo(mut_repo.store())?;
-
     validate_remote_name(new_remote_name)?;
 
+    let view = mut_repo.view();
+    let in_view = view.get_remote_config(old_remote_name).is_some();
+    let mut git_repo = get_git_repo(mut_repo.store())
+        .ok()
+        .filter(|git_repo| git_repo.try_find_remote(old_remote_name).is_some());
+    if !in_view && git_repo.is_none() {
+        return Err(GitRemoteManagementError::NoSuchRemote(
+            old_remote_name.to_owned(),
+        ));
+    }
+    if view.get_remote_config(new_remote_name).is_some() {
+        return Err(GitRemoteManagementError::RemoteAlreadyExists(
+            new_remote_name.to_owned(),
+        ));
+    }
+    if let Some(git_repo) = &mut git_repo {
+        rename_git_remote(git_repo, old_remote_name, new_remote_name)?;
+    }
+
+    if old_remote_name != REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+        rename_remote_refs(mut_repo, old_remote_name, new_remote_name);
+    }
+
+    Ok(())
+}
+
+fn rename_git_remote(
+    git_repo: &mut gix::Repository,
+    old_remote_name: &str,
+    new_remote_name: &str,
+) -> Result<(), GitRemoteManagementError> {
     let Some(result) = git_repo.try_find_remote(old_remote_name) else {
         return Err(GitRemoteManagementError::NoSuchRemote(
             old_remote_name.to_owned(),
@@ -1723,13 +1947,8 @@ pub fn rename_remote(
     remove_remote_git_config_sections(&mut config, old_remote_name)?;
     save_git_config(&config).map_err(GitRemoteManagementError::GitConfigSaveError)?;
 
-    rename_remote_git_refs(&mut git_repo, old_remote_name, new_remote_name)
+    rename_remote_git_refs(git_repo, old_remote_name, new_remote_name)
         .map_err(GitRemoteManagementError::from_git)?;
-
-    if old_remote_name != REMOTE_NAME_FOR_LOCAL_GIT_REPO {
-        rename_remote_refs(mut_repo, old_remote_name, new_remote_name);
-    }
-
     Ok(())
 }
 
@@ -1804,22 +2023,44 @@ where
     Ok(new_remote)
 }
 
+/// Sets the URL of a remote in the repo view, and in the config of the backing
+/// Git repo if there is one.
 pub fn set_remote_url(
-    store: &Store,
+    mut_repo: &mut MutableRepo,
     remote_name: &str,
     new_remote_url: &str,
 ) -> Result<(), GitRemoteManagementError> {
-    let git_repo = get_git_repo(store)?;
-
     validate_remote_name(remote_name)?;
 
-    let Some(result) = git_repo.try_find_remote_without_url_rewrite(remote_name) else {
+    let mut found = mut_repo.view().get_remote_config(remote_name).is_some();
+    if let Ok(git_repo) = get_git_repo(mut_repo.store()) {
+        if let Some(result) = git_repo.try_find_remote_without_url_rewrite(remote_name) {
+            let remote = result.map_err(GitRemoteManagementError::from_git)?;
+            set_git_remote_url(&git_repo, remote_name, remote, new_remote_url)?;
+            found = true;
+        }
+    }
+    if !found {
         return Err(GitRemoteManagementError::NoSuchRemote(
             remote_name.to_owned(),
         ));
-    };
-    let mut remote = result.map_err(GitRemoteManagementError::from_git)?;
+    }
+
+    mut_repo.set_remote_config(
+        remote_name,
+        RemoteConfig {
+            url: new_remote_url.to_owned(),
+        },
+    );
+    Ok(())
+}
 
+fn set_git_remote_url(
+    git_repo: &gix::Repository,
+    remote_name: &str,
+    mut remote: gix::Remote,
+    new_remote_url: &str,
+) -> Result<(), GitRemoteManagementError> {
     if remote.url(gix::remote::Direction::Push) != remote.url(gix::remote::Direction::Fetch) {
         return Err(GitRemoteManagementError::NonstandardConfiguration(
             remote_name.to_owned(),
@@ -2252,6 +2493,12 @@ pub enum GitPushError {
     Subprocess(#[from] GitSubprocessError),
     #[error(transparent)]
     UnexpectedBackend(#[from] UnexpectedGitBackendError),
+    #[error("No visible commit for change {0}")]
+    NoSuchChange(String),
+    #[error("Change {0} is divergent")]
+    DivergentChange(String),
+    #[error("Bookmark {0} is conflicted on the remote")]
+    ConflictedRemoteBookmark(String),
 }
 
 #[derive(Clone, Debug)]
@@ -2307,6 +2554,80 @@ pub fn push_branches(
     Ok(())
 }
 
+/// Number of change id digits in the short bookmark names derived by
+/// [`change_bookmark_name()`].
+const CHANGE_BOOKMARK_ID_LEN: usize = 12;
+
+/// Returns the name of the bookmark that [`push_change()`] pushes `change_id`
+/// to.
+///
+/// The name is `prefix` followed by a short change id. The full change id is
+/// used instead if a bookmark with that name already exists, or if the short
+/// id is ambiguous.
+pub fn change_bookmark_name(repo: &dyn Repo, prefix: &str, change_id: &ChangeId) -> String {
+    let full_name = format!("{prefix}{}", change_id.hex());
+    if repo.view().get_local_bookmark(&full_name).is_present() {
+        return full_name;
+    }
+    let is_short_id_unique = repo.shortest_unique_change_id_prefix_len(change_id)
+        <= CHANGE_BOOKMARK_ID_LEN
+        && repo
+            .resolve_change_id(change_id)
+            .is_some_and(|ids| ids.len() == 1);
+    if is_short_id_unique {
+        format!("{prefix}{change_id:.CHANGE_BOOKMARK_ID_LEN$}")
+    } else {
+        full_name
+    }
+}
+
+/// Points the bookmark derived from `change_id` at the visible commit of the
+/// change, and pushes the bookmark to `remote`.
+///
+/// The bookmark is named by [`change_bookmark_name()`] with the
+/// `git.push-bookmark-prefix` setting. It is created if it doesn't exist, and
+/// becomes tracked once pushed. Returns the bookmark name.
+pub fn push_change(
+    mut_repo: &mut MutableRepo,
+    git_settings: &GitSettings,
+    change_id: &ChangeId,
+    remote: &str,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<String, GitPushError> {
+    validate_remote_name(remote)?;
+    let commit_id = match mut_repo.resolve_change_id(change_id).as_deref() {
+        Some([commit_id]) => commit_id.clone(),
+        Some(_) => return Err(GitPushError::DivergentChange(change_id.to_string())),
+        None => return Err(GitPushError::NoSuchChange(change_id.to_string())),
+    };
+    let name = change_bookmark_name(mut_repo, &git_settings.push_bookmark_prefix, change_id);
+    mut_repo.set_local_bookmark_target(&name, RefTarget::normal(commit_id.clone()));
+
+    let remote_symbol = RemoteRefSymbol {
+        name: &name,
+        remote,
+    };
+    let remote_target = &mut_repo.view().get_remote_bookmark(remote_symbol).target;
+    if remote_target.has_conflict() {
+        return Err(GitPushError::ConflictedRemoteBookmark(name));
+    }
+    let old_target = remote_target.as_normal().cloned();
+    if old_target.as_ref() == Some(&commit_id) {
+        return Ok(name);
+    }
+    let targets = GitBranchPushTargets {
+        branch_updates: vec![(
+            name.clone(),
+            BookmarkPushUpdate {
+                old_target,
+                new_target: Some(commit_id),
+            },
+        )],
+    };
+    push_branches(mut_repo, git_settings, remote, &targets, callbacks)?;
+    Ok(name)
+}
+
 /// Pushes the specified Git refs without updating the repo view.
 pub fn push_updates(
     repo: &dyn Repo,
@@ -2594,6 +2915,17 @@ pub struct RemoteCallbacks<'a> {
 }
 
 impl<'a> RemoteCallbacks<'a> {
+    /// Borrows the callbacks for another remote operation.
+    pub fn reborrow(&mut self) -> RemoteCallbacks<'_> {
+        RemoteCallbacks {
+            progress: self.progress.as_deref_mut().map(|cb| cb as _),
+            sideband_progress: self.sideband_progress.as_deref_mut().map(|cb| cb as _),
+            get_ssh_keys: self.get_ssh_keys.as_deref_mut().map(|cb| cb as _),
+            get_password: self.get_password.as_deref_mut().map(|cb| cb as _),
+            get_username_password: self.get_username_password.as_deref_mut().map(|cb| cb as _),
+        }
+    }
+
     fn into_git(mut self) -> git2::RemoteCallbacks<'a> {
         let mut callbacks = git2::RemoteCallbacks::new();
         if let Some(progress_cb) = self.progress {
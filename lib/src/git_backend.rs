@@ -20,9 +20,14 @@ use std::fmt::Debug;
 use std::fmt::Error;
 use std::fmt::Formatter;
 use std::fs;
+use std::fs::File;
 use std::io;
+use std::io::BufRead as _;
+use std::io::BufReader;
 use std::io::Cursor;
 use std::io::Read;
+use std::io::Seek as _;
+use std::io::Write as _;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
@@ -34,10 +39,12 @@ use std::sync::MutexGuard;
 use std::time::SystemTime;
 
 use async_trait::async_trait;
+use flate2::read::ZlibDecoder;
 use futures::stream::BoxStream;
 use gix::bstr::BString;
 use gix::objs::CommitRef;
 use gix::objs::CommitRefIter;
+use gix::objs::Write as _;
 use gix::objs::WriteTo;
 use itertools::Itertools;
 use pollster::FutureExt;
@@ -69,16 +76,20 @@ use crate::backend::Timestamp;
 use crate::backend::Tree;
 use crate::backend::TreeId;
 use crate::backend::TreeValue;
+use crate::config::ConfigGetError;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
 use crate::index::Index;
-use crate::lock::FileLock;
+use crate::lock::LockKind;
+use crate::lock::LockManager;
+use crate::lock::ManagedLock;
 use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::object_id::ObjectId;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponentBuf;
+use crate::settings::HumanByteSize;
 use crate::settings::UserSettings;
 use crate::stacked_table::MutableTable;
 use crate::stacked_table::ReadonlyTable;
@@ -101,6 +112,8 @@ pub enum GitBackendInitError {
     #[error("Failed to open git repository")]
     OpenRepository(#[source] gix::open::Error),
     #[error(transparent)]
+    Config(ConfigGetError),
+    #[error(transparent)]
     Path(PathError),
 }
 
@@ -115,6 +128,8 @@ pub enum GitBackendLoadError {
     #[error("Failed to open git repository")]
     OpenRepository(#[source] gix::open::Error),
     #[error(transparent)]
+    Config(ConfigGetError),
+    #[error(transparent)]
     Path(PathError),
 }
 
@@ -159,6 +174,10 @@ pub struct GitBackend {
     empty_tree_id: TreeId,
     extra_metadata_store: TableStore,
     cached_extra_metadata: Mutex<Option<Arc<ReadonlyTable>>>,
+    /// Files larger than this are written through a temporary file instead of
+    /// an in-memory buffer, and read from loose objects without buffering.
+    max_buffered_file_size: u64,
+    lock_manager: LockManager,
 }
 
 impl GitBackend {
@@ -166,7 +185,12 @@ impl GitBackend {
         "git"
     }
 
-    fn new(base_repo: gix::ThreadSafeRepository, extra_metadata_store: TableStore) -> Self {
+    fn new(
+        base_repo: gix::ThreadSafeRepository,
+        extra_metadata_store: TableStore,
+        max_buffered_file_size: u64,
+        lock_manager: LockManager,
+    ) -> Self {
         let repo = Mutex::new(base_repo.to_thread_local());
         let root_commit_id = CommitId::from_bytes(&[0; HASH_LENGTH]);
         let root_change_id = ChangeId::from_bytes(&[0; CHANGE_ID_LENGTH]);
@@ -179,6 +203,8 @@ impl GitBackend {
             empty_tree_id,
             extra_metadata_store,
             cached_extra_metadata: Mutex::new(None),
+            max_buffered_file_size,
+            lock_manager,
         }
     }
 
@@ -194,7 +220,7 @@ impl GitBackend {
             gix_open_opts_from_settings(settings),
         )
         .map_err(GitBackendInitError::InitRepository)?;
-        Self::init_with_repo(store_path, git_repo_path, git_repo)
+        Self::init_with_repo(settings, store_path, git_repo_path, git_repo)
     }
 
     /// Initializes backend by creating a new Git repo at the specified
@@ -218,7 +244,7 @@ impl GitBackend {
         )
         .map_err(GitBackendInitError::InitRepository)?;
         let git_repo_path = workspace_root.join(".git");
-        Self::init_with_repo(store_path, &git_repo_path, git_repo)
+        Self::init_with_repo(settings, store_path, &git_repo_path, git_repo)
     }
 
     /// Initializes backend with an existing Git repo at the specified path.
@@ -238,14 +264,17 @@ impl GitBackend {
             gix_open_opts_from_settings(settings),
         )
         .map_err(GitBackendInitError::OpenRepository)?;
-        Self::init_with_repo(store_path, git_repo_path, git_repo)
+        Self::init_with_repo(settings, store_path, git_repo_path, git_repo)
     }
 
     fn init_with_repo(
+        settings: &UserSettings,
         store_path: &Path,
         git_repo_path: &Path,
         git_repo: gix::ThreadSafeRepository,
     ) -> Result<Self, Box<GitBackendInitError>> {
+        let max_buffered_file_size =
+            max_buffered_file_size_from_settings(settings).map_err(GitBackendInitError::Config)?;
         let extra_path = store_path.join("extra");
         fs::create_dir(&extra_path)
             .context(&extra_path)
@@ -270,8 +299,15 @@ impl GitBackend {
                 .context(&target_path)
                 .map_err(GitBackendInitError::Path)?;
         };
-        let extra_metadata_store = TableStore::init(extra_path, HASH_LENGTH);
-        Ok(GitBackend::new(git_repo, extra_metadata_store))
+        let lock_manager = settings.lock_manager();
+        let extra_metadata_store =
+            TableStore::init(extra_path, HASH_LENGTH).with_lock_manager(lock_manager.clone());
+        Ok(GitBackend::new(
+            git_repo,
+            extra_metadata_store,
+            max_buffered_file_size,
+            lock_manager,
+        ))
     }
 
     pub fn load(
@@ -293,8 +329,17 @@ impl GitBackend {
             gix_open_opts_from_settings(settings),
         )
         .map_err(GitBackendLoadError::OpenRepository)?;
-        let extra_metadata_store = TableStore::load(store_path.join("extra"), HASH_LENGTH);
-        Ok(GitBackend::new(repo, extra_metadata_store))
+        let max_buffered_file_size =
+            max_buffered_file_size_from_settings(settings).map_err(GitBackendLoadError::Config)?;
+        let lock_manager = settings.lock_manager();
+        let extra_metadata_store = TableStore::load(store_path.join("extra"), HASH_LENGTH)
+            .with_lock_manager(lock_manager.clone());
+        Ok(GitBackend::new(
+            repo,
+            extra_metadata_store,
+            max_buffered_file_size,
+            lock_manager,
+        ))
     }
 
     fn lock_git_repo(&self) -> MutexGuard<'_, gix::Repository> {
@@ -316,6 +361,11 @@ impl GitBackend {
         self.base_repo.path()
     }
 
+    /// The lock manager configured for the repo this backend belongs to.
+    pub fn lock_manager(&self) -> &LockManager {
+        &self.lock_manager
+    }
+
     /// Path to the working directory if the repository isn't bare.
     pub fn git_workdir(&self) -> Option<&Path> {
         self.base_repo.work_dir()
@@ -336,18 +386,22 @@ impl GitBackend {
         }
     }
 
-    fn read_extra_metadata_table_locked(&self) -> BackendResult<(Arc<ReadonlyTable>, FileLock)> {
-        let table = self
+    fn read_extra_metadata_table_locked(&self) -> BackendResult<(Arc<ReadonlyTable>, ManagedLock)> {
+        let order = self
+            .lock_manager
+            .enter(LockKind::GitRepo)
+            .map_err(|err| BackendError::Other(err.into()))?;
+        let (table, lock) = self
             .extra_metadata_store
             .get_head_locked()
             .map_err(GitBackendError::ReadMetadata)?;
-        Ok(table)
+        Ok((table, ManagedLock::new(order, lock)))
     }
 
     fn save_extra_metadata_table(
         &self,
         mut_table: MutableTable,
-        _table_lock: &FileLock,
+        _table_lock: &ManagedLock,
     ) -> BackendResult<()> {
         let table = self
             .extra_metadata_store
@@ -412,6 +466,25 @@ impl GitBackend {
     fn read_file_sync(&self, id: &FileId) -> BackendResult<Box<dyn Read>> {
         let git_blob_id = validate_git_object_id(id)?;
         let locked_repo = self.lock_git_repo();
+        let header = locked_repo
+            .find_header(git_blob_id)
+            .map_err(|err| map_not_found_err(err, id))?;
+        // Packed objects may be stored as deltas, so only loose objects can be
+        // decompressed while they're read.
+        if let gix::odb::find::Header::Loose {
+            kind: gix::object::Kind::Blob,
+            size,
+        } = header
+        {
+            if size > self.max_buffered_file_size {
+                if let Some(reader) = self
+                    .open_loose_blob(&git_blob_id, size)
+                    .map_err(|err| to_read_object_err(err, id))?
+                {
+                    return Ok(reader);
+                }
+            }
+        }
         let mut blob = locked_repo
             .find_object(git_blob_id)
             .map_err(|err| map_not_found_err(err, id))?
@@ -420,6 +493,30 @@ impl GitBackend {
         Ok(Box::new(Cursor::new(blob.take_data())))
     }
 
+    /// Opens a reader which decompresses the loose blob `id` of `size` bytes
+    /// as it's read. Returns `None` if the object isn't stored in the object
+    /// directory of this repo, e.g. because it's in an alternate.
+    fn open_loose_blob(&self, id: &gix::oid, size: u64) -> io::Result<Option<Box<dyn Read>>> {
+        let hex = id.to_string();
+        let path = self.base_repo.objects_dir().join(&hex[..2]).join(&hex[2..]);
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut reader = BufReader::new(ZlibDecoder::new(file));
+        // Skip the "blob <size>" header, which is terminated by a NUL byte.
+        let mut header = Vec::new();
+        reader.read_until(0, &mut header)?;
+        if header.last() != Some(&0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Loose object header is not terminated",
+            ));
+        }
+        Ok(Some(Box::new(reader.take(size))))
+    }
+
     fn new_diff_platform(&self) -> BackendResult<gix::diff::blob::Platform> {
         let attributes = gix::worktree::Stack::new(
             Path::new(""),
@@ -478,6 +575,12 @@ pub fn canonicalize_git_repo_path(path: &Path) -> io::Result<PathBuf> {
     }
 }
 
+fn max_buffered_file_size_from_settings(settings: &UserSettings) -> Result<u64, ConfigGetError> {
+    let HumanByteSize(size) =
+        settings.get_value_with("git.max-buffered-file-size", TryInto::try_into)?;
+    Ok(size)
+}
+
 fn gix_open_opts_from_settings(settings: &UserSettings) -> gix::open::Options {
     let user_name = settings.user_name();
     let user_email = settings.user_email();
@@ -867,7 +970,7 @@ fn to_invalid_utf8_err(source: str::Utf8Error, id: &impl ObjectId) -> BackendErr
 fn import_extra_metadata_entries_from_heads(
     git_repo: &gix::Repository,
     mut_table: &mut MutableTable,
-    _table_lock: &FileLock,
+    _table_lock: &ManagedLock,
     head_ids: &HashSet<&CommitId>,
     uses_tree_conflict_format: bool,
 ) -> BackendResult<()> {
@@ -958,14 +1061,40 @@ impl Backend for GitBackend {
         _path: &RepoPath,
         contents: &mut (dyn Read + Send),
     ) -> BackendResult<FileId> {
+        let to_write_err = |err: io::Error| BackendError::WriteObject {
+            object_type: "file",
+            source: Box::new(err),
+        };
         let mut bytes = Vec::new();
-        contents.read_to_end(&mut bytes).unwrap();
+        contents
+            .take(self.max_buffered_file_size)
+            .read_to_end(&mut bytes)
+            .map_err(to_write_err)?;
+        if (bytes.len() as u64) < self.max_buffered_file_size {
+            let locked_repo = self.lock_git_repo();
+            let oid = locked_repo
+                .write_blob(bytes)
+                .map_err(|err| BackendError::WriteObject {
+                    object_type: "file",
+                    source: Box::new(err),
+                })?;
+            return Ok(FileId::new(oid.as_bytes().to_vec()));
+        }
+
+        // The object header needs the size of the content, so large files are
+        // spooled to a temporary file instead of being buffered in memory.
+        let mut temp_file = tempfile::tempfile_in(self.git_repo_path()).map_err(to_write_err)?;
+        temp_file.write_all(&bytes).map_err(to_write_err)?;
+        let size = bytes.len() as u64 + io::copy(contents, &mut temp_file).map_err(to_write_err)?;
+        drop(bytes);
+        temp_file.rewind().map_err(to_write_err)?;
         let locked_repo = self.lock_git_repo();
         let oid = locked_repo
-            .write_blob(bytes)
+            .objects
+            .write_stream(gix::object::Kind::Blob, size, &mut temp_file)
             .map_err(|err| BackendError::WriteObject {
                 object_type: "file",
-                source: Box::new(err),
+                source: err,
             })?;
         Ok(FileId::new(oid.as_bytes().to_vec()))
     }
@@ -1540,6 +1669,8 @@ mod tests {
     use test_case::test_case;
 
     use super::*;
+    use crate::config::ConfigLayer;
+    use crate::config::ConfigSource;
     use crate::config::StackedConfig;
     use crate::content_hash::blake2b_hash;
     use crate::tests::new_temp_dir;
@@ -2281,6 +2412,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn write_large_file() {
+        let mut config = StackedConfig::with_defaults();
+        config.add_layer(
+            ConfigLayer::parse(ConfigSource::User, "git.max-buffered-file-size = 10").unwrap(),
+        );
+        let settings = UserSettings::from_config(config).unwrap();
+        let temp_dir = new_temp_dir();
+        let backend = GitBackend::init_internal(&settings, temp_dir.path()).unwrap();
+        let git_repo = backend.git_repo();
+        let path = RepoPath::from_internal_string("file");
+
+        // Small files are buffered, and large files are spooled to a temporary
+        // file. Both should be stored as regular blobs.
+        for content in ["small", "0123456789", "content larger than the limit"] {
+            let file_id = backend
+                .write_file(path, &mut content.as_bytes())
+                .block_on()
+                .unwrap();
+            let blob_id = git_repo.write_blob(content).unwrap();
+            assert_eq!(file_id.as_bytes(), blob_id.as_bytes());
+            let mut read_content = String::new();
+            backend
+                .read_file(path, &file_id)
+                .block_on()
+                .unwrap()
+                .read_to_string(&mut read_content)
+                .unwrap();
+            assert_eq!(read_content, content);
+        }
+    }
+
+    #[test]
+    fn read_large_file() {
+        let mut config = StackedConfig::with_defaults();
+        config.add_layer(
+            ConfigLayer::parse(ConfigSource::User, "git.max-buffered-file-size = 10").unwrap(),
+        );
+        let settings = UserSettings::from_config(config).unwrap();
+        let temp_dir = new_temp_dir();
+        let backend = GitBackend::init_internal(&settings, temp_dir.path()).unwrap();
+        let path = RepoPath::from_internal_string("file");
+
+        let content = "content larger than the limit";
+        let file_id = backend
+            .write_file(path, &mut content.as_bytes())
+            .block_on()
+            .unwrap();
+        let blob_id = gix::ObjectId::from_bytes_or_panic(file_id.as_bytes());
+
+        // The loose object is decompressed as it's read
+        let mut read_content = String::new();
+        backend
+            .open_loose_blob(&blob_id, content.len() as u64)
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut read_content)
+            .unwrap();
+        assert_eq!(read_content, content);
+
+        // Objects missing from the object directory can't be streamed
+        let missing_id =
+            gix::ObjectId::from_hex(b"0123456789012345678901234567890123456789").unwrap();
+        assert!(backend.open_loose_blob(&missing_id, 1).unwrap().is_none());
+
+        // read_file() streams large loose objects too
+        let mut read_content = String::new();
+        backend
+            .read_file(path, &file_id)
+            .block_on()
+            .unwrap()
+            .read_to_string(&mut read_content)
+            .unwrap();
+        assert_eq!(read_content, content);
+    }
+
     // Not using testutils::user_settings() because there is a dependency cycle
     // 'jj_lib (1) -> testutils -> jj_lib (2)' which creates another distinct
     // UserSettings type. testutils returns jj_lib (2)'s UserSettings, whereas
@@ -0,0 +1,444 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Git LFS pointer files.
+//!
+//! Repositories using Git LFS store pointer files in place of large file
+//! contents. See <https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md>
+//! for the format.
+//!
+//! Files with the `lfs` attribute are converted like Git LFS does: the
+//! contents of files on disk are moved to the local LFS storage in
+//! `.git/lfs/objects` and replaced by pointers when snapshotting, and pointers
+//! are replaced by the contents when checking out.
+
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::str;
+
+use hex::ToHex as _;
+use sha2::Digest as _;
+use sha2::Sha256;
+use tempfile::NamedTempFile;
+
+use crate::attributes::FileAttributes;
+use crate::config::ConfigGetError;
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::lock::LockManager;
+use crate::repo_path::RepoPath;
+use crate::settings::UserSettings;
+
+/// The first line of a Git LFS pointer file.
+const VERSION_LINE: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Pointer files larger than this are not considered valid by Git LFS.
+pub const MAX_POINTER_SIZE: usize = 1024;
+
+/// Parsed Git LFS pointer, which refers to the actual file contents stored in
+/// an LFS server.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct LfsPointer {
+    /// Hex-encoded SHA-256 hash of the file contents.
+    pub oid: String,
+    /// Size of the file contents in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Parses the `content` of a file as a Git LFS pointer. Returns `None` if
+    /// the `content` isn't a valid pointer.
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() > MAX_POINTER_SIZE {
+            return None;
+        }
+        let text = str::from_utf8(content).ok()?;
+        let mut lines = text.strip_suffix('\n')?.split('\n');
+        if lines.next()? != VERSION_LINE {
+            return None;
+        }
+        let mut oid = None;
+        let mut size = None;
+        let mut prev_key = "";
+        for line in lines {
+            let (key, value) = line.split_once(' ')?;
+            // Keys other than "version" must be sorted and unique.
+            if key <= prev_key {
+                return None;
+            }
+            prev_key = key;
+            match key {
+                "oid" => {
+                    let hash = value.strip_prefix("sha256:")?;
+                    if hash.len() != 64
+                        || !hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+                    {
+                        return None;
+                    }
+                    oid = Some(hash.to_owned());
+                }
+                "size" => {
+                    if !value.bytes().all(|b| b.is_ascii_digit()) {
+                        return None;
+                    }
+                    size = Some(value.parse().ok()?);
+                }
+                _ => {}
+            }
+        }
+        Some(LfsPointer {
+            oid: oid?,
+            size: size?,
+        })
+    }
+
+    /// Formats this as the contents of a pointer file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "{VERSION_LINE}\noid sha256:{oid}\nsize {size}\n",
+            oid = self.oid,
+            size = self.size
+        )
+        .into_bytes()
+    }
+}
+
+/// Settings for converting Git LFS files in the working copy.
+#[derive(Clone, Debug, Default)]
+pub struct LfsSettings {
+    /// Git executable to download objects missing from the local LFS storage
+    /// with, or `None` to check out their pointers instead.
+    fetch_with: Option<PathBuf>,
+    attributes: FileAttributes,
+}
+
+impl LfsSettings {
+    /// Reads `git.fetch-lfs-objects` and the `lfs` attribute of the
+    /// `[attributes]` table.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let fetch_with = settings
+            .get_bool("git.fetch-lfs-objects")?
+            .then(|| settings.get("git.executable-path"))
+            .transpose()?;
+        Ok(LfsSettings {
+            fetch_with,
+            attributes: FileAttributes::from_settings(settings)?,
+        })
+    }
+
+    /// Uses the `lfs` attribute of `attributes` to select the files to
+    /// convert.
+    pub fn with_attributes(mut self, attributes: FileAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Whether the file at `path` is stored as a pointer.
+    pub fn is_lfs(&self, path: &RepoPath) -> bool {
+        self.attributes.lfs(path)
+    }
+
+    /// The Git executable to download missing objects with, if enabled.
+    pub fn fetch_with(&self) -> Option<&Path> {
+        self.fetch_with.as_deref()
+    }
+}
+
+/// The local storage of Git LFS objects of a Git repository.
+#[derive(Clone, Debug)]
+pub struct LfsObjectStore {
+    git_dir: PathBuf,
+    lock_manager: LockManager,
+}
+
+impl LfsObjectStore {
+    /// Uses the storage in the `lfs` directory of `git_dir`.
+    pub fn new(git_dir: PathBuf) -> Self {
+        LfsObjectStore {
+            git_dir,
+            lock_manager: LockManager::new(),
+        }
+    }
+
+    /// Sets the lock manager whose mode decides how objects are written.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
+    fn object_path(&self, oid: &str) -> PathBuf {
+        self.git_dir
+            .join("lfs")
+            .join("objects")
+            .join(&oid[0..2])
+            .join(&oid[2..4])
+            .join(oid)
+    }
+
+    /// Moves `contents` to the storage and returns the pointer to them.
+    pub fn clean(&self, contents: &mut dyn Read) -> io::Result<LfsPointer> {
+        let temp_dir = self.git_dir.join("lfs").join("tmp");
+        fs::create_dir_all(&temp_dir)?;
+        let temp_file = NamedTempFile::new_in(&temp_dir)?;
+        let mut file = temp_file.as_file();
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        let mut buf = vec![0; 1 << 14];
+        loop {
+            let bytes_read = contents.read(&mut buf)?;
+            if bytes_read == 0 {
+                break;
+            }
+            file.write_all(&buf[..bytes_read])?;
+            hasher.update(&buf[..bytes_read]);
+            size += bytes_read as u64;
+        }
+        let pointer = LfsPointer {
+            oid: hasher.finalize().encode_hex(),
+            size,
+        };
+        let object_path = self.object_path(&pointer.oid);
+        fs::create_dir_all(object_path.parent().unwrap())?;
+        persist_content_addressed_temp_file(temp_file, object_path, self.lock_manager.mode())?;
+        Ok(pointer)
+    }
+
+    /// Converts the contents of a file on disk to the contents to store: a
+    /// pointer to the contents moved to the storage. Files which are already
+    /// pointers are stored as they are.
+    pub fn to_pointer(&self, contents: &mut dyn Read) -> io::Result<Vec<u8>> {
+        let prefix = read_pointer_prefix(contents)?;
+        if LfsPointer::parse(&prefix).is_some() {
+            return Ok(prefix);
+        }
+        let pointer = self.clean(&mut prefix.as_slice().chain(contents))?;
+        Ok(pointer.to_bytes())
+    }
+
+    /// Converts stored contents to the contents to write to disk. Pointers
+    /// are replaced by the contents they refer to, which are downloaded with
+    /// `fetch_with` if they aren't in the storage. Pointers to unavailable
+    /// contents are written as they are.
+    pub fn from_pointer<'a>(
+        &self,
+        contents: &'a mut dyn Read,
+        fetch_with: Option<&Path>,
+    ) -> io::Result<Box<dyn Read + 'a>> {
+        let prefix = read_pointer_prefix(contents)?;
+        let Some(pointer) = LfsPointer::parse(&prefix) else {
+            return Ok(Box::new(io::Cursor::new(prefix).chain(contents)));
+        };
+        if let Some(file) = self.open(&pointer)? {
+            return Ok(Box::new(file));
+        }
+        if let Some(git_executable) = fetch_with {
+            self.fetch(&pointer, git_executable)?;
+            if let Some(file) = self.open(&pointer)? {
+                return Ok(Box::new(file));
+            }
+        }
+        Ok(Box::new(io::Cursor::new(prefix)))
+    }
+
+    /// Opens the contents `pointer` refers to. Returns `None` if they aren't
+    /// in the storage.
+    pub fn open(&self, pointer: &LfsPointer) -> io::Result<Option<File>> {
+        match File::open(self.object_path(&pointer.oid)) {
+            Ok(file) => Ok(Some(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Downloads the contents `pointer` refers to from the LFS server into
+    /// the storage. The server and credentials are configured in Git as
+    /// usual for `git lfs`.
+    pub fn fetch(&self, pointer: &LfsPointer, git_executable: &Path) -> io::Result<()> {
+        let mut child = Command::new(git_executable)
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(["lfs", "smudge"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let written = child.stdin.take().unwrap().write_all(&pointer.to_bytes());
+        let output = child.wait_with_output()?;
+        written?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "git lfs smudge failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reads as much of `contents` as a pointer can be long, plus one byte to
+/// tell if it's longer.
+fn read_pointer_prefix(contents: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut prefix = vec![];
+    contents
+        .take(MAX_POINTER_SIZE as u64 + 1)
+        .read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    #[test]
+    fn test_parse_pointer() {
+        let pointer = LfsPointer {
+            oid: OID.to_owned(),
+            size: 12345,
+        };
+        let content =
+            format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize 12345\n");
+        assert_eq!(LfsPointer::parse(content.as_bytes()), Some(pointer.clone()));
+        assert_eq!(pointer.to_bytes(), content.as_bytes());
+
+        // Unknown keys are allowed
+        let content = format!(
+            "version https://git-lfs.github.com/spec/v1\next-0-foo sha256:{OID}\noid \
+             sha256:{OID}\nsize 12345\n"
+        );
+        assert_eq!(LfsPointer::parse(content.as_bytes()), Some(pointer));
+    }
+
+    #[test]
+    fn test_object_store() {
+        let temp_dir = crate::tests::new_temp_dir();
+        let store = LfsObjectStore::new(temp_dir.path().to_owned());
+        let pointer = store.clean(&mut &b"large\n"[..]).unwrap();
+        assert_eq!(
+            pointer,
+            LfsPointer {
+                oid: "9bfce334a37bd1bc1d36b0370195b31fe9a9389dd43d0873891a3544ef1140a1".to_owned(),
+                size: 6,
+            }
+        );
+        let mut contents = vec![];
+        store
+            .open(&pointer)
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, b"large\n");
+
+        let missing = LfsPointer {
+            oid: OID.to_owned(),
+            size: 1,
+        };
+        assert!(store.open(&missing).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_convert_pointer() {
+        let temp_dir = crate::tests::new_temp_dir();
+        let store = LfsObjectStore::new(temp_dir.path().to_owned());
+        let read_all = |mut reader: Box<dyn Read + '_>| {
+            let mut contents = vec![];
+            reader.read_to_end(&mut contents).unwrap();
+            contents
+        };
+
+        let pointer = store.to_pointer(&mut &b"large\n"[..]).unwrap();
+        assert!(LfsPointer::parse(&pointer).is_some());
+        assert_eq!(store.to_pointer(&mut pointer.as_slice()).unwrap(), pointer);
+        assert_eq!(
+            read_all(store.from_pointer(&mut pointer.as_slice(), None).unwrap()),
+            b"large\n"
+        );
+
+        // Contents which aren't pointers are written as they are
+        let contents = "x".repeat(MAX_POINTER_SIZE * 2);
+        assert_eq!(
+            read_all(store.from_pointer(&mut contents.as_bytes(), None).unwrap()),
+            contents.as_bytes()
+        );
+
+        // Pointers to missing objects are written as they are
+        let missing = LfsPointer {
+            oid: OID.to_owned(),
+            size: 1,
+        }
+        .to_bytes();
+        assert_eq!(
+            read_all(store.from_pointer(&mut missing.as_slice(), None).unwrap()),
+            missing
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_pointer() {
+        let parse = |content: &str| LfsPointer::parse(content.as_bytes());
+        // Not a pointer
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("hello\n"), None);
+        // Missing trailing newline
+        assert_eq!(
+            parse(&format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize 1"
+            )),
+            None
+        );
+        // Missing size
+        assert_eq!(
+            parse(&format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\n"
+            )),
+            None
+        );
+        // Unsorted keys
+        assert_eq!(
+            parse(&format!(
+                "version https://git-lfs.github.com/spec/v1\nsize 1\noid sha256:{OID}\n"
+            )),
+            None
+        );
+        // Invalid oid
+        assert_eq!(
+            parse("version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 1\n"),
+            None
+        );
+        // Invalid size
+        assert_eq!(
+            parse(&format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize +1\n"
+            )),
+            None
+        );
+        // Too large
+        let padding = "x".repeat(MAX_POINTER_SIZE);
+        assert_eq!(
+            parse(&format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize 1\nz \
+                 {padding}\n"
+            )),
+            None
+        );
+    }
+}
@@ -75,6 +75,51 @@ fn reachable_targets<N>(edges: &[GraphEdge<N>]) -> impl DoubleEndedIterator<Item
         .map(|edge| &edge.target)
 }
 
+/// Prepares the edges of a node for rendering.
+///
+/// The returned edges are keyed by `(id, is_synthetic)`. Multiple missing
+/// edges are collapsed into one since the renderer only needs to know that
+/// some parents are missing. If `use_elided_nodes` is true, indirect edges are
+/// turned into direct edges to synthetic nodes, and the targets of these
+/// synthetic nodes are returned so the caller can render them as "elided"
+/// nodes pointing to the real targets.
+pub fn elide_graph_edges<N>(
+    edges: Vec<GraphEdge<N>>,
+    use_elided_nodes: bool,
+) -> (Vec<GraphEdge<(N, bool)>>, Vec<N>)
+where
+    N: Clone,
+{
+    let mut graph_edges = vec![];
+    // TODO: Should we update revset.iter_graph() to yield a `has_missing` flag
+    // instead of all the missing edges since we don't care about
+    // where they point here anyway?
+    let mut missing_edge_id = None;
+    let mut elided_targets = vec![];
+    for edge in edges {
+        match edge.edge_type {
+            GraphEdgeType::Missing => {
+                missing_edge_id = Some(edge.target);
+            }
+            GraphEdgeType::Direct => {
+                graph_edges.push(GraphEdge::direct((edge.target, false)));
+            }
+            GraphEdgeType::Indirect => {
+                if use_elided_nodes {
+                    elided_targets.push(edge.target.clone());
+                    graph_edges.push(GraphEdge::direct((edge.target, true)));
+                } else {
+                    graph_edges.push(GraphEdge::indirect((edge.target, false)));
+                }
+            }
+        }
+    }
+    if let Some(missing_edge_id) = missing_edge_id {
+        graph_edges.push(GraphEdge::missing((missing_edge_id, false)));
+    }
+    (graph_edges, elided_targets)
+}
+
 /// Creates new graph in which nodes and edges are reversed.
 pub fn reverse_graph<N, ID: Clone + Eq + Hash, E>(
     input: impl Iterator<Item = Result<GraphNode<N, ID>, E>>,
@@ -415,6 +460,32 @@ mod tests {
         ");
     }
 
+    #[test]
+    fn test_elide_graph_edges() {
+        let edges = vec![missing('X'), direct('C'), indirect('B'), missing('Y')];
+        let (graph_edges, elided_targets) = elide_graph_edges(edges.clone(), false);
+        assert_eq!(
+            graph_edges,
+            vec![
+                GraphEdge::direct(('C', false)),
+                GraphEdge::indirect(('B', false)),
+                GraphEdge::missing(('Y', false)),
+            ]
+        );
+        assert!(elided_targets.is_empty());
+
+        let (graph_edges, elided_targets) = elide_graph_edges(edges, true);
+        assert_eq!(
+            graph_edges,
+            vec![
+                GraphEdge::direct(('C', false)),
+                GraphEdge::direct(('B', true)),
+                GraphEdge::missing(('Y', false)),
+            ]
+        );
+        assert_eq!(elided_targets, vec!['B']);
+    }
+
     fn topo_grouped<I, E>(graph_iter: I) -> TopoGroupedGraphIterator<char, I::IntoIter>
     where
         I: IntoIterator<Item = Result<GraphNode<char>, E>>,
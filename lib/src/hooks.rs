@@ -0,0 +1,251 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Repo-local hook scripts.
+//!
+//! Hooks are executables stored in `.jj/hooks/` of a workspace. They receive a
+//! JSON payload describing the event on stdin, and their stdout is redirected
+//! to stderr. A hook exiting with non-zero status makes the `pre-*` events
+//! fail. Since hooks run arbitrary code checked into the workspace, they have
+//! to be enabled explicitly by `hooks.enabled`.
+
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::ExitStatus;
+use std::process::Stdio;
+
+use thiserror::Error;
+
+use crate::config::ConfigGetError;
+use crate::settings::UserSettings;
+
+/// Event which a hook can be attached to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HookKind {
+    /// Run before an operation is committed.
+    PreCommitOperation,
+    /// Run after the working copy has been updated.
+    PostCheckout,
+    /// Run before refs are pushed to a remote.
+    PrePush,
+}
+
+impl HookKind {
+    /// File name of the hook in the hooks directory.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            HookKind::PreCommitOperation => "pre-commit-operation",
+            HookKind::PostCheckout => "post-checkout",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+
+    /// Whether the failure of this hook should abort the action. Hooks run
+    /// after the action has completed can't abort it.
+    pub fn can_abort(self) -> bool {
+        match self {
+            HookKind::PreCommitOperation | HookKind::PrePush => true,
+            HookKind::PostCheckout => false,
+        }
+    }
+}
+
+/// Error while running a hook.
+#[derive(Debug, Error)]
+pub enum HookError {
+    /// The hook couldn't be started or communicated with.
+    #[error("Failed to run hook {}", path.display())]
+    Io {
+        /// Path to the hook.
+        path: PathBuf,
+        /// Underlying error.
+        source: io::Error,
+    },
+    /// The hook exited with non-zero status.
+    #[error("Hook {} failed with {status}", path.display())]
+    Failed {
+        /// Path to the hook.
+        path: PathBuf,
+        /// Exit status of the hook.
+        status: ExitStatus,
+    },
+}
+
+/// Hooks of a workspace.
+#[derive(Clone, Debug)]
+pub struct Hooks {
+    workspace_root: PathBuf,
+    hooks_dir: PathBuf,
+    enabled: bool,
+}
+
+impl Hooks {
+    /// Creates hooks for the workspace at `workspace_root`, which are stored
+    /// in `.jj/hooks/`. Hooks are disabled if `hooks.enabled` is false.
+    pub fn new(workspace_root: &Path, settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(Hooks {
+            workspace_root: workspace_root.to_owned(),
+            hooks_dir: workspace_root.join(".jj").join("hooks"),
+            enabled: settings.get_bool("hooks.enabled")?,
+        })
+    }
+
+    /// Returns the path to the executable for the `kind` of hook, if hooks
+    /// are enabled and the hook exists.
+    pub fn find(&self, kind: HookKind) -> Option<PathBuf> {
+        if !self.enabled {
+            return None;
+        }
+        let path = self.hooks_dir.join(kind.file_name());
+        let metadata = fs::metadata(&path).ok()?;
+        is_executable(&metadata).then_some(path)
+    }
+
+    /// Runs the `kind` of hook, if any, in the workspace root with the
+    /// `payload` written to its stdin. The hook's stdout is redirected to
+    /// stderr so that it doesn't mix with the command output.
+    pub fn run(&self, kind: HookKind, payload: &serde_json::Value) -> Result<(), HookError> {
+        let Some(path) = self.find(kind) else {
+            return Ok(());
+        };
+        let to_io_error = |source| HookError::Io {
+            path: path.clone(),
+            source,
+        };
+        let mut child = Command::new(&path)
+            .current_dir(&self.workspace_root)
+            .stdin(Stdio::piped())
+            .stdout(io::stderr())
+            .spawn()
+            .map_err(to_io_error)?;
+        let mut stdin = child.stdin.take().unwrap();
+        match stdin.write_all(payload.to_string().as_bytes()) {
+            // The hook may exit without reading its input.
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(to_io_error(err));
+            }
+            _ => {}
+        }
+        drop(stdin);
+        let status = child.wait().map_err(to_io_error)?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(HookError::Failed { path, status })
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    metadata.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::config::ConfigLayer;
+    use crate::config::ConfigSource;
+    use crate::config::StackedConfig;
+    use crate::tests::new_temp_dir;
+
+    fn settings(enabled: bool) -> UserSettings {
+        let mut config = StackedConfig::with_defaults();
+        let mut layer = ConfigLayer::empty(ConfigSource::User);
+        layer.set_value("hooks.enabled", enabled).unwrap();
+        config.add_layer(layer);
+        UserSettings::from_config(config).unwrap()
+    }
+
+    fn write_hook(workspace_root: &Path, kind: HookKind, script: &str) {
+        let hooks_dir = workspace_root.join(".jj").join("hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        let path = hooks_dir.join(kind.file_name());
+        fs::write(&path, format!("#!/bin/sh\n{script}")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let temp_dir = new_temp_dir();
+        let root = temp_dir.path();
+        write_hook(root, HookKind::PostCheckout, "cat > payload\n");
+        write_hook(root, HookKind::PrePush, "exit 1\n");
+        let hooks = Hooks::new(root, &settings(true)).unwrap();
+
+        // Missing hook is ignored
+        assert_eq!(hooks.find(HookKind::PreCommitOperation), None);
+        assert_matches!(
+            hooks.run(HookKind::PreCommitOperation, &serde_json::json!({})),
+            Ok(())
+        );
+
+        let payload = serde_json::json!({"commit_id": "abc"});
+        assert_matches!(hooks.run(HookKind::PostCheckout, &payload), Ok(()));
+        assert_eq!(
+            fs::read_to_string(root.join("payload")).unwrap(),
+            payload.to_string()
+        );
+
+        assert_matches!(
+            hooks.run(HookKind::PrePush, &payload),
+            Err(HookError::Failed { status, .. }) if status.code() == Some(1)
+        );
+    }
+
+    #[test]
+    fn test_disabled_hooks() {
+        let temp_dir = new_temp_dir();
+        let root = temp_dir.path();
+        write_hook(root, HookKind::PrePush, "exit 1\n");
+        let hooks = Hooks::new(root, &settings(false)).unwrap();
+        assert_eq!(hooks.find(HookKind::PrePush), None);
+        assert_matches!(hooks.run(HookKind::PrePush, &serde_json::json!({})), Ok(()));
+    }
+
+    #[test]
+    fn test_hooks_disabled_by_default() {
+        let temp_dir = new_temp_dir();
+        let root = temp_dir.path();
+        write_hook(root, HookKind::PrePush, "exit 1\n");
+        let settings = UserSettings::from_config(StackedConfig::with_defaults()).unwrap();
+        let hooks = Hooks::new(root, &settings).unwrap();
+        assert_eq!(hooks.find(HookKind::PrePush), None);
+    }
+
+    #[test]
+    fn test_non_executable_hook() {
+        let temp_dir = new_temp_dir();
+        let root = temp_dir.path();
+        write_hook(root, HookKind::PrePush, "exit 1\n");
+        let path = root.join(".jj").join("hooks").join("pre-push");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let hooks = Hooks::new(root, &settings(true)).unwrap();
+        assert_eq!(hooks.find(HookKind::PrePush), None);
+    }
+}
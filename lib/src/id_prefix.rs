@@ -14,6 +14,8 @@
 
 #![allow(missing_docs)]
 
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::iter;
 use std::marker::PhantomData;
 use std::rc::Rc;
@@ -31,7 +33,9 @@ use crate::object_id::ObjectId;
 use crate::object_id::PrefixResolution;
 use crate::repo::Repo;
 use crate::revset::DefaultSymbolResolver;
+use crate::revset::Revset;
 use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
 use crate::revset::RevsetExtensions;
 use crate::revset::RevsetResolutionError;
 use crate::revset::SymbolResolverExtension;
@@ -43,6 +47,8 @@ pub enum IdPrefixIndexLoadError {
     Resolution(#[from] RevsetResolutionError),
     #[error("Failed to evaluate short-prefixes disambiguation revset")]
     Evaluation(#[from] RevsetEvaluationError),
+    #[error("Failed to load index of visible commits")]
+    VisibleCommits(#[source] RevsetEvaluationError),
 }
 
 struct DisambiguationData {
@@ -68,20 +74,41 @@ impl DisambiguationData {
                 .expression
                 .resolve_user_expression(repo, &symbol_resolver)?
                 .evaluate(repo)?;
+            Ok(Indexes::build(revset.as_ref())?)
+        })
+    }
+}
 
-            let commit_change_ids: Vec<_> = revset.commit_change_ids().try_collect()?;
-            let mut commit_index = IdIndex::with_capacity(commit_change_ids.len());
-            let mut change_index = IdIndex::with_capacity(commit_change_ids.len());
-            for (i, (commit_id, change_id)) in commit_change_ids.iter().enumerate() {
-                let i: u32 = i.try_into().unwrap();
-                commit_index.insert(commit_id, i);
-                change_index.insert(change_id, i);
-            }
-            Ok(Indexes {
-                commit_change_ids,
-                commit_index: commit_index.build(),
-                change_index: change_index.build(),
-            })
+impl Indexes {
+    fn build(revset: &dyn Revset) -> Result<Self, RevsetEvaluationError> {
+        let commit_change_ids: Vec<_> = revset.commit_change_ids().try_collect()?;
+        let mut commit_index = IdIndex::with_capacity(commit_change_ids.len());
+        let mut change_index = IdIndex::with_capacity(commit_change_ids.len());
+        for (i, (commit_id, change_id)) in commit_change_ids.iter().enumerate() {
+            let i: u32 = i.try_into().unwrap();
+            commit_index.insert(commit_id, i);
+            change_index.insert(change_id, i);
+        }
+        Ok(Indexes {
+            commit_change_ids,
+            commit_index: commit_index.build(),
+            change_index: change_index.build(),
+        })
+    }
+}
+
+/// Index of the visible commits, tagged with the view heads it was built from.
+struct VisibleIndexes {
+    heads: HashSet<CommitId>,
+    indexes: Indexes,
+}
+
+impl VisibleIndexes {
+    fn load(repo: &dyn Repo) -> Result<Self, RevsetEvaluationError> {
+        let revset = RevsetExpression::all().evaluate(repo)?;
+        Ok(VisibleIndexes {
+            heads: repo.view().heads().clone(),
+            indexes: Indexes::build(revset.as_ref())?,
         })
     }
 }
@@ -112,6 +139,7 @@ impl IdIndexSourceEntry<ChangeId> for &'_ (CommitId, ChangeId) {
 #[derive(Default)]
 pub struct IdPrefixContext {
     disambiguation: Option<DisambiguationData>,
+    visible_indexes: RefCell<Option<Rc<VisibleIndexes>>>,
     extensions: Arc<RevsetExtensions>,
 }
 
@@ -119,6 +147,7 @@ impl IdPrefixContext {
     pub fn new(extensions: Arc<RevsetExtensions>) -> Self {
         Self {
             disambiguation: None,
+            visible_indexes: RefCell::new(None),
             extensions,
         }
     }
@@ -139,19 +168,55 @@ impl IdPrefixContext {
         } else {
             None
         };
-        Ok(IdPrefixIndex { indexes })
+        Ok(IdPrefixIndex {
+            indexes,
+            visible_indexes: Some(&self.visible_indexes),
+        })
     }
 }
 
 /// Loaded index to disambiguate commit/change IDs.
 pub struct IdPrefixIndex<'a> {
     indexes: Option<&'a Indexes>,
+    visible_indexes: Option<&'a RefCell<Option<Rc<VisibleIndexes>>>>,
 }
 
 impl IdPrefixIndex<'_> {
     /// Returns an empty index that just falls back to a provided `repo`.
+    ///
+    /// Unlike an index populated from an [`IdPrefixContext`], this doesn't
+    /// cache the index of visible commits.
     pub const fn empty() -> IdPrefixIndex<'static> {
-        IdPrefixIndex { indexes: None }
+        IdPrefixIndex {
+            indexes: None,
+            visible_indexes: None,
+        }
+    }
+
+    /// Returns the index of the commits visible in `repo`.
+    ///
+    /// The index is cached in the context, and rebuilt whenever the view
+    /// heads of `repo` differ from the ones it was built from.
+    fn visible_indexes(
+        &self,
+        repo: &dyn Repo,
+    ) -> Result<Rc<VisibleIndexes>, IdPrefixIndexLoadError> {
+        let load = || {
+            VisibleIndexes::load(repo)
+                .map(Rc::new)
+                .map_err(IdPrefixIndexLoadError::VisibleCommits)
+        };
+        let Some(cache) = self.visible_indexes else {
+            return load();
+        };
+        if let Some(visible) = &*cache.borrow() {
+            if visible.heads == *repo.view().heads() {
+                return Ok(visible.clone());
+            }
+        }
+        let visible = load()?;
+        *cache.borrow_mut() = Some(visible.clone());
+        Ok(visible)
     }
 
     /// Resolve an unambiguous commit ID prefix.
@@ -228,6 +293,112 @@ impl IdPrefixIndex<'_> {
     }
 }
 
+/// Resolves commit/change ID prefixes, preferring the commits visible in the
+/// repo.
+///
+/// Unlike [`IdPrefixIndex`], a prefix that is ambiguous only because of hidden
+/// commits resolves to the visible commit. Hidden commits aren't matched at all
+/// unless [`include_hidden()`](Self::include_hidden) is set, in which case they
+/// are used as a fallback when no visible commit matches or the prefix is
+/// ambiguous within the visible commits. Change IDs of hidden commits are never
+/// resolved.
+pub struct VisibleIdPrefixResolver<'a> {
+    index: &'a IdPrefixIndex<'a>,
+    include_hidden: bool,
+}
+
+impl<'a> VisibleIdPrefixResolver<'a> {
+    pub fn new(index: &'a IdPrefixIndex<'a>) -> Self {
+        VisibleIdPrefixResolver {
+            index,
+            include_hidden: false,
+        }
+    }
+
+    /// Whether to fall back to hidden commits when resolving commit IDs.
+    pub fn include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Resolve an unambiguous commit ID prefix.
+    pub fn resolve_commit_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &HexPrefix,
+    ) -> Result<PrefixResolution<CommitId>, IdPrefixIndexLoadError> {
+        if let Some(indexes) = self.index.indexes {
+            let resolution = indexes
+                .commit_index
+                .resolve_prefix_to_key(&*indexes.commit_change_ids, prefix);
+            if let PrefixResolution::SingleMatch(id) = resolution {
+                // The disambiguation set may be loaded from a different repo,
+                // and may contain hidden commits. If the commit isn't found,
+                // other commits may still match the prefix.
+                let found = if self.include_hidden {
+                    repo.index().has_id(&id)
+                } else {
+                    let visible = self.index.visible_indexes(repo)?;
+                    visible
+                        .indexes
+                        .commit_index
+                        .lookup_exact(&*visible.indexes.commit_change_ids, &id)
+                        .is_some()
+                };
+                if found {
+                    return Ok(PrefixResolution::SingleMatch(id));
+                }
+            }
+        }
+        if self.include_hidden {
+            // Look up the visible commits only if the prefix is ambiguous, as
+            // building their index requires a scan of the whole repo.
+            match repo.index().resolve_commit_id_prefix(prefix) {
+                PrefixResolution::AmbiguousMatch => {}
+                resolution => return Ok(resolution),
+            }
+        }
+        let visible = self.index.visible_indexes(repo)?;
+        let resolution = visible
+            .indexes
+            .commit_index
+            .resolve_prefix_to_key(&*visible.indexes.commit_change_ids, prefix);
+        match resolution {
+            PrefixResolution::NoMatch if self.include_hidden => {
+                Ok(PrefixResolution::AmbiguousMatch)
+            }
+            resolution => Ok(resolution),
+        }
+    }
+
+    /// Resolve an unambiguous change ID prefix to the visible commit IDs.
+    pub fn resolve_change_prefix(
+        &self,
+        repo: &dyn Repo,
+        prefix: &HexPrefix,
+    ) -> Result<PrefixResolution<Vec<CommitId>>, IdPrefixIndexLoadError> {
+        match self.index.resolve_change_prefix(repo, prefix) {
+            // The change id index may report ambiguity caused by hidden
+            // commits. Retry within the visible commits.
+            PrefixResolution::AmbiguousMatch => {
+                let visible = self.index.visible_indexes(repo)?;
+                let resolution = visible
+                    .indexes
+                    .change_index
+                    .resolve_prefix_to_key(&*visible.indexes.commit_change_ids, prefix);
+                Ok(match resolution {
+                    PrefixResolution::SingleMatch(change_id) => repo
+                        .resolve_change_id(&change_id)
+                        .map_or(PrefixResolution::NoMatch, PrefixResolution::SingleMatch),
+                    PrefixResolution::AmbiguousMatch => PrefixResolution::AmbiguousMatch,
+                    PrefixResolution::NoMatch => PrefixResolution::NoMatch,
+                })
+            }
+            resolution => Ok(resolution),
+        }
+    }
+}
+
 /// In-memory immutable index to do prefix lookup of key `K` through `P`.
 ///
 /// In a nutshell, this is a mapping of `K` -> `P` -> `S::Entry` where `S:
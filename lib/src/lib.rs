@@ -30,24 +30,34 @@ pub mod content_hash;
 
 pub mod absorb;
 pub mod annotate;
+pub mod attributes;
 pub mod backend;
+pub mod bisect;
+pub mod cancellation;
+pub mod clock;
 pub mod commit;
 pub mod commit_builder;
 pub mod config;
 mod config_resolver;
 pub mod conflicts;
 pub mod copies;
+#[cfg(unix)]
+pub mod daemon;
 pub mod dag_walk;
 pub mod default_index;
 pub mod default_submodule_store;
 pub mod diff;
+pub mod diff_presentation;
 pub mod dsl_util;
+pub mod eol;
 pub mod extensions_map;
+pub mod file_history;
 pub mod file_util;
 pub mod files;
 pub mod fileset;
 mod fileset_parser;
 pub mod fmt_util;
+pub mod fsck;
 pub mod fsmonitor;
 #[cfg(feature = "git")]
 pub mod git;
@@ -65,12 +75,14 @@ pub mod git {
 }
 #[cfg(feature = "git")]
 pub mod git_backend;
+pub mod git_lfs;
 #[cfg(feature = "git")]
 mod git_subprocess;
 pub mod gitignore;
 pub mod gpg_signing;
 pub mod graph;
 pub mod hex_util;
+pub mod hooks;
 pub mod id_prefix;
 pub mod index;
 pub mod local_backend;
@@ -80,24 +92,35 @@ pub mod matchers;
 pub mod merge;
 pub mod merged_tree;
 pub mod object_id;
+pub mod op_export;
 pub mod op_heads_store;
 pub mod op_store;
+pub mod op_store_json;
 pub mod op_walk;
 pub mod operation;
+pub mod patch;
 #[expect(missing_docs)]
 pub mod protos;
 pub mod refs;
 pub mod repo;
 pub mod repo_path;
+pub mod resolution_cache;
 pub mod revset;
 mod revset_parser;
 pub mod rewrite;
+pub mod search;
 #[cfg(feature = "testing")]
 pub mod secret_backend;
+pub mod session;
 pub mod settings;
 pub mod signing;
 pub mod simple_op_heads_store;
 pub mod simple_op_store;
+pub mod speculative_repo;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_op_heads_store;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_op_store;
 pub mod ssh_signing;
 pub mod stacked_table;
 pub mod store;
@@ -105,7 +128,10 @@ pub mod str_util;
 pub mod submodule_store;
 #[cfg(feature = "testing")]
 pub mod test_signing_backend;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod time_util;
+pub mod trailer;
 pub mod transaction;
 pub mod tree;
 pub mod tree_builder;
@@ -15,9 +15,11 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::path::Path;
@@ -29,6 +31,7 @@ use blake2::Blake2b512;
 use blake2::Digest;
 use futures::stream;
 use futures::stream::BoxStream;
+use hex::ToHex as _;
 use pollster::FutureExt;
 use prost::Message;
 use tempfile::NamedTempFile;
@@ -55,17 +58,23 @@ use crate::backend::Timestamp;
 use crate::backend::Tree;
 use crate::backend::TreeId;
 use crate::backend::TreeValue;
+use crate::config::ConfigGetError;
 use crate::content_hash::blake2b_hash;
 use crate::file_util::persist_content_addressed_temp_file;
 use crate::index::Index;
+use crate::lock::LockManager;
 use crate::merge::MergeBuilder;
 use crate::object_id::ObjectId;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponentBuf;
+use crate::settings::HumanByteSize;
+use crate::settings::UserSettings;
 
 const COMMIT_ID_LENGTH: usize = 64;
 const CHANGE_ID_LENGTH: usize = 16;
+/// Files larger than this are split into chunks of this size by default.
+const DEFAULT_CHUNK_SIZE: u64 = 16 << 20;
 
 fn map_not_found_err(err: std::io::Error, id: &impl ObjectId) -> BackendError {
     if err.kind() == std::io::ErrorKind::NotFound {
@@ -87,12 +96,27 @@ fn to_other_err(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Bac
     BackendError::Other(err.into())
 }
 
+/// Reads the size above which files are split into chunks from the
+/// `local-backend.chunk-size` setting.
+pub fn chunk_size_from_settings(settings: &UserSettings) -> Result<u64, ConfigGetError> {
+    settings.get_value_with("local-backend.chunk-size", |value| {
+        let HumanByteSize(size) = value.try_into()?;
+        if size == 0 {
+            return Err("Chunk size must be greater than zero");
+        }
+        Ok(size)
+    })
+}
+
 #[derive(Debug)]
 pub struct LocalBackend {
     path: PathBuf,
     root_commit_id: CommitId,
     root_change_id: ChangeId,
     empty_tree_id: TreeId,
+    /// Files larger than this are stored as a list of chunks of this size.
+    chunk_size: u64,
+    lock_manager: LockManager,
 }
 
 impl LocalBackend {
@@ -106,6 +130,8 @@ impl LocalBackend {
         fs::create_dir(store_path.join("files")).unwrap();
         fs::create_dir(store_path.join("symlinks")).unwrap();
         fs::create_dir(store_path.join("conflicts")).unwrap();
+        fs::create_dir(store_path.join("chunked_files")).unwrap();
+        fs::create_dir(store_path.join("chunks")).unwrap();
         let backend = Self::load(store_path);
         let empty_tree_id = backend
             .write_tree(RepoPath::root(), &Tree::default())
@@ -126,13 +152,97 @@ impl LocalBackend {
             root_commit_id,
             root_change_id,
             empty_tree_id,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            lock_manager: LockManager::new(),
         }
     }
 
+    /// Sets the size above which files are split into chunks.
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        assert!(chunk_size > 0);
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the lock manager whose mode decides how objects are written.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
     fn file_path(&self, id: &FileId) -> PathBuf {
         self.path.join("files").join(id.hex())
     }
 
+    /// Path to the list of chunks of a file larger than the chunk size.
+    fn chunked_file_path(&self, id: &FileId) -> PathBuf {
+        self.path.join("chunked_files").join(id.hex())
+    }
+
+    fn chunk_path(&self, hex: &str) -> PathBuf {
+        self.path.join("chunks").join(hex)
+    }
+
+    /// Copies `contents` to a new temporary file. Returns the file, the hash of
+    /// the contents, and their size.
+    fn write_temp_file(
+        &self,
+        contents: &mut dyn Read,
+    ) -> BackendResult<(NamedTempFile, Vec<u8>, u64)> {
+        // TODO: Write temporary file in the destination directory (#5712)
+        let temp_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
+        let mut file = temp_file.as_file();
+        let mut hasher = Blake2b512::new();
+        let mut size = 0;
+        let mut buff: Vec<u8> = vec![0; 1 << 14];
+        loop {
+            let bytes_read = contents.read(&mut buff).map_err(to_other_err)?;
+            if bytes_read == 0 {
+                break;
+            }
+            let bytes = &buff[..bytes_read];
+            file.write_all(bytes).map_err(to_other_err)?;
+            hasher.update(bytes);
+            size += bytes_read as u64;
+        }
+        file.flush().map_err(to_other_err)?;
+        Ok((temp_file, hasher.finalize().to_vec(), size))
+    }
+
+    /// Splits the file written to `temp_file` into chunks and records their
+    /// hashes in the chunk list of the file `id`.
+    fn write_chunks(&self, id: &FileId, temp_file: NamedTempFile) -> BackendResult<()> {
+        let mut file = temp_file.reopen().map_err(to_other_err)?;
+        // Directories are missing in stores created before chunking was added.
+        fs::create_dir_all(self.path.join("chunks")).map_err(to_other_err)?;
+        fs::create_dir_all(self.path.join("chunked_files")).map_err(to_other_err)?;
+        let mut chunk_list = String::new();
+        loop {
+            let (chunk_file, chunk_hash, chunk_size) =
+                self.write_temp_file(&mut (&mut file).take(self.chunk_size))?;
+            if chunk_size == 0 {
+                break;
+            }
+            let chunk_hex: String = chunk_hash.encode_hex();
+            persist_content_addressed_temp_file(
+                chunk_file,
+                self.chunk_path(&chunk_hex),
+                self.lock_manager.mode(),
+            )
+            .map_err(to_other_err)?;
+            chunk_list.push_str(&chunk_hex);
+            chunk_list.push('\n');
+        }
+        let (list_file, _, _) = self.write_temp_file(&mut chunk_list.as_bytes())?;
+        persist_content_addressed_temp_file(
+            list_file,
+            self.chunked_file_path(id),
+            self.lock_manager.mode(),
+        )
+        .map_err(to_other_err)?;
+        Ok(())
+    }
+
     fn symlink_path(&self, id: &SymlinkId) -> PathBuf {
         self.path.join("symlinks").join(id.hex())
     }
@@ -185,9 +295,19 @@ impl Backend for LocalBackend {
     }
 
     async fn read_file(&self, _path: &RepoPath, id: &FileId) -> BackendResult<Box<dyn Read>> {
-        let path = self.file_path(id);
-        let file = File::open(path).map_err(|err| map_not_found_err(err, id))?;
-        Ok(Box::new(file))
+        match File::open(self.file_path(id)) {
+            Ok(file) => return Ok(Box::new(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(map_not_found_err(err, id)),
+        }
+        let chunk_list = fs::read_to_string(self.chunked_file_path(id))
+            .map_err(|err| map_not_found_err(err, id))?;
+        let chunk_paths = chunk_list.lines().map(|hex| self.chunk_path(hex)).collect();
+        Ok(Box::new(ChunkedFileReader {
+            id: id.clone(),
+            chunk_paths,
+            current: None,
+        }))
     }
 
     async fn write_file(
@@ -195,25 +315,19 @@ impl Backend for LocalBackend {
         _path: &RepoPath,
         contents: &mut (dyn Read + Send),
     ) -> BackendResult<FileId> {
-        // TODO: Write temporary file in the destination directory (#5712)
-        let temp_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
-        let mut file = temp_file.as_file();
-        let mut hasher = Blake2b512::new();
-        let mut buff: Vec<u8> = vec![0; 1 << 14];
-        loop {
-            let bytes_read = contents.read(&mut buff).map_err(to_other_err)?;
-            if bytes_read == 0 {
-                break;
-            }
-            let bytes = &buff[..bytes_read];
-            file.write_all(bytes).map_err(to_other_err)?;
-            hasher.update(bytes);
-        }
-        file.flush().map_err(to_other_err)?;
-        let id = FileId::new(hasher.finalize().to_vec());
-
-        persist_content_addressed_temp_file(temp_file, self.file_path(&id))
+        let (temp_file, hash, size) = self.write_temp_file(contents)?;
+        // The id is the hash of the whole content, whether it's chunked or not.
+        let id = FileId::new(hash);
+        if size > self.chunk_size {
+            self.write_chunks(&id, temp_file)?;
+        } else {
+            persist_content_addressed_temp_file(
+                temp_file,
+                self.file_path(&id),
+                self.lock_manager.mode(),
+            )
             .map_err(to_other_err)?;
+        }
         Ok(id)
     }
 
@@ -233,8 +347,12 @@ impl Backend for LocalBackend {
         hasher.update(target.as_bytes());
         let id = SymlinkId::new(hasher.finalize().to_vec());
 
-        persist_content_addressed_temp_file(temp_file, self.symlink_path(&id))
-            .map_err(to_other_err)?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            self.symlink_path(&id),
+            self.lock_manager.mode(),
+        )
+        .map_err(to_other_err)?;
         Ok(id)
     }
 
@@ -258,8 +376,12 @@ impl Backend for LocalBackend {
 
         let id = TreeId::new(blake2b_hash(tree).to_vec());
 
-        persist_content_addressed_temp_file(temp_file, self.tree_path(&id))
-            .map_err(to_other_err)?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            self.tree_path(&id),
+            self.lock_manager.mode(),
+        )
+        .map_err(to_other_err)?;
         Ok(id)
     }
 
@@ -283,8 +405,12 @@ impl Backend for LocalBackend {
 
         let id = ConflictId::new(blake2b_hash(conflict).to_vec());
 
-        persist_content_addressed_temp_file(temp_file, self.conflict_path(&id))
-            .map_err(to_other_err)?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            self.conflict_path(&id),
+            self.lock_manager.mode(),
+        )
+        .map_err(to_other_err)?;
         Ok(id)
     }
 
@@ -333,8 +459,12 @@ impl Backend for LocalBackend {
 
         let id = CommitId::new(blake2b_hash(&commit).to_vec());
 
-        persist_content_addressed_temp_file(temp_file, self.commit_path(&id))
-            .map_err(to_other_err)?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            self.commit_path(&id),
+            self.lock_manager.mode(),
+        )
+        .map_err(to_other_err)?;
         Ok((id, commit))
     }
 
@@ -352,6 +482,42 @@ impl Backend for LocalBackend {
     }
 }
 
+/// Reads the chunks of a large file one after another, only keeping the
+/// current chunk open.
+struct ChunkedFileReader {
+    id: FileId,
+    chunk_paths: VecDeque<PathBuf>,
+    current: Option<File>,
+}
+
+impl Read for ChunkedFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(file) = &mut self.current {
+                let bytes_read = file.read(buf)?;
+                if bytes_read > 0 || buf.is_empty() {
+                    return Ok(bytes_read);
+                }
+                self.current = None;
+            }
+            let Some(path) = self.chunk_paths.pop_front() else {
+                return Ok(0);
+            };
+            let file = File::open(&path).map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!(
+                        "Failed to open chunk {} of file {}: {err}",
+                        path.display(),
+                        self.id.hex()
+                    ),
+                )
+            })?;
+            self.current = Some(file);
+        }
+    }
+}
+
 #[expect(clippy::assigning_clones)]
 pub fn commit_to_proto(commit: &Commit) -> crate::protos::local_store::Commit {
     let mut proto = crate::protos::local_store::Commit::default();
@@ -546,6 +712,9 @@ mod tests {
     use pollster::FutureExt;
 
     use super::*;
+    use crate::config::ConfigLayer;
+    use crate::config::ConfigSource;
+    use crate::config::StackedConfig;
     use crate::tests::new_temp_dir;
 
     /// Test that parents get written correctly
@@ -602,6 +771,77 @@ mod tests {
         assert_eq!(root_merge_commit, commit);
     }
 
+    #[test]
+    fn write_file_chunked() {
+        let temp_dir = new_temp_dir();
+        let store_path = temp_dir.path();
+        let backend = LocalBackend::init(store_path).with_chunk_size(4);
+        let path = RepoPath::from_internal_string("file");
+        let read_file = |id: &FileId| {
+            let mut content = vec![];
+            let mut reader = backend.read_file(path, id).block_on().unwrap();
+            reader.read_to_end(&mut content).unwrap();
+            content
+        };
+
+        // Small files are stored as a whole
+        let small_id = backend
+            .write_file(path, &mut &b"abcd"[..])
+            .block_on()
+            .unwrap();
+        assert!(backend.file_path(&small_id).exists());
+        assert_eq!(read_file(&small_id), b"abcd");
+
+        // Large files are split, and the id is the hash of the whole content
+        let large_id = backend
+            .write_file(path, &mut &b"abcdefghij"[..])
+            .block_on()
+            .unwrap();
+        assert!(!backend.file_path(&large_id).exists());
+        assert!(backend.chunked_file_path(&large_id).exists());
+        assert_eq!(read_file(&large_id), b"abcdefghij");
+        let unchunked_backend = LocalBackend::load(store_path);
+        let unchunked_id = unchunked_backend
+            .write_file(path, &mut &b"abcdefghij"[..])
+            .block_on()
+            .unwrap();
+        assert_eq!(unchunked_id, large_id);
+
+        // Identical chunks are shared
+        let other_id = backend
+            .write_file(path, &mut &b"abcdefghXY"[..])
+            .block_on()
+            .unwrap();
+        assert_eq!(fs::read_dir(store_path.join("chunks")).unwrap().count(), 4);
+
+        // Missing chunks are reported when reading
+        fs::remove_dir_all(store_path.join("chunks")).unwrap();
+        let mut reader = backend.read_file(path, &other_id).block_on().unwrap();
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn chunk_size_from_config() {
+        let settings_with = |text: &str| {
+            let mut config = StackedConfig::with_defaults();
+            config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+            UserSettings::from_config(config).unwrap()
+        };
+        assert_eq!(
+            chunk_size_from_settings(&settings_with("")).unwrap(),
+            16 << 20
+        );
+        assert_eq!(
+            chunk_size_from_settings(&settings_with("local-backend.chunk-size = '1KiB'")).unwrap(),
+            1024
+        );
+        assert_eq!(
+            chunk_size_from_settings(&settings_with("local-backend.chunk-size = 4")).unwrap(),
+            4
+        );
+        assert!(chunk_size_from_settings(&settings_with("local-backend.chunk-size = 0")).is_err());
+    }
+
     fn create_signature() -> Signature {
         Signature {
             name: "Someone".to_string(),
@@ -17,6 +17,7 @@
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
@@ -31,16 +32,21 @@ use std::iter;
 use std::mem;
 use std::ops::Range;
 #[cfg(unix)]
+use std::os::unix::fs::MetadataExt as _;
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::slice;
+use std::sync::atomic;
+use std::sync::atomic::AtomicUsize;
 use std::sync::mpsc::channel;
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::time::UNIX_EPOCH;
 
+use bstr::BString;
 use either::Either;
 use futures::StreamExt;
 use itertools::EitherOrBoth;
@@ -67,11 +73,13 @@ use crate::backend::TreeValue;
 use crate::commit::Commit;
 use crate::conflicts;
 use crate::conflicts::choose_materialized_conflict_marker_len;
-use crate::conflicts::materialize_merge_result_to_bytes_with_marker_len;
+use crate::conflicts::materialize_file_conflict_to_bytes_with_marker_len;
 use crate::conflicts::materialize_tree_value;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MaterializedTreeValue;
 use crate::conflicts::MIN_CONFLICT_MARKER_LEN;
+use crate::eol::EolConversionMode;
+use crate::eol::EolSettings;
 use crate::file_util::check_symlink_support;
 use crate::file_util::try_symlink;
 #[cfg(feature = "watchman")]
@@ -79,14 +87,19 @@ use crate::fsmonitor::watchman;
 use crate::fsmonitor::FsmonitorSettings;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::WatchmanConfig;
+use crate::git_lfs::LfsObjectStore;
+use crate::git_lfs::LfsSettings;
 use crate::gitignore::GitIgnoreFile;
-use crate::lock::FileLock;
+use crate::lock::LockKind;
+use crate::lock::LockManager;
+use crate::lock::ManagedLock;
 use crate::matchers::DifferenceMatcher;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::FilesMatcher;
 use crate::matchers::IntersectionMatcher;
 use crate::matchers::Matcher;
 use crate::matchers::PrefixMatcher;
+use crate::merge::ConflictKind;
 use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeValue;
@@ -96,20 +109,24 @@ use crate::merged_tree::TreeDiffEntry;
 use crate::object_id::ObjectId;
 use crate::op_store::OperationId;
 use crate::op_store::WorkspaceId;
+use crate::repo_path::PathNormalization;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponent;
+use crate::settings::UserSettings;
 use crate::store::Store;
 use crate::tree::Tree;
 use crate::working_copy::CheckoutError;
 use crate::working_copy::CheckoutOptions;
 use crate::working_copy::CheckoutStats;
+use crate::working_copy::FileCacheStats;
 use crate::working_copy::LockedWorkingCopy;
 use crate::working_copy::ResetError;
 use crate::working_copy::SnapshotError;
 use crate::working_copy::SnapshotOptions;
 use crate::working_copy::SnapshotProgress;
 use crate::working_copy::SnapshotStats;
+use crate::working_copy::TrackingPatterns;
 use crate::working_copy::UntrackedReason;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
@@ -137,6 +154,9 @@ pub struct FileState {
     pub file_type: FileType,
     pub mtime: MillisSinceEpoch,
     pub size: u64,
+    /// Inode number of the file, or 0 if unknown (e.g. if the state was
+    /// recorded by an older version)
+    pub inode: u64,
     pub materialized_conflict_data: Option<MaterializedConflictData>,
     /* TODO: What else do we need here? Git stores a lot of fields.
      * TODO: Could possibly handle case-insensitive file systems keeping an
@@ -150,6 +170,9 @@ impl FileState {
         self.file_type == old_file_state.file_type
             && self.mtime == old_file_state.mtime
             && self.size == old_file_state.size
+            // A file replaced by another one with the same mtime and size
+            // can still be detected by its inode.
+            && (self.inode == old_file_state.inode || self.inode == 0 || old_file_state.inode == 0)
     }
 
     /// Indicates that a file exists in the tree but that it needs to be
@@ -163,6 +186,7 @@ impl FileState {
             file_type: FileType::Normal { executable },
             mtime: MillisSinceEpoch(0),
             size: 0,
+            inode: 0,
             materialized_conflict_data: None,
         }
     }
@@ -182,6 +206,7 @@ impl FileState {
             file_type: FileType::Normal { executable },
             mtime: mtime_from_metadata(metadata),
             size,
+            inode: inode_from_metadata(metadata),
             materialized_conflict_data,
         }
     }
@@ -194,6 +219,7 @@ impl FileState {
             file_type: FileType::Symlink,
             mtime: mtime_from_metadata(metadata),
             size: metadata.len(),
+            inode: inode_from_metadata(metadata),
             materialized_conflict_data: None,
         }
     }
@@ -203,6 +229,7 @@ impl FileState {
             file_type: FileType::GitSubmodule,
             mtime: MillisSinceEpoch(0),
             size: 0,
+            inode: 0,
             materialized_conflict_data: None,
         }
     }
@@ -379,6 +406,27 @@ impl<'a> FileStates<'a> {
         start..(start + len)
     }
 
+    /// Returns the sorted names of the direct children of `dir`. Entries under
+    /// each child directory are skipped over by binary search. Requires that
+    /// all entries share the same prefix `dir`.
+    fn child_names_at(&self, dir: &RepoPath) -> Vec<&'a str> {
+        fn entry_name<'b>(
+            dir: &RepoPath,
+            entry: &'b crate::protos::working_copy::FileStateEntry,
+        ) -> &'b str {
+            child_entry_name(dir, RepoPath::from_internal_string(&entry.path)).1
+        }
+        let mut names = Vec::new();
+        let mut rest = self.data;
+        while let Some(first) = rest.first() {
+            let name = entry_name(dir, first);
+            let len = rest.partition_point(|entry| entry_name(dir, entry) == name);
+            names.push(name);
+            rest = &rest[len..];
+        }
+        names
+    }
+
     /// Iterates file state entries sorted by path.
     pub fn iter(&self) -> FileStatesIter<'a> {
         self.data.iter().map(file_state_entry_from_proto)
@@ -406,6 +454,16 @@ impl<'a> IntoIterator for FileStates<'a> {
     }
 }
 
+/// Version of the "tree_state" file format written by this version.
+///
+/// Files written by older versions are migrated when read. Files written by
+/// newer versions are rebuilt from the recorded tree, since their file states
+/// may not mean what this version expects.
+const TREE_STATE_FORMAT_VERSION: u32 = 1;
+
+/// Version of the "checkout" file format written by this version.
+const CHECKOUT_FORMAT_VERSION: u32 = 1;
+
 pub struct TreeState {
     store: Arc<Store>,
     working_copy_path: PathBuf,
@@ -414,6 +472,7 @@ pub struct TreeState {
     file_states: FileStatesMap,
     // Currently only path prefixes
     sparse_patterns: Vec<RepoPathBuf>,
+    tracking_patterns: TrackingPatterns,
     own_mtime: MillisSinceEpoch,
     symlink_support: bool,
 
@@ -421,6 +480,32 @@ pub struct TreeState {
     /// the repo is configured to use the Watchman filesystem monitor and
     /// Watchman has been queried at least once.
     watchman_clock: Option<crate::protos::working_copy::WatchmanClock>,
+
+    /// The update an interrupted checkout was making to the working copy, as
+    /// recorded in the checkout journal.
+    pending_checkout: Option<PendingCheckout>,
+}
+
+/// An update of the working copy recorded in the checkout journal.
+#[derive(Clone, Debug)]
+struct PendingCheckout {
+    tree_id: MergedTreeId,
+    /// The new sparse patterns if they're being changed, in which case
+    /// `tree_id` is the current tree.
+    sparse_patterns: Option<Vec<RepoPathBuf>>,
+    /// When the journal was written. Files modified since then may have been
+    /// written by the checkout.
+    started: MillisSinceEpoch,
+}
+
+/// An interrupted checkout being finished by `TreeState::update()`.
+struct InterruptedCheckout {
+    /// When the checkout started. Files modified since then may have been
+    /// written by it.
+    started: MillisSinceEpoch,
+    /// Paths whose files on disk are neither what the checkout writes nor
+    /// unchanged since their states were recorded. They were left alone.
+    changed_paths: Vec<RepoPathBuf>,
 }
 
 fn file_state_from_proto(proto: &crate::protos::working_copy::FileState) -> FileState {
@@ -443,6 +528,7 @@ fn file_state_from_proto(proto: &crate::protos::working_copy::FileState) -> File
         file_type,
         mtime: MillisSinceEpoch(proto.mtime_millis_since_epoch),
         size: proto.size,
+        inode: proto.inode,
         materialized_conflict_data: proto.materialized_conflict_data.as_ref().map(|data| {
             MaterializedConflictData {
                 conflict_marker_len: data.conflict_marker_len,
@@ -466,6 +552,7 @@ fn file_state_to_proto(file_state: &FileState) -> crate::protos::working_copy::F
     proto.file_type = file_type as i32;
     proto.mtime_millis_since_epoch = file_state.mtime.0;
     proto.size = file_state.size;
+    proto.inode = file_state.inode;
     proto.materialized_conflict_data = file_state.materialized_conflict_data.map(|data| {
         crate::protos::working_copy::MaterializedConflictData {
             conflict_marker_len: data.conflict_marker_len,
@@ -500,6 +587,17 @@ fn is_file_state_entries_proto_unique_and_sorted(
         .all(|(path1, path2)| path1 < path2)
 }
 
+fn sparse_patterns_to_proto(
+    sparse_patterns: &[RepoPathBuf],
+) -> crate::protos::working_copy::SparsePatterns {
+    crate::protos::working_copy::SparsePatterns {
+        prefixes: sparse_patterns
+            .iter()
+            .map(|path| path.as_internal_file_string().to_owned())
+            .collect(),
+    }
+}
+
 fn sparse_patterns_from_proto(
     proto: Option<&crate::protos::working_copy::SparsePatterns>,
 ) -> Vec<RepoPathBuf> {
@@ -516,6 +614,74 @@ fn sparse_patterns_from_proto(
     sparse_patterns
 }
 
+/// Upgrades a tree state written by an older version to
+/// `TREE_STATE_FORMAT_VERSION`, one version at a time.
+fn migrate_tree_state_proto(proto: &mut crate::protos::working_copy::TreeState) {
+    if proto.format_version == 0 {
+        // Version 1 started recording the version. The fields added before
+        // that default to what older files implied, e.g. unsorted file states.
+        proto.format_version = 1;
+    }
+    debug_assert_eq!(proto.format_version, TREE_STATE_FORMAT_VERSION);
+}
+
+fn tree_id_from_proto(proto: &crate::protos::working_copy::TreeState) -> MergedTreeId {
+    if proto.tree_ids.is_empty() {
+        MergedTreeId::Legacy(TreeId::new(proto.legacy_tree_id.clone()))
+    } else {
+        let tree_ids_builder: MergeBuilder<TreeId> = proto
+            .tree_ids
+            .iter()
+            .map(|id| TreeId::new(id.clone()))
+            .collect();
+        MergedTreeId::Merge(tree_ids_builder.build())
+    }
+}
+
+fn tracking_patterns_from_proto(
+    proto: Option<&crate::protos::working_copy::TrackingPatterns>,
+) -> TrackingPatterns {
+    let Some(proto) = proto else {
+        return TrackingPatterns::default();
+    };
+    let to_paths = |prefixes: &[String]| {
+        prefixes
+            .iter()
+            .map(RepoPathBuf::from_internal_string)
+            .collect_vec()
+    };
+    TrackingPatterns::new(to_paths(&proto.tracked), to_paths(&proto.untracked))
+}
+
+fn tracking_patterns_to_proto(
+    patterns: &TrackingPatterns,
+) -> crate::protos::working_copy::TrackingPatterns {
+    let to_strings = |paths: &[RepoPathBuf]| {
+        paths
+            .iter()
+            .map(|path| path.as_internal_file_string().to_owned())
+            .collect()
+    };
+    crate::protos::working_copy::TrackingPatterns {
+        tracked: to_strings(patterns.tracked()),
+        untracked: to_strings(patterns.untracked()),
+    }
+}
+
+/// Returns the disk path of `repo_path` if all its parents are existing
+/// directories. Unlike `create_parent_dirs()`, nothing is created.
+fn existing_file_path(working_copy_path: &Path, repo_path: &RepoPath) -> Option<PathBuf> {
+    let mut disk_path = working_copy_path.to_owned();
+    for c in repo_path.components() {
+        // Don't follow symlinks, which may point outside the working copy.
+        if !disk_path.symlink_metadata().ok()?.is_dir() {
+            return None;
+        }
+        disk_path.push(c.to_fs_name().ok()?);
+    }
+    Some(disk_path)
+}
+
 /// Creates intermediate directories from the `working_copy_path` to the
 /// `repo_path` parent. Returns disk path for the `repo_path` file.
 ///
@@ -596,6 +762,29 @@ fn remove_old_file(disk_path: &Path) -> Result<bool, CheckoutError> {
     }
 }
 
+/// Removes the empty directory where an old Git submodule was materialized.
+///
+/// Returns `Ok(false)` if the directory doesn't exist or isn't empty, in which
+/// case the submodule contents are left alone.
+fn remove_old_submodule_dir(disk_path: &Path) -> Result<bool, CheckoutError> {
+    reject_reserved_existing_path(disk_path)?;
+    match fs::remove_dir(disk_path) {
+        Ok(()) => Ok(true),
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::DirectoryNotEmpty
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(CheckoutError::Other {
+            message: format!("Failed to remove directory {}", disk_path.display()),
+            err: err.into(),
+        }),
+    }
+}
+
 /// Checks if new file or symlink named `disk_path` can be created.
 ///
 /// If the file already exists, this function return `Ok(false)` to signal
@@ -672,6 +861,15 @@ fn reject_reserved_existing_path(disk_path: &Path) -> Result<(), CheckoutError>
     Ok(())
 }
 
+/// Checks if the file or symlink named `disk_path` was modified at or after
+/// `time`. Files modified within the file system's timestamp granularity
+/// before `time` may be included.
+fn modified_since(disk_path: &Path, time: MillisSinceEpoch) -> bool {
+    disk_path
+        .symlink_metadata()
+        .is_ok_and(|metadata| !metadata.is_dir() && mtime_from_metadata(&metadata) >= time)
+}
+
 fn mtime_from_metadata(metadata: &Metadata) -> MillisSinceEpoch {
     let time = metadata
         .modified()
@@ -686,6 +884,17 @@ fn mtime_from_metadata(metadata: &Metadata) -> MillisSinceEpoch {
     )
 }
 
+#[cfg(unix)]
+fn inode_from_metadata(metadata: &Metadata) -> u64 {
+    metadata.ino()
+}
+
+#[cfg(windows)]
+fn inode_from_metadata(_metadata: &Metadata) -> u64 {
+    // The file index isn't available from the stable std API.
+    0
+}
+
 fn file_state(metadata: &Metadata) -> Option<FileState> {
     let metadata_file_type = metadata.file_type();
     let file_type = if metadata_file_type.is_dir() {
@@ -711,6 +920,7 @@ fn file_state(metadata: &Metadata) -> Option<FileState> {
             file_type,
             mtime,
             size,
+            inode: inode_from_metadata(metadata),
             materialized_conflict_data: None,
         }
     })
@@ -734,6 +944,17 @@ pub enum TreeStateError {
     WriteTreeState { path: PathBuf, source: io::Error },
     #[error("Persisting tree state to file {path}")]
     PersistTreeState { path: PathBuf, source: io::Error },
+    #[error("Rebuilding tree state read from {path}")]
+    RebuildTreeState { path: PathBuf, source: ResetError },
+    #[error("Reading checkout journal from {path}")]
+    ReadCheckoutJournal { path: PathBuf, source: io::Error },
+    #[error("Decoding checkout journal from {path}")]
+    DecodeCheckoutJournal {
+        path: PathBuf,
+        source: prost::DecodeError,
+    },
+    #[error("Removing checkout journal {path}")]
+    RemoveCheckoutJournal { path: PathBuf, source: io::Error },
     #[error("Filesystem monitor error")]
     Fsmonitor(#[source] Box<dyn Error + Send + Sync>),
 }
@@ -751,10 +972,30 @@ impl TreeState {
         self.file_states.all()
     }
 
+    /// The tree an interrupted checkout was updating the working copy to, if
+    /// any. The files in the working copy may be partially updated.
+    pub fn pending_checkout(&self) -> Option<&MergedTreeId> {
+        self.pending_checkout
+            .as_ref()
+            .map(|pending| &pending.tree_id)
+    }
+
     pub fn sparse_patterns(&self) -> &Vec<RepoPathBuf> {
         &self.sparse_patterns
     }
 
+    pub fn tracking_patterns(&self) -> &TrackingPatterns {
+        &self.tracking_patterns
+    }
+
+    pub fn track(&mut self, paths: &[RepoPathBuf]) {
+        self.tracking_patterns.track(paths);
+    }
+
+    pub fn untrack(&mut self, paths: &[RepoPathBuf]) {
+        self.tracking_patterns.untrack(paths);
+    }
+
     fn sparse_matcher(&self) -> Box<dyn Matcher> {
         Box::new(PrefixMatcher::new(&self.sparse_patterns))
     }
@@ -778,9 +1019,11 @@ impl TreeState {
             tree_id,
             file_states: FileStatesMap::new(),
             sparse_patterns: vec![RepoPathBuf::root()],
+            tracking_patterns: TrackingPatterns::default(),
             own_mtime: MillisSinceEpoch(0),
             symlink_support: check_symlink_support().unwrap_or(false),
             watchman_clock: None,
+            pending_checkout: None,
         }
     }
 
@@ -805,6 +1048,7 @@ impl TreeState {
 
         let mut wc = TreeState::empty(store, working_copy_path, state_path);
         wc.read(&tree_state_path, file)?;
+        wc.read_checkout_journal()?;
         Ok(wc)
     }
 
@@ -824,32 +1068,147 @@ impl TreeState {
                 path: tree_state_path.to_owned(),
                 source: err,
             })?;
-        let proto = crate::protos::working_copy::TreeState::decode(&*buf).map_err(|err| {
+        let mut proto = crate::protos::working_copy::TreeState::decode(&*buf).map_err(|err| {
             TreeStateError::DecodeTreeState {
                 path: tree_state_path.to_owned(),
                 source: err,
             }
         })?;
-        if proto.tree_ids.is_empty() {
-            self.tree_id = MergedTreeId::Legacy(TreeId::new(proto.legacy_tree_id.clone()));
-        } else {
-            let tree_ids_builder: MergeBuilder<TreeId> = proto
-                .tree_ids
-                .iter()
-                .map(|id| TreeId::new(id.clone()))
-                .collect();
-            self.tree_id = MergedTreeId::Merge(tree_ids_builder.build());
+        if proto.format_version > TREE_STATE_FORMAT_VERSION {
+            // Only keep the state which doesn't depend on the file system.
+            // The file states will be recomputed by the next snapshot.
+            tracing::warn!(
+                version = proto.format_version,
+                "rebuilding tree state written by a newer version"
+            );
+            self.sparse_patterns = sparse_patterns_from_proto(proto.sparse_patterns.as_ref());
+            self.tracking_patterns = tracking_patterns_from_proto(proto.tracking_patterns.as_ref());
+            return self
+                .store
+                .get_root_tree(&tree_id_from_proto(&proto))
+                .map_err(ResetError::from)
+                .and_then(|tree| self.recover(&tree).block_on())
+                .map_err(|err| TreeStateError::RebuildTreeState {
+                    path: tree_state_path.to_owned(),
+                    source: err,
+                });
         }
+        migrate_tree_state_proto(&mut proto);
+        self.tree_id = tree_id_from_proto(&proto);
         self.file_states =
             FileStatesMap::from_proto(proto.file_states, proto.is_file_states_sorted);
         self.sparse_patterns = sparse_patterns_from_proto(proto.sparse_patterns.as_ref());
+        self.tracking_patterns = tracking_patterns_from_proto(proto.tracking_patterns.as_ref());
         self.watchman_clock = proto.watchman_clock;
         Ok(())
     }
 
+    fn read_checkout_journal(&mut self) -> Result<(), TreeStateError> {
+        let journal_path = self.state_path.join("checkout_journal");
+        let buf = match fs::read(&journal_path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => {
+                return Err(TreeStateError::ReadCheckoutJournal {
+                    path: journal_path,
+                    source: err,
+                });
+            }
+        };
+        let metadata =
+            journal_path
+                .symlink_metadata()
+                .map_err(|err| TreeStateError::ReadCheckoutJournal {
+                    path: journal_path.clone(),
+                    source: err,
+                })?;
+        let proto = crate::protos::working_copy::CheckoutJournal::decode(&*buf).map_err(|err| {
+            TreeStateError::DecodeCheckoutJournal {
+                path: journal_path.clone(),
+                source: err,
+            }
+        })?;
+        let tree_ids_builder: MergeBuilder<TreeId> = proto
+            .new_tree_ids
+            .iter()
+            .map(|id| TreeId::new(id.clone()))
+            .collect();
+        let new_tree_id = MergedTreeId::Merge(tree_ids_builder.build());
+        let new_sparse_patterns = proto
+            .new_sparse_patterns
+            .as_ref()
+            .map(|patterns| sparse_patterns_from_proto(Some(patterns)));
+        // The journal is left behind if the checkout finished but removing
+        // the journal failed.
+        let sparse_patterns_changed = new_sparse_patterns
+            .as_ref()
+            .is_some_and(|patterns| *patterns != self.sparse_patterns);
+        if new_tree_id != self.tree_id || sparse_patterns_changed {
+            self.pending_checkout = Some(PendingCheckout {
+                tree_id: new_tree_id,
+                sparse_patterns: new_sparse_patterns.filter(|_| sparse_patterns_changed),
+                started: mtime_from_metadata(&metadata),
+            });
+        }
+        Ok(())
+    }
+
+    /// Records the update about to be made to the working copy so that it can
+    /// be finished if it's interrupted.
+    fn write_checkout_journal(
+        &mut self,
+        new_tree_id: MergedTreeId,
+        new_sparse_patterns: Option<Vec<RepoPathBuf>>,
+    ) -> Result<(), CheckoutError> {
+        let proto = crate::protos::working_copy::CheckoutJournal {
+            new_tree_ids: new_tree_id
+                .to_merge()
+                .iter()
+                .map(|id| id.to_bytes())
+                .collect(),
+            new_sparse_patterns: new_sparse_patterns.as_deref().map(sparse_patterns_to_proto),
+        };
+        let write = || -> io::Result<Metadata> {
+            let mut temp_file = NamedTempFile::new_in(&self.state_path)?;
+            temp_file.as_file_mut().write_all(&proto.encode_to_vec())?;
+            let file = temp_file
+                .persist(self.state_path.join("checkout_journal"))
+                .map_err(|err| err.error)?;
+            file.metadata()
+        };
+        let metadata = write().map_err(|err| CheckoutError::Other {
+            message: "Failed to write checkout journal".to_string(),
+            err: err.into(),
+        })?;
+        self.pending_checkout = Some(PendingCheckout {
+            tree_id: new_tree_id,
+            sparse_patterns: new_sparse_patterns,
+            started: mtime_from_metadata(&metadata),
+        });
+        Ok(())
+    }
+
+    fn remove_checkout_journal(&self) -> io::Result<()> {
+        match fs::remove_file(self.state_path.join("checkout_journal")) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Forgets the checkout in progress after it failed. The journal is only
+    /// meant for checkouts interrupted by the process exiting, and finishing
+    /// a failed checkout would probably fail again.
+    fn discard_checkout_journal(&mut self) {
+        self.pending_checkout = None;
+        self.remove_checkout_journal().ok();
+    }
+
     #[expect(clippy::assigning_clones)]
     fn save(&mut self) -> Result<(), TreeStateError> {
-        let mut proto: crate::protos::working_copy::TreeState = Default::default();
+        let mut proto = crate::protos::working_copy::TreeState {
+            format_version: TREE_STATE_FORMAT_VERSION,
+            ..Default::default()
+        };
         match &self.tree_id {
             MergedTreeId::Legacy(tree_id) => {
                 proto.legacy_tree_id = tree_id.to_bytes();
@@ -862,13 +1221,10 @@ impl TreeState {
         proto.file_states = self.file_states.data.clone();
         // `FileStatesMap` is guaranteed to be sorted.
         proto.is_file_states_sorted = true;
-        let mut sparse_patterns = crate::protos::working_copy::SparsePatterns::default();
-        for path in &self.sparse_patterns {
-            sparse_patterns
-                .prefixes
-                .push(path.as_internal_file_string().to_owned());
+        proto.sparse_patterns = Some(sparse_patterns_to_proto(&self.sparse_patterns));
+        if !self.tracking_patterns.is_empty() {
+            proto.tracking_patterns = Some(tracking_patterns_to_proto(&self.tracking_patterns));
         }
-        proto.sparse_patterns = Some(sparse_patterns);
         proto.watchman_clock = self.watchman_clock.clone();
 
         let mut temp_file = NamedTempFile::new_in(&self.state_path).unwrap();
@@ -893,6 +1249,16 @@ impl TreeState {
                     source: error,
                 }
             })?;
+        // The saved state describes the updated files unless a checkout is
+        // still to be finished.
+        if self.pending_checkout.is_none() {
+            self.remove_checkout_journal().map_err(|err| {
+                TreeStateError::RemoveCheckoutJournal {
+                    path: self.state_path.join("checkout_journal"),
+                    source: err,
+                }
+            })?;
+        }
         Ok(())
     }
 
@@ -939,6 +1305,46 @@ impl TreeState {
     }
 }
 
+/// Materializes a conflict between regular files with conflict markers.
+fn file_conflict_data(
+    path: &RepoPath,
+    id: &Merge<Option<FileId>>,
+    contents: &Merge<BString>,
+    options: &CheckoutOptions,
+) -> (Vec<u8>, MaterializedConflictData) {
+    let conflict_marker_len = choose_materialized_conflict_marker_len(contents);
+    let data = materialize_file_conflict_to_bytes_with_marker_len(
+        id,
+        contents,
+        options.conflict_marker_style,
+        conflict_marker_len,
+    );
+    let data = options
+        .eol_settings
+        .mode_for(path)
+        .convert_output(&data)
+        .into_owned();
+    let materialized_conflict_data = MaterializedConflictData {
+        conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
+    };
+    (data, materialized_conflict_data)
+}
+
+/// The local Git LFS storage of the repo, if it's backed by Git.
+#[cfg(feature = "git")]
+fn lfs_object_store(store: &Store) -> Option<LfsObjectStore> {
+    let git_backend = crate::git::get_git_backend(store).ok()?;
+    Some(
+        LfsObjectStore::new(git_backend.git_repo_path().to_owned())
+            .with_lock_manager(git_backend.lock_manager().clone()),
+    )
+}
+
+#[cfg(not(feature = "git"))]
+fn lfs_object_store(_store: &Store) -> Option<LfsObjectStore> {
+    None
+}
+
 /// Functions to snapshot local-disk files to the store.
 impl TreeState {
     /// Look for changes to the working copy. If there are any changes, create
@@ -955,12 +1361,32 @@ impl TreeState {
             start_tracking_matcher,
             max_new_file_size,
             conflict_marker_style,
+            path_normalization,
+            ref eol_settings,
+            ref lfs_settings,
         } = options;
 
+        // Files left behind by an interrupted checkout would otherwise be
+        // snapshotted as changes. Files changed by the user since are kept and
+        // snapshotted as changes to the new tree.
+        let checkout_options = CheckoutOptions {
+            conflict_marker_style,
+            progress: None,
+            eol_settings: eol_settings.clone(),
+            lfs_settings: lfs_settings.clone(),
+            force: false,
+        };
+        let finished_checkout = self
+            .finish_interrupted_checkout_keeping_changes(&checkout_options)
+            .map_err(|err| SnapshotError::Other {
+                message: "Failed to finish interrupted checkout".to_string(),
+                err: err.into(),
+            })?;
+
         let sparse_matcher = self.sparse_matcher();
 
         let fsmonitor_clock_needs_save = *fsmonitor_settings != FsmonitorSettings::None;
-        let mut is_dirty = fsmonitor_clock_needs_save;
+        let mut is_dirty = fsmonitor_clock_needs_save || finished_checkout.is_some();
         let FsmonitorMatcher {
             matcher: fsmonitor_matcher,
             watchman_clock,
@@ -982,21 +1408,29 @@ impl TreeState {
         let (untracked_paths_tx, untracked_paths_rx) = channel();
         let (deleted_files_tx, deleted_files_rx) = channel();
 
-        trace_span!("traverse filesystem").in_scope(|| -> Result<(), SnapshotError> {
+        let file_cache = trace_span!("traverse filesystem").in_scope(|| {
             let snapshotter = FileSnapshotter {
                 tree_state: self,
                 current_tree: &self.current_tree()?,
                 matcher: &matcher,
                 start_tracking_matcher,
+                tracking_patterns: &self.tracking_patterns,
                 // Move tx sides so they'll be dropped at the end of the scope.
                 tree_entries_tx,
                 file_states_tx,
                 untracked_paths_tx,
                 deleted_files_tx,
                 error: OnceLock::new(),
+                file_cache_hits: AtomicUsize::new(0),
+                file_cache_misses: AtomicUsize::new(0),
+                racily_clean_files: AtomicUsize::new(0),
                 progress,
                 max_new_file_size,
                 conflict_marker_style,
+                path_normalization,
+                eol_settings,
+                lfs_settings,
+                lfs_store: lfs_object_store(&self.store),
             };
             let directory_to_visit = DirectoryToVisit {
                 dir: RepoPathBuf::root(),
@@ -1015,7 +1449,11 @@ impl TreeState {
 
         let stats = SnapshotStats {
             untracked_paths: untracked_paths_rx.into_iter().collect(),
+            file_cache,
         };
+        // Saving the state again makes it newer than the racily clean files,
+        // so they don't have to be read again next time.
+        is_dirty |= file_cache.racily_clean > 0;
         let mut tree_builder = MergedTreeBuilder::new(self.tree_id.clone());
         trace_span!("process tree entries").in_scope(|| {
             for (path, tree_values) in &tree_entries_rx {
@@ -1130,20 +1568,42 @@ struct PresentDirEntries {
     files: HashSet<String>,
 }
 
+/// Extracts the kind and `<name>` of the `dir` entry from `<dir>`,
+/// `<dir>/<name>`, or `<dir>/<name>/**`. (file_states may contain `<dir>` file
+/// on file->dir transition.)
+fn child_entry_name<'a>(dir: &RepoPath, path: &'a RepoPath) -> (PresentDirEntryKind, &'a str) {
+    debug_assert!(path.starts_with(dir));
+    let slash = !dir.is_root() as usize;
+    let len = dir.as_internal_file_string().len() + slash;
+    let tail = path.as_internal_file_string().get(len..).unwrap_or("");
+    match tail.split_once('/') {
+        Some((name, _)) => (PresentDirEntryKind::Dir, name),
+        None => (PresentDirEntryKind::File, tail),
+    }
+}
+
 /// Helper to scan local-disk directories and files in parallel.
 struct FileSnapshotter<'a> {
     tree_state: &'a TreeState,
     current_tree: &'a MergedTree,
     matcher: &'a dyn Matcher,
     start_tracking_matcher: &'a dyn Matcher,
+    tracking_patterns: &'a TrackingPatterns,
     tree_entries_tx: Sender<(RepoPathBuf, MergedTreeValue)>,
     file_states_tx: Sender<(RepoPathBuf, FileState)>,
     untracked_paths_tx: Sender<(RepoPathBuf, UntrackedReason)>,
     deleted_files_tx: Sender<RepoPathBuf>,
     error: OnceLock<SnapshotError>,
+    file_cache_hits: AtomicUsize,
+    file_cache_misses: AtomicUsize,
+    racily_clean_files: AtomicUsize,
     progress: Option<&'a SnapshotProgress<'a>>,
     max_new_file_size: u64,
     conflict_marker_style: ConflictMarkerStyle,
+    path_normalization: PathNormalization,
+    eol_settings: &'a EolSettings,
+    lfs_settings: &'a LfsSettings,
+    lfs_store: Option<LfsObjectStore>,
 }
 
 impl FileSnapshotter<'_> {
@@ -1163,10 +1623,14 @@ impl FileSnapshotter<'_> {
     }
 
     /// Extracts the result of the snapshot.
-    fn into_result(self) -> Result<(), SnapshotError> {
+    fn into_result(self) -> Result<FileCacheStats, SnapshotError> {
         match self.error.into_inner() {
             Some(err) => Err(err),
-            None => Ok(()),
+            None => Ok(FileCacheStats {
+                hits: self.file_cache_hits.into_inner(),
+                misses: self.file_cache_misses.into_inner(),
+                racily_clean: self.racily_clean_files.into_inner(),
+            }),
         }
     }
 
@@ -1193,14 +1657,22 @@ impl FileSnapshotter<'_> {
                 message: format!("Failed to read directory {}", disk_dir.display()),
                 err: err.into(),
             })?;
+        let renamed_entries = self.renamed_dir_entries(&dir, file_states, &dir_entries);
         let (dirs, files) = dir_entries
             .into_par_iter()
             // Don't split into too many small jobs. For a small directory,
             // sequential scan should be fast enough.
             .with_min_len(100)
             .filter_map(|entry| {
-                self.process_dir_entry(&dir, &git_ignore, file_states, &entry, scope)
-                    .transpose()
+                self.process_dir_entry(
+                    &dir,
+                    &git_ignore,
+                    file_states,
+                    &renamed_entries,
+                    &entry,
+                    scope,
+                )
+                .transpose()
             })
             .map(|item| match item {
                 Ok((PresentDirEntryKind::Dir, name)) => Ok(Either::Left(name)),
@@ -1213,23 +1685,65 @@ impl FileSnapshotter<'_> {
         Ok(())
     }
 
+    /// Maps names of directory entries on disk to the names they should be
+    /// recorded as according to the path normalization policy. Entries that
+    /// keep their on-disk name aren't included.
+    fn renamed_dir_entries(
+        &self,
+        dir: &RepoPath,
+        file_states: FileStates<'_>,
+        dir_entries: &[DirEntry],
+    ) -> HashMap<String, String> {
+        let normalization = &self.path_normalization;
+        if normalization.is_identity() {
+            return HashMap::new();
+        }
+        let disk_names: HashSet<String> = dir_entries
+            .iter()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        let tracked_names: HashSet<&str> = file_states.child_names_at(dir).into_iter().collect();
+        let tracked_names_by_key: HashMap<String, &str> = tracked_names
+            .iter()
+            .map(|&name| (normalization.comparison_key(name).into_owned(), name))
+            .collect();
+        disk_names
+            .iter()
+            .filter(|name| !tracked_names.contains(name.as_str()))
+            .filter_map(|name| {
+                let key = normalization.comparison_key(name);
+                let new_name = match tracked_names_by_key.get(key.as_ref()) {
+                    Some(&tracked_name) => tracked_name.to_owned(),
+                    None => normalization.normalize_name(name).into_owned(),
+                };
+                // Don't merge distinct entries which both exist on disk.
+                (new_name != *name && !disk_names.contains(&new_name))
+                    .then(|| (name.clone(), new_name))
+            })
+            .collect()
+    }
+
     fn process_dir_entry<'scope>(
         &'scope self,
         dir: &RepoPath,
         git_ignore: &Arc<GitIgnoreFile>,
         file_states: FileStates<'scope>,
+        renamed_entries: &HashMap<String, String>,
         entry: &DirEntry,
         scope: &rayon::Scope<'scope>,
     ) -> Result<Option<(PresentDirEntryKind, String)>, SnapshotError> {
         let file_type = entry.file_type().unwrap();
         let file_name = entry.file_name();
-        let name_string = file_name
+        let mut name_string = file_name
             .into_string()
             .map_err(|path| SnapshotError::InvalidUtf8Path { path })?;
 
         if RESERVED_DIR_NAMES.contains(&name_string.as_str()) {
             return Ok(None);
         }
+        if let Some(new_name) = renamed_entries.get(&name_string) {
+            name_string = new_name.clone();
+        }
         let name = RepoPathComponent::new(&name_string);
         let path = dir.join(name);
         let maybe_current_file_state = file_states.get_at(dir, name);
@@ -1272,9 +1786,7 @@ impl FileSnapshotter<'_> {
                 // If it wasn't already tracked and it matches
                 // the ignored paths, then ignore it.
                 Ok(None)
-            } else if maybe_current_file_state.is_none()
-                && !self.start_tracking_matcher.matches(&path)
-            {
+            } else if maybe_current_file_state.is_none() && !self.should_start_tracking(&path) {
                 // Leave the file untracked
                 self.untracked_paths_tx
                     .send((path, UntrackedReason::FileNotAutoTracked))
@@ -1311,6 +1823,13 @@ impl FileSnapshotter<'_> {
         }
     }
 
+    /// Returns whether the new file at `path` should be tracked.
+    fn should_start_tracking(&self, path: &RepoPath) -> bool {
+        self.tracking_patterns
+            .is_tracked(path)
+            .unwrap_or_else(|| self.start_tracking_matcher.matches(path))
+    }
+
     /// Visits only paths we're already tracking.
     fn visit_tracked_files(&self, file_states: FileStates<'_>) -> Result<(), SnapshotError> {
         for (tracked_path, current_file_state) in file_states {
@@ -1381,18 +1900,9 @@ impl FileSnapshotter<'_> {
         file_states: FileStates<'_>,
         present_entries: &PresentDirEntries,
     ) {
-        let file_state_chunks = file_states.iter().chunk_by(|(path, _state)| {
-            // Extract <name> from <dir>, <dir>/<name>, or <dir>/<name>/**.
-            // (file_states may contain <dir> file on file->dir transition.)
-            debug_assert!(path.starts_with(dir));
-            let slash = !dir.is_root() as usize;
-            let len = dir.as_internal_file_string().len() + slash;
-            let tail = path.as_internal_file_string().get(len..).unwrap_or("");
-            match tail.split_once('/') {
-                Some((name, _)) => (PresentDirEntryKind::Dir, name),
-                None => (PresentDirEntryKind::File, tail),
-            }
-        });
+        let file_state_chunks = file_states
+            .iter()
+            .chunk_by(|(path, _state)| child_entry_name(dir, path));
         file_state_chunks
             .into_iter()
             .filter(|&((kind, name), _)| match kind {
@@ -1420,15 +1930,26 @@ impl FileSnapshotter<'_> {
                 false
             }
             Some(current_file_state) => {
-                // If the file's mtime was set at the same time as this state file's own mtime,
-                // then we don't know if the file was modified before or after this state file.
-                new_file_state.is_clean(current_file_state)
-                    && current_file_state.mtime < self.tree_state.own_mtime
+                if !new_file_state.is_clean(current_file_state) {
+                    false
+                } else if current_file_state.mtime < self.tree_state.own_mtime {
+                    true
+                } else {
+                    // If the file's mtime was set at the same time as this state file's own
+                    // mtime, then we don't know if the file was modified before or after this
+                    // state file.
+                    self.racily_clean_files
+                        .fetch_add(1, atomic::Ordering::Relaxed);
+                    false
+                }
             }
         };
         if clean {
+            self.file_cache_hits.fetch_add(1, atomic::Ordering::Relaxed);
             Ok(None)
         } else {
+            self.file_cache_misses
+                .fetch_add(1, atomic::Ordering::Relaxed);
             let current_tree_values = self.current_tree.path_value(repo_path)?;
             let new_file_type = if !self.tree_state.symlink_support {
                 let mut new_file_type = new_file_state.file_type.clone();
@@ -1459,7 +1980,11 @@ impl FileSnapshotter<'_> {
                 }
                 FileType::GitSubmodule => panic!("git submodule cannot be written to store"),
             };
-            if new_tree_values != current_tree_values {
+            // A newly tracked file may match the current tree value only
+            // because a parent path was conflicted (e.g. a file/directory
+            // conflict whose materialized file was replaced by the directory),
+            // so it needs to be written explicitly.
+            if new_tree_values != current_tree_values || maybe_current_file_state.is_none() {
                 Ok(Some(new_tree_values))
             } else {
                 Ok(None)
@@ -1502,6 +2027,10 @@ impl FileSnapshotter<'_> {
                 message: format!("Failed to open file {}", disk_path.display()),
                 err: err.into(),
             })?;
+            let content = self
+                .eol_settings
+                .mode_for(repo_path)
+                .convert_input(&content);
             let new_file_ids = conflicts::update_from_content(
                 &old_file_ids,
                 self.store(),
@@ -1532,12 +2061,50 @@ impl FileSnapshotter<'_> {
                 }
                 Err(new_file_ids) => {
                     if new_file_ids != old_file_ids {
-                        Ok(current_tree_values.with_new_file_ids(&new_file_ids))
-                    } else {
-                        Ok(current_tree_values.clone())
+                        return Ok(current_tree_values.with_new_file_ids(&new_file_ids));
+                    }
+                    // If only the executable bits conflict, changing the bit of
+                    // the materialized file resolves the conflict.
+                    #[cfg(unix)]
+                    if current_tree_values.conflict_kind() == Some(ConflictKind::Executable) {
+                        let materialized_executable = current_tree_values
+                            .to_executable_merge()
+                            .and_then(|merge| merge.resolve_trivial().copied())
+                            .unwrap_or_default();
+                        if executable != materialized_executable {
+                            let id = new_file_ids.iter().flatten().next().unwrap().clone();
+                            return Ok(Merge::normal(TreeValue::File { id, executable }));
+                        }
                     }
+                    Ok(current_tree_values.clone())
                 }
             }
+        } else if current_tree_values.conflict_kind() == Some(ConflictKind::FileDirectory) {
+            // A file/directory conflict is materialized as a description of
+            // the conflict. If the file was edited, it replaces the conflict.
+            let content = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to open file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            let description = conflicts::describe_conflict(
+                &self.tree_state.store,
+                repo_path,
+                current_tree_values,
+            )?;
+            let content = self
+                .eol_settings
+                .mode_for(repo_path)
+                .convert_input(&content);
+            if content == description.as_bytes() {
+                return Ok(current_tree_values.clone());
+            }
+            let id = self.write_file_to_store(repo_path, disk_path).await?;
+            #[cfg(windows)]
+            let executable = {
+                let () = executable; // use the variable
+                false
+            };
+            Ok(Merge::normal(TreeValue::File { id, executable }))
         } else {
             Ok(current_tree_values.clone())
         }
@@ -1548,6 +2115,35 @@ impl FileSnapshotter<'_> {
         path: &RepoPath,
         disk_path: &Path,
     ) -> Result<FileId, SnapshotError> {
+        if let Some(lfs_store) = self
+            .lfs_store
+            .as_ref()
+            .filter(|_| self.lfs_settings.is_lfs(path))
+        {
+            let mut file = File::open(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to open file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            let pointer = lfs_store
+                .to_pointer(&mut file)
+                .map_err(|err| SnapshotError::Other {
+                    message: format!("Failed to store {} in Git LFS", disk_path.display()),
+                    err: err.into(),
+                })?;
+            return Ok(self
+                .store()
+                .write_file(path, &mut pointer.as_slice())
+                .await?);
+        }
+        let eol_mode = self.eol_settings.mode_for(path);
+        if eol_mode != EolConversionMode::None {
+            let content = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to read file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            let content = eol_mode.convert_input(&content);
+            return Ok(self.store().write_file(path, &mut content.as_ref()).await?);
+        }
         let mut file = File::open(disk_path).map_err(|err| SnapshotError::Other {
             message: format!("Failed to open file {}", disk_path.display()),
             err: err.into(),
@@ -1588,6 +2184,130 @@ impl FileSnapshotter<'_> {
 
 /// Functions to update local-disk files from the store.
 impl TreeState {
+    /// Returns the state of the file at `disk_path` if it's what checking out
+    /// `value` writes, in which case an interrupted checkout wrote it already.
+    fn checked_out_file_state(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        metadata: &Metadata,
+        value: MaterializedTreeValue,
+        options: &CheckoutOptions,
+    ) -> Result<Option<FileState>, CheckoutError> {
+        let (expected_state, expected_contents) = match value {
+            MaterializedTreeValue::Absent
+            | MaterializedTreeValue::AccessDenied(_)
+            | MaterializedTreeValue::GitSubmodule(_) => return Ok(None),
+            MaterializedTreeValue::File {
+                executable,
+                mut reader,
+                ..
+            } => {
+                let contents = self.file_contents(path, &mut reader, options)?;
+                let size = contents.len() as u64;
+                (
+                    FileState::for_file(executable, size, metadata, None),
+                    contents,
+                )
+            }
+            MaterializedTreeValue::Symlink { id: _, target } if self.symlink_support => {
+                let is_same_target = metadata.is_symlink()
+                    && fs::read_link(disk_path).is_ok_and(|link| link == Path::new(&target));
+                return Ok(is_same_target.then(|| FileState::for_symlink(metadata)));
+            }
+            MaterializedTreeValue::Symlink { id: _, target } => {
+                let size = target.len() as u64;
+                (
+                    FileState::for_file(false, size, metadata, None),
+                    target.into_bytes(),
+                )
+            }
+            MaterializedTreeValue::FileConflict {
+                id,
+                contents,
+                executable,
+            } => {
+                let (data, materialized_conflict_data) =
+                    file_conflict_data(path, &id, &contents, options);
+                let size = data.len() as u64;
+                (
+                    FileState::for_file(
+                        executable,
+                        size,
+                        metadata,
+                        Some(materialized_conflict_data),
+                    ),
+                    data,
+                )
+            }
+            MaterializedTreeValue::OtherConflict { id } => {
+                let data = self.other_conflict_data(path, &id, options)?;
+                let size = data.len() as u64;
+                (FileState::for_file(false, size, metadata, None), data)
+            }
+            MaterializedTreeValue::Tree(_) => {
+                panic!("unexpected tree entry in diff at {path:?}");
+            }
+        };
+        let is_same_type_and_size = file_state(metadata).is_some_and(|state| {
+            state.file_type == expected_state.file_type && state.size == expected_state.size
+        });
+        if !is_same_type_and_size {
+            return Ok(None);
+        }
+        let contents = fs::read(disk_path).map_err(|err| CheckoutError::Other {
+            message: format!("Failed to read file {}", disk_path.display()),
+            err: err.into(),
+        })?;
+        Ok((contents == expected_contents).then_some(expected_state))
+    }
+
+    /// Reads the contents `update()` writes for the file at `path`.
+    fn file_contents(
+        &self,
+        path: &RepoPath,
+        reader: &mut dyn Read,
+        options: &CheckoutOptions,
+    ) -> Result<Vec<u8>, CheckoutError> {
+        let lfs_settings = &options.lfs_settings;
+        let mut contents = vec![];
+        let result = match lfs_object_store(&self.store) {
+            Some(lfs_store) if lfs_settings.is_lfs(path) => lfs_store
+                .from_pointer(reader, lfs_settings.fetch_with())
+                .and_then(|mut reader| reader.read_to_end(&mut contents)),
+            _ => reader.read_to_end(&mut contents).map(|_| {
+                if options.eol_settings.mode_for(path) == EolConversionMode::InputOutput {
+                    contents = EolConversionMode::InputOutput
+                        .convert_output(&contents)
+                        .into_owned();
+                }
+                0
+            }),
+        };
+        result.map_err(|err| CheckoutError::Other {
+            message: format!("Failed to read file {path:?}"),
+            err: err.into(),
+        })?;
+        Ok(contents)
+    }
+
+    /// Describes a conflict which isn't between regular files only.
+    fn other_conflict_data(
+        &self,
+        path: &RepoPath,
+        id: &MergedTreeValue,
+        options: &CheckoutOptions,
+    ) -> Result<Vec<u8>, CheckoutError> {
+        // Unless all terms are regular files, we can't do much better than
+        // trying to describe the merge.
+        let data = conflicts::describe_conflict(&self.store, path, id)?;
+        Ok(options
+            .eol_settings
+            .mode_for(path)
+            .convert_output(data.as_bytes())
+            .into_owned())
+    }
+
     fn write_file(
         &self,
         disk_path: &Path,
@@ -1633,6 +2353,16 @@ impl TreeState {
         Ok(FileState::for_symlink(&metadata))
     }
 
+    /// Creates an empty directory for Git submodule. The submodule contents
+    /// aren't checked out.
+    fn write_submodule_dir(&self, disk_path: &Path) -> Result<FileState, CheckoutError> {
+        fs::create_dir(disk_path).map_err(|err| CheckoutError::Other {
+            message: format!("Failed to create directory {}", disk_path.display()),
+            err: err.into(),
+        })?;
+        Ok(FileState::for_gitsubmodule())
+    }
+
     fn write_conflict(
         &self,
         disk_path: &Path,
@@ -1682,28 +2412,143 @@ impl TreeState {
         new_tree: &MergedTree,
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
+        self.finish_interrupted_checkout(options)?;
         let old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
                 source: Box::new(err),
             },
             other => CheckoutError::InternalBackendError(other),
         })?;
+        let matcher = self.sparse_matcher();
+        if !options.force {
+            self.check_changed_files(&old_tree, new_tree, matcher.as_ref())
+                .block_on()?;
+        }
+        // Record the new tree before touching any files so that the checkout
+        // can be finished if it's interrupted.
+        self.write_checkout_journal(new_tree.id(), None)?;
         let stats = self
-            .update(
-                &old_tree,
-                new_tree,
-                self.sparse_matcher().as_ref(),
-                options.conflict_marker_style,
-            )
-            .block_on()?;
+            .update(&old_tree, new_tree, matcher.as_ref(), options, None)
+            .block_on()
+            .inspect_err(|_| self.discard_checkout_journal())?;
         self.tree_id = new_tree.id();
+        self.pending_checkout = None;
         Ok(stats)
     }
 
+    /// Updates the files to the tree or sparse patterns an interrupted
+    /// checkout was updating the working copy to.
+    ///
+    /// Files which are already what the checkout writes are kept as they are.
+    /// Unless `options.force` is set, files changed since their states were
+    /// recorded are kept too, and the update fails with
+    /// `CheckoutError::ChangedFiles` after the other files were updated.
+    ///
+    /// Returns `None` if no checkout was interrupted.
+    pub fn finish_interrupted_checkout(
+        &mut self,
+        options: &CheckoutOptions,
+    ) -> Result<Option<CheckoutStats>, CheckoutError> {
+        let Some((stats, changed_paths)) =
+            self.finish_interrupted_checkout_keeping_changes(options)?
+        else {
+            return Ok(None);
+        };
+        if !changed_paths.is_empty() {
+            return Err(CheckoutError::ChangedFiles {
+                paths: changed_paths,
+            });
+        }
+        Ok(Some(stats))
+    }
+
+    /// Like `finish_interrupted_checkout()`, but the changed files are kept
+    /// as changes to the new tree and returned instead of failing.
+    fn finish_interrupted_checkout_keeping_changes(
+        &mut self,
+        options: &CheckoutOptions,
+    ) -> Result<Option<(CheckoutStats, Vec<RepoPathBuf>)>, CheckoutError> {
+        let Some(pending) = self.pending_checkout.clone() else {
+            return Ok(None);
+        };
+        let mut interrupted = InterruptedCheckout {
+            started: pending.started,
+            changed_paths: vec![],
+        };
+        let stats = if let Some(sparse_patterns) = pending.sparse_patterns {
+            self.update_sparse_patterns(sparse_patterns, options, Some(&mut interrupted))
+        } else {
+            self.finish_interrupted_tree_update(&pending.tree_id, options, &mut interrupted)
+        }
+        .inspect_err(|_| self.discard_checkout_journal())?;
+        self.tree_id = pending.tree_id;
+        self.pending_checkout = None;
+        Ok(Some((stats, interrupted.changed_paths)))
+    }
+
+    fn finish_interrupted_tree_update(
+        &mut self,
+        new_tree_id: &MergedTreeId,
+        options: &CheckoutOptions,
+        interrupted: &mut InterruptedCheckout,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let old_tree = self.current_tree().map_err(|err| match err {
+            err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
+                source: Box::new(err),
+            },
+            other => CheckoutError::InternalBackendError(other),
+        })?;
+        let new_tree = self.store.get_root_tree(new_tree_id)?;
+        let matcher = self.sparse_matcher();
+        self.update(
+            &old_tree,
+            &new_tree,
+            matcher.as_ref(),
+            options,
+            Some(interrupted),
+        )
+        .block_on()
+    }
+
     pub fn set_sparse_patterns(
         &mut self,
         sparse_patterns: Vec<RepoPathBuf>,
         options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.finish_interrupted_checkout(options)?;
+        if !options.force {
+            let tree = self.current_tree().map_err(|err| match err {
+                err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
+                    source: Box::new(err),
+                },
+                other => CheckoutError::InternalBackendError(other),
+            })?;
+            let old_matcher = PrefixMatcher::new(&self.sparse_patterns);
+            let new_matcher = PrefixMatcher::new(&sparse_patterns);
+            let removed_matcher = DifferenceMatcher::new(&old_matcher, &new_matcher);
+            let empty_tree =
+                MergedTree::resolved(Tree::empty(self.store.clone(), RepoPathBuf::root()));
+            // Newly added paths have no recorded states to compare against.
+            self.check_changed_files(&tree, &empty_tree, &removed_matcher)
+                .block_on()?;
+        }
+        // Record the new patterns before touching any files so that the update
+        // can be finished if it's interrupted.
+        self.write_checkout_journal(self.tree_id.clone(), Some(sparse_patterns.clone()))?;
+        let stats = self
+            .update_sparse_patterns(sparse_patterns, options, None)
+            .inspect_err(|_| self.discard_checkout_journal())?;
+        self.pending_checkout = None;
+        Ok(stats)
+    }
+
+    /// Adds the files matching only the new sparse patterns and removes the
+    /// files matching only the old ones.
+    fn update_sparse_patterns(
+        &mut self,
+        sparse_patterns: Vec<RepoPathBuf>,
+        options: &CheckoutOptions,
+        mut interrupted: Option<&mut InterruptedCheckout>,
     ) -> Result<CheckoutStats, CheckoutError> {
         let tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
@@ -1721,7 +2566,8 @@ impl TreeState {
                 &empty_tree,
                 &tree,
                 &added_matcher,
-                options.conflict_marker_style,
+                options,
+                interrupted.as_deref_mut(),
             )
             .block_on()?;
         let removed_stats = self
@@ -1729,7 +2575,8 @@ impl TreeState {
                 &tree,
                 &empty_tree,
                 &removed_matcher,
-                options.conflict_marker_style,
+                options,
+                interrupted,
             )
             .block_on()?;
         self.sparse_patterns = sparse_patterns;
@@ -1746,13 +2593,20 @@ impl TreeState {
         })
     }
 
+    /// Updates the files from `old_tree` to `new_tree`. If an interrupted
+    /// checkout is being finished, `interrupted` describes it, and files
+    /// changed since then are left alone unless `options.force` is set.
     async fn update(
         &mut self,
         old_tree: &MergedTree,
         new_tree: &MergedTree,
         matcher: &dyn Matcher,
-        conflict_marker_style: ConflictMarkerStyle,
+        options: &CheckoutOptions<'_>,
+        mut interrupted: Option<&mut InterruptedCheckout>,
     ) -> Result<CheckoutStats, CheckoutError> {
+        let eol_settings = &options.eol_settings;
+        let lfs_settings = &options.lfs_settings;
+        let lfs_store = lfs_object_store(&self.store);
         // TODO: maybe it's better not include the skipped counts in the "intended"
         // counts
         let mut stats = CheckoutStats {
@@ -1777,7 +2631,11 @@ impl TreeState {
             .buffered(self.store.concurrency().max(1));
         while let Some((path, data)) = diff_stream.next().await {
             let (before, after) = data?;
-            if after.is_absent() {
+            if let Some(progress) = options.progress {
+                progress(&path);
+            }
+            let after_is_absent = after.is_absent();
+            if after_is_absent {
                 stats.removed_files += 1;
             } else if before.is_absent() {
                 stats.added_files += 1;
@@ -1808,8 +2666,56 @@ impl TreeState {
                 stats.skipped_files += 1;
                 continue;
             };
+            // The interrupted checkout may have written the file already, or
+            // the user may have changed it since.
+            if let Some(interrupted) = interrupted.as_deref_mut().filter(|_| !options.force) {
+                let metadata = disk_path.symlink_metadata().ok().filter(|m| !m.is_dir());
+                let is_unchanged = |metadata: &Metadata| {
+                    if before.is_present() {
+                        let recorded_state = self.file_states.all().get(&path);
+                        file_state(metadata).is_some_and(|state| {
+                            recorded_state.is_some_and(|recorded| state.is_clean(&recorded))
+                        })
+                    } else {
+                        mtime_from_metadata(metadata) < interrupted.started
+                    }
+                };
+                if let Some(metadata) = metadata.filter(|m| !is_unchanged(m)) {
+                    if let Some(file_state) =
+                        self.checked_out_file_state(&path, &disk_path, &metadata, after, options)?
+                    {
+                        changed_file_states.push((path, file_state));
+                    } else if after_is_absent {
+                        // The file is no longer tracked, but it's kept.
+                        interrupted.changed_paths.push(path.clone());
+                        deleted_files.insert(path);
+                    } else {
+                        // The next snapshot records the file as changed.
+                        interrupted.changed_paths.push(path.clone());
+                        changed_file_states.push((path, FileState::placeholder()));
+                        stats.skipped_files += 1;
+                    }
+                    continue;
+                }
+            }
             // If the path was present, check reserved path first and delete it.
-            let present_file_deleted = before.is_present() && remove_old_file(&disk_path)?;
+            // Git submodule is materialized as a directory, which is deleted
+            // only if it's empty. A file at an absent path is deleted too if
+            // the interrupted checkout may have written it.
+            let present_file_deleted = if before.is_present() {
+                if matches!(before.as_normal(), Some(TreeValue::GitSubmodule(_))) {
+                    remove_old_submodule_dir(&disk_path)?
+                } else {
+                    remove_old_file(&disk_path)?
+                }
+            } else if interrupted
+                .as_deref()
+                .is_some_and(|interrupted| modified_since(&disk_path, interrupted.started))
+            {
+                remove_old_file(&disk_path)?
+            } else {
+                false
+            };
             // If not, create temporary file to test the path validity.
             if !present_file_deleted && !can_create_new_file(&disk_path)? {
                 changed_file_states.push((path, FileState::placeholder()));
@@ -1817,7 +2723,6 @@ impl TreeState {
                 continue;
             }
 
-            // TODO: Check that the file has not changed before overwriting/removing it.
             let file_state = match after {
                 MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
                     let mut parent_dir = disk_path.parent().unwrap();
@@ -1834,7 +2739,36 @@ impl TreeState {
                     executable,
                     mut reader,
                     ..
-                } => self.write_file(&disk_path, &mut reader, executable)?,
+                } if lfs_store.is_some() && lfs_settings.is_lfs(&path) => {
+                    let lfs_store = lfs_store.as_ref().unwrap();
+                    let mut contents = lfs_store
+                        .from_pointer(&mut reader, lfs_settings.fetch_with())
+                        .map_err(|err| CheckoutError::Other {
+                            message: format!("Failed to read Git LFS file {path:?}"),
+                            err: err.into(),
+                        })?;
+                    self.write_file(&disk_path, &mut contents, executable)?
+                }
+                MaterializedTreeValue::File {
+                    executable,
+                    mut reader,
+                    ..
+                } => match eol_settings.mode_for(&path) {
+                    EolConversionMode::InputOutput => {
+                        let mut contents = vec![];
+                        reader
+                            .read_to_end(&mut contents)
+                            .map_err(|err| CheckoutError::Other {
+                                message: format!("Failed to read file {path:?}"),
+                                err: err.into(),
+                            })?;
+                        let contents = EolConversionMode::InputOutput.convert_output(&contents);
+                        self.write_file(&disk_path, &mut contents.as_ref(), executable)?
+                    }
+                    EolConversionMode::None | EolConversionMode::Input => {
+                        self.write_file(&disk_path, &mut reader, executable)?
+                    }
+                },
                 MaterializedTreeValue::Symlink { id: _, target } => {
                     if self.symlink_support {
                         self.write_symlink(&disk_path, target)?
@@ -1842,28 +2776,17 @@ impl TreeState {
                         self.write_file(&disk_path, &mut target.as_bytes(), false)?
                     }
                 }
-                MaterializedTreeValue::GitSubmodule(_) => {
-                    eprintln!("ignoring git submodule at {path:?}");
-                    FileState::for_gitsubmodule()
-                }
+                MaterializedTreeValue::GitSubmodule(_) => self.write_submodule_dir(&disk_path)?,
                 MaterializedTreeValue::Tree(_) => {
                     panic!("unexpected tree entry in diff at {path:?}");
                 }
                 MaterializedTreeValue::FileConflict {
-                    id: _,
+                    id,
                     contents,
                     executable,
                 } => {
-                    let conflict_marker_len = choose_materialized_conflict_marker_len(&contents);
-                    let data = materialize_merge_result_to_bytes_with_marker_len(
-                        &contents,
-                        conflict_marker_style,
-                        conflict_marker_len,
-                    )
-                    .into();
-                    let materialized_conflict_data = MaterializedConflictData {
-                        conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
-                    };
+                    let (data, materialized_conflict_data) =
+                        file_conflict_data(&path, &id, &contents, options);
                     self.write_conflict(
                         &disk_path,
                         data,
@@ -1872,9 +2795,7 @@ impl TreeState {
                     )?
                 }
                 MaterializedTreeValue::OtherConflict { id } => {
-                    // Unless all terms are regular files, we can't do much
-                    // better than trying to describe the merge.
-                    let data = id.describe().into_bytes();
+                    let data = self.other_conflict_data(&path, &id, options)?;
                     let executable = false;
                     self.write_conflict(&disk_path, data, executable, None)?
                 }
@@ -1886,6 +2807,50 @@ impl TreeState {
         Ok(stats)
     }
 
+    /// Fails with the paths to be updated whose files changed on disk since
+    /// their states were recorded. Files which were deleted from disk aren't
+    /// considered changed since checking them out loses nothing.
+    ///
+    /// Like snapshotting, this only compares file metadata, so a change
+    /// within the file system's timestamp granularity may go unnoticed.
+    async fn check_changed_files(
+        &self,
+        old_tree: &MergedTree,
+        new_tree: &MergedTree,
+        matcher: &dyn Matcher,
+    ) -> Result<(), CheckoutError> {
+        let file_states = self.file_states.all();
+        let mut changed_paths = vec![];
+        let mut diff_stream = old_tree.diff_stream(new_tree, matcher);
+        while let Some(TreeDiffEntry { path, .. }) = diff_stream.next().await {
+            let Some(recorded_state) = file_states.get(&path) else {
+                continue;
+            };
+            // States without mtime (such as placeholders for skipped files)
+            // don't describe the file on disk.
+            if recorded_state.mtime == MillisSinceEpoch(0) {
+                continue;
+            }
+            let Some(disk_path) = existing_file_path(&self.working_copy_path, &path) else {
+                continue;
+            };
+            let Ok(metadata) = disk_path.symlink_metadata() else {
+                continue;
+            };
+            // Directories and other special files are never overwritten.
+            if file_state(&metadata).is_some_and(|state| !state.is_clean(&recorded_state)) {
+                changed_paths.push(path);
+            }
+        }
+        if changed_paths.is_empty() {
+            Ok(())
+        } else {
+            Err(CheckoutError::ChangedFiles {
+                paths: changed_paths,
+            })
+        }
+    }
+
     pub async fn reset(&mut self, new_tree: &MergedTree) -> Result<(), ResetError> {
         let old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => ResetError::SourceNotFound {
@@ -1932,6 +2897,7 @@ impl TreeState {
                     file_type,
                     mtime: MillisSinceEpoch(0),
                     size: 0,
+                    inode: 0,
                     materialized_conflict_data: None,
                 };
                 changed_file_states.push((path, file_state));
@@ -1940,6 +2906,9 @@ impl TreeState {
         self.file_states
             .merge_in(changed_file_states, &deleted_files);
         self.tree_id = new_tree.id();
+        // The files are recorded as they are, so an interrupted checkout is
+        // abandoned.
+        self.pending_checkout = None;
         Ok(())
     }
 
@@ -1970,6 +2939,7 @@ pub struct LocalWorkingCopy {
     state_path: PathBuf,
     checkout_state: OnceCell<CheckoutState>,
     tree_state: OnceCell<TreeState>,
+    lock_manager: LockManager,
 }
 
 impl WorkingCopy for LocalWorkingCopy {
@@ -1997,12 +2967,19 @@ impl WorkingCopy for LocalWorkingCopy {
         Ok(self.tree_state()?.sparse_patterns())
     }
 
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        Ok(self.tree_state()?.tracking_patterns())
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let lock_path = self.state_path.join("working_copy.lock");
-        let lock = FileLock::lock(lock_path).map_err(|err| WorkingCopyStateError {
-            message: "Failed to lock working copy".to_owned(),
-            err: err.into(),
-        })?;
+        let lock = self
+            .lock_manager
+            .lock(LockKind::WorkingCopy, lock_path)
+            .map_err(|err| WorkingCopyStateError {
+                message: "Failed to lock working copy".to_owned(),
+                err: err.into(),
+            })?;
 
         let wc = LocalWorkingCopy {
             store: self.store.clone(),
@@ -2013,9 +2990,16 @@ impl WorkingCopy for LocalWorkingCopy {
             // TODO: It's expensive to reload the whole tree. We should copy it from `self` if it
             // hasn't changed.
             tree_state: OnceCell::new(),
+            lock_manager: self.lock_manager.clone(),
         };
         let old_operation_id = wc.operation_id().clone();
-        let old_tree_id = wc.tree_id()?.clone();
+        // If a checkout was interrupted, it will be finished before the files
+        // are snapshotted or updated again.
+        let tree_state = wc.tree_state()?;
+        let old_tree_id = tree_state
+            .pending_checkout()
+            .unwrap_or(tree_state.current_tree_id())
+            .clone();
         Ok(Box::new(LockedLocalWorkingCopy {
             wc,
             lock,
@@ -2043,6 +3027,7 @@ impl LocalWorkingCopy {
         workspace_id: WorkspaceId,
     ) -> Result<LocalWorkingCopy, WorkingCopyStateError> {
         let proto = crate::protos::working_copy::Checkout {
+            format_version: CHECKOUT_FORMAT_VERSION,
             operation_id: operation_id.to_bytes(),
             workspace_id: workspace_id.as_str().to_string(),
         };
@@ -2065,6 +3050,7 @@ impl LocalWorkingCopy {
             state_path,
             checkout_state: OnceCell::new(),
             tree_state: OnceCell::with_value(tree_state),
+            lock_manager: LockManager::new(),
         })
     }
 
@@ -2079,9 +3065,16 @@ impl LocalWorkingCopy {
             state_path,
             checkout_state: OnceCell::new(),
             tree_state: OnceCell::new(),
+            lock_manager: LockManager::new(),
         }
     }
 
+    /// Sets the lock manager used to lock the working copy.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
     pub fn state_path(&self) -> &Path {
         &self.state_path
     }
@@ -2101,6 +3094,13 @@ impl LocalWorkingCopy {
         self.checkout_state.get_or_init(|| {
             let buf = fs::read(self.state_path.join("checkout")).unwrap();
             let proto = crate::protos::working_copy::Checkout::decode(&*buf).unwrap();
+            if proto.format_version > CHECKOUT_FORMAT_VERSION {
+                // The fields we know about keep their meaning in newer versions.
+                tracing::warn!(
+                    version = proto.format_version,
+                    "reading checkout file written by a newer version"
+                );
+            }
             CheckoutState {
                 operation_id: OperationId::new(proto.operation_id),
                 workspace_id: if proto.workspace_id.is_empty() {
@@ -2147,6 +3147,7 @@ impl LocalWorkingCopy {
     #[instrument(skip_all)]
     fn save(&mut self) {
         self.write_proto(crate::protos::working_copy::Checkout {
+            format_version: CHECKOUT_FORMAT_VERSION,
             operation_id: self.operation_id().to_bytes(),
             workspace_id: self.workspace_id().as_str().to_string(),
         });
@@ -2189,14 +3190,18 @@ impl WorkingCopyFactory for LocalWorkingCopyFactory {
         state_path: PathBuf,
         operation_id: OperationId,
         workspace_id: WorkspaceId,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
-        Ok(Box::new(LocalWorkingCopy::init(
-            store,
-            working_copy_path,
-            state_path,
-            operation_id,
-            workspace_id,
-        )?))
+        Ok(Box::new(
+            LocalWorkingCopy::init(
+                store,
+                working_copy_path,
+                state_path,
+                operation_id,
+                workspace_id,
+            )?
+            .with_lock_manager(user_settings.lock_manager()),
+        ))
     }
 
     fn load_working_copy(
@@ -2204,12 +3209,12 @@ impl WorkingCopyFactory for LocalWorkingCopyFactory {
         store: Arc<Store>,
         working_copy_path: PathBuf,
         state_path: PathBuf,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
-        Ok(Box::new(LocalWorkingCopy::load(
-            store,
-            working_copy_path,
-            state_path,
-        )))
+        Ok(Box::new(
+            LocalWorkingCopy::load(store, working_copy_path, state_path)
+                .with_lock_manager(user_settings.lock_manager()),
+        ))
     }
 }
 
@@ -2218,7 +3223,7 @@ impl WorkingCopyFactory for LocalWorkingCopyFactory {
 pub struct LockedLocalWorkingCopy {
     wc: LocalWorkingCopy,
     #[expect(dead_code)]
-    lock: FileLock,
+    lock: ManagedLock,
     old_operation_id: OperationId,
     old_tree_id: MergedTreeId,
     tree_state_dirty: bool,
@@ -2263,8 +3268,6 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         commit: &Commit,
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
-        // TODO: Write a "pending_checkout" file with the new TreeId so we can
-        // continue an interrupted update if we find such a file.
         let new_tree = commit.tree()?;
         let tree_state = self
             .wc
@@ -2273,7 +3276,7 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
                 message: "Failed to load the working copy state".to_string(),
                 err: err.into(),
             })?;
-        if tree_state.tree_id != *commit.tree_id() {
+        if tree_state.tree_id != *commit.tree_id() || tree_state.pending_checkout.is_some() {
             let stats = tree_state.check_out(&new_tree, options)?;
             self.tree_state_dirty = true;
             Ok(stats)
@@ -2323,8 +3326,6 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         new_sparse_patterns: Vec<RepoPathBuf>,
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
-        // TODO: Write a "pending_checkout" file with new sparse patterns so we can
-        // continue an interrupted update if we find such a file.
         let stats = self
             .wc
             .tree_state_mut()
@@ -2337,12 +3338,35 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(stats)
     }
 
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        self.wc.tracking_patterns()
+    }
+
+    fn track(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        self.wc.tree_state_mut()?.track(paths);
+        self.tree_state_dirty = true;
+        Ok(())
+    }
+
+    fn untrack(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        self.wc.tree_state_mut()?.untrack(paths);
+        self.tree_state_dirty = true;
+        Ok(())
+    }
+
     #[instrument(skip_all)]
     fn finish(
         mut self: Box<Self>,
         operation_id: OperationId,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
-        assert!(self.tree_state_dirty || &self.old_tree_id == self.wc.tree_id()?);
+        let tree_state = self.wc.tree_state()?;
+        assert!(
+            self.tree_state_dirty
+                || self.old_tree_id
+                    == *tree_state
+                        .pending_checkout()
+                        .unwrap_or(tree_state.current_tree_id())
+        );
         if self.tree_state_dirty {
             self.wc
                 .tree_state_mut()?
@@ -2396,6 +3420,7 @@ mod tests {
             },
             mtime: MillisSinceEpoch(0),
             size,
+            inode: 0,
             materialized_conflict_data: None,
         };
         let new_static_entry = |path: &'static str, size| (repo_path(path), new_state(size));
@@ -2445,6 +3470,7 @@ mod tests {
             },
             mtime: MillisSinceEpoch(0),
             size,
+            inode: 0,
             materialized_conflict_data: None,
         };
         let new_proto_entry = |path: &str, size| {
@@ -2510,6 +3536,7 @@ mod tests {
             },
             mtime: MillisSinceEpoch(0),
             size,
+            inode: 0,
             materialized_conflict_data: None,
         };
         let new_proto_entry = |path: &str, size| {
@@ -2534,9 +3561,15 @@ mod tests {
             Some(new_state(4))
         );
 
+        assert_eq!(file_states.child_names_at(RepoPath::root()), ["b", "b#"]);
+
         // At prefixed dir
         let prefixed_states =
             file_states.prefixed_at(RepoPath::root(), RepoPathComponent::new("b"));
+        assert_eq!(
+            prefixed_states.child_names_at(repo_path("b")),
+            ["c", "d", "d#", "e"]
+        );
         assert_eq!(
             prefixed_states.paths().collect_vec(),
             ["b/c", "b/d/e", "b/d#", "b/e"].map(repo_path)
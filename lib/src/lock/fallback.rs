@@ -14,11 +14,15 @@
 
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::io::Write as _;
 use std::path::PathBuf;
 use std::time::Duration;
 
 use tracing::instrument;
 
+use super::current_holder;
+use super::read_holder;
+use super::timed_out_error;
 use super::FileLockError;
 
 pub struct FileLock {
@@ -29,13 +33,15 @@ pub struct FileLock {
 struct BackoffIterator {
     next_sleep_secs: f32,
     elapsed_secs: f32,
+    limit_secs: f32,
 }
 
 impl BackoffIterator {
-    fn new() -> Self {
+    fn new(limit: Duration) -> Self {
         Self {
             next_sleep_secs: 0.001,
             elapsed_secs: 0.0,
+            limit_secs: limit.as_secs_f32(),
         }
     }
 }
@@ -44,7 +50,7 @@ impl Iterator for BackoffIterator {
     type Item = Duration;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.elapsed_secs >= 10.0 {
+        if self.elapsed_secs >= self.limit_secs {
             None
         } else {
             let current_sleep = self.next_sleep_secs * (rand::random::<f32>() + 0.5);
@@ -59,13 +65,26 @@ impl Iterator for BackoffIterator {
 #[cfg_attr(unix, allow(dead_code))]
 impl FileLock {
     pub fn lock(path: PathBuf) -> Result<FileLock, FileLockError> {
+        Self::lock_with_timeout(path, None)
+    }
+
+    /// Like `lock()`, but gives up after `timeout` if the lock is held by
+    /// someone else. Since a lock file left behind by a crashed process can't
+    /// be told apart from a live one, this gives up after 10 seconds if
+    /// `timeout` is `None`.
+    pub fn lock_with_timeout(
+        path: PathBuf,
+        timeout: Option<Duration>,
+    ) -> Result<FileLock, FileLockError> {
         let mut options = OpenOptions::new();
         options.create_new(true);
         options.write(true);
-        let mut backoff_iterator = BackoffIterator::new();
+        let mut backoff_iterator = BackoffIterator::new(timeout.unwrap_or(Duration::from_secs(10)));
         loop {
             match options.open(&path) {
-                Ok(file) => {
+                Ok(mut file) => {
+                    // Only used for diagnostics, so failing to write it isn't an error.
+                    _ = file.write_all(current_holder().as_bytes());
                     return Ok(FileLock { path, _file: file });
                 }
                 Err(err)
@@ -76,10 +95,11 @@ impl FileLock {
                     if let Some(duration) = backoff_iterator.next() {
                         std::thread::sleep(duration);
                     } else {
+                        let holder = read_holder(&path);
                         return Err(FileLockError {
                             message: "Timed out while trying to create lock file",
                             path,
-                            err,
+                            err: timed_out_error(&holder),
                         });
                     }
                 }
@@ -15,18 +15,27 @@
 #![allow(missing_docs)]
 
 mod fallback;
+mod nfs;
 #[cfg(unix)]
 mod unix;
 
+use std::collections::HashMap;
+use std::fmt;
 use std::io;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::ThreadId;
+use std::time::Duration;
 
+use serde::Deserialize;
 use thiserror::Error;
 
 #[cfg(not(unix))]
-pub use self::fallback::FileLock;
+use self::fallback::FileLock as NativeFileLock;
 #[cfg(unix)]
-pub use self::unix::FileLock;
+use self::unix::FileLock as NativeFileLock;
 
 #[derive(Debug, Error)]
 #[error("{message}: {path}")]
@@ -37,6 +46,238 @@ pub struct FileLockError {
     pub err: io::Error,
 }
 
+/// Describes the current process. Written to lock files so that processes
+/// waiting for the lock can tell who holds it.
+///
+/// Lock files may be readable by other users, so this must not include the
+/// command-line arguments, which could contain secrets.
+fn current_holder() -> String {
+    let executable = std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown executable".to_owned());
+    format!("process {} ({executable})", std::process::id())
+}
+
+/// Reads the description of the process holding the lock at `path`.
+fn read_holder(path: &Path) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(holder) if !holder.is_empty() => holder.lines().next().unwrap_or_default().to_owned(),
+        _ => "an unknown process".to_owned(),
+    }
+}
+
+pub(crate) fn timed_out_error(holder: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("Timed out waiting for the lock held by {holder}"),
+    )
+}
+
+/// How lock files are acquired.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LockMode {
+    /// Use the native locking of the platform, which is `flock()` on Unix.
+    #[default]
+    Native,
+    /// Create lock files exclusively and keep them alive with heartbeats, so
+    /// that locking works on network file systems where `flock()` doesn't.
+    /// Content-addressed files are also written without replacing existing
+    /// files by rename.
+    Nfs,
+}
+
+/// A lock file, which is released when dropped.
+pub struct FileLock {
+    _inner: FileLockInner,
+}
+
+// The locks are only held to be released on drop.
+#[expect(dead_code)]
+enum FileLockInner {
+    Native(NativeFileLock),
+    Nfs(nfs::FileLock),
+}
+
+impl FileLock {
+    /// Acquires the lock file at `path` using native file locks, waiting for
+    /// it forever.
+    pub fn lock(path: PathBuf) -> Result<FileLock, FileLockError> {
+        Self::lock_with_timeout(path, None)
+    }
+
+    /// Like `lock()`, but gives up after `timeout` if the lock is held by
+    /// someone else.
+    pub fn lock_with_timeout(
+        path: PathBuf,
+        timeout: Option<Duration>,
+    ) -> Result<FileLock, FileLockError> {
+        Self::lock_with_mode(path, timeout, LockMode::Native)
+    }
+
+    /// Like `lock_with_timeout()`, but acquires the lock in the given `mode`.
+    pub fn lock_with_mode(
+        path: PathBuf,
+        timeout: Option<Duration>,
+        mode: LockMode,
+    ) -> Result<FileLock, FileLockError> {
+        let inner = match mode {
+            LockMode::Native => {
+                FileLockInner::Native(NativeFileLock::lock_with_timeout(path, timeout)?)
+            }
+            LockMode::Nfs => FileLockInner::Nfs(nfs::FileLock::lock_with_timeout(path, timeout)?),
+        };
+        Ok(FileLock { _inner: inner })
+    }
+}
+
+/// The locks which may be held at the same time, in the order they must be
+/// acquired.
+///
+/// A thread holding a lock may only acquire locks of the same or a later kind.
+/// For example, the working copy is locked while it's snapshotted, and the
+/// snapshot is then committed by taking the op-heads lock. Resolving
+/// divergent operations under the op-heads lock may in turn write commits,
+/// which takes the Git repo lock. A process taking these locks in any other
+/// order could deadlock against another one.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LockKind {
+    /// Lock on the working-copy state of a workspace.
+    WorkingCopy,
+    /// Lock on the op heads of a repo.
+    OpHeads,
+    /// Lock on the metadata the Git backend stores next to the Git repo.
+    GitRepo,
+}
+
+impl fmt::Display for LockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LockKind::WorkingCopy => "working-copy",
+            LockKind::OpHeads => "op-heads",
+            LockKind::GitRepo => "Git repo",
+        })
+    }
+}
+
+/// Error returned when a lock would be acquired out of the `LockKind` order.
+#[derive(Debug, Error)]
+#[error("Cannot acquire the {kind} lock while holding the {held} lock")]
+pub struct LockOrderError {
+    pub kind: LockKind,
+    pub held: LockKind,
+}
+
+/// Kinds of the locks held by each thread, in acquisition order.
+static HELD_LOCKS: Mutex<Option<HashMap<ThreadId, Vec<LockKind>>>> = Mutex::new(None);
+
+/// Records that a thread holds a lock of some kind until dropped.
+///
+/// The guard may be dropped on another thread; the lock is still attributed
+/// to the thread which acquired it.
+#[derive(Debug)]
+pub struct LockOrderGuard {
+    kind: LockKind,
+    thread: ThreadId,
+}
+
+impl Drop for LockOrderGuard {
+    fn drop(&mut self) {
+        let mut held_locks = HELD_LOCKS.lock().unwrap();
+        let held_locks = held_locks.get_or_insert_with(HashMap::new);
+        if let Some(kinds) = held_locks.get_mut(&self.thread) {
+            if let Some(index) = kinds.iter().rposition(|kind| *kind == self.kind) {
+                kinds.remove(index);
+            }
+            if kinds.is_empty() {
+                held_locks.remove(&self.thread);
+            }
+        }
+    }
+}
+
+/// A file lock acquired through a `LockManager`.
+pub struct ManagedLock {
+    // Declared first so the file lock is released before the order guard.
+    _lock: FileLock,
+    _order: LockOrderGuard,
+}
+
+impl ManagedLock {
+    /// Combines a file lock with the guard that was entered before taking it.
+    pub fn new(order: LockOrderGuard, lock: FileLock) -> Self {
+        ManagedLock {
+            _lock: lock,
+            _order: order,
+        }
+    }
+}
+
+/// Acquires file locks, enforcing the `LockKind` order.
+#[derive(Clone, Debug, Default)]
+pub struct LockManager {
+    timeout: Option<Duration>,
+    mode: LockMode,
+}
+
+impl LockManager {
+    /// Creates a lock manager which waits for locks forever.
+    pub fn new() -> Self {
+        LockManager {
+            timeout: None,
+            mode: LockMode::Native,
+        }
+    }
+
+    /// Sets how long to wait for a lock held by someone else before failing.
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Self {
+        LockManager { timeout, ..self }
+    }
+
+    /// Sets how lock files are acquired.
+    pub fn with_mode(self, mode: LockMode) -> Self {
+        LockManager { mode, ..self }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    /// Records that the current thread is about to acquire a lock of the
+    /// given kind, failing if it already holds a lock of a later kind.
+    pub fn enter(&self, kind: LockKind) -> Result<LockOrderGuard, LockOrderError> {
+        let thread = thread::current().id();
+        let mut held_locks = HELD_LOCKS.lock().unwrap();
+        let kinds = held_locks
+            .get_or_insert_with(HashMap::new)
+            .entry(thread)
+            .or_default();
+        if let Some(&held) = kinds.iter().max() {
+            if held > kind {
+                return Err(LockOrderError { kind, held });
+            }
+        }
+        kinds.push(kind);
+        Ok(LockOrderGuard { kind, thread })
+    }
+
+    /// Acquires the lock file at `path` as a lock of the given kind.
+    pub fn lock(&self, kind: LockKind, path: PathBuf) -> Result<ManagedLock, FileLockError> {
+        let order = self.enter(kind).map_err(|err| FileLockError {
+            message: "Failed to lock lock file",
+            path: path.clone(),
+            err: io::Error::other(err),
+        })?;
+        let lock = FileLock::lock_with_mode(path, self.timeout, self.mode)?;
+        Ok(ManagedLock::new(order, lock))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::max;
@@ -51,6 +292,7 @@ mod tests {
 
     #[test_case(FileLock::lock)]
     #[cfg_attr(unix, test_case(fallback::FileLock::lock))]
+    #[test_case(nfs::FileLock::lock)]
     fn lock_basic<T>(lock_fn: fn(PathBuf) -> Result<T, FileLockError>) {
         let temp_dir = new_temp_dir();
         let lock_path = temp_dir.path().join("test.lock");
@@ -64,6 +306,7 @@ mod tests {
 
     #[test_case(FileLock::lock)]
     #[cfg_attr(unix, test_case(fallback::FileLock::lock))]
+    #[test_case(nfs::FileLock::lock)]
     fn lock_concurrent<T>(lock_fn: fn(PathBuf) -> Result<T, FileLockError>) {
         let temp_dir = new_temp_dir();
         let data_path = temp_dir.path().join("test");
@@ -85,4 +328,96 @@ mod tests {
         let value = u32::from_le_bytes(data.try_into().unwrap());
         assert_eq!(value, num_threads as u32);
     }
+
+    #[test_case(FileLock::lock_with_timeout)]
+    #[cfg_attr(unix, test_case(fallback::FileLock::lock_with_timeout))]
+    #[test_case(nfs::FileLock::lock_with_timeout)]
+    fn lock_timeout<T>(lock_fn: fn(PathBuf, Option<Duration>) -> Result<T, FileLockError>) {
+        let temp_dir = new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        let _lock = lock_fn(lock_path.clone(), None).unwrap();
+        let err = lock_fn(lock_path.clone(), Some(Duration::from_millis(10)))
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.path, lock_path);
+        assert_eq!(err.err.kind(), io::ErrorKind::TimedOut);
+        let executable = std::env::current_exe().unwrap();
+        let holder = format!(
+            "held by process {} ({})",
+            std::process::id(),
+            executable.file_name().unwrap().to_string_lossy()
+        );
+        assert!(err.err.to_string().ends_with(&holder), "{err:?}");
+    }
+
+    #[test]
+    fn lock_nfs_stale() {
+        let temp_dir = new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        let stale_after = Duration::from_millis(50);
+
+        // A lock file without heartbeat is removed once it becomes stale
+        fs::write(&lock_path, "process 0 (crashed)\nheartbeat: 0\n").unwrap();
+        let lock =
+            nfs::FileLock::lock_with_stale_after(lock_path.clone(), None, stale_after).unwrap();
+        let holder = format!("process {} (", std::process::id());
+        assert!(fs::read_to_string(&lock_path).unwrap().starts_with(&holder));
+
+        // A lock kept alive by its holder isn't
+        let err = nfs::FileLock::lock_with_stale_after(
+            lock_path.clone(),
+            Some(Duration::from_millis(2500)),
+            Duration::from_secs(2),
+        )
+        .map(|_| ())
+        .unwrap_err();
+        assert_eq!(err.err.kind(), io::ErrorKind::TimedOut);
+        drop(lock);
+        assert!(!lock_path.exists());
+
+        // A lock file recreated by another process isn't removed on drop
+        let lock = nfs::FileLock::lock(lock_path.clone()).unwrap();
+        fs::remove_file(&lock_path).unwrap();
+        fs::write(&lock_path, "process 0 (other)\nheartbeat: 0\n").unwrap();
+        drop(lock);
+        assert!(lock_path.exists());
+    }
+
+    #[test]
+    fn lock_order() {
+        let temp_dir = new_temp_dir();
+        let manager = LockManager::new();
+        let wc_path = temp_dir.path().join("wc.lock");
+        let op_heads_path = temp_dir.path().join("op_heads.lock");
+        let git_path = temp_dir.path().join("git.lock");
+
+        let op_heads_lock = manager
+            .lock(LockKind::OpHeads, op_heads_path.clone())
+            .unwrap();
+        let err = manager
+            .lock(LockKind::WorkingCopy, wc_path.clone())
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            err.err.to_string(),
+            "Cannot acquire the working-copy lock while holding the op-heads lock"
+        );
+        assert!(!wc_path.exists());
+        let git_lock = manager.lock(LockKind::GitRepo, git_path.clone()).unwrap();
+        drop(op_heads_lock);
+        assert!(manager.enter(LockKind::OpHeads).is_err());
+        drop(git_lock);
+
+        // Locks held by other threads don't count
+        let _wc_lock = manager.lock(LockKind::WorkingCopy, wc_path).unwrap();
+        let op_heads_lock = manager.lock(LockKind::OpHeads, op_heads_path).unwrap();
+        thread::spawn(move || {
+            let _git_lock = manager.lock(LockKind::GitRepo, git_path.clone()).unwrap();
+            drop(op_heads_lock);
+            assert!(manager.enter(LockKind::WorkingCopy).is_err());
+        })
+        .join()
+        .unwrap();
+        assert!(LockManager::new().enter(LockKind::OpHeads).is_ok());
+    }
 }
@@ -0,0 +1,240 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock files for network file systems.
+//!
+//! `flock()` is unreliable on NFS, but exclusive file creation isn't. The lock
+//! is held by creating the lock file with `O_EXCL`. The holder keeps rewriting
+//! a heartbeat counter in the file, so that a lock file left behind by a
+//! crashed process (possibly on another machine) can be detected and removed.
+//! Staleness is judged by the local clock of the waiting process, so clock
+//! skew between machines doesn't matter.
+
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Seek as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use tracing::instrument;
+
+use super::current_holder;
+use super::read_holder;
+use super::timed_out_error;
+use super::FileLockError;
+
+/// How often the holder of a lock updates the heartbeat.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the heartbeat may stay unchanged before the lock is considered
+/// abandoned.
+const STALE_AFTER: Duration = Duration::from_secs(10);
+/// Upper bound of the backoff between attempts to create the lock file.
+const MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+pub struct FileLock {
+    path: PathBuf,
+    // Unique line written to the lock file, so that a lock file recreated by
+    // another process after ours was removed as stale isn't deleted on drop.
+    token: String,
+    // Dropping the sender stops the heartbeat thread.
+    stop_heartbeat: Option<mpsc::Sender<()>>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl FileLock {
+    #[cfg(test)]
+    pub fn lock(path: PathBuf) -> Result<FileLock, FileLockError> {
+        Self::lock_with_timeout(path, None)
+    }
+
+    /// Acquires the lock file at `path`. Gives up after `timeout` if the lock
+    /// is held by someone else, or waits forever if `timeout` is `None`.
+    pub fn lock_with_timeout(
+        path: PathBuf,
+        timeout: Option<Duration>,
+    ) -> Result<FileLock, FileLockError> {
+        Self::lock_with_stale_after(path, timeout, STALE_AFTER)
+    }
+
+    pub(super) fn lock_with_stale_after(
+        path: PathBuf,
+        timeout: Option<Duration>,
+        stale_after: Duration,
+    ) -> Result<FileLock, FileLockError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut backoff = Duration::from_millis(1);
+        // Contents of the existing lock file, and when they were first seen.
+        let mut last_seen: Option<(Vec<u8>, Instant)> = None;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Self::start_heartbeat(path, file),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(FileLockError {
+                        message: "Failed to create lock file",
+                        path,
+                        err,
+                    });
+                }
+            }
+
+            let now = Instant::now();
+            // The file may have been released in the meantime, in which case
+            // the next attempt will likely succeed.
+            if let Ok(contents) = fs::read(&path) {
+                match &last_seen {
+                    Some((seen, since)) if *seen == contents => {
+                        if now.duration_since(*since) >= stale_after {
+                            let holder = read_holder(&path);
+                            tracing::warn!(?path, holder, "removing stale lock file");
+                            remove_stale_lock_file(&path, &contents);
+                            last_seen = None;
+                            continue;
+                        }
+                    }
+                    _ => {
+                        tracing::info!(
+                            ?path,
+                            holder = read_holder(&path),
+                            "waiting for lock held by another process"
+                        );
+                        last_seen = Some((contents, now));
+                    }
+                }
+            }
+
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                let holder = read_holder(&path);
+                return Err(FileLockError {
+                    message: "Timed out while trying to create lock file",
+                    path,
+                    err: timed_out_error(&holder),
+                });
+            }
+            let jitter = 0.5 + rand::random::<f64>();
+            let sleep = backoff.mul_f64(jitter);
+            thread::sleep(deadline.map_or(sleep, |deadline| {
+                sleep.min(deadline.saturating_duration_since(now))
+            }));
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    fn start_heartbeat(path: PathBuf, mut file: File) -> Result<Self, FileLockError> {
+        let token = format!("token: {:016x}", rand::random::<u64>());
+        // The contents are otherwise only used for diagnostics and stale lock
+        // detection, so failing to write them isn't an error.
+        let owner = format!("{}\nhost: {}\n{token}\n", current_holder(), hostname());
+        _ = write_heartbeat(&mut file, &owner, 0);
+        let (stop_heartbeat, stopped) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("jj-lock-heartbeat".to_owned())
+            .spawn(move || {
+                let mut count: u64 = 0;
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stopped.recv_timeout(HEARTBEAT_INTERVAL)
+                {
+                    count += 1;
+                    // The file is written through the handle, so this never
+                    // touches a lock file created by another process.
+                    _ = write_heartbeat(&mut file, &owner, count);
+                }
+            });
+        match spawned {
+            Ok(heartbeat) => Ok(FileLock {
+                path,
+                token,
+                stop_heartbeat: Some(stop_heartbeat),
+                heartbeat: Some(heartbeat),
+            }),
+            Err(err) => {
+                // Without the heartbeat, other processes would soon consider
+                // the lock stale, so don't pretend to hold it.
+                _ = fs::remove_file(&path);
+                Err(FileLockError {
+                    message: "Failed to start lock heartbeat",
+                    path,
+                    err,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "hostname")]
+fn hostname() -> String {
+    whoami::fallible::hostname().unwrap_or_default()
+}
+
+#[cfg(not(feature = "hostname"))]
+fn hostname() -> String {
+    String::new()
+}
+
+fn write_heartbeat(file: &mut File, owner: &str, count: u64) -> io::Result<()> {
+    file.rewind()?;
+    file.write_all(format!("{owner}heartbeat: {count}\n").as_bytes())?;
+    file.flush()?;
+    file.sync_data()
+}
+
+/// Removes the lock file at `path` if it still has the stale `contents`.
+fn remove_stale_lock_file(path: &Path, contents: &[u8]) {
+    // Move the file out of the way first, so that a lock file which was
+    // recreated by another process in the meantime can be put back.
+    let stale_path = path.with_extension(format!(
+        "stale-{}-{:x}",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    if fs::rename(path, &stale_path).is_err() {
+        return;
+    }
+    if fs::read(&stale_path).is_ok_and(|moved| moved != contents) {
+        // Fails if yet another process created the lock file, in which case
+        // the holder we moved away will notice that its lock is gone only
+        // when it's released. There's nothing better we can do.
+        _ = fs::hard_link(&stale_path, path);
+    }
+    _ = fs::remove_file(&stale_path);
+}
+
+impl Drop for FileLock {
+    #[instrument(skip_all)]
+    fn drop(&mut self) {
+        drop(self.stop_heartbeat.take());
+        if let Some(heartbeat) = self.heartbeat.take() {
+            _ = heartbeat.join();
+        }
+        // If our lock file was removed as stale, the file at the path may
+        // belong to another process by now.
+        let ours = fs::read_to_string(&self.path)
+            .is_ok_and(|contents| contents.lines().any(|line| line == self.token));
+        if !ours {
+            tracing::warn!(?self.path, "Lock file was taken over by another process");
+            return;
+        }
+        fs::remove_file(&self.path)
+            .inspect_err(|err| tracing::warn!(?err, ?self.path, "Failed to delete lock file"))
+            .ok();
+    }
+}
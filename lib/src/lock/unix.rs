@@ -15,11 +15,18 @@
 #![allow(missing_docs)]
 
 use std::fs::File;
+use std::fs::OpenOptions;
+use std::os::unix::fs::FileExt as _;
 use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
 
 use rustix::fs::FlockOperation;
+use rustix::io::Errno;
 use tracing::instrument;
 
+use super::current_holder;
+use super::timed_out_error;
 use super::FileLockError;
 
 pub struct FileLock {
@@ -28,22 +35,50 @@ pub struct FileLock {
 }
 
 impl FileLock {
-    pub fn lock(path: PathBuf) -> Result<FileLock, FileLockError> {
+    /// Acquires the lock file at `path`. Gives up after `timeout` if the lock
+    /// is held by someone else, or waits forever if `timeout` is `None`.
+    pub fn lock_with_timeout(
+        path: PathBuf,
+        timeout: Option<Duration>,
+    ) -> Result<FileLock, FileLockError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
         loop {
-            // Create lockfile, or open pre-existing one
-            let file = File::create(&path).map_err(|err| FileLockError {
-                message: "Failed to open lock file",
-                path: path.clone(),
-                err,
-            })?;
-            // If the lock was already held, wait for it to be released
-            rustix::fs::flock(&file, FlockOperation::LockExclusive).map_err(|errno| {
-                FileLockError {
-                    message: "Failed to lock lock file",
+            // Create lockfile, or open pre-existing one. Don't truncate it since
+            // it describes the current holder.
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .map_err(|err| FileLockError {
+                    message: "Failed to open lock file",
                     path: path.clone(),
-                    err: errno.into(),
+                    err,
+                })?;
+            match rustix::fs::flock(&file, FlockOperation::NonBlockingLockExclusive) {
+                Ok(()) => {}
+                Err(Errno::WOULDBLOCK) => {
+                    // If the lock was already held, wait for it to be released
+                    let holder = super::read_holder(&path);
+                    tracing::info!(?path, holder, "waiting for lock held by another process");
+                    wait_for_lock(&file, deadline).map_err(|err| FileLockError {
+                        message: "Failed to lock lock file",
+                        path: path.clone(),
+                        err: match err {
+                            None => timed_out_error(&holder),
+                            Some(errno) => errno.into(),
+                        },
+                    })?;
                 }
-            })?;
+                Err(errno) => {
+                    return Err(FileLockError {
+                        message: "Failed to lock lock file",
+                        path: path.clone(),
+                        err: errno.into(),
+                    });
+                }
+            }
 
             let stat = rustix::fs::fstat(&file).map_err(|errno| FileLockError {
                 message: "failed to stat lock file",
@@ -58,11 +93,38 @@ impl FileLock {
                 continue;
             }
 
+            // The holder description is only used for diagnostics, so failing
+            // to write it isn't an error.
+            _ = file
+                .set_len(0)
+                .and_then(|()| file.write_all_at(current_holder().as_bytes(), 0));
             return Ok(Self { path, file });
         }
     }
 }
 
+/// Blocks until `file` is locked. Returns `Err(None)` if `deadline` passed
+/// first.
+fn wait_for_lock(file: &File, deadline: Option<Instant>) -> Result<(), Option<Errno>> {
+    let Some(deadline) = deadline else {
+        return rustix::fs::flock(file, FlockOperation::LockExclusive).map_err(Some);
+    };
+    let mut sleep = Duration::from_millis(1);
+    loop {
+        match rustix::fs::flock(file, FlockOperation::NonBlockingLockExclusive) {
+            Ok(()) => return Ok(()),
+            Err(Errno::WOULDBLOCK) => {}
+            Err(errno) => return Err(Some(errno)),
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(None);
+        }
+        std::thread::sleep(sleep.min(deadline - now));
+        sleep = (sleep * 2).min(Duration::from_millis(100));
+    }
+}
+
 impl Drop for FileLock {
     #[instrument(skip_all)]
     fn drop(&mut self) {
@@ -23,7 +23,9 @@ use std::iter;
 use itertools::Itertools as _;
 use tracing::instrument;
 
+use crate::repo_path::PathNormalization;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathComponent;
 use crate::repo_path::RepoPathComponentBuf;
 
 #[derive(PartialEq, Eq, Debug)]
@@ -58,6 +60,32 @@ impl Visit {
     pub fn is_nothing(&self) -> bool {
         *self == Visit::Nothing
     }
+
+    /// Returns the sorted names of the directories and files to visit, or
+    /// `None` if any entry in the directory may need to be visited.
+    ///
+    /// Tree walkers use this to look up the named entries instead of scanning
+    /// the whole directory.
+    pub fn specific_names(&self) -> Option<Vec<&RepoPathComponent>> {
+        match self {
+            Visit::AllRecursively => None,
+            Visit::Specific {
+                dirs: VisitDirs::Set(dirs),
+                files: VisitFiles::Set(files),
+            } => {
+                let names = dirs
+                    .iter()
+                    .chain(files)
+                    .map(|name| name.as_ref())
+                    .sorted()
+                    .dedup()
+                    .collect();
+                Some(names)
+            }
+            Visit::Specific { .. } => None,
+            Visit::Nothing => Some(vec![]),
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -244,17 +272,36 @@ fn prefix_tree_to_visit_sets(tree: &RepoPathTree<PrefixNodeKind>) -> Visit {
 #[derive(Clone, Debug)]
 pub struct FileGlobsMatcher {
     tree: RepoPathTree<Vec<glob::Pattern>>,
+    case_sensitive: bool,
 }
 
 impl FileGlobsMatcher {
     pub fn new<D: AsRef<RepoPath>>(
         dir_patterns: impl IntoIterator<Item = (D, glob::Pattern)>,
+    ) -> Self {
+        Self::with_case_sensitivity(dir_patterns, true)
+    }
+
+    /// Like `new()`, but the patterns are matched case-insensitively. The
+    /// `dir` paths are still matched exactly.
+    pub fn new_case_insensitive<D: AsRef<RepoPath>>(
+        dir_patterns: impl IntoIterator<Item = (D, glob::Pattern)>,
+    ) -> Self {
+        Self::with_case_sensitivity(dir_patterns, false)
+    }
+
+    fn with_case_sensitivity<D: AsRef<RepoPath>>(
+        dir_patterns: impl IntoIterator<Item = (D, glob::Pattern)>,
+        case_sensitive: bool,
     ) -> Self {
         let mut tree: RepoPathTree<Vec<glob::Pattern>> = Default::default();
         for (dir, pattern) in dir_patterns {
             tree.add(dir.as_ref()).value.push(pattern);
         }
-        FileGlobsMatcher { tree }
+        FileGlobsMatcher {
+            tree,
+            case_sensitive,
+        }
     }
 }
 
@@ -263,8 +310,8 @@ impl Matcher for FileGlobsMatcher {
         // TODO: glob::Pattern relies on path::is_separator() internally, but
         // RepoPath separator should be '/'. One way to address this problem is
         // to switch to globset::Glob, and use the underlying regex pattern.
-        const OPTIONS: glob::MatchOptions = glob::MatchOptions {
-            case_sensitive: true,
+        let options = glob::MatchOptions {
+            case_sensitive: self.case_sensitive,
             require_literal_separator: true,
             require_literal_leading_dot: false,
         };
@@ -274,7 +321,7 @@ impl Matcher for FileGlobsMatcher {
             .take_while(|(_, tail_path)| !tail_path.is_root()) // only dirs
             .any(|(sub, tail_path)| {
                 let name = tail_path.as_internal_file_string();
-                sub.value.iter().any(|pat| pat.matches_with(name, OPTIONS))
+                sub.value.iter().any(|pat| pat.matches_with(name, options))
             })
     }
 
@@ -297,6 +344,42 @@ impl Matcher for FileGlobsMatcher {
     }
 }
 
+/// Matches file paths with regular expressions.
+///
+/// The patterns are matched against the whole workspace-relative path using
+/// `/` as the separator. They aren't anchored, so `\.rs$` matches all `.rs`
+/// files.
+#[derive(Clone, Debug)]
+pub struct FileRegexMatcher {
+    regexes: Vec<regex::Regex>,
+}
+
+impl FileRegexMatcher {
+    pub fn new(regexes: impl IntoIterator<Item = regex::Regex>) -> Self {
+        FileRegexMatcher {
+            regexes: regexes.into_iter().collect(),
+        }
+    }
+}
+
+impl Matcher for FileRegexMatcher {
+    fn matches(&self, file: &RepoPath) -> bool {
+        let path = file.as_internal_file_string();
+        self.regexes.iter().any(|regex| regex.is_match(path))
+    }
+
+    fn visit(&self, _dir: &RepoPath) -> Visit {
+        if self.regexes.is_empty() {
+            return Visit::Nothing;
+        }
+        // A regex can match a path in any directory
+        Visit::Specific {
+            dirs: VisitDirs::All,
+            files: VisitFiles::All,
+        }
+    }
+}
+
 /// Matches paths that are matched by any of the input matchers.
 #[derive(Clone, Debug)]
 pub struct UnionMatcher<M1, M2> {
@@ -452,6 +535,47 @@ impl<M1: Matcher, M2: Matcher> Matcher for IntersectionMatcher<M1, M2> {
     }
 }
 
+/// Matches paths by their normalized spelling.
+///
+/// The queried paths are converted by `PathNormalization::comparison_path()`
+/// before being passed to the input matcher, so the input matcher should be
+/// built from paths normalized the same way.
+#[derive(Clone, Debug)]
+pub struct NormalizedMatcher<M> {
+    normalization: PathNormalization,
+    input: M,
+}
+
+impl<M: Matcher> NormalizedMatcher<M> {
+    pub fn new(normalization: PathNormalization, input: M) -> Self {
+        Self {
+            normalization,
+            input,
+        }
+    }
+}
+
+impl<M: Matcher> Matcher for NormalizedMatcher<M> {
+    fn matches(&self, file: &RepoPath) -> bool {
+        self.input
+            .matches(&self.normalization.comparison_path(file))
+    }
+
+    fn visit(&self, dir: &RepoPath) -> Visit {
+        match self.input.visit(&self.normalization.comparison_path(dir)) {
+            Visit::AllRecursively => Visit::AllRecursively,
+            Visit::Nothing => Visit::Nothing,
+            // The entry names in the sets are normalized, so they can't be
+            // compared with the names on disk.
+            Visit::Specific { .. } if !self.normalization.is_identity() => Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            },
+            visit @ Visit::Specific { .. } => visit,
+        }
+    }
+}
+
 /// Tree that maps `RepoPath` to value of type `V`.
 #[derive(Clone, Default, Eq, PartialEq)]
 struct RepoPathTree<V> {
@@ -519,6 +643,26 @@ mod tests {
         RepoPath::from_internal_string(value)
     }
 
+    #[test]
+    fn test_visit_specific_names() {
+        fn names(visit: &Visit) -> Option<Vec<&str>> {
+            let names = visit.specific_names()?;
+            Some(names.iter().map(|name| name.as_internal_str()).collect())
+        }
+        assert_eq!(names(&Visit::AllRecursively), None);
+        assert_eq!(names(&Visit::Nothing), Some(vec![]));
+        let visit = Visit::sets(
+            hashset! {RepoPathComponentBuf::from("b"), RepoPathComponentBuf::from("a")},
+            hashset! {RepoPathComponentBuf::from("c"), RepoPathComponentBuf::from("a")},
+        );
+        assert_eq!(names(&visit), Some(vec!["a", "b", "c"]));
+        let visit = Visit::Specific {
+            dirs: VisitDirs::All,
+            files: VisitFiles::Set(hashset! {RepoPathComponentBuf::from("a")}),
+        };
+        assert_eq!(names(&visit), None);
+    }
+
     #[test]
     fn test_nothingmatcher() {
         let m = NothingMatcher;
@@ -820,6 +964,62 @@ mod tests {
         assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
     }
 
+    #[test]
+    fn test_fileglobsmatcher_case_insensitive() {
+        let to_pattern = |s| glob::Pattern::new(s).unwrap();
+
+        let m = FileGlobsMatcher::new_case_insensitive([(repo_path("foo"), to_pattern("**/*.rs"))]);
+        assert!(m.matches(repo_path("foo/bar.rs")));
+        assert!(m.matches(repo_path("foo/Bar/BAZ.RS")));
+        assert!(!m.matches(repo_path("foo/bar.rss")));
+        // The directory is matched exactly
+        assert!(!m.matches(repo_path("Foo/bar.rs")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::Set(hashset! {RepoPathComponentBuf::from("foo")}),
+                files: VisitFiles::Set(hashset! {}),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fileregexmatcher() {
+        let m = FileRegexMatcher::new([]);
+        assert!(!m.matches(repo_path("foo")));
+        assert_eq!(m.visit(RepoPath::root()), Visit::Nothing);
+
+        let m = FileRegexMatcher::new([
+            regex::Regex::new(r"^src/.*\.rs$").unwrap(),
+            regex::Regex::new(r"(?i)readme").unwrap(),
+        ]);
+        assert!(m.matches(repo_path("src/lib.rs")));
+        assert!(m.matches(repo_path("src/foo/bar.rs")));
+        assert!(!m.matches(repo_path("lib/src/lib.rs")));
+        assert!(!m.matches(repo_path("src/lib.rs.orig")));
+        assert!(m.matches(repo_path("README.md")));
+        assert!(m.matches(repo_path("docs/ReadMe")));
+        assert_eq!(
+            m.visit(repo_path("foo")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All
+            }
+        );
+
+        // Combined with a prefix matcher, the visit hints of the prefix are kept
+        let m = IntersectionMatcher::new(m, PrefixMatcher::new([repo_path("src")]));
+        assert!(m.matches(repo_path("src/lib.rs")));
+        assert!(!m.matches(repo_path("README.md")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::sets(
+                hashset! {RepoPathComponentBuf::from("src")},
+                hashset! {RepoPathComponentBuf::from("src")}
+            )
+        );
+    }
+
     #[test]
     fn test_unionmatcher_concatenate_roots() {
         let m1 = PrefixMatcher::new([repo_path("foo"), repo_path("bar")]);
@@ -1042,4 +1242,41 @@ mod tests {
         );
         assert_eq!(m.visit(repo_path("foo/bar")), Visit::AllRecursively);
     }
+
+    #[test]
+    fn test_normalizedmatcher() {
+        let normalization = PathNormalization {
+            unicode_nfc: true,
+            case_insensitive: true,
+        };
+        let m = NormalizedMatcher::new(
+            normalization,
+            FilesMatcher::new([repo_path("dir/caf\u{e9}")]),
+        );
+
+        assert!(m.matches(repo_path("dir/caf\u{e9}")));
+        assert!(m.matches(repo_path("Dir/CAFE\u{301}")));
+        assert!(!m.matches(repo_path("dir/cafe")));
+
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(m.visit(repo_path("DIR/sub")), Visit::Nothing);
+        assert_eq!(m.visit(repo_path("other")), Visit::Nothing);
+
+        // Without normalization, the input matcher is used as is.
+        let m = NormalizedMatcher::new(
+            PathNormalization::default(),
+            FilesMatcher::new([repo_path("dir/file")]),
+        );
+        assert!(!m.matches(repo_path("DIR/file")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::sets(hashset! {RepoPathComponentBuf::from("dir")}, hashset! {})
+        );
+    }
 }
@@ -543,6 +543,24 @@ impl MergedTreeValue {
     }
 }
 
+/// The kind of a conflict between tree values at a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// All sides are regular files or absent, and their contents conflict.
+    Content,
+    /// Some sides deleted the file while other sides modified it.
+    ModifyDelete,
+    /// All sides are regular files (or absent) with the same contents, but
+    /// their executable bits differ. For example, both sides added the same
+    /// file, but only one side made it executable.
+    Executable,
+    /// Some sides are directories and other sides are files (or symlinks,
+    /// etc.).
+    FileDirectory,
+    /// Any other conflict, e.g. between a file and a symlink.
+    Other,
+}
+
 impl<T> Merge<Option<T>>
 where
     T: Borrow<TreeValue>,
@@ -631,10 +649,47 @@ where
         Merge { values }
     }
 
+    /// Classifies the conflict. Returns `None` if the merge can be resolved
+    /// trivially.
+    pub fn conflict_kind(&self) -> Option<ConflictKind> {
+        let values = self.map(|term| borrow_tree_value(term.as_ref()));
+        if values.resolve_trivial().is_some() {
+            return None;
+        }
+        let has_tree = self
+            .iter()
+            .any(|term| matches!(borrow_tree_value(term.as_ref()), Some(TreeValue::Tree(_))));
+        let has_non_tree = self.iter().any(|term| {
+            !matches!(
+                borrow_tree_value(term.as_ref()),
+                Some(TreeValue::Tree(_)) | None
+            )
+        });
+        if has_tree && has_non_tree {
+            Some(ConflictKind::FileDirectory)
+        } else if let Some(file_ids) = self.to_file_merge() {
+            if file_ids.adds().any(Option::is_none) && file_ids.removes().any(Option::is_some) {
+                Some(ConflictKind::ModifyDelete)
+            } else if file_ids.resolve_trivial().is_some() {
+                Some(ConflictKind::Executable)
+            } else {
+                Some(ConflictKind::Content)
+            }
+        } else {
+            Some(ConflictKind::Other)
+        }
+    }
+
     /// Give a summary description of the conflict's "removes" and "adds"
     pub fn describe(&self) -> String {
         let mut buf = String::new();
-        writeln!(buf, "Conflict:").unwrap();
+        let header = match self.conflict_kind() {
+            Some(ConflictKind::Executable) => "Conflict between executable bits:",
+            Some(ConflictKind::ModifyDelete) => "Conflict between a modification and a deletion:",
+            Some(ConflictKind::FileDirectory) => "Conflict between a file and a directory:",
+            _ => "Conflict:",
+        };
+        writeln!(buf, "{header}").unwrap();
         for term in self.removes().flatten() {
             writeln!(buf, "  Removing {}", describe_conflict_term(term.borrow())).unwrap();
         }
@@ -1167,4 +1222,60 @@ mod tests {
             c(&[0, 1, 2, 5, 4, 3, 6, 7, 8])
         );
     }
+
+    #[test]
+    fn test_conflict_kind() {
+        let file = |hex: &'static str, executable: bool| {
+            Some(TreeValue::File {
+                id: FileId::from_hex(hex),
+                executable,
+            })
+        };
+        let tree = Some(TreeValue::Tree(TreeId::from_hex("aa")));
+        let symlink = Some(TreeValue::Symlink(backend::SymlinkId::from_hex("bb")));
+
+        // Trivially resolvable
+        assert_eq!(c(&[file("01", false)]).conflict_kind(), None);
+        assert_eq!(
+            c(&[file("02", false), file("01", false), file("01", false)]).conflict_kind(),
+            None
+        );
+        // Contents conflict, even if the executable bits do too
+        assert_eq!(
+            c(&[file("02", false), file("01", false), file("03", false)]).conflict_kind(),
+            Some(ConflictKind::Content)
+        );
+        assert_eq!(
+            c(&[file("02", true), None, file("03", false)]).conflict_kind(),
+            Some(ConflictKind::Content)
+        );
+        // Both sides added the same contents with different executable bits
+        assert_eq!(
+            c(&[file("01", true), None, file("01", false)]).conflict_kind(),
+            Some(ConflictKind::Executable)
+        );
+        // One side modified the file, the other deleted it
+        assert_eq!(
+            c(&[file("02", false), file("01", false), None]).conflict_kind(),
+            Some(ConflictKind::ModifyDelete)
+        );
+        assert_eq!(
+            c(&[None, file("01", false), file("01", true)]).conflict_kind(),
+            Some(ConflictKind::ModifyDelete)
+        );
+        // File and directory
+        assert_eq!(
+            c(&[file("02", false), file("01", false), tree.clone()]).conflict_kind(),
+            Some(ConflictKind::FileDirectory)
+        );
+        assert_eq!(
+            c(&[symlink.clone(), None, tree.clone()]).conflict_kind(),
+            Some(ConflictKind::FileDirectory)
+        );
+        // Other
+        assert_eq!(
+            c(&[file("02", false), None, symlink]).conflict_kind(),
+            Some(ConflictKind::Other)
+        );
+    }
 }
@@ -18,6 +18,7 @@ use std::borrow::Borrow;
 use std::cmp::max;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::iter;
 use std::iter::zip;
@@ -42,11 +43,13 @@ use crate::backend::BackendResult;
 use crate::backend::MergedTreeId;
 use crate::backend::TreeId;
 use crate::backend::TreeValue;
+use crate::copies::find_exact_renames;
 use crate::copies::CopiesTreeDiffEntry;
 use crate::copies::CopiesTreeDiffStream;
 use crate::copies::CopyRecords;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::Matcher;
+use crate::matchers::Visit;
 use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeVal;
@@ -237,6 +240,44 @@ impl MergedTree {
         }
     }
 
+    /// Looks up the values at the given paths, like `path_value()`. Subtrees
+    /// shared by several of the paths are only loaded once.
+    pub fn values_for_paths(&self, paths: &[&RepoPath]) -> BackendResult<Vec<MergedTreeValue>> {
+        assert_eq!(self.dir(), RepoPath::root());
+        let mut sub_trees = HashMap::new();
+        paths
+            .iter()
+            .map(|path| match path.split() {
+                Some((dir, basename)) => match self.cached_sub_tree(&mut sub_trees, dir)? {
+                    None => Ok(Merge::absent()),
+                    Some(tree) => Ok(tree.value(basename).cloned()),
+                },
+                None => Ok(self
+                    .trees
+                    .map(|tree| Some(TreeValue::Tree(tree.id().clone())))),
+            })
+            .collect()
+    }
+
+    fn cached_sub_tree(
+        &self,
+        cache: &mut HashMap<RepoPathBuf, Option<MergedTree>>,
+        dir: &RepoPath,
+    ) -> BackendResult<Option<MergedTree>> {
+        if let Some(tree) = cache.get(dir) {
+            return Ok(tree.clone());
+        }
+        let tree = match dir.split() {
+            Some((parent_dir, name)) => match self.cached_sub_tree(cache, parent_dir)? {
+                Some(parent_tree) => parent_tree.sub_tree(name)?,
+                None => None,
+            },
+            None => Some(self.clone()),
+        };
+        cache.insert(dir.to_owned(), tree.clone());
+        Ok(tree)
+    }
+
     /// The tree's id
     pub fn id(&self) -> MergedTreeId {
         MergedTreeId::Merge(self.trees.map(|tree| tree.id().clone()))
@@ -327,6 +368,67 @@ impl MergedTree {
         self.merge_no_resolve(base, other).resolve()
     }
 
+    /// Like [`MergedTree::merge()`], but a file renamed on one side is merged
+    /// with the changes made to it at its old path on the other side, so the
+    /// changes end up at the new path.
+    ///
+    /// Only renames which don't change the file contents are detected (see
+    /// [`find_exact_renames()`]), and only if none of the trees has conflicts.
+    pub fn merge_with_renames(
+        &self,
+        base: &MergedTree,
+        other: &MergedTree,
+    ) -> BackendResult<MergedTree> {
+        if !(self.trees.is_resolved() && base.trees.is_resolved() && other.trees.is_resolved()) {
+            return self.merge(base, other);
+        }
+        let self_renames = find_exact_renames(base, self)?;
+        let other_renames = find_exact_renames(base, other)?;
+        if self_renames.is_empty() && other_renames.is_empty() {
+            return self.merge(base, other);
+        }
+
+        // Move the renamed files in the base and in the other side so the sides
+        // are merged at the new path.
+        let mut base_builder = MergedTreeBuilder::new(base.id());
+        let mut self_builder = MergedTreeBuilder::new(self.id());
+        let mut other_builder = MergedTreeBuilder::new(other.id());
+        let mut follow_renames = |renames: &[(RepoPathBuf, RepoPathBuf)],
+                                  renames_on_other_side: &[(RepoPathBuf, RepoPathBuf)],
+                                  other_side: &MergedTree,
+                                  other_side_builder: &mut MergedTreeBuilder|
+         -> BackendResult<()> {
+            for (source, target) in renames {
+                if renames_on_other_side.iter().any(|(path, _)| path == source) {
+                    continue;
+                }
+                let base_value = base.path_value(source)?;
+                let other_side_value = other_side.path_value(source)?;
+                // Unchanged or deleted files on the other side are merged
+                // correctly without following the rename.
+                if other_side_value.is_absent() || other_side_value == base_value {
+                    continue;
+                }
+                if other_side.path_value(target)?.is_present() {
+                    continue;
+                }
+                base_builder.set_or_remove(source.clone(), Merge::absent());
+                base_builder.set_or_remove(target.clone(), base_value);
+                other_side_builder.set_or_remove(source.clone(), Merge::absent());
+                other_side_builder.set_or_remove(target.clone(), other_side_value);
+            }
+            Ok(())
+        };
+        follow_renames(&self_renames, &other_renames, other, &mut other_builder)?;
+        follow_renames(&other_renames, &self_renames, self, &mut self_builder)?;
+
+        let store = self.store();
+        let base = store.get_root_tree(&base_builder.write_tree(store)?)?;
+        let new_self = store.get_root_tree(&self_builder.write_tree(store)?)?;
+        let new_other = store.get_root_tree(&other_builder.write_tree(store)?)?;
+        new_self.merge(&base, &new_other)
+    }
+
     /// Merges this tree with `other`, using `base` as base, without attempting
     /// to resolve file conflicts.
     pub fn merge_no_resolve(&self, base: &MergedTree, other: &MergedTree) -> MergedTree {
@@ -410,13 +512,31 @@ fn all_merged_tree_entries(
     })
 }
 
+/// Like `all_tree_entries()`, but only looks up the entries named by `visit` if
+/// it specifies the names to visit.
+fn visited_tree_entries<'a>(
+    trees: &'a Merge<Tree>,
+    visit: &'a Visit,
+) -> impl Iterator<Item = (&'a RepoPathComponent, MergedTreeVal<'a>)> {
+    match visit.specific_names() {
+        Some(names) => Either::Left(
+            names
+                .into_iter()
+                .map(|name| (name, trees_value(trees, name)))
+                .filter(|(_, value)| value.is_present()),
+        ),
+        None => Either::Right(all_tree_entries(trees)),
+    }
+}
+
 fn merged_tree_entry_diff<'a>(
     trees1: &'a Merge<Tree>,
     trees2: &'a Merge<Tree>,
+    visit: &'a Visit,
 ) -> impl Iterator<Item = (&'a RepoPathComponent, MergedTreeVal<'a>, MergedTreeVal<'a>)> {
     itertools::merge_join_by(
-        all_tree_entries(trees1),
-        all_tree_entries(trees2),
+        visited_tree_entries(trees1, visit),
+        visited_tree_entries(trees2, visit),
         |(name1, _), (name2, _)| name1.cmp(name2),
     )
     .map(|entry| match entry {
@@ -563,10 +683,10 @@ impl TreeEntriesDirItem {
     fn new(trees: &Merge<Tree>, matcher: &dyn Matcher) -> Self {
         let mut entries = vec![];
         let dir = trees.first().dir();
-        for (name, value) in all_tree_entries(trees) {
+        let visit = matcher.visit(dir);
+        for (name, value) in visited_tree_entries(trees, &visit) {
             let path = dir.join(name);
             if value.is_tree() {
-                // TODO: Handle the other cases (specific files and trees)
                 if matcher.visit(&path).is_nothing() {
                     continue;
                 }
@@ -741,7 +861,8 @@ impl TreeDiffDirItem {
         matcher: &dyn Matcher,
     ) -> Self {
         let mut entries = vec![];
-        for (name, before, after) in merged_tree_entry_diff(trees1, trees2) {
+        let visit = matcher.visit(dir);
+        for (name, before, after) in merged_tree_entry_diff(trees1, trees2, &visit) {
             let path = dir.join(name);
             let tree_before = before.is_tree();
             let tree_after = after.is_tree();
@@ -971,7 +1092,8 @@ impl<'matcher> TreeDiffStreamImpl<'matcher> {
     }
 
     fn add_dir_diff_items(&mut self, dir: &RepoPath, trees1: &Merge<Tree>, trees2: &Merge<Tree>) {
-        for (basename, before, after) in merged_tree_entry_diff(trees1, trees2) {
+        let visit = self.matcher.visit(dir);
+        for (basename, before, after) in merged_tree_entry_diff(trees1, trees2, &visit) {
             let path = dir.join(basename);
             let tree_before = before.is_tree();
             let tree_after = after.is_tree();
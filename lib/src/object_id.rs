@@ -84,6 +84,15 @@ macro_rules! impl_id_type {
             }
         }
 
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(&self.$hex_method())
+            }
+        }
+
         impl crate::object_id::ObjectId for $name {
             fn object_type(&self) -> String {
                 stringify!($name)
@@ -0,0 +1,97 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured representation of operations for exporting the operation log
+//! to external tools.
+
+use std::collections::BTreeSet;
+
+use crate::op_store;
+use crate::op_store::OpStoreResult;
+use crate::op_store::OperationId;
+use crate::op_store_json::OperationJson;
+use crate::operation::Operation;
+use crate::view;
+use crate::view::View;
+
+/// Summary of the changes an operation made to the view, relative to its
+/// first parent.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ViewDiffSummary {
+    /// Number of visible heads added.
+    pub num_added_heads: usize,
+    /// Number of visible heads removed.
+    pub num_removed_heads: usize,
+    /// Names of local bookmarks which were created, moved, or deleted.
+    pub changed_bookmarks: BTreeSet<String>,
+}
+
+impl ViewDiffSummary {
+    /// Compares the `new` view with the `old` view.
+    pub fn new(old: &View, new: &View) -> Self {
+        let diff = view::diff(old, new);
+        ViewDiffSummary {
+            num_added_heads: diff.added_heads.len(),
+            num_removed_heads: diff.removed_heads.len(),
+            changed_bookmarks: diff
+                .local_bookmarks
+                .iter()
+                .map(|(name, _)| (*name).to_owned())
+                .collect(),
+        }
+    }
+}
+
+/// Operation metadata in a form suitable for auditing and analytics.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperationEvent {
+    /// Id of the operation.
+    pub id: OperationId,
+    /// The operation as stored. The CLI records the command line in the
+    /// `args` tag of its metadata.
+    pub operation: op_store::Operation,
+    /// Changes made to the view by the operation.
+    pub view_diff: ViewDiffSummary,
+}
+
+impl OperationEvent {
+    /// Builds an event from the operation, reading its view and the view of
+    /// its first parent.
+    pub fn from_operation(op: &Operation) -> OpStoreResult<Self> {
+        let new_view = op.view()?;
+        let view_diff = match op.parents().next() {
+            Some(parent) => ViewDiffSummary::new(&parent?.view()?, &new_view),
+            None => ViewDiffSummary::default(),
+        };
+        Ok(OperationEvent {
+            id: op.id().clone(),
+            operation: op.store_operation().clone(),
+            view_diff,
+        })
+    }
+
+    /// Serializes this event as a JSON object. The operation is serialized in
+    /// the schema of [`crate::op_store_json`], with the `id` and `view_diff`
+    /// added.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(OperationJson::new(Some(&self.id), &self.operation))
+            .expect("operation should be serializable");
+        json["view_diff"] = serde_json::json!({
+            "num_added_heads": self.view_diff.num_added_heads,
+            "num_removed_heads": self.view_diff.num_removed_heads,
+            "changed_bookmarks": self.view_diff.changed_bookmarks,
+        });
+        json
+    }
+}
@@ -31,7 +31,9 @@ use thiserror::Error;
 use crate::backend::CommitId;
 use crate::backend::MillisSinceEpoch;
 use crate::backend::Timestamp;
+use crate::backend::TreeId;
 use crate::content_hash::ContentHash;
+use crate::content_hash::DigestUpdate;
 use crate::merge::Merge;
 use crate::object_id::id_type;
 use crate::object_id::HexPrefix;
@@ -264,7 +266,7 @@ pub struct BookmarkTarget<'a> {
 
 /// Represents the way the repo looks at a given time, just like how a Tree
 /// object represents how the file system looks at a given time.
-#[derive(ContentHash, PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct View {
     /// All head commits
     pub head_ids: HashSet<CommitId>,
@@ -280,6 +282,48 @@ pub struct View {
     // (.jj/working_copy/) has the source of truth about which commit *is* checked out (to be
     // precise: the commit to which we most recently completed an update to).
     pub wc_commit_ids: BTreeMap<WorkspaceId, CommitId>,
+    /// Auto-generated names of visible heads that no bookmark, tag, or working
+    /// copy points to. The names are derived from the change ids.
+    pub anonymous_heads: BTreeMap<String, CommitId>,
+    /// Commits marked during an ongoing bisection.
+    pub bisect_state: BisectState,
+    /// Remotes configured in the repo, independently of the backend.
+    pub remotes: BTreeMap<String, RemoteConfig>,
+}
+
+impl ContentHash for View {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        let View {
+            head_ids,
+            local_bookmarks,
+            tags,
+            remote_views,
+            git_refs,
+            git_head,
+            wc_commit_ids,
+            anonymous_heads,
+            bisect_state,
+            remotes,
+        } = self;
+        head_ids.hash(state);
+        local_bookmarks.hash(state);
+        tags.hash(state);
+        remote_views.hash(state);
+        git_refs.hash(state);
+        git_head.hash(state);
+        wc_commit_ids.hash(state);
+        // Views without anonymous heads, bisection marks, or remotes hash the
+        // same as before these fields were introduced.
+        if !anonymous_heads.is_empty() {
+            anonymous_heads.hash(state);
+        }
+        if !bisect_state.is_empty() {
+            bisect_state.hash(state);
+        }
+        if !remotes.is_empty() {
+            remotes.hash(state);
+        }
+    }
 }
 
 impl View {
@@ -296,6 +340,9 @@ impl View {
             git_refs: BTreeMap::new(),
             git_head: RefTarget::absent(),
             wc_commit_ids: BTreeMap::new(),
+            anonymous_heads: BTreeMap::new(),
+            bisect_state: BisectState::default(),
+            remotes: BTreeMap::new(),
         }
     }
 
@@ -309,10 +356,37 @@ impl View {
             git_refs: BTreeMap::new(),
             git_head: RefTarget::absent(),
             wc_commit_ids: BTreeMap::new(),
+            anonymous_heads: BTreeMap::new(),
+            bisect_state: BisectState::default(),
+            remotes: BTreeMap::new(),
         }
     }
 }
 
+/// Commits marked as good, bad, or skipped by a bisection. The order of the
+/// marks is preserved.
+#[derive(ContentHash, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BisectState {
+    pub good_commits: Vec<CommitId>,
+    pub bad_commits: Vec<CommitId>,
+    pub skipped_commits: Vec<CommitId>,
+}
+
+impl BisectState {
+    /// Whether no commit is marked, i.e. no bisection is ongoing.
+    pub fn is_empty(&self) -> bool {
+        self.good_commits.is_empty()
+            && self.bad_commits.is_empty()
+            && self.skipped_commits.is_empty()
+    }
+}
+
+/// Configuration of a remote repo.
+#[derive(ContentHash, Clone, Debug, Eq, PartialEq)]
+pub struct RemoteConfig {
+    pub url: String,
+}
+
 /// Represents the state of the remote repo.
 #[derive(ContentHash, Clone, Debug, Default, Eq, PartialEq)]
 pub struct RemoteView {
@@ -412,6 +486,9 @@ impl Operation {
             username: "".to_string(),
             is_snapshot: false,
             tags: HashMap::new(),
+            working_copy_tree_ids: None,
+            includes_snapshot: false,
+            stopped_rebase: None,
         };
         Operation {
             view_id: root_view_id,
@@ -421,7 +498,7 @@ impl Operation {
     }
 }
 
-#[derive(ContentHash, PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct OperationMetadata {
     pub start_time: Timestamp,
     pub end_time: Timestamp,
@@ -433,6 +510,73 @@ pub struct OperationMetadata {
     /// copy.
     pub is_snapshot: bool,
     pub tags: HashMap<String, String>,
+    /// Tree of the working-copy changes snapshotted by the command that created
+    /// this operation, or `None` if no changes were snapshotted.
+    pub working_copy_tree_ids: Option<Merge<TreeId>>,
+    /// Whether the command that created this operation recorded changes in the
+    /// working copy as a separate snapshot operation first. Undoing such an
+    /// operation alone keeps the snapshotted changes.
+    pub includes_snapshot: bool,
+    /// Rebase which was stopped by a conflict in this operation, and can be
+    /// resumed later.
+    pub stopped_rebase: Option<StoppedRebase>,
+}
+
+impl ContentHash for OperationMetadata {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        let OperationMetadata {
+            start_time,
+            end_time,
+            description,
+            hostname,
+            username,
+            is_snapshot,
+            tags,
+            working_copy_tree_ids,
+            includes_snapshot,
+            stopped_rebase,
+        } = self;
+        start_time.hash(state);
+        end_time.hash(state);
+        description.hash(state);
+        hostname.hash(state);
+        username.hash(state);
+        is_snapshot.hash(state);
+        tags.hash(state);
+        // Operations without working-copy snapshot information hash the same
+        // as before the fields were introduced.
+        if working_copy_tree_ids.is_some() || *includes_snapshot {
+            working_copy_tree_ids.hash(state);
+            includes_snapshot.hash(state);
+        }
+        if stopped_rebase.is_some() {
+            stopped_rebase.hash(state);
+        }
+    }
+}
+
+/// A rebase stopped by a conflict.
+///
+/// See [`RebaseState`](crate::rewrite::RebaseState) for how it's resumed.
+#[derive(ContentHash, Clone, Debug, Eq, PartialEq)]
+pub struct StoppedRebase {
+    /// The commit which would have become conflicted. It hasn't been rebased.
+    pub conflicted_commit_id: CommitId,
+    /// Rewritten commits whose descendants haven't been rebased yet, keyed by
+    /// the old commit id.
+    pub rewrites: BTreeMap<CommitId, PendingRewrite>,
+}
+
+/// How a commit in [`StoppedRebase::rewrites`] was rewritten.
+#[derive(ContentHash, Clone, Debug, Eq, PartialEq)]
+pub enum PendingRewrite {
+    /// The commit was rewritten as this new commit.
+    Rewritten(CommitId),
+    /// The commit was rewritten as multiple other commits.
+    Divergent(Vec<CommitId>),
+    /// The commit was abandoned. Its children should be rebased onto the given
+    /// commits.
+    Abandoned(Vec<CommitId>),
 }
 
 /// Data to be loaded into the root operation/view.
@@ -467,6 +611,28 @@ pub enum OpStoreError {
 
 pub type OpStoreResult<T> = Result<T, OpStoreError>;
 
+/// Serialization of operations and views for op stores which keep them as
+/// byte strings.
+///
+/// Operation and view ids are hashes of the objects, not of their encoding, so
+/// the same objects have the same ids regardless of the encoding.
+pub trait OpStoreEncoding: Send + Sync + Debug {
+    /// Name of the op store type which stores objects in this encoding. It's
+    /// recorded in the store directory to pick the encoding when loading.
+    fn store_name(&self) -> &'static str;
+
+    fn encode_view(&self, view: &View) -> Vec<u8>;
+
+    fn decode_view(&self, buf: &[u8]) -> Result<View, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn encode_operation(&self, operation: &Operation) -> Vec<u8>;
+
+    fn decode_operation(
+        &self,
+        buf: &[u8],
+    ) -> Result<Operation, Box<dyn std::error::Error + Send + Sync>>;
+}
+
 pub trait OpStore: Send + Sync + Debug {
     fn as_any(&self) -> &dyn Any;
 
@@ -0,0 +1,595 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human-readable JSON serialization of operations and views.
+//!
+//! This is an alternative to the protobuf encoding used by
+//! [`SimpleOpStore`] by default. It can be used by debugging tools, test
+//! fixtures, and custom [`OpStore`] implementations, or by [`SimpleOpStore`]
+//! itself with the `json-op-store` feature. The output is stable: maps are
+//! sorted by key and heads are sorted by id. Timestamps are RFC 3339 strings.
+//!
+//! This is the only JSON schema of operations. The `serde::Serialize` impl of
+//! [`operation::Operation`] and [`OperationEvent::to_json()`] use it too, with
+//! the operation id added.
+//!
+//! [`operation::Operation`]: crate::operation::Operation
+//! [`OperationEvent::to_json()`]: crate::op_export::OperationEvent::to_json
+//!
+//! [`OpStore`]: crate::op_store::OpStore
+//! [`SimpleOpStore`]: crate::simple_op_store::SimpleOpStore
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools as _;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::backend::CommitId;
+use crate::backend::Timestamp;
+use crate::backend::TreeId;
+use crate::merge::Merge;
+use crate::object_id::ObjectId as _;
+use crate::op_store::BisectState;
+use crate::op_store::Operation;
+use crate::op_store::OperationId;
+use crate::op_store::OperationMetadata;
+use crate::op_store::PendingRewrite;
+use crate::op_store::RefTarget;
+use crate::op_store::RemoteConfig;
+use crate::op_store::RemoteRef;
+use crate::op_store::RemoteRefState;
+use crate::op_store::RemoteView;
+use crate::op_store::StoppedRebase;
+use crate::op_store::View;
+use crate::op_store::ViewId;
+use crate::op_store::WorkspaceId;
+
+/// Error that may occur when decoding JSON operation or view.
+#[derive(Debug, Error)]
+pub enum JsonDecodeError {
+    /// The input isn't valid JSON of the expected structure.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// An object id isn't a valid hex string.
+    #[error("Invalid {kind} id: {value:?}")]
+    InvalidId {
+        /// Type of the object.
+        kind: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    /// A ref target doesn't have an odd number of terms.
+    #[error("Invalid ref target with {0} terms")]
+    InvalidRefTarget(usize),
+    /// A working-copy tree doesn't have an odd number of terms.
+    #[error("Invalid working-copy tree with {0} terms")]
+    InvalidTreeIds(usize),
+}
+
+/// Serializes `operation` to pretty-printed JSON.
+pub fn operation_to_json(operation: &Operation) -> String {
+    let json = OperationJson::new(None, operation);
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+/// Deserializes operation from JSON produced by [`operation_to_json()`].
+pub fn operation_from_json(text: &str) -> Result<Operation, JsonDecodeError> {
+    let json: OperationJson = serde_json::from_str(text)?;
+    json.into_operation()
+}
+
+/// Serializes `view` to pretty-printed JSON.
+pub fn view_to_json(view: &View) -> String {
+    let json = ViewJson::from_view(view);
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+/// Deserializes view from JSON produced by [`view_to_json()`].
+pub fn view_from_json(text: &str) -> Result<View, JsonDecodeError> {
+    let json: ViewJson = serde_json::from_str(text)?;
+    json.into_view()
+}
+
+/// Encoding of operations and views as JSON files in a
+/// [`SimpleOpStore`](crate::simple_op_store::SimpleOpStore).
+#[cfg(feature = "json-op-store")]
+#[derive(Debug)]
+pub struct JsonOpStoreEncoding;
+
+#[cfg(feature = "json-op-store")]
+impl JsonOpStoreEncoding {
+    /// Name of the op store type which stores objects in this encoding.
+    pub fn store_name() -> &'static str {
+        "json_op_store"
+    }
+}
+
+#[cfg(feature = "json-op-store")]
+impl crate::op_store::OpStoreEncoding for JsonOpStoreEncoding {
+    fn store_name(&self) -> &'static str {
+        Self::store_name()
+    }
+
+    fn encode_view(&self, view: &View) -> Vec<u8> {
+        view_to_json(view).into_bytes()
+    }
+
+    fn decode_view(&self, buf: &[u8]) -> Result<View, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(view_from_json(std::str::from_utf8(buf)?)?)
+    }
+
+    fn encode_operation(&self, operation: &Operation) -> Vec<u8> {
+        operation_to_json(operation).into_bytes()
+    }
+
+    fn decode_operation(
+        &self,
+        buf: &[u8],
+    ) -> Result<Operation, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(operation_from_json(std::str::from_utf8(buf)?)?)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct OperationJson {
+    // Omitted from the op store, which addresses operations by the hash of
+    // their contents. Ignored when decoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    view_id: String,
+    parents: Vec<String>,
+    metadata: OperationMetadataJson,
+}
+
+impl OperationJson {
+    /// Converts the `operation`, which is identified by `id` if specified.
+    pub(crate) fn new(id: Option<&OperationId>, operation: &Operation) -> Self {
+        let Operation {
+            view_id,
+            parents,
+            metadata,
+        } = operation;
+        OperationJson {
+            id: id.map(|id| id.hex()),
+            view_id: view_id.hex(),
+            parents: parents.iter().map(|id| id.hex()).collect(),
+            metadata: OperationMetadataJson::from_metadata(metadata),
+        }
+    }
+
+    fn into_operation(self) -> Result<Operation, JsonDecodeError> {
+        let view_id =
+            ViewId::try_from_hex(&self.view_id).map_err(|_| invalid_id("view", self.view_id))?;
+        let parents = self
+            .parents
+            .into_iter()
+            .map(|hex| OperationId::try_from_hex(&hex).map_err(|_| invalid_id("operation", hex)))
+            .try_collect()?;
+        Ok(Operation {
+            view_id,
+            parents,
+            metadata: self.metadata.into_metadata()?,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct OperationMetadataJson {
+    start_time: Timestamp,
+    end_time: Timestamp,
+    description: String,
+    hostname: String,
+    username: String,
+    is_snapshot: bool,
+    tags: BTreeMap<String, String>,
+    #[serde(default)]
+    working_copy_tree_ids: Option<Vec<String>>,
+    #[serde(default)]
+    includes_snapshot: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stopped_rebase: Option<StoppedRebaseJson>,
+}
+
+impl OperationMetadataJson {
+    fn from_metadata(metadata: &OperationMetadata) -> Self {
+        let OperationMetadata {
+            start_time,
+            end_time,
+            description,
+            hostname,
+            username,
+            is_snapshot,
+            tags,
+            working_copy_tree_ids,
+            includes_snapshot,
+            stopped_rebase,
+        } = metadata;
+        OperationMetadataJson {
+            start_time: *start_time,
+            end_time: *end_time,
+            description: description.clone(),
+            hostname: hostname.clone(),
+            username: username.clone(),
+            is_snapshot: *is_snapshot,
+            tags: tags.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            working_copy_tree_ids: working_copy_tree_ids
+                .as_ref()
+                .map(|ids| ids.iter().map(|id| id.hex()).collect()),
+            includes_snapshot: *includes_snapshot,
+            stopped_rebase: stopped_rebase
+                .as_ref()
+                .map(StoppedRebaseJson::from_stopped_rebase),
+        }
+    }
+
+    fn into_metadata(self) -> Result<OperationMetadata, JsonDecodeError> {
+        let working_copy_tree_ids = self
+            .working_copy_tree_ids
+            .map(|ids| -> Result<_, JsonDecodeError> {
+                let ids: Vec<_> = ids
+                    .into_iter()
+                    .map(|hex| TreeId::try_from_hex(&hex).map_err(|_| invalid_id("tree", hex)))
+                    .try_collect()?;
+                if ids.len() % 2 == 0 {
+                    return Err(JsonDecodeError::InvalidTreeIds(ids.len()));
+                }
+                Ok(Merge::from_vec(ids))
+            })
+            .transpose()?;
+        Ok(OperationMetadata {
+            start_time: self.start_time,
+            end_time: self.end_time,
+            description: self.description,
+            hostname: self.hostname,
+            username: self.username,
+            is_snapshot: self.is_snapshot,
+            tags: self.tags.into_iter().collect(),
+            working_copy_tree_ids,
+            includes_snapshot: self.includes_snapshot,
+            stopped_rebase: self
+                .stopped_rebase
+                .map(StoppedRebaseJson::into_stopped_rebase)
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct StoppedRebaseJson {
+    conflicted_commit_id: String,
+    rewrites: BTreeMap<String, PendingRewriteJson>,
+}
+
+impl StoppedRebaseJson {
+    fn from_stopped_rebase(stopped_rebase: &StoppedRebase) -> Self {
+        let hex_ids = |ids: &[CommitId]| ids.iter().map(|id| id.hex()).collect();
+        let rewrites = stopped_rebase
+            .rewrites
+            .iter()
+            .map(|(old_id, rewrite)| {
+                let rewrite = match rewrite {
+                    PendingRewrite::Rewritten(new_id) => {
+                        PendingRewriteJson::Rewritten(new_id.hex())
+                    }
+                    PendingRewrite::Divergent(new_ids) => {
+                        PendingRewriteJson::Divergent(hex_ids(new_ids))
+                    }
+                    PendingRewrite::Abandoned(new_ids) => {
+                        PendingRewriteJson::Abandoned(hex_ids(new_ids))
+                    }
+                };
+                (old_id.hex(), rewrite)
+            })
+            .collect();
+        StoppedRebaseJson {
+            conflicted_commit_id: stopped_rebase.conflicted_commit_id.hex(),
+            rewrites,
+        }
+    }
+
+    fn into_stopped_rebase(self) -> Result<StoppedRebase, JsonDecodeError> {
+        let commit_ids_from_hex =
+            |ids: Vec<String>| ids.into_iter().map(commit_id_from_hex).try_collect();
+        let rewrites = self
+            .rewrites
+            .into_iter()
+            .map(|(old_hex, rewrite)| {
+                let rewrite = match rewrite {
+                    PendingRewriteJson::Rewritten(hex) => {
+                        PendingRewrite::Rewritten(commit_id_from_hex(hex)?)
+                    }
+                    PendingRewriteJson::Divergent(ids) => {
+                        PendingRewrite::Divergent(commit_ids_from_hex(ids)?)
+                    }
+                    PendingRewriteJson::Abandoned(ids) => {
+                        PendingRewrite::Abandoned(commit_ids_from_hex(ids)?)
+                    }
+                };
+                Ok((commit_id_from_hex(old_hex)?, rewrite))
+            })
+            .collect::<Result<_, JsonDecodeError>>()?;
+        Ok(StoppedRebase {
+            conflicted_commit_id: commit_id_from_hex(self.conflicted_commit_id)?,
+            rewrites,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum PendingRewriteJson {
+    Rewritten(String),
+    Divergent(Vec<String>),
+    Abandoned(Vec<String>),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ViewJson {
+    head_ids: Vec<String>,
+    local_bookmarks: BTreeMap<String, RefTargetJson>,
+    tags: BTreeMap<String, RefTargetJson>,
+    remote_views: BTreeMap<String, BTreeMap<String, RemoteRefJson>>,
+    git_refs: BTreeMap<String, RefTargetJson>,
+    git_head: RefTargetJson,
+    wc_commit_ids: BTreeMap<String, String>,
+    anonymous_heads: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bisect_state: Option<BisectStateJson>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    remotes: BTreeMap<String, RemoteConfigJson>,
+}
+
+impl ViewJson {
+    fn from_view(view: &View) -> Self {
+        let View {
+            head_ids,
+            local_bookmarks,
+            tags,
+            remote_views,
+            git_refs,
+            git_head,
+            wc_commit_ids,
+            anonymous_heads,
+            bisect_state,
+            remotes,
+        } = view;
+        let ref_targets_to_json = |targets: &BTreeMap<String, RefTarget>| {
+            targets
+                .iter()
+                .map(|(name, target)| (name.clone(), RefTargetJson::from_ref_target(target)))
+                .collect()
+        };
+        ViewJson {
+            head_ids: head_ids.iter().map(|id| id.hex()).sorted().collect(),
+            local_bookmarks: ref_targets_to_json(local_bookmarks),
+            tags: ref_targets_to_json(tags),
+            remote_views: remote_views
+                .iter()
+                .map(|(remote_name, remote_view)| {
+                    let bookmarks = remote_view
+                        .bookmarks
+                        .iter()
+                        .map(|(name, remote_ref)| {
+                            (name.clone(), RemoteRefJson::from_remote_ref(remote_ref))
+                        })
+                        .collect();
+                    (remote_name.clone(), bookmarks)
+                })
+                .collect(),
+            git_refs: ref_targets_to_json(git_refs),
+            git_head: RefTargetJson::from_ref_target(git_head),
+            wc_commit_ids: wc_commit_ids
+                .iter()
+                .map(|(workspace_id, id)| (workspace_id.as_str().to_owned(), id.hex()))
+                .collect(),
+            anonymous_heads: anonymous_heads
+                .iter()
+                .map(|(name, id)| (name.clone(), id.hex()))
+                .collect(),
+            bisect_state: (!bisect_state.is_empty())
+                .then(|| BisectStateJson::from_bisect_state(bisect_state)),
+            remotes: remotes
+                .iter()
+                .map(|(name, config)| (name.clone(), RemoteConfigJson::from_remote_config(config)))
+                .collect(),
+        }
+    }
+
+    fn into_view(self) -> Result<View, JsonDecodeError> {
+        fn ref_targets_from_json(
+            targets: BTreeMap<String, RefTargetJson>,
+        ) -> Result<BTreeMap<String, RefTarget>, JsonDecodeError> {
+            targets
+                .into_iter()
+                .map(|(name, target)| Ok((name, target.into_ref_target()?)))
+                .collect()
+        }
+
+        let head_ids = self
+            .head_ids
+            .into_iter()
+            .map(commit_id_from_hex)
+            .try_collect()?;
+        let remote_views = self
+            .remote_views
+            .into_iter()
+            .map(|(remote_name, bookmarks)| {
+                let bookmarks = bookmarks
+                    .into_iter()
+                    .map(|(name, remote_ref)| Ok((name, remote_ref.into_remote_ref()?)))
+                    .collect::<Result<_, JsonDecodeError>>()?;
+                Ok((remote_name, RemoteView { bookmarks }))
+            })
+            .collect::<Result<_, JsonDecodeError>>()?;
+        let wc_commit_ids = self
+            .wc_commit_ids
+            .into_iter()
+            .map(|(workspace_id, hex)| {
+                Ok((WorkspaceId::new(workspace_id), commit_id_from_hex(hex)?))
+            })
+            .collect::<Result<_, JsonDecodeError>>()?;
+        let anonymous_heads = self
+            .anonymous_heads
+            .into_iter()
+            .map(|(name, hex)| Ok((name, commit_id_from_hex(hex)?)))
+            .collect::<Result<_, JsonDecodeError>>()?;
+        Ok(View {
+            head_ids,
+            local_bookmarks: ref_targets_from_json(self.local_bookmarks)?,
+            tags: ref_targets_from_json(self.tags)?,
+            remote_views,
+            git_refs: ref_targets_from_json(self.git_refs)?,
+            git_head: self.git_head.into_ref_target()?,
+            wc_commit_ids,
+            anonymous_heads,
+            bisect_state: self
+                .bisect_state
+                .map(BisectStateJson::into_bisect_state)
+                .transpose()?
+                .unwrap_or_default(),
+            remotes: self
+                .remotes
+                .into_iter()
+                .map(|(name, config)| (name, config.into_remote_config()))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BisectStateJson {
+    good_commits: Vec<String>,
+    bad_commits: Vec<String>,
+    skipped_commits: Vec<String>,
+}
+
+impl BisectStateJson {
+    fn from_bisect_state(state: &BisectState) -> Self {
+        let to_json = |ids: &[CommitId]| ids.iter().map(|id| id.hex()).collect();
+        BisectStateJson {
+            good_commits: to_json(&state.good_commits),
+            bad_commits: to_json(&state.bad_commits),
+            skipped_commits: to_json(&state.skipped_commits),
+        }
+    }
+
+    fn into_bisect_state(self) -> Result<BisectState, JsonDecodeError> {
+        let from_json = |ids: Vec<String>| ids.into_iter().map(commit_id_from_hex).try_collect();
+        Ok(BisectState {
+            good_commits: from_json(self.good_commits)?,
+            bad_commits: from_json(self.bad_commits)?,
+            skipped_commits: from_json(self.skipped_commits)?,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteConfigJson {
+    url: String,
+}
+
+impl RemoteConfigJson {
+    fn from_remote_config(config: &RemoteConfig) -> Self {
+        RemoteConfigJson {
+            url: config.url.clone(),
+        }
+    }
+
+    fn into_remote_config(self) -> RemoteConfig {
+        RemoteConfig { url: self.url }
+    }
+}
+
+/// Ref target as a list of alternating added and removed commit ids, in the
+/// same order as [`Merge`] terms. Absent terms are `null`.
+#[derive(Deserialize, Serialize)]
+#[serde(transparent)]
+struct RefTargetJson(Vec<Option<String>>);
+
+impl RefTargetJson {
+    fn from_ref_target(target: &RefTarget) -> Self {
+        let terms = target
+            .as_merge()
+            .iter()
+            .map(|id| id.as_ref().map(|id| id.hex()))
+            .collect();
+        RefTargetJson(terms)
+    }
+
+    fn into_ref_target(self) -> Result<RefTarget, JsonDecodeError> {
+        if self.0.len() % 2 == 0 {
+            return Err(JsonDecodeError::InvalidRefTarget(self.0.len()));
+        }
+        let terms: Vec<_> = self
+            .0
+            .into_iter()
+            .map(|hex| hex.map(commit_id_from_hex).transpose())
+            .try_collect()?;
+        Ok(RefTarget::from_merge(Merge::from_vec(terms)))
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteRefJson {
+    target: RefTargetJson,
+    state: RemoteRefStateJson,
+}
+
+impl RemoteRefJson {
+    fn from_remote_ref(remote_ref: &RemoteRef) -> Self {
+        let state = match remote_ref.state {
+            RemoteRefState::New => RemoteRefStateJson::New,
+            RemoteRefState::Tracking => RemoteRefStateJson::Tracking,
+        };
+        RemoteRefJson {
+            target: RefTargetJson::from_ref_target(&remote_ref.target),
+            state,
+        }
+    }
+
+    fn into_remote_ref(self) -> Result<RemoteRef, JsonDecodeError> {
+        let state = match self.state {
+            RemoteRefStateJson::New => RemoteRefState::New,
+            RemoteRefStateJson::Tracking => RemoteRefState::Tracking,
+        };
+        Ok(RemoteRef {
+            target: self.target.into_ref_target()?,
+            state,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum RemoteRefStateJson {
+    New,
+    Tracking,
+}
+
+fn commit_id_from_hex(hex: String) -> Result<CommitId, JsonDecodeError> {
+    CommitId::try_from_hex(&hex).map_err(|_| invalid_id("commit", hex))
+}
+
+fn invalid_id(kind: &'static str, value: String) -> JsonDecodeError {
+    JsonDecodeError::InvalidId { kind, value }
+}
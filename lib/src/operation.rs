@@ -28,6 +28,7 @@ use crate::op_store::OpStoreResult;
 use crate::op_store::OperationId;
 use crate::op_store::OperationMetadata;
 use crate::op_store::ViewId;
+use crate::op_store_json::OperationJson;
 use crate::view::View;
 
 /// A wrapper around [`op_store::Operation`] that defines additional methods and
@@ -71,6 +72,17 @@ impl Hash for Operation {
     }
 }
 
+/// Serializes the operation in the schema of [`crate::op_store_json`], with the
+/// operation id added. The contents of the view are omitted.
+impl serde::Serialize for Operation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        OperationJson::new(Some(self.id()), &self.data).serialize(serializer)
+    }
+}
+
 impl Operation {
     pub fn new(
         op_store: Arc<dyn OpStore>,
@@ -0,0 +1,1233 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Patch series in the format of `git format-patch`, and applying patches
+//! like `git apply`.
+
+use std::collections::BTreeMap;
+use std::io::Read as _;
+use std::ops::Range;
+use std::rc::Rc;
+
+use flate2::read::ZlibDecoder;
+use futures::StreamExt as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::backend::MergedTreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::conflicts::materialized_diff_stream;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::conflicts::MaterializedTreeDiffEntry;
+use crate::copies::CopyRecords;
+use crate::diff_presentation::unified::git_diff_file_paths;
+use crate::diff_presentation::unified::git_diff_part;
+use crate::diff_presentation::unified::unified_diff_hunks;
+use crate::diff_presentation::unified::write_git_binary_patch;
+use crate::diff_presentation::unified::write_git_diff_header;
+use crate::diff_presentation::unified::DiffLineType;
+use crate::diff_presentation::unified::UnifiedDiffError;
+use crate::diff_presentation::LineCompareMode;
+use crate::matchers::EverythingMatcher;
+use crate::merge::Merge;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::object_id::ObjectId as _;
+use crate::repo::Repo;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::ResolvedRevsetExpression;
+use crate::revset::RevsetEvaluationError;
+
+/// Maximum length of the subject part of a patch file name.
+const FILE_NAME_SUBJECT_MAX_LEN: usize = 52;
+
+/// Options for [`format_patches()`].
+#[derive(Clone, Debug)]
+pub struct FormatPatchOptions {
+    /// Number of context lines around each change.
+    pub context: usize,
+    /// Prefix in the brackets of the subject line, e.g. `PATCH` or `RFC`.
+    pub subject_prefix: String,
+    /// Signature appended after the `-- ` line of each patch, or `None` to
+    /// omit it.
+    pub signature: Option<String>,
+    /// Style of conflict markers in conflicted files.
+    pub conflict_marker_style: ConflictMarkerStyle,
+}
+
+impl Default for FormatPatchOptions {
+    fn default() -> Self {
+        Self {
+            context: 3,
+            subject_prefix: "PATCH".to_owned(),
+            signature: Some(format!("jj {}", env!("CARGO_PKG_VERSION"))),
+            conflict_marker_style: ConflictMarkerStyle::Git,
+        }
+    }
+}
+
+/// One patch of a series.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Patch {
+    /// Commit the patch was made from.
+    pub commit_id: CommitId,
+    /// File name like `0001-subject-of-the-change.patch`.
+    pub file_name: String,
+    /// Contents of the patch as an mbox message.
+    pub text: Vec<u8>,
+}
+
+/// Error while formatting a patch series.
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// Failed to evaluate the revset.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// Failed to read a commit or file from the backend.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to prepare a file diff.
+    #[error(transparent)]
+    Diff(#[from] UnifiedDiffError),
+    /// A merge commit can't be represented as a patch.
+    #[error("Cannot format merge commit {} as a patch", .0.hex())]
+    MergeCommit(CommitId),
+    /// The author timestamp can't be represented as a date.
+    #[error("Invalid author timestamp in commit {}", .0.hex())]
+    InvalidTimestamp(CommitId),
+}
+
+/// Formats the commits in `expression` as a patch series.
+///
+/// Patches are ordered parents first and numbered `[PATCH n/m]`, or just
+/// `[PATCH]` if there is only one. If all the commits are based on a single
+/// commit outside of the set, it is recorded as `base-commit:` in the first
+/// patch.
+pub fn format_patches(
+    repo: &dyn Repo,
+    expression: &Rc<ResolvedRevsetExpression>,
+    options: &FormatPatchOptions,
+) -> Result<Vec<Patch>, PatchError> {
+    let commit_ids: Vec<CommitId> = expression.clone().evaluate(repo)?.iter().try_collect()?;
+    let commits: Vec<Commit> = commit_ids
+        .iter()
+        .rev()
+        .map(|id| repo.store().get_commit(id))
+        .try_collect()?;
+    if let Some(commit) = commits.iter().find(|commit| commit.parent_ids().len() > 1) {
+        return Err(PatchError::MergeCommit(commit.id().clone()));
+    }
+    let base_commit_id = {
+        let bases = expression.parents().minus(expression);
+        let base_ids: Vec<CommitId> = bases.evaluate(repo)?.iter().try_collect()?;
+        match &base_ids[..] {
+            [id] if id != repo.store().root_commit_id() => Some(id.clone()),
+            _ => None,
+        }
+    };
+
+    let total = commits.len();
+    let mut patches = Vec::with_capacity(total);
+    for (index, commit) in commits.iter().enumerate() {
+        let number = index + 1;
+        let subject_prefix = if total == 1 {
+            format!("[{}]", options.subject_prefix)
+        } else {
+            format!("[{} {number}/{total}]", options.subject_prefix)
+        };
+        let base_commit_id = base_commit_id.as_ref().filter(|_| index == 0);
+        let text = format_patch(repo, commit, &subject_prefix, base_commit_id, options)?;
+        patches.push(Patch {
+            commit_id: commit.id().clone(),
+            file_name: format!("{number:04}-{}.patch", sanitize_subject(commit.subject())),
+            text,
+        });
+    }
+    Ok(patches)
+}
+
+/// Formats the commits in `expression` as a single mbox stream.
+pub fn format_mbox(
+    repo: &dyn Repo,
+    expression: &Rc<ResolvedRevsetExpression>,
+    options: &FormatPatchOptions,
+) -> Result<Vec<u8>, PatchError> {
+    let patches = format_patches(repo, expression, options)?;
+    Ok(patches.into_iter().flat_map(|patch| patch.text).collect())
+}
+
+fn format_patch(
+    repo: &dyn Repo,
+    commit: &Commit,
+    subject_prefix: &str,
+    base_commit_id: Option<&CommitId>,
+    options: &FormatPatchOptions,
+) -> Result<Vec<u8>, PatchError> {
+    let author = commit.author();
+    let date = author
+        .timestamp
+        .to_datetime()
+        .ok_or_else(|| PatchError::InvalidTimestamp(commit.id().clone()))?;
+    let subject = commit.subject();
+    let body = commit.body().trim_end();
+    let mut headers = String::new();
+    headers.push_str(&format!(
+        "From {} Mon Sep 17 00:00:00 2001\n",
+        commit.id().hex()
+    ));
+    headers.push_str(&format!(
+        "From: {} <{}>\n",
+        encode_header_value(&author.name),
+        author.email
+    ));
+    headers.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+    headers.push_str(&format!(
+        "Subject: {} {}\n",
+        subject_prefix,
+        encode_header_value(subject)
+    ));
+
+    let mut message = String::new();
+    message.push('\n');
+    for line in body.lines() {
+        // Quote lines which would be taken as the start of the next message,
+        // as in the mboxrd format.
+        if line.trim_start_matches('>').starts_with("From ") {
+            message.push('>');
+        }
+        message.push_str(line);
+        message.push('\n');
+    }
+    message.push_str("---\n");
+
+    let mut diff = vec![];
+    let parent_tree = commit.parent_tree(repo)?;
+    let tree = commit.tree()?;
+    write_git_diff(&mut diff, repo, &parent_tree, &tree, options)?;
+
+    let mut trailer = String::new();
+    if let Some(id) = base_commit_id {
+        trailer.push_str(&format!("\nbase-commit: {}\n", id.hex()));
+    }
+    if let Some(signature) = &options.signature {
+        trailer.push_str(&format!("-- \n{signature}\n"));
+    }
+    trailer.push('\n');
+
+    if !message.is_ascii() || !diff.is_ascii() {
+        headers.push_str("MIME-Version: 1.0\n");
+        headers.push_str("Content-Type: text/plain; charset=UTF-8\n");
+        headers.push_str("Content-Transfer-Encoding: 8bit\n");
+    }
+    let mut text = headers.into_bytes();
+    text.extend_from_slice(message.as_bytes());
+    text.extend_from_slice(&diff);
+    text.extend_from_slice(trailer.as_bytes());
+    Ok(text)
+}
+
+fn write_git_diff(
+    out: &mut Vec<u8>,
+    repo: &dyn Repo,
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    options: &FormatPatchOptions,
+) -> Result<(), PatchError> {
+    let copy_records = CopyRecords::default();
+    let tree_diff = from_tree.diff_stream_with_copies(to_tree, &EverythingMatcher, &copy_records);
+    let mut diff_stream = materialized_diff_stream(repo.store(), tree_diff);
+    async {
+        while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
+            let (left_value, right_value) = values?;
+            let left_part =
+                git_diff_part(path.source(), left_value, options.conflict_marker_style)?;
+            let right_part =
+                git_diff_part(path.target(), right_value, options.conflict_marker_style)?;
+            // Binary patches can only be applied with the full object ids.
+            let is_binary = left_part.content.is_binary || right_part.content.is_binary;
+            write_git_diff_header(out, &path, &left_part, &right_part, is_binary)?;
+
+            if left_part.content.contents == right_part.content.contents {
+                continue; // no content hunks
+            }
+
+            if is_binary {
+                write_git_binary_patch(
+                    out,
+                    &left_part.content.contents,
+                    &right_part.content.contents,
+                )
+                .map_err(UnifiedDiffError::from)?;
+                continue;
+            }
+            let (left_path, right_path) = git_diff_file_paths(&path, &left_part, &right_part);
+            push_line(out, &format!("--- {left_path}"));
+            push_line(out, &format!("+++ {right_path}"));
+            let hunks = unified_diff_hunks(
+                &left_part.content.contents,
+                &right_part.content.contents,
+                options.context,
+                LineCompareMode::Exact,
+            );
+            for hunk in hunks {
+                push_line(
+                    out,
+                    &format!(
+                        "@@ -{},{} +{},{} @@",
+                        hunk_line_number(hunk.left_line_range.clone()),
+                        hunk.left_line_range.len(),
+                        hunk_line_number(hunk.right_line_range.clone()),
+                        hunk.right_line_range.len()
+                    ),
+                );
+                for (line_type, tokens) in &hunk.lines {
+                    out.push(match line_type {
+                        DiffLineType::Context => b' ',
+                        DiffLineType::Removed => b'-',
+                        DiffLineType::Added => b'+',
+                    });
+                    for (_, content) in tokens {
+                        out.extend_from_slice(content);
+                    }
+                    let (_, content) = tokens.last().expect("hunk line must not be empty");
+                    if !content.ends_with(b"\n") {
+                        out.extend_from_slice(b"\n\\ No newline at end of file\n");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    .block_on()
+}
+
+fn push_line(out: &mut Vec<u8>, line: &str) {
+    out.extend_from_slice(line.as_bytes());
+    out.push(b'\n');
+}
+
+/// Returns the first line number of a hunk range. Empty ranges are numbered
+/// after the preceding line, as in POSIX `diff -u`.
+fn hunk_line_number(range: Range<usize>) -> usize {
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
+    }
+}
+
+/// Turns a subject into the file name part, like `git format-patch` does.
+fn sanitize_subject(subject: &str) -> String {
+    let mut name = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            if name.len() >= FILE_NAME_SUBJECT_MAX_LEN {
+                break;
+            }
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_end_matches(['-', '.']);
+    if name.is_empty() {
+        "patch".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Encodes a non-ASCII header value as an RFC 2047 encoded word.
+fn encode_header_value(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_owned();
+    }
+    let mut encoded = "=?UTF-8?q?".to_owned();
+    for &b in value.as_bytes() {
+        match b {
+            b' ' => encoded.push('_'),
+            b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' | b'!' | b'*' | b'+' | b'-' | b'/' => {
+                encoded.push(char::from(b));
+            }
+            _ => encoded.push_str(&format!("={b:02X}")),
+        }
+    }
+    encoded.push_str("?=");
+    encoded
+}
+
+/// Result of [`apply()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ApplyPatchResult {
+    /// All file patches applied cleanly, producing this tree.
+    Applied(MergedTreeId),
+    /// Some file patches didn't apply. The tree is left unchanged.
+    Conflicts(Vec<ApplyPatchConflict>),
+}
+
+/// File patch which couldn't be applied.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApplyPatchConflict {
+    /// Path of the file in the tree.
+    pub path: RepoPathBuf,
+    /// Why the patch didn't apply.
+    pub reason: ApplyPatchConflictReason,
+}
+
+/// Reason why a file patch didn't apply.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ApplyPatchConflictReason {
+    /// The patch modifies a file which doesn't exist.
+    #[error("No such file")]
+    FileNotFound,
+    /// The patch creates a file which already exists.
+    #[error("File already exists")]
+    FileExists,
+    /// The file isn't a regular file or symlink, or it is conflicted.
+    #[error("Not a regular file or symlink, or has conflicts")]
+    UnsupportedFile,
+    /// A hunk didn't match the file contents.
+    #[error("Hunk starting at line {0} doesn't match")]
+    HunkMismatch(usize),
+    /// The file contents don't match the preimage of the patch.
+    #[error("File contents don't match the patch")]
+    ContentMismatch,
+    /// The patch says the binary file changed, but doesn't include the data.
+    #[error("Binary patch without data")]
+    MissingBinaryData,
+}
+
+/// Malformed patch text.
+#[derive(Debug, Error)]
+#[error("Invalid patch at line {line}: {message}")]
+pub struct PatchParseError {
+    /// 1-based line number in the patch text.
+    pub line: usize,
+    /// Description of the problem.
+    pub message: String,
+}
+
+/// Error while applying a patch.
+#[derive(Debug, Error)]
+pub enum ApplyPatchError {
+    /// The patch couldn't be parsed.
+    #[error(transparent)]
+    Parse(#[from] PatchParseError),
+    /// Failed to read or write files in the backend.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to read file contents.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// File mode in a Git-style diff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FileMode {
+    Normal,
+    Executable,
+    Symlink,
+    Unsupported,
+}
+
+impl FileMode {
+    fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "100644" | "100664" => Some(FileMode::Normal),
+            "100755" => Some(FileMode::Executable),
+            "120000" => Some(FileMode::Symlink),
+            "040000" | "160000" => Some(FileMode::Unsupported),
+            _ => None,
+        }
+    }
+}
+
+/// Changes to a single file parsed from a patch.
+#[derive(Debug)]
+struct FilePatch {
+    /// Path before the change, or `None` if the file is created.
+    old_path: Option<RepoPathBuf>,
+    /// Path after the change, or `None` if the file is deleted.
+    new_path: Option<RepoPathBuf>,
+    old_mode: Option<FileMode>,
+    new_mode: Option<FileMode>,
+    /// The old file is kept, as in `copy from`/`copy to`.
+    is_copy: bool,
+    body: FilePatchBody,
+}
+
+#[derive(Debug)]
+enum FilePatchBody {
+    Text(Vec<TextHunk>),
+    Binary(BinaryHunk),
+    /// `Binary files a/.. and b/.. differ`
+    BinaryWithoutData,
+}
+
+#[derive(Debug)]
+struct TextHunk {
+    /// 1-based line number of the first old line, or of the line before an
+    /// empty range.
+    old_start: usize,
+    old_lines: Vec<Vec<u8>>,
+    new_lines: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+enum BinaryHunk {
+    Literal(Vec<u8>),
+    Delta(Vec<u8>),
+}
+
+#[derive(Clone, Debug)]
+struct FileState {
+    mode: FileMode,
+    contents: Vec<u8>,
+}
+
+/// Applies `patch_text` to `tree`.
+///
+/// The patch can be a plain unified diff or a Git-style diff, including
+/// renames, copies, mode changes, and `GIT binary patch` data. Text before
+/// the first file diff, such as mbox headers and the commit message, is
+/// ignored, so a `git format-patch` series can be applied as a whole. Hunks
+/// are located at the recorded line numbers or the nearest offset where the
+/// context matches, but no fuzz is allowed.
+pub fn apply(
+    repo: &dyn Repo,
+    tree: &MergedTree,
+    patch_text: &[u8],
+) -> Result<ApplyPatchResult, ApplyPatchError> {
+    let file_patches = parse_patch(patch_text)?;
+    let store = repo.store();
+    let mut overrides: BTreeMap<RepoPathBuf, Option<FileState>> = BTreeMap::new();
+    let mut conflicts = vec![];
+    let read_file =
+        |overrides: &BTreeMap<RepoPathBuf, Option<FileState>>,
+         path: &RepoPath|
+         -> Result<Result<Option<FileState>, ApplyPatchConflictReason>, ApplyPatchError> {
+            if let Some(state) = overrides.get(path) {
+                return Ok(Ok(state.clone()));
+            }
+            let value = tree.path_value(path)?;
+            let state = match value.as_resolved() {
+                Some(None) => None,
+                Some(Some(TreeValue::File { id, executable })) => {
+                    let mut contents = vec![];
+                    store.read_file(path, id)?.read_to_end(&mut contents)?;
+                    let mode = if *executable {
+                        FileMode::Executable
+                    } else {
+                        FileMode::Normal
+                    };
+                    Some(FileState { mode, contents })
+                }
+                Some(Some(TreeValue::Symlink(id))) => Some(FileState {
+                    mode: FileMode::Symlink,
+                    contents: store.read_symlink(path, id)?.into_bytes(),
+                }),
+                _ => return Ok(Err(ApplyPatchConflictReason::UnsupportedFile)),
+            };
+            Ok(Ok(state))
+        };
+
+    for file_patch in file_patches {
+        let conflict_path = file_patch
+            .new_path
+            .as_ref()
+            .or(file_patch.old_path.as_ref())
+            .expect("parser should reject file patches without paths")
+            .clone();
+        let mut conflict = |reason| {
+            conflicts.push(ApplyPatchConflict {
+                path: conflict_path.clone(),
+                reason,
+            });
+        };
+        let old_state = match &file_patch.old_path {
+            Some(path) => match read_file(&overrides, path)? {
+                Ok(Some(state)) => state,
+                Ok(None) => {
+                    conflict(ApplyPatchConflictReason::FileNotFound);
+                    continue;
+                }
+                Err(reason) => {
+                    conflict(reason);
+                    continue;
+                }
+            },
+            None => FileState {
+                mode: FileMode::Normal,
+                contents: vec![],
+            },
+        };
+        if let Some(path) = &file_patch.new_path {
+            if file_patch.old_path.as_ref() != Some(path) {
+                match read_file(&overrides, path)? {
+                    Ok(None) => {}
+                    Ok(Some(_)) => {
+                        conflict(ApplyPatchConflictReason::FileExists);
+                        continue;
+                    }
+                    Err(reason) => {
+                        conflict(reason);
+                        continue;
+                    }
+                }
+            }
+        }
+        if file_patch.old_mode == Some(FileMode::Unsupported)
+            || file_patch.new_mode == Some(FileMode::Unsupported)
+        {
+            conflict(ApplyPatchConflictReason::UnsupportedFile);
+            continue;
+        }
+
+        let new_contents = match &file_patch.body {
+            FilePatchBody::Text(hunks) => apply_text_hunks(&old_state.contents, hunks),
+            FilePatchBody::Binary(BinaryHunk::Literal(data)) => Ok(data.clone()),
+            FilePatchBody::Binary(BinaryHunk::Delta(delta)) => {
+                apply_binary_delta(&old_state.contents, delta)
+                    .ok_or(ApplyPatchConflictReason::ContentMismatch)
+            }
+            FilePatchBody::BinaryWithoutData => Err(ApplyPatchConflictReason::MissingBinaryData),
+        };
+        let new_contents = match new_contents {
+            Ok(contents) => contents,
+            Err(reason) => {
+                conflict(reason);
+                continue;
+            }
+        };
+
+        if let Some(path) = &file_patch.old_path {
+            if !file_patch.is_copy && file_patch.new_path.as_ref() != Some(path) {
+                overrides.insert(path.clone(), None);
+            }
+        }
+        match &file_patch.new_path {
+            Some(path) => {
+                let mode = file_patch.new_mode.unwrap_or(old_state.mode);
+                let state = FileState {
+                    mode,
+                    contents: new_contents,
+                };
+                overrides.insert(path.clone(), Some(state));
+            }
+            None => {
+                // A deletion should remove all of the contents.
+                if !new_contents.is_empty() {
+                    conflict(ApplyPatchConflictReason::ContentMismatch);
+                }
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Ok(ApplyPatchResult::Conflicts(conflicts));
+    }
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    for (path, state) in overrides {
+        let value = match state {
+            None => None,
+            Some(FileState {
+                mode: FileMode::Symlink,
+                contents,
+            }) => {
+                let target = String::from_utf8_lossy(&contents);
+                let id = store.write_symlink(&path, &target).block_on()?;
+                Some(TreeValue::Symlink(id))
+            }
+            Some(FileState { mode, contents }) => {
+                let id = store
+                    .write_file(&path, &mut contents.as_slice())
+                    .block_on()?;
+                Some(TreeValue::File {
+                    id,
+                    executable: mode == FileMode::Executable,
+                })
+            }
+        };
+        tree_builder.set_or_remove(path, Merge::resolved(value));
+    }
+    let tree_id = tree_builder.write_tree(store)?;
+    Ok(ApplyPatchResult::Applied(tree_id))
+}
+
+fn apply_text_hunks(
+    contents: &[u8],
+    hunks: &[TextHunk],
+) -> Result<Vec<u8>, ApplyPatchConflictReason> {
+    let lines = contents.split_inclusive(|b| *b == b'\n').collect_vec();
+    let mut new_contents = vec![];
+    // Index of the first line which isn't copied to the new contents yet
+    let mut pos = 0;
+    // Difference between the actual and the recorded positions of hunks
+    let mut offset: isize = 0;
+    for hunk in hunks {
+        if hunk.old_start > lines.len() + 1 {
+            return Err(ApplyPatchConflictReason::HunkMismatch(hunk.old_start));
+        }
+        let expected = if hunk.old_lines.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        // No hunk can start past the end, so the search below is bounded by
+        // the number of lines.
+        let expected = expected.saturating_add_signed(offset).min(lines.len());
+        let matches_at = |start: usize| {
+            start >= pos
+                && start
+                    .checked_add(hunk.old_lines.len())
+                    .is_some_and(|end| end <= lines.len())
+                && hunk
+                    .old_lines
+                    .iter()
+                    .zip(&lines[start..])
+                    .all(|(old, line)| old == line)
+        };
+        let max_distance = expected.max(lines.len().saturating_sub(expected));
+        let start = (0..=max_distance)
+            .flat_map(|distance| {
+                [
+                    expected.checked_sub(distance),
+                    expected.checked_add(distance),
+                ]
+            })
+            .flatten()
+            .find(|&start| matches_at(start))
+            .ok_or(ApplyPatchConflictReason::HunkMismatch(hunk.old_start))?;
+        offset += start as isize - expected as isize;
+        new_contents.extend(lines[pos..start].iter().copied().flatten());
+        new_contents.extend(hunk.new_lines.iter().flatten());
+        pos = start + hunk.old_lines.len();
+    }
+    new_contents.extend(lines[pos..].iter().copied().flatten());
+    Ok(new_contents)
+}
+
+/// Applies a Git binary delta to `base`. Returns `None` if the delta doesn't
+/// apply.
+fn apply_binary_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    fn read_size(data: &mut &[u8]) -> Option<usize> {
+        let mut size = 0;
+        let mut shift = 0;
+        loop {
+            let (&byte, rest) = data.split_first()?;
+            *data = rest;
+            size |= usize::from(byte & 0x7f).checked_shl(shift)?;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(size);
+            }
+        }
+    }
+
+    let mut data = delta;
+    let base_size = read_size(&mut data)?;
+    let result_size = read_size(&mut data)?;
+    if base_size != base.len() {
+        return None;
+    }
+    // The size comes from the patch, so don't trust it for allocation. The
+    // result can't be larger than the copied and inserted bytes.
+    let mut result = Vec::with_capacity(result_size.min(base.len() + delta.len()));
+    while let Some((&command, rest)) = data.split_first() {
+        if result.len() > result_size {
+            return None;
+        }
+        data = rest;
+        if command & 0x80 != 0 {
+            // Copy from the base. The bits select the offset and size bytes.
+            let mut read_bytes = |bits: std::ops::Range<u32>| {
+                let mut value = 0;
+                for (i, bit) in bits.enumerate() {
+                    if command & (1 << bit) != 0 {
+                        let (&byte, rest) = data.split_first()?;
+                        data = rest;
+                        value |= usize::from(byte) << (8 * i);
+                    }
+                }
+                Some(value)
+            };
+            let copy_offset = read_bytes(0..4)?;
+            let copy_size = match read_bytes(4..7)? {
+                0 => 0x10000,
+                size => size,
+            };
+            result.extend_from_slice(base.get(copy_offset..copy_offset.checked_add(copy_size)?)?);
+        } else if command != 0 {
+            // Insert the following bytes.
+            let size = usize::from(command);
+            result.extend_from_slice(data.get(..size)?);
+            data = &data[size..];
+        } else {
+            return None;
+        }
+    }
+    (result.len() == result_size).then_some(result)
+}
+
+/// Parses file patches from a plain or Git-style unified diff.
+fn parse_patch(text: &[u8]) -> Result<Vec<FilePatch>, PatchParseError> {
+    let mut parser = PatchParser {
+        lines: text.split_inclusive(|b| *b == b'\n').collect(),
+        pos: 0,
+    };
+    let mut file_patches = vec![];
+    while let Some(line) = parser.peek() {
+        if line.starts_with(b"diff --git ") {
+            file_patches.push(parser.parse_git_file_patch()?);
+        } else if line.starts_with(b"--- ")
+            && parser
+                .peek_at(1)
+                .is_some_and(|next| next.starts_with(b"+++ "))
+        {
+            file_patches.push(parser.parse_unified_file_patch()?);
+        } else {
+            parser.pos += 1;
+        }
+    }
+    Ok(file_patches)
+}
+
+struct PatchParser<'a> {
+    lines: Vec<&'a [u8]>,
+    pos: usize,
+}
+
+impl<'a> PatchParser<'a> {
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&'a [u8]> {
+        self.lines.get(self.pos + n).copied()
+    }
+
+    fn next_line(&mut self) -> Option<&'a [u8]> {
+        let line = self.peek()?;
+        self.pos += 1;
+        Some(line)
+    }
+
+    /// Creates an error at the last consumed line.
+    fn error(&self, message: impl Into<String>) -> PatchParseError {
+        PatchParseError {
+            line: self.pos,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the line without the line terminator as a string.
+    fn text_line(&self, line: &[u8]) -> Result<String, PatchParseError> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        String::from_utf8(line.to_vec()).map_err(|_| self.error("Invalid UTF-8 in header"))
+    }
+
+    fn parse_git_file_patch(&mut self) -> Result<FilePatch, PatchParseError> {
+        let header = self.next_line().unwrap();
+        let header = self.text_line(header)?;
+        let header = header.strip_prefix("diff --git ").unwrap();
+        let (old_path, new_path) = self.parse_git_header_paths(header)?;
+        let mut file_patch = FilePatch {
+            old_path: Some(old_path),
+            new_path: Some(new_path),
+            old_mode: None,
+            new_mode: None,
+            is_copy: false,
+            body: FilePatchBody::Text(vec![]),
+        };
+        while let Some(line) = self.peek() {
+            if line.starts_with(b"--- ") || line.starts_with(b"@@ ") {
+                break;
+            }
+            if line.starts_with(b"GIT binary patch") {
+                self.pos += 1;
+                file_patch.body = FilePatchBody::Binary(self.parse_binary_hunk()?);
+                // The reverse hunk is optional and not needed
+                if self.peek().is_some_and(|line| {
+                    line.starts_with(b"literal ") || line.starts_with(b"delta ")
+                }) {
+                    self.parse_binary_hunk()?;
+                }
+                return Ok(file_patch);
+            }
+            if line.starts_with(b"Binary files ") {
+                self.pos += 1;
+                file_patch.body = FilePatchBody::BinaryWithoutData;
+                return Ok(file_patch);
+            }
+            self.pos += 1;
+            let line = self.text_line(line)?;
+            let parse_mode = |mode: &str| {
+                FileMode::parse(mode).ok_or_else(|| self.error(format!("Invalid mode {mode}")))
+            };
+            if let Some(mode) = line.strip_prefix("old mode ") {
+                file_patch.old_mode = Some(parse_mode(mode)?);
+            } else if let Some(mode) = line.strip_prefix("new mode ") {
+                file_patch.new_mode = Some(parse_mode(mode)?);
+            } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                if file_patch.old_path.is_none() {
+                    return Err(self.error("File is both deleted and new"));
+                }
+                file_patch.old_mode = Some(parse_mode(mode)?);
+                file_patch.new_path = None;
+            } else if let Some(mode) = line.strip_prefix("new file mode ") {
+                if file_patch.new_path.is_none() {
+                    return Err(self.error("File is both deleted and new"));
+                }
+                file_patch.new_mode = Some(parse_mode(mode)?);
+                file_patch.old_path = None;
+            } else if let Some(path) = line
+                .strip_prefix("rename from ")
+                .or_else(|| line.strip_prefix("copy from "))
+            {
+                file_patch.old_path = Some(self.parse_path(path)?);
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                file_patch.new_path = Some(self.parse_path(path)?);
+            } else if let Some(path) = line.strip_prefix("copy to ") {
+                file_patch.new_path = Some(self.parse_path(path)?);
+                file_patch.is_copy = true;
+            } else if let Some(index) = line.strip_prefix("index ") {
+                if let Some((_, mode)) = index.split_once(' ') {
+                    let mode = parse_mode(mode)?;
+                    file_patch.old_mode.get_or_insert(mode);
+                    file_patch.new_mode.get_or_insert(mode);
+                }
+            } else if !line.starts_with("similarity index ")
+                && !line.starts_with("dissimilarity index ")
+            {
+                // End of a file patch without content changes
+                self.pos -= 1;
+                return Ok(file_patch);
+            }
+        }
+        if self.peek().is_some_and(|line| line.starts_with(b"--- ")) {
+            // The paths in the header are more reliable than the ---/+++
+            // lines, which may have a trailing timestamp.
+            self.pos += 1;
+            if !self
+                .next_line()
+                .is_some_and(|line| line.starts_with(b"+++ "))
+            {
+                return Err(self.error("Expected +++ line"));
+            }
+        }
+        file_patch.body = FilePatchBody::Text(self.parse_text_hunks()?);
+        Ok(file_patch)
+    }
+
+    fn parse_unified_file_patch(&mut self) -> Result<FilePatch, PatchParseError> {
+        let old_path = self.next_line().unwrap();
+        let old_path = self.parse_unified_header_path(&self.text_line(old_path)?[4..])?;
+        let new_path = self.next_line().unwrap();
+        let new_path = self.parse_unified_header_path(&self.text_line(new_path)?[4..])?;
+        if old_path.is_none() && new_path.is_none() {
+            return Err(self.error("Both sides of the diff are /dev/null"));
+        }
+        Ok(FilePatch {
+            old_path,
+            new_path,
+            old_mode: None,
+            new_mode: None,
+            is_copy: false,
+            body: FilePatchBody::Text(self.parse_text_hunks()?),
+        })
+    }
+
+    /// Parses `a/<path> b/<path>` of a `diff --git` line.
+    fn parse_git_header_paths(
+        &self,
+        paths: &str,
+    ) -> Result<(RepoPathBuf, RepoPathBuf), PatchParseError> {
+        if paths.starts_with('"') {
+            let (old, rest) = split_quoted(paths).ok_or_else(|| self.error("Invalid path"))?;
+            let old = strip_path_prefix(&old);
+            let new = self.unquote(rest.trim_start())?;
+            let new = strip_path_prefix(&new);
+            return Ok((self.parse_path_str(old)?, self.parse_path_str(new)?));
+        }
+        if let Some(rest) = paths.strip_prefix("a/") {
+            // Without renames, both paths are the same.
+            let len = rest.len().saturating_sub(3) / 2;
+            if rest.is_char_boundary(len) && rest[len..].starts_with(" b/") {
+                let (old, new) = (&rest[..len], &rest[len + 3..]);
+                if old == new {
+                    return Ok((self.parse_path_str(old)?, self.parse_path_str(new)?));
+                }
+            }
+            if let Some((old, new)) = rest.split_once(" b/") {
+                return Ok((self.parse_path_str(old)?, self.parse_path_str(new)?));
+            }
+            if let Some((old, new)) = rest.split_once(" \"") {
+                let new = self.unquote(&format!("\"{new}"))?;
+                return Ok((
+                    self.parse_path_str(old)?,
+                    self.parse_path_str(strip_path_prefix(&new))?,
+                ));
+            }
+        }
+        Err(self.error("Invalid diff --git header"))
+    }
+
+    /// Parses the path of a `---`/`+++` line, which may be followed by a tab
+    /// and a timestamp.
+    fn parse_unified_header_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<RepoPathBuf>, PatchParseError> {
+        let path = if path.starts_with('"') {
+            self.unquote(path)?
+        } else {
+            path.split('\t').next().unwrap().to_owned()
+        };
+        if path == "/dev/null" {
+            return Ok(None);
+        }
+        self.parse_path_str(strip_path_prefix(&path)).map(Some)
+    }
+
+    /// Parses a path of a `rename from` line or similar.
+    fn parse_path(&self, path: &str) -> Result<RepoPathBuf, PatchParseError> {
+        if path.starts_with('"') {
+            self.parse_path_str(&self.unquote(path)?)
+        } else {
+            self.parse_path_str(path)
+        }
+    }
+
+    fn parse_path_str(&self, path: &str) -> Result<RepoPathBuf, PatchParseError> {
+        let is_valid = !path.is_empty()
+            && path
+                .split('/')
+                .all(|name| !name.is_empty() && name != "." && name != "..");
+        if is_valid {
+            Ok(RepoPathBuf::from_internal_string(path))
+        } else {
+            Err(self.error(format!("Invalid path {path:?}")))
+        }
+    }
+
+    fn unquote(&self, quoted: &str) -> Result<String, PatchParseError> {
+        match split_quoted(quoted) {
+            Some((path, "")) => Ok(path),
+            _ => Err(self.error(format!("Invalid quoted path {quoted}"))),
+        }
+    }
+
+    fn parse_text_hunks(&mut self) -> Result<Vec<TextHunk>, PatchParseError> {
+        let mut hunks = vec![];
+        while let Some(line) = self.peek() {
+            if !line.starts_with(b"@@ ") {
+                break;
+            }
+            self.pos += 1;
+            let header = self.text_line(line)?;
+            let (old_start, mut old_remaining, _new_start, mut new_remaining) =
+                parse_hunk_header(&header).ok_or_else(|| self.error("Invalid hunk header"))?;
+            let mut hunk = TextHunk {
+                old_start,
+                old_lines: vec![],
+                new_lines: vec![],
+            };
+            while old_remaining > 0 || new_remaining > 0 {
+                let line = self
+                    .next_line()
+                    .ok_or_else(|| self.error("Truncated hunk"))?;
+                let (sigil, content) = match line.split_first() {
+                    // Some mail clients strip the space of empty context lines
+                    Some((b'\n', _)) => (b' ', line),
+                    Some((&sigil, content)) => (sigil, content),
+                    None => (b' ', line),
+                };
+                match sigil {
+                    b' ' if old_remaining > 0 && new_remaining > 0 => {
+                        hunk.old_lines.push(content.to_vec());
+                        hunk.new_lines.push(content.to_vec());
+                        old_remaining -= 1;
+                        new_remaining -= 1;
+                    }
+                    b'-' if old_remaining > 0 => {
+                        hunk.old_lines.push(content.to_vec());
+                        old_remaining -= 1;
+                    }
+                    b'+' if new_remaining > 0 => {
+                        hunk.new_lines.push(content.to_vec());
+                        new_remaining -= 1;
+                    }
+                    b'\\' => self.strip_last_newline(&mut hunk),
+                    _ => return Err(self.error("Unexpected line in hunk")),
+                }
+            }
+            if self.peek().is_some_and(|line| line.starts_with(b"\\")) {
+                self.pos += 1;
+                self.strip_last_newline(&mut hunk);
+            }
+            hunks.push(hunk);
+        }
+        Ok(hunks)
+    }
+
+    /// Handles `\ No newline at end of file` after the previous hunk line.
+    fn strip_last_newline(&self, hunk: &mut TextHunk) {
+        let previous = self.lines[self.pos - 2];
+        let strip = |lines: &mut Vec<Vec<u8>>| {
+            if let Some(last) = lines.last_mut() {
+                if last.ends_with(b"\n") {
+                    last.pop();
+                }
+            }
+        };
+        match previous.first() {
+            Some(b'-') => strip(&mut hunk.old_lines),
+            Some(b'+') => strip(&mut hunk.new_lines),
+            _ => {
+                strip(&mut hunk.old_lines);
+                strip(&mut hunk.new_lines);
+            }
+        }
+    }
+
+    fn parse_binary_hunk(&mut self) -> Result<BinaryHunk, PatchParseError> {
+        let header = self
+            .next_line()
+            .ok_or_else(|| self.error("Missing binary hunk"))?;
+        let header = self.text_line(header)?;
+        let (kind, size) = header
+            .split_once(' ')
+            .and_then(|(kind, size)| Some((kind, size.parse::<usize>().ok()?)))
+            .ok_or_else(|| self.error("Invalid binary hunk header"))?;
+        let mut compressed = vec![];
+        while let Some(line) = self.next_line() {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            if line.is_empty() {
+                break;
+            }
+            let decoded = decode_base85_line(line)
+                .ok_or_else(|| self.error("Invalid base85 data in binary patch"))?;
+            compressed.extend(decoded);
+        }
+        // The size comes from the patch, so the decompressed data is read up to
+        // one byte more than that instead of being allocated upfront.
+        let mut data = vec![];
+        ZlibDecoder::new(compressed.as_slice())
+            .take(u64::try_from(size).unwrap_or(u64::MAX).saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(|err| self.error(format!("Invalid compressed data: {err}")))?;
+        if data.len() != size {
+            return Err(self.error("Binary hunk size mismatch"));
+        }
+        match kind {
+            "literal" => Ok(BinaryHunk::Literal(data)),
+            "delta" => Ok(BinaryHunk::Delta(data)),
+            _ => Err(self.error(format!("Unknown binary hunk type {kind}"))),
+        }
+    }
+}
+
+/// Parses `@@ -l,s +l,s @@` into the starts and lengths of both sides.
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize, usize)> {
+    fn parse_range(range: &str) -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    }
+    let rest = header.strip_prefix("@@ -")?;
+    let (old, rest) = rest.split_once(" +")?;
+    let (new, _) = rest.split_once(" @@")?;
+    let (old_start, old_len) = parse_range(old)?;
+    let (new_start, new_len) = parse_range(new)?;
+    Some((old_start, old_len, new_start, new_len))
+}
+
+/// Strips the `a/` or `b/` prefix of a path in a Git-style diff.
+fn strip_path_prefix(path: &str) -> &str {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// Splits a C-style quoted string from the start of `text`. Returns the
+/// unquoted string and the rest of `text`.
+fn split_quoted(text: &str) -> Option<(String, &str)> {
+    let mut bytes = vec![];
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = &text[1 + i + 1..];
+                return Some((String::from_utf8(bytes).ok()?, rest));
+            }
+            '\\' => {
+                let (_, c) = chars.next()?;
+                match c {
+                    'a' => bytes.push(0x07),
+                    'b' => bytes.push(0x08),
+                    't' => bytes.push(b'\t'),
+                    'n' => bytes.push(b'\n'),
+                    'v' => bytes.push(0x0b),
+                    'f' => bytes.push(0x0c),
+                    'r' => bytes.push(b'\r'),
+                    '0'..='3' => {
+                        let mut value = c.to_digit(8)?;
+                        for _ in 0..2 {
+                            let (_, c) = chars.next()?;
+                            value = value * 8 + c.to_digit(8)?;
+                        }
+                        bytes.push(u8::try_from(value).ok()?);
+                    }
+                    _ => bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                }
+            }
+            _ => bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    None
+}
+
+/// Decodes a line of `GIT binary patch` data. The first character encodes
+/// the number of decoded bytes.
+fn decode_base85_line(line: &[u8]) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] =
+        b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+    let (&len_char, encoded) = line.split_first()?;
+    let len = match len_char {
+        b'A'..=b'Z' => usize::from(len_char - b'A') + 1,
+        b'a'..=b'z' => usize::from(len_char - b'a') + 27,
+        _ => return None,
+    };
+    if encoded.len() != len.div_ceil(4) * 5 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(len);
+    for chunk in encoded.chunks(5) {
+        let mut value: u32 = 0;
+        for c in chunk {
+            let digit = ALPHABET.iter().position(|a| a == c)?;
+            value = value.checked_mul(85)?.checked_add(digit as u32)?;
+        }
+        decoded.extend(value.to_be_bytes());
+    }
+    decoded.truncate(len);
+    Some(decoded)
+}
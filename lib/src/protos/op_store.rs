@@ -96,6 +96,22 @@ pub struct Tag {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BisectState {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub good_commit_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub bad_commit_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub skipped_commit_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoteConfig {
+    #[prost(string, tag = "1")]
+    pub url: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct View {
     #[prost(bytes = "vec", repeated, tag = "1")]
     pub head_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
@@ -103,10 +119,8 @@ pub struct View {
     #[prost(bytes = "vec", tag = "2")]
     pub wc_commit_id: ::prost::alloc::vec::Vec<u8>,
     #[prost(map = "string, bytes", tag = "8")]
-    pub wc_commit_ids: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::vec::Vec<u8>,
-    >,
+    pub wc_commit_ids:
+        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::vec::Vec<u8>>,
     #[prost(message, repeated, tag = "5")]
     pub bookmarks: ::prost::alloc::vec::Vec<Bookmark>,
     #[prost(message, repeated, tag = "6")]
@@ -122,6 +136,16 @@ pub struct View {
     pub git_head_legacy: ::prost::alloc::vec::Vec<u8>,
     #[prost(message, optional, tag = "9")]
     pub git_head: ::core::option::Option<RefTarget>,
+    /// Auto-generated names of visible heads that no ref points to.
+    #[prost(map = "string, bytes", tag = "11")]
+    pub anonymous_heads:
+        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::vec::Vec<u8>>,
+    /// Commits marked during an ongoing bisection.
+    #[prost(message, optional, tag = "12")]
+    pub bisect_state: ::core::option::Option<BisectState>,
+    /// Remotes configured in the repo, independently of the backend.
+    #[prost(map = "string, message", tag = "13")]
+    pub remotes: ::std::collections::HashMap<::prost::alloc::string::String, RemoteConfig>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -158,10 +182,72 @@ pub struct OperationMetadata {
     #[prost(bool, tag = "7")]
     pub is_snapshot: bool,
     #[prost(map = "string, string", tag = "6")]
-    pub tags: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
+    pub tags:
+        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    /// Tree the working copy was snapshotted to, if any. Empty if the working
+    /// copy wasn't snapshotted.
+    #[prost(bytes = "vec", repeated, tag = "8")]
+    pub working_copy_tree_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bool, tag = "9")]
+    pub includes_snapshot: bool,
+    /// Set if a rebase was stopped by a conflict in this operation.
+    #[prost(message, optional, tag = "10")]
+    pub stopped_rebase: ::core::option::Option<StoppedRebase>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StoppedRebase {
+    #[prost(bytes = "vec", tag = "1")]
+    pub conflicted_commit_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, repeated, tag = "2")]
+    pub rewrites: ::prost::alloc::vec::Vec<stopped_rebase::Rewrite>,
+}
+/// Nested message and enum types in `StoppedRebase`.
+pub mod stopped_rebase {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Rewrite {
+        #[prost(bytes = "vec", tag = "1")]
+        pub old_commit_id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(enumeration = "rewrite::Kind", tag = "2")]
+        pub kind: i32,
+        #[prost(bytes = "vec", repeated, tag = "3")]
+        pub new_commit_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    }
+    /// Nested message and enum types in `Rewrite`.
+    pub mod rewrite {
+        #[derive(
+            Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration,
+        )]
+        #[repr(i32)]
+        pub enum Kind {
+            Rewritten = 0,
+            Divergent = 1,
+            Abandoned = 2,
+        }
+        impl Kind {
+            /// String value of the enum field names used in the ProtoBuf definition.
+            ///
+            /// The values are not transformed in any way and thus are considered stable
+            /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+            pub fn as_str_name(&self) -> &'static str {
+                match self {
+                    Kind::Rewritten => "Rewritten",
+                    Kind::Divergent => "Divergent",
+                    Kind::Abandoned => "Abandoned",
+                }
+            }
+            /// Creates an enum from field names used in the ProtoBuf definition.
+            pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+                match value {
+                    "Rewritten" => Some(Self::Rewritten),
+                    "Divergent" => Some(Self::Divergent),
+                    "Abandoned" => Some(Self::Abandoned),
+                    _ => None,
+                }
+            }
+        }
+    }
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -21,6 +21,9 @@ pub struct FileState {
     pub conflict_id: ::prost::alloc::vec::Vec<u8>,
     #[prost(message, optional, tag = "5")]
     pub materialized_conflict_data: ::core::option::Option<MaterializedConflictData>,
+    /// Inode number of the file, or 0 if unknown
+    #[prost(uint64, tag = "6")]
+    pub inode: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -38,7 +41,24 @@ pub struct SparsePatterns {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackingPatterns {
+    /// Path prefixes of new files to track
+    #[prost(string, repeated, tag = "1")]
+    pub tracked: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Path prefixes of new files to leave untracked
+    #[prost(string, repeated, tag = "2")]
+    pub untracked: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Field numbers must never be reused, so that files written by other versions
+/// can still be decoded. Changes that old readers can't safely ignore must bump
+/// the format_version.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TreeState {
+    /// Version of the file format. 0 for files written before the version was
+    /// recorded.
+    #[prost(uint32, tag = "8")]
+    pub format_version: u32,
     #[prost(bytes = "vec", tag = "1")]
     pub legacy_tree_id: ::prost::alloc::vec::Vec<u8>,
     /// Alternating positive and negative terms if there's a conflict, otherwise a
@@ -53,6 +73,8 @@ pub struct TreeState {
     pub sparse_patterns: ::core::option::Option<SparsePatterns>,
     #[prost(message, optional, tag = "4")]
     pub watchman_clock: ::core::option::Option<WatchmanClock>,
+    #[prost(message, optional, tag = "7")]
+    pub tracking_patterns: ::core::option::Option<TrackingPatterns>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -74,6 +96,10 @@ pub mod watchman_clock {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Checkout {
+    /// Version of the file format. 0 for files written before the version was
+    /// recorded.
+    #[prost(uint32, tag = "4")]
+    pub format_version: u32,
     /// The operation at which the working copy was updated.
     #[prost(bytes = "vec", tag = "2")]
     pub operation_id: ::prost::alloc::vec::Vec<u8>,
@@ -84,6 +110,21 @@ pub struct Checkout {
     #[prost(string, tag = "3")]
     pub workspace_id: ::prost::alloc::string::String,
 }
+/// Written before the working copy is updated to another tree, and removed when
+/// the updated tree state is saved. If it's still present, the update was
+/// interrupted and the files in the working copy may be partially updated.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CheckoutJournal {
+    /// The tree the working copy was being updated to. Alternating positive and
+    /// negative terms if there's a conflict, otherwise a single (positive) value
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub new_tree_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// The sparse patterns the working copy was being updated to, if they were
+    /// being changed. The tree is then the current tree.
+    #[prost(message, optional, tag = "2")]
+    pub new_sparse_patterns: ::core::option::Option<SparsePatterns>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum FileType {
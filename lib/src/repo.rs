@@ -15,12 +15,17 @@
 #![allow(missing_docs)]
 
 use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fs;
+use std::iter;
+use std::mem;
 use std::path::Path;
+use std::path::PathBuf;
 use std::slice;
 use std::sync::Arc;
 
@@ -48,12 +53,15 @@ use crate::default_index::DefaultMutableIndex;
 use crate::default_submodule_store::DefaultSubmoduleStore;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
+use crate::fsck;
+use crate::fsck::VerifyReport;
 use crate::index::ChangeIdIndex;
 use crate::index::Index;
 use crate::index::IndexReadError;
 use crate::index::IndexStore;
 use crate::index::MutableIndex;
 use crate::index::ReadonlyIndex;
+use crate::local_backend;
 use crate::local_backend::LocalBackend;
 use crate::merge::MergeBuilder;
 use crate::object_id::HexPrefix;
@@ -64,28 +72,32 @@ use crate::op_heads_store::OpHeadResolutionError;
 use crate::op_heads_store::OpHeadsStore;
 use crate::op_heads_store::OpHeadsStoreError;
 use crate::op_store;
+use crate::op_store::BisectState;
 use crate::op_store::OpStore;
 use crate::op_store::OpStoreError;
 use crate::op_store::OpStoreResult;
 use crate::op_store::OperationId;
 use crate::op_store::RefTarget;
+use crate::op_store::RemoteConfig;
 use crate::op_store::RemoteRef;
 use crate::op_store::RemoteRefState;
 use crate::op_store::RootOperationData;
 use crate::op_store::WorkspaceId;
 use crate::operation::Operation;
-use crate::refs::diff_named_ref_targets;
-use crate::refs::diff_named_remote_refs;
 use crate::refs::merge_ref_targets;
 use crate::refs::merge_remote_refs;
 use crate::refs::RemoteRefSymbol;
+use crate::resolution_cache::may_resolve_conflicts;
+use crate::resolution_cache::ResolutionCache;
 use crate::revset;
 use crate::revset::RevsetExpression;
 use crate::revset::RevsetIteratorExt;
 use crate::rewrite::merge_commit_trees;
-use crate::rewrite::rebase_commit_with_options;
 use crate::rewrite::CommitRewriter;
+use crate::rewrite::ConflictBehaviour;
+use crate::rewrite::RebaseError;
 use crate::rewrite::RebaseOptions;
+use crate::rewrite::RebaseState;
 use crate::rewrite::RebasedCommit;
 use crate::rewrite::RewriteRefsOptions;
 use crate::settings::UserSettings;
@@ -96,8 +108,10 @@ use crate::simple_op_store::SimpleOpStore;
 use crate::store::Store;
 use crate::submodule_store::SubmoduleStore;
 use crate::transaction::Transaction;
+use crate::view;
 use crate::view::RenameWorkspaceError;
 use crate::view::View;
+use crate::view::ANONYMOUS_HEAD_PREFIX;
 
 pub trait Repo {
     /// Base repository that contains all committed data. Returns `self` if this
@@ -114,6 +128,18 @@ pub trait Repo {
 
     fn submodule_store(&self) -> &Arc<dyn SubmoduleStore>;
 
+    /// Returns the best common ancestors of all of the `commit_ids`. A "best
+    /// common ancestor" has no descendants that are also common ancestors.
+    fn common_ancestors(&self, commit_ids: &[CommitId]) -> Vec<CommitId> {
+        let Some((first, rest)) = commit_ids.split_first() else {
+            return vec![];
+        };
+        let index = self.index();
+        rest.iter().fold(vec![first.clone()], |ancestor_ids, id| {
+            index.common_ancestors(&ancestor_ids, slice::from_ref(id))
+        })
+    }
+
     fn resolve_change_id(&self, change_id: &ChangeId) -> Option<Vec<CommitId>> {
         // Replace this if we added more efficient lookup method.
         let prefix = HexPrefix::from_bytes(change_id.as_bytes());
@@ -156,19 +182,82 @@ pub enum RepoInitError {
     Path(#[from] PathError),
 }
 
+/// Error from [`ReadonlyRepo::load_bare()`].
+#[derive(Error, Debug)]
+pub enum BareRepoLoadError {
+    #[error("There is no jj repo in {}", .0.display())]
+    NoRepoHere(PathBuf),
+    #[error(transparent)]
+    StoreLoad(#[from] StoreLoadError),
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+}
+
 impl ReadonlyRepo {
     pub fn default_op_store_initializer() -> &'static OpStoreInitializer<'static> {
-        &|_settings, store_path, root_data| {
-            Ok(Box::new(SimpleOpStore::init(store_path, root_data)?))
+        &|settings, store_path, root_data| {
+            Ok(Box::new(
+                SimpleOpStore::init(store_path, root_data)?
+                    .with_lock_manager(settings.lock_manager()),
+            ))
         }
     }
 
     pub fn default_op_heads_store_initializer() -> &'static OpHeadsStoreInitializer<'static> {
-        &|_settings, store_path| Ok(Box::new(SimpleOpHeadsStore::init(store_path)?))
+        &|settings, store_path| {
+            Ok(Box::new(
+                SimpleOpHeadsStore::init(store_path)?.with_lock_manager(settings.lock_manager()),
+            ))
+        }
+    }
+
+    /// Initializer for an op store which keeps operations and views in JSON
+    /// files.
+    #[cfg(feature = "json-op-store")]
+    pub fn json_op_store_initializer() -> &'static OpStoreInitializer<'static> {
+        &|settings, store_path, root_data| {
+            Ok(Box::new(
+                SimpleOpStore::init_with_encoding(
+                    store_path,
+                    root_data,
+                    Box::new(crate::op_store_json::JsonOpStoreEncoding),
+                )?
+                .with_lock_manager(settings.lock_manager()),
+            ))
+        }
+    }
+
+    /// Initializer for an op store backed by an SQLite database.
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite_op_store_initializer() -> &'static OpStoreInitializer<'static> {
+        &|settings, store_path, root_data| {
+            Ok(Box::new(crate::sqlite_op_store::SqliteOpStore::init(
+                store_path,
+                root_data,
+                &settings.lock_manager(),
+            )?))
+        }
+    }
+
+    /// Initializer for an op heads store backed by an SQLite database.
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite_op_heads_store_initializer() -> &'static OpHeadsStoreInitializer<'static> {
+        &|settings, store_path| {
+            Ok(Box::new(
+                crate::sqlite_op_heads_store::SqliteOpHeadsStore::init(
+                    store_path,
+                    settings.lock_manager(),
+                )?,
+            ))
+        }
     }
 
     pub fn default_index_store_initializer() -> &'static IndexStoreInitializer<'static> {
-        &|_settings, store_path| Ok(Box::new(DefaultIndexStore::init(store_path)?))
+        &|settings, store_path| {
+            Ok(Box::new(
+                DefaultIndexStore::init(store_path)?.with_lock_manager(settings.lock_manager()),
+            ))
+        }
     }
 
     pub fn default_submodule_store_initializer() -> &'static SubmoduleStoreInitializer<'static> {
@@ -235,6 +324,9 @@ impl ReadonlyRepo {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_cache: Some(Arc::new(ResolutionCache::load(
+                &repo_path.join("resolution_cache"),
+            ))),
         };
 
         let root_operation = loader.root_operation();
@@ -255,6 +347,24 @@ impl ReadonlyRepo {
         }))
     }
 
+    /// Loads the repo at `repo_path` at its head operation, without a
+    /// workspace.
+    ///
+    /// `repo_path` is the repo directory, which is `.jj/repo` in the default
+    /// workspace. The working-copy state isn't read, so this also works for
+    /// repos stored without one, e.g. on a server.
+    pub fn load_bare(
+        settings: &UserSettings,
+        repo_path: &Path,
+        store_factories: &StoreFactories,
+    ) -> Result<Arc<ReadonlyRepo>, BareRepoLoadError> {
+        if !repo_path.join("store").is_dir() {
+            return Err(BareRepoLoadError::NoRepoHere(repo_path.to_owned()));
+        }
+        let loader = RepoLoader::init_from_file_system(settings, repo_path, store_factories)?;
+        Ok(loader.load_at_head()?)
+    }
+
     pub fn loader(&self) -> &RepoLoader {
         &self.loader
     }
@@ -271,11 +381,20 @@ impl ReadonlyRepo {
         &self.view
     }
 
+    /// Reads all operations reachable from this repo's operation, and all
+    /// commits, trees, and files reachable from their views, reporting the
+    /// objects which are missing or can't be decoded.
+    pub fn verify(&self) -> VerifyReport {
+        fsck::verify(self)
+    }
+
     pub fn readonly_index(&self) -> &dyn ReadonlyIndex {
         self.index.as_ref()
     }
 
-    fn change_id_index(&self) -> &dyn ChangeIdIndex {
+    /// Returns the change id index of the visible commits, which is built
+    /// lazily and cached for the lifetime of this repo.
+    pub fn change_id_index(&self) -> &dyn ChangeIdIndex {
         self.change_id_index
             .get_or_init(|| {
                 self.readonly_index()
@@ -296,6 +415,10 @@ impl ReadonlyRepo {
         self.loader.settings()
     }
 
+    pub fn resolution_cache(&self) -> Option<&Arc<ResolutionCache>> {
+        self.loader.resolution_cache()
+    }
+
     pub fn start_transaction(self: &Arc<ReadonlyRepo>) -> Transaction {
         let mut_repo = MutableRepo::new(self.clone(), self.readonly_index(), &self.view);
         Transaction::new(mut_repo, self.settings())
@@ -398,7 +521,15 @@ impl Default for StoreFactories {
         // Backends
         factories.add_backend(
             LocalBackend::name(),
-            Box::new(|_settings, store_path| Ok(Box::new(LocalBackend::load(store_path)))),
+            Box::new(|settings, store_path| {
+                let chunk_size = local_backend::chunk_size_from_settings(settings)
+                    .map_err(|err| BackendLoadError(err.into()))?;
+                Ok(Box::new(
+                    LocalBackend::load(store_path)
+                        .with_chunk_size(chunk_size)
+                        .with_lock_manager(settings.lock_manager()),
+                ))
+            }),
         );
         #[cfg(feature = "git")]
         factories.add_backend(
@@ -422,21 +553,69 @@ impl Default for StoreFactories {
         // OpStores
         factories.add_op_store(
             SimpleOpStore::name(),
-            Box::new(|_settings, store_path, root_data| {
-                Ok(Box::new(SimpleOpStore::load(store_path, root_data)))
+            Box::new(|settings, store_path, root_data| {
+                Ok(Box::new(
+                    SimpleOpStore::load(store_path, root_data)
+                        .with_lock_manager(settings.lock_manager()),
+                ))
+            }),
+        );
+        #[cfg(feature = "json-op-store")]
+        factories.add_op_store(
+            crate::op_store_json::JsonOpStoreEncoding::store_name(),
+            Box::new(|settings, store_path, root_data| {
+                Ok(Box::new(
+                    SimpleOpStore::load_with_encoding(
+                        store_path,
+                        root_data,
+                        Box::new(crate::op_store_json::JsonOpStoreEncoding),
+                    )
+                    .with_lock_manager(settings.lock_manager()),
+                ))
+            }),
+        );
+        #[cfg(feature = "sqlite")]
+        factories.add_op_store(
+            crate::sqlite_op_store::SqliteOpStore::name(),
+            Box::new(|settings, store_path, root_data| {
+                Ok(Box::new(crate::sqlite_op_store::SqliteOpStore::load(
+                    store_path,
+                    root_data,
+                    &settings.lock_manager(),
+                )?))
             }),
         );
 
         // OpHeadsStores
         factories.add_op_heads_store(
             SimpleOpHeadsStore::name(),
-            Box::new(|_settings, store_path| Ok(Box::new(SimpleOpHeadsStore::load(store_path)))),
+            Box::new(|settings, store_path| {
+                Ok(Box::new(
+                    SimpleOpHeadsStore::load(store_path).with_lock_manager(settings.lock_manager()),
+                ))
+            }),
+        );
+        #[cfg(feature = "sqlite")]
+        factories.add_op_heads_store(
+            crate::sqlite_op_heads_store::SqliteOpHeadsStore::name(),
+            Box::new(|settings, store_path| {
+                Ok(Box::new(
+                    crate::sqlite_op_heads_store::SqliteOpHeadsStore::load(
+                        store_path,
+                        settings.lock_manager(),
+                    )?,
+                ))
+            }),
         );
 
         // Index
         factories.add_index_store(
             DefaultIndexStore::name(),
-            Box::new(|_settings, store_path| Ok(Box::new(DefaultIndexStore::load(store_path)))),
+            Box::new(|settings, store_path| {
+                Ok(Box::new(
+                    DefaultIndexStore::load(store_path).with_lock_manager(settings.lock_manager()),
+                ))
+            }),
         );
 
         // SubmoduleStores
@@ -622,6 +801,8 @@ pub enum RepoLoaderError {
     OpHeadsStoreError(#[from] OpHeadsStoreError),
     #[error(transparent)]
     OpStore(#[from] OpStoreError),
+    #[error(transparent)]
+    Rebase(#[from] RebaseError),
 }
 
 /// Helps create `ReadonlyRepoo` instances of a repo at the head operation or at
@@ -634,6 +815,7 @@ pub struct RepoLoader {
     op_heads_store: Arc<dyn OpHeadsStore>,
     index_store: Arc<dyn IndexStore>,
     submodule_store: Arc<dyn SubmoduleStore>,
+    resolution_cache: Option<Arc<ResolutionCache>>,
 }
 
 impl RepoLoader {
@@ -652,6 +834,7 @@ impl RepoLoader {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_cache: None,
         }
     }
 
@@ -689,6 +872,9 @@ impl RepoLoader {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_cache: Some(Arc::new(ResolutionCache::load(
+                &repo_path.join("resolution_cache"),
+            ))),
         })
     }
 
@@ -716,6 +902,11 @@ impl RepoLoader {
         &self.submodule_store
     }
 
+    /// The cache of conflict resolutions, if the repo is stored on disk.
+    pub fn resolution_cache(&self) -> Option<&Arc<ResolutionCache>> {
+        self.resolution_cache.as_ref()
+    }
+
     pub fn load_at_head(&self) -> Result<Arc<ReadonlyRepo>, RepoLoaderError> {
         let op = op_heads_store::resolve_op_heads(
             self.op_heads_store.as_ref(),
@@ -786,7 +977,7 @@ impl RepoLoader {
                 || format!("merge {num_operations} operations"),
                 |tx_description| tx_description.to_string(),
             );
-            let merged_repo = tx.write(tx_description).leave_unpublished();
+            let merged_repo = tx.write(tx_description)?.leave_unpublished();
             merged_repo.operation().clone()
         } else {
             base_op
@@ -818,7 +1009,7 @@ impl RepoLoader {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-enum Rewrite {
+pub(crate) enum Rewrite {
     /// The old commit was rewritten as this new commit. Children should be
     /// rebased onto the new commit.
     Rewritten(CommitId),
@@ -838,6 +1029,14 @@ impl Rewrite {
             Rewrite::Abandoned(new_parent_ids) => new_parent_ids.as_slice(),
         }
     }
+
+    pub(crate) fn map_ids(&self, f: impl Fn(&CommitId) -> CommitId) -> Self {
+        match self {
+            Rewrite::Rewritten(id) => Rewrite::Rewritten(f(id)),
+            Rewrite::Divergent(ids) => Rewrite::Divergent(ids.iter().map(f).collect()),
+            Rewrite::Abandoned(ids) => Rewrite::Abandoned(ids.iter().map(f).collect()),
+        }
+    }
 }
 
 pub struct MutableRepo {
@@ -853,6 +1052,10 @@ pub struct MutableRepo {
     //   commits. However, if the type is `Abandoned`, a new working-copy commit should be created
     //   on top of all of the new commits instead.
     parent_mapping: HashMap<CommitId, Rewrite>,
+    // Pairs of (old, new) commits where the new commit resolved conflicts of the
+    // old one. They're recorded in the resolution cache once the transaction is
+    // published.
+    resolved_conflicts: Vec<(Commit, Commit)>,
 }
 
 impl MutableRepo {
@@ -868,6 +1071,7 @@ impl MutableRepo {
             index: mut_index,
             view: DirtyCell::with_clean(mut_view),
             parent_mapping: Default::default(),
+            resolved_conflicts: vec![],
         }
     }
 
@@ -888,9 +1092,26 @@ impl MutableRepo {
         !(self.parent_mapping.is_empty() && self.view() == &self.base_repo.view)
     }
 
-    pub(crate) fn consume(self) -> (Box<dyn MutableIndex>, View) {
+    /// Remembers that `new_commit` may have resolved conflicts of
+    /// `old_commit`, to be recorded when the transaction is published.
+    pub(crate) fn add_resolved_conflicts(&mut self, old_commit: &Commit, new_commit: &Commit) {
+        if may_resolve_conflicts(&self.base_repo, old_commit, new_commit) {
+            self.resolved_conflicts
+                .push((old_commit.clone(), new_commit.clone()));
+        }
+    }
+
+    pub(crate) fn take_resolved_conflicts(&mut self) -> Vec<(Commit, Commit)> {
+        mem::take(&mut self.resolved_conflicts)
+    }
+
+    pub(crate) fn consume(self) -> BackendResult<(Box<dyn MutableIndex>, View)> {
         self.view.ensure_clean(|v| self.enforce_view_invariants(v));
-        (self.index, self.view.into_inner())
+        let mut view = self.view.into_inner();
+        let old_names = self.base_repo.view().anonymous_heads();
+        let names = anonymous_head_names(self.base_repo.store(), view.store_view(), old_names)?;
+        view.store_view_mut().anonymous_heads = names;
+        Ok((self.index, view))
     }
 
     /// Returns a [`CommitBuilder`] to write new commit to the repo.
@@ -973,16 +1194,63 @@ impl MutableRepo {
         );
     }
 
+    /// Records a rewrite taken from a stopped rebase.
+    pub(crate) fn restore_rewrite(&mut self, old_id: CommitId, rewrite: Rewrite) {
+        assert_ne!(old_id, *self.store().root_commit_id());
+        self.parent_mapping.insert(old_id, rewrite);
+    }
+
+    /// The rewrites recorded so far, keyed by the old commit id.
+    pub(crate) fn rewrites(&self) -> &HashMap<CommitId, Rewrite> {
+        &self.parent_mapping
+    }
+
     pub fn has_rewrites(&self) -> bool {
         !self.parent_mapping.is_empty()
     }
 
+    /// Checks that no commit is transitively rewritten into itself.
+    ///
+    /// Returns the commits forming the first cycle found in `parent_mapping`,
+    /// which would otherwise make the descendants unrebasable.
+    pub fn check_rewrite_cycles(&self) -> Result<(), RewriteCycleError> {
+        let mut finished: HashSet<&CommitId> = HashSet::new();
+        // Visit in a stable order so the reported cycle is deterministic.
+        for start_id in self.parent_mapping.keys().sorted() {
+            if finished.contains(start_id) {
+                continue;
+            }
+            // Path of rewritten commits being visited, and the index of the
+            // next replacement to follow from each of them
+            let mut path: Vec<(&CommitId, usize)> = vec![(start_id, 0)];
+            while let Some((id, next_index)) = path.last_mut() {
+                let id: &CommitId = id;
+                let replacements = self.parent_mapping[id].new_parent_ids();
+                let Some(new_id) = replacements.get(*next_index) else {
+                    finished.insert(id);
+                    path.pop();
+                    continue;
+                };
+                *next_index += 1;
+                if let Some(pos) = path.iter().position(|&(id, _)| id == new_id) {
+                    let commit_ids = path[pos..].iter().map(|&(id, _)| id.clone()).collect();
+                    return Err(RewriteCycleError { commit_ids });
+                }
+                if self.parent_mapping.contains_key(new_id) && !finished.contains(new_id) {
+                    path.push((new_id, 0));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Calculates new parents for a commit that's currently based on the given
     /// parents. It does that by considering how previous commits have been
     /// rewritten and abandoned.
     ///
     /// If `parent_mapping` contains cycles, this function may either panic or
-    /// drop parents that caused cycles.
+    /// drop parents that caused cycles. Use [`Self::check_rewrite_cycles()`]
+    /// to detect them upfront.
     pub fn new_parents(&self, old_ids: &[CommitId]) -> Vec<CommitId> {
         self.rewritten_ids_with(old_ids, |rewrite| !matches!(rewrite, Rewrite::Divergent(_)))
     }
@@ -1065,13 +1333,14 @@ impl MutableRepo {
     pub fn update_rewritten_references(
         &mut self,
         options: &RewriteRefsOptions,
-    ) -> BackendResult<()> {
+    ) -> Result<(), RebaseError> {
         self.update_all_references(options)?;
         self.update_heads();
         Ok(())
     }
 
-    fn update_all_references(&mut self, options: &RewriteRefsOptions) -> BackendResult<()> {
+    fn update_all_references(&mut self, options: &RewriteRefsOptions) -> Result<(), RebaseError> {
+        self.check_rewrite_cycles()?;
         let rewrite_mapping = self.resolve_rewrite_mapping_with(|_| true);
         self.update_local_bookmarks(&rewrite_mapping, options);
         self.update_wc_commits(&rewrite_mapping)?;
@@ -1242,7 +1511,7 @@ impl MutableRepo {
         &mut self,
         roots: Vec<CommitId>,
         callback: impl FnMut(CommitRewriter) -> BackendResult<()>,
-    ) -> BackendResult<()> {
+    ) -> Result<(), RebaseError> {
         let options = RewriteRefsOptions::default();
         self.transform_descendants_with_options(roots, &options, callback)
     }
@@ -1255,7 +1524,8 @@ impl MutableRepo {
         roots: Vec<CommitId>,
         options: &RewriteRefsOptions,
         mut callback: impl FnMut(CommitRewriter) -> BackendResult<()>,
-    ) -> BackendResult<()> {
+    ) -> Result<(), RebaseError> {
+        self.check_rewrite_cycles()?;
         let mut to_visit = self.find_descendants_to_rebase(roots)?;
         while let Some(old_commit) = to_visit.pop() {
             let new_parent_ids = self.new_parents(old_commit.parent_ids());
@@ -1288,24 +1558,63 @@ impl MutableRepo {
     /// parent will inherit the descendants and the bookmarks of the abandoned
     /// commit.
     ///
+    /// If rebasing a commit would introduce a conflict, `options.conflicts`
+    /// decides whether the conflict is recorded, or the commit is skipped, or
+    /// the rebase stops. A stopped rebase returns the remaining work as a
+    /// [`RebaseState`], and the mapping of rewritten commits is cleared as if
+    /// the rebase had completed.
+    ///
     /// The `progress` callback will be invoked for each rebase operation with
     /// `(old_commit, rebased_commit)` as arguments.
     pub fn rebase_descendants_with_options(
         &mut self,
         options: &RebaseOptions,
         mut progress: impl FnMut(Commit, RebasedCommit),
-    ) -> BackendResult<()> {
-        let roots = self.parent_mapping.keys().cloned().collect();
+    ) -> Result<Option<RebaseState>, RebaseError> {
+        self.check_rewrite_cycles()?;
+        let roots: Vec<_> = self.parent_mapping.keys().cloned().collect();
+        let included_ids = options.descendants_filter.as_deref();
+        let mut conflicted_commit_id = None;
+        let mut cancelled = None;
         self.transform_descendants_with_options(roots, &options.rewrite_refs, |rewriter| {
+            if conflicted_commit_id.is_some() || cancelled.is_some() {
+                return Ok(());
+            }
+            if let Err(err) = options.cancellation.check() {
+                cancelled = Some(err);
+                return Ok(());
+            }
+            if included_ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(rewriter.old_commit().id()))
+            {
+                // Leave the commit on top of the old parents
+                return Ok(());
+            }
             if rewriter.parents_changed() {
                 let old_commit = rewriter.old_commit().clone();
-                let rebased_commit = rebase_commit_with_options(rewriter, options)?;
+                let rebased_commit = rewriter.rebase_with_options(options)?;
+                if matches!(rebased_commit, RebasedCommit::Skipped)
+                    && options.conflicts == ConflictBehaviour::Stop
+                {
+                    conflicted_commit_id = Some(old_commit.id().clone());
+                }
                 progress(old_commit, rebased_commit);
             }
             Ok(())
         })?;
-        self.parent_mapping.clear();
-        Ok(())
+        if let Some(err) = cancelled {
+            return Err(err.into());
+        }
+        let rewrites = mem::take(&mut self.parent_mapping);
+        let state = conflicted_commit_id.map(|commit_id| {
+            let rewrites = rewrites
+                .into_iter()
+                .sorted_by(|(id1, _), (id2, _)| id1.cmp(id2))
+                .collect();
+            RebaseState::new(commit_id, rewrites)
+        });
+        Ok(state)
     }
 
     /// Rebase descendants of the rewritten commits.
@@ -1317,7 +1626,7 @@ impl MutableRepo {
     /// All rebased descendant commits will be preserved even if they were
     /// emptied following the rebase operation. To customize the rebase
     /// behavior, use [`MutableRepo::rebase_descendants_with_options`].
-    pub fn rebase_descendants(&mut self) -> BackendResult<usize> {
+    pub fn rebase_descendants(&mut self) -> Result<usize, RebaseError> {
         let options = RebaseOptions::default();
         let mut num_rebased = 0;
         self.rebase_descendants_with_options(&options, |_old_commit, _rebased_commit| {
@@ -1332,7 +1641,7 @@ impl MutableRepo {
     /// be recursively reparented onto the new version of their parents.
     /// The content of those descendants will remain untouched.
     /// Returns the number of reparented descendants.
-    pub fn reparent_descendants(&mut self) -> BackendResult<usize> {
+    pub fn reparent_descendants(&mut self) -> Result<usize, RebaseError> {
         let roots = self.parent_mapping.keys().cloned().collect_vec();
         let mut num_reparented = 0;
         self.transform_descendants(roots, |rewriter| {
@@ -1379,9 +1688,26 @@ impl MutableRepo {
         workspace_id: WorkspaceId,
         commit: &Commit,
     ) -> Result<Commit, CheckOutCommitError> {
-        let wc_commit = self
-            .new_commit(vec![commit.id().clone()], commit.tree_id().clone())
-            .write()?;
+        self.check_out_new_commit(
+            workspace_id,
+            vec![commit.id().clone()],
+            commit.tree_id().clone(),
+        )
+    }
+
+    /// Creates a new commit with the given `parents` and `tree_id`, and makes
+    /// it the working-copy commit of the workspace.
+    ///
+    /// The previous working-copy commit is abandoned if it is discardable, as
+    /// in [`MutableRepo::edit()`]. Its descendants are rebased by the next
+    /// call to [`MutableRepo::rebase_descendants()`].
+    pub fn check_out_new_commit(
+        &mut self,
+        workspace_id: WorkspaceId,
+        parents: Vec<CommitId>,
+        tree_id: MergedTreeId,
+    ) -> Result<Commit, CheckOutCommitError> {
+        let wc_commit = self.new_commit(parents, tree_id).write()?;
         self.edit(workspace_id, &wc_commit)?;
         Ok(wc_commit)
     }
@@ -1588,6 +1914,10 @@ impl MutableRepo {
         self.set_remote_bookmark(symbol, remote_ref);
     }
 
+    pub fn set_remote_config(&mut self, remote_name: &str, config: RemoteConfig) {
+        self.view_mut().set_remote_config(remote_name, config);
+    }
+
     pub fn remove_remote(&mut self, remote_name: &str) {
         self.view_mut().remove_remote(remote_name);
     }
@@ -1636,6 +1966,10 @@ impl MutableRepo {
         self.view_mut().set_git_head_target(target);
     }
 
+    pub fn set_bisect_state(&mut self, state: BisectState) {
+        self.view_mut().set_bisect_state(state);
+    }
+
     pub fn set_view(&mut self, data: op_store::View) {
         self.view_mut().set_view(data);
         self.view.mark_dirty();
@@ -1664,16 +1998,13 @@ impl MutableRepo {
     }
 
     fn merge_view(&mut self, base: &View, other: &View) -> BackendResult<()> {
-        // TODO: Use `diff_named_commit_ids` to simplify this.
+        let changes = view::diff(base, other);
         // Merge working-copy commits. If there's a conflict, we keep the self side.
-        for (workspace_id, base_wc_commit) in base.wc_commit_ids() {
-            let self_wc_commit = self.view().get_wc_commit_id(workspace_id);
-            let other_wc_commit = other.get_wc_commit_id(workspace_id);
-            if other_wc_commit == Some(base_wc_commit) || other_wc_commit == self_wc_commit {
-                // The other side didn't change or both sides changed in the
-                // same way.
-            } else if let Some(other_wc_commit) = other_wc_commit {
-                if self_wc_commit == Some(base_wc_commit) {
+        for (workspace_id, (base_wc_commit, other_wc_commit)) in changes.wc_commits {
+            if let Some(other_wc_commit) = other_wc_commit {
+                // The other side added the workspace or changed the
+                // working-copy commit.
+                if self.view().get_wc_commit_id(workspace_id) == base_wc_commit {
                     self.view_mut()
                         .set_wc_commit(workspace_id.clone(), other_wc_commit.clone());
                 }
@@ -1683,15 +2014,6 @@ impl MutableRepo {
                 self.view_mut().remove_wc_commit(workspace_id);
             }
         }
-        for (workspace_id, other_wc_commit) in other.wc_commit_ids() {
-            if self.view().get_wc_commit_id(workspace_id).is_none()
-                && base.get_wc_commit_id(workspace_id).is_none()
-            {
-                // The other side added the workspace.
-                self.view_mut()
-                    .set_wc_commit(workspace_id.clone(), other_wc_commit.clone());
-            }
-        }
         let base_heads = base.heads().iter().cloned().collect_vec();
         let own_heads = self.view().heads().iter().cloned().collect_vec();
         let other_heads = other.heads().iter().cloned().collect_vec();
@@ -1708,33 +2030,27 @@ impl MutableRepo {
             // No need to remove heads removed by `other` because we already
             // marked them abandoned or rewritten.
         } else {
-            for removed_head in base.heads().difference(other.heads()) {
+            for removed_head in changes.removed_heads {
                 self.view_mut().remove_head(removed_head);
             }
         }
-        for added_head in other.heads().difference(base.heads()) {
+        for added_head in changes.added_heads {
             self.view_mut().add_head(added_head);
         }
 
-        let changed_local_bookmarks =
-            diff_named_ref_targets(base.local_bookmarks(), other.local_bookmarks());
-        for (name, (base_target, other_target)) in changed_local_bookmarks {
+        for (name, (base_target, other_target)) in changes.local_bookmarks {
             self.merge_local_bookmark(name, base_target, other_target);
         }
 
-        let changed_tags = diff_named_ref_targets(base.tags(), other.tags());
-        for (name, (base_target, other_target)) in changed_tags {
+        for (name, (base_target, other_target)) in changes.tags {
             self.merge_tag(name, base_target, other_target);
         }
 
-        let changed_git_refs = diff_named_ref_targets(base.git_refs(), other.git_refs());
-        for (name, (base_target, other_target)) in changed_git_refs {
+        for (name, (base_target, other_target)) in changes.git_refs {
             self.merge_git_ref(name, base_target, other_target);
         }
 
-        let changed_remote_bookmarks =
-            diff_named_remote_refs(base.all_remote_bookmarks(), other.all_remote_bookmarks());
-        for (symbol, (base_ref, other_ref)) in changed_remote_bookmarks {
+        for (symbol, (base_ref, other_ref)) in changes.remote_bookmarks {
             self.merge_remote_bookmark(symbol, base_ref, other_ref);
         }
 
@@ -1746,6 +2062,29 @@ impl MutableRepo {
         );
         self.set_git_head_target(new_git_head_target);
 
+        // If both sides changed the bisection state, we keep the self side.
+        if self.view().bisect_state() == base.bisect_state() {
+            self.set_bisect_state(other.bisect_state().clone());
+        }
+
+        // Merge remote configs. If both sides changed a remote, we keep the self
+        // side.
+        let remote_names: BTreeSet<_> =
+            itertools::chain(base.remote_configs().keys(), other.remote_configs().keys())
+                .cloned()
+                .collect();
+        for name in remote_names {
+            let base_config = base.get_remote_config(&name);
+            let other_config = other.get_remote_config(&name);
+            if other_config == base_config || self.view().get_remote_config(&name) != base_config {
+                continue;
+            }
+            match other_config {
+                Some(config) => self.set_remote_config(&name, config.clone()),
+                None => self.view_mut().remove_remote_config(&name),
+            }
+        }
+
         Ok(())
     }
 
@@ -1812,6 +2151,120 @@ impl MutableRepo {
     }
 }
 
+/// Minimum number of change id digits in the name of an anonymous head.
+const ANONYMOUS_HEAD_MIN_ID_LEN: usize = 8;
+
+/// Assigns names to the visible heads that no bookmark, tag, or working copy
+/// points to.
+///
+/// Names are derived from the change ids, extended as needed to disambiguate
+/// them from the other anonymous heads. A change keeps its previous name as
+/// long as the name stays unambiguous, even if the head is rewritten.
+/// Divergent heads are left unnamed since they can't be told apart by change
+/// id.
+///
+/// Only the commits of heads which weren't named in `old_names`, and of named
+/// heads whose names became ambiguous, are loaded from the store.
+fn anonymous_head_names(
+    store: &Arc<Store>,
+    view: &op_store::View,
+    old_names: &BTreeMap<String, CommitId>,
+) -> BackendResult<BTreeMap<String, CommitId>> {
+    let referenced_ids: HashSet<&CommitId> = itertools::chain!(
+        view.local_bookmarks
+            .values()
+            .flat_map(|target| target.added_ids()),
+        view.tags.values().flat_map(|target| target.added_ids()),
+        view.remote_views
+            .values()
+            .flat_map(|remote_view| remote_view.bookmarks.values())
+            .flat_map(|remote_ref| remote_ref.target.added_ids()),
+        view.wc_commit_ids.values(),
+    )
+    .collect();
+    let old_names_by_id: HashMap<&CommitId, &str> = old_names
+        .iter()
+        .map(|(name, id)| (id, name.as_str()))
+        .collect();
+    // Heads which kept their names, keyed by the change id prefix in the name
+    let mut kept_heads: BTreeMap<&str, &CommitId> = BTreeMap::new();
+    let mut new_heads: HashMap<String, Vec<&CommitId>> = HashMap::new();
+    for id in &view.head_ids {
+        if id == store.root_commit_id() || referenced_ids.contains(id) {
+            continue;
+        }
+        if let Some(name) = old_names_by_id.get(id) {
+            let prefix = name.strip_prefix(ANONYMOUS_HEAD_PREFIX).unwrap_or(name);
+            kept_heads.insert(prefix, id);
+        } else {
+            let commit = store.get_commit(id)?;
+            new_heads
+                .entry(commit.change_id().reverse_hex())
+                .or_default()
+                .push(id);
+        }
+    }
+    if new_heads.is_empty() {
+        return Ok(kept_heads
+            .into_iter()
+            .map(|(prefix, id)| (format!("{ANONYMOUS_HEAD_PREFIX}{prefix}"), id.clone()))
+            .collect());
+    }
+    // A kept name is still unambiguous unless a new head's change id starts
+    // with it. Those heads are named again along with the new ones.
+    let ambiguous_prefixes = kept_heads
+        .keys()
+        .copied()
+        .filter(|prefix| new_heads.keys().any(|hex| hex.starts_with(prefix)))
+        .collect_vec();
+    for prefix in ambiguous_prefixes {
+        let id = kept_heads.remove(prefix).unwrap();
+        let commit = store.get_commit(id)?;
+        new_heads
+            .entry(commit.change_id().reverse_hex())
+            .or_default()
+            .push(id);
+    }
+    // The remaining kept heads are represented by their name prefixes. Since
+    // no new change id starts with any of them, the common prefix length of a
+    // new change id and a kept prefix is the same as with the full change id.
+    let change_hexes = new_heads
+        .keys()
+        .map(String::as_str)
+        .chain(kept_heads.keys().copied())
+        .sorted()
+        .collect_vec();
+    let mut names: BTreeMap<String, CommitId> = kept_heads
+        .iter()
+        .map(|(prefix, id)| (format!("{ANONYMOUS_HEAD_PREFIX}{prefix}"), (*id).clone()))
+        .collect();
+    for (i, change_hex) in change_hexes.iter().enumerate() {
+        let Some([id]) = new_heads.get(*change_hex).map(|ids| &ids[..]) else {
+            continue;
+        };
+        let common_len = |other: &&str| {
+            iter::zip(change_hex.chars(), other.chars())
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        let neighbors = [i.checked_sub(1), Some(i + 1)];
+        let min_len = neighbors
+            .into_iter()
+            .flatten()
+            .filter_map(|j| change_hexes.get(j))
+            .map(|other| common_len(other) + 1)
+            .fold(ANONYMOUS_HEAD_MIN_ID_LEN, usize::max)
+            .min(change_hex.len());
+        // Prefer the previous name of the change if it's still unambiguous.
+        let name = (min_len..=change_hex.len())
+            .map(|len| format!("{ANONYMOUS_HEAD_PREFIX}{}", &change_hex[..len]))
+            .find(|name| old_names.contains_key(name))
+            .unwrap_or_else(|| format!("{ANONYMOUS_HEAD_PREFIX}{}", &change_hex[..min_len]));
+        names.insert(name, (*id).clone());
+    }
+    Ok(names)
+}
+
 impl Repo for MutableRepo {
     fn base_repo(&self) -> &ReadonlyRepo {
         &self.base_repo
@@ -1854,6 +2307,18 @@ impl Repo for MutableRepo {
 #[error("Cannot rewrite the root commit")]
 pub struct RewriteRootCommit;
 
+/// Error from [`MutableRepo::check_rewrite_cycles()`]
+#[derive(Debug, Error)]
+#[error(
+    "Rewritten commits form a cycle: {}",
+    commit_ids.iter().map(|id| id.hex()).join(" -> ")
+)]
+pub struct RewriteCycleError {
+    /// Commits in the cycle, each rewritten into the next one, and the last
+    /// one rewritten into the first one.
+    pub commit_ids: Vec<CommitId>,
+}
+
 /// Error from attempts to edit a commit
 #[derive(Debug, Error)]
 pub enum EditCommitError {
@@ -15,6 +15,7 @@
 #![allow(missing_docs)]
 
 use std::borrow::Borrow;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Debug;
@@ -29,6 +30,8 @@ use itertools::Itertools;
 use ref_cast::ref_cast_custom;
 use ref_cast::RefCastCustom;
 use thiserror::Error;
+use unicode_normalization::is_nfc;
+use unicode_normalization::UnicodeNormalization as _;
 
 use crate::content_hash::ContentHash;
 use crate::file_util;
@@ -276,7 +279,8 @@ impl RepoPathBuf {
     ///
     /// The `cwd` and `base` paths are supposed to be absolute and normalized in
     /// the same manner. The `input` path may be either relative to `cwd` or
-    /// absolute.
+    /// absolute, and may contain `..` components as long as the resolved path
+    /// stays within `base`.
     pub fn parse_fs_path(
         cwd: &Path,
         base: &Path,
@@ -285,7 +289,18 @@ impl RepoPathBuf {
         let input = input.as_ref();
         let abs_input_path = file_util::normalize_path(&cwd.join(input));
         let repo_relative_path = file_util::relative_path(base, &abs_input_path);
-        Self::from_relative_path(repo_relative_path).map_err(|source| FsPathParseError {
+        let escapes_base = matches!(
+            repo_relative_path.components().next(),
+            Some(Component::Prefix(_) | Component::RootDir | Component::ParentDir)
+        );
+        let parsed = if escapes_base {
+            Err(RelativePathParseError::OutsideBase {
+                path: repo_relative_path.into(),
+            })
+        } else {
+            Self::from_relative_path(repo_relative_path)
+        };
+        parsed.map_err(|source| FsPathParseError {
             base: file_util::relative_path(cwd, base).into(),
             input: input.into(),
             source,
@@ -481,6 +496,25 @@ impl PartialOrd for RepoPathBuf {
     }
 }
 
+/// Serializes as the internal (slash-separated) path string.
+impl serde::Serialize for RepoPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+impl serde::Serialize for RepoPathBuf {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        <RepoPath as serde::Serialize>::serialize(self, serializer)
+    }
+}
+
 /// `RepoPath` contained invalid file/directory component such as `..`.
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
 #[error(r#"Invalid repository path "{}""#, path.as_internal_file_string())]
@@ -517,6 +551,8 @@ pub enum RelativePathParseError {
     },
     #[error(r#"Not valid UTF-8 path "{path}""#)]
     InvalidUtf8 { path: Box<Path> },
+    #[error(r#"Path "{path}" points outside of the repo root"#)]
+    OutsideBase { path: Box<Path> },
 }
 
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
@@ -654,6 +690,58 @@ impl RepoPathUiConverter {
     }
 }
 
+/// Policy for treating different spellings of a path as the same file.
+///
+/// Some file systems normalize Unicode file names (e.g. to NFD on macOS) or
+/// compare names case-insensitively. If enabled, a file whose name on disk
+/// differs from the tracked name only by such differences is considered the
+/// same file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PathNormalization {
+    /// Compare names after Unicode NFC normalization, and record new files
+    /// with NFC-normalized names.
+    pub unicode_nfc: bool,
+    /// Compare names case-insensitively.
+    pub case_insensitive: bool,
+}
+
+impl PathNormalization {
+    /// Returns true if no normalization is applied.
+    pub fn is_identity(&self) -> bool {
+        !self.unicode_nfc && !self.case_insensitive
+    }
+
+    /// Returns the name to record for a newly-added file or directory. Case
+    /// is preserved.
+    pub fn normalize_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if self.unicode_nfc && !is_nfc(name) {
+            Cow::Owned(name.nfc().collect())
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+
+    /// Returns the key by which names are compared. Two names referring to the
+    /// same file under this policy have the same key.
+    pub fn comparison_key<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let name = self.normalize_name(name);
+        if self.case_insensitive && name.chars().any(|c| c.to_lowercase().ne([c])) {
+            Cow::Owned(name.to_lowercase())
+        } else {
+            name
+        }
+    }
+
+    /// Returns `path` with each component replaced by its comparison key.
+    pub fn comparison_path<'a>(&self, path: &'a RepoPath) -> Cow<'a, RepoPath> {
+        let key = self.comparison_key(path.as_internal_file_string());
+        match key {
+            Cow::Borrowed(_) => Cow::Borrowed(path),
+            Cow::Owned(value) => Cow::Owned(RepoPathBuf { value }),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::panic;
@@ -989,7 +1077,7 @@ mod tests {
         assert_matches!(
             RepoPathBuf::parse_fs_path(&cwd_path, wc_path, ".."),
             Err(FsPathParseError {
-                source: RelativePathParseError::InvalidComponent { .. },
+                source: RelativePathParseError::OutsideBase { .. },
                 ..
             })
         );
@@ -1011,6 +1099,31 @@ mod tests {
             .as_deref(),
             Ok(RepoPath::root())
         );
+        assert_eq!(
+            RepoPathBuf::parse_fs_path(&cwd_path, wc_path, cwd_path.join("dir/file")).as_deref(),
+            Ok(repo_path("dir/file"))
+        );
+        assert_eq!(
+            RepoPathBuf::parse_fs_path(&cwd_path, wc_path, "dir/../file").as_deref(),
+            Ok(repo_path("file"))
+        );
+        assert_eq!(
+            RepoPathBuf::parse_fs_path(&cwd_path, wc_path, "../out"),
+            Err(FsPathParseError {
+                base: Path::new(".").into(),
+                input: Path::new("../out").into(),
+                source: RelativePathParseError::OutsideBase {
+                    path: Path::new("../out").into(),
+                },
+            })
+        );
+        assert_matches!(
+            RepoPathBuf::parse_fs_path(&cwd_path, wc_path, temp_dir.path().join("out")),
+            Err(FsPathParseError {
+                source: RelativePathParseError::OutsideBase { .. },
+                ..
+            })
+        );
     }
 
     #[test]
@@ -1042,7 +1155,7 @@ mod tests {
         assert_matches!(
             RepoPathBuf::parse_fs_path(&cwd_path, &wc_path, "../.."),
             Err(FsPathParseError {
-                source: RelativePathParseError::InvalidComponent { .. },
+                source: RelativePathParseError::OutsideBase { .. },
                 ..
             })
         );
@@ -1061,14 +1174,14 @@ mod tests {
         assert_matches!(
             RepoPathBuf::parse_fs_path(&cwd_path, &wc_path, ""),
             Err(FsPathParseError {
-                source: RelativePathParseError::InvalidComponent { .. },
+                source: RelativePathParseError::OutsideBase { .. },
                 ..
             })
         );
         assert_matches!(
             RepoPathBuf::parse_fs_path(&cwd_path, &wc_path, "not-repo"),
             Err(FsPathParseError {
-                source: RelativePathParseError::InvalidComponent { .. },
+                source: RelativePathParseError::OutsideBase { .. },
                 ..
             })
         );
@@ -1119,4 +1232,39 @@ mod tests {
         assert_eq!(format("file1", "file2"), "{file1 => file2}");
         assert_eq!(format("file-1", "file-2"), "{file-1 => file-2}");
     }
+
+    #[test]
+    fn test_path_normalization() {
+        let nfc = "caf\u{e9}";
+        let nfd = "cafe\u{301}";
+
+        let identity = PathNormalization::default();
+        assert!(identity.is_identity());
+        assert_eq!(identity.normalize_name(nfd), nfd);
+        assert_eq!(identity.comparison_key("Foo"), "Foo");
+
+        let unicode = PathNormalization {
+            unicode_nfc: true,
+            case_insensitive: false,
+        };
+        assert_eq!(unicode.normalize_name(nfd), nfc);
+        assert_eq!(unicode.comparison_key(nfd), unicode.comparison_key(nfc));
+        assert_ne!(unicode.comparison_key("Foo"), unicode.comparison_key("foo"));
+
+        let both = PathNormalization {
+            unicode_nfc: true,
+            case_insensitive: true,
+        };
+        assert_eq!(both.normalize_name("Foo"), "Foo");
+        assert_eq!(both.comparison_key("Foo"), "foo");
+        assert_eq!(both.comparison_key(&nfd.to_uppercase()), nfc);
+        assert_eq!(
+            both.comparison_path(repo_path("Dir/FILE")).as_ref(),
+            repo_path("dir/file")
+        );
+        assert_matches!(
+            both.comparison_path(repo_path("dir/file")),
+            Cow::Borrowed(_)
+        );
+    }
 }
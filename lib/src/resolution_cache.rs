@@ -0,0 +1,305 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cache of conflict resolutions, which are replayed when the same conflict
+//! appears again.
+//!
+//! Entries are keyed by a hash of the conflicted value. Since file ids are
+//! content hashes, two conflicts get the same key only if all their terms
+//! have the same contents.
+
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::FileId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::content_hash::blake2b_hash;
+use crate::file_util::create_or_reuse_dir;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::merge::Merge;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::object_id::ObjectId as _;
+use crate::repo::ReadonlyRepo;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::store::Store;
+
+/// Error that may occur when reading or updating a [`ResolutionCache`].
+#[derive(Debug, Error)]
+pub enum ResolutionCacheError {
+    /// Failed to read or write trees.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to read or write a cache entry.
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+/// Records how conflicts were resolved, keyed by the conflicted value.
+///
+/// Only resolutions to regular files are recorded.
+#[derive(Debug)]
+pub struct ResolutionCache {
+    dir: PathBuf,
+}
+
+impl ResolutionCache {
+    /// Creates a cache stored in `dir`. The directory is created when the
+    /// first resolution is recorded.
+    pub fn load(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_owned(),
+        }
+    }
+
+    /// The directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, conflict: &MergedTreeValue) -> PathBuf {
+        let key = hex::encode(blake2b_hash(&conflict.clone().simplify()));
+        self.dir.join(key)
+    }
+
+    /// Looks up the recorded resolution of `conflict`.
+    pub fn get(&self, conflict: &MergedTreeValue) -> Result<Option<TreeValue>, PathError> {
+        let entry = read_entry(&self.entry_path(conflict))?;
+        Ok(entry.map(|(resolution, _)| resolution))
+    }
+
+    /// Forgets the recorded resolution of `conflict`, if any.
+    pub fn remove(&self, conflict: &MergedTreeValue) -> Result<(), PathError> {
+        remove_entry(&self.entry_path(conflict))
+    }
+
+    /// Records that `conflict` at `path` was resolved to `resolution`,
+    /// replacing any previously recorded resolution. Does nothing if
+    /// `resolution` isn't a regular file.
+    ///
+    /// The resolution is used for the same conflict at any path. The `path` is
+    /// only kept to look up the resolved file in the backend.
+    pub fn insert(
+        &self,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+        resolution: &TreeValue,
+    ) -> Result<(), PathError> {
+        let TreeValue::File { id, executable } = resolution else {
+            return Ok(());
+        };
+        let kind = if *executable { "executable" } else { "file" };
+        create_or_reuse_dir(&self.dir).context(&self.dir)?;
+        let mut temp_file = NamedTempFile::new_in(&self.dir).context(&self.dir)?;
+        writeln!(
+            temp_file,
+            "{kind} {} {}",
+            id.hex(),
+            path.as_internal_file_string()
+        )
+        .context(temp_file.path())?;
+        let entry_path = self.entry_path(conflict);
+        temp_file
+            .persist(&entry_path)
+            .map_err(|err| err.error)
+            .context(&entry_path)?;
+        Ok(())
+    }
+
+    /// Records the resolutions of the conflicts in `old_tree` which are
+    /// resolved in `new_tree`.
+    pub fn record_resolutions(
+        &self,
+        old_tree: &MergedTree,
+        new_tree: &MergedTree,
+    ) -> Result<(), ResolutionCacheError> {
+        if !old_tree.has_conflict() {
+            return Ok(());
+        }
+        for (path, conflict) in old_tree.conflicts() {
+            let conflict = conflict?;
+            if let Some(Some(resolution)) = new_tree.path_value(&path)?.as_resolved() {
+                self.insert(&path, &conflict, resolution)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the conflicts in `tree` which have a recorded resolution.
+    pub fn resolve_tree(&self, tree: &MergedTree) -> Result<MergedTree, ResolutionCacheError> {
+        if !tree.has_conflict() {
+            return Ok(tree.clone());
+        }
+        let store = tree.store();
+        let mut tree_builder = MergedTreeBuilder::new(tree.id());
+        let mut resolved_any = false;
+        for (path, conflict) in tree.conflicts() {
+            let conflict = conflict?;
+            let Some(resolution) = self.get(&conflict)? else {
+                continue;
+            };
+            // The resolved file may have been garbage-collected since it was
+            // recorded. Such entries can't be used anymore.
+            if !has_file(store, &path, &resolution)? {
+                self.remove(&conflict)?;
+                continue;
+            }
+            tree_builder.set_or_remove(path, Merge::normal(resolution));
+            resolved_any = true;
+        }
+        if !resolved_any {
+            return Ok(tree.clone());
+        }
+        let tree_id = tree_builder.write_tree(store)?;
+        Ok(store.get_root_tree(&tree_id)?)
+    }
+
+    /// Removes the entries which can't be used anymore: entries which can't be
+    /// parsed, and entries whose resolved file doesn't exist in the `store`.
+    ///
+    /// This should be run after the backend is garbage-collected.
+    pub fn gc(&self, store: &Store) -> Result<(), ResolutionCacheError> {
+        let entries = match fs::read_dir(&self.dir).context(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        for entry in entries {
+            let entry = entry.context(&self.dir)?;
+            // Skip temporary files of entries being recorded
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let entry_path = entry.path();
+            let is_usable = match read_entry(&entry_path)? {
+                Some((resolution, path)) => has_file(store, &path, &resolution)?,
+                None => false,
+            };
+            if !is_usable {
+                remove_entry(&entry_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the resolution and the path it was recorded at from the entry file.
+fn read_entry(entry_path: &Path) -> Result<Option<(TreeValue, RepoPathBuf)>, PathError> {
+    let content = match fs::read_to_string(entry_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context(entry_path),
+    };
+    // Entries we can't parse are ignored. They may have been written by a
+    // newer version, and can be recorded again.
+    Ok(parse_entry(content.strip_suffix('\n').unwrap_or(&content)))
+}
+
+fn parse_entry(content: &str) -> Option<(TreeValue, RepoPathBuf)> {
+    let mut fields = content.splitn(3, ' ');
+    let executable = match fields.next()? {
+        "file" => false,
+        "executable" => true,
+        _ => return None,
+    };
+    let id = FileId::try_from_hex(fields.next()?).ok()?;
+    let path = RepoPathBuf::from_relative_path(fields.next()?).ok()?;
+    Some((TreeValue::File { id, executable }, path))
+}
+
+fn remove_entry(path: &Path) -> Result<(), PathError> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).context(path),
+    }
+}
+
+fn has_file(store: &Store, path: &RepoPath, value: &TreeValue) -> Result<bool, BackendError> {
+    let TreeValue::File { id, .. } = value else {
+        return Ok(true);
+    };
+    match store.read_file(path, id) {
+        Ok(_) => Ok(true),
+        Err(BackendError::ObjectNotFound { .. }) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether `new_commit` may have resolved conflicts of `old_commit` which
+/// should be recorded, as enabled by the `merge.reuse-resolutions` setting.
+///
+/// Conflicts are only recorded if the parents didn't change, since conflicts
+/// resolved by rebasing weren't resolved by the user.
+pub(crate) fn may_resolve_conflicts(
+    repo: &ReadonlyRepo,
+    old_commit: &Commit,
+    new_commit: &Commit,
+) -> bool {
+    enabled_cache(repo).is_some()
+        && old_commit.parent_ids() == new_commit.parent_ids()
+        && old_commit.tree_id() != new_commit.tree_id()
+        && !old_commit.tree_id().to_merge().is_resolved()
+}
+
+/// Records how the new commits resolved the conflicts of the old commits in
+/// the `(old_commit, new_commit)` pairs.
+///
+/// This is called once the operation which rewrote the commits is published,
+/// so resolutions made in discarded transactions aren't recorded.
+pub(crate) fn record_commit_resolutions(
+    repo: &ReadonlyRepo,
+    resolved_conflicts: &[(Commit, Commit)],
+) -> Result<(), ResolutionCacheError> {
+    let Some(cache) = enabled_cache(repo) else {
+        return Ok(());
+    };
+    for (old_commit, new_commit) in resolved_conflicts {
+        cache.record_resolutions(&old_commit.tree()?, &new_commit.tree()?)?;
+    }
+    Ok(())
+}
+
+/// Replaces the conflicts in `tree` which have a recorded resolution, if
+/// enabled by the `merge.reuse-resolutions` setting.
+pub(crate) fn resolve_known_conflicts(
+    repo: &ReadonlyRepo,
+    tree: MergedTree,
+) -> Result<MergedTree, BackendError> {
+    match enabled_cache(repo) {
+        Some(cache) if tree.has_conflict() => cache
+            .resolve_tree(&tree)
+            .map_err(|err| BackendError::Other(err.into())),
+        _ => Ok(tree),
+    }
+}
+
+fn enabled_cache(repo: &ReadonlyRepo) -> Option<&ResolutionCache> {
+    if repo.settings().merge_reuse_resolutions() {
+        repo.resolution_cache().map(AsRef::as_ref)
+    } else {
+        None
+    }
+}
@@ -159,6 +159,9 @@ pub enum RevsetFilterPredicate {
     Description(StringPattern),
     /// Commits with first line of the description matching the pattern.
     Subject(StringPattern),
+    /// Commits with a trailer of the `key` (compared case-insensitively)
+    /// whose value matches the pattern.
+    DescriptionTrailer { key: String, value: StringPattern },
     /// Commits with author name matching the pattern.
     AuthorName(StringPattern),
     /// Commits with author email matching the pattern.
@@ -797,6 +800,20 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             RevsetFilterPredicate::Description(pattern),
         ))
     });
+    map.insert(
+        "description_contains_trailer",
+        |diagnostics, function, _context| {
+            let ([key_arg], [value_opt_arg]) = function.expect_arguments()?;
+            let key = expect_literal(diagnostics, "string", key_arg)?;
+            let value = if let Some(value_arg) = value_opt_arg {
+                expect_string_pattern(diagnostics, value_arg)?
+            } else {
+                StringPattern::everything()
+            };
+            let predicate = RevsetFilterPredicate::DescriptionTrailer { key, value };
+            Ok(RevsetExpression::filter(predicate))
+        },
+    );
     map.insert("subject", |diagnostics, function, _context| {
         let [arg] = function.expect_exact_arguments()?;
         let pattern = expect_string_pattern(diagnostics, arg)?;
@@ -1804,7 +1821,8 @@ fn reload_repo_at_operation(
         RepoLoaderError::IndexRead(_)
         | RepoLoaderError::OpHeadResolution(_)
         | RepoLoaderError::OpHeadsStoreError(_)
-        | RepoLoaderError::OpStore(_) => RevsetResolutionError::Other(err.into()),
+        | RepoLoaderError::OpStore(_)
+        | RepoLoaderError::Rebase(_) => RevsetResolutionError::Other(err.into()),
     })
 }
 
@@ -1932,8 +1950,27 @@ impl PartialSymbolResolver for GitRefResolver {
     }
 }
 
-const DEFAULT_RESOLVERS: &[&'static dyn PartialSymbolResolver] =
-    &[&TagResolver, &BookmarkResolver, &GitRefResolver];
+struct AnonymousHeadResolver;
+
+impl PartialSymbolResolver for AnonymousHeadResolver {
+    fn resolve_symbol(
+        &self,
+        repo: &dyn Repo,
+        symbol: &str,
+    ) -> Result<Option<Vec<CommitId>>, RevsetResolutionError> {
+        Ok(repo
+            .view()
+            .get_anonymous_head(symbol)
+            .map(|id| vec![id.clone()]))
+    }
+}
+
+const DEFAULT_RESOLVERS: &[&'static dyn PartialSymbolResolver] = &[
+    &TagResolver,
+    &BookmarkResolver,
+    &GitRefResolver,
+    &AnonymousHeadResolver,
+];
 
 struct CommitPrefixResolver<'a> {
     context_repo: &'a dyn Repo,
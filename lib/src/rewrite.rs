@@ -16,6 +16,8 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
+use std::slice;
 use std::sync::Arc;
 
 use futures::StreamExt;
@@ -23,41 +25,92 @@ use indexmap::IndexMap;
 use indexmap::IndexSet;
 use itertools::Itertools;
 use pollster::FutureExt;
+use thiserror::Error;
 use tracing::instrument;
 
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::CommitId;
 use crate::backend::MergedTreeId;
+use crate::cancellation::CancellationToken;
+use crate::cancellation::Cancelled;
 use crate::commit::Commit;
 use crate::commit::CommitIteratorExt;
 use crate::commit_builder::CommitBuilder;
+use crate::commit_builder::DetachedCommitBuilder;
 use crate::dag_walk;
 use crate::index::Index;
 use crate::index::IndexError;
+use crate::matchers::EverythingMatcher;
 use crate::matchers::Matcher;
 use crate::matchers::Visit;
 use crate::merged_tree::MergedTree;
 use crate::merged_tree::MergedTreeBuilder;
 use crate::merged_tree::TreeDiffEntry;
+use crate::op_store::PendingRewrite;
+use crate::op_store::StoppedRebase;
+use crate::operation::Operation;
 use crate::repo::MutableRepo;
 use crate::repo::Repo;
+use crate::repo::Rewrite;
+use crate::repo::RewriteCycleError;
 use crate::repo_path::RepoPath;
+use crate::resolution_cache::resolve_known_conflicts;
 use crate::revset::RevsetExpression;
 use crate::revset::RevsetIteratorExt;
 use crate::store::Store;
+use crate::transaction::Transaction;
 
-/// Merges `commits` and tries to resolve any conflicts recursively.
+/// Merges the trees of `commits` and tries to resolve any conflicts
+/// recursively.
+///
+/// This computes the tree a new commit with `commits` as parents starts from.
+/// If `commits` is empty, the result is the empty tree. With one commit, it's
+/// that commit's tree. Otherwise, the commits are merged one by one using
+/// their common ancestors in `repo` as the base. If a pair of commits has
+/// several merge bases (such as in criss-cross histories), the merge bases are
+/// merged and resolved first, and the result is used as the base.
+///
+/// Conflicts that can't be resolved are recorded in the returned tree rather
+/// than reported as errors. Renamed files are followed if the
+/// `merge.detect-renames` setting is enabled. Errors are only returned if the
+/// commits or trees can't be read.
 #[instrument(skip(repo))]
 pub fn merge_commit_trees(repo: &dyn Repo, commits: &[Commit]) -> BackendResult<MergedTree> {
-    if let [commit] = commits {
-        commit.tree()
-    } else {
-        merge_commit_trees_no_resolve_without_repo(repo.store(), repo.index(), commits)?.resolve()
+    let store = repo.store();
+    match commits {
+        [] => Ok(store.get_root_tree(&store.empty_merged_tree_id())?),
+        [commit] => commit.tree(),
+        [first, others @ ..] => {
+            let mut new_tree = first.tree()?;
+            let mut commit_ids = vec![first.id().clone()];
+            for other_commit in others {
+                let ancestor_ids = repo
+                    .index()
+                    .common_ancestors(&commit_ids, slice::from_ref(other_commit.id()));
+                let ancestors: Vec<_> = ancestor_ids
+                    .iter()
+                    .map(|id| store.get_commit(id))
+                    .try_collect()?;
+                let ancestor_tree = merge_commit_trees(repo, &ancestors)?;
+                let other_tree = other_commit.tree()?;
+                new_tree = if repo.base_repo().settings().merge_detect_renames() {
+                    new_tree.merge_with_renames(&ancestor_tree, &other_tree)?
+                } else {
+                    new_tree.merge_no_resolve(&ancestor_tree, &other_tree)
+                };
+                commit_ids.push(other_commit.id().clone());
+            }
+            new_tree.resolve()
+        }
     }
 }
 
-/// Merges `commits` without attempting to resolve file conflicts.
+/// Merges the trees of `commits` without attempting to resolve file
+/// conflicts.
+///
+/// Like [`merge_commit_trees()`], but takes the `store` and `index` instead of
+/// a repo, doesn't follow renames, and leaves the merged trees unresolved.
 #[instrument(skip(index))]
 pub fn merge_commit_trees_no_resolve_without_repo(
     store: &Arc<Store>,
@@ -118,6 +171,13 @@ pub fn restore_tree(
     }
 }
 
+/// Rebases `old_commit` onto `new_parents` and records the rewrite in
+/// `mut_repo`.
+///
+/// The commit is kept even if it becomes empty, and conflicts are recorded in
+/// the new commit. Descendants of the old commit aren't rebased until
+/// [`MutableRepo::rebase_descendants()`] is called. Use
+/// [`rebase_commit_with_options()`] to customize the behavior.
 pub fn rebase_commit(
     mut_repo: &mut MutableRepo,
     old_commit: Commit,
@@ -128,6 +188,24 @@ pub fn rebase_commit(
     builder.write()
 }
 
+/// Rebases `old_commit` onto `new_parents` as configured by `options`, and
+/// records the rewrite (or abandonment) in `mut_repo`.
+///
+/// Descendants of the old commit aren't rebased until
+/// [`MutableRepo::rebase_descendants_with_options()`] is called.
+/// `options.rewrite_refs`, `options.cancellation` and
+/// `options.descendants_filter` only apply to that step. Signatures and other
+/// settings are taken from the repo's
+/// [`UserSettings`](crate::settings::UserSettings).
+pub fn rebase_commit_with_options(
+    mut_repo: &mut MutableRepo,
+    old_commit: Commit,
+    new_parents: Vec<CommitId>,
+    options: &RebaseOptions,
+) -> BackendResult<RebasedCommit> {
+    CommitRewriter::new(mut_repo, old_commit, new_parents).rebase_with_options(options)
+}
+
 /// Helps rewrite a commit.
 pub struct CommitRewriter<'repo> {
     mut_repo: &'repo mut MutableRepo,
@@ -252,9 +330,15 @@ impl<'repo> CommitRewriter<'repo> {
             let old_base_tree = merge_commit_trees(self.mut_repo, &old_parents)?;
             let new_base_tree = merge_commit_trees(self.mut_repo, &new_parents)?;
             let old_tree = self.old_commit.tree()?;
+            let new_tree = if self.mut_repo.base_repo().settings().merge_detect_renames() {
+                new_base_tree.merge_with_renames(&old_base_tree, &old_tree)?
+            } else {
+                new_base_tree.merge(&old_base_tree, &old_tree)?
+            };
+            let new_tree = resolve_known_conflicts(self.mut_repo.base_repo(), new_tree)?;
             (
                 old_base_tree.id() == *self.old_commit.tree_id(),
-                new_base_tree.merge(&old_base_tree, &old_tree)?.id(),
+                new_tree.id(),
             )
         };
         // Ensure we don't abandon commits with multiple parents (merge commits), even
@@ -286,6 +370,49 @@ impl<'repo> CommitRewriter<'repo> {
         Ok(builder.unwrap())
     }
 
+    /// Rebase the old commit onto the new parents as configured by `options`.
+    /// Writes the new commit unless it was abandoned or skipped.
+    pub fn rebase_with_options(mut self, options: &RebaseOptions) -> BackendResult<RebasedCommit> {
+        // If specified, don't create commit where one parent is an ancestor of another.
+        if options.simplify_ancestor_merge {
+            // TODO: BackendError is not the right error here because
+            // the error does not come from `Backend`, but `Index`.
+            self.simplify_ancestor_merge()
+                .map_err(|err| BackendError::Other(err.into()))?;
+        }
+
+        let single_parent = match &self.new_parents[..] {
+            [parent_id] => Some(parent_id.clone()),
+            _ => None,
+        };
+        let new_parents_len = self.new_parents.len();
+        let had_conflict = self.old_commit.has_conflict()?;
+        let description = options
+            .description
+            .as_ref()
+            .map(|template| template.render(&self.old_commit));
+        if let Some(mut builder) = self.rebase_with_empty_behavior(options.empty)? {
+            if options.conflicts != ConflictBehaviour::Keep
+                && !had_conflict
+                && !builder.tree_id().to_merge().is_resolved()
+            {
+                // Nothing has been written yet, so dropping the builder leaves the
+                // old commit in place.
+                return Ok(RebasedCommit::Skipped);
+            }
+            if let Some(description) = description {
+                builder = builder.set_description(description);
+            }
+            let new_commit = builder.write()?;
+            Ok(RebasedCommit::Rewritten(new_commit))
+        } else {
+            assert_eq!(new_parents_len, 1);
+            Ok(RebasedCommit::Abandoned {
+                parent_id: single_parent.unwrap(),
+            })
+        }
+    }
+
     /// Rewrite the old commit onto the new parents without changing its
     /// contents. Returns a `CommitBuilder` for the new commit.
     pub fn reparent(self) -> CommitBuilder<'repo> {
@@ -295,38 +422,16 @@ impl<'repo> CommitRewriter<'repo> {
     }
 }
 
+/// The outcome of rebasing a single commit.
 pub enum RebasedCommit {
+    /// The commit was rebased as this new commit.
     Rewritten(Commit),
+    /// The commit became empty and was abandoned in favor of its new parent.
+    /// See [`EmptyBehaviour`].
     Abandoned { parent_id: CommitId },
-}
-
-pub fn rebase_commit_with_options(
-    mut rewriter: CommitRewriter<'_>,
-    options: &RebaseOptions,
-) -> BackendResult<RebasedCommit> {
-    // If specified, don't create commit where one parent is an ancestor of another.
-    if options.simplify_ancestor_merge {
-        // TODO: BackendError is not the right error here because
-        // the error does not come from `Backend`, but `Index`.
-        rewriter
-            .simplify_ancestor_merge()
-            .map_err(|err| BackendError::Other(err.into()))?;
-    }
-
-    let single_parent = match &rewriter.new_parents[..] {
-        [parent_id] => Some(parent_id.clone()),
-        _ => None,
-    };
-    let new_parents_len = rewriter.new_parents.len();
-    if let Some(builder) = rewriter.rebase_with_empty_behavior(options.empty)? {
-        let new_commit = builder.write()?;
-        Ok(RebasedCommit::Rewritten(new_commit))
-    } else {
-        assert_eq!(new_parents_len, 1);
-        Ok(RebasedCommit::Abandoned {
-            parent_id: single_parent.unwrap(),
-        })
-    }
+    /// The commit was left in place because rebasing it would have introduced
+    /// a conflict. See [`ConflictBehaviour`].
+    Skipped,
 }
 
 /// Moves changes from `sources` to the `destination` parent, returns new tree.
@@ -365,6 +470,48 @@ pub enum EmptyBehaviour {
     AbandonAllEmpty,
 }
 
+/// What to do when rebasing a commit would introduce a conflict.
+///
+/// Commits which were already conflicted before the rebase are always rebased.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ConflictBehaviour {
+    /// Record the conflict in the rebased commit and continue
+    #[default]
+    Keep,
+    /// Leave the commit (and its descendants) on top of the old parents and
+    /// continue with the other commits
+    Skip,
+    /// Leave the commit in place and stop rebasing. The remaining work is
+    /// returned as a [`RebaseState`] by
+    /// [`MutableRepo::rebase_descendants_with_options()`]. Other callers
+    /// treat this like [`ConflictBehaviour::Skip`].
+    Stop,
+}
+
+/// Computes the description of a rebased commit from the old commit.
+///
+/// This can be used to e.g. add a trailer to every commit rebased by an
+/// automated tool.
+#[derive(Clone)]
+pub struct DescriptionTemplate(Arc<dyn Fn(&Commit) -> String + Send + Sync>);
+
+impl DescriptionTemplate {
+    pub fn new(render: impl Fn(&Commit) -> String + Send + Sync + 'static) -> Self {
+        DescriptionTemplate(Arc::new(render))
+    }
+
+    /// Returns the new description of the rebased `old_commit`.
+    pub fn render(&self, old_commit: &Commit) -> String {
+        (self.0)(old_commit)
+    }
+}
+
+impl fmt::Debug for DescriptionTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DescriptionTemplate").finish_non_exhaustive()
+    }
+}
+
 /// Controls the configuration of a rebase.
 // If we wanted to add a flag similar to `git rebase --ignore-date`, then this
 // makes it much easier by ensuring that the only changes required are to
@@ -374,10 +521,131 @@ pub enum EmptyBehaviour {
 #[derive(Clone, Debug, Default)]
 pub struct RebaseOptions {
     pub empty: EmptyBehaviour,
+    pub conflicts: ConflictBehaviour,
     pub rewrite_refs: RewriteRefsOptions,
     /// If a merge commit would end up with one parent being an ancestor of the
     /// other, then filter out the ancestor.
     pub simplify_ancestor_merge: bool,
+    /// Checked before each commit is rebased. If cancelled, the rebase stops
+    /// with [`RebaseError::Cancelled`] and the transaction should be
+    /// discarded.
+    pub cancellation: CancellationToken,
+    /// If set, [`MutableRepo::rebase_descendants_with_options()`] only
+    /// rebases the descendants in this set. The other descendants (and their
+    /// descendants) stay on top of the rewritten commits.
+    ///
+    /// The set is precomputed by the caller (e.g. by evaluating a revset) so
+    /// that the options can be sent to other threads.
+    pub descendants_filter: Option<Arc<HashSet<CommitId>>>,
+    /// If set, the rebased commits are described by this template instead of
+    /// keeping their old descriptions.
+    pub description: Option<DescriptionTemplate>,
+}
+
+/// Error that can occur while rebasing commits.
+#[derive(Debug, Error)]
+pub enum RebaseError {
+    /// The rebase was cancelled through [`RebaseOptions::cancellation`].
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+    /// Failed to read or write commits.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Commits were rewritten into themselves, so their descendants can't be
+    /// rebased.
+    #[error(transparent)]
+    Cycle(#[from] RewriteCycleError),
+}
+
+impl RebaseError {
+    /// Converts this into [`BackendError`]. Use this only if the rebase can't
+    /// be cancelled and no rewrite cycle can exist, e.g. because the default
+    /// options are used on a freshly rewritten commit.
+    pub fn into_backend_error(self) -> BackendError {
+        match self {
+            Self::Cancelled(err) => BackendError::Other(err.into()),
+            Self::Backend(err) => err,
+            Self::Cycle(err) => BackendError::Other(err.into()),
+        }
+    }
+}
+
+/// A rebase stopped by [`ConflictBehaviour::Stop`].
+///
+/// Holds the rewrites whose descendants haven't been rebased yet. The state
+/// can be saved in the operation which recorded the partial rebase, and
+/// restored in a later transaction to resume the rebase. Before resuming, the
+/// caller may resolve the conflicted commit by rewriting it onto
+/// `mut_repo.new_parents(commit.parent_ids())`, or resume with
+/// [`ConflictBehaviour::Keep`] to accept the conflict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RebaseState {
+    conflicted_commit_id: CommitId,
+    rewrites: Vec<(CommitId, Rewrite)>,
+}
+
+impl RebaseState {
+    pub(crate) fn new(conflicted_commit_id: CommitId, rewrites: Vec<(CommitId, Rewrite)>) -> Self {
+        RebaseState {
+            conflicted_commit_id,
+            rewrites,
+        }
+    }
+
+    /// The commit which would have become conflicted. It hasn't been rebased.
+    pub fn conflicted_commit_id(&self) -> &CommitId {
+        &self.conflicted_commit_id
+    }
+
+    /// Records the state in the metadata of the operation `tx` will create.
+    pub fn save_to_transaction(&self, tx: &mut Transaction) {
+        let rewrites = self
+            .rewrites
+            .iter()
+            .map(|(old_id, rewrite)| {
+                let rewrite = match rewrite {
+                    Rewrite::Rewritten(new_id) => PendingRewrite::Rewritten(new_id.clone()),
+                    Rewrite::Divergent(new_ids) => PendingRewrite::Divergent(new_ids.clone()),
+                    Rewrite::Abandoned(new_ids) => PendingRewrite::Abandoned(new_ids.clone()),
+                };
+                (old_id.clone(), rewrite)
+            })
+            .collect();
+        tx.set_stopped_rebase(Some(StoppedRebase {
+            conflicted_commit_id: self.conflicted_commit_id.clone(),
+            rewrites,
+        }));
+    }
+
+    /// Loads the state saved in the given operation, if any.
+    pub fn load_from_operation(operation: &Operation) -> Option<Self> {
+        let stopped_rebase = operation.metadata().stopped_rebase.as_ref()?;
+        let rewrites = stopped_rebase
+            .rewrites
+            .iter()
+            .map(|(old_id, rewrite)| {
+                let rewrite = match rewrite {
+                    PendingRewrite::Rewritten(new_id) => Rewrite::Rewritten(new_id.clone()),
+                    PendingRewrite::Divergent(new_ids) => Rewrite::Divergent(new_ids.clone()),
+                    PendingRewrite::Abandoned(new_ids) => Rewrite::Abandoned(new_ids.clone()),
+                };
+                (old_id.clone(), rewrite)
+            })
+            .collect();
+        Some(RebaseState {
+            conflicted_commit_id: stopped_rebase.conflicted_commit_id.clone(),
+            rewrites,
+        })
+    }
+
+    /// Records the pending rewrites in `mut_repo` so that a later
+    /// [`MutableRepo::rebase_descendants_with_options()`] continues the
+    /// rebase.
+    pub fn restore(self, mut_repo: &mut MutableRepo) {
+        for (old_id, rewrite) in self.rewrites {
+            mut_repo.restore_rewrite(old_id, rewrite);
+        }
+    }
 }
 
 /// Configuration for [`MutableRepo::update_rewritten_references()`].
@@ -399,6 +667,9 @@ pub struct MoveCommitsStats {
     /// The number of commits for which rebase was skipped, due to the commit
     /// already being in place.
     pub num_skipped_rebases: u32,
+    /// The number of commits which were left in place because rebasing them
+    /// would have introduced a conflict. See [`ConflictBehaviour::Skip`].
+    pub num_skipped_conflicts: u32,
     /// The number of commits which were abandoned.
     pub num_abandoned: u32,
 }
@@ -425,7 +696,7 @@ pub fn move_commits(
     new_children: &[Commit],
     target: &MoveCommitsTarget,
     options: &RebaseOptions,
-) -> BackendResult<MoveCommitsStats> {
+) -> Result<MoveCommitsStats, RebaseError> {
     let target_commits: Vec<Commit>;
     let target_commit_ids: HashSet<_>;
     let connected_target_commits: Vec<Commit>;
@@ -737,34 +1008,39 @@ pub fn move_commits(
     let mut num_rebased_targets = 0;
     let mut num_rebased_descendants = 0;
     let mut num_skipped_rebases = 0;
+    let mut num_skipped_conflicts = 0;
     let mut num_abandoned = 0;
 
-    // Always keep empty commits when rebasing descendants.
+    // Always keep empty commits and descriptions when rebasing descendants.
     let rebase_descendant_options = &RebaseOptions {
         empty: EmptyBehaviour::Keep,
+        conflicts: options.conflicts,
         rewrite_refs: options.rewrite_refs.clone(),
         simplify_ancestor_merge: options.simplify_ancestor_merge,
+        cancellation: options.cancellation.clone(),
+        descendants_filter: None,
+        description: None,
     };
 
     // Rebase each commit onto its new parents in the reverse topological order
     // computed above.
     while let Some(old_commit_id) = to_visit.pop() {
+        options.cancellation.check()?;
         let old_commit = to_visit_commits.get(&old_commit_id).unwrap();
         let parent_ids = to_visit_commits_new_parents.get(&old_commit_id).unwrap();
         let new_parent_ids = mut_repo.new_parents(parent_ids);
         let rewriter = CommitRewriter::new(mut_repo, old_commit.clone(), new_parent_ids);
         if rewriter.parents_changed() {
             let is_target_commit = target_commit_ids.contains(&old_commit_id);
-            let rebased_commit = rebase_commit_with_options(
-                rewriter,
-                if is_target_commit {
-                    options
-                } else {
-                    rebase_descendant_options
-                },
-            )?;
+            let rebased_commit = rewriter.rebase_with_options(if is_target_commit {
+                options
+            } else {
+                rebase_descendant_options
+            })?;
             if let RebasedCommit::Abandoned { .. } = rebased_commit {
                 num_abandoned += 1;
+            } else if let RebasedCommit::Skipped = rebased_commit {
+                num_skipped_conflicts += 1;
             } else if is_target_commit {
                 num_rebased_targets += 1;
             } else {
@@ -780,10 +1056,27 @@ pub fn move_commits(
         num_rebased_targets,
         num_rebased_descendants,
         num_skipped_rebases,
+        num_skipped_conflicts,
         num_abandoned,
     })
 }
 
+/// Duplicates the `commit` onto the `new_parent_ids` by applying its changes
+/// there. The new commit gets a new change id.
+///
+/// Unlike rewriting the `commit`, the original commit is kept visible and its
+/// descendants are not rebased.
+pub fn duplicate_commit(
+    mut_repo: &mut MutableRepo,
+    commit: &Commit,
+    new_parent_ids: Vec<CommitId>,
+) -> BackendResult<Commit> {
+    CommitRewriter::new(mut_repo, commit.clone(), new_parent_ids)
+        .rebase()?
+        .generate_new_change_id()
+        .write()
+}
+
 #[derive(Default)]
 pub struct DuplicateCommitsStats {
     /// Map of original commit ID to newly duplicated commit.
@@ -810,7 +1103,7 @@ pub fn duplicate_commits(
     target_commits: &[CommitId],
     parent_commit_ids: &[CommitId],
     children_commit_ids: &[CommitId],
-) -> BackendResult<DuplicateCommitsStats> {
+) -> Result<DuplicateCommitsStats, RebaseError> {
     if target_commits.is_empty() {
         return Ok(DuplicateCommitsStats::default());
     }
@@ -879,10 +1172,7 @@ pub fn duplicate_commits(
                 })
                 .collect()
         };
-        let new_commit = CommitRewriter::new(mut_repo, original_commit, new_parent_ids)
-            .rebase()?
-            .generate_new_change_id()
-            .write()?;
+        let new_commit = duplicate_commit(mut_repo, &original_commit, new_parent_ids)?;
         duplicated_old_to_new.insert(original_commit_id.clone(), new_commit);
     }
 
@@ -1048,6 +1338,191 @@ impl CommitWithSelection {
     }
 }
 
+/// Rebases descendants of the `target` commit which has been split into
+/// `first_commit` and `second_commit`. Returns the number of rebased commits.
+///
+/// The `first_commit` should have been written as a rewrite of the `target`,
+/// and the `second_commit` should have a new change id. If `parallel` is true,
+/// the two commits are siblings, and the descendants will be rebased onto both
+/// of them. Otherwise, the descendants will be rebased onto the
+/// `second_commit`.
+///
+/// If `move_bookmarks_to_second` is true, the `target` will be marked as
+/// rewritten to the `second_commit`, which moves bookmarks pointing to the
+/// `target` to the `second_commit`.
+pub fn rebase_descendants_of_split_commit(
+    repo: &mut MutableRepo,
+    target: &Commit,
+    first_commit: &Commit,
+    second_commit: &Commit,
+    parallel: bool,
+    move_bookmarks_to_second: bool,
+) -> Result<u32, RebaseError> {
+    if move_bookmarks_to_second {
+        repo.set_rewritten_commit(target.id().clone(), second_commit.id().clone());
+    }
+    // The descendants are first rebased onto the commit the target was
+    // rewritten to, so replace it with the split commits.
+    let rewritten_id = if move_bookmarks_to_second {
+        second_commit.id()
+    } else {
+        first_commit.id()
+    };
+    let new_parent_ids = if parallel {
+        vec![first_commit.id(), second_commit.id()]
+    } else {
+        vec![second_commit.id()]
+    };
+    let mut num_rebased = 0;
+    repo.transform_descendants(vec![target.id().clone()], |mut rewriter| {
+        num_rebased += 1;
+        rewriter.replace_parent(rewritten_id, new_parent_ids.iter().copied());
+        rewriter.rebase()?.write()?;
+        Ok(())
+    })?;
+    Ok(num_rebased)
+}
+
+/// Options for [`split_commit()`].
+#[derive(Clone, Debug, Default)]
+pub struct SplitOptions {
+    /// Whether the two commits become siblings instead of the second commit
+    /// becoming a child of the first.
+    pub parallel: bool,
+    /// Whether bookmarks pointing to the split commit are moved to the second
+    /// commit instead of the first.
+    pub move_bookmarks_to_second: bool,
+}
+
+/// Starts splitting `target.commit` in two. The first commit gets the selected
+/// changes, and the second commit gets the remaining changes.
+///
+/// The first commit is a rewrite of the target, and the second commit gets a
+/// new change id so that the split commit doesn't become divergent. Each
+/// commit is written by the caller after optionally changing its builder, e.g.
+/// to set the description. Writing the second commit rebases the descendants
+/// of the target as described in [`rebase_descendants_of_split_commit()`].
+pub fn split_commit(
+    repo: &mut MutableRepo,
+    target: CommitWithSelection,
+    options: &SplitOptions,
+) -> SplitFirstCommit {
+    let mut commit_builder = repo.rewrite_commit(&target.commit).detach();
+    commit_builder.set_tree_id(target.selected_tree.id());
+    SplitFirstCommit {
+        target,
+        options: options.clone(),
+        commit_builder,
+    }
+}
+
+/// The first commit of a split started by [`split_commit()`].
+pub struct SplitFirstCommit {
+    target: CommitWithSelection,
+    options: SplitOptions,
+    commit_builder: DetachedCommitBuilder,
+}
+
+impl SplitFirstCommit {
+    /// The builder of the first commit.
+    pub fn commit_builder(&mut self) -> &mut DetachedCommitBuilder {
+        &mut self.commit_builder
+    }
+
+    /// Writes the first commit and prepares the second commit.
+    pub fn write(self, repo: &mut MutableRepo) -> BackendResult<SplitSecondCommit> {
+        let first_commit = self.commit_builder.write(repo)?;
+        let target_tree = self.target.commit.tree()?;
+        let (parent_ids, new_tree) = if self.options.parallel {
+            // Merge the original commit tree with its parent using the tree
+            // containing the selected changes as the base for the merge. This
+            // results in a tree with the changes which weren't selected.
+            let new_tree =
+                target_tree.merge(&self.target.selected_tree, &self.target.parent_tree)?;
+            (self.target.commit.parent_ids().to_vec(), new_tree)
+        } else {
+            (vec![first_commit.id().clone()], target_tree)
+        };
+        let mut commit_builder = repo.rewrite_commit(&self.target.commit).detach();
+        commit_builder
+            .set_parents(parent_ids)
+            .set_tree_id(new_tree.id())
+            .generate_new_change_id();
+        Ok(SplitSecondCommit {
+            target: self.target,
+            options: self.options,
+            first_commit,
+            commit_builder,
+        })
+    }
+}
+
+/// The second commit of a split started by [`split_commit()`].
+pub struct SplitSecondCommit {
+    target: CommitWithSelection,
+    options: SplitOptions,
+    first_commit: Commit,
+    commit_builder: DetachedCommitBuilder,
+}
+
+impl SplitSecondCommit {
+    /// The first commit, which has already been written.
+    pub fn first_commit(&self) -> &Commit {
+        &self.first_commit
+    }
+
+    /// The builder of the second commit.
+    pub fn commit_builder(&mut self) -> &mut DetachedCommitBuilder {
+        &mut self.commit_builder
+    }
+
+    /// Writes the second commit and rebases the descendants of the split
+    /// commit.
+    pub fn write(self, repo: &mut MutableRepo) -> Result<SplitCommits, RebaseError> {
+        let second_commit = self.commit_builder.write(repo)?;
+        let num_rebased = rebase_descendants_of_split_commit(
+            repo,
+            &self.target.commit,
+            &self.first_commit,
+            &second_commit,
+            self.options.parallel,
+            self.options.move_bookmarks_to_second,
+        )?;
+        Ok(SplitCommits {
+            first_commit: self.first_commit,
+            second_commit,
+            num_rebased,
+        })
+    }
+}
+
+/// Commits created by [`split_commit()`].
+pub struct SplitCommits {
+    pub first_commit: Commit,
+    pub second_commit: Commit,
+    /// The number of descendants rebased onto the split commits.
+    pub num_rebased: u32,
+}
+
+/// Creates a commit on top of the `new_parents` which reverses the changes in
+/// the `commit`. Returns the builder for the new commit. Caller is responsible
+/// for setting the description and finishing the commit.
+///
+/// If the reverse changes don't apply cleanly, the new commit will have a
+/// conflicted tree.
+pub fn back_out_commit<'repo>(
+    repo: &'repo mut MutableRepo,
+    commit: &Commit,
+    new_parents: &[Commit],
+) -> BackendResult<CommitBuilder<'repo>> {
+    let old_base_tree = commit.parent_tree(repo)?;
+    let old_tree = commit.tree()?;
+    let new_base_tree = merge_commit_trees(repo, new_parents)?;
+    let new_tree = new_base_tree.merge(&old_tree, &old_base_tree)?;
+    let new_parent_ids = new_parents.iter().ids().cloned().collect();
+    Ok(repo.new_commit(new_parent_ids, new_tree.id()))
+}
+
 /// Resulting commit builder and stats to be returned by [`squash_commits()`].
 #[must_use]
 pub struct SquashedCommit<'repo> {
@@ -1124,9 +1599,12 @@ pub fn squash_commits<'repo>(
             }
             rewritten_destination = match rebased_commit {
                 RebasedCommit::Rewritten(commit) => commit,
-                RebasedCommit::Abandoned { .. } => panic!("all commits should be kept"),
+                RebasedCommit::Abandoned { .. } | RebasedCommit::Skipped => {
+                    panic!("all commits should be kept")
+                }
             };
-        })?;
+        })
+        .map_err(RebaseError::into_backend_error)?;
     }
     // Apply the selected changes onto the destination
     let mut destination_tree = rewritten_destination.tree()?;
@@ -1150,3 +1628,74 @@ pub fn squash_commits<'repo>(
         abandoned_commits,
     }))
 }
+
+/// Moves the changes in `source` matched by `matcher` into `destination`, and
+/// rebases descendants. Returns the rewritten destination commit, or `None` if
+/// no changes were matched or if either commit is the root commit, which can't
+/// be rewritten.
+///
+/// If all changes are moved, the `source` is abandoned, and its description is
+/// used if the `destination` has no description.
+pub fn move_changes(
+    repo: &mut MutableRepo,
+    source: &Commit,
+    destination: &Commit,
+    matcher: &dyn Matcher,
+) -> Result<Option<Commit>, RebaseError> {
+    let root_commit_id = repo.store().root_commit_id();
+    if source.id() == root_commit_id || destination.id() == root_commit_id {
+        return Ok(None);
+    }
+    let parent_tree = source.parent_tree(repo)?;
+    let selected_tree_id = restore_tree(&source.tree()?, &parent_tree, matcher)?;
+    let selection = CommitWithSelection {
+        commit: source.clone(),
+        selected_tree: repo.store().get_root_tree(&selected_tree_id)?,
+        parent_tree,
+    };
+    let Some(squashed) = squash_commits(repo, &[selection], destination, false)? else {
+        return Ok(None);
+    };
+    let mut commit_builder = squashed.commit_builder;
+    if !squashed.abandoned_commits.is_empty() && commit_builder.description().is_empty() {
+        commit_builder = commit_builder.set_description(source.description());
+    }
+    let new_commit = commit_builder.write()?;
+    repo.rebase_descendants()?;
+    Ok(Some(new_commit))
+}
+
+/// Squashes all changes in `commit` into its parent, abandons the `commit`,
+/// and rebases descendants. Returns the rewritten parent commit, or `None` if
+/// the `commit` doesn't have exactly one parent or if the parent is the root
+/// commit.
+pub fn squash_into_parent(
+    repo: &mut MutableRepo,
+    commit: &Commit,
+) -> Result<Option<Commit>, RebaseError> {
+    let [parent_id] = commit.parent_ids() else {
+        return Ok(None);
+    };
+    let parent = repo.store().get_commit(parent_id)?;
+    move_changes(repo, commit, &parent, &EverythingMatcher)
+}
+
+/// Moves the changes in the parent of `commit` matched by `matcher` into the
+/// `commit`, and rebases descendants. This is the inverse of
+/// [`squash_into_parent()`]. Returns the rewritten `commit`, or `None` if the
+/// `commit` doesn't have exactly one parent, if the parent is the root commit,
+/// or if no changes were matched.
+///
+/// If all changes are moved, the parent is abandoned, and its description is
+/// used if the `commit` has no description.
+pub fn unsquash_from_parent(
+    repo: &mut MutableRepo,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+) -> Result<Option<Commit>, RebaseError> {
+    let [parent_id] = commit.parent_ids() else {
+        return Ok(None);
+    };
+    let parent = repo.store().get_commit(parent_id)?;
+    move_changes(repo, &parent, commit, matcher)
+}
@@ -0,0 +1,227 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Search of file contents across a set of commits.
+
+use std::io::Read as _;
+use std::rc::Rc;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use futures::StreamExt as _;
+use itertools::Itertools as _;
+use rayon::prelude::*;
+use regex::bytes::Regex;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::diff::Diff;
+use crate::diff::DiffHunkKind;
+use crate::matchers::Matcher;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::repo::Repo;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::ResolvedRevsetExpression;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetIteratorExt as _;
+use crate::store::Store;
+
+/// Which contents of each commit are searched.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GrepScope {
+    /// All files in the commit's tree.
+    Tree,
+    /// Only the lines added or removed by the commit, compared to its parents.
+    Diff,
+}
+
+/// Line which matched the pattern.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrepMatch {
+    /// Commit the line was found in.
+    pub commit: Commit,
+    /// Path of the file containing the line.
+    pub path: RepoPathBuf,
+    /// 1-based line number. For removed lines, this is the line number in the
+    /// parent tree.
+    pub line_number: usize,
+    /// Content of the line, without the line terminator.
+    pub line: BString,
+    /// Whether the line was removed by the commit. Always false for
+    /// `GrepScope::Tree`.
+    pub removed: bool,
+}
+
+/// Error searching commits.
+#[derive(Debug, Error)]
+pub enum GrepError {
+    /// Error while contacting the Backend.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Error evaluating the set of commits.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+}
+
+/// Searches the files matching `path_filter` for lines matching `pattern` at
+/// each commit in `revset`.
+///
+/// The commits are searched in parallel. The matches are ordered by the
+/// commits in `revset` order, then by path and line. Binary files (files
+/// containing a NUL byte) and conflicted files are skipped.
+pub fn grep(
+    repo: &dyn Repo,
+    revset: &Rc<ResolvedRevsetExpression>,
+    pattern: &Regex,
+    path_filter: &dyn Matcher,
+    scope: GrepScope,
+) -> Result<Vec<GrepMatch>, GrepError> {
+    let commits: Vec<Commit> = revset
+        .clone()
+        .evaluate(repo)?
+        .iter()
+        .commits(repo.store())
+        .try_collect()?;
+    // Parent trees are merged up front since that needs the index, which can't
+    // be shared across threads.
+    let parent_trees: Vec<Option<MergedTree>> = match scope {
+        GrepScope::Tree => commits.iter().map(|_| None).collect(),
+        GrepScope::Diff => commits
+            .iter()
+            .map(|commit| commit.parent_tree(repo).map(Some))
+            .try_collect()?,
+    };
+    let store = repo.store();
+    let matches_per_commit: Vec<Vec<GrepMatch>> = commits
+        .into_par_iter()
+        .zip(parent_trees)
+        .map(|(commit, parent_tree)| {
+            let tree = commit.tree()?;
+            match parent_tree {
+                None => grep_tree(store, &commit, &tree, pattern, path_filter),
+                Some(parent_tree) => {
+                    grep_diff(store, &commit, &parent_tree, &tree, pattern, path_filter)
+                }
+            }
+        })
+        .collect::<BackendResult<_>>()?;
+    Ok(matches_per_commit.into_iter().flatten().collect())
+}
+
+fn grep_tree(
+    store: &Store,
+    commit: &Commit,
+    tree: &MergedTree,
+    pattern: &Regex,
+    path_filter: &dyn Matcher,
+) -> BackendResult<Vec<GrepMatch>> {
+    let mut matches = Vec::new();
+    for (path, value) in tree.entries_matching(path_filter) {
+        let Some(content) = read_text_file(store, &path, &value?)? else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            if pattern.is_match(line) {
+                matches.push(GrepMatch {
+                    commit: commit.clone(),
+                    path: path.clone(),
+                    line_number: index + 1,
+                    line: line.into(),
+                    removed: false,
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+fn grep_diff(
+    store: &Store,
+    commit: &Commit,
+    parent_tree: &MergedTree,
+    tree: &MergedTree,
+    pattern: &Regex,
+    path_filter: &dyn Matcher,
+) -> BackendResult<Vec<GrepMatch>> {
+    let mut matches = Vec::new();
+    let mut diff_stream = parent_tree.diff_stream(tree, path_filter);
+    while let Some(entry) = pollster::block_on(diff_stream.next()) {
+        let (before, after) = entry.values?;
+        let path = entry.path;
+        let before_content = read_text_file(store, &path, &before)?.unwrap_or_default();
+        let after_content = read_text_file(store, &path, &after)?.unwrap_or_default();
+        let diff = Diff::by_line([&before_content, &after_content]);
+        let mut removed_matches = Vec::new();
+        let mut added_matches = Vec::new();
+        for hunk in diff.hunk_ranges() {
+            if hunk.kind != DiffHunkKind::Different {
+                continue;
+            }
+            for (side, content) in [&before_content, &after_content].into_iter().enumerate() {
+                let range = hunk.ranges[side].clone();
+                let first_line_number = content[..range.start].lines_with_terminator().count() + 1;
+                for (index, line) in content[range].lines().enumerate() {
+                    if !pattern.is_match(line) {
+                        continue;
+                    }
+                    let grep_match = GrepMatch {
+                        commit: commit.clone(),
+                        path: path.clone(),
+                        line_number: first_line_number + index,
+                        line: line.into(),
+                        removed: side == 0,
+                    };
+                    if side == 0 {
+                        removed_matches.push(grep_match);
+                    } else {
+                        added_matches.push(grep_match);
+                    }
+                }
+            }
+        }
+        matches.extend(removed_matches);
+        matches.extend(added_matches);
+    }
+    Ok(matches)
+}
+
+/// Reads the content of a resolved regular file. Returns `None` if the value
+/// isn't such a file, or if the file looks binary.
+fn read_text_file(
+    store: &Store,
+    path: &RepoPath,
+    value: &MergedTreeValue,
+) -> BackendResult<Option<Vec<u8>>> {
+    let Some(Some(TreeValue::File { id, .. })) = value.as_resolved() else {
+        return Ok(None);
+    };
+    let mut content = Vec::new();
+    store
+        .read_file(path, id)?
+        .read_to_end(&mut content)
+        .map_err(|err| BackendError::ReadFile {
+            path: path.to_owned(),
+            id: id.clone(),
+            source: err.into(),
+        })?;
+    if content.contains(&0) {
+        return Ok(None);
+    }
+    Ok(Some(content))
+}
@@ -0,0 +1,326 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! High-level API for tools such as editor integrations.
+//!
+//! A [`Session`] bundles a [`Workspace`] with the repo loaded at its latest
+//! operation, and takes care of snapshotting the working copy and updating it
+//! after each transaction, like the `jj` CLI does for every command.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::StreamExt as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::commit::Commit;
+use crate::config::ConfigGetError;
+use crate::fileset::FilesetDiagnostics;
+use crate::gitignore::GitIgnoreError;
+use crate::gitignore::GitIgnoreFile;
+use crate::matchers::EverythingMatcher;
+use crate::op_store::OpStoreError;
+use crate::op_store::WorkspaceId;
+use crate::repo::CheckOutCommitError;
+use crate::repo::MutableRepo;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo;
+use crate::repo::RepoLoaderError;
+use crate::repo::RewriteRootCommit;
+use crate::repo::StoreFactories;
+use crate::repo_path::RepoPathBuf;
+use crate::rewrite::merge_commit_trees;
+use crate::rewrite::RebaseError;
+use crate::settings::UserSettings;
+use crate::store::Store;
+use crate::transaction::TransactionCommitError;
+use crate::working_copy::auto_tracking_matcher;
+use crate::working_copy::AutoTrackingError;
+use crate::working_copy::CheckoutError;
+use crate::working_copy::CheckoutOptions;
+use crate::working_copy::SnapshotOptions;
+use crate::working_copy::SnapshotStats;
+use crate::working_copy::WorkingCopyStateError;
+use crate::working_copy::WorkspaceSnapshotError;
+use crate::workspace::default_working_copy_factories;
+use crate::workspace::Workspace;
+use crate::workspace::WorkspaceLoadError;
+
+/// Error from a [`Session`] operation.
+#[derive(Debug, Error)]
+pub enum SessionError {
+    /// Failed to load the workspace.
+    #[error(transparent)]
+    WorkspaceLoad(#[from] WorkspaceLoadError),
+    /// Failed to load the repo.
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+    /// Invalid configuration.
+    #[error(transparent)]
+    Config(#[from] ConfigGetError),
+    /// Backend error.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to rebase descendants of rewritten commits.
+    #[error(transparent)]
+    Rebase(#[from] RebaseError),
+    /// Error reading operations.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Error publishing an operation.
+    #[error(transparent)]
+    TransactionCommit(#[from] TransactionCommitError),
+    /// Error reading or writing the working copy state.
+    #[error(transparent)]
+    WorkingCopyState(#[from] WorkingCopyStateError),
+    /// Invalid `snapshot.auto-track` pattern.
+    #[error(transparent)]
+    AutoTracking(#[from] AutoTrackingError),
+    /// Failed to read the Git ignore files.
+    #[error(transparent)]
+    GitIgnore(#[from] GitIgnoreError),
+    /// Failed to update the working copy.
+    #[error(transparent)]
+    Checkout(#[from] CheckoutError),
+    /// Failed to create or edit the working-copy commit.
+    #[error(transparent)]
+    CheckOutCommit(#[from] CheckOutCommitError),
+    /// Attempted to rewrite the root commit.
+    #[error(transparent)]
+    RewriteRootCommit(#[from] RewriteRootCommit),
+    /// Failed to snapshot the working copy into the working-copy commit.
+    #[error(transparent)]
+    WorkspaceSnapshot(#[from] WorkspaceSnapshotError),
+    /// The workspace has been deleted from the repo.
+    #[error("Workspace {} has no working-copy commit", .0.as_str())]
+    WorkspaceMissingWorkingCopy(WorkspaceId),
+}
+
+/// Result of a [`Session`] operation.
+pub type SessionResult<T> = Result<T, SessionError>;
+
+/// How a file was changed in the working-copy commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileChangeKind {
+    /// The file didn't exist in the parent.
+    Added,
+    /// The file exists in both the parent and the working-copy commit.
+    Modified,
+    /// The file was deleted from the parent.
+    Removed,
+}
+
+/// File changed in the working-copy commit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileChange {
+    /// Path to the file.
+    pub path: RepoPathBuf,
+    /// How the file was changed.
+    pub kind: FileChangeKind,
+}
+
+/// State of the working copy returned by [`Session::status()`].
+#[derive(Clone, Debug)]
+pub struct SessionStatus {
+    /// The working-copy commit.
+    pub working_copy_commit: Commit,
+    /// Parents of the working-copy commit.
+    pub parents: Vec<Commit>,
+    /// Files changed in the working-copy commit compared to its parents,
+    /// sorted by path.
+    pub changed_files: Vec<FileChange>,
+    /// New files which weren't tracked by the last snapshot.
+    pub untracked_paths: Vec<RepoPathBuf>,
+}
+
+/// A workspace and the repo loaded at its latest operation.
+pub struct Session {
+    workspace: Workspace,
+    repo: Arc<ReadonlyRepo>,
+}
+
+impl Session {
+    /// Loads the workspace at `workspace_root` with the default backends and
+    /// working-copy implementations.
+    pub fn load(settings: &UserSettings, workspace_root: &Path) -> SessionResult<Self> {
+        let workspace = Workspace::load(
+            settings,
+            workspace_root,
+            &StoreFactories::default(),
+            &default_working_copy_factories(),
+        )?;
+        Self::from_workspace(workspace)
+    }
+
+    /// Creates session for an already loaded workspace.
+    pub fn from_workspace(workspace: Workspace) -> SessionResult<Self> {
+        let repo = workspace.repo_loader().load_at_head()?;
+        Ok(Session { workspace, repo })
+    }
+
+    /// The workspace this session operates on.
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// The repo as of the last operation done through this session.
+    pub fn repo(&self) -> &Arc<ReadonlyRepo> {
+        &self.repo
+    }
+
+    /// Settings for this session.
+    pub fn settings(&self) -> &UserSettings {
+        self.repo.settings()
+    }
+
+    /// Returns the working-copy commit as of the last operation. Call
+    /// [`Session::snapshot()`] first to include changes made on disk.
+    pub fn working_copy_commit(&self) -> SessionResult<Commit> {
+        get_wc_commit(self.repo.as_ref(), self.workspace.workspace_id())
+    }
+
+    /// Reloads the repo at head, and records changes made in the working copy
+    /// in the working-copy commit.
+    ///
+    /// New files are tracked and ignored as configured for the CLI. Unlike the
+    /// CLI, Git refs aren't exported.
+    pub fn snapshot(&mut self) -> SessionResult<SnapshotStats> {
+        let repo = self.workspace.repo_loader().load_at_head()?;
+        // There's no UI to report parse warnings to.
+        let auto_tracking_matcher =
+            auto_tracking_matcher(repo.settings(), &mut FilesetDiagnostics::new())?;
+        let options = SnapshotOptions::from_settings(
+            repo.settings(),
+            base_ignores(repo.store(), self.workspace.workspace_root())?,
+            &*auto_tracking_matcher,
+        )?;
+        let (repo, _wc_commit, stats) = self.workspace.snapshot(repo, &options, &HashMap::new())?;
+        self.repo = repo;
+        Ok(stats)
+    }
+
+    /// Snapshots the working copy, and returns the working-copy commit and the
+    /// files changed in it.
+    pub fn status(&mut self) -> SessionResult<SessionStatus> {
+        let stats = self.snapshot()?;
+        let working_copy_commit = self.working_copy_commit()?;
+        let parents: Vec<_> = working_copy_commit.parents().try_collect()?;
+        let parent_tree = merge_commit_trees(self.repo.as_ref(), &parents)?;
+        let tree = working_copy_commit.tree()?;
+        let mut changed_files = vec![];
+        let mut diff_stream = parent_tree.diff_stream(&tree, &EverythingMatcher);
+        async {
+            while let Some(entry) = diff_stream.next().await {
+                let (before, after) = entry.values?;
+                let kind = match (before.is_present(), after.is_present()) {
+                    (false, _) => FileChangeKind::Added,
+                    (true, true) => FileChangeKind::Modified,
+                    (true, false) => FileChangeKind::Removed,
+                };
+                changed_files.push(FileChange {
+                    path: entry.path,
+                    kind,
+                });
+            }
+            Ok::<(), BackendError>(())
+        }
+        .block_on()?;
+        Ok(SessionStatus {
+            working_copy_commit,
+            parents,
+            changed_files,
+            untracked_paths: stats.untracked_paths.into_keys().collect(),
+        })
+    }
+
+    /// Snapshots the working copy, and runs `f` in a new transaction. The
+    /// descendants of rewritten commits are rebased, and the working copy is
+    /// updated if the working-copy commit changed.
+    pub fn run_transaction<T>(
+        &mut self,
+        description: impl Into<String>,
+        f: impl FnOnce(&mut MutableRepo) -> SessionResult<T>,
+    ) -> SessionResult<T> {
+        self.snapshot()?;
+        let workspace_id = self.workspace.workspace_id().clone();
+        let old_wc_commit = get_wc_commit(self.repo.as_ref(), &workspace_id)?;
+        let mut tx = self.repo.start_transaction();
+        let value = f(tx.repo_mut())?;
+        tx.repo_mut().rebase_descendants()?;
+        self.repo = tx.commit(description)?;
+        let new_wc_commit = get_wc_commit(self.repo.as_ref(), &workspace_id)?;
+        if new_wc_commit.id() != old_wc_commit.id() {
+            let options = checkout_options(self.repo.settings())?;
+            self.workspace.check_out(
+                self.repo.op_id().clone(),
+                Some(old_wc_commit.tree_id()),
+                &new_wc_commit,
+                &options,
+            )?;
+        }
+        Ok(value)
+    }
+
+    /// Sets the description of the working-copy commit. Returns the rewritten
+    /// commit.
+    pub fn describe(&mut self, description: impl Into<String>) -> SessionResult<Commit> {
+        let description = description.into();
+        let workspace_id = self.workspace.workspace_id().clone();
+        self.run_transaction("describe working copy", |mut_repo| {
+            let wc_commit = get_wc_commit(&*mut_repo, &workspace_id)?;
+            let commit = mut_repo
+                .rewrite_commit(&wc_commit)
+                .set_description(description)
+                .write()?;
+            mut_repo.set_wc_commit(workspace_id, commit.id().clone())?;
+            Ok(commit)
+        })
+    }
+
+    /// Creates a new empty working-copy commit on top of the current one.
+    /// Returns the new commit.
+    pub fn new_child(&mut self) -> SessionResult<Commit> {
+        let workspace_id = self.workspace.workspace_id().clone();
+        self.run_transaction("new empty commit", |mut_repo| {
+            let wc_commit = get_wc_commit(&*mut_repo, &workspace_id)?;
+            Ok(mut_repo.check_out(workspace_id, &wc_commit)?)
+        })
+    }
+}
+
+fn get_wc_commit(repo: &dyn Repo, workspace_id: &WorkspaceId) -> SessionResult<Commit> {
+    let commit_id = repo
+        .view()
+        .get_wc_commit_id(workspace_id)
+        .ok_or_else(|| SessionError::WorkspaceMissingWorkingCopy(workspace_id.clone()))?;
+    Ok(repo.store().get_commit(commit_id)?)
+}
+
+#[cfg(feature = "git")]
+fn base_ignores(store: &Store, workspace_root: &Path) -> SessionResult<Arc<GitIgnoreFile>> {
+    Ok(crate::git::base_ignores(store, workspace_root)?)
+}
+
+#[cfg(not(feature = "git"))]
+fn base_ignores(_store: &Store, _workspace_root: &Path) -> SessionResult<Arc<GitIgnoreFile>> {
+    Ok(GitIgnoreFile::empty())
+}
+
+fn checkout_options(settings: &UserSettings) -> SessionResult<CheckoutOptions<'static>> {
+    Ok(CheckoutOptions::from_settings(settings)?)
+}
@@ -18,6 +18,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use chrono::DateTime;
 use rand::prelude::*;
@@ -28,14 +29,20 @@ use crate::backend::ChangeId;
 use crate::backend::Commit;
 use crate::backend::Signature;
 use crate::backend::Timestamp;
+use crate::clock::Clock;
+use crate::clock::FixedClock;
+use crate::clock::SystemClock;
 use crate::config::ConfigGetError;
 use crate::config::ConfigGetResultExt as _;
+use crate::config::ConfigLayer;
 use crate::config::ConfigTable;
 use crate::config::ConfigValue;
 use crate::config::StackedConfig;
 use crate::config::ToConfigNamePath;
 use crate::fmt_util::binary_prefix;
 use crate::fsmonitor::FsmonitorSettings;
+use crate::lock::LockManager;
+use crate::lock::LockMode;
 use crate::signing::SignBehavior;
 
 #[derive(Debug, Clone)]
@@ -43,6 +50,7 @@ pub struct UserSettings {
     config: Arc<StackedConfig>,
     data: Arc<UserSettingsData>,
     rng: Arc<JJRng>,
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
@@ -55,12 +63,20 @@ struct UserSettingsData {
     operation_username: String,
     signing_behavior: SignBehavior,
     signing_key: Option<String>,
+    trailer_settings: TrailerSettings,
+    rewrite_timestamps: RewriteTimestamps,
+    merge_detect_renames: bool,
+    merge_reuse_resolutions: bool,
+    lock_mode: LockMode,
+    lock_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GitSettings {
     pub auto_local_bookmark: bool,
     pub abandon_unreachable_commits: bool,
+    pub mirror_refs: bool,
+    pub push_bookmark_prefix: String,
     pub subprocess: bool,
     pub executable_path: PathBuf,
 }
@@ -70,6 +86,8 @@ impl GitSettings {
         Ok(GitSettings {
             auto_local_bookmark: settings.get_bool("git.auto-local-bookmark")?,
             abandon_unreachable_commits: settings.get_bool("git.abandon-unreachable-commits")?,
+            mirror_refs: settings.get_bool("git.mirror-refs")?,
+            push_bookmark_prefix: settings.get_string("git.push-bookmark-prefix")?,
             subprocess: settings.get_bool("git.subprocess")?,
             executable_path: settings.get("git.executable-path")?,
         })
@@ -81,6 +99,8 @@ impl Default for GitSettings {
         GitSettings {
             auto_local_bookmark: false,
             abandon_unreachable_commits: true,
+            mirror_refs: false,
+            push_bookmark_prefix: "push-".to_owned(),
             subprocess: true,
             executable_path: PathBuf::from("git"),
         }
@@ -114,6 +134,32 @@ impl SignSettings {
     }
 }
 
+/// Trailers to be added to non-empty commit descriptions when commits are
+/// written.
+#[derive(Debug, Clone, Default)]
+pub struct TrailerSettings {
+    /// Whether to add a `Signed-off-by` trailer with the committer.
+    pub signed_off_by: bool,
+    /// Whether to add a Gerrit `Change-Id` trailer derived from the change id.
+    pub change_id: bool,
+}
+
+/// How author and committer timestamps are updated when a commit is
+/// rewritten.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RewriteTimestamps {
+    /// Set the committer timestamp to the current time. The author timestamp
+    /// is kept, except that it is also reset for discardable commits authored
+    /// by the committer.
+    #[default]
+    RefreshCommitter,
+    /// Keep both the author and committer timestamps of the predecessor.
+    Keep,
+    /// Set both the author and committer timestamps to the current time.
+    ResetAuthor,
+}
+
 fn to_timestamp(value: ConfigValue) -> Result<Timestamp, Box<dyn std::error::Error + Send + Sync>> {
     // Since toml_edit::Datetime isn't the date-time type used across our code
     // base, we accept both string and date-time types.
@@ -132,10 +178,18 @@ fn to_timestamp(value: ConfigValue) -> Result<Timestamp, Box<dyn std::error::Err
 impl UserSettings {
     pub fn from_config(config: StackedConfig) -> Result<Self, ConfigGetError> {
         let rng_seed = config.get::<u64>("debug.randomness-seed").optional()?;
-        Self::from_config_and_rng(config, Arc::new(JJRng::new(rng_seed)))
-    }
-
-    fn from_config_and_rng(config: StackedConfig, rng: Arc<JJRng>) -> Result<Self, ConfigGetError> {
+        Self::from_config_and_state(
+            config,
+            Arc::new(JJRng::new(rng_seed)),
+            Arc::new(SystemClock),
+        )
+    }
+
+    fn from_config_and_state(
+        config: StackedConfig,
+        rng: Arc<JJRng>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, ConfigGetError> {
         let user_name = config.get("user.name")?;
         let user_email = config.get("user.email")?;
         let commit_timestamp = config
@@ -148,6 +202,18 @@ impl UserSettings {
         let operation_username = config.get("operation.username")?;
         let signing_behavior = config.get("signing.behavior")?;
         let signing_key = config.get("signing.key").optional()?;
+        let trailer_settings = TrailerSettings {
+            signed_off_by: config.get("commit-trailers.signed-off-by")?,
+            change_id: config.get("commit-trailers.change-id")?,
+        };
+        let rewrite_timestamps = config.get("rewrite.timestamps")?;
+        let merge_detect_renames = config.get("merge.detect-renames")?;
+        let merge_reuse_resolutions = config.get("merge.reuse-resolutions")?;
+        let lock_mode = config.get("core.lock-mode")?;
+        let lock_timeout = config
+            .get::<u64>("core.lock-timeout")
+            .optional()?
+            .map(Duration::from_secs);
         let data = UserSettingsData {
             user_name,
             user_email,
@@ -157,11 +223,18 @@ impl UserSettings {
             operation_username,
             signing_behavior,
             signing_key,
+            trailer_settings,
+            rewrite_timestamps,
+            merge_detect_renames,
+            merge_reuse_resolutions,
+            lock_mode,
+            lock_timeout,
         };
         Ok(UserSettings {
             config: Arc::new(config),
             data: Arc::new(data),
             rng,
+            clock,
         })
     }
 
@@ -170,13 +243,52 @@ impl UserSettings {
     /// This ensures that no duplicated change IDs are generated within the
     /// current process. New `debug.randomness-seed` value is ignored.
     pub fn with_new_config(&self, config: StackedConfig) -> Result<Self, ConfigGetError> {
-        Self::from_config_and_rng(config, self.rng.clone())
+        Self::from_config_and_state(config, self.rng.clone(), self.clock.clone())
+    }
+
+    /// Returns new settings which read the current time from the `clock`.
+    ///
+    /// The `debug.commit-timestamp` and `debug.operation-timestamp` values
+    /// still take precedence over the clock.
+    pub fn with_clock(&self, clock: Arc<dyn Clock>) -> Self {
+        UserSettings {
+            clock,
+            ..self.clone()
+        }
+    }
+
+    /// Returns new settings with the `layer` added on top of the current
+    /// config.
+    ///
+    /// This can be used to override variables without writing config files,
+    /// in the same way as `--config NAME=VALUE` arguments. The internal state
+    /// is retained as in [`UserSettings::with_new_config()`].
+    pub fn with_config_layer(
+        &self,
+        layer: impl Into<Arc<ConfigLayer>>,
+    ) -> Result<Self, ConfigGetError> {
+        let mut config = self.config().clone();
+        config.add_layer(layer);
+        self.with_new_config(config)
     }
 
     pub fn get_rng(&self) -> Arc<JJRng> {
         self.rng.clone()
     }
 
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Returns the clock for timestamps of new operations, which is fixed if
+    /// `debug.operation-timestamp` is set.
+    pub fn operation_clock(&self) -> Arc<dyn Clock> {
+        match self.data.operation_timestamp {
+            Some(timestamp) => Arc::new(FixedClock::new(timestamp)),
+            None => self.clock.clone(),
+        }
+    }
+
     pub fn user_name(&self) -> &str {
         &self.data.user_name
     }
@@ -213,7 +325,10 @@ impl UserSettings {
     }
 
     pub fn signature(&self) -> Signature {
-        let timestamp = self.data.commit_timestamp.unwrap_or_else(Timestamp::now);
+        let timestamp = self
+            .data
+            .commit_timestamp
+            .unwrap_or_else(|| self.clock.now());
         Signature {
             name: self.user_name().to_owned(),
             email: self.user_email().to_owned(),
@@ -246,6 +361,45 @@ impl UserSettings {
             key: self.data.signing_key.clone(),
         }
     }
+
+    pub fn trailer_settings(&self) -> TrailerSettings {
+        self.data.trailer_settings.clone()
+    }
+
+    pub fn rewrite_timestamps(&self) -> RewriteTimestamps {
+        self.data.rewrite_timestamps
+    }
+
+    /// Whether merging trees follows files renamed on one side.
+    pub fn merge_detect_renames(&self) -> bool {
+        self.data.merge_detect_renames
+    }
+
+    /// Whether resolved conflicts are recorded, and resolved the same way
+    /// when they reappear while rebasing.
+    pub fn merge_reuse_resolutions(&self) -> bool {
+        self.data.merge_reuse_resolutions
+    }
+
+    /// How lock files are acquired, and how content-addressed files are
+    /// written.
+    pub fn lock_mode(&self) -> LockMode {
+        self.data.lock_mode
+    }
+
+    /// How long to wait for a lock held by another process before failing.
+    /// `None` means to wait forever.
+    pub fn lock_timeout(&self) -> Option<Duration> {
+        self.data.lock_timeout
+    }
+
+    /// Returns a lock manager which acquires locks in the configured mode and
+    /// with the configured timeout.
+    pub fn lock_manager(&self) -> LockManager {
+        LockManager::new()
+            .with_mode(self.lock_mode())
+            .with_timeout(self.lock_timeout())
+    }
 }
 
 /// General-purpose accessors.
@@ -397,6 +551,74 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
+    use crate::config::ConfigSource;
+
+    #[test]
+    fn test_with_config_layer() {
+        let mut config = StackedConfig::with_defaults();
+        config.add_layer(
+            ConfigLayer::parse(
+                ConfigSource::User,
+                "user.name = 'Test User'\nuser.email = 'test.user@example.com'",
+            )
+            .unwrap(),
+        );
+        let settings = UserSettings::from_config(config).unwrap();
+
+        let mut layer = ConfigLayer::empty(ConfigSource::CommandArg);
+        layer
+            .set_value("user.email", "override@example.com")
+            .unwrap();
+        layer.set_value("foo.bar", 1).unwrap();
+        let new_settings = settings.with_config_layer(layer).unwrap();
+        assert_eq!(new_settings.user_name(), "Test User");
+        assert_eq!(new_settings.user_email(), "override@example.com");
+        assert_eq!(new_settings.get_int("foo.bar").unwrap(), 1);
+        assert!(Arc::ptr_eq(&new_settings.get_rng(), &settings.get_rng()));
+        // The original settings are unchanged
+        assert_eq!(settings.user_email(), "test.user@example.com");
+
+        // Invalid override is reported
+        let mut layer = ConfigLayer::empty(ConfigSource::CommandArg);
+        layer.set_value("user.email", 0).unwrap();
+        assert_matches!(
+            settings.with_config_layer(layer),
+            Err(ConfigGetError::Type { name, .. }) if name == "user.email"
+        );
+    }
+
+    #[test]
+    fn test_lock_timeout() {
+        let mut config = StackedConfig::with_defaults();
+        let settings = UserSettings::from_config(config.clone()).unwrap();
+        assert_eq!(settings.lock_timeout(), None);
+
+        config.add_layer(ConfigLayer::parse(ConfigSource::User, "core.lock-timeout = 30").unwrap());
+        let settings = UserSettings::from_config(config).unwrap();
+        assert_eq!(settings.lock_timeout(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_lock_manager() {
+        let mut config = StackedConfig::with_defaults();
+        let settings = UserSettings::from_config(config.clone()).unwrap();
+        assert_eq!(settings.lock_manager().mode(), LockMode::Native);
+        assert_eq!(settings.lock_manager().timeout(), None);
+
+        config.add_layer(
+            ConfigLayer::parse(
+                ConfigSource::User,
+                "core.lock-mode = 'nfs'\ncore.lock-timeout = 30",
+            )
+            .unwrap(),
+        );
+        let settings = UserSettings::from_config(config).unwrap();
+        assert_eq!(settings.lock_manager().mode(), LockMode::Nfs);
+        assert_eq!(
+            settings.lock_manager().timeout(),
+            Some(Duration::from_secs(30))
+        );
+    }
 
     #[test]
     fn byte_size_parse() {
@@ -27,7 +27,9 @@ use thiserror::Error;
 use crate::backend::BackendInitError;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
-use crate::lock::FileLock;
+use crate::lock::LockKind;
+use crate::lock::LockManager;
+use crate::lock::ManagedLock;
 use crate::object_id::ObjectId;
 use crate::op_heads_store::OpHeadsStore;
 use crate::op_heads_store::OpHeadsStoreError;
@@ -47,6 +49,7 @@ impl From<SimpleOpHeadsStoreInitError> for BackendInitError {
 
 pub struct SimpleOpHeadsStore {
     dir: PathBuf,
+    lock_manager: LockManager,
 }
 
 impl Debug for SimpleOpHeadsStore {
@@ -65,12 +68,24 @@ impl SimpleOpHeadsStore {
     pub fn init(dir: &Path) -> Result<Self, SimpleOpHeadsStoreInitError> {
         let op_heads_dir = dir.join("heads");
         fs::create_dir(&op_heads_dir).context(&op_heads_dir)?;
-        Ok(Self { dir: op_heads_dir })
+        Ok(Self {
+            dir: op_heads_dir,
+            lock_manager: LockManager::new(),
+        })
     }
 
     pub fn load(dir: &Path) -> Self {
         let op_heads_dir = dir.join("heads");
-        Self { dir: op_heads_dir }
+        Self {
+            dir: op_heads_dir,
+            lock_manager: LockManager::new(),
+        }
+    }
+
+    /// Sets the lock manager used to take the op-heads lock.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
     }
 
     fn add_op_head(&self, id: &OperationId) -> io::Result<()> {
@@ -93,7 +108,7 @@ impl SimpleOpHeadsStore {
 }
 
 struct SimpleOpHeadsStoreLock {
-    _lock: FileLock,
+    _lock: ManagedLock,
 }
 
 impl OpHeadsStoreLock for SimpleOpHeadsStoreLock {}
@@ -149,7 +164,9 @@ impl OpHeadsStore for SimpleOpHeadsStore {
     }
 
     fn lock(&self) -> Result<Box<dyn OpHeadsStoreLock + '_>, OpHeadsStoreError> {
-        let lock = FileLock::lock(self.dir.join("lock"))
+        let lock = self
+            .lock_manager
+            .lock(LockKind::OpHeads, self.dir.join("lock"))
             .map_err(|err| OpHeadsStoreError::Lock(err.into()))?;
         Ok(Box::new(SimpleOpHeadsStoreLock { _lock: lock }))
     }
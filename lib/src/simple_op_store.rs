@@ -25,6 +25,7 @@ use std::io::ErrorKind;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::slice;
 use std::time::SystemTime;
 
 use itertools::Itertools as _;
@@ -36,34 +37,41 @@ use crate::backend::BackendInitError;
 use crate::backend::CommitId;
 use crate::backend::MillisSinceEpoch;
 use crate::backend::Timestamp;
+use crate::backend::TreeId;
 use crate::content_hash::blake2b_hash;
 use crate::dag_walk;
 use crate::file_util::persist_content_addressed_temp_file;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
+use crate::lock::LockManager;
 use crate::merge::Merge;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId;
 use crate::object_id::PrefixResolution;
 use crate::op_store;
+use crate::op_store::BisectState;
 use crate::op_store::OpStore;
+use crate::op_store::OpStoreEncoding;
 use crate::op_store::OpStoreError;
 use crate::op_store::OpStoreResult;
 use crate::op_store::Operation;
 use crate::op_store::OperationId;
 use crate::op_store::OperationMetadata;
+use crate::op_store::PendingRewrite;
 use crate::op_store::RefTarget;
+use crate::op_store::RemoteConfig;
 use crate::op_store::RemoteRef;
 use crate::op_store::RemoteRefState;
 use crate::op_store::RemoteView;
 use crate::op_store::RootOperationData;
+use crate::op_store::StoppedRebase;
 use crate::op_store::View;
 use crate::op_store::ViewId;
 use crate::op_store::WorkspaceId;
 
 // BLAKE2b-512 hash length in bytes
-const OPERATION_ID_LENGTH: usize = 64;
-const VIEW_ID_LENGTH: usize = 64;
+pub(crate) const OPERATION_ID_LENGTH: usize = 64;
+pub(crate) const VIEW_ID_LENGTH: usize = 64;
 
 /// Error that may occur during [`SimpleOpStore`] initialization.
 #[derive(Debug, Error)]
@@ -76,12 +84,45 @@ impl From<SimpleOpStoreInitError> for BackendInitError {
     }
 }
 
+/// The protobuf encoding of operations and views used by [`SimpleOpStore`] by
+/// default.
+#[derive(Debug)]
+pub struct ProtoOpStoreEncoding;
+
+impl OpStoreEncoding for ProtoOpStoreEncoding {
+    fn store_name(&self) -> &'static str {
+        SimpleOpStore::name()
+    }
+
+    fn encode_view(&self, view: &View) -> Vec<u8> {
+        encode_view(view)
+    }
+
+    fn decode_view(&self, buf: &[u8]) -> Result<View, Box<dyn std::error::Error + Send + Sync>> {
+        decode_view(buf)
+    }
+
+    fn encode_operation(&self, operation: &Operation) -> Vec<u8> {
+        encode_operation(operation)
+    }
+
+    fn decode_operation(
+        &self,
+        buf: &[u8],
+    ) -> Result<Operation, Box<dyn std::error::Error + Send + Sync>> {
+        decode_operation(buf)
+    }
+}
+
+/// Op store which keeps each operation and view in a file named by its id.
 #[derive(Debug)]
 pub struct SimpleOpStore {
     path: PathBuf,
     root_data: RootOperationData,
     root_operation_id: OperationId,
     root_view_id: ViewId,
+    encoding: Box<dyn OpStoreEncoding>,
+    lock_manager: LockManager,
 }
 
 impl SimpleOpStore {
@@ -94,25 +135,56 @@ impl SimpleOpStore {
         store_path: &Path,
         root_data: RootOperationData,
     ) -> Result<Self, SimpleOpStoreInitError> {
-        let store = Self::new(store_path, root_data);
+        Self::init_with_encoding(store_path, root_data, Box::new(ProtoOpStoreEncoding))
+    }
+
+    /// Creates an empty OpStore which stores objects in the given `encoding`.
+    /// Returns error if it already exists.
+    pub fn init_with_encoding(
+        store_path: &Path,
+        root_data: RootOperationData,
+        encoding: Box<dyn OpStoreEncoding>,
+    ) -> Result<Self, SimpleOpStoreInitError> {
+        let store = Self::new(store_path, root_data, encoding);
         store.init_base_dirs()?;
         Ok(store)
     }
 
     /// Load an existing OpStore
     pub fn load(store_path: &Path, root_data: RootOperationData) -> Self {
-        Self::new(store_path, root_data)
+        Self::load_with_encoding(store_path, root_data, Box::new(ProtoOpStoreEncoding))
     }
 
-    fn new(store_path: &Path, root_data: RootOperationData) -> Self {
+    /// Load an existing OpStore which stores objects in the given `encoding`.
+    pub fn load_with_encoding(
+        store_path: &Path,
+        root_data: RootOperationData,
+        encoding: Box<dyn OpStoreEncoding>,
+    ) -> Self {
+        Self::new(store_path, root_data, encoding)
+    }
+
+    fn new(
+        store_path: &Path,
+        root_data: RootOperationData,
+        encoding: Box<dyn OpStoreEncoding>,
+    ) -> Self {
         SimpleOpStore {
             path: store_path.to_path_buf(),
             root_data,
             root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
             root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+            encoding,
+            lock_manager: LockManager::new(),
         }
     }
 
+    /// Sets the lock manager whose mode decides how objects are written.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
     fn init_base_dirs(&self) -> Result<(), PathError> {
         for dir in [self.views_dir(), self.operations_dir()] {
             fs::create_dir(&dir).context(&dir)?;
@@ -135,7 +207,7 @@ impl OpStore for SimpleOpStore {
     }
 
     fn name(&self) -> &str {
-        Self::name()
+        self.encoding.store_name()
     }
 
     fn root_operation_id(&self) -> &OperationId {
@@ -150,9 +222,9 @@ impl OpStore for SimpleOpStore {
         let path = self.views_dir().join(id.hex());
         let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
 
-        let proto = crate::protos::op_store::View::decode(&*buf)
-            .map_err(|err| to_read_error(err.into(), id))?;
-        Ok(view_from_proto(proto))
+        self.encoding
+            .decode_view(&buf)
+            .map_err(|err| to_read_error(err, id))
     }
 
     fn write_view(&self, view: &View) -> OpStoreResult<ViewId> {
@@ -160,16 +232,19 @@ impl OpStore for SimpleOpStore {
         let temp_file =
             NamedTempFile::new_in(&dir).map_err(|err| io_to_write_error(err, "view"))?;
 
-        let proto = view_to_proto(view);
         temp_file
             .as_file()
-            .write_all(&proto.encode_to_vec())
+            .write_all(&self.encoding.encode_view(view))
             .map_err(|err| io_to_write_error(err, "view"))?;
 
         let id = ViewId::new(blake2b_hash(view).to_vec());
 
-        persist_content_addressed_temp_file(temp_file, dir.join(id.hex()))
-            .map_err(|err| io_to_write_error(err, "view"))?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            dir.join(id.hex()),
+            self.lock_manager.mode(),
+        )
+        .map_err(|err| io_to_write_error(err, "view"))?;
         Ok(id)
     }
 
@@ -181,10 +256,10 @@ impl OpStore for SimpleOpStore {
         let path = self.operations_dir().join(id.hex());
         let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
 
-        let proto = crate::protos::op_store::Operation::decode(&*buf)
-            .map_err(|err| to_read_error(err.into(), id))?;
-        let mut operation =
-            operation_from_proto(proto).map_err(|err| to_read_error(err.into(), id))?;
+        let mut operation = self
+            .encoding
+            .decode_operation(&buf)
+            .map_err(|err| to_read_error(err, id))?;
         if operation.parents.is_empty() {
             // Repos created before we had the root operation will have an operation without
             // parents.
@@ -199,16 +274,19 @@ impl OpStore for SimpleOpStore {
         let temp_file =
             NamedTempFile::new_in(&dir).map_err(|err| io_to_write_error(err, "operation"))?;
 
-        let proto = operation_to_proto(operation);
         temp_file
             .as_file()
-            .write_all(&proto.encode_to_vec())
+            .write_all(&self.encoding.encode_operation(operation))
             .map_err(|err| io_to_write_error(err, "operation"))?;
 
         let id = OperationId::new(blake2b_hash(operation).to_vec());
 
-        persist_content_addressed_temp_file(temp_file, dir.join(id.hex()))
-            .map_err(|err| io_to_write_error(err, "operation"))?;
+        persist_content_addressed_temp_file(
+            temp_file,
+            dir.join(id.hex()),
+            self.lock_manager.mode(),
+        )
+        .map_err(|err| io_to_write_error(err, "operation"))?;
         Ok(id)
     }
 
@@ -371,10 +449,38 @@ fn io_to_write_error(err: std::io::Error, object_type: &'static str) -> OpStoreE
     }
 }
 
+/// Encodes `view` in the protobuf format used by [`SimpleOpStore`].
+pub(crate) fn encode_view(view: &View) -> Vec<u8> {
+    view_to_proto(view).encode_to_vec()
+}
+
+/// Decodes view encoded by [`encode_view()`].
+pub(crate) fn decode_view(buf: &[u8]) -> Result<View, Box<dyn std::error::Error + Send + Sync>> {
+    let proto = crate::protos::op_store::View::decode(buf)?;
+    Ok(view_from_proto(proto)?)
+}
+
+/// Encodes `operation` in the protobuf format used by [`SimpleOpStore`].
+pub(crate) fn encode_operation(operation: &Operation) -> Vec<u8> {
+    operation_to_proto(operation).encode_to_vec()
+}
+
+/// Decodes operation encoded by [`encode_operation()`].
+pub(crate) fn decode_operation(
+    buf: &[u8],
+) -> Result<Operation, Box<dyn std::error::Error + Send + Sync>> {
+    let proto = crate::protos::op_store::Operation::decode(buf)?;
+    Ok(operation_from_proto(proto)?)
+}
+
 #[derive(Debug, Error)]
 enum PostDecodeError {
     #[error("Invalid hash length (expected {expected} bytes, got {actual} bytes)")]
     InvalidHashLength { expected: usize, actual: usize },
+    #[error("Missing ref target value")]
+    MissingRefTargetValue,
+    #[error("Invalid ref conflict ({adds} adds, {removes} removes)")]
+    InvalidRefConflict { adds: usize, removes: usize },
 }
 
 fn operation_id_from_proto(bytes: Vec<u8>) -> Result<OperationId, PostDecodeError> {
@@ -424,6 +530,17 @@ fn operation_metadata_to_proto(
         username: metadata.username.clone(),
         is_snapshot: metadata.is_snapshot,
         tags: metadata.tags.clone(),
+        working_copy_tree_ids: metadata
+            .working_copy_tree_ids
+            .iter()
+            .flat_map(|ids| ids.iter())
+            .map(|id| id.to_bytes())
+            .collect(),
+        includes_snapshot: metadata.includes_snapshot,
+        stopped_rebase: metadata
+            .stopped_rebase
+            .as_ref()
+            .map(stopped_rebase_to_proto),
     }
 }
 
@@ -440,6 +557,67 @@ fn operation_metadata_from_proto(
         username: proto.username,
         is_snapshot: proto.is_snapshot,
         tags: proto.tags,
+        // An even number of terms can't be a valid merge, so it's ignored.
+        working_copy_tree_ids: (proto.working_copy_tree_ids.len() % 2 == 1).then(|| {
+            Merge::from_vec(
+                proto
+                    .working_copy_tree_ids
+                    .into_iter()
+                    .map(TreeId::new)
+                    .collect_vec(),
+            )
+        }),
+        includes_snapshot: proto.includes_snapshot,
+        stopped_rebase: proto.stopped_rebase.map(stopped_rebase_from_proto),
+    }
+}
+
+fn stopped_rebase_to_proto(
+    stopped_rebase: &StoppedRebase,
+) -> crate::protos::op_store::StoppedRebase {
+    use crate::protos::op_store::stopped_rebase::rewrite::Kind;
+    let rewrites = stopped_rebase
+        .rewrites
+        .iter()
+        .map(|(old_id, rewrite)| {
+            let (kind, new_ids) = match rewrite {
+                PendingRewrite::Rewritten(new_id) => (Kind::Rewritten, slice::from_ref(new_id)),
+                PendingRewrite::Divergent(new_ids) => (Kind::Divergent, new_ids.as_slice()),
+                PendingRewrite::Abandoned(new_ids) => (Kind::Abandoned, new_ids.as_slice()),
+            };
+            crate::protos::op_store::stopped_rebase::Rewrite {
+                old_commit_id: old_id.to_bytes(),
+                kind: kind as i32,
+                new_commit_ids: new_ids.iter().map(|id| id.to_bytes()).collect(),
+            }
+        })
+        .collect();
+    crate::protos::op_store::StoppedRebase {
+        conflicted_commit_id: stopped_rebase.conflicted_commit_id.to_bytes(),
+        rewrites,
+    }
+}
+
+fn stopped_rebase_from_proto(proto: crate::protos::op_store::StoppedRebase) -> StoppedRebase {
+    use crate::protos::op_store::stopped_rebase::rewrite::Kind;
+    let rewrites = proto
+        .rewrites
+        .into_iter()
+        .filter_map(|rewrite| {
+            let kind = rewrite.kind();
+            let mut new_ids = rewrite.new_commit_ids.into_iter().map(CommitId::new);
+            let pending = match kind {
+                // A rewrite without the new commit is ignored.
+                Kind::Rewritten => PendingRewrite::Rewritten(new_ids.next()?),
+                Kind::Divergent => PendingRewrite::Divergent(new_ids.collect()),
+                Kind::Abandoned => PendingRewrite::Abandoned(new_ids.collect()),
+            };
+            Some((CommitId::new(rewrite.old_commit_id), pending))
+        })
+        .collect();
+    StoppedRebase {
+        conflicted_commit_id: CommitId::new(proto.conflicted_commit_id),
+        rewrites,
     }
 }
 
@@ -504,10 +682,28 @@ fn view_to_proto(view: &View) -> crate::protos::op_store::View {
 
     proto.git_head = ref_target_to_proto(&view.git_head);
 
+    for (name, commit_id) in &view.anonymous_heads {
+        proto
+            .anonymous_heads
+            .insert(name.clone(), commit_id.to_bytes());
+    }
+
+    if !view.bisect_state.is_empty() {
+        proto.bisect_state = Some(bisect_state_to_proto(&view.bisect_state));
+    }
+
+    for (name, config) in &view.remotes {
+        let RemoteConfig { url } = config;
+        proto.remotes.insert(
+            name.clone(),
+            crate::protos::op_store::RemoteConfig { url: url.clone() },
+        );
+    }
+
     proto
 }
 
-fn view_from_proto(proto: crate::protos::op_store::View) -> View {
+fn view_from_proto(proto: crate::protos::op_store::View) -> Result<View, PostDecodeError> {
     // TODO: validate commit id length?
     let mut view = View::empty();
     // For compatibility with old repos before we had support for multiple working
@@ -525,18 +721,18 @@ fn view_from_proto(proto: crate::protos::op_store::View) -> View {
         view.head_ids.insert(CommitId::new(head_id_bytes));
     }
 
-    let (local_bookmarks, remote_views) = bookmark_views_from_proto_legacy(proto.bookmarks);
+    let (local_bookmarks, remote_views) = bookmark_views_from_proto_legacy(proto.bookmarks)?;
     view.local_bookmarks = local_bookmarks;
     view.remote_views = remote_views;
 
     for tag_proto in proto.tags {
         view.tags
-            .insert(tag_proto.name, ref_target_from_proto(tag_proto.target));
+            .insert(tag_proto.name, ref_target_from_proto(tag_proto.target)?);
     }
 
     for git_ref in proto.git_refs {
         let target = if git_ref.target.is_some() {
-            ref_target_from_proto(git_ref.target)
+            ref_target_from_proto(git_ref.target)?
         } else {
             // Legacy format
             RefTarget::normal(CommitId::new(git_ref.commit_id))
@@ -546,12 +742,42 @@ fn view_from_proto(proto: crate::protos::op_store::View) -> View {
 
     #[expect(deprecated)]
     if proto.git_head.is_some() {
-        view.git_head = ref_target_from_proto(proto.git_head);
+        view.git_head = ref_target_from_proto(proto.git_head)?;
     } else if !proto.git_head_legacy.is_empty() {
         view.git_head = RefTarget::normal(CommitId::new(proto.git_head_legacy));
     }
 
-    view
+    for (name, commit_id) in proto.anonymous_heads {
+        view.anonymous_heads.insert(name, CommitId::new(commit_id));
+    }
+
+    if let Some(bisect_state) = proto.bisect_state {
+        view.bisect_state = bisect_state_from_proto(bisect_state);
+    }
+
+    for (name, config) in proto.remotes {
+        view.remotes.insert(name, RemoteConfig { url: config.url });
+    }
+
+    Ok(view)
+}
+
+fn bisect_state_to_proto(state: &BisectState) -> crate::protos::op_store::BisectState {
+    let to_proto = |ids: &[CommitId]| ids.iter().map(|id| id.to_bytes()).collect();
+    crate::protos::op_store::BisectState {
+        good_commit_ids: to_proto(&state.good_commits),
+        bad_commit_ids: to_proto(&state.bad_commits),
+        skipped_commit_ids: to_proto(&state.skipped_commits),
+    }
+}
+
+fn bisect_state_from_proto(proto: crate::protos::op_store::BisectState) -> BisectState {
+    let from_proto = |ids: Vec<Vec<u8>>| ids.into_iter().map(CommitId::new).collect();
+    BisectState {
+        good_commits: from_proto(proto.good_commit_ids),
+        bad_commits: from_proto(proto.bad_commit_ids),
+        skipped_commits: from_proto(proto.skipped_commit_ids),
+    }
 }
 
 fn bookmark_views_to_proto_legacy(
@@ -581,13 +807,14 @@ fn bookmark_views_to_proto_legacy(
         .collect()
 }
 
+#[expect(clippy::type_complexity)]
 fn bookmark_views_from_proto_legacy(
     bookmarks_legacy: Vec<crate::protos::op_store::Bookmark>,
-) -> (BTreeMap<String, RefTarget>, BTreeMap<String, RemoteView>) {
+) -> Result<(BTreeMap<String, RefTarget>, BTreeMap<String, RemoteView>), PostDecodeError> {
     let mut local_bookmarks: BTreeMap<String, RefTarget> = BTreeMap::new();
     let mut remote_views: BTreeMap<String, RemoteView> = BTreeMap::new();
     for bookmark_proto in bookmarks_legacy {
-        let local_target = ref_target_from_proto(bookmark_proto.local_target);
+        let local_target = ref_target_from_proto(bookmark_proto.local_target)?;
         for remote_bookmark in bookmark_proto.remote_bookmarks {
             let state = remote_ref_state_from_proto(remote_bookmark.state).unwrap_or_else(|| {
                 // If local bookmark doesn't exist, we assume that the remote bookmark hasn't
@@ -613,7 +840,7 @@ fn bookmark_views_from_proto_legacy(
             });
             let remote_view = remote_views.entry(remote_bookmark.remote_name).or_default();
             let remote_ref = RemoteRef {
-                target: ref_target_from_proto(remote_bookmark.target),
+                target: ref_target_from_proto(remote_bookmark.target)?,
                 state,
             };
             remote_view
@@ -624,7 +851,7 @@ fn bookmark_views_from_proto_legacy(
             local_bookmarks.insert(bookmark_proto.name, local_target);
         }
     }
-    (local_bookmarks, remote_views)
+    Ok((local_bookmarks, remote_views))
 }
 
 fn ref_target_to_proto(value: &RefTarget) -> Option<crate::protos::op_store::RefTarget> {
@@ -671,14 +898,17 @@ fn ref_target_to_proto_legacy(value: &RefTarget) -> Option<crate::protos::op_sto
     }
 }
 
-fn ref_target_from_proto(maybe_proto: Option<crate::protos::op_store::RefTarget>) -> RefTarget {
+fn ref_target_from_proto(
+    maybe_proto: Option<crate::protos::op_store::RefTarget>,
+) -> Result<RefTarget, PostDecodeError> {
     // TODO: Delete legacy format handling when we decide to drop support for views
     // saved by jj <= 0.8.
     let Some(proto) = maybe_proto else {
         // Legacy absent id
-        return RefTarget::absent();
+        return Ok(RefTarget::absent());
     };
-    match proto.value.unwrap() {
+    let value = proto.value.ok_or(PostDecodeError::MissingRefTargetValue)?;
+    let target = match value {
         crate::protos::op_store::ref_target::Value::CommitId(id) => {
             // Legacy non-conflicting id
             RefTarget::normal(CommitId::new(id))
@@ -691,13 +921,20 @@ fn ref_target_from_proto(maybe_proto: Option<crate::protos::op_store::RefTarget>
             RefTarget::from_legacy_form(removes, adds)
         }
         crate::protos::op_store::ref_target::Value::Conflict(conflict) => {
+            if conflict.adds.len() != conflict.removes.len() + 1 {
+                return Err(PostDecodeError::InvalidRefConflict {
+                    adds: conflict.adds.len(),
+                    removes: conflict.removes.len(),
+                });
+            }
             let term_from_proto =
                 |term: crate::protos::op_store::ref_conflict::Term| term.value.map(CommitId::new);
             let removes = conflict.removes.into_iter().map(term_from_proto);
             let adds = conflict.adds.into_iter().map(term_from_proto);
             RefTarget::from_merge(Merge::from_removes_adds(removes, adds))
         }
-    }
+    };
+    Ok(target)
 }
 
 fn remote_ref_state_to_proto(state: RemoteRefState) -> Option<i32> {
@@ -719,6 +956,7 @@ fn remote_ref_state_from_proto(proto_value: Option<i32>) -> Option<RemoteRefStat
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
     use insta::assert_snapshot;
     use itertools::Itertools as _;
     use maplit::btreemap;
@@ -751,7 +989,7 @@ mod tests {
         let default_wc_commit_id = CommitId::from_hex("abc111");
         let test_wc_commit_id = CommitId::from_hex("abc222");
         View {
-            head_ids: hashset! {head_id1, head_id2},
+            head_ids: hashset! {head_id1, head_id2.clone()},
             local_bookmarks: btreemap! {
                 "main".to_string() => bookmark_main_local_target,
             },
@@ -775,6 +1013,15 @@ mod tests {
                 WorkspaceId::default() => default_wc_commit_id,
                 WorkspaceId::new("test".to_string()) => test_wc_commit_id,
             },
+            anonymous_heads: btreemap! {
+                "head-kkkkkkkk".to_string() => head_id2.clone(),
+            },
+            bisect_state: BisectState::default(),
+            remotes: btreemap! {
+                "origin".to_string() => RemoteConfig {
+                    url: "https://example.com/repo.git".to_string(),
+                },
+            },
         }
     }
 
@@ -807,6 +1054,9 @@ mod tests {
                     "key1".to_string() => "value1".to_string(),
                     "key2".to_string() => "value2".to_string(),
                 },
+                working_copy_tree_ids: None,
+                includes_snapshot: false,
+                stopped_rebase: None,
             },
         }
     }
@@ -816,6 +1066,16 @@ mod tests {
         // Test exact output so we detect regressions in compatibility
         assert_snapshot!(
             ViewId::new(blake2b_hash(&create_view()).to_vec()).hex(),
+            @"1888f7840c255a23dcab105be2b46618bb727a4291c07cd5e648551d9fc6fe1bfe476f8321b0c1ce7520598a96e03cc68935c4d0951f5d3e9a14cb0b9ba24274"
+        );
+        // Views without anonymous heads or remotes keep their old ids
+        let view = View {
+            anonymous_heads: BTreeMap::new(),
+            remotes: BTreeMap::new(),
+            ..create_view()
+        };
+        assert_snapshot!(
+            ViewId::new(blake2b_hash(&view).to_vec()).hex(),
             @"f426676b3a2f7c6b9ec8677cb05ed249d0d244ab7e86a7c51117e2d8a4829db65e55970c761231e2107d303bf3d33a1f2afdd4ed2181f223e99753674b20a35e"
         );
     }
@@ -827,6 +1087,15 @@ mod tests {
             OperationId::new(blake2b_hash(&create_operation()).to_vec()).hex(),
             @"a721c8bfe6d30b4279437722417743c2c5d9efe731942663e3e7d37320e0ab6b49a7c1452d101cc427ceb8927a4cab03d49dabe73c0677bb9edf5c8b2aa83585"
         );
+        // Working-copy snapshot information contributes to the id
+        let mut operation = create_operation();
+        operation.metadata.working_copy_tree_ids =
+            Some(Merge::resolved(TreeId::from_hex("ccc111")));
+        operation.metadata.includes_snapshot = true;
+        assert_snapshot!(
+            OperationId::new(blake2b_hash(&operation).to_vec()).hex(),
+            @"1b5c9658d42da7799535f6ee9a45f27b9980abdf6c71501bcf18adfba1b45dd0976d4ce20ac10be164027b29a7449531a345ec80651b1b9e92b9d7c55f07dd5f"
+        );
     }
 
     #[test]
@@ -840,6 +1109,18 @@ mod tests {
         let view_id = store.write_view(&view).unwrap();
         let read_view = store.read_view(&view_id).unwrap();
         assert_eq!(read_view, view);
+
+        let view = View {
+            bisect_state: BisectState {
+                good_commits: vec![CommitId::from_hex("eee111"), CommitId::from_hex("eee222")],
+                bad_commits: vec![CommitId::from_hex("eee333")],
+                skipped_commits: vec![CommitId::from_hex("eee444")],
+            },
+            ..create_view()
+        };
+        let view_id = store.write_view(&view).unwrap();
+        let read_view = store.read_view(&view_id).unwrap();
+        assert_eq!(read_view, view);
     }
 
     #[test]
@@ -853,6 +1134,17 @@ mod tests {
         let op_id = store.write_operation(&operation).unwrap();
         let read_operation = store.read_operation(&op_id).unwrap();
         assert_eq!(read_operation, operation);
+
+        let mut operation = create_operation();
+        operation.metadata.working_copy_tree_ids = Some(Merge::from_vec(vec![
+            TreeId::from_hex("ccc111"),
+            TreeId::from_hex("ccc222"),
+            TreeId::from_hex("ccc333"),
+        ]));
+        operation.metadata.includes_snapshot = true;
+        let op_id = store.write_operation(&operation).unwrap();
+        let read_operation = store.read_operation(&op_id).unwrap();
+        assert_eq!(read_operation, operation);
     }
 
     #[test]
@@ -906,7 +1198,7 @@ mod tests {
         );
 
         let (local_bookmarks_reconstructed, remote_views_reconstructed) =
-            bookmark_views_from_proto_legacy(bookmarks_legacy);
+            bookmark_views_from_proto_legacy(bookmarks_legacy).unwrap();
         assert_eq!(local_bookmarks_reconstructed, local_bookmarks);
         assert_eq!(remote_views_reconstructed, remote_views);
     }
@@ -918,7 +1210,7 @@ mod tests {
             vec![Some(CommitId::from_hex("222222")), None],
         ));
         let maybe_proto = ref_target_to_proto(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
 
         // If it were legacy format, order of None entry would be lost.
         let target = RefTarget::from_merge(Merge::from_removes_adds(
@@ -926,18 +1218,43 @@ mod tests {
             vec![None, Some(CommitId::from_hex("222222"))],
         ));
         let maybe_proto = ref_target_to_proto(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
+    }
+
+    #[test]
+    fn test_ref_target_from_corrupt_proto() {
+        let proto = crate::protos::op_store::RefTarget { value: None };
+        assert_matches!(
+            ref_target_from_proto(Some(proto)),
+            Err(PostDecodeError::MissingRefTargetValue)
+        );
+
+        let proto = crate::protos::op_store::RefTarget {
+            value: Some(crate::protos::op_store::ref_target::Value::Conflict(
+                crate::protos::op_store::RefConflict {
+                    removes: vec![Default::default()],
+                    adds: vec![Default::default()],
+                },
+            )),
+        };
+        assert_matches!(
+            ref_target_from_proto(Some(proto)),
+            Err(PostDecodeError::InvalidRefConflict {
+                adds: 1,
+                removes: 1
+            })
+        );
     }
 
     #[test]
     fn test_ref_target_legacy_roundtrip() {
         let target = RefTarget::absent();
         let maybe_proto = ref_target_to_proto_legacy(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
 
         let target = RefTarget::normal(CommitId::from_hex("111111"));
         let maybe_proto = ref_target_to_proto_legacy(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
 
         // N-way conflict
         let target = RefTarget::from_legacy_form(
@@ -949,7 +1266,7 @@ mod tests {
             ],
         );
         let maybe_proto = ref_target_to_proto_legacy(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
 
         // Change-delete conflict
         let target = RefTarget::from_legacy_form(
@@ -957,6 +1274,6 @@ mod tests {
             [CommitId::from_hex("222222")],
         );
         let maybe_proto = ref_target_to_proto_legacy(&target);
-        assert_eq!(ref_target_from_proto(maybe_proto), target);
+        assert_eq!(ref_target_from_proto(maybe_proto).unwrap(), target);
     }
 }
@@ -0,0 +1,618 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Speculative transactions that keep new objects in memory.
+//!
+//! A [`SpeculativeRepo`] behaves like a [`Transaction`], but the commits,
+//! trees, files, and conflicts it creates are buffered in memory instead of
+//! being written to the backend. Dropping it throws everything away. Calling
+//! [`SpeculativeRepo::promote()`] writes the objects to the real store and
+//! returns a regular transaction with the same view changes.
+//!
+//! The ids of the buffered objects are content hashes computed in memory, not
+//! by the base backend. The Git backend, for example, hashes Git objects
+//! instead. The objects therefore get different ids when they're promoted.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use indexmap::IndexMap;
+use pollster::FutureExt as _;
+use thiserror::Error;
+
+use crate::backend::Backend;
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::ChangeId;
+use crate::backend::Commit;
+use crate::backend::CommitId;
+use crate::backend::Conflict;
+use crate::backend::ConflictId;
+use crate::backend::ConflictTerm;
+use crate::backend::CopyRecord;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::SigningFn;
+use crate::backend::SymlinkId;
+use crate::backend::Tree;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::content_hash::blake2b_hash;
+use crate::content_hash::ContentHash;
+use crate::index::Index;
+use crate::merge::Merge;
+use crate::op_store;
+use crate::op_store::BisectState;
+use crate::op_store::RefTarget;
+use crate::op_store::RemoteRef;
+use crate::op_store::RemoteView;
+use crate::repo::MutableRepo;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo::RepoLoader;
+use crate::repo::RepoLoaderError;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::signing::SignInitError;
+use crate::signing::Signer;
+use crate::store::Store;
+use crate::transaction::Transaction;
+
+/// Error that may occur when starting a [`SpeculativeRepo`].
+#[derive(Debug, Error)]
+pub enum SpeculativeRepoError {
+    /// Failed to set up commit signing.
+    #[error(transparent)]
+    Signing(#[from] SignInitError),
+    /// Failed to load the repo on top of the in-memory store.
+    #[error(transparent)]
+    Load(#[from] RepoLoaderError),
+}
+
+/// A transaction whose new objects only live in memory until promoted.
+///
+/// Ids of the new commits, trees, and files differ from the ids the objects
+/// get in the base store. Don't persist them or compare them with ids from
+/// other repos. Use the map returned by [`SpeculativeRepo::promote()`] to
+/// translate commit ids.
+pub struct SpeculativeRepo {
+    base_repo: Arc<ReadonlyRepo>,
+    objects: Arc<OverlayObjects>,
+    tx: Transaction,
+}
+
+impl SpeculativeRepo {
+    /// Starts a speculative transaction on top of `base_repo`.
+    pub fn new(base_repo: &Arc<ReadonlyRepo>) -> Result<Self, SpeculativeRepoError> {
+        let objects = Arc::new(OverlayObjects::default());
+        let backend = OverlayBackend {
+            base: base_repo.store().clone(),
+            objects: objects.clone(),
+        };
+        let settings = base_repo.settings();
+        let store = Store::new(Box::new(backend), Signer::from_settings(settings)?);
+        let base_loader = base_repo.loader();
+        let loader = RepoLoader::new(
+            settings.clone(),
+            store,
+            base_loader.op_store().clone(),
+            base_loader.op_heads_store().clone(),
+            base_loader.index_store().clone(),
+            base_loader.submodule_store().clone(),
+        );
+        let tx = loader.load_at(base_repo.operation())?.start_transaction();
+        Ok(SpeculativeRepo {
+            base_repo: base_repo.clone(),
+            objects,
+            tx,
+        })
+    }
+
+    /// The repo the speculative changes are made on top of.
+    pub fn base_repo(&self) -> &Arc<ReadonlyRepo> {
+        &self.base_repo
+    }
+
+    /// The speculative state of the repo.
+    pub fn repo(&self) -> &MutableRepo {
+        self.tx.repo()
+    }
+
+    /// Mutable access to the speculative state. Objects written through it
+    /// stay in memory.
+    pub fn repo_mut(&mut self) -> &mut MutableRepo {
+        self.tx.repo_mut()
+    }
+
+    /// Number of commits created so far. None of them has been written to the
+    /// backend.
+    pub fn num_new_commits(&self) -> usize {
+        self.objects.commits.lock().unwrap().len()
+    }
+
+    /// Writes the buffered objects to the base repo's store and replays the
+    /// view changes and pending rewrites in a regular transaction.
+    ///
+    /// Returns the transaction, which the caller is expected to commit, and a
+    /// map from the speculative commit ids to the ids of the written commits.
+    pub fn promote(self) -> BackendResult<(Transaction, HashMap<CommitId, CommitId>)> {
+        let SpeculativeRepo {
+            base_repo,
+            objects,
+            tx,
+        } = self;
+        let mut promoter = Promoter {
+            objects: &objects,
+            store: base_repo.store(),
+            sign_key: base_repo.settings().sign_settings().key,
+            files: HashMap::new(),
+            symlinks: HashMap::new(),
+            conflicts: HashMap::new(),
+            trees: HashMap::new(),
+            commits: HashMap::new(),
+        };
+        // Commits were buffered in the order they were written, so parents
+        // come before their children.
+        let speculative_ids = objects
+            .commits
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut new_commits = Vec::with_capacity(speculative_ids.len());
+        for id in &speculative_ids {
+            let new_id = promoter.promote_commit(id)?;
+            new_commits.push(base_repo.store().get_commit(&new_id)?);
+        }
+        let commit_ids = promoter.commits;
+        let remap = |id: &CommitId| commit_ids.get(id).unwrap_or(id).clone();
+
+        let speculative_repo = tx.repo();
+        let view = remap_view(speculative_repo.view().store_view(), &remap);
+        let rewrites = speculative_repo
+            .rewrites()
+            .iter()
+            .map(|(old_id, rewrite)| (remap(old_id), rewrite.map_ids(remap)))
+            .collect::<Vec<_>>();
+
+        let mut new_tx = base_repo.start_transaction();
+        let mut_repo = new_tx.repo_mut();
+        mut_repo.add_heads(&new_commits)?;
+        mut_repo.set_view(view);
+        for (old_id, rewrite) in rewrites {
+            mut_repo.restore_rewrite(old_id, rewrite);
+        }
+        Ok((new_tx, commit_ids))
+    }
+}
+
+fn remap_ref_target(target: &RefTarget, remap: &impl Fn(&CommitId) -> CommitId) -> RefTarget {
+    RefTarget::from_merge(target.as_merge().map(|id| id.as_ref().map(remap)))
+}
+
+fn remap_view(view: &op_store::View, remap: &impl Fn(&CommitId) -> CommitId) -> op_store::View {
+    let remap_targets = |targets: &BTreeMap<String, RefTarget>| {
+        targets
+            .iter()
+            .map(|(name, target)| (name.clone(), remap_ref_target(target, remap)))
+            .collect()
+    };
+    op_store::View {
+        head_ids: view.head_ids.iter().map(remap).collect(),
+        local_bookmarks: remap_targets(&view.local_bookmarks),
+        tags: remap_targets(&view.tags),
+        remote_views: view
+            .remote_views
+            .iter()
+            .map(|(remote, remote_view)| {
+                let bookmarks = remote_view
+                    .bookmarks
+                    .iter()
+                    .map(|(name, remote_ref)| {
+                        let remote_ref = RemoteRef {
+                            target: remap_ref_target(&remote_ref.target, remap),
+                            state: remote_ref.state,
+                        };
+                        (name.clone(), remote_ref)
+                    })
+                    .collect();
+                (remote.clone(), RemoteView { bookmarks })
+            })
+            .collect(),
+        git_refs: remap_targets(&view.git_refs),
+        git_head: remap_ref_target(&view.git_head, remap),
+        wc_commit_ids: view
+            .wc_commit_ids
+            .iter()
+            .map(|(workspace_id, id)| (workspace_id.clone(), remap(id)))
+            .collect(),
+        anonymous_heads: view
+            .anonymous_heads
+            .iter()
+            .map(|(name, id)| (name.clone(), remap(id)))
+            .collect(),
+        bisect_state: BisectState {
+            good_commits: view.bisect_state.good_commits.iter().map(remap).collect(),
+            bad_commits: view.bisect_state.bad_commits.iter().map(remap).collect(),
+            skipped_commits: view
+                .bisect_state
+                .skipped_commits
+                .iter()
+                .map(remap)
+                .collect(),
+        },
+        remotes: view.remotes.clone(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct OverlayObjects {
+    files: Mutex<HashMap<FileId, Arc<Vec<u8>>>>,
+    symlinks: Mutex<HashMap<SymlinkId, String>>,
+    trees: Mutex<HashMap<TreeId, Tree>>,
+    /// Ids of trees read from the base store, by content. Lets a speculative
+    /// tree with the same content as an existing tree reuse its id.
+    base_tree_ids: Mutex<HashMap<Vec<u8>, TreeId>>,
+    conflicts: Mutex<HashMap<ConflictId, Conflict>>,
+    /// New commits in the order they were written, and whether they should be
+    /// signed when promoted.
+    commits: Mutex<IndexMap<CommitId, (Commit, bool)>>,
+}
+
+/// Backend that keeps written objects in memory and reads everything else
+/// from the base store.
+#[derive(Debug)]
+struct OverlayBackend {
+    base: Arc<Store>,
+    objects: Arc<OverlayObjects>,
+}
+
+impl OverlayBackend {
+    /// Computes an id for a buffered object. It doesn't match the id the base
+    /// backend would assign.
+    fn hash_id(&self, content: &(impl ContentHash + ?Sized)) -> Vec<u8> {
+        let id_length = self.base.commit_id_length();
+        blake2b_hash(content)[..id_length].to_vec()
+    }
+}
+
+#[async_trait]
+impl Backend for OverlayBackend {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "speculative"
+    }
+
+    fn commit_id_length(&self) -> usize {
+        self.base.commit_id_length()
+    }
+
+    fn change_id_length(&self) -> usize {
+        self.base.change_id_length()
+    }
+
+    fn root_commit_id(&self) -> &CommitId {
+        self.base.root_commit_id()
+    }
+
+    fn root_change_id(&self) -> &ChangeId {
+        self.base.root_change_id()
+    }
+
+    fn empty_tree_id(&self) -> &TreeId {
+        self.base.empty_tree_id()
+    }
+
+    fn concurrency(&self) -> usize {
+        self.base.concurrency()
+    }
+
+    async fn read_file(&self, path: &RepoPath, id: &FileId) -> BackendResult<Box<dyn Read>> {
+        let content = self.objects.files.lock().unwrap().get(id).cloned();
+        match content {
+            Some(content) => Ok(Box::new(Cursor::new(content.to_vec()))),
+            None => self.base.read_file_async(path, id).await,
+        }
+    }
+
+    async fn write_file(
+        &self,
+        _path: &RepoPath,
+        contents: &mut (dyn Read + Send),
+    ) -> BackendResult<FileId> {
+        let mut content = vec![];
+        contents
+            .read_to_end(&mut content)
+            .map_err(|err| BackendError::WriteObject {
+                object_type: "file",
+                source: Box::new(err),
+            })?;
+        let id = FileId::new(self.hash_id(&content));
+        self.objects
+            .files
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::new(content));
+        Ok(id)
+    }
+
+    async fn read_symlink(&self, path: &RepoPath, id: &SymlinkId) -> BackendResult<String> {
+        let target = self.objects.symlinks.lock().unwrap().get(id).cloned();
+        match target {
+            Some(target) => Ok(target),
+            None => self.base.read_symlink_async(path, id).await,
+        }
+    }
+
+    async fn write_symlink(&self, _path: &RepoPath, target: &str) -> BackendResult<SymlinkId> {
+        let id = SymlinkId::new(self.hash_id(target.as_bytes()));
+        self.objects
+            .symlinks
+            .lock()
+            .unwrap()
+            .insert(id.clone(), target.to_owned());
+        Ok(id)
+    }
+
+    async fn read_tree(&self, path: &RepoPath, id: &TreeId) -> BackendResult<Tree> {
+        let tree = self.objects.trees.lock().unwrap().get(id).cloned();
+        if let Some(tree) = tree {
+            return Ok(tree);
+        }
+        let tree = self.base.get_tree_async(path.to_owned(), id).await?;
+        let content_id = self.hash_id(tree.data());
+        self.objects
+            .base_tree_ids
+            .lock()
+            .unwrap()
+            .insert(content_id, id.clone());
+        Ok(tree.data().clone())
+    }
+
+    async fn write_tree(&self, _path: &RepoPath, contents: &Tree) -> BackendResult<TreeId> {
+        if contents.is_empty() {
+            return Ok(self.base.empty_tree_id().clone());
+        }
+        let content_id = self.hash_id(contents);
+        if let Some(id) = self.objects.base_tree_ids.lock().unwrap().get(&content_id) {
+            return Ok(id.clone());
+        }
+        let id = TreeId::new(content_id);
+        self.objects
+            .trees
+            .lock()
+            .unwrap()
+            .insert(id.clone(), contents.clone());
+        Ok(id)
+    }
+
+    fn read_conflict(&self, path: &RepoPath, id: &ConflictId) -> BackendResult<Conflict> {
+        let conflict = self.objects.conflicts.lock().unwrap().get(id).cloned();
+        match conflict {
+            Some(conflict) => Ok(conflict),
+            None => Ok(self.base.read_conflict(path, id)?.into_backend_conflict()),
+        }
+    }
+
+    fn write_conflict(&self, _path: &RepoPath, contents: &Conflict) -> BackendResult<ConflictId> {
+        let id = ConflictId::new(self.hash_id(contents));
+        self.objects
+            .conflicts
+            .lock()
+            .unwrap()
+            .insert(id.clone(), contents.clone());
+        Ok(id)
+    }
+
+    async fn read_commit(&self, id: &CommitId) -> BackendResult<Commit> {
+        let commit = self
+            .objects
+            .commits
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(commit, _)| commit.clone());
+        match commit {
+            Some(commit) => Ok(commit),
+            None => Ok(self.base.get_commit_async(id).await?.store_commit().clone()),
+        }
+    }
+
+    async fn write_commit(
+        &self,
+        mut contents: Commit,
+        sign_with: Option<&mut SigningFn>,
+    ) -> BackendResult<(CommitId, Commit)> {
+        assert!(contents.secure_sig.is_none(), "commit.secure_sig was set");
+        // Signing is deferred until the commit is promoted so that throwaway
+        // commits don't prompt for keys.
+        let should_sign = sign_with.is_some();
+        contents.secure_sig = None;
+        let id = CommitId::new(self.hash_id(&contents));
+        self.objects
+            .commits
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (contents.clone(), should_sign));
+        Ok((id, contents))
+    }
+
+    fn get_copy_records(
+        &self,
+        paths: Option<&[RepoPathBuf]>,
+        root: &CommitId,
+        head: &CommitId,
+    ) -> BackendResult<BoxStream<'_, BackendResult<CopyRecord>>> {
+        self.base.get_copy_records(paths, root, head)
+    }
+
+    fn gc(&self, _index: &dyn Index, _keep_newer: SystemTime) -> BackendResult<()> {
+        Err(BackendError::Unsupported(
+            "Speculative repos cannot be garbage-collected".to_owned(),
+        ))
+    }
+}
+
+/// Writes buffered objects to the real store, remembering the new ids.
+struct Promoter<'a> {
+    objects: &'a OverlayObjects,
+    store: &'a Arc<Store>,
+    sign_key: Option<String>,
+    files: HashMap<FileId, FileId>,
+    symlinks: HashMap<SymlinkId, SymlinkId>,
+    conflicts: HashMap<ConflictId, ConflictId>,
+    trees: HashMap<(RepoPathBuf, TreeId), TreeId>,
+    commits: HashMap<CommitId, CommitId>,
+}
+
+impl Promoter<'_> {
+    fn promote_value(&mut self, path: &RepoPath, value: &TreeValue) -> BackendResult<TreeValue> {
+        let value = match value {
+            TreeValue::File { id, executable } => TreeValue::File {
+                id: self.promote_file(path, id)?,
+                executable: *executable,
+            },
+            TreeValue::Symlink(id) => TreeValue::Symlink(self.promote_symlink(path, id)?),
+            TreeValue::Tree(id) => TreeValue::Tree(self.promote_tree(path, id)?),
+            TreeValue::Conflict(id) => TreeValue::Conflict(self.promote_conflict(path, id)?),
+            TreeValue::GitSubmodule(_) => value.clone(),
+        };
+        Ok(value)
+    }
+
+    fn promote_file(&mut self, path: &RepoPath, id: &FileId) -> BackendResult<FileId> {
+        if let Some(new_id) = self.files.get(id) {
+            return Ok(new_id.clone());
+        }
+        let Some(content) = self.objects.files.lock().unwrap().get(id).cloned() else {
+            return Ok(id.clone());
+        };
+        let new_id = self
+            .store
+            .write_file(path, &mut content.as_slice())
+            .block_on()?;
+        self.files.insert(id.clone(), new_id.clone());
+        Ok(new_id)
+    }
+
+    fn promote_symlink(&mut self, path: &RepoPath, id: &SymlinkId) -> BackendResult<SymlinkId> {
+        if let Some(new_id) = self.symlinks.get(id) {
+            return Ok(new_id.clone());
+        }
+        let Some(target) = self.objects.symlinks.lock().unwrap().get(id).cloned() else {
+            return Ok(id.clone());
+        };
+        let new_id = self.store.write_symlink(path, &target).block_on()?;
+        self.symlinks.insert(id.clone(), new_id.clone());
+        Ok(new_id)
+    }
+
+    fn promote_conflict(&mut self, path: &RepoPath, id: &ConflictId) -> BackendResult<ConflictId> {
+        if let Some(new_id) = self.conflicts.get(id) {
+            return Ok(new_id.clone());
+        }
+        let Some(conflict) = self.objects.conflicts.lock().unwrap().get(id).cloned() else {
+            return Ok(id.clone());
+        };
+        let mut promote_terms = |terms: Vec<ConflictTerm>| -> BackendResult<Vec<ConflictTerm>> {
+            terms
+                .into_iter()
+                .map(|term| {
+                    let value = self.promote_value(path, &term.value)?;
+                    Ok(ConflictTerm { value })
+                })
+                .collect()
+        };
+        let conflict = Conflict {
+            removes: promote_terms(conflict.removes)?,
+            adds: promote_terms(conflict.adds)?,
+        };
+        let merge = Merge::from_backend_conflict(conflict);
+        let new_id = self.store.write_conflict(path, &merge)?;
+        self.conflicts.insert(id.clone(), new_id.clone());
+        Ok(new_id)
+    }
+
+    fn promote_tree(&mut self, dir: &RepoPath, id: &TreeId) -> BackendResult<TreeId> {
+        let key = (dir.to_owned(), id.clone());
+        if let Some(new_id) = self.trees.get(&key) {
+            return Ok(new_id.clone());
+        }
+        let Some(tree) = self.objects.trees.lock().unwrap().get(id).cloned() else {
+            return Ok(id.clone());
+        };
+        let mut new_tree = Tree::default();
+        for entry in tree.entries() {
+            let value = self.promote_value(&dir.join(entry.name()), entry.value())?;
+            new_tree.set(entry.name().to_owned(), value);
+        }
+        let new_id = self
+            .store
+            .write_tree(dir, new_tree)
+            .block_on()?
+            .id()
+            .clone();
+        self.trees.insert(key, new_id.clone());
+        Ok(new_id)
+    }
+
+    fn promote_commit(&mut self, id: &CommitId) -> BackendResult<CommitId> {
+        if let Some(new_id) = self.commits.get(id) {
+            return Ok(new_id.clone());
+        }
+        let Some((mut commit, should_sign)) = self.objects.commits.lock().unwrap().get(id).cloned()
+        else {
+            return Ok(id.clone());
+        };
+        commit.parents = commit
+            .parents
+            .iter()
+            .map(|id| self.promote_commit(id))
+            .collect::<BackendResult<_>>()?;
+        commit.predecessors = commit
+            .predecessors
+            .iter()
+            .map(|id| self.promote_commit(id))
+            .collect::<BackendResult<_>>()?;
+        commit.root_tree = match &commit.root_tree {
+            MergedTreeId::Legacy(tree_id) => {
+                MergedTreeId::Legacy(self.promote_tree(RepoPath::root(), tree_id)?)
+            }
+            MergedTreeId::Merge(tree_ids) => MergedTreeId::Merge(
+                tree_ids.try_map(|tree_id| self.promote_tree(RepoPath::root(), tree_id))?,
+            ),
+        };
+        let store = self.store;
+        let sign_key = self.sign_key.as_deref();
+        let sign_fn = |data: &[u8]| store.signer().sign(data, sign_key);
+        let should_sign = should_sign && store.signer().can_sign();
+        let new_commit = store
+            .write_commit(commit, should_sign.then_some(&mut &sign_fn))
+            .block_on()?;
+        self.commits.insert(id.clone(), new_commit.id().clone());
+        Ok(new_commit.id().clone())
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operation heads store backed by an SQLite database.
+//!
+//! Heads are replaced within a single database transaction, so concurrent
+//! processes never observe a state where the old heads were removed but the
+//! new head wasn't added yet.
+
+#![allow(missing_docs)]
+
+use std::any::Any;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use itertools::Itertools as _;
+use rusqlite::Connection;
+use thiserror::Error;
+
+use crate::backend::BackendInitError;
+use crate::backend::BackendLoadError;
+use crate::lock::FileLockError;
+use crate::lock::LockKind;
+use crate::lock::LockManager;
+use crate::lock::LockOrderGuard;
+use crate::object_id::ObjectId;
+use crate::op_heads_store::OpHeadsStore;
+use crate::op_heads_store::OpHeadsStoreError;
+use crate::op_heads_store::OpHeadsStoreLock;
+use crate::op_store::OperationId;
+use crate::sqlite_op_store::open_connection;
+use crate::sqlite_op_store::set_busy_timeout;
+use crate::sqlite_op_store::to_io_error;
+
+const DATABASE_FILE_NAME: &str = "op_heads.sqlite";
+// The lock lives in a separate database so that holding it doesn't block
+// updates of the heads themselves.
+const LOCK_FILE_NAME: &str = "op_heads_lock.sqlite";
+
+/// Error that may occur during [`SqliteOpHeadsStore`] initialization.
+#[derive(Debug, Error)]
+#[error("Failed to initialize SQLite operation heads store")]
+pub struct SqliteOpHeadsStoreInitError(#[from] pub rusqlite::Error);
+
+impl From<SqliteOpHeadsStoreInitError> for BackendInitError {
+    fn from(err: SqliteOpHeadsStoreInitError) -> Self {
+        BackendInitError(err.into())
+    }
+}
+
+#[derive(Debug)]
+pub struct SqliteOpHeadsStore {
+    dir: PathBuf,
+    connection: Mutex<Connection>,
+    lock_manager: LockManager,
+}
+
+impl SqliteOpHeadsStore {
+    pub fn name() -> &'static str {
+        "sqlite_op_heads_store"
+    }
+
+    pub fn init(
+        dir: &Path,
+        lock_manager: LockManager,
+    ) -> Result<Self, SqliteOpHeadsStoreInitError> {
+        let connection = Connection::open(dir.join(DATABASE_FILE_NAME))?;
+        set_busy_timeout(&connection, lock_manager.timeout())?;
+        connection.execute_batch(
+            "
+            PRAGMA journal_mode = DELETE;
+            CREATE TABLE op_heads (id BLOB PRIMARY KEY);
+            ",
+        )?;
+        Connection::open(dir.join(LOCK_FILE_NAME))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            connection: Mutex::new(connection),
+            lock_manager,
+        })
+    }
+
+    pub fn load(dir: &Path, lock_manager: LockManager) -> Result<Self, BackendLoadError> {
+        let connection = open_connection(&dir.join(DATABASE_FILE_NAME), lock_manager.timeout())
+            .map_err(|err| BackendLoadError(err.into()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            connection: Mutex::new(connection),
+            lock_manager,
+        })
+    }
+}
+
+struct SqliteOpHeadsStoreLock {
+    // The exclusive transaction is rolled back when the connection is closed,
+    // which must happen before the lock order is released.
+    _connection: Connection,
+    _order: LockOrderGuard,
+}
+
+impl OpHeadsStoreLock for SqliteOpHeadsStoreLock {}
+
+impl OpHeadsStore for SqliteOpHeadsStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn update_op_heads(
+        &self,
+        old_ids: &[OperationId],
+        new_id: &OperationId,
+    ) -> Result<(), OpHeadsStoreError> {
+        assert!(!old_ids.contains(new_id));
+        let to_write_error = |err: rusqlite::Error| OpHeadsStoreError::Write {
+            new_op_id: new_id.clone(),
+            source: to_io_error(err).into(),
+        };
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(to_write_error)?;
+        transaction
+            .execute(
+                "INSERT OR IGNORE INTO op_heads (id) VALUES (?1)",
+                [new_id.as_bytes()],
+            )
+            .map_err(to_write_error)?;
+        for old_id in old_ids {
+            transaction
+                .execute("DELETE FROM op_heads WHERE id = ?1", [old_id.as_bytes()])
+                .map_err(to_write_error)?;
+        }
+        transaction.commit().map_err(to_write_error)
+    }
+
+    fn get_op_heads(&self) -> Result<Vec<OperationId>, OpHeadsStoreError> {
+        let to_read_error = |err: rusqlite::Error| OpHeadsStoreError::Read(to_io_error(err).into());
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare_cached("SELECT id FROM op_heads")
+            .map_err(to_read_error)?;
+        let ids: Vec<Vec<u8>> = statement
+            .query_map([], |row| row.get(0))
+            .and_then(|rows| rows.try_collect())
+            .map_err(to_read_error)?;
+        Ok(ids.into_iter().map(OperationId::new).collect())
+    }
+
+    fn lock(&self) -> Result<Box<dyn OpHeadsStoreLock + '_>, OpHeadsStoreError> {
+        let path = self.dir.join(LOCK_FILE_NAME);
+        let to_lock_error = |err: io::Error| {
+            OpHeadsStoreError::Lock(
+                FileLockError {
+                    message: "Failed to lock lock file",
+                    path: path.clone(),
+                    err,
+                }
+                .into(),
+            )
+        };
+        let order = self
+            .lock_manager
+            .enter(LockKind::OpHeads)
+            .map_err(|err| to_lock_error(io::Error::other(err)))?;
+        let connection = open_connection(&path, self.lock_manager.timeout())
+            .and_then(|connection| {
+                connection.execute_batch("BEGIN EXCLUSIVE")?;
+                Ok(connection)
+            })
+            .map_err(|err| to_lock_error(to_io_error(err)))?;
+        Ok(Box::new(SqliteOpHeadsStoreLock {
+            _connection: connection,
+            _order: order,
+        }))
+    }
+}
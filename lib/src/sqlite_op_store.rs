@@ -0,0 +1,377 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operation store backed by an SQLite database.
+//!
+//! Operations and views are stored in the same protobuf encoding as
+//! [`SimpleOpStore`](crate::simple_op_store::SimpleOpStore), but in a single
+//! database file instead of one file per object. This avoids relying on
+//! atomic file renames, which are unreliable on some network file systems.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use itertools::Itertools as _;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension as _;
+use thiserror::Error;
+
+use crate::backend::BackendInitError;
+use crate::backend::BackendLoadError;
+use crate::content_hash::blake2b_hash;
+use crate::dag_walk;
+use crate::lock::timed_out_error;
+use crate::lock::LockManager;
+use crate::object_id::HexPrefix;
+use crate::object_id::ObjectId;
+use crate::object_id::PrefixResolution;
+use crate::op_store::OpStore;
+use crate::op_store::OpStoreError;
+use crate::op_store::OpStoreResult;
+use crate::op_store::Operation;
+use crate::op_store::OperationId;
+use crate::op_store::RootOperationData;
+use crate::op_store::View;
+use crate::op_store::ViewId;
+use crate::simple_op_store::decode_operation;
+use crate::simple_op_store::decode_view;
+use crate::simple_op_store::encode_operation;
+use crate::simple_op_store::encode_view;
+use crate::simple_op_store::OPERATION_ID_LENGTH;
+use crate::simple_op_store::VIEW_ID_LENGTH;
+
+const DATABASE_FILE_NAME: &str = "op_store.sqlite";
+
+/// Error that may occur during [`SqliteOpStore`] initialization.
+#[derive(Debug, Error)]
+#[error("Failed to initialize SQLite operation store")]
+pub struct SqliteOpStoreInitError(#[from] pub rusqlite::Error);
+
+impl From<SqliteOpStoreInitError> for BackendInitError {
+    fn from(err: SqliteOpStoreInitError) -> Self {
+        BackendInitError(err.into())
+    }
+}
+
+/// Operation store which keeps operations and views in an SQLite database.
+#[derive(Debug)]
+pub struct SqliteOpStore {
+    path: PathBuf,
+    connection: Mutex<Connection>,
+    root_data: RootOperationData,
+    root_operation_id: OperationId,
+    root_view_id: ViewId,
+}
+
+impl SqliteOpStore {
+    /// Name of this op store, as recorded in `.jj/repo/op_store/type`.
+    pub fn name() -> &'static str {
+        "sqlite_op_store"
+    }
+
+    /// Creates an empty OpStore. Returns error if it already exists.
+    ///
+    /// The database is locked by other processes while they write to it,
+    /// which is waited for up to the timeout of `lock_manager`.
+    pub fn init(
+        store_path: &Path,
+        root_data: RootOperationData,
+        lock_manager: &LockManager,
+    ) -> Result<Self, SqliteOpStoreInitError> {
+        let connection = Connection::open(store_path.join(DATABASE_FILE_NAME))?;
+        set_busy_timeout(&connection, lock_manager.timeout())?;
+        connection.execute_batch(
+            "
+            PRAGMA journal_mode = DELETE;
+            CREATE TABLE views (
+                id BLOB PRIMARY KEY,
+                data BLOB NOT NULL,
+                modified INTEGER NOT NULL
+            );
+            CREATE TABLE operations (
+                id BLOB PRIMARY KEY,
+                hex_id TEXT NOT NULL,
+                data BLOB NOT NULL,
+                modified INTEGER NOT NULL
+            );
+            CREATE INDEX operations_hex_id ON operations (hex_id);
+            ",
+        )?;
+        Ok(Self::new(store_path, connection, root_data))
+    }
+
+    /// Load an existing OpStore
+    pub fn load(
+        store_path: &Path,
+        root_data: RootOperationData,
+        lock_manager: &LockManager,
+    ) -> Result<Self, BackendLoadError> {
+        let connection =
+            open_connection(&store_path.join(DATABASE_FILE_NAME), lock_manager.timeout())
+                .map_err(|err| BackendLoadError(err.into()))?;
+        Ok(Self::new(store_path, connection, root_data))
+    }
+
+    fn new(store_path: &Path, connection: Connection, root_data: RootOperationData) -> Self {
+        SqliteOpStore {
+            path: store_path.to_path_buf(),
+            connection: Mutex::new(connection),
+            root_data,
+            root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
+            root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+        }
+    }
+
+    /// Path to the directory containing the database.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl OpStore for SqliteOpStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn root_operation_id(&self) -> &OperationId {
+        &self.root_operation_id
+    }
+
+    fn read_view(&self, id: &ViewId) -> OpStoreResult<View> {
+        if *id == self.root_view_id {
+            return Ok(View::make_root(self.root_data.root_commit_id.clone()));
+        }
+
+        let buf = read_object(&self.connection.lock().unwrap(), "views", id)?;
+        decode_view(&buf).map_err(|err| to_read_error(err, id))
+    }
+
+    fn write_view(&self, view: &View) -> OpStoreResult<ViewId> {
+        let id = ViewId::new(blake2b_hash(view).to_vec());
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute(
+                "INSERT INTO views (id, data, modified) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET modified = excluded.modified",
+                (id.as_bytes(), encode_view(view), now_millis()),
+            )
+            .map_err(|err| to_write_error(err, "view"))?;
+        Ok(id)
+    }
+
+    fn read_operation(&self, id: &OperationId) -> OpStoreResult<Operation> {
+        if *id == self.root_operation_id {
+            return Ok(Operation::make_root(self.root_view_id.clone()));
+        }
+
+        let buf = read_object(&self.connection.lock().unwrap(), "operations", id)?;
+        decode_operation(&buf).map_err(|err| to_read_error(err, id))
+    }
+
+    fn write_operation(&self, operation: &Operation) -> OpStoreResult<OperationId> {
+        assert!(!operation.parents.is_empty());
+        let id = OperationId::new(blake2b_hash(operation).to_vec());
+        let connection = self.connection.lock().unwrap();
+        connection
+            .execute(
+                "INSERT INTO operations (id, hex_id, data, modified) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO UPDATE SET modified = excluded.modified",
+                (
+                    id.as_bytes(),
+                    id.hex(),
+                    encode_operation(operation),
+                    now_millis(),
+                ),
+            )
+            .map_err(|err| to_write_error(err, "operation"))?;
+        Ok(id)
+    }
+
+    fn resolve_operation_id_prefix(
+        &self,
+        prefix: &HexPrefix,
+    ) -> OpStoreResult<PrefixResolution<OperationId>> {
+        let matches_root = prefix.matches(&self.root_operation_id);
+        let connection = self.connection.lock().unwrap();
+        // Prefix is a hex string, so it doesn't contain LIKE wildcards.
+        let mut statement = connection
+            .prepare_cached("SELECT id FROM operations WHERE hex_id LIKE ?1 LIMIT 2")
+            .map_err(to_other_error)?;
+        let ids: Vec<Vec<u8>> = statement
+            .query_map([format!("{}%", prefix.hex())], |row| row.get(0))
+            .and_then(|rows| rows.try_collect())
+            .map_err(to_other_error)?;
+        let matched = itertools::chain(
+            matches_root.then(|| self.root_operation_id.clone()),
+            ids.into_iter().map(OperationId::new),
+        )
+        .collect_vec();
+        match &matched[..] {
+            [] => Ok(PrefixResolution::NoMatch),
+            [id] => Ok(PrefixResolution::SingleMatch(id.clone())),
+            _ => Ok(PrefixResolution::AmbiguousMatch),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn gc(&self, head_ids: &[OperationId], keep_newer: SystemTime) -> OpStoreResult<()> {
+        // Reachable objects are resolved without considering the keep_newer
+        // parameter, as in SimpleOpStore.
+        let read_op = |id: &OperationId| self.read_operation(id).map(|data| (id.clone(), data));
+        let reachable_ops: HashMap<OperationId, Operation> = dag_walk::dfs_ok(
+            head_ids.iter().map(read_op),
+            |(id, _)| id.clone(),
+            |(_, data)| data.parents.iter().map(read_op).collect_vec(),
+        )
+        .try_collect()?;
+        let reachable_views: HashSet<&ViewId> =
+            reachable_ops.values().map(|data| &data.view_id).collect();
+        tracing::info!(
+            reachable_op_count = reachable_ops.len(),
+            reachable_view_count = reachable_views.len(),
+            "collected reachable objects"
+        );
+
+        let keep_newer = keep_newer
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as i64);
+        let mut connection = self.connection.lock().unwrap();
+        // Take the write lock up front. SQLite fails without waiting if a
+        // deferred transaction can't upgrade its read lock.
+        let transaction = connection
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(to_other_error)?;
+        let find_old_ids = |table: &str| -> rusqlite::Result<Vec<Vec<u8>>> {
+            let mut statement =
+                transaction.prepare(&format!("SELECT id FROM {table} WHERE modified <= ?1"))?;
+            let ids = statement
+                .query_map([keep_newer], |row| row.get(0))?
+                .try_collect()?;
+            Ok(ids)
+        };
+        let delete = |table: &str, id: &[u8]| -> rusqlite::Result<()> {
+            transaction.execute(&format!("DELETE FROM {table} WHERE id = ?1"), [id])?;
+            Ok(())
+        };
+        let prune = || -> rusqlite::Result<()> {
+            for id in find_old_ids("operations")? {
+                if !reachable_ops.contains_key(&OperationId::new(id.clone())) {
+                    delete("operations", &id)?;
+                }
+            }
+            for id in find_old_ids("views")? {
+                if !reachable_views.contains(&ViewId::new(id.clone())) {
+                    delete("views", &id)?;
+                }
+            }
+            Ok(())
+        };
+        prune().map_err(to_other_error)?;
+        transaction.commit().map_err(to_other_error)?;
+        Ok(())
+    }
+}
+
+/// Opens an existing database, waiting up to `timeout` for locks held by
+/// other processes, or forever if `timeout` is `None`.
+pub(crate) fn open_connection(
+    path: &Path,
+    timeout: Option<Duration>,
+) -> rusqlite::Result<Connection> {
+    let connection = Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    set_busy_timeout(&connection, timeout)?;
+    Ok(connection)
+}
+
+pub(crate) fn set_busy_timeout(
+    connection: &Connection,
+    timeout: Option<Duration>,
+) -> rusqlite::Result<()> {
+    match timeout {
+        Some(timeout) => connection.busy_timeout(timeout),
+        None => connection.busy_handler(Some(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            true
+        })),
+    }
+}
+
+/// Converts a database error to an I/O error. A database which stayed locked
+/// by another process past the lock timeout is reported as a timed-out lock.
+pub(crate) fn to_io_error(err: rusqlite::Error) -> io::Error {
+    if err.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseBusy) {
+        timed_out_error("another process")
+    } else {
+        io::Error::other(err)
+    }
+}
+
+fn read_object(connection: &Connection, table: &str, id: &impl ObjectId) -> OpStoreResult<Vec<u8>> {
+    connection
+        .query_row(
+            &format!("SELECT data FROM {table} WHERE id = ?1"),
+            [id.as_bytes()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|err| to_read_error(to_io_error(err).into(), id))?
+        .ok_or_else(|| OpStoreError::ObjectNotFound {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: "No such object in database".into(),
+        })
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+fn to_read_error(
+    source: Box<dyn std::error::Error + Send + Sync>,
+    id: &impl ObjectId,
+) -> OpStoreError {
+    OpStoreError::ReadObject {
+        object_type: id.object_type(),
+        hash: id.hex(),
+        source,
+    }
+}
+
+fn to_write_error(err: rusqlite::Error, object_type: &'static str) -> OpStoreError {
+    OpStoreError::WriteObject {
+        object_type,
+        source: to_io_error(err).into(),
+    }
+}
+
+fn to_other_error(err: rusqlite::Error) -> OpStoreError {
+    OpStoreError::Other(to_io_error(err).into())
+}
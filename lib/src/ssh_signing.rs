@@ -111,6 +111,16 @@ fn ensure_key_as_file(key: &str) -> SshResult<Either<PathBuf, tempfile::TempPath
     Ok(either::Right(pub_key_path))
 }
 
+// Extracts the key fingerprint from the output of `ssh-keygen -Y verify` or
+// `ssh-keygen -Y check-novalidate`, which looks like:
+// `Good "git" signature for <principal> with ED25519 key SHA256:<hash>`
+fn parse_key_fingerprint(output: &[u8]) -> Option<String> {
+    let output = std::str::from_utf8(output).ok()?;
+    let (_, rest) = output.lines().next()?.rsplit_once(" key ")?;
+    let fingerprint = rest.trim();
+    (!fingerprint.is_empty()).then(|| fingerprint.to_owned())
+}
+
 impl SshBackend {
     pub fn new(program: OsString, allowed_signers: Option<OsString>) -> Self {
         Self {
@@ -245,11 +255,11 @@ impl SigningBackend for SshBackend {
 
                 let result = run_command(&mut command, data);
 
-                let status = match result {
-                    Ok(_) => SigStatus::Good,
-                    Err(_) => SigStatus::Bad,
+                let (status, key) = match result {
+                    Ok(output) => (SigStatus::Good, parse_key_fingerprint(&output)),
+                    Err(_) => (SigStatus::Bad, None),
                 };
-                Ok(Verification::new(status, None, Some(principal)))
+                Ok(Verification::new(status, key, Some(principal)))
             }
             _ => {
                 command
@@ -263,9 +273,9 @@ impl SigningBackend for SshBackend {
                 let result = run_command(&mut command, data);
 
                 match result {
-                    Ok(_) => Ok(Verification::new(
+                    Ok(output) => Ok(Verification::new(
                         SigStatus::Unknown,
-                        None,
+                        parse_key_fingerprint(&output),
                         Some("Signature OK. Unknown principal".into()),
                     )),
                     Err(_) => Ok(Verification::new(SigStatus::Bad, None, None)),
@@ -282,6 +292,23 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_parse_key_fingerprint() {
+        assert_eq!(
+            parse_key_fingerprint(
+                b"Good \"git\" signature for test@example.com with ED25519 key SHA256:abc\n"
+            )
+            .as_deref(),
+            Some("SHA256:abc")
+        );
+        assert_eq!(
+            parse_key_fingerprint(b"Good \"git\" signature with RSA key SHA256:def\n").as_deref(),
+            Some("SHA256:def")
+        );
+        assert_eq!(parse_key_fingerprint(b""), None);
+        assert_eq!(parse_key_fingerprint(b"garbage"), None);
+    }
+
     #[test]
     fn test_ssh_key_to_file_conversion_raw_key_data() {
         let keydata = "ssh-ed25519 some-key-data";
@@ -41,6 +41,7 @@ use thiserror::Error;
 use crate::file_util::persist_content_addressed_temp_file;
 use crate::lock::FileLock;
 use crate::lock::FileLockError;
+use crate::lock::LockManager;
 
 pub trait TableSegment {
     fn segment_num_entries(&self) -> usize;
@@ -348,7 +349,8 @@ impl MutableTable {
         let mut temp_file = NamedTempFile::new_in(&store.dir).map_err(to_save_err)?;
         let file = temp_file.as_file_mut();
         file.write_all(&buf).map_err(to_save_err)?;
-        persist_content_addressed_temp_file(temp_file, file_path).map_err(to_save_err)?;
+        persist_content_addressed_temp_file(temp_file, file_path, store.lock_manager.mode())
+            .map_err(to_save_err)?;
 
         ReadonlyTable::load_from(&mut buf.as_slice(), store, file_id_hex, store.key_size)
     }
@@ -402,6 +404,7 @@ pub struct TableStore {
     dir: PathBuf,
     key_size: usize,
     cached_tables: RwLock<HashMap<String, Arc<ReadonlyTable>>>,
+    lock_manager: LockManager,
 }
 
 impl TableStore {
@@ -411,6 +414,7 @@ impl TableStore {
             dir,
             key_size,
             cached_tables: Default::default(),
+            lock_manager: LockManager::new(),
         }
     }
 
@@ -423,11 +427,18 @@ impl TableStore {
         self.key_size
     }
 
+    /// Sets the lock manager used to lock the store and to write tables.
+    pub fn with_lock_manager(mut self, lock_manager: LockManager) -> Self {
+        self.lock_manager = lock_manager;
+        self
+    }
+
     pub fn load(dir: PathBuf, key_size: usize) -> Self {
         TableStore {
             dir,
             key_size,
             cached_tables: Default::default(),
+            lock_manager: LockManager::new(),
         }
     }
 
@@ -461,7 +472,12 @@ impl TableStore {
     }
 
     fn lock(&self) -> TableStoreResult<FileLock> {
-        FileLock::lock(self.dir.join("lock")).map_err(TableStoreError::Lock)
+        FileLock::lock_with_mode(
+            self.dir.join("lock"),
+            self.lock_manager.timeout(),
+            self.lock_manager.mode(),
+        )
+        .map_err(TableStoreError::Lock)
     }
 
     fn load_table(&self, name: String) -> TableStoreResult<Arc<ReadonlyTable>> {
@@ -550,6 +566,7 @@ mod tests {
     use test_case::test_case;
 
     use super::*;
+    use crate::lock::LockMode;
     use crate::tests::new_temp_dir;
 
     #[test_case(false; "memory")]
@@ -736,4 +753,19 @@ mod tests {
         let table = store.get_head().unwrap();
         assert_eq!(table.get_value(b"abc"), Some(b"value".as_slice()));
     }
+
+    #[test]
+    fn stacked_table_store_lock_mode() {
+        let temp_dir = new_temp_dir();
+        let store = TableStore::init(temp_dir.path().to_path_buf(), 3)
+            .with_lock_manager(LockManager::new().with_mode(LockMode::Nfs));
+        let lock_path = temp_dir.path().join("lock");
+
+        // The lock is taken in the mode of the store's lock manager
+        let lock = store.lock().unwrap();
+        let contents = std::fs::read_to_string(&lock_path).unwrap();
+        assert!(contents.contains("heartbeat: "), "{contents:?}");
+        drop(lock);
+        assert!(!lock_path.exists());
+    }
 }
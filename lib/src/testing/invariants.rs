@@ -0,0 +1,377 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Property-based checks of rebase and merge invariants.
+//!
+//! This module is only available with the `proptest` feature.
+//!
+//! The strategies in this module generate random commit DAGs and tree edits
+//! over a small set of paths, so that edits overlap often. The `check_*()`
+//! functions write them to a repo and verify invariants that must hold
+//! regardless of the [`Backend`](crate::backend::Backend) in use. A backend
+//! implementation can run them against its own store:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn rebase_preserves_change_ids(dag in arb_commit_dag(8), index: Index) {
+//!         let repo = init_repo_with_my_backend();
+//!         check_rebase_preserves_change_ids(&repo, &dag, index)?;
+//!     }
+//! }
+//! ```
+//!
+//! Backend errors panic; violated invariants are reported as test case
+//! failures so that proptest can shrink them.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use proptest::prelude::*;
+use proptest::sample::Index;
+use proptest::test_runner::TestCaseError;
+
+use crate::backend::BackendResult;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::conflicts::choose_materialized_conflict_marker_len;
+use crate::conflicts::extract_as_single_hunk;
+use crate::conflicts::materialize_merge_result_to_bytes;
+use crate::conflicts::parse_conflict;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::files;
+use crate::files::MergeResult;
+use crate::merge::Merge;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::repo::MutableRepo;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo_path::RepoPathBuf;
+use crate::rewrite::merge_commit_trees;
+use crate::rewrite::RebaseOptions;
+use crate::rewrite::RebasedCommit;
+use crate::store::Store;
+
+/// Paths that generated tree edits touch. None of them is a prefix of
+/// another, so edits never turn a file into a directory or vice versa.
+pub const PATHS: &[&str] = &["a", "b", "dir/c", "dir/d", "dir/sub/e"];
+
+/// Lines that generated file contents are made of.
+const LINES: &[&str] = &["1\n", "2\n", "3\n"];
+
+/// Edits to apply on top of a tree. Paths mapped to `None` are removed.
+#[derive(Clone, Debug, Default)]
+pub struct TreeEdits(pub BTreeMap<RepoPathBuf, Option<String>>);
+
+/// Commit in a [`CommitDag`].
+#[derive(Clone, Debug)]
+pub struct DagCommit {
+    /// Indexes of the parents in [`CommitDag::commits`]. Parents always come
+    /// before their children. If empty, the parent is the root commit.
+    pub parents: Vec<usize>,
+    /// Edits on top of the merged parent trees.
+    pub edits: TreeEdits,
+}
+
+/// Shape and contents of a commit graph, in topological order.
+#[derive(Clone, Debug)]
+pub struct CommitDag {
+    /// The commits, parents before children.
+    pub commits: Vec<DagCommit>,
+}
+
+impl CommitDag {
+    /// Returns the indexes of the commits that are descendants of the commit
+    /// at `index`, excluding the commit itself.
+    pub fn descendants(&self, index: usize) -> HashSet<usize> {
+        let mut descendants = HashSet::new();
+        for (i, commit) in self.commits.iter().enumerate().skip(index + 1) {
+            if commit
+                .parents
+                .iter()
+                .any(|parent| *parent == index || descendants.contains(parent))
+            {
+                descendants.insert(i);
+            }
+        }
+        descendants
+    }
+}
+
+/// Generates file contents of up to a few lines.
+pub fn arb_file_contents() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(LINES), 0..4).prop_map(|lines| lines.concat())
+}
+
+/// Generates edits to some of the [`PATHS`].
+pub fn arb_tree_edits() -> impl Strategy<Value = TreeEdits> {
+    prop::collection::btree_map(
+        prop::sample::select(PATHS),
+        prop::option::of(arb_file_contents()),
+        0..=PATHS.len(),
+    )
+    .prop_map(|edits| {
+        TreeEdits(
+            edits
+                .into_iter()
+                .map(|(path, contents)| (RepoPathBuf::from_internal_string(path), contents))
+                .collect(),
+        )
+    })
+}
+
+/// Generates a commit DAG of 1 to `max_commits` commits. Each commit has at
+/// most two parents.
+pub fn arb_commit_dag(max_commits: usize) -> impl Strategy<Value = CommitDag> {
+    (1..=max_commits)
+        .prop_flat_map(|num_commits| {
+            (0..num_commits)
+                .map(|i| {
+                    let parents = prop::sample::subsequence((0..i).collect_vec(), 0..=i.min(2));
+                    (parents, arb_tree_edits())
+                        .prop_map(|(parents, edits)| DagCommit { parents, edits })
+                })
+                .collect_vec()
+        })
+        .prop_map(|commits| CommitDag { commits })
+}
+
+/// Writes the files in `edits` to the store and returns `base` with the edits
+/// applied.
+pub fn apply_tree_edits(
+    store: &Arc<Store>,
+    base: &MergedTree,
+    edits: &TreeEdits,
+) -> BackendResult<MergedTree> {
+    let mut tree_builder = MergedTreeBuilder::new(base.id());
+    for (path, contents) in &edits.0 {
+        let value = match contents {
+            Some(contents) => {
+                let id = store
+                    .write_file(path, &mut contents.as_bytes())
+                    .block_on()?;
+                Merge::normal(TreeValue::File {
+                    id,
+                    executable: false,
+                })
+            }
+            None => Merge::absent(),
+        };
+        tree_builder.set_or_remove(path.clone(), value);
+    }
+    let tree_id = tree_builder.write_tree(store)?;
+    store.get_root_tree(&tree_id)
+}
+
+/// Writes the commits in `dag` to `mut_repo`. Returns them in the same order.
+pub fn write_commit_dag(mut_repo: &mut MutableRepo, dag: &CommitDag) -> BackendResult<Vec<Commit>> {
+    let store = mut_repo.store().clone();
+    let mut commits: Vec<Commit> = vec![];
+    for (i, dag_commit) in dag.commits.iter().enumerate() {
+        let parents = if dag_commit.parents.is_empty() {
+            vec![store.root_commit()]
+        } else {
+            dag_commit
+                .parents
+                .iter()
+                .map(|&parent| commits[parent].clone())
+                .collect()
+        };
+        let parent_tree = merge_commit_trees(mut_repo, &parents)?;
+        let tree = apply_tree_edits(&store, &parent_tree, &dag_commit.edits)?;
+        let parent_ids = parents.iter().map(|parent| parent.id().clone()).collect();
+        let commit = mut_repo
+            .new_commit(parent_ids, tree.id())
+            .set_description(format!("commit {i}"))
+            .write()?;
+        commits.push(commit);
+    }
+    Ok(commits)
+}
+
+fn write_tree(store: &Arc<Store>, base: &MergedTree, edits: &TreeEdits) -> MergedTree {
+    apply_tree_edits(store, base, edits).unwrap()
+}
+
+fn write_base_tree(store: &Arc<Store>, edits: &TreeEdits) -> MergedTree {
+    let empty_tree = store.get_root_tree(&store.empty_merged_tree_id()).unwrap();
+    write_tree(store, &empty_tree, edits)
+}
+
+/// Checks that rewriting one commit in `dag` rebases exactly its descendants,
+/// and that the rebased commits keep their change ids and trees.
+///
+/// Nothing is committed to `repo`.
+pub fn check_rebase_preserves_change_ids(
+    repo: &Arc<ReadonlyRepo>,
+    dag: &CommitDag,
+    rewritten: Index,
+) -> Result<(), TestCaseError> {
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commits = write_commit_dag(mut_repo, dag).unwrap();
+    let rewritten_index = rewritten.index(commits.len());
+    mut_repo
+        .rewrite_commit(&commits[rewritten_index])
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    let mut rebased = vec![];
+    mut_repo
+        .rebase_descendants_with_options(&RebaseOptions::default(), |old_commit, new_commit| {
+            rebased.push((old_commit, new_commit));
+        })
+        .unwrap();
+
+    let expected_ids: HashSet<_> = dag
+        .descendants(rewritten_index)
+        .into_iter()
+        .map(|i| commits[i].id().clone())
+        .collect();
+    let actual_ids: HashSet<_> = rebased
+        .iter()
+        .map(|(old_commit, _)| old_commit.id().clone())
+        .collect();
+    prop_assert_eq!(actual_ids, expected_ids);
+    for (old_commit, new_commit) in &rebased {
+        let RebasedCommit::Rewritten(new_commit) = new_commit else {
+            return Err(TestCaseError::fail(format!(
+                "commit {} was abandoned",
+                old_commit.id()
+            )));
+        };
+        prop_assert_eq!(new_commit.change_id(), old_commit.change_id());
+        // Only the description changed, so the trees don't change either.
+        prop_assert_eq!(new_commit.tree_id(), old_commit.tree_id());
+    }
+    Ok(())
+}
+
+/// Checks that merging two trees edited from a common base gives the same
+/// result in either order, if the merge has no conflicts.
+pub fn check_merge_commutative(
+    repo: &Arc<ReadonlyRepo>,
+    base: &TreeEdits,
+    side1: &TreeEdits,
+    side2: &TreeEdits,
+) -> Result<(), TestCaseError> {
+    let store = repo.store();
+    let base_tree = write_base_tree(store, base);
+    let tree1 = write_tree(store, &base_tree, side1);
+    let tree2 = write_tree(store, &base_tree, side2);
+
+    let merged12 = tree1.merge(&base_tree, &tree2).unwrap();
+    let merged21 = tree2.merge(&base_tree, &tree1).unwrap();
+    prop_assert_eq!(merged12.has_conflict(), merged21.has_conflict());
+    if !merged12.has_conflict() {
+        prop_assert_eq!(merged12.id(), merged21.id());
+    }
+    Ok(())
+}
+
+/// Checks that merging three trees edited from a common base gives the same
+/// result regardless of grouping, if the merges have no conflicts.
+pub fn check_merge_associative(
+    repo: &Arc<ReadonlyRepo>,
+    base: &TreeEdits,
+    side1: &TreeEdits,
+    side2: &TreeEdits,
+    side3: &TreeEdits,
+) -> Result<(), TestCaseError> {
+    let store = repo.store();
+    let base_tree = write_base_tree(store, base);
+    let tree1 = write_tree(store, &base_tree, side1);
+    let tree2 = write_tree(store, &base_tree, side2);
+    let tree3 = write_tree(store, &base_tree, side3);
+
+    let merged12 = tree1.merge(&base_tree, &tree2).unwrap();
+    let merged12_3 = merged12.merge(&base_tree, &tree3).unwrap();
+    let merged23 = tree2.merge(&base_tree, &tree3).unwrap();
+    let merged1_23 = tree1.merge(&base_tree, &merged23).unwrap();
+    if !merged12_3.has_conflict() && !merged1_23.has_conflict() {
+        prop_assert_eq!(merged12_3.id(), merged1_23.id());
+    }
+    Ok(())
+}
+
+/// Checks that a conflicted merge of two trees survives being stored in a
+/// commit, and that each conflicted file can be materialized with conflict
+/// markers and parsed back.
+///
+/// `reload_store` must return a new [`Store`] for the same backend storage as
+/// `repo`, so that reading from it bypasses the caches of `repo.store()`. It
+/// is called after the commit has been written. Nothing is committed to
+/// `repo`.
+pub fn check_conflict_round_trip(
+    repo: &Arc<ReadonlyRepo>,
+    reload_store: impl FnOnce() -> Arc<Store>,
+    base: &TreeEdits,
+    side1: &TreeEdits,
+    side2: &TreeEdits,
+) -> Result<(), TestCaseError> {
+    let store = repo.store();
+    let base_tree = write_base_tree(store, base);
+    let tree1 = write_tree(store, &base_tree, side1);
+    let tree2 = write_tree(store, &base_tree, side2);
+    let merged = tree1.merge(&base_tree, &tree2).unwrap();
+
+    let mut tx = repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(vec![store.root_commit_id().clone()], merged.id())
+        .write()
+        .unwrap();
+    let reloaded_store = reload_store();
+    let reloaded_commit = reloaded_store.get_commit(commit.id()).unwrap();
+    prop_assert_eq!(reloaded_commit.tree_id(), &merged.id());
+    let reloaded_tree = reloaded_commit.tree().unwrap();
+    let entries = merged.entries().map(|(path, value)| (path, value.unwrap()));
+    let reloaded_entries = reloaded_tree
+        .entries()
+        .map(|(path, value)| (path, value.unwrap()));
+    prop_assert_eq!(entries.collect_vec(), reloaded_entries.collect_vec());
+
+    for (path, value) in merged.conflicts() {
+        let Some(file_ids) = value.unwrap().to_file_merge() else {
+            continue;
+        };
+        let contents = extract_as_single_hunk(&file_ids, store, &path)
+            .block_on()
+            .unwrap();
+        let MergeResult::Conflict(hunks) = files::merge(&contents) else {
+            continue;
+        };
+        let marker_len = choose_materialized_conflict_marker_len(&contents);
+        let mut styles = vec![ConflictMarkerStyle::Diff, ConflictMarkerStyle::Snapshot];
+        if contents.num_sides() == 2 {
+            styles.push(ConflictMarkerStyle::Git);
+        }
+        for style in styles {
+            let materialized = materialize_merge_result_to_bytes(&contents, style);
+            let parsed = parse_conflict(&materialized, contents.num_sides(), marker_len);
+            prop_assert_eq!(
+                parsed.as_ref(),
+                Some(&hunks),
+                "style: {:?}, materialized: {:?}",
+                style,
+                materialized
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,220 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixtures for writing tests against this crate.
+//!
+//! This module is only available with the `testing` feature. It lets crates
+//! built on top of `jj-lib` set up throwaway repositories and commit graphs in
+//! their own tests. The functions here panic on failure instead of returning
+//! errors, which is usually what tests want.
+
+#[cfg(feature = "proptest")]
+pub mod invariants;
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use tempfile::TempDir;
+
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::commit_builder::CommitBuilder;
+use crate::config::ConfigLayer;
+use crate::config::ConfigSource;
+use crate::config::StackedConfig;
+use crate::local_backend::LocalBackend;
+use crate::merged_tree::MergedTree;
+use crate::repo::MutableRepo;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::settings::UserSettings;
+use crate::signing::Signer;
+use crate::store::Store;
+use crate::tree::Tree;
+use crate::tree_builder::TreeBuilder;
+
+/// Returns new low-level config object that includes fake user configuration
+/// needed to run basic operations.
+pub fn base_user_config() -> StackedConfig {
+    let config_text = r#"
+        user.name = "Test User"
+        user.email = "test.user@example.com"
+        operation.username = "test-username"
+        operation.hostname = "host.example.com"
+        debug.randomness-seed = 42
+    "#;
+    let mut config = StackedConfig::with_defaults();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, config_text).unwrap());
+    config
+}
+
+/// Returns new immutable settings object that includes fake user configuration
+/// needed to run basic operations.
+pub fn user_settings() -> UserSettings {
+    UserSettings::from_config(base_user_config()).unwrap()
+}
+
+/// Repository backed by the local backend in a temporary directory.
+///
+/// The directory is deleted when this value is dropped.
+#[derive(Debug)]
+pub struct TestRepo {
+    /// The repository at the operation it was initialized at.
+    pub repo: Arc<ReadonlyRepo>,
+    repo_path: PathBuf,
+    _temp_dir: TempDir,
+}
+
+impl TestRepo {
+    /// Initializes a new repository with [`user_settings()`].
+    pub fn init() -> Self {
+        Self::init_with_settings(&user_settings())
+    }
+
+    /// Initializes a new repository with the given settings.
+    pub fn init_with_settings(settings: &UserSettings) -> Self {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("jj-test-")
+            .tempdir()
+            .unwrap();
+        let repo_path = temp_dir.path().join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        let repo = ReadonlyRepo::init(
+            settings,
+            &repo_path,
+            &|_settings, store_path| Ok(Box::new(LocalBackend::init(store_path))),
+            Signer::from_settings(settings).unwrap(),
+            ReadonlyRepo::default_op_store_initializer(),
+            ReadonlyRepo::default_op_heads_store_initializer(),
+            ReadonlyRepo::default_index_store_initializer(),
+            ReadonlyRepo::default_submodule_store_initializer(),
+        )
+        .unwrap();
+        TestRepo {
+            repo,
+            repo_path,
+            _temp_dir: temp_dir,
+        }
+    }
+
+    /// Path to the repository directory (the one containing `store/`,
+    /// `op_store/`, etc.)
+    pub fn repo_path(&self) -> &Path {
+        &self.repo_path
+    }
+}
+
+/// Writes a file with the given contents to the store.
+pub fn write_file(store: &Store, path: &RepoPath, contents: &str) -> FileId {
+    store
+        .write_file(path, &mut contents.as_bytes())
+        .block_on()
+        .unwrap()
+}
+
+/// Writes a non-executable file to the store and adds it to the tree builder.
+pub fn write_normal_file(
+    tree_builder: &mut TreeBuilder,
+    path: &RepoPath,
+    contents: &str,
+) -> FileId {
+    let id = write_file(tree_builder.store(), path, contents);
+    tree_builder.set(
+        path.to_owned(),
+        TreeValue::File {
+            id: id.clone(),
+            executable: false,
+        },
+    );
+    id
+}
+
+/// Creates a tree containing the given files.
+pub fn create_single_tree(repo: &Arc<ReadonlyRepo>, path_contents: &[(&RepoPath, &str)]) -> Tree {
+    let store = repo.store();
+    let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+    for (path, contents) in path_contents {
+        write_normal_file(&mut tree_builder, path, contents);
+    }
+    let id = tree_builder.write_tree().unwrap();
+    store.get_tree(RepoPathBuf::root(), &id).unwrap()
+}
+
+/// Creates a resolved merged tree containing the given files.
+pub fn create_tree(repo: &Arc<ReadonlyRepo>, path_contents: &[(&RepoPath, &str)]) -> MergedTree {
+    MergedTree::resolved(create_single_tree(repo, path_contents))
+}
+
+/// Creates a tree containing a single file with a random name.
+#[must_use]
+pub fn create_random_tree(repo: &Arc<ReadonlyRepo>) -> MergedTreeId {
+    let number = rand::random::<u32>();
+    let path = RepoPathBuf::from_internal_string(format!("file{number}"));
+    create_tree(repo, &[(&path, "contents")]).id()
+}
+
+/// Starts building a commit on top of the root commit with a random tree and
+/// description.
+pub fn create_random_commit(mut_repo: &mut MutableRepo) -> CommitBuilder<'_> {
+    let tree_id = create_random_tree(mut_repo.base_repo());
+    let number = rand::random::<u32>();
+    mut_repo
+        .new_commit(vec![mut_repo.store().root_commit_id().clone()], tree_id)
+        .set_description(format!("random commit {number}"))
+}
+
+/// Writes a commit on top of the root commit with a random tree and
+/// description.
+pub fn write_random_commit(mut_repo: &mut MutableRepo) -> Commit {
+    create_random_commit(mut_repo).write().unwrap()
+}
+
+/// Helper for creating commit graphs of a given shape.
+///
+/// Each commit gets a random tree, so no two commits are the same.
+pub struct CommitGraphBuilder<'repo> {
+    mut_repo: &'repo mut MutableRepo,
+}
+
+impl<'repo> CommitGraphBuilder<'repo> {
+    /// Creates a builder that writes commits to `mut_repo`.
+    pub fn new(mut_repo: &'repo mut MutableRepo) -> Self {
+        CommitGraphBuilder { mut_repo }
+    }
+
+    /// Writes a commit whose only parent is the root commit.
+    pub fn initial_commit(&mut self) -> Commit {
+        write_random_commit(self.mut_repo)
+    }
+
+    /// Writes a commit with the given parents.
+    pub fn commit_with_parents(&mut self, parents: &[&Commit]) -> Commit {
+        let parent_ids = parents
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect_vec();
+        create_random_commit(self.mut_repo)
+            .set_parents(parent_ids)
+            .write()
+            .unwrap()
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Git-style trailers (`Key: value` lines) at the end of commit descriptions.
+
+use crate::backend::ChangeId;
+use crate::backend::Signature;
+use crate::object_id::ObjectId as _;
+
+/// Returns the `(key, value)` pairs of the trailer paragraph of the
+/// `description`, or an empty list if the last paragraph isn't made of
+/// trailers only.
+pub fn parse_trailers(description: &str) -> Vec<(&str, &str)> {
+    let Some(paragraph) = description.trim_end().rsplit("\n\n").next() else {
+        return vec![];
+    };
+    // The subject line can't be a trailer paragraph.
+    if paragraph.len() == description.trim_end().len() {
+        return vec![];
+    }
+    paragraph
+        .lines()
+        .map(parse_trailer_line)
+        .collect::<Option<_>>()
+        .unwrap_or_default()
+}
+
+fn parse_trailer_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(": ")?;
+    let is_key = !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+    is_key.then_some((key, value.trim()))
+}
+
+/// Appends the `key: value` trailer to the `description` unless an identical
+/// trailer is already present. Empty descriptions are left unchanged.
+pub fn append_trailer(description: &str, key: &str, value: &str) -> String {
+    if description.trim().is_empty() {
+        return description.to_owned();
+    }
+    let trailers = parse_trailers(description);
+    if trailers.contains(&(key, value)) {
+        return description.to_owned();
+    }
+    let mut new_description = description.trim_end().to_owned();
+    if trailers.is_empty() {
+        new_description.push('\n');
+    }
+    new_description.push('\n');
+    new_description.push_str(&format!("{key}: {value}\n"));
+    new_description
+}
+
+/// Formats the value of a `Signed-off-by` trailer for the `signature`.
+pub fn signed_off_by_value(signature: &Signature) -> String {
+    format!("{} <{}>", signature.name, signature.email)
+}
+
+/// Formats the `change_id` as a Gerrit `Change-Id` value, which must be "I"
+/// followed by 40 hex digits.
+///
+/// Shorter ids are padded with the repeated hex encoding of "jjid", and longer
+/// ids are truncated.
+pub fn gerrit_change_id_value(change_id: &ChangeId) -> String {
+    const PADDING: &str = "6a6a6964";
+    let hex = change_id.hex();
+    let digits: String = hex
+        .chars()
+        .chain(PADDING.chars().cycle())
+        .take(40)
+        .collect();
+    format!("I{digits}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trailers() {
+        assert_eq!(parse_trailers(""), vec![]);
+        assert_eq!(parse_trailers("Key: value\n"), vec![]);
+        assert_eq!(parse_trailers("subject\n\nbody\n"), vec![]);
+        assert_eq!(
+            parse_trailers("subject\n\nKey: value\nOther-Key: x y\n"),
+            vec![("Key", "value"), ("Other-Key", "x y")]
+        );
+        // Mixed with non-trailer lines
+        assert_eq!(
+            parse_trailers("subject\n\nKey: value\nnot a trailer\n"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_append_trailer() {
+        assert_eq!(append_trailer("", "Key", "value"), "");
+        assert_eq!(
+            append_trailer("subject", "Key", "value"),
+            "subject\n\nKey: value\n"
+        );
+        assert_eq!(
+            append_trailer("subject\n\nbody\n", "Key", "value"),
+            "subject\n\nbody\n\nKey: value\n"
+        );
+        // Appended to existing trailers
+        assert_eq!(
+            append_trailer("subject\n\nA: b\n", "Key", "value"),
+            "subject\n\nA: b\nKey: value\n"
+        );
+        // Already present
+        assert_eq!(
+            append_trailer("subject\n\nKey: value\nA: b\n", "Key", "value"),
+            "subject\n\nKey: value\nA: b\n"
+        );
+    }
+
+    #[test]
+    fn test_gerrit_change_id_value() {
+        let change_id = ChangeId::from_hex("0123456789abcdef0123456789abcdef");
+        let value = gerrit_change_id_value(&change_id);
+        assert_eq!(value, "I0123456789abcdef0123456789abcdef6a6a6964");
+        assert_eq!(value.len(), 41);
+
+        let change_id = ChangeId::from_hex("0123456789abcdef");
+        let value = gerrit_change_id_value(&change_id);
+        assert_eq!(value, "I0123456789abcdef6a6a69646a6a69646a6a6964");
+
+        let change_id = ChangeId::from_hex("01234567890123456789012345678901234567890123456789");
+        let value = gerrit_change_id_value(&change_id);
+        assert_eq!(value, "I0123456789012345678901234567890123456789");
+    }
+}
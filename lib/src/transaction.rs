@@ -17,23 +17,40 @@
 use std::sync::Arc;
 
 use itertools::Itertools as _;
+use thiserror::Error;
 
-use crate::backend::Timestamp;
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::MergedTreeId;
+use crate::clock::Clock;
+use crate::commit::Commit;
 use crate::dag_walk;
 use crate::index::ReadonlyIndex;
 use crate::op_heads_store::OpHeadsStore;
 use crate::op_heads_store::OpHeadsStoreError;
 use crate::op_store;
 use crate::op_store::OperationMetadata;
+use crate::op_store::StoppedRebase;
 use crate::operation::Operation;
 use crate::repo::MutableRepo;
 use crate::repo::ReadonlyRepo;
 use crate::repo::Repo;
 use crate::repo::RepoLoader;
 use crate::repo::RepoLoaderError;
+use crate::resolution_cache::record_commit_resolutions;
+use crate::rewrite::RebaseError;
 use crate::settings::UserSettings;
 use crate::view::View;
 
+/// Error from attempts to write and publish a transaction.
+#[derive(Debug, Error)]
+pub enum TransactionCommitError {
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    OpHeadsStore(#[from] OpHeadsStoreError),
+}
+
 /// An in-memory representation of a repo and any changes being made to it.
 ///
 /// Within the scope of a transaction, changes to the repository are made
@@ -49,19 +66,19 @@ pub struct Transaction {
     mut_repo: MutableRepo,
     parent_ops: Vec<Operation>,
     op_metadata: OperationMetadata,
-    end_time: Option<Timestamp>,
+    clock: Arc<dyn Clock>,
 }
 
 impl Transaction {
     pub fn new(mut_repo: MutableRepo, user_settings: &UserSettings) -> Transaction {
         let parent_ops = vec![mut_repo.base_repo().operation().clone()];
         let op_metadata = create_op_metadata(user_settings, "".to_string(), false);
-        let end_time = user_settings.operation_timestamp();
+        let clock = user_settings.operation_clock();
         Transaction {
             mut_repo,
             parent_ops,
             op_metadata,
-            end_time,
+            clock,
         }
     }
 
@@ -98,34 +115,118 @@ impl Transaction {
         Ok(())
     }
 
+    /// Replaces the view with `view`, keeping working-copy changes made since
+    /// `since_view`.
+    ///
+    /// `view` should point each workspace at the working-copy commit to
+    /// restore. For each workspace whose working-copy commit differs between
+    /// the base repo and `view`, the changes between the working-copy commit in
+    /// `since_view` and the current one are applied on top of the restored
+    /// commit, and so is the description if it was changed since. The commit
+    /// is rewritten in place, so it keeps its change id, and the current
+    /// working-copy commit is abandoned if it's a visible head no longer
+    /// checked out anywhere. This is useful for undoing an operation
+    /// (with `since_view` being the view of the undone operation) without
+    /// losing files edited afterwards.
+    pub fn restore_view_preserving_working_copies(
+        &mut self,
+        view: op_store::View,
+        since_view: &op_store::View,
+    ) -> Result<(), RebaseError> {
+        let base_repo = self.base_repo().clone();
+        let store = base_repo.store();
+        self.mut_repo.set_view(view);
+        for (workspace_id, current_wc_id) in base_repo.view().wc_commit_ids() {
+            let Some(restored_wc_id) = self.mut_repo.view().get_wc_commit_id(workspace_id) else {
+                continue;
+            };
+            let Some(since_wc_id) = since_view.wc_commit_ids.get(workspace_id) else {
+                continue;
+            };
+            if restored_wc_id == current_wc_id || since_wc_id == current_wc_id {
+                continue;
+            }
+            let restored_wc_commit = store.get_commit(restored_wc_id)?;
+            let current_wc_commit = store.get_commit(current_wc_id)?;
+            let since_wc_commit = store.get_commit(since_wc_id)?;
+            let new_tree = restored_wc_commit
+                .tree()?
+                .merge(&since_wc_commit.tree()?, &current_wc_commit.tree()?)?;
+            let new_description =
+                if current_wc_commit.description() != since_wc_commit.description() {
+                    current_wc_commit.description()
+                } else {
+                    restored_wc_commit.description()
+                };
+            if new_tree.id() == *restored_wc_commit.tree_id()
+                && new_description == restored_wc_commit.description()
+            {
+                continue;
+            }
+            self.mut_repo
+                .rewrite_commit(&restored_wc_commit)
+                .set_tree_id(new_tree.id())
+                .set_description(new_description)
+                .write()?;
+            // The changes now live in the restored commit.
+            let view = self.mut_repo.view();
+            if view.heads().contains(current_wc_id)
+                && !view.wc_commit_ids().values().contains(current_wc_id)
+            {
+                self.mut_repo.record_abandoned_commit(&current_wc_commit);
+            }
+        }
+        // Moves the working-copy commits to the rewritten commits.
+        self.mut_repo.rebase_descendants()?;
+        Ok(())
+    }
+
     pub fn set_is_snapshot(&mut self, is_snapshot: bool) {
         self.op_metadata.is_snapshot = is_snapshot;
     }
 
+    /// Records the tree the working copy was snapshotted to before or during
+    /// this transaction.
+    pub fn set_working_copy_tree_id(&mut self, tree_id: &MergedTreeId) {
+        self.op_metadata.working_copy_tree_ids = Some(tree_id.to_merge());
+    }
+
+    /// Records that changes in the working copy were committed as a separate
+    /// snapshot operation before this transaction.
+    pub fn set_includes_snapshot(&mut self, includes_snapshot: bool) {
+        self.op_metadata.includes_snapshot = includes_snapshot;
+    }
+
+    /// Records a rebase which was stopped by a conflict in this transaction.
+    pub fn set_stopped_rebase(&mut self, stopped_rebase: Option<StoppedRebase>) {
+        self.op_metadata.stopped_rebase = stopped_rebase;
+    }
+
     /// Writes the transaction to the operation store and publishes it.
     pub fn commit(
         self,
         description: impl Into<String>,
-    ) -> Result<Arc<ReadonlyRepo>, OpHeadsStoreError> {
-        self.write(description).publish()
+    ) -> Result<Arc<ReadonlyRepo>, TransactionCommitError> {
+        Ok(self.write(description)?.publish()?)
     }
 
     /// Writes the transaction to the operation store, but does not publish it.
     /// That means that a repo can be loaded at the operation, but the
     /// operation will not be seen when loading the repo at head.
-    pub fn write(mut self, description: impl Into<String>) -> UnpublishedOperation {
-        let mut_repo = self.mut_repo;
+    pub fn write(mut self, description: impl Into<String>) -> BackendResult<UnpublishedOperation> {
+        let mut mut_repo = self.mut_repo;
         // TODO: Should we instead just do the rebasing here if necessary?
         assert!(
             !mut_repo.has_rewrites(),
             "BUG: Descendants have not been rebased after the last rewrites."
         );
         let base_repo = mut_repo.base_repo().clone();
-        let (mut_index, view) = mut_repo.consume();
+        let resolved_conflicts = mut_repo.take_resolved_conflicts();
+        let (mut_index, view) = mut_repo.consume()?;
 
         let view_id = base_repo.op_store().write_view(view.store_view()).unwrap();
         self.op_metadata.description = description.into();
-        self.op_metadata.end_time = self.end_time.unwrap_or_else(Timestamp::now);
+        self.op_metadata.end_time = self.clock.now();
         let parents = self.parent_ops.iter().map(|op| op.id().clone()).collect();
         let store_operation = op_store::Operation {
             view_id,
@@ -142,7 +243,13 @@ impl Transaction {
             .index_store()
             .write_index(mut_index, &operation)
             .unwrap();
-        UnpublishedOperation::new(base_repo.loader(), operation, view, index)
+        Ok(UnpublishedOperation::new(
+            base_repo.loader(),
+            operation,
+            view,
+            index,
+            resolved_conflicts,
+        ))
     }
 }
 
@@ -151,9 +258,7 @@ pub fn create_op_metadata(
     description: String,
     is_snapshot: bool,
 ) -> OperationMetadata {
-    let start_time = user_settings
-        .operation_timestamp()
-        .unwrap_or_else(Timestamp::now);
+    let start_time = user_settings.operation_clock().now();
     let end_time = start_time;
     let hostname = user_settings.operation_hostname().to_owned();
     let username = user_settings.operation_username().to_owned();
@@ -165,6 +270,9 @@ pub fn create_op_metadata(
         username,
         is_snapshot,
         tags: Default::default(),
+        working_copy_tree_ids: None,
+        includes_snapshot: false,
+        stopped_rebase: None,
     }
 }
 
@@ -180,6 +288,7 @@ pub fn create_op_metadata(
 pub struct UnpublishedOperation {
     op_heads_store: Arc<dyn OpHeadsStore>,
     repo: Arc<ReadonlyRepo>,
+    resolved_conflicts: Vec<(Commit, Commit)>,
 }
 
 impl UnpublishedOperation {
@@ -188,10 +297,12 @@ impl UnpublishedOperation {
         operation: Operation,
         view: View,
         index: Box<dyn ReadonlyIndex>,
+        resolved_conflicts: Vec<(Commit, Commit)>,
     ) -> Self {
         UnpublishedOperation {
             op_heads_store: repo_loader.op_heads_store().clone(),
             repo: repo_loader.create_from(operation, view, index),
+            resolved_conflicts,
         }
     }
 
@@ -200,9 +311,16 @@ impl UnpublishedOperation {
     }
 
     pub fn publish(self) -> Result<Arc<ReadonlyRepo>, OpHeadsStoreError> {
-        let _lock = self.op_heads_store.lock()?;
-        self.op_heads_store
-            .update_op_heads(self.operation().parent_ids(), self.operation().id())?;
+        {
+            let _lock = self.op_heads_store.lock()?;
+            self.op_heads_store
+                .update_op_heads(self.operation().parent_ids(), self.operation().id())?;
+        }
+        // The cache only saves work when rebasing later, so the operation
+        // succeeds even if the resolutions can't be recorded.
+        if let Err(err) = record_commit_resolutions(&self.repo, &self.resolved_conflicts) {
+            tracing::warn!(?err, "failed to record conflict resolutions");
+        }
         Ok(self.repo)
     }
 
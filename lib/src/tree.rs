@@ -14,6 +14,7 @@
 
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Error;
 use std::fmt::Formatter;
@@ -150,6 +151,42 @@ impl Tree {
         }
     }
 
+    /// Looks up the values at the given paths. Subtrees shared by several of
+    /// the paths are only loaded once.
+    pub fn values_for_paths(&self, paths: &[&RepoPath]) -> BackendResult<Vec<Option<TreeValue>>> {
+        assert_eq!(self.dir(), RepoPath::root());
+        let mut sub_trees = HashMap::new();
+        paths
+            .iter()
+            .map(|path| match path.split() {
+                Some((dir, basename)) => {
+                    let tree = self.cached_sub_tree(&mut sub_trees, dir)?;
+                    Ok(tree.and_then(|tree| tree.data.value(basename).cloned()))
+                }
+                None => Ok(Some(TreeValue::Tree(self.id.clone()))),
+            })
+            .collect()
+    }
+
+    fn cached_sub_tree(
+        &self,
+        cache: &mut HashMap<RepoPathBuf, Option<Tree>>,
+        dir: &RepoPath,
+    ) -> BackendResult<Option<Tree>> {
+        if let Some(tree) = cache.get(dir) {
+            return Ok(tree.clone());
+        }
+        let tree = match dir.split() {
+            Some((parent_dir, name)) => match self.cached_sub_tree(cache, parent_dir)? {
+                Some(parent_tree) => parent_tree.sub_tree(name)?,
+                None => None,
+            },
+            None => Some(self.clone()),
+        };
+        cache.insert(dir.to_owned(), tree.clone());
+        Ok(tree)
+    }
+
     pub fn sub_tree(&self, name: &RepoPathComponent) -> BackendResult<Option<Tree>> {
         if let Some(sub_tree) = self.data.value(name) {
             match sub_tree {
@@ -218,12 +255,19 @@ struct TreeEntriesDirItem {
     entries: Vec<(RepoPathBuf, TreeValue)>,
 }
 
-impl From<Tree> for TreeEntriesDirItem {
-    fn from(tree: Tree) -> Self {
-        let mut entries = tree
-            .entries_non_recursive()
-            .map(|entry| (tree.dir().join(entry.name()), entry.value().clone()))
-            .collect_vec();
+impl TreeEntriesDirItem {
+    fn new(tree: Tree, matcher: &dyn Matcher) -> Self {
+        let dir = tree.dir();
+        let mut entries = match matcher.visit(dir).specific_names() {
+            Some(names) => names
+                .into_iter()
+                .filter_map(|name| Some((dir.join(name), tree.value(name)?.clone())))
+                .collect_vec(),
+            None => tree
+                .entries_non_recursive()
+                .map(|entry| (dir.join(entry.name()), entry.value().clone()))
+                .collect_vec(),
+        };
         entries.reverse();
         Self { tree, entries }
     }
@@ -231,9 +275,8 @@ impl From<Tree> for TreeEntriesDirItem {
 
 impl<'matcher> TreeEntriesIterator<'matcher> {
     fn new(tree: Tree, matcher: &'matcher dyn Matcher) -> Self {
-        // TODO: Restrict walk according to Matcher::visit()
         Self {
-            stack: vec![TreeEntriesDirItem::from(tree)],
+            stack: vec![TreeEntriesDirItem::new(tree, matcher)],
             matcher,
         }
     }
@@ -252,7 +295,8 @@ impl Iterator for TreeEntriesIterator<'_> {
                             continue;
                         }
                         let subtree = top.tree.known_sub_tree(path, &id);
-                        self.stack.push(TreeEntriesDirItem::from(subtree));
+                        self.stack
+                            .push(TreeEntriesDirItem::new(subtree, self.matcher));
                     }
                     value => {
                         if self.matcher.matches(&path) {
@@ -22,16 +22,24 @@ use thiserror::Error;
 
 use crate::backend::CommitId;
 use crate::op_store;
+use crate::op_store::BisectState;
 use crate::op_store::BookmarkTarget;
 use crate::op_store::RefTarget;
 use crate::op_store::RefTargetOptionExt as _;
+use crate::op_store::RemoteConfig;
 use crate::op_store::RemoteRef;
 use crate::op_store::WorkspaceId;
 use crate::refs;
+use crate::refs::diff_named_commit_ids;
+use crate::refs::diff_named_ref_targets;
+use crate::refs::diff_named_remote_refs;
 use crate::refs::LocalAndRemoteRef;
 use crate::refs::RemoteRefSymbol;
 use crate::str_util::StringPattern;
 
+/// Prefix of the auto-generated names of anonymous heads.
+pub const ANONYMOUS_HEAD_PREFIX: &str = "head-";
+
 /// A wrapper around [`op_store::View`] that defines additional methods.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct View {
@@ -71,6 +79,39 @@ impl View {
         &self.data.head_ids
     }
 
+    /// Returns the auto-generated names of heads that no bookmark, tag, or
+    /// working copy points to.
+    ///
+    /// The names are assigned when a transaction is committed, so they don't
+    /// reflect changes made to a view within a transaction.
+    pub fn anonymous_heads(&self) -> &BTreeMap<String, CommitId> {
+        &self.data.anonymous_heads
+    }
+
+    /// Returns the commit that the anonymous head `name` points to.
+    pub fn get_anonymous_head(&self, name: &str) -> Option<&CommitId> {
+        self.data.anonymous_heads.get(name)
+    }
+
+    /// Returns the auto-generated name of the head `commit_id` if it is
+    /// anonymous.
+    pub fn anonymous_head_name(&self, commit_id: &CommitId) -> Option<&str> {
+        self.data
+            .anonymous_heads
+            .iter()
+            .find(|(_, id)| *id == commit_id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the commits marked during an ongoing bisection.
+    pub fn bisect_state(&self) -> &BisectState {
+        &self.data.bisect_state
+    }
+
+    pub fn set_bisect_state(&mut self, state: BisectState) {
+        self.data.bisect_state = state;
+    }
+
     /// Iterates pair of local and remote bookmarks by bookmark name.
     pub fn bookmarks(&self) -> impl Iterator<Item = (&str, BookmarkTarget<'_>)> {
         op_store::merge_join_bookmark_views(&self.data.local_bookmarks, &self.data.remote_views)
@@ -301,14 +342,38 @@ impl View {
         })
     }
 
+    /// Returns the remotes configured in the repo. Remotes of the backing Git
+    /// repo are only included if they were added or changed through
+    /// `git::add_remote()` and related functions.
+    pub fn remote_configs(&self) -> &BTreeMap<String, RemoteConfig> {
+        &self.data.remotes
+    }
+
+    pub fn get_remote_config(&self, remote_name: &str) -> Option<&RemoteConfig> {
+        self.data.remotes.get(remote_name)
+    }
+
+    pub fn set_remote_config(&mut self, remote_name: &str, config: RemoteConfig) {
+        self.data.remotes.insert(remote_name.to_owned(), config);
+    }
+
+    pub fn remove_remote_config(&mut self, remote_name: &str) {
+        self.data.remotes.remove(remote_name);
+    }
+
+    /// Removes the remote's configuration and the bookmarks tracked from it.
     pub fn remove_remote(&mut self, remote_name: &str) {
         self.data.remote_views.remove(remote_name);
+        self.data.remotes.remove(remote_name);
     }
 
     pub fn rename_remote(&mut self, old: &str, new: &str) {
         if let Some(remote_view) = self.data.remote_views.remove(old) {
             self.data.remote_views.insert(new.to_owned(), remote_view);
         }
+        if let Some(config) = self.data.remotes.remove(old) {
+            self.data.remotes.insert(new.to_owned(), config);
+        }
     }
 
     pub fn get_tag(&self, name: &str) -> &RefTarget {
@@ -381,6 +446,9 @@ impl View {
             git_refs,
             git_head,
             wc_commit_ids,
+            anonymous_heads,
+            bisect_state,
+            remotes: _,
         } = &self.data;
         itertools::chain!(
             head_ids,
@@ -394,7 +462,11 @@ impl View {
             }),
             git_refs.values().flat_map(ref_target_ids),
             ref_target_ids(git_head),
-            wc_commit_ids.values()
+            wc_commit_ids.values(),
+            anonymous_heads.values(),
+            &bisect_state.good_commits,
+            &bisect_state.bad_commits,
+            &bisect_state.skipped_commits
         )
     }
 
@@ -411,6 +483,108 @@ impl View {
     }
 }
 
+/// Changes between two views, as computed by [`diff()`].
+///
+/// Each changed entry holds the `(old, new)` pair. Absent refs are represented
+/// by absent targets, so added and removed refs can be told apart with
+/// [`RefTarget::is_absent()`]. Entries are sorted by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[expect(clippy::type_complexity)]
+pub struct ViewDiff<'a> {
+    pub wc_commits: Vec<(
+        &'a WorkspaceId,
+        (Option<&'a CommitId>, Option<&'a CommitId>),
+    )>,
+    pub added_heads: Vec<&'a CommitId>,
+    pub removed_heads: Vec<&'a CommitId>,
+    pub local_bookmarks: Vec<(&'a str, (&'a RefTarget, &'a RefTarget))>,
+    pub remote_bookmarks: Vec<(RemoteRefSymbol<'a>, (&'a RemoteRef, &'a RemoteRef))>,
+    pub tags: Vec<(&'a str, (&'a RefTarget, &'a RefTarget))>,
+    pub git_refs: Vec<(&'a str, (&'a RefTarget, &'a RefTarget))>,
+    pub git_head: Option<(&'a RefTarget, &'a RefTarget)>,
+}
+
+impl ViewDiff<'_> {
+    /// Returns true if the views are equivalent.
+    pub fn is_empty(&self) -> bool {
+        let ViewDiff {
+            wc_commits,
+            added_heads,
+            removed_heads,
+            local_bookmarks,
+            remote_bookmarks,
+            tags,
+            git_refs,
+            git_head,
+        } = self;
+        wc_commits.is_empty()
+            && added_heads.is_empty()
+            && removed_heads.is_empty()
+            && local_bookmarks.is_empty()
+            && remote_bookmarks.is_empty()
+            && tags.is_empty()
+            && git_refs.is_empty()
+            && git_head.is_none()
+    }
+}
+
+/// Compares `old_view` and `new_view`.
+pub fn diff<'a>(old_view: &'a View, new_view: &'a View) -> ViewDiff<'a> {
+    let wc_commits =
+        diff_named_commit_ids(old_view.wc_commit_ids(), new_view.wc_commit_ids()).collect();
+    let added_heads = new_view
+        .heads()
+        .difference(old_view.heads())
+        .sorted()
+        .collect();
+    let removed_heads = old_view
+        .heads()
+        .difference(new_view.heads())
+        .sorted()
+        .collect();
+    let local_bookmarks =
+        diff_named_ref_targets(old_view.local_bookmarks(), new_view.local_bookmarks()).collect();
+    let remote_bookmarks = diff_named_remote_refs(
+        old_view.all_remote_bookmarks(),
+        new_view.all_remote_bookmarks(),
+    )
+    .collect();
+    let tags = diff_named_ref_targets(
+        old_view
+            .tags()
+            .iter()
+            .map(|(name, target)| (name.as_str(), target)),
+        new_view
+            .tags()
+            .iter()
+            .map(|(name, target)| (name.as_str(), target)),
+    )
+    .collect();
+    let git_refs = diff_named_ref_targets(
+        old_view
+            .git_refs()
+            .iter()
+            .map(|(name, target)| (name.as_str(), target)),
+        new_view
+            .git_refs()
+            .iter()
+            .map(|(name, target)| (name.as_str(), target)),
+    )
+    .collect();
+    let git_head = (old_view.git_head() != new_view.git_head())
+        .then(|| (old_view.git_head(), new_view.git_head()));
+    ViewDiff {
+        wc_commits,
+        added_heads,
+        removed_heads,
+        local_bookmarks,
+        remote_bookmarks,
+        tags,
+        git_refs,
+        git_head,
+    }
+}
+
 /// Error from attempts to rename a workspace
 #[derive(Debug, Error)]
 pub enum RenameWorkspaceError {
@@ -26,27 +26,43 @@ use thiserror::Error;
 use tracing::instrument;
 
 use crate::backend::BackendError;
+use crate::backend::CommitId;
 use crate::backend::MergedTreeId;
 use crate::commit::Commit;
+use crate::config::ConfigGetError;
+use crate::config::ConfigGetResultExt as _;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::dag_walk;
+use crate::eol::EolSettings;
+use crate::fileset;
+use crate::fileset::FilesetDiagnostics;
+use crate::fileset::FilesetParseError;
 use crate::fsmonitor::FsmonitorSettings;
+use crate::git_lfs::LfsSettings;
 use crate::gitignore::GitIgnoreError;
 use crate::gitignore::GitIgnoreFile;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::Matcher;
-use crate::op_heads_store::OpHeadsStoreError;
+use crate::matchers::NothingMatcher;
+use crate::object_id::ObjectId;
 use crate::op_store::OpStoreError;
 use crate::op_store::OperationId;
 use crate::op_store::WorkspaceId;
 use crate::operation::Operation;
 use crate::repo::ReadonlyRepo;
 use crate::repo::Repo;
+use crate::repo::RepoLoaderError;
 use crate::repo::RewriteRootCommit;
 use crate::repo_path::InvalidRepoPathError;
+use crate::repo_path::PathNormalization;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
+use crate::repo_path::RepoPathUiConverter;
+use crate::rewrite::RebaseError;
+use crate::settings::HumanByteSize;
+use crate::settings::UserSettings;
 use crate::store::Store;
+use crate::transaction::TransactionCommitError;
 
 /// The trait all working-copy implementations must implement.
 pub trait WorkingCopy: Send {
@@ -72,6 +88,14 @@ pub trait WorkingCopy: Send {
     /// that all files should be checked out.
     fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError>;
 
+    /// Paths which new files were explicitly tracked or untracked at.
+    ///
+    /// The default implementation returns empty patterns, which is correct
+    /// for working copies that don't support `LockedWorkingCopy::track()`.
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        Ok(&EMPTY_TRACKING_PATTERNS)
+    }
+
     /// Locks the working copy and returns an instance with methods for updating
     /// the working copy files and state.
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError>;
@@ -87,6 +111,7 @@ pub trait WorkingCopyFactory {
         state_path: PathBuf,
         operation_id: OperationId,
         workspace_id: WorkspaceId,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError>;
 
     /// Load an existing working copy.
@@ -95,6 +120,7 @@ pub trait WorkingCopyFactory {
         store: Arc<Store>,
         working_copy_path: PathBuf,
         state_path: PathBuf,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError>;
 }
 
@@ -150,6 +176,31 @@ pub trait LockedWorkingCopy {
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError>;
 
+    /// See `WorkingCopy::tracking_patterns()`
+    fn tracking_patterns(&self) -> Result<&TrackingPatterns, WorkingCopyStateError> {
+        Ok(&EMPTY_TRACKING_PATTERNS)
+    }
+
+    /// Starts tracking new files at or under the `paths` in subsequent
+    /// snapshots, even if they don't match the start-tracking matcher.
+    ///
+    /// The default implementation fails as unsupported.
+    fn track(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        let _ = paths;
+        Err(tracking_patterns_unsupported_error())
+    }
+
+    /// Leaves new files at or under the `paths` untracked in subsequent
+    /// snapshots, even if they match the start-tracking matcher. Files which
+    /// are already tracked stay in the tree until removed from it, e.g. by
+    /// `reset()`.
+    ///
+    /// The default implementation fails as unsupported.
+    fn untrack(&mut self, paths: &[RepoPathBuf]) -> Result<(), WorkingCopyStateError> {
+        let _ = paths;
+        Err(tracking_patterns_unsupported_error())
+    }
+
     /// Finish the modifications to the working copy by writing the updated
     /// states to disk. Returns the new (unlocked) working copy.
     fn finish(
@@ -221,9 +272,49 @@ pub struct SnapshotOptions<'a> {
     pub max_new_file_size: u64,
     /// Expected conflict marker style for checking for changed files.
     pub conflict_marker_style: ConflictMarkerStyle,
+    /// How file names on disk are matched against tracked names, and how the
+    /// names of new files are recorded.
+    pub path_normalization: PathNormalization,
+    /// How line endings of files on disk are converted before storing them.
+    pub eol_settings: EolSettings,
+    /// Which files on disk are replaced by Git LFS pointers when storing them.
+    pub lfs_settings: LfsSettings,
 }
 
-impl SnapshotOptions<'_> {
+impl<'a> SnapshotOptions<'a> {
+    /// Creates options as configured by the user, without progress callback.
+    ///
+    /// New files matching `start_tracking_matcher` are tracked, and the files
+    /// ignored by `base_ignores` and the `.gitignore` files are skipped.
+    pub fn from_settings(
+        settings: &UserSettings,
+        base_ignores: Arc<GitIgnoreFile>,
+        start_tracking_matcher: &'a dyn Matcher,
+    ) -> Result<Self, ConfigGetError> {
+        let HumanByteSize(mut max_new_file_size) =
+            settings.get_value_with("snapshot.max-new-file-size", TryInto::try_into)?;
+        if max_new_file_size == 0 {
+            max_new_file_size = u64::MAX;
+        }
+        Ok(SnapshotOptions {
+            base_ignores,
+            fsmonitor_settings: settings.fsmonitor_settings()?,
+            progress: None,
+            start_tracking_matcher,
+            max_new_file_size,
+            conflict_marker_style: settings
+                .get("ui.conflict-marker-style")
+                .optional()?
+                .unwrap_or_default(),
+            path_normalization: PathNormalization {
+                unicode_nfc: settings.get_bool("snapshot.normalize-unicode-paths")?,
+                case_insensitive: settings.get_bool("snapshot.case-insensitive-paths")?,
+            },
+            eol_settings: EolSettings::from_settings(settings)?,
+            lfs_settings: LfsSettings::from_settings(settings)?,
+        })
+    }
+
     /// Create an instance for use in tests.
     pub fn empty_for_test() -> Self {
         SnapshotOptions {
@@ -233,10 +324,63 @@ impl SnapshotOptions<'_> {
             start_tracking_matcher: &EverythingMatcher,
             max_new_file_size: u64::MAX,
             conflict_marker_style: ConflictMarkerStyle::default(),
+            path_normalization: PathNormalization::default(),
+            eol_settings: EolSettings::default(),
+            lfs_settings: LfsSettings::default(),
         }
     }
 }
 
+/// How new files in the working copy are treated when snapshotting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewFilesMode {
+    /// Track new files matching `snapshot.auto-track`.
+    Track,
+    /// Leave new files untracked until they're tracked by `jj file track`.
+    LeaveUntracked,
+}
+
+impl NewFilesMode {
+    /// Reads the mode from the `snapshot.new-files` setting.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        settings.get("snapshot.new-files")
+    }
+}
+
+/// Error while building the matcher of new files to track.
+#[derive(Debug, Error)]
+pub enum AutoTrackingError {
+    /// Failed to read the settings.
+    #[error(transparent)]
+    Config(#[from] ConfigGetError),
+    /// The `snapshot.auto-track` fileset is invalid.
+    #[error(transparent)]
+    Parse(#[from] FilesetParseError),
+}
+
+/// Returns the matcher of new files to start tracking, as configured by
+/// `snapshot.new-files` and `snapshot.auto-track`. Parse warnings are added to
+/// `diagnostics`.
+pub fn auto_tracking_matcher(
+    settings: &UserSettings,
+    diagnostics: &mut FilesetDiagnostics,
+) -> Result<Box<dyn Matcher>, AutoTrackingError> {
+    if NewFilesMode::from_settings(settings)? == NewFilesMode::LeaveUntracked {
+        return Ok(Box::new(NothingMatcher));
+    }
+    let pattern = settings.get_string("snapshot.auto-track")?;
+    let expression = fileset::parse(
+        diagnostics,
+        &pattern,
+        &RepoPathUiConverter::Fs {
+            cwd: "".into(),
+            base: "".into(),
+        },
+    )?;
+    Ok(expression.to_matcher())
+}
+
 /// A callback for getting progress updates.
 pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 
@@ -245,6 +389,23 @@ pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 pub struct SnapshotStats {
     /// List of new (previously untracked) files which are still untracked.
     pub untracked_paths: BTreeMap<RepoPathBuf, UntrackedReason>,
+    /// How many files could be skipped based on their recorded metadata.
+    pub file_cache: FileCacheStats,
+}
+
+/// Stats about how many files were read while snapshotting.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FileCacheStats {
+    /// Number of files whose metadata matched the recorded state, so they
+    /// weren't read.
+    pub hits: usize,
+    /// Number of files which were read and hashed, including the racily clean
+    /// ones.
+    pub misses: usize,
+    /// Number of files whose metadata matched the recorded state, but were
+    /// read anyway because they might have changed right after the state was
+    /// recorded.
+    pub racily_clean: usize,
 }
 
 /// Reason why the new path isn't tracked.
@@ -257,26 +418,155 @@ pub enum UntrackedReason {
         /// Maximum allowed size.
         max_size: u64,
     },
-    /// File does not match the fileset specified in snapshot.auto-track.
+    /// File does not match the fileset specified in snapshot.auto-track, or
+    /// was explicitly untracked.
     FileNotAutoTracked,
 }
 
+/// Paths which were explicitly tracked or untracked.
+///
+/// New files at or under these paths are tracked or left untracked regardless
+/// of `SnapshotOptions::start_tracking_matcher`. If a file is under several of
+/// the paths, the longest one wins. Files which are already tracked aren't
+/// affected.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrackingPatterns {
+    tracked: Vec<RepoPathBuf>,
+    untracked: Vec<RepoPathBuf>,
+}
+
+static EMPTY_TRACKING_PATTERNS: TrackingPatterns = TrackingPatterns {
+    tracked: vec![],
+    untracked: vec![],
+};
+
+fn tracking_patterns_unsupported_error() -> WorkingCopyStateError {
+    WorkingCopyStateError {
+        message: "Failed to update tracking patterns".to_owned(),
+        err: "Tracking patterns are not supported by this working copy".into(),
+    }
+}
+
+impl TrackingPatterns {
+    /// Creates patterns from the lists of tracked and untracked paths. The
+    /// lists shouldn't have paths in common.
+    pub fn new(mut tracked: Vec<RepoPathBuf>, mut untracked: Vec<RepoPathBuf>) -> Self {
+        tracked.sort();
+        untracked.sort();
+        TrackingPatterns { tracked, untracked }
+    }
+
+    /// Paths which new files should be tracked at or under.
+    pub fn tracked(&self) -> &[RepoPathBuf] {
+        &self.tracked
+    }
+
+    /// Paths which new files should be left untracked at or under.
+    pub fn untracked(&self) -> &[RepoPathBuf] {
+        &self.untracked
+    }
+
+    /// Returns true if there are no explicitly tracked or untracked paths.
+    pub fn is_empty(&self) -> bool {
+        self.tracked.is_empty() && self.untracked.is_empty()
+    }
+
+    /// Returns whether a new file at `path` should be tracked, or `None` if
+    /// the path isn't covered by any of the patterns.
+    pub fn is_tracked(&self, path: &RepoPath) -> Option<bool> {
+        let longest_prefix = |prefixes: &[RepoPathBuf]| {
+            prefixes
+                .iter()
+                .filter(|prefix| path.starts_with(prefix))
+                .map(|prefix| prefix.components().count())
+                .max()
+        };
+        match (
+            longest_prefix(&self.tracked),
+            longest_prefix(&self.untracked),
+        ) {
+            (None, None) => None,
+            (tracked, untracked) => Some(tracked > untracked),
+        }
+    }
+
+    /// Marks new files at or under the `paths` as tracked.
+    pub fn track(&mut self, paths: &[RepoPathBuf]) {
+        self.update(paths, true);
+    }
+
+    /// Marks new files at or under the `paths` as untracked.
+    pub fn untrack(&mut self, paths: &[RepoPathBuf]) {
+        self.update(paths, false);
+    }
+
+    fn update(&mut self, paths: &[RepoPathBuf], tracked: bool) {
+        for path in paths {
+            // More specific patterns are overridden by the new one.
+            self.tracked.retain(|prefix| !prefix.starts_with(path));
+            self.untracked.retain(|prefix| !prefix.starts_with(path));
+            // The new pattern is redundant if a shorter one says the same.
+            if self.is_tracked(path) != Some(tracked) {
+                let prefixes = if tracked {
+                    &mut self.tracked
+                } else {
+                    &mut self.untracked
+                };
+                prefixes.push(path.clone());
+                prefixes.sort();
+            }
+        }
+    }
+}
+
 /// Options used when checking out a tree in the working copy.
 #[derive(Clone)]
-pub struct CheckoutOptions {
+pub struct CheckoutOptions<'a> {
     /// Conflict marker style to use when materializing files
     pub conflict_marker_style: ConflictMarkerStyle,
+    /// Callback for the progress of the checkout, which is called for each
+    /// path to be updated.
+    pub progress: Option<&'a CheckoutProgress<'a>>,
+    /// How line endings of stored files are converted when writing them.
+    pub eol_settings: EolSettings,
+    /// Which stored Git LFS pointers are replaced by their contents when
+    /// writing them.
+    pub lfs_settings: LfsSettings,
+    /// Overwrite or remove files even if they changed on disk since the
+    /// working copy was last snapshotted.
+    pub force: bool,
 }
 
-impl CheckoutOptions {
+impl CheckoutOptions<'_> {
+    /// Creates options as configured by the user, without progress callback.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(CheckoutOptions {
+            conflict_marker_style: settings
+                .get("ui.conflict-marker-style")
+                .optional()?
+                .unwrap_or_default(),
+            progress: None,
+            eol_settings: EolSettings::from_settings(settings)?,
+            lfs_settings: LfsSettings::from_settings(settings)?,
+            force: false,
+        })
+    }
+
     /// Create an instance for use in tests.
     pub fn empty_for_test() -> Self {
         CheckoutOptions {
             conflict_marker_style: ConflictMarkerStyle::default(),
+            progress: None,
+            eol_settings: EolSettings::default(),
+            lfs_settings: LfsSettings::default(),
+            force: false,
         }
     }
 }
 
+/// A callback for getting progress updates.
+pub type CheckoutProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
+
 /// Stats about a checkout operation on a working copy. All "files" mentioned
 /// below may also be symlinks or materialized conflicts.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -308,6 +598,14 @@ pub enum CheckoutError {
     /// running (after the working copy was read by the current process).
     #[error("Concurrent checkout")]
     ConcurrentCheckout,
+    /// Files to be updated were changed on disk since the working copy was
+    /// last snapshotted. Nothing was written. Set [`CheckoutOptions::force`]
+    /// to overwrite them.
+    #[error("Files in the working copy changed since the last snapshot")]
+    ChangedFiles {
+        /// The changed files.
+        paths: Vec<RepoPathBuf>,
+    },
     /// Path in the commit contained invalid component such as `..`.
     #[error(transparent)]
     InvalidRepoPath(#[from] InvalidRepoPathError),
@@ -410,6 +708,75 @@ impl WorkingCopyFreshness {
     }
 }
 
+/// An error while updating a stale working copy.
+#[derive(Debug, Error)]
+pub enum UpdateStaleError {
+    /// Error reading the operations.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Error loading the repo at the working copy's operation.
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+    /// Backend error.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Error reading or writing the working copy state.
+    #[error(transparent)]
+    WorkingCopyState(#[from] WorkingCopyStateError),
+    /// The working copy doesn't match the commit recorded at its operation,
+    /// probably because another process is updating it.
+    #[error("Concurrent working copy operation")]
+    ConcurrentOperation,
+    /// Failed to check out the new working-copy commit.
+    #[error("Failed to check out commit {}", commit_id.hex())]
+    Checkout {
+        /// The commit which was being checked out.
+        commit_id: CommitId,
+        /// The underlying error.
+        #[source]
+        source: CheckoutError,
+    },
+    /// Working copy commit is missing.
+    #[error("\"{0:?}\" doesn't have a working-copy commit")]
+    WorkspaceMissingWorkingCopy(WorkspaceId),
+}
+
+/// An error while snapshotting the working copy into the working-copy commit.
+#[derive(Debug, Error)]
+pub enum WorkspaceSnapshotError {
+    /// Error reading the operations.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Error loading the repo at the working copy's operation.
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+    /// Backend error.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Error rebasing descendants of the working-copy commit.
+    #[error(transparent)]
+    Rebase(#[from] RebaseError),
+    /// Error reading or writing the working copy state.
+    #[error(transparent)]
+    WorkingCopyState(#[from] WorkingCopyStateError),
+    /// Error snapshotting the working copy.
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+    /// Error committing the snapshot.
+    #[error(transparent)]
+    TransactionCommit(#[from] TransactionCommitError),
+    /// Error updating the working-copy commit.
+    #[error(transparent)]
+    RewriteRootCommit(#[from] RewriteRootCommit),
+    /// The working copy wasn't updated by the latest operation. Use
+    /// `Workspace::update_stale()` to recover.
+    #[error("The working copy is stale (not updated since operation {})", .0.hex())]
+    StaleWorkingCopy(OperationId),
+    /// Working copy commit is missing.
+    #[error("\"{0:?}\" doesn't have a working-copy commit")]
+    WorkspaceMissingWorkingCopy(WorkspaceId),
+}
+
 /// An error while recovering a stale working copy.
 #[derive(Debug, Error)]
 pub enum RecoverWorkspaceError {
@@ -418,7 +785,7 @@ pub enum RecoverWorkspaceError {
     Backend(#[from] BackendError),
     /// Error during transaction.
     #[error(transparent)]
-    OpHeadsStore(#[from] OpHeadsStoreError),
+    TransactionCommit(#[from] TransactionCommitError),
     /// Error during checkout.
     #[error(transparent)]
     Reset(#[from] ResetError),
@@ -30,6 +30,7 @@ use crate::backend::MergedTreeId;
 use crate::commit::Commit;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
+use crate::local_backend;
 use crate::local_backend::LocalBackend;
 use crate::local_working_copy::LocalWorkingCopy;
 use crate::local_working_copy::LocalWorkingCopyFactory;
@@ -53,13 +54,19 @@ use crate::settings::UserSettings;
 use crate::signing::SignInitError;
 use crate::signing::Signer;
 use crate::store::Store;
+use crate::transaction::TransactionCommitError;
 use crate::working_copy::CheckoutError;
 use crate::working_copy::CheckoutOptions;
 use crate::working_copy::CheckoutStats;
 use crate::working_copy::LockedWorkingCopy;
+use crate::working_copy::SnapshotOptions;
+use crate::working_copy::SnapshotStats;
+use crate::working_copy::UpdateStaleError;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
+use crate::working_copy::WorkingCopyFreshness;
 use crate::working_copy::WorkingCopyStateError;
+use crate::working_copy::WorkspaceSnapshotError;
 
 #[derive(Error, Debug)]
 pub enum WorkspaceInitError {
@@ -76,11 +83,93 @@ pub enum WorkspaceInitError {
     #[error(transparent)]
     OpHeadsStore(#[from] OpHeadsStoreError),
     #[error(transparent)]
+    TransactionCommit(#[from] TransactionCommitError),
+    #[error(transparent)]
     Backend(#[from] BackendInitError),
     #[error(transparent)]
     SignInit(#[from] SignInitError),
 }
 
+/// Error from [`Workspace::clone_git()`].
+#[cfg(feature = "git")]
+#[derive(Error, Debug)]
+pub enum GitCloneError {
+    #[error("Destination path {0} exists and is not an empty directory")]
+    DestinationNotEmpty(PathBuf),
+    #[error(transparent)]
+    Path(#[from] PathError),
+    #[error(transparent)]
+    WorkspaceInit(#[from] WorkspaceInitError),
+    #[error(transparent)]
+    WorkspaceLoad(#[from] WorkspaceLoadError),
+    #[error(transparent)]
+    RepoLoader(#[from] crate::repo::RepoLoaderError),
+    #[error(transparent)]
+    Config(#[from] crate::config::ConfigGetError),
+    #[error(transparent)]
+    RemoteManagement(#[from] crate::git::GitRemoteManagementError),
+    #[error(transparent)]
+    FetchPrepare(#[from] crate::git::GitFetchPrepareError),
+    #[error(transparent)]
+    Fetch(#[from] crate::git::GitFetchError),
+    #[error(transparent)]
+    Import(#[from] crate::git::GitImportError),
+    #[error(transparent)]
+    Backend(#[from] crate::backend::BackendError),
+    #[error(transparent)]
+    Rebase(#[from] crate::rewrite::RebaseError),
+    #[error(transparent)]
+    CheckOutCommit(#[from] CheckOutCommitError),
+    #[error(transparent)]
+    TransactionCommit(#[from] TransactionCommitError),
+    #[error(transparent)]
+    Checkout(#[from] CheckoutError),
+}
+
+/// Options for [`Workspace::clone_git()`].
+#[cfg(feature = "git")]
+#[derive(Clone, Debug)]
+pub struct GitCloneOptions<'a> {
+    /// Name of the remote added for the source repo.
+    pub remote_name: &'a str,
+    /// Whether to colocate the workspace with the backing Git repo.
+    pub colocate: bool,
+    /// Depth of a shallow clone, or `None` to fetch the whole history.
+    pub depth: Option<std::num::NonZeroU32>,
+    /// Whether to track the remote's default branch and check it out. If
+    /// disabled, this is left to the caller, which gets the branch name from
+    /// [`GitCloneStats::default_branch`].
+    pub check_out_default_branch: bool,
+    /// Tags recorded on the operations created by the clone.
+    pub op_tags: HashMap<String, String>,
+}
+
+#[cfg(feature = "git")]
+impl Default for GitCloneOptions<'_> {
+    fn default() -> Self {
+        GitCloneOptions {
+            remote_name: "origin",
+            colocate: false,
+            depth: None,
+            check_out_default_branch: true,
+            op_tags: HashMap::new(),
+        }
+    }
+}
+
+/// Outcome of [`Workspace::clone_git()`].
+#[cfg(feature = "git")]
+#[derive(Debug)]
+pub struct GitCloneStats {
+    /// Name of the remote's default branch, if it has one.
+    pub default_branch: Option<String>,
+    /// Refs imported from the fetched repo.
+    pub import_stats: crate::git::GitImportStats,
+    /// Files written by the checkout of the default branch, if it was checked
+    /// out.
+    pub checkout_stats: Option<CheckoutStats>,
+}
+
 #[derive(Error, Debug)]
 pub enum WorkspaceLoadError {
     #[error("The repo appears to no longer be at {0}")]
@@ -144,6 +233,7 @@ fn init_working_copy(
         working_copy_state_path.clone(),
         repo.op_id().clone(),
         workspace_id,
+        repo.settings(),
     )?;
     let working_copy_type_path = working_copy_state_path.join("type");
     fs::write(&working_copy_type_path, working_copy.name()).context(&working_copy_type_path)?;
@@ -184,8 +274,15 @@ impl Workspace {
         user_settings: &UserSettings,
         workspace_root: &Path,
     ) -> Result<(Self, Arc<ReadonlyRepo>), WorkspaceInitError> {
-        let backend_initializer: &BackendInitializer =
-            &|_settings, store_path| Ok(Box::new(LocalBackend::init(store_path)));
+        let backend_initializer: &BackendInitializer = &|settings, store_path| {
+            let chunk_size = local_backend::chunk_size_from_settings(settings)
+                .map_err(|err| BackendInitError(err.into()))?;
+            Ok(Box::new(
+                LocalBackend::init(store_path)
+                    .with_chunk_size(chunk_size)
+                    .with_lock_manager(settings.lock_manager()),
+            ))
+        };
         let signer = Signer::from_settings(user_settings)?;
         Self::init_with_backend(user_settings, workspace_root, backend_initializer, signer)
     }
@@ -276,6 +373,159 @@ impl Workspace {
         Self::init_with_backend(user_settings, workspace_root, &backend_initializer, signer)
     }
 
+    /// Clones the Git repo at `url` into a new workspace at `workspace_root`.
+    ///
+    /// The remote is added as `options.remote_name`, and all of its branches
+    /// are fetched and imported. If the remote has a default branch, it's
+    /// tracked, and a new working-copy commit is checked out on top of it
+    /// unless disabled by the `options`. The directory is created if needed,
+    /// but must be empty. It's removed again if the clone fails.
+    #[cfg(feature = "git")]
+    pub fn clone_git(
+        url: &str,
+        workspace_root: &Path,
+        user_settings: &UserSettings,
+        store_factories: &StoreFactories,
+        working_copy_factories: &WorkingCopyFactories,
+        options: &GitCloneOptions,
+        callbacks: crate::git::RemoteCallbacks<'_>,
+    ) -> Result<(Self, Arc<ReadonlyRepo>, GitCloneStats), GitCloneError> {
+        let root_existed = workspace_root.exists();
+        if root_existed
+            && !std::fs::read_dir(workspace_root).is_ok_and(|mut entries| entries.next().is_none())
+        {
+            return Err(GitCloneError::DestinationNotEmpty(
+                workspace_root.to_owned(),
+            ));
+        }
+        std::fs::create_dir_all(workspace_root).context(workspace_root)?;
+        let workspace_root = dunce::canonicalize(workspace_root).context(workspace_root)?;
+        Self::clone_git_into(
+            url,
+            &workspace_root,
+            user_settings,
+            store_factories,
+            working_copy_factories,
+            options,
+            callbacks,
+        )
+        .inspect_err(|_err| {
+            let _ = std::fs::remove_dir_all(workspace_root.join(".jj"));
+            if options.colocate {
+                let _ = std::fs::remove_dir_all(workspace_root.join(".git"));
+            }
+            if !root_existed {
+                let _ = std::fs::remove_dir(&workspace_root);
+            }
+        })
+    }
+
+    #[cfg(feature = "git")]
+    fn clone_git_into(
+        url: &str,
+        workspace_root: &Path,
+        user_settings: &UserSettings,
+        store_factories: &StoreFactories,
+        working_copy_factories: &WorkingCopyFactories,
+        options: &GitCloneOptions,
+        mut callbacks: crate::git::RemoteCallbacks<'_>,
+    ) -> Result<(Self, Arc<ReadonlyRepo>, GitCloneStats), GitCloneError> {
+        use crate::git;
+        use crate::git::GitFetch;
+        use crate::refs::RemoteRefSymbol;
+        use crate::str_util::StringPattern;
+
+        let remote_name = options.remote_name;
+        let (workspace, repo) = if options.colocate {
+            Self::init_colocated_git(user_settings, workspace_root)?
+        } else {
+            Self::init_internal_git(user_settings, workspace_root)?
+        };
+        git::add_git_remote_config(repo.store(), remote_name, url)?;
+        // Reload the workspace so that the Git backend sees the new remote.
+        let mut workspace = Self::load(
+            user_settings,
+            workspace.workspace_root(),
+            store_factories,
+            working_copy_factories,
+        )?;
+        let repo = workspace.repo_loader().load_at(repo.operation())?;
+
+        let git_settings = user_settings.git_settings()?;
+        let mut tx = repo.start_transaction();
+        for (key, value) in &options.op_tags {
+            tx.set_tag(key.clone(), value.clone());
+        }
+        let mut git_fetch = GitFetch::new(tx.repo_mut(), &git_settings)?;
+        git_fetch.fetch(
+            remote_name,
+            &[StringPattern::everything()],
+            callbacks.reborrow(),
+            options.depth,
+        )?;
+        let default_branch = git_fetch.get_default_branch(remote_name, callbacks)?;
+        let import_stats = git_fetch.import_refs()?;
+        tx.repo_mut().rebase_descendants()?;
+        // Like fetching into an existing repo, fetching nothing doesn't
+        // record an operation.
+        let repo = if tx.repo().has_changes() {
+            tx.commit("fetch from git remote into empty repo")?
+        } else {
+            repo
+        };
+        let mut stats = GitCloneStats {
+            default_branch,
+            import_stats,
+            checkout_stats: None,
+        };
+
+        let Some(name) = stats
+            .default_branch
+            .as_ref()
+            .filter(|_| options.check_out_default_branch)
+        else {
+            return Ok((workspace, repo, stats));
+        };
+        let default_symbol = RemoteRefSymbol {
+            name,
+            remote: remote_name,
+        };
+        let Some(commit_id) = repo
+            .view()
+            .get_remote_bookmark(default_symbol)
+            .target
+            .as_normal()
+            .cloned()
+        else {
+            return Ok((workspace, repo, stats));
+        };
+        let old_tree_id = repo
+            .view()
+            .get_wc_commit_id(workspace.workspace_id())
+            .map(|id| repo.store().get_commit(id))
+            .transpose()?
+            .map(|commit| commit.tree_id().clone());
+        let mut tx = repo.start_transaction();
+        for (key, value) in &options.op_tags {
+            tx.set_tag(key.clone(), value.clone());
+        }
+        tx.repo_mut().track_remote_bookmark(default_symbol);
+        let commit = tx.repo().store().get_commit(&commit_id)?;
+        let wc_commit = tx
+            .repo_mut()
+            .check_out(workspace.workspace_id().clone(), &commit)?;
+        tx.repo_mut().rebase_descendants()?;
+        let repo = tx.commit("check out git remote's default branch")?;
+        let checkout_stats = workspace.check_out(
+            repo.op_id().clone(),
+            old_tree_id.as_ref(),
+            &wc_commit,
+            &CheckoutOptions::from_settings(user_settings)?,
+        )?;
+        stats.checkout_stats = Some(checkout_stats);
+        Ok((workspace, repo, stats))
+    }
+
     #[expect(clippy::too_many_arguments)]
     pub fn init_with_factories(
         user_settings: &UserSettings,
@@ -458,6 +708,111 @@ impl Workspace {
             })?;
         Ok(stats)
     }
+
+    /// Updates the working copy to the working-copy commit in `repo`'s view if
+    /// the working copy is stale, i.e. if the view moved while the working
+    /// copy wasn't updated. Returns `None` if the working copy isn't stale.
+    ///
+    /// The working copy must not have been modified since its operation was
+    /// recorded. Otherwise, `UpdateStaleError::ConcurrentOperation` is
+    /// returned.
+    pub fn update_stale(
+        &mut self,
+        repo: &ReadonlyRepo,
+        options: &CheckoutOptions,
+    ) -> Result<Option<CheckoutStats>, UpdateStaleError> {
+        let workspace_id = self.workspace_id().clone();
+        let wc_commit_id = repo
+            .view()
+            .get_wc_commit_id(&workspace_id)
+            .ok_or_else(|| UpdateStaleError::WorkspaceMissingWorkingCopy(workspace_id.clone()))?;
+        let wc_commit = repo.store().get_commit(wc_commit_id)?;
+        let mut locked_ws = self.start_working_copy_mutation()?;
+        match WorkingCopyFreshness::check_stale(locked_ws.locked_wc(), &wc_commit, repo)? {
+            WorkingCopyFreshness::Fresh | WorkingCopyFreshness::Updated(_) => return Ok(None),
+            WorkingCopyFreshness::WorkingCopyStale | WorkingCopyFreshness::SiblingOperation => {}
+        }
+        // Check that the working copy still matches the commit recorded at its
+        // operation. Otherwise, we would overwrite changes made since then.
+        let stale_op = repo
+            .loader()
+            .load_operation(locked_ws.locked_wc().old_operation_id())?;
+        let stale_repo = repo.loader().load_at(&stale_op)?;
+        if let Some(stale_commit_id) = stale_repo.view().get_wc_commit_id(&workspace_id) {
+            let stale_commit = stale_repo.store().get_commit(stale_commit_id)?;
+            if stale_commit.tree_id() != locked_ws.locked_wc().old_tree_id() {
+                return Err(UpdateStaleError::ConcurrentOperation);
+            }
+        }
+        let stats = locked_ws
+            .locked_wc()
+            .check_out(&wc_commit, options)
+            .map_err(|source| UpdateStaleError::Checkout {
+                commit_id: wc_commit.id().clone(),
+                source,
+            })?;
+        locked_ws.finish(repo.op_id().clone())?;
+        Ok(Some(stats))
+    }
+
+    /// Snapshots the working copy into the working-copy commit in `repo`'s
+    /// view. If the working copy changed, the commit is rewritten in a new
+    /// operation tagged with `op_tags`.
+    ///
+    /// If the working copy was updated by an operation which `repo` doesn't
+    /// include yet, the snapshot is taken at that operation instead. Returns
+    /// the repo at the resulting operation, the working-copy commit, and the
+    /// snapshot stats.
+    pub fn snapshot(
+        &mut self,
+        repo: Arc<ReadonlyRepo>,
+        options: &SnapshotOptions,
+        op_tags: &HashMap<String, String>,
+    ) -> Result<(Arc<ReadonlyRepo>, Commit, SnapshotStats), WorkspaceSnapshotError> {
+        let workspace_id = self.workspace_id().clone();
+        let get_wc_commit = |repo: &ReadonlyRepo| -> Result<Commit, WorkspaceSnapshotError> {
+            let wc_commit_id = repo.view().get_wc_commit_id(&workspace_id).ok_or_else(|| {
+                WorkspaceSnapshotError::WorkspaceMissingWorkingCopy(workspace_id.clone())
+            })?;
+            Ok(repo.store().get_commit(wc_commit_id)?)
+        };
+        let wc_commit = get_wc_commit(&repo)?;
+        let mut locked_ws = self.start_working_copy_mutation()?;
+        let (repo, wc_commit) =
+            match WorkingCopyFreshness::check_stale(locked_ws.locked_wc(), &wc_commit, &repo)? {
+                WorkingCopyFreshness::Fresh => (repo, wc_commit),
+                WorkingCopyFreshness::Updated(wc_operation) => {
+                    let repo = repo.reload_at(&wc_operation)?;
+                    let wc_commit = get_wc_commit(&repo)?;
+                    (repo, wc_commit)
+                }
+                WorkingCopyFreshness::WorkingCopyStale | WorkingCopyFreshness::SiblingOperation => {
+                    let op_id = locked_ws.locked_wc().old_operation_id().clone();
+                    return Err(WorkspaceSnapshotError::StaleWorkingCopy(op_id));
+                }
+            };
+        let (new_tree_id, stats) = locked_ws.locked_wc().snapshot(options)?;
+        let (repo, wc_commit) = if new_tree_id != *wc_commit.tree_id() {
+            let mut tx = repo.start_transaction();
+            for (key, value) in op_tags {
+                tx.set_tag(key.clone(), value.clone());
+            }
+            tx.set_is_snapshot(true);
+            tx.set_working_copy_tree_id(&new_tree_id);
+            let mut_repo = tx.repo_mut();
+            let commit = mut_repo
+                .rewrite_commit(&wc_commit)
+                .set_tree_id(new_tree_id)
+                .write()?;
+            mut_repo.set_wc_commit(workspace_id.clone(), commit.id().clone())?;
+            mut_repo.rebase_descendants()?;
+            (tx.commit("snapshot working copy")?, commit)
+        } else {
+            (repo, wc_commit)
+        };
+        locked_ws.finish(repo.op_id().clone())?;
+        Ok((repo, wc_commit, stats))
+    }
 }
 
 pub struct LockedWorkspace<'a> {
@@ -524,6 +879,7 @@ pub trait WorkspaceLoader {
         &self,
         store: &Arc<Store>,
         working_copy_factory: &dyn WorkingCopyFactory,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkspaceLoadError>;
 }
 
@@ -596,7 +952,8 @@ impl WorkspaceLoader for DefaultWorkspaceLoader {
         let repo_loader =
             RepoLoader::init_from_file_system(user_settings, &self.repo_path, store_factories)?;
         let working_copy_factory = get_working_copy_factory(self, working_copy_factories)?;
-        let working_copy = self.load_working_copy(repo_loader.store(), working_copy_factory)?;
+        let working_copy =
+            self.load_working_copy(repo_loader.store(), working_copy_factory, user_settings)?;
         let workspace = Workspace::new(
             &self.workspace_root,
             self.repo_path.clone(),
@@ -614,11 +971,13 @@ impl WorkspaceLoader for DefaultWorkspaceLoader {
         &self,
         store: &Arc<Store>,
         working_copy_factory: &dyn WorkingCopyFactory,
+        user_settings: &UserSettings,
     ) -> Result<Box<dyn WorkingCopy>, WorkspaceLoadError> {
         Ok(working_copy_factory.load_working_copy(
             store.clone(),
             self.workspace_root.clone(),
             self.working_copy_state_path.clone(),
+            user_settings,
         )?)
     }
 }
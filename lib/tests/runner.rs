@@ -8,10 +8,16 @@ fn test_no_forgotten_test_files() {
 
 mod test_annotate;
 mod test_bad_locking;
+mod test_bisect;
 mod test_commit_builder;
 mod test_commit_concurrent;
 mod test_conflicts;
+#[cfg(unix)]
+mod test_daemon;
 mod test_default_revset_graph_iterator;
+mod test_diff;
+mod test_file_history;
+mod test_fsck;
 mod test_git;
 mod test_git_backend;
 mod test_gpg;
@@ -22,16 +28,28 @@ mod test_load_repo;
 mod test_local_working_copy;
 mod test_local_working_copy_concurrent;
 mod test_local_working_copy_sparse;
+#[cfg(feature = "proptest")]
+mod test_merge_invariants;
 mod test_merge_trees;
 mod test_merged_tree;
 mod test_mut_repo;
+#[cfg(feature = "json-op-store")]
+mod test_op_store_json;
 mod test_operations;
+mod test_patch;
 mod test_refs;
+mod test_resolution_cache;
 mod test_revset;
 mod test_rewrite;
 mod test_rewrite_duplicate;
 mod test_rewrite_transform;
+mod test_search;
+mod test_session;
 mod test_signing;
+mod test_speculative_repo;
+#[cfg(feature = "sqlite")]
+mod test_sqlite_op_store;
 mod test_ssh_signing;
+mod test_testing;
 mod test_view;
 mod test_workspace;
@@ -32,6 +32,7 @@ use jj_lib::revset::ResolvedRevsetExpression;
 use jj_lib::revset::RevsetExpression;
 use testutils::create_tree;
 use testutils::TestRepo;
+use testutils::TestRepoBackend;
 
 fn create_commit_fn(
     mut_repo: &mut MutableRepo,
@@ -138,6 +139,39 @@ fn test_annotate_linear() {
     ");
 }
 
+#[test]
+fn test_annotate_rename() {
+    // Copy records are only detected by the Git backend
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("new");
+
+    let mut tx = repo.start_transaction();
+    let mut create_commit = create_commit_fn(tx.repo_mut());
+    let content1 = "1a\n1b\n1c\n1d\n1e\n";
+    let content2 = "1a\n1b\n2\n1d\n1e\n";
+    let content3 = "1a\n1b\n2\n1d\n1e\n3\n";
+    let tree1 = create_tree(repo, &[(old_path, content1)]);
+    let tree2 = create_tree(repo, &[(old_path, content2)]);
+    let tree3 = create_tree(repo, &[(new_path, content3)]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    drop(create_commit);
+
+    insta::assert_snapshot!(annotate(tx.repo(), &commit3, new_path), @r"
+    commit1: 1a
+    commit1: 1b
+    commit2: 2
+    commit1: 1d
+    commit1: 1e
+    commit3: 3
+    ");
+}
+
 #[test]
 fn test_annotate_merge_simple() {
     let test_repo = TestRepo::init();
@@ -0,0 +1,207 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use assert_matches::assert_matches;
+use itertools::Itertools as _;
+use jj_lib::bisect::BisectionError;
+use jj_lib::bisect::BisectionResult;
+use jj_lib::bisect::Bisector;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo as _;
+use jj_lib::revset::RevsetExpression;
+use testutils::write_random_commit;
+use testutils::TestRepo;
+
+#[test]
+fn test_bisect_linear() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut commits: Vec<Commit> = vec![];
+    for _ in 0..16 {
+        let parent_id = commits
+            .last()
+            .map_or(repo.store().root_commit_id().clone(), |c| c.id().clone());
+        let commit = tx
+            .repo_mut()
+            .new_commit(vec![parent_id], repo.store().empty_merged_tree_id())
+            .set_description(format!("commit {}", commits.len()))
+            .write()
+            .unwrap();
+        commits.push(commit);
+    }
+    let first_bad = &commits[11];
+
+    let input_range = RevsetExpression::commits(commits.iter().map(|c| c.id().clone()).collect());
+    let mut bisector = Bisector::new(tx.repo_mut(), input_range);
+    let mut num_steps = 0;
+    let found = loop {
+        match bisector.next_step().unwrap() {
+            BisectionResult::Evaluate(commit) => {
+                num_steps += 1;
+                let pos = commits.iter().position(|c| c == &commit).unwrap();
+                if pos >= 11 {
+                    bisector.mark_bad(commit.id().clone());
+                } else {
+                    bisector.mark_good(commit.id().clone());
+                }
+            }
+            BisectionResult::Found(found) => break found,
+            BisectionResult::Indeterminate(_) => panic!("unexpected indeterminate result"),
+        }
+    };
+    assert_eq!(found, vec![first_bad.clone()]);
+    assert!(num_steps <= 5, "took {num_steps} steps");
+}
+
+#[test]
+fn test_bisect_midpoint_of_dag() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // D   E
+    // |   |
+    // C   |
+    // |   |
+    // B   |
+    //  \ /
+    //   A
+    //   |
+    //   F (merge of D and E)
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let mut child = |parents: &[&Commit]| {
+        testutils::create_random_commit(mut_repo)
+            .set_parents(parents.iter().map(|c| c.id().clone()).collect())
+            .write()
+            .unwrap()
+    };
+    let commit_b = child(&[&commit_a]);
+    let commit_c = child(&[&commit_b]);
+    let commit_d = child(&[&commit_c]);
+    let commit_e = child(&[&commit_a]);
+    let commit_f = child(&[&commit_d, &commit_e]);
+
+    let input_range = RevsetExpression::commit(commit_f.id().clone())
+        .ancestors()
+        .minus(&RevsetExpression::root());
+    let mut bisector = Bisector::new(tx.repo_mut(), input_range);
+    // B has 2 of 6 candidates as ancestors, and C has 3.
+    assert_matches!(
+        bisector.next_step().unwrap(),
+        BisectionResult::Evaluate(commit) if commit == commit_c
+    );
+
+    bisector.mark_good(commit_c.id().clone());
+    bisector.mark_bad(commit_f.id().clone());
+    let BisectionResult::Evaluate(commit) = bisector.next_step().unwrap() else {
+        panic!("expected commit to evaluate");
+    };
+    assert!([&commit_d, &commit_e].contains(&&commit));
+
+    // Skipping all remaining commits leaves the result undetermined.
+    bisector.mark_skipped(commit_d.id().clone());
+    bisector.mark_skipped(commit_e.id().clone());
+    assert_matches!(
+        bisector.next_step().unwrap(),
+        BisectionResult::Indeterminate(commits)
+            if commits.iter().map(|c| c.id()).sorted().collect_vec()
+                == [commit_d.id(), commit_e.id(), commit_f.id()].into_iter().sorted().collect_vec()
+    );
+}
+
+#[test]
+fn test_bisect_state_persisted_in_view() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // A-B-C-D
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let mut child = |parent: &Commit| {
+        testutils::create_random_commit(mut_repo)
+            .set_parents(vec![parent.id().clone()])
+            .write()
+            .unwrap()
+    };
+    let commit_b = child(&commit_a);
+    let commit_c = child(&commit_b);
+    let commit_d = child(&commit_c);
+    let repo = tx.commit("test").unwrap();
+    let input_range = RevsetExpression::commits(
+        [&commit_a, &commit_b, &commit_c, &commit_d]
+            .iter()
+            .map(|c| c.id().clone())
+            .collect(),
+    );
+
+    let mut tx = repo.start_transaction();
+    let mut bisector = Bisector::new(tx.repo_mut(), input_range.clone());
+    bisector.mark_good(commit_a.id().clone());
+    bisector.mark_bad(commit_c.id().clone());
+    let repo = tx.commit("mark commits").unwrap();
+    assert_eq!(
+        repo.view().bisect_state().good_commits,
+        [commit_a.id().clone()]
+    );
+    assert_eq!(
+        repo.view().bisect_state().bad_commits,
+        [commit_c.id().clone()]
+    );
+
+    // A later transaction continues from the recorded marks
+    let mut tx = repo.start_transaction();
+    let mut bisector = Bisector::new(tx.repo_mut(), input_range);
+    assert_eq!(bisector.good_commits(), [commit_a.id().clone()]);
+    assert_matches!(
+        bisector.next_step().unwrap(),
+        BisectionResult::Evaluate(commit) if commit == commit_b
+    );
+    bisector.mark_bad(commit_b.id().clone());
+    assert_matches!(
+        bisector.next_step().unwrap(),
+        BisectionResult::Found(commits) if commits == [commit_b.clone()]
+    );
+
+    bisector.reset();
+    assert!(tx.repo().view().bisect_state().is_empty());
+}
+
+#[test]
+fn test_bisect_contradictory_marks() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // A-B
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_a = write_random_commit(mut_repo);
+    let commit_b = testutils::create_random_commit(mut_repo)
+        .set_parents(vec![commit_a.id().clone()])
+        .write()
+        .unwrap();
+    let input_range = RevsetExpression::commits(vec![commit_a.id().clone(), commit_b.id().clone()]);
+
+    // The good commit is a descendant of the bad commit
+    let mut bisector = Bisector::new(tx.repo_mut(), input_range);
+    bisector.mark_bad(commit_a.id().clone());
+    bisector.mark_good(commit_b.id().clone());
+    assert_matches!(
+        bisector.next_step(),
+        Err(BisectionError::ContradictoryMarks)
+    );
+}
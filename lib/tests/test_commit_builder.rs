@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use futures::StreamExt as _;
 use indoc::indoc;
 use itertools::Itertools;
@@ -19,19 +21,25 @@ use jj_lib::backend::ChangeId;
 use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
+use jj_lib::clock::FixedClock;
+use jj_lib::config::resolve;
 use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigResolutionContext;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::merged_tree::MergedTree;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::rewrite::RebaseOptions;
+use jj_lib::settings::RewriteTimestamps;
 use jj_lib::settings::UserSettings;
 use pollster::FutureExt as _;
 use test_case::test_case;
 use testutils::assert_rebased_onto;
+use testutils::create_random_commit;
 use testutils::create_tree;
 use testutils::rebase_descendants_with_options_return_map;
 use testutils::CommitGraphBuilder;
@@ -218,6 +226,175 @@ fn test_rewrite(backend: TestRepoBackend) {
     );
 }
 
+#[test]
+fn test_new_commit_with_repo_scoped_identity() {
+    let test_repo = TestRepo::init();
+    let test_env = &test_repo.env;
+    let repo_path = test_repo.repo_path();
+    let other_repo_path = test_env.root().join("other");
+
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(
+            ConfigSource::User,
+            &format!(
+                indoc! {"
+                    [[--scope]]
+                    --when.repositories = ['{repo_path}']
+                    user.email = 'repo.user@example.com'
+                    [[--scope]]
+                    --when.repositories = ['{other_repo_path}']
+                    user.email = 'other.user@example.com'
+                "},
+                repo_path = repo_path.display(),
+                other_repo_path = other_repo_path.display(),
+            ),
+        )
+        .unwrap(),
+    );
+    let context = ConfigResolutionContext {
+        home_dir: None,
+        repo_path: Some(repo_path),
+        command: None,
+    };
+    let settings = UserSettings::from_config(resolve(&config, &context).unwrap()).unwrap();
+    assert_eq!(settings.user_email(), "repo.user@example.com");
+
+    let repo = test_env.load_repo_at_head(&settings, repo_path);
+    let store = repo.store();
+    let mut tx = repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(
+            vec![store.root_commit_id().clone()],
+            store.empty_merged_tree_id(),
+        )
+        .write()
+        .unwrap();
+    assert_eq!(commit.author().name, "Test User");
+    assert_eq!(commit.author().email, "repo.user@example.com");
+    assert_eq!(commit.committer().email, "repo.user@example.com");
+}
+
+#[test]
+fn test_commit_trailers() {
+    let test_repo = TestRepo::init();
+    let test_env = &test_repo.env;
+
+    let mut config = testutils::base_user_config();
+    let mut layer = ConfigLayer::empty(ConfigSource::User);
+    layer
+        .set_value("commit-trailers.signed-off-by", true)
+        .unwrap();
+    layer.set_value("commit-trailers.change-id", true).unwrap();
+    config.add_layer(layer);
+    let settings = UserSettings::from_config(config).unwrap();
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    let store = repo.store();
+
+    let mut tx = repo.start_transaction();
+    // Empty descriptions are left alone so the commit stays discardable
+    let empty_commit = tx
+        .repo_mut()
+        .new_commit(
+            vec![store.root_commit_id().clone()],
+            store.empty_merged_tree_id(),
+        )
+        .write()
+        .unwrap();
+    assert_eq!(empty_commit.description(), "");
+
+    let commit = tx
+        .repo_mut()
+        .rewrite_commit(&empty_commit)
+        .set_description("subject\n")
+        .write()
+        .unwrap();
+    let change_id_hex = commit.change_id().hex();
+    assert_eq!(
+        commit.description(),
+        format!(
+            "subject\n\nSigned-off-by: Test User <test.user@example.com>\nChange-Id: \
+             I{change_id_hex}6a6a6964\n"
+        )
+    );
+
+    // Rewriting doesn't duplicate the trailers
+    let rewritten_commit = tx
+        .repo_mut()
+        .rewrite_commit(&commit)
+        .set_tree_id(create_tree(&repo, &[(RepoPath::from_internal_string("file"), "a")]).id())
+        .write()
+        .unwrap();
+    assert_eq!(rewritten_commit.description(), commit.description());
+
+    // Existing Change-Id is preserved
+    let commit = tx
+        .repo_mut()
+        .rewrite_commit(&commit)
+        .set_description("subject\n\nChange-Id: I0123\n")
+        .write()
+        .unwrap();
+    assert_eq!(
+        commit.description(),
+        "subject\n\nChange-Id: I0123\nSigned-off-by: Test User <test.user@example.com>\n"
+    );
+
+    // Descriptions carried over by rewrites are left unchanged
+    let mut tx = test_repo.repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(
+            vec![store.root_commit_id().clone()],
+            store.empty_merged_tree_id(),
+        )
+        .set_description("no trailers\n")
+        .write()
+        .unwrap();
+    tx.commit("test").unwrap();
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    let mut tx = repo.start_transaction();
+    let rewritten_commit = tx
+        .repo_mut()
+        .rewrite_commit(&commit)
+        .set_tree_id(create_tree(&repo, &[(RepoPath::from_internal_string("file"), "b")]).id())
+        .write()
+        .unwrap();
+    assert_eq!(rewritten_commit.description(), "no trailers\n");
+}
+
+#[test]
+fn test_commit_description_parts() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut write_commit = |description: &str| {
+        create_random_commit(tx.repo_mut())
+            .set_description(description)
+            .write()
+            .unwrap()
+    };
+
+    let commit = write_commit("");
+    assert_eq!(commit.subject(), "");
+    assert_eq!(commit.body(), "");
+    assert_eq!(commit.trailers(), vec![]);
+
+    let commit = write_commit("subject\n");
+    assert_eq!(commit.subject(), "subject");
+    assert_eq!(commit.body(), "");
+    assert_eq!(commit.trailers(), vec![]);
+
+    let commit = write_commit("subject\n\n\nbody\n\nFixes: #1\nAcked-by: Someone\n");
+    assert_eq!(commit.subject(), "subject");
+    assert_eq!(commit.body(), "body\n\nFixes: #1\nAcked-by: Someone\n");
+    assert_eq!(
+        commit.trailers(),
+        vec![("Fixes", "#1"), ("Acked-by", "Someone")]
+    );
+}
+
 // An author field with an empty name/email should get filled in on rewrite
 #[test_case(TestRepoBackend::Local ; "local backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
@@ -275,6 +452,51 @@ fn test_rewrite_update_missing_user(backend: TestRepoBackend) {
     );
 }
 
+#[test_case(TestRepoBackend::Local ; "local backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn test_fixed_clock(backend: TestRepoBackend) {
+    let timestamp = Timestamp {
+        timestamp: MillisSinceEpoch(1_234_567_890_000),
+        tz_offset: 60,
+    };
+    let clock = Arc::new(FixedClock::new(timestamp));
+
+    // Identical repos written with the same clock and seed have identical
+    // commits and operations.
+    let write_commit = || {
+        let settings = testutils::user_settings().with_clock(clock.clone());
+        let test_repo = TestRepo::init_with_backend_and_settings(backend, &settings);
+        let repo = &test_repo.repo;
+        let mut tx = repo.start_transaction();
+        let commit = tx
+            .repo_mut()
+            .new_commit(
+                vec![repo.store().root_commit_id().clone()],
+                repo.store().empty_merged_tree_id(),
+            )
+            .set_description("description")
+            .write()
+            .unwrap();
+        let repo = tx.commit("test").unwrap();
+        (commit, repo.operation().clone())
+    };
+    let (commit1, op1) = write_commit();
+    let (commit2, op2) = write_commit();
+    assert_eq!(commit1.author().timestamp, timestamp);
+    assert_eq!(commit1.committer().timestamp, timestamp);
+    assert_eq!(op1.metadata().start_time, timestamp);
+    assert_eq!(op1.metadata().end_time, timestamp);
+    assert_eq!(commit1.id(), commit2.id());
+    assert_eq!(op1.id(), op2.id());
+
+    // debug.commit-timestamp takes precedence over the clock
+    let settings =
+        UserSettings::from_config(config_with_commit_timestamp("2001-02-03T04:05:06+07:00"))
+            .unwrap()
+            .with_clock(clock);
+    assert_ne!(settings.signature().timestamp, timestamp);
+}
+
 #[test_case(TestRepoBackend::Local ; "local backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_rewrite_resets_author_timestamp(backend: TestRepoBackend) {
@@ -350,6 +572,78 @@ fn test_rewrite_resets_author_timestamp(backend: TestRepoBackend) {
     assert_eq!(rewritten_commit_2.committer().timestamp, new_timestamp_2);
 }
 
+#[test_case(RewriteTimestamps::RefreshCommitter, false, true ; "refresh committer")]
+#[test_case(RewriteTimestamps::Keep, false, false ; "keep")]
+#[test_case(RewriteTimestamps::ResetAuthor, true, true ; "reset author")]
+fn test_rewrite_timestamps_setting(
+    policy: RewriteTimestamps,
+    expect_new_author_timestamp: bool,
+    expect_new_committer_timestamp: bool,
+) {
+    let test_repo = TestRepo::init();
+    let test_env = &test_repo.env;
+
+    let initial_timestamp = "2001-02-03T04:05:06+07:00";
+    let settings =
+        UserSettings::from_config(config_with_commit_timestamp(initial_timestamp)).unwrap();
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    let mut tx = repo.start_transaction();
+    let initial_commit = tx
+        .repo_mut()
+        .new_commit(
+            vec![repo.store().root_commit_id().clone()],
+            repo.store().empty_merged_tree_id(),
+        )
+        .set_description("initial")
+        .write()
+        .unwrap();
+    tx.commit("test").unwrap();
+
+    let new_timestamp = "2002-03-04T05:06:07+08:00";
+    let mut config = config_with_commit_timestamp(new_timestamp);
+    let mut layer = ConfigLayer::empty(ConfigSource::User);
+    let policy_name = match policy {
+        RewriteTimestamps::RefreshCommitter => "refresh-committer",
+        RewriteTimestamps::Keep => "keep",
+        RewriteTimestamps::ResetAuthor => "reset-author",
+    };
+    layer.set_value("rewrite.timestamps", policy_name).unwrap();
+    config.add_layer(layer);
+    let settings = UserSettings::from_config(config).unwrap();
+    assert_eq!(settings.rewrite_timestamps(), policy);
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    let initial_commit = repo.store().get_commit(initial_commit.id()).unwrap();
+    let mut tx = repo.start_transaction();
+    let rewritten_commit = tx
+        .repo_mut()
+        .rewrite_commit(&initial_commit)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+
+    let new_timestamp =
+        Timestamp::from_datetime(chrono::DateTime::parse_from_rfc3339(new_timestamp).unwrap());
+    let initial_timestamp = initial_commit.committer().timestamp;
+    let expected_author_timestamp = if expect_new_author_timestamp {
+        new_timestamp
+    } else {
+        initial_timestamp
+    };
+    let expected_committer_timestamp = if expect_new_committer_timestamp {
+        new_timestamp
+    } else {
+        initial_timestamp
+    };
+    assert_eq!(
+        rewritten_commit.author().timestamp,
+        expected_author_timestamp
+    );
+    assert_eq!(
+        rewritten_commit.committer().timestamp,
+        expected_committer_timestamp
+    );
+}
+
 #[test_case(TestRepoBackend::Local ; "local backend")]
 // #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_commit_builder_descendants(backend: TestRepoBackend) {
@@ -12,15 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use assert_matches::assert_matches;
+use bstr::BString;
 use indoc::indoc;
 use itertools::Itertools;
 use jj_lib::backend::FileId;
 use jj_lib::conflicts::choose_materialized_conflict_marker_len;
 use jj_lib::conflicts::extract_as_single_hunk;
+use jj_lib::conflicts::materialize_file_conflict_to_bytes_with_marker_len;
 use jj_lib::conflicts::materialize_merge_result_to_bytes;
+use jj_lib::conflicts::parse;
 use jj_lib::conflicts::parse_conflict;
+use jj_lib::conflicts::resolve_hunks;
 use jj_lib::conflicts::update_from_content;
+use jj_lib::conflicts::ConflictHunk;
 use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::conflicts::HunkResolution;
+use jj_lib::conflicts::ResolveHunksError;
 use jj_lib::conflicts::MIN_CONFLICT_MARKER_LEN;
 use jj_lib::merge::Merge;
 use jj_lib::repo::Repo;
@@ -754,6 +762,74 @@ fn test_materialize_conflict_modify_delete() {
     >>>>>>> Conflict 1 of 1 ends
     "
     );
+
+    // When materialized as a file conflict, the deleted side is labeled and
+    // never used as the target of a diff
+    insta::assert_snapshot!(&materialize_file_conflict_string(store, path, &conflict, ConflictMarkerStyle::Diff), @r"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+     line 1
+     line 2
+    -line 3
+    +modified
+     line 4
+     line 5
+    +++++++ Contents of side #2 (file deleted)
+    >>>>>>> Conflict 1 of 1 ends
+    "
+    );
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_id.clone())],
+        vec![None, Some(modified_id.clone())],
+    );
+    insta::assert_snapshot!(&materialize_file_conflict_string(store, path, &conflict, ConflictMarkerStyle::Diff), @r"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1 (file deleted)
+    %%%%%%% Changes from base to side #2
+     line 1
+     line 2
+    -line 3
+    +modified
+     line 4
+     line 5
+    >>>>>>> Conflict 1 of 1 ends
+    "
+    );
+    insta::assert_snapshot!(&materialize_file_conflict_string(store, path, &conflict, ConflictMarkerStyle::Snapshot), @r"
+    <<<<<<< Conflict 1 of 1
+    +++++++ Contents of side #1 (file deleted)
+    ------- Contents of base
+    line 1
+    line 2
+    line 3
+    line 4
+    line 5
+    +++++++ Contents of side #2
+    line 1
+    line 2
+    modified
+    line 4
+    line 5
+    >>>>>>> Conflict 1 of 1 ends
+    "
+    );
+    insta::assert_snapshot!(&materialize_file_conflict_string(store, path, &conflict, ConflictMarkerStyle::Git), @r"
+    <<<<<<< Side #1 (file deleted) (Conflict 1 of 1)
+    ||||||| Base
+    line 1
+    line 2
+    line 3
+    line 4
+    line 5
+    =======
+    line 1
+    line 2
+    modified
+    line 4
+    line 5
+    >>>>>>> Side #2 (Conflict 1 of 1 ends)
+    "
+    );
 }
 
 #[test]
@@ -1709,7 +1785,7 @@ fn test_update_conflict_from_content_modify_delete() {
     // If the content is unchanged compared to the materialized value, we get the
     // old conflict id back.
     let materialized =
-        materialize_conflict_string(store, path, &conflict, ConflictMarkerStyle::Diff);
+        materialize_file_conflict_string(store, path, &conflict, ConflictMarkerStyle::Diff);
     let parse = |content| {
         update_from_content(
             &conflict,
@@ -2443,6 +2519,129 @@ fn test_update_from_content_malformed_conflict() {
     assert_eq!(second_snapshot, new_conflict);
 }
 
+#[test]
+fn test_parse_hunks() {
+    let conflict = Merge::from_removes_adds(
+        vec!["a\nb\nc\nd\ne\n"],
+        vec!["a\nB1\nc\nd\ne\n", "a\nB2\nc\nd\nE2\n"],
+    );
+    let hunks = parse(&conflict);
+    assert_eq!(
+        hunks,
+        vec![ConflictHunk {
+            contents: Merge::from_removes_adds(
+                vec![BString::from("b\n")],
+                vec![BString::from("B1\n"), BString::from("B2\n")],
+            ),
+        }]
+    );
+    assert_eq!(hunks[0].num_sides(), 2);
+    assert_eq!(hunks[0].side(1), Some(&BString::from("B2\n")));
+    assert_eq!(hunks[0].side(2), None);
+
+    // Two conflicted hunks separated by a resolved one
+    let conflict = Merge::from_removes_adds(
+        vec!["a\nb\nc\nd\ne\n"],
+        vec!["A1\nb\nc\nD1\ne\n", "A2\nb\nc\nD2\ne\n"],
+    );
+    let hunks = parse(&conflict);
+    assert_eq!(
+        hunks
+            .iter()
+            .map(|hunk| hunk.contents.iter().cloned().collect_vec())
+            .collect_vec(),
+        vec![
+            vec![
+                BString::from("A1\n"),
+                BString::from("a\n"),
+                BString::from("A2\n")
+            ],
+            vec![
+                BString::from("D1\n"),
+                BString::from("d\n"),
+                BString::from("D2\n")
+            ],
+        ]
+    );
+
+    // No hunks if the conflict merges cleanly
+    let conflict = Merge::from_removes_adds(vec!["a\nb\nc\n"], vec!["A\nb\nc\n", "a\nb\nC\n"]);
+    assert_eq!(parse(&conflict), vec![]);
+}
+
+#[test]
+fn test_resolve_hunks() {
+    let conflict = Merge::from_removes_adds(
+        vec!["a\nb\nc\nd\ne\n"],
+        vec!["A1\nb\nc\nD1\ne\n", "A2\nb\nc\nD2\ne\n"],
+    );
+
+    // Resolving all hunks results in a resolved merge
+    let resolved = resolve_hunks(
+        &conflict,
+        &[
+            HunkResolution::Side(0),
+            HunkResolution::Contents("new\n".into()),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        resolved,
+        Merge::resolved(BString::from("A1\nb\nc\nnew\ne\n"))
+    );
+    let resolved = resolve_hunks(
+        &conflict,
+        &[HunkResolution::Side(1), HunkResolution::Side(1)],
+    )
+    .unwrap();
+    assert_eq!(
+        resolved,
+        Merge::resolved(BString::from("A2\nb\nc\nD2\ne\n"))
+    );
+
+    // Unresolved hunks are kept and resolved ones are applied to all terms
+    let partial = resolve_hunks(
+        &conflict,
+        &[HunkResolution::Side(1), HunkResolution::Unresolved],
+    )
+    .unwrap();
+    assert_eq!(
+        partial,
+        Merge::from_removes_adds(
+            vec![BString::from("A2\nb\nc\nd\ne\n")],
+            vec![
+                BString::from("A2\nb\nc\nD1\ne\n"),
+                BString::from("A2\nb\nc\nD2\ne\n")
+            ],
+        )
+    );
+    assert_eq!(parse(&partial).len(), 1);
+
+    // Leaving everything unresolved returns the original conflict
+    let unchanged = resolve_hunks(
+        &conflict,
+        &[HunkResolution::Unresolved, HunkResolution::Unresolved],
+    )
+    .unwrap();
+    assert_eq!(unchanged, conflict.map(|s| BString::from(*s)));
+
+    // Invalid resolutions
+    assert_matches!(
+        resolve_hunks(&conflict, &[HunkResolution::Side(0)]),
+        Err(ResolveHunksError::WrongNumberOfResolutions {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_matches!(
+        resolve_hunks(
+            &conflict,
+            &[HunkResolution::Unresolved, HunkResolution::Side(2)]
+        ),
+        Err(ResolveHunksError::NoSuchSide { hunk: 1, side: 2 })
+    );
+}
+
 fn materialize_conflict_string(
     store: &Store,
     path: &RepoPath,
@@ -2455,3 +2654,25 @@ fn materialize_conflict_string(
     String::from_utf8(materialize_merge_result_to_bytes(&contents, conflict_marker_style).into())
         .unwrap()
 }
+
+fn materialize_file_conflict_string(
+    store: &Store,
+    path: &RepoPath,
+    conflict: &Merge<Option<FileId>>,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> String {
+    let contents = extract_as_single_hunk(conflict, store, path)
+        .block_on()
+        .unwrap();
+    let conflict_marker_len = choose_materialized_conflict_marker_len(&contents);
+    String::from_utf8(
+        materialize_file_conflict_to_bytes_with_marker_len(
+            conflict,
+            &contents,
+            conflict_marker_style,
+            conflict_marker_len,
+        )
+        .into(),
+    )
+    .unwrap()
+}
@@ -0,0 +1,212 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::Write as _;
+use std::os::unix::fs::PermissionsExt as _;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use jj_lib::daemon;
+use jj_lib::daemon::Daemon;
+use jj_lib::daemon::DaemonClient;
+use jj_lib::daemon::DaemonError;
+use jj_lib::daemon::DaemonOptions;
+use jj_lib::daemon::DaemonRequest;
+use jj_lib::daemon::DaemonResponse;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use testutils::TestWorkspace;
+
+#[test]
+fn test_daemon_snapshot_and_status() {
+    let TestWorkspace {
+        env: _env,
+        workspace,
+        repo,
+    } = TestWorkspace::init();
+    let workspace_root = workspace.workspace_root().to_owned();
+    let workspace_id = workspace.workspace_id().clone();
+    let old_wc_commit_id = repo.view().get_wc_commit_id(&workspace_id).unwrap().hex();
+    let socket_path = daemon::socket_path(&workspace_root).unwrap();
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    let final_repo = thread::scope(|s| {
+        let daemon_thread = s.spawn(|| {
+            let mut daemon = Daemon::new(workspace, repo.clone(), DaemonOptions::empty_for_test());
+            daemon.serve(&listener).unwrap();
+            daemon.repo().clone()
+        });
+        let mut client = DaemonClient::connect(&socket_path).unwrap();
+
+        // Nothing to snapshot yet
+        let response = client.snapshot().unwrap();
+        assert_eq!(response.operation_id, repo.op_id().hex());
+        assert_eq!(response.wc_commit_id, old_wc_commit_id);
+        assert!(!response.changed);
+
+        // New files are snapshotted and reported
+        let file_path = RepoPath::from_internal_string("dir/file");
+        testutils::write_working_copy_file(&workspace_root, file_path, "contents");
+        let status = client.status().unwrap();
+        assert_ne!(status.operation_id, repo.op_id().hex());
+        assert_ne!(status.wc_commit_id, old_wc_commit_id);
+        assert_eq!(status.changed_paths, vec!["dir/file".to_owned()]);
+        let response = client.snapshot().unwrap();
+        assert_eq!(response.operation_id, status.operation_id);
+        assert_eq!(response.wc_commit_id, status.wc_commit_id);
+        assert!(!response.changed);
+
+        drop(client);
+
+        // Malformed requests are rejected without ending the connection
+        let mut stream = UnixStream::connect(&socket_path).unwrap();
+        stream.write_all(b"{\"type\":\"bogus\"}\n").unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_matches!(
+            serde_json::from_str(&line).unwrap(),
+            DaemonResponse::Error { message } if message.starts_with("Invalid request")
+        );
+        stream.write_all(b"{\"type\":\"snapshot\"}\n").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_matches!(
+            serde_json::from_str(&line).unwrap(),
+            DaemonResponse::Snapshot(response) if response.wc_commit_id == status.wc_commit_id
+        );
+        drop((stream, reader));
+        let mut client = DaemonClient::connect(&socket_path).unwrap();
+        assert_matches!(
+            client.request(&DaemonRequest::Shutdown),
+            Ok(DaemonResponse::Shutdown)
+        );
+        let repo = daemon_thread.join().unwrap();
+        assert_eq!(repo.op_id().hex(), status.operation_id);
+        repo
+    });
+
+    drop(listener);
+
+    // The snapshot was committed to the repo
+    let repo = final_repo.reload_at_head().unwrap();
+    let wc_commit_id = repo.view().get_wc_commit_id(&workspace_id).unwrap();
+    let wc_commit = repo.store().get_commit(wc_commit_id).unwrap();
+    assert!(wc_commit
+        .tree()
+        .unwrap()
+        .path_value(RepoPath::from_internal_string("dir/file"))
+        .unwrap()
+        .is_present());
+    // The daemon has stopped listening
+    assert_matches!(
+        DaemonClient::connect(&socket_path).and_then(|mut client| client.snapshot()),
+        Err(DaemonError::Io(_))
+    );
+}
+
+#[test]
+fn test_daemon_snapshot_op_tags() {
+    let TestWorkspace {
+        env: _env,
+        workspace,
+        repo,
+    } = TestWorkspace::init();
+    let workspace_root = workspace.workspace_root().to_owned();
+    let options = DaemonOptions {
+        op_tags: HashMap::from([("args".to_owned(), "jj debug daemon".to_owned())]),
+        ..DaemonOptions::empty_for_test()
+    };
+    let mut daemon = Daemon::new(workspace, repo, options);
+
+    let file_path = RepoPath::from_internal_string("file");
+    testutils::write_working_copy_file(&workspace_root, file_path, "contents");
+    assert_matches!(
+        daemon.handle_request(&DaemonRequest::Snapshot),
+        DaemonResponse::Snapshot(response) if response.changed
+    );
+    let metadata = daemon.repo().operation().metadata();
+    assert!(metadata.is_snapshot);
+    assert_eq!(metadata.description, "snapshot working copy");
+    assert_eq!(
+        metadata.tags,
+        HashMap::from([("args".to_owned(), "jj debug daemon".to_owned())])
+    );
+}
+
+#[test]
+fn test_daemon_disconnects_idle_clients() {
+    let TestWorkspace {
+        env: _env,
+        workspace,
+        repo,
+    } = TestWorkspace::init();
+    let socket_path = daemon::socket_path(workspace.workspace_root()).unwrap();
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    thread::scope(|s| {
+        let daemon_thread = s.spawn(|| {
+            let options = DaemonOptions {
+                client_timeout: Duration::from_millis(100),
+                ..DaemonOptions::empty_for_test()
+            };
+            let mut daemon = Daemon::new(workspace, repo.clone(), options);
+            daemon.serve(&listener).unwrap();
+        });
+
+        // A client which never sends a request doesn't block other clients
+        let idle_stream = UnixStream::connect(&socket_path).unwrap();
+        let mut client = DaemonClient::connect(&socket_path).unwrap();
+        let response = client.snapshot().unwrap();
+        assert_eq!(response.operation_id, repo.op_id().hex());
+        client.shutdown().unwrap();
+        daemon_thread.join().unwrap();
+        drop(idle_stream);
+    });
+}
+
+#[test]
+fn test_socket_path() {
+    let temp_dir = testutils::new_temp_dir();
+    let short_root = temp_dir.path().join("repo");
+    assert_eq!(
+        daemon::socket_path(&short_root).unwrap(),
+        short_root.join(".jj").join("daemon.sock")
+    );
+
+    // Paths which don't fit in a socket address are replaced by a short path
+    // derived from the workspace root.
+    let long_root = temp_dir.path().join("a".repeat(100));
+    let other_long_root = temp_dir.path().join("b".repeat(100));
+    let socket_path = daemon::socket_path(&long_root).unwrap();
+    assert!(socket_path.as_os_str().len() < 104);
+    assert!(!socket_path.starts_with(&long_root));
+    assert_eq!(daemon::socket_path(&long_root).unwrap(), socket_path);
+    assert_ne!(daemon::socket_path(&other_long_root).unwrap(), socket_path);
+
+    // Without a runtime directory, the socket is in a directory only the
+    // current user can access.
+    if std::env::var_os("XDG_RUNTIME_DIR").is_none_or(|dir| dir.is_empty()) {
+        let metadata = socket_path.parent().unwrap().symlink_metadata().unwrap();
+        assert!(metadata.is_dir());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o700);
+    }
+}
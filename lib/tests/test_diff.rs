@@ -0,0 +1,105 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use futures::StreamExt as _;
+use jj_lib::commit::Commit;
+use jj_lib::diff::interdiff;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::MutableRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use pollster::FutureExt as _;
+use testutils::create_tree;
+use testutils::TestRepo;
+
+fn changed_paths(from_tree: &MergedTree, to_tree: &MergedTree) -> Vec<RepoPathBuf> {
+    from_tree
+        .diff_stream(to_tree, &EverythingMatcher)
+        .map(|diff| {
+            let _ = diff.values.unwrap();
+            diff.path
+        })
+        .collect()
+        .block_on()
+}
+
+fn write_commit(
+    mut_repo: &mut MutableRepo,
+    parent: &Commit,
+    files: &[(&RepoPath, &str)],
+) -> Commit {
+    let tree_id = create_tree(mut_repo.base_repo(), files).id();
+    mut_repo
+        .new_commit(vec![parent.id().clone()], tree_id)
+        .write()
+        .unwrap()
+}
+
+#[test]
+fn test_interdiff_across_rebase() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file1 = RepoPath::from_internal_string("file1");
+    let file2 = RepoPath::from_internal_string("file2");
+    let mut tx = repo.start_transaction();
+    // v1 is based on A. v2 is based on B, which changed file2, and also
+    // changes file1 a bit more.
+    let commit_a = write_commit(
+        tx.repo_mut(),
+        &repo.store().root_commit(),
+        &[(file1, "a\n"), (file2, "a\n")],
+    );
+    let commit_b = write_commit(tx.repo_mut(), &commit_a, &[(file1, "a\n"), (file2, "b\n")]);
+    let commit_v1 = write_commit(tx.repo_mut(), &commit_a, &[(file1, "v1\n"), (file2, "a\n")]);
+    let commit_v2 = write_commit(tx.repo_mut(), &commit_b, &[(file1, "v2\n"), (file2, "b\n")]);
+
+    // The change to file2 comes from the parents and isn't included
+    let (old_tree, new_tree) = interdiff(tx.repo(), &commit_v1, &commit_v2).unwrap();
+    assert_eq!(new_tree.id(), commit_v2.tree_id().clone());
+    assert_eq!(changed_paths(&old_tree, &new_tree), vec![file1.to_owned()]);
+    assert_eq!(
+        changed_paths(&commit_v1.tree().unwrap(), &new_tree),
+        vec![file1.to_owned(), file2.to_owned()]
+    );
+
+    // With the same parents, the trees are compared as is
+    let commit_v3 = write_commit(
+        tx.repo_mut(),
+        &commit_b,
+        &[(file1, "v2\n"), (file2, "v3\n")],
+    );
+    let (old_tree, new_tree) = interdiff(tx.repo(), &commit_v2, &commit_v3).unwrap();
+    assert_eq!(old_tree.id(), commit_v2.tree_id().clone());
+    assert_eq!(changed_paths(&old_tree, &new_tree), vec![file2.to_owned()]);
+}
+
+#[test]
+fn test_interdiff_conflict() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file = RepoPath::from_internal_string("file");
+    let mut tx = repo.start_transaction();
+    let commit_a = write_commit(tx.repo_mut(), &repo.store().root_commit(), &[(file, "a\n")]);
+    let commit_b = write_commit(tx.repo_mut(), &commit_a, &[(file, "b\n")]);
+    let commit_v1 = write_commit(tx.repo_mut(), &commit_a, &[(file, "v1\n")]);
+    let commit_v2 = write_commit(tx.repo_mut(), &commit_b, &[(file, "v2\n")]);
+
+    // The old change doesn't apply cleanly on top of the new parent
+    let (old_tree, _new_tree) = interdiff(tx.repo(), &commit_v1, &commit_v2).unwrap();
+    assert!(!old_tree.path_value(file).unwrap().is_resolved());
+}
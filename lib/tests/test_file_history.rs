@@ -0,0 +1,78 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::file_history::file_history;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use testutils::create_tree;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+#[test]
+fn test_file_history_follows_rename() {
+    // Copy records are only detected by the Git backend
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    let old_path = RepoPath::from_internal_string("dir/old");
+    let new_path = RepoPath::from_internal_string("new");
+    let other_path = RepoPath::from_internal_string("other");
+    let content = "1\n2\n3\n4\n5\n";
+
+    // D renames "dir/old" to "new", C changes an unrelated file.
+    let mut tx = repo.start_transaction();
+    let mut new_commit = |parent: &Commit, files: &[(&RepoPath, &str)]| {
+        tx.repo_mut()
+            .new_commit(vec![parent.id().clone()], create_tree(repo, files).id())
+            .write()
+            .unwrap()
+    };
+    let commit_a = new_commit(&store.root_commit(), &[(old_path, content)]);
+    let commit_b = new_commit(&commit_a, &[(old_path, "0\n2\n3\n4\n5\n")]);
+    let commit_c = new_commit(
+        &commit_b,
+        &[(old_path, "0\n2\n3\n4\n5\n"), (other_path, "x\n")],
+    );
+    let commit_d = new_commit(
+        &commit_c,
+        &[(new_path, "0\n2\n3\n4\n5\n6\n"), (other_path, "x\n")],
+    );
+    let commit_e = new_commit(
+        &commit_d,
+        &[(new_path, "0\n2\n3\n4\n5\n6\n"), (other_path, "y\n")],
+    );
+
+    let history = file_history(tx.repo(), &commit_e, new_path).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| (entry.commit.id(), entry.path.as_ref()))
+            .collect_vec(),
+        vec![
+            (commit_d.id(), new_path),
+            (commit_b.id(), old_path),
+            (commit_a.id(), old_path),
+        ]
+    );
+
+    // Without a rename, the history stops where the file was added
+    let history = file_history(tx.repo(), &commit_e, other_path).unwrap();
+    assert_eq!(
+        history.iter().map(|entry| entry.commit.id()).collect_vec(),
+        vec![commit_e.id(), commit_c.id()]
+    );
+}
@@ -0,0 +1,105 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+use assert_matches::assert_matches;
+use jj_lib::backend::BackendError;
+use jj_lib::backend::TreeValue;
+use jj_lib::fsck::VerifyError;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store::OpStoreError;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use testutils::create_tree;
+use testutils::write_random_commit;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+#[test]
+fn test_verify_clean_repo() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Local);
+    let repo = &test_repo.repo;
+
+    let tree = create_tree(repo, &[(RepoPath::from_internal_string("dir/file"), "a")]);
+    let mut tx = repo.start_transaction();
+    tx.repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let report = repo.verify();
+    assert!(report.is_ok(), "{:?}", report.errors);
+    // Root operation and the "test" operation
+    assert_eq!(report.num_operations, 2);
+    // Root commit and the new commit
+    assert_eq!(report.num_commits, 2);
+    // Empty tree, root tree of the new commit, and "dir"
+    assert_eq!(report.num_trees, 3);
+    assert_eq!(report.num_files, 1);
+}
+
+#[test]
+fn test_verify_missing_objects() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Local);
+    let repo = &test_repo.repo;
+
+    let file_path = RepoPath::from_internal_string("file");
+    let tree = create_tree(repo, &[(file_path, "a")]);
+    let mut tx = repo.start_transaction();
+    tx.repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    let Some(TreeValue::File { id: file_id, .. }) =
+        tree.path_value(file_path).unwrap().into_resolved().unwrap()
+    else {
+        panic!("expected a file");
+    };
+    fs::remove_file(
+        test_repo
+            .repo_path()
+            .join("store/files")
+            .join(file_id.hex()),
+    )
+    .unwrap();
+    // The view of the parent operation is missing, but the new commit is
+    // still reachable from the view of the current operation.
+    let parent_op = repo.operation().parents().next().unwrap().unwrap();
+    let view_id = parent_op.view_id();
+    fs::remove_file(
+        test_repo
+            .repo_path()
+            .join("op_store/views")
+            .join(view_id.hex()),
+    )
+    .unwrap();
+
+    let report = repo.verify();
+    assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+    assert_matches!(
+        &report.errors[0],
+        VerifyError::OpStore(OpStoreError::ObjectNotFound { hash, .. }) if *hash == view_id.hex()
+    );
+    assert_matches!(
+        &report.errors[1],
+        VerifyError::Backend(BackendError::ObjectNotFound { hash, .. }) if *hash == file_id.hex()
+    );
+}
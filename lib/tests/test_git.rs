@@ -46,18 +46,22 @@ use jj_lib::git::GitImportError;
 use jj_lib::git::GitPushError;
 use jj_lib::git::GitRefKind;
 use jj_lib::git::GitRefUpdate;
+use jj_lib::git::GitRemoteManagementError;
 use jj_lib::git::RefName;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::BookmarkTarget;
 use jj_lib::op_store::RefTarget;
+use jj_lib::op_store::RemoteConfig;
 use jj_lib::op_store::RemoteRef;
 use jj_lib::op_store::RemoteRefState;
+use jj_lib::op_store::WorkspaceId;
 use jj_lib::refs::BookmarkPushUpdate;
 use jj_lib::refs::RemoteRefSymbol;
 use jj_lib::repo::MutableRepo;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo::StoreFactories;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::settings::GitSettings;
@@ -65,6 +69,10 @@ use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
 use jj_lib::str_util::StringPattern;
 use jj_lib::tree_builder::TreeBuilder;
+use jj_lib::workspace::default_working_copy_factories;
+use jj_lib::workspace::GitCloneError;
+use jj_lib::workspace::GitCloneOptions;
+use jj_lib::workspace::GitCloneStats;
 use jj_lib::workspace::Workspace;
 use maplit::btreemap;
 use maplit::hashset;
@@ -2254,6 +2262,75 @@ fn test_export_undo_reexport() {
     );
 }
 
+#[test]
+fn test_mirror_refs() {
+    let test_data = GitRepoData::create();
+    let git_repo = test_data.git_repo;
+    let mut tx = test_data.repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let root_commit_id = mut_repo.store().root_commit_id().clone();
+    let commit_a = write_random_commit(mut_repo);
+    let commit_b = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("main", RefTarget::normal(commit_a.id().clone()));
+    mut_repo.set_local_bookmark_target("feature", RefTarget::normal(commit_b.id().clone()));
+    mut_repo.set_local_bookmark_target(
+        "conflicted",
+        RefTarget::from_legacy_form([], [commit_a.id().clone(), commit_b.id().clone()]),
+    );
+    mut_repo.set_local_bookmark_target("root", RefTarget::normal(root_commit_id));
+    mut_repo
+        .set_wc_commit(WorkspaceId::default(), commit_b.id().clone())
+        .unwrap();
+    let find_ref = |name: &str| {
+        git_repo
+            .find_reference(name)
+            .ok()
+            .map(|git_ref| git_ref.target().id().to_owned())
+    };
+
+    let failed = git::mirror_refs(mut_repo).unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].name, RefName::LocalBranch("root".to_owned()));
+    assert_matches!(failed[0].reason, FailedRefExportReason::OnRootCommit);
+    assert_eq!(find_ref("refs/jj/bookmarks/main"), Some(git_id(&commit_a)));
+    assert_eq!(
+        find_ref("refs/jj/bookmarks/feature"),
+        Some(git_id(&commit_b))
+    );
+    assert_eq!(find_ref("refs/jj/bookmarks/conflicted"), None);
+    assert_eq!(
+        find_ref("refs/jj/workspaces/default"),
+        Some(git_id(&commit_b))
+    );
+    // Bookmarks aren't exported to refs/heads/, and mirrored refs aren't tracked
+    assert_eq!(find_ref("refs/heads/main"), None);
+    assert!(mut_repo.view().git_refs().is_empty());
+
+    // Mirrored refs follow the view, even if they were changed in Git
+    mut_repo.set_local_bookmark_target("main", RefTarget::normal(commit_b.id().clone()));
+    mut_repo.set_local_bookmark_target("feature", RefTarget::absent());
+    git_repo
+        .reference(
+            "refs/jj/workspaces/default",
+            git_id(&commit_a),
+            gix::refs::transaction::PreviousValue::Any,
+            "",
+        )
+        .unwrap();
+    git::mirror_refs(mut_repo).unwrap();
+    assert_eq!(find_ref("refs/jj/bookmarks/main"), Some(git_id(&commit_b)));
+    assert_eq!(find_ref("refs/jj/bookmarks/feature"), None);
+    assert_eq!(
+        find_ref("refs/jj/workspaces/default"),
+        Some(git_id(&commit_b))
+    );
+
+    // Mirrored refs aren't imported back
+    let stats = git::import_refs(mut_repo, &GitSettings::default()).unwrap();
+    assert!(stats.changed_remote_refs.is_empty());
+    assert!(mut_repo.view().git_refs().is_empty());
+}
+
 #[test]
 fn test_reset_head_to_root() {
     // Create colocated workspace
@@ -3409,6 +3486,115 @@ fn test_push_bookmarks_not_fast_forward(subprocess: bool) {
     assert_eq!(new_target.target().id(), git_id(&setup.sideways_commit));
 }
 
+#[test_case(false; "use git2 for remote calls")]
+#[test_case(true; "spawn a git subprocess for remote calls")]
+fn test_push_change(subprocess: bool) {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let mut tx = setup.jj_repo.start_transaction();
+    let git_settings = get_git_settings(subprocess);
+    let source_repo = testutils::git::open(&setup.source_repo_dir);
+    let change_id = setup.child_of_main_commit.change_id();
+    let expected_name = format!("push-{change_id:.12}");
+
+    // The bookmark is created and pushed
+    let result = git::push_change(
+        tx.repo_mut(),
+        &git_settings,
+        change_id,
+        "origin",
+        git::RemoteCallbacks::default(),
+    );
+    assert_eq!(result.unwrap(), expected_name);
+    let new_target = source_repo
+        .find_reference(&format!("refs/heads/{expected_name}"))
+        .unwrap();
+    assert_eq!(
+        new_target.target().id(),
+        git_id(&setup.child_of_main_commit)
+    );
+    let view = tx.repo().view();
+    assert_eq!(
+        *view.get_local_bookmark(&expected_name),
+        RefTarget::normal(setup.child_of_main_commit.id().clone()),
+    );
+    assert_eq!(
+        *view.get_remote_bookmark(remote_symbol(&expected_name, "origin")),
+        RemoteRef {
+            target: RefTarget::normal(setup.child_of_main_commit.id().clone()),
+            state: RemoteRefState::Tracking,
+        },
+    );
+
+    // Rewriting the change moves the bookmark to the new commit
+    let rewritten_commit = tx
+        .repo_mut()
+        .rewrite_commit(&setup.child_of_main_commit)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    let result = git::push_change(
+        tx.repo_mut(),
+        &git_settings,
+        change_id,
+        "origin",
+        git::RemoteCallbacks::default(),
+    );
+    assert_eq!(result.unwrap(), expected_name);
+    let new_target = source_repo
+        .find_reference(&format!("refs/heads/{expected_name}"))
+        .unwrap();
+    assert_eq!(new_target.target().id(), git_id(&rewritten_commit));
+
+    // Pushing an unknown change fails
+    let result = git::push_change(
+        tx.repo_mut(),
+        &git_settings,
+        &ChangeId::from_hex("0123456789abcdef0123456789abcdef"),
+        "origin",
+        git::RemoteCallbacks::default(),
+    );
+    assert_matches!(result, Err(GitPushError::NoSuchChange(_)));
+}
+
+#[test]
+fn test_change_bookmark_name() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let mut tx = test_repo.repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit = write_random_commit(mut_repo);
+    let change_id = commit.change_id();
+
+    // The short change id is used by default
+    assert_eq!(
+        git::change_bookmark_name(mut_repo, "push-", change_id),
+        format!("push-{change_id:.12}")
+    );
+
+    // Divergent changes use the full change id
+    let divergent_commit = create_random_commit(mut_repo)
+        .set_change_id(change_id.clone())
+        .write()
+        .unwrap();
+    assert_eq!(
+        git::change_bookmark_name(mut_repo, "push-", change_id),
+        format!("push-{}", change_id.hex())
+    );
+    mut_repo.remove_head(divergent_commit.id());
+
+    // An existing bookmark with the full change id is reused
+    mut_repo.set_local_bookmark_target(
+        &format!("push-{}", change_id.hex()),
+        RefTarget::normal(commit.id().clone()),
+    );
+    assert_eq!(
+        git::change_bookmark_name(mut_repo, "push-", change_id),
+        format!("push-{}", change_id.hex())
+    );
+}
+
 // TODO(ilyagr): More tests for push safety checks were originally planned. We
 // may want to add tests for when a bookmark unexpectedly moved backwards or
 // unexpectedly does not exist for bookmark deletion.
@@ -4084,7 +4270,9 @@ fn test_remote_remove_refs() {
     let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
     let repo = &test_repo.repo;
 
-    git::add_remote(repo.store(), "foo", "https://example.com/").unwrap();
+    let mut tx = repo.start_transaction();
+    git::add_remote(tx.repo_mut(), "foo", "https://example.com/").unwrap();
+    tx.commit("add").unwrap();
     // Reload after Git configuration change.
     let repo = &test_repo
         .env
@@ -4122,7 +4310,9 @@ fn test_remote_rename_refs() {
     let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
     let repo = &test_repo.repo;
 
-    git::add_remote(repo.store(), "foo", "https://example.com/").unwrap();
+    let mut tx = repo.start_transaction();
+    git::add_remote(tx.repo_mut(), "foo", "https://example.com/").unwrap();
+    tx.commit("add").unwrap();
     // Reload after Git configuration change.
     let repo = &test_repo
         .env
@@ -4165,3 +4355,186 @@ fn test_remote_rename_refs() {
         commit_foobar_a,
     );
 }
+
+#[test]
+fn test_remote_config_in_view() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    // Reload after each Git configuration change.
+    let reload = || {
+        test_repo
+            .env
+            .load_repo_at_head(&testutils::user_settings(), test_repo.repo_path())
+    };
+    let remote_config = |url: &str| RemoteConfig {
+        url: url.to_owned(),
+    };
+
+    let mut tx = repo.start_transaction();
+    git::add_remote(tx.repo_mut(), "foo", "https://example.com/foo").unwrap();
+    tx.commit("add").unwrap();
+    let repo = &reload();
+    assert_eq!(
+        *repo.view().remote_configs(),
+        btreemap! {"foo".to_owned() => remote_config("https://example.com/foo")},
+    );
+    assert!(get_git_repo(repo).try_find_remote("foo").is_some());
+
+    let mut tx = repo.start_transaction();
+    git::set_remote_url(tx.repo_mut(), "foo", "https://example.com/bar").unwrap();
+    tx.commit("set url").unwrap();
+    let repo = &reload();
+    let mut tx = repo.start_transaction();
+    git::rename_remote(tx.repo_mut(), "foo", "bar").unwrap();
+    tx.commit("rename").unwrap();
+    let repo = &reload();
+    assert_eq!(
+        *repo.view().remote_configs(),
+        btreemap! {"bar".to_owned() => remote_config("https://example.com/bar")},
+    );
+    let git_repo = get_git_repo(repo);
+    assert!(git_repo.try_find_remote("foo").is_none());
+    let git_remote = git_repo.find_remote("bar").unwrap();
+    assert_eq!(
+        git_remote
+            .url(gix::remote::Direction::Fetch)
+            .unwrap()
+            .to_bstring(),
+        "https://example.com/bar",
+    );
+
+    let mut tx = repo.start_transaction();
+    git::remove_remote(tx.repo_mut(), "bar").unwrap();
+    tx.commit("remove").unwrap();
+    let repo = &reload();
+    assert!(repo.view().remote_configs().is_empty());
+    assert!(get_git_repo(repo).try_find_remote("bar").is_none());
+}
+
+#[test]
+fn test_remote_management_without_git_backend() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let remote_config = |url: &str| RemoteConfig {
+        url: url.to_owned(),
+    };
+
+    let mut tx = repo.start_transaction();
+    git::add_remote(tx.repo_mut(), "foo", "https://example.com/foo").unwrap();
+    git::add_remote(tx.repo_mut(), "bar", "https://example.com/bar").unwrap();
+    assert_matches!(
+        git::add_remote(tx.repo_mut(), "foo", "https://example.com/baz"),
+        Err(GitRemoteManagementError::RemoteAlreadyExists(_))
+    );
+    let repo = &tx.commit("add").unwrap();
+    assert_eq!(
+        *repo.view().remote_configs(),
+        btreemap! {
+            "bar".to_owned() => remote_config("https://example.com/bar"),
+            "foo".to_owned() => remote_config("https://example.com/foo"),
+        },
+    );
+
+    let mut tx = repo.start_transaction();
+    assert_matches!(
+        git::rename_remote(tx.repo_mut(), "foo", "bar"),
+        Err(GitRemoteManagementError::RemoteAlreadyExists(_))
+    );
+    git::rename_remote(tx.repo_mut(), "foo", "baz").unwrap();
+    git::set_remote_url(tx.repo_mut(), "bar", "https://example.com/qux").unwrap();
+    git::remove_remote(tx.repo_mut(), "baz").unwrap();
+    assert_matches!(
+        git::remove_remote(tx.repo_mut(), "foo"),
+        Err(GitRemoteManagementError::NoSuchRemote(_))
+    );
+    assert_matches!(
+        git::set_remote_url(tx.repo_mut(), "foo", "https://example.com/foo"),
+        Err(GitRemoteManagementError::NoSuchRemote(_))
+    );
+    let repo = &tx.commit("update").unwrap();
+    assert_eq!(
+        *repo.view().remote_configs(),
+        btreemap! {"bar".to_owned() => remote_config("https://example.com/qux")},
+    );
+}
+
+fn clone_git(
+    url: &str,
+    workspace_root: &Path,
+) -> Result<(Workspace, Arc<ReadonlyRepo>, GitCloneStats), GitCloneError> {
+    Workspace::clone_git(
+        url,
+        workspace_root,
+        &testutils::user_settings(),
+        &StoreFactories::default(),
+        &default_working_copy_factories(),
+        &GitCloneOptions::default(),
+        git::RemoteCallbacks::default(),
+    )
+}
+
+#[test]
+fn test_clone_git() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let source_path = temp_dir.path().join("source");
+    let source_repo = testutils::git::init(&source_path);
+    let commit_id = testutils::git::add_commit(
+        &source_repo,
+        "refs/heads/main",
+        "file",
+        b"contents",
+        "message",
+        &[],
+    )
+    .commit_id;
+    testutils::git::set_symbolic_reference(&source_repo, "HEAD", "refs/heads/main");
+
+    let workspace_root = temp_dir.path().join("clone");
+    let (workspace, repo, stats) =
+        clone_git(source_path.to_str().unwrap(), &workspace_root).unwrap();
+    assert_eq!(stats.default_branch.as_deref(), Some("main"));
+    assert_eq!(stats.checkout_stats.unwrap().added_files, 1);
+    assert_eq!(
+        repo.operation().metadata().description,
+        "check out git remote's default branch"
+    );
+
+    // The default branch is tracked and checked out
+    let view = repo.view();
+    assert_eq!(
+        view.get_local_bookmark("main"),
+        &RefTarget::normal(jj_id(commit_id))
+    );
+    assert!(view
+        .get_remote_bookmark(remote_symbol("main", "origin"))
+        .is_tracking());
+    let wc_commit_id = view.get_wc_commit_id(workspace.workspace_id()).unwrap();
+    let wc_commit = repo.store().get_commit(wc_commit_id).unwrap();
+    assert_eq!(wc_commit.parent_ids(), [jj_id(commit_id)]);
+    assert_eq!(fs::read(workspace_root.join("file")).unwrap(), b"contents");
+
+    // The workspace can be loaded again
+    let loaded_repo =
+        testutils::TestEnvironment::init().load_repo_at_head(&settings, workspace.repo_path());
+    assert_eq!(loaded_repo.op_id(), repo.op_id());
+}
+
+#[test]
+fn test_clone_git_failure() {
+    let temp_dir = testutils::new_temp_dir();
+
+    // The destination must be empty
+    let workspace_root = temp_dir.path().join("non-empty");
+    fs::create_dir(&workspace_root).unwrap();
+    fs::write(workspace_root.join("file"), "").unwrap();
+    let result = clone_git("unused", &workspace_root).map(|_| ());
+    assert_matches!(result, Err(GitCloneError::DestinationNotEmpty(_)));
+
+    // A failed clone doesn't leave anything behind
+    let workspace_root = temp_dir.path().join("clone");
+    let missing_source = temp_dir.path().join("missing");
+    let result = clone_git(missing_source.to_str().unwrap(), &workspace_root).map(|_| ());
+    assert_matches!(result, Err(GitCloneError::Fetch(_)));
+    assert!(!workspace_root.exists());
+}
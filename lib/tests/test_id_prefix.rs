@@ -19,6 +19,7 @@ use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::id_prefix::VisibleIdPrefixResolver;
 use jj_lib::object_id::HexPrefix;
 use jj_lib::object_id::ObjectId;
 use jj_lib::object_id::PrefixResolution::AmbiguousMatch;
@@ -532,3 +533,132 @@ fn test_id_prefix_hidden() {
         NoMatch
     );
 }
+
+#[test]
+fn test_visible_id_prefix_resolver() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    let root_commit_id = repo.store().root_commit_id();
+
+    let mut tx = repo.start_transaction();
+    let mut commits = vec![];
+    for i in 0..10 {
+        let signature = Signature {
+            name: "Some One".to_string(),
+            email: "some.one@example.com".to_string(),
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(i),
+                tz_offset: 0,
+            },
+        };
+        let commit = tx
+            .repo_mut()
+            .new_commit(
+                vec![root_commit_id.clone()],
+                repo.store().empty_merged_tree_id(),
+            )
+            .set_author(signature.clone())
+            .set_committer(signature)
+            .write()
+            .unwrap();
+        commits.push(commit);
+    }
+
+    // Same commits as in test_id_prefix_hidden(): commits[8] ("648") shares
+    // the first commit ID digit with commits[2] ("62e"), and the first two
+    // change ID digits with commits[3] ("26b").
+    let hidden_commit = &commits[8];
+    tx.repo_mut().record_abandoned_commit(hidden_commit);
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let prefix = |x: &str| HexPrefix::new(x).unwrap();
+    let hidden_commit_prefix = |len: usize| prefix(&hidden_commit.id().hex()[..len]);
+    let hidden_change_prefix = |len: usize| prefix(&hidden_commit.change_id().hex()[..len]);
+
+    let context = IdPrefixContext::default();
+    let index = context.populate(repo.as_ref()).unwrap();
+
+    // Hidden commits are ignored by default
+    let resolver = VisibleIdPrefixResolver::new(&index);
+    assert_eq!(
+        resolver
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(1))
+            .unwrap(),
+        SingleMatch(commits[2].id().clone())
+    );
+    assert_eq!(
+        resolver
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(2))
+            .unwrap(),
+        NoMatch
+    );
+
+    // Hidden commits are used as a fallback if requested
+    let resolver = VisibleIdPrefixResolver::new(&index).include_hidden(true);
+    assert_eq!(
+        resolver
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(1))
+            .unwrap(),
+        SingleMatch(commits[2].id().clone())
+    );
+    assert_eq!(
+        resolver
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(2))
+            .unwrap(),
+        SingleMatch(hidden_commit.id().clone())
+    );
+
+    // Change IDs of hidden commits are never resolved
+    assert_eq!(
+        resolver
+            .resolve_change_prefix(repo.as_ref(), &hidden_change_prefix(2))
+            .unwrap(),
+        SingleMatch(vec![commits[3].id().clone()])
+    );
+    assert_eq!(
+        resolver
+            .resolve_change_prefix(repo.as_ref(), &hidden_change_prefix(3))
+            .unwrap(),
+        NoMatch
+    );
+    // Ambiguous within the visible commits
+    assert_eq!(
+        resolver
+            .resolve_change_prefix(repo.as_ref(), &prefix("8"))
+            .unwrap(),
+        AmbiguousMatch
+    );
+
+    // Hidden commit in the disambiguation set falls back to the visible
+    // commits
+    let expression = RevsetExpression::commit(hidden_commit.id().clone());
+    let context = IdPrefixContext::default().disambiguate_within(expression);
+    let disambiguation_index = context.populate(repo.as_ref()).unwrap();
+    assert_eq!(
+        VisibleIdPrefixResolver::new(&disambiguation_index)
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(1))
+            .unwrap(),
+        SingleMatch(commits[2].id().clone())
+    );
+
+    // The cached visible commits are reloaded when resolving against a repo
+    // with different heads.
+    let mut tx = repo.start_transaction();
+    tx.repo_mut().record_abandoned_commit(&commits[2]);
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        VisibleIdPrefixResolver::new(&index)
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(1))
+            .unwrap(),
+        NoMatch
+    );
+    assert_eq!(
+        VisibleIdPrefixResolver::new(&index)
+            .include_hidden(true)
+            .resolve_commit_prefix(repo.as_ref(), &hidden_commit_prefix(1))
+            .unwrap(),
+        AmbiguousMatch
+    );
+}
@@ -17,6 +17,7 @@ use std::fs;
 use std::sync::Arc;
 
 use assert_matches::assert_matches;
+use itertools::Itertools as _;
 use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
 use jj_lib::commit::Commit;
@@ -592,6 +593,72 @@ fn test_index_commits_incremental_squashed() {
     assert_eq!(commits_by_level(&repo), vec![71, 20]);
 }
 
+#[test]
+fn test_reindex_reuses_parent_segment() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let index_operations_dir = test_repo.repo_path().join("index").join("operations");
+
+    let repo = create_n_commits(repo, 32);
+    let parent_segment_name =
+        fs::read_to_string(index_operations_dir.join(repo.op_id().hex())).unwrap();
+    let repo = create_n_commits(&repo, 2);
+    let commit_ids = itertools::chain(repo.view().heads(), [repo.store().root_commit_id()])
+        .cloned()
+        .collect_vec();
+    assert_eq!(commit_ids.len(), 35);
+
+    // The index of the last operation is built on top of the segment of its
+    // parent operation.
+    fs::remove_file(index_operations_dir.join(repo.op_id().hex())).unwrap();
+    let default_index_store: &DefaultIndexStore =
+        repo.index_store().as_any().downcast_ref().unwrap();
+    let incremental_index = default_index_store
+        .build_index_at_operation(repo.operation(), repo.store())
+        .unwrap();
+    let levels = incremental_index.as_composite().stats().levels;
+    assert_eq!(
+        levels.iter().map(|level| level.num_commits).collect_vec(),
+        [33, 2]
+    );
+    assert_eq!(levels[0].name.as_ref(), Some(&parent_segment_name));
+
+    // The result matches an index rebuilt from scratch.
+    default_index_store.reinit().unwrap();
+    let full_index = default_index_store
+        .build_index_at_operation(repo.operation(), repo.store())
+        .unwrap();
+    assert_eq!(
+        full_index
+            .as_composite()
+            .stats()
+            .levels
+            .iter()
+            .map(|level| level.num_commits)
+            .collect_vec(),
+        [35]
+    );
+    let describe = |index: &CompositeIndex, id: &CommitId| {
+        let entry = index.entry_by_id(id).unwrap();
+        let parent_ids = entry
+            .parents()
+            .map(|parent| parent.commit_id())
+            .collect_vec();
+        (entry.change_id(), entry.generation_number(), parent_ids)
+    };
+    for id in &commit_ids {
+        assert_eq!(
+            describe(incremental_index.as_composite(), id),
+            describe(full_index.as_composite(), id)
+        );
+    }
+    let incremental_stats = incremental_index.as_composite().stats();
+    let full_stats = full_index.as_composite().stats();
+    assert_eq!(incremental_stats.num_commits, full_stats.num_commits);
+    assert_eq!(incremental_stats.num_heads, full_stats.num_heads);
+    assert_eq!(incremental_stats.num_changes, full_stats.num_changes);
+}
+
 #[test]
 fn test_reindex_no_segments_dir() {
     let settings = testutils::user_settings();
@@ -836,3 +903,36 @@ fn test_change_id_index() {
     assert_eq!(resolve_prefix("a"), PrefixResolution::AmbiguousMatch);
     assert_eq!(resolve_prefix("b"), PrefixResolution::NoMatch);
 }
+
+#[test]
+fn test_change_id_index_of_readonly_repo() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit_a = write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    let mut tx = repo.start_transaction();
+    let commit_a2 = tx
+        .repo_mut()
+        .rewrite_commit(&commit_a)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    // The rewritten commit is hidden, so the change id should resolve to the
+    // new commit only.
+    let change_id_index = repo.change_id_index();
+    let prefix = HexPrefix::new(&commit_a.change_id().hex()).unwrap();
+    assert_eq!(
+        change_id_index.resolve_prefix(&prefix),
+        PrefixResolution::SingleMatch(vec![commit_a2.id().clone()])
+    );
+    assert_eq!(
+        repo.resolve_change_id(commit_a.change_id()),
+        Some(vec![commit_a2.id().clone()])
+    );
+}
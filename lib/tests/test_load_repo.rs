@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use assert_matches::assert_matches;
+use jj_lib::repo::BareRepoLoadError;
+use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::RepoLoader;
 use testutils::write_random_commit;
 use testutils::TestRepo;
+use testutils::TestWorkspace;
 
 #[test]
 fn test_load_at_operation() {
@@ -52,3 +56,38 @@ fn test_load_at_operation() {
     let old_repo = loader.load_at(repo.operation()).unwrap();
     assert!(old_repo.view().heads().contains(commit.id()));
 }
+
+#[test]
+fn test_load_bare() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    let repo = tx.commit("add commit").unwrap();
+
+    // The working-copy state isn't needed
+    let working_copy_path = test_workspace
+        .workspace
+        .workspace_root()
+        .join(".jj")
+        .join("working_copy");
+    std::fs::remove_dir_all(working_copy_path).unwrap();
+    let bare_repo = ReadonlyRepo::load_bare(
+        &settings,
+        test_workspace.repo_path(),
+        &test_workspace.env.default_store_factories(),
+    )
+    .unwrap();
+    assert_eq!(bare_repo.op_id(), repo.op_id());
+    assert!(bare_repo.view().heads().contains(commit.id()));
+
+    // The workspace root isn't a repo directory
+    let result = ReadonlyRepo::load_bare(
+        &settings,
+        test_workspace.workspace.workspace_root(),
+        &test_workspace.env.default_store_factories(),
+    );
+    assert_matches!(result, Err(BareRepoLoadError::NoRepoHere(_)));
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs::File;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 #[cfg(unix)]
@@ -19,18 +20,29 @@ use std::os::unix::net::UnixListener;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use assert_matches::assert_matches;
 use indoc::indoc;
 use itertools::Itertools;
+use jj_lib::attributes::AttributeValues;
+use jj_lib::attributes::FileAttributes;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::TreeId;
 use jj_lib::backend::TreeValue;
+use jj_lib::eol::EolConversionMode;
+use jj_lib::eol::EolSettings;
 use jj_lib::file_util::check_symlink_support;
 use jj_lib::file_util::try_symlink;
 use jj_lib::fsmonitor::FsmonitorSettings;
+use jj_lib::git_lfs::LfsPointer;
+use jj_lib::git_lfs::LfsSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::local_working_copy::TreeState;
+use jj_lib::matchers::NothingMatcher;
 use jj_lib::merge::Merge;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
@@ -39,6 +51,7 @@ use jj_lib::op_store::OperationId;
 use jj_lib::op_store::WorkspaceId;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::PathNormalization;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::RepoPathComponent;
@@ -46,17 +59,21 @@ use jj_lib::secret_backend::SecretBackend;
 use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::FileCacheStats;
 use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::TrackingPatterns;
 use jj_lib::working_copy::UntrackedReason;
 use jj_lib::working_copy::WorkingCopy;
 use jj_lib::workspace::default_working_copy_factories;
 use jj_lib::workspace::LockedWorkspace;
 use jj_lib::workspace::Workspace;
 use pollster::FutureExt;
+use prost::Message as _;
 use test_case::test_case;
 use testutils::commit_with_tree;
 use testutils::create_tree;
 use testutils::write_random_commit;
+use testutils::write_working_copy_file;
 use testutils::TestRepoBackend;
 use testutils::TestWorkspace;
 
@@ -364,13 +381,70 @@ fn test_checkout_file_transitions(backend: TestRepoBackend) {
                 assert!(metadata.is_dir(), "{path:?} should be a directory");
             }
             Kind::GitSubmodule => {
-                // Not supported for now
-                assert!(maybe_metadata.is_err(), "{path:?} should not exist");
+                // Materialized as an empty directory
+                assert!(maybe_metadata.is_ok(), "{path:?} should exist");
+                let metadata = maybe_metadata.unwrap();
+                assert!(metadata.is_dir(), "{path:?} should be a directory");
             }
         };
     }
 }
 
+#[test]
+fn test_checkout_progress() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let store = repo.store().clone();
+
+    let added_path = RepoPath::from_internal_string("added");
+    let modified_path = RepoPath::from_internal_string("dir/modified");
+    let removed_path = RepoPath::from_internal_string("removed");
+    let unchanged_path = RepoPath::from_internal_string("unchanged");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (modified_path, "1"),
+            (removed_path, "1"),
+            (unchanged_path, "1"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (added_path, "2"),
+            (modified_path, "2"),
+            (unchanged_path, "1"),
+        ],
+    );
+    let commit1 = commit_with_tree(&store, tree1.id());
+    let commit2 = commit_with_tree(&store, tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    let op_id = repo.op_id().clone();
+    ws.check_out(
+        op_id.clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    let reported_paths = Mutex::new(vec![]);
+    let progress = |path: &RepoPath| reported_paths.lock().unwrap().push(path.to_owned());
+    let options = CheckoutOptions {
+        progress: Some(&progress),
+        ..CheckoutOptions::empty_for_test()
+    };
+    let stats = ws.check_out(op_id, None, &commit2, &options).unwrap();
+    assert_eq!(stats.added_files, 1);
+    assert_eq!(stats.updated_files, 1);
+    assert_eq!(stats.removed_files, 1);
+    assert_eq!(
+        reported_paths.into_inner().unwrap(),
+        [added_path, modified_path, removed_path].map(RepoPath::to_owned)
+    );
+}
+
 #[test]
 fn test_checkout_no_op() {
     // Check out another commit with the same tree that's already checked out. The
@@ -958,6 +1032,276 @@ fn test_materialize_snapshot_conflicted_files() {
     );
 }
 
+#[test]
+fn test_materialize_snapshot_file_directory_conflict() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo.clone();
+    let ws = &mut test_workspace.workspace;
+    let workspace_root = ws.workspace_root().to_owned();
+
+    // One side adds a file at "path", the other adds a directory there
+    let path = RepoPath::from_internal_string("path");
+    let file_in_dir_path = RepoPath::from_internal_string("path/file");
+    let base_tree = create_tree(repo, &[]);
+    let file_tree = create_tree(repo, &[(path, "file\n")]);
+    let dir_tree = create_tree(repo, &[(file_in_dir_path, "dir\n")]);
+    let merged_tree = file_tree.merge(&base_tree, &dir_tree).unwrap();
+    let commit = commit_with_tree(repo.store(), merged_tree.id());
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    // The conflict is materialized as a file describing both sides, including
+    // the files in the directory.
+    let content = std::fs::read_to_string(path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    insta::assert_snapshot!(content, @r"
+    Conflict between a file and a directory:
+      Adding file with id f239f77da55093d24ac9
+      Adding tree with id 3c7d65ac3078e6b91656
+    Files in tree with id 3c7d65ac3078e6b91656:
+      path/file
+    Replace this file with the file or directory to keep to resolve the conflict.
+    ");
+
+    // Snapshotting the unchanged file keeps the conflict
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), merged_tree.id());
+
+    // Replacing the conflict description with the file resolves the conflict
+    testutils::write_working_copy_file(&workspace_root, path, "file\n");
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), file_tree.id());
+
+    // Replacing the conflict description with the directory also resolves the
+    // conflict
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    std::fs::remove_file(path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    testutils::write_working_copy_file(&workspace_root, file_in_dir_path, "dir\n");
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), dir_tree.id());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_materialize_snapshot_executable_conflict() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo.clone();
+    let ws = &mut test_workspace.workspace;
+    let workspace_root = ws.workspace_root().to_owned();
+
+    // Both sides add the same contents, but only one side is executable
+    let path = RepoPath::from_internal_string("file");
+    let store = repo.store();
+    let base_tree = create_tree(repo, &[]);
+    let normal_tree = create_tree(repo, &[(path, "contents\n")]);
+    let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+    testutils::write_executable_file(&mut tree_builder, path, "contents\n");
+    let executable_tree = MergedTree::resolved(
+        store
+            .get_tree(RepoPathBuf::root(), &tree_builder.write_tree().unwrap())
+            .unwrap(),
+    );
+    let merged_tree = executable_tree.merge(&base_tree, &normal_tree).unwrap();
+    assert!(merged_tree.path_value(path).unwrap().is_present());
+    assert!(!merged_tree.path_value(path).unwrap().is_resolved());
+    let commit = commit_with_tree(store, merged_tree.id());
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    // The file contents are written as-is, and it's executable
+    let disk_path = path.to_fs_path_unchecked(&workspace_root);
+    assert_eq!(std::fs::read_to_string(&disk_path).unwrap(), "contents\n");
+    let mode = std::fs::metadata(&disk_path).unwrap().permissions().mode();
+    assert_ne!(mode & 0o111, 0);
+
+    // Snapshotting the unchanged file keeps the conflict
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), merged_tree.id());
+
+    // Changing the executable bit resolves the conflict
+    std::fs::set_permissions(&disk_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), normal_tree.id());
+}
+
+#[test]
+fn test_eol_conversion() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let attributes = FileAttributes::empty()
+        .with_rule(
+            "*.sh",
+            AttributeValues {
+                eol: Some(EolConversionMode::None),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let eol_settings = EolSettings::new(EolConversionMode::InputOutput).with_attributes(attributes);
+
+    let text_path = RepoPath::from_internal_string("file.txt");
+    let script_path = RepoPath::from_internal_string("script.sh");
+    let conflict_path = RepoPath::from_internal_string("conflict.txt");
+    let base_tree = create_tree(repo, &[(conflict_path, "base\n")]);
+    let left_tree = create_tree(
+        repo,
+        &[
+            (text_path, "a\nb\n"),
+            (script_path, "a\nb\n"),
+            (conflict_path, "left\n"),
+        ],
+    );
+    let right_tree = create_tree(repo, &[(conflict_path, "right\n")]);
+    let merged_tree = left_tree.merge(&base_tree, &right_tree).unwrap();
+    let commit = commit_with_tree(repo.store(), merged_tree.id());
+    let checkout_options = CheckoutOptions {
+        eol_settings: eol_settings.clone(),
+        ..CheckoutOptions::empty_for_test()
+    };
+    test_workspace
+        .workspace
+        .check_out(repo.op_id().clone(), None, &commit, &checkout_options)
+        .unwrap();
+
+    // Line endings are converted on checkout unless overridden
+    let read = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    assert_eq!(read(text_path), b"a\r\nb\r\n");
+    assert_eq!(read(script_path), b"a\nb\n");
+    insta::assert_snapshot!(String::from_utf8(read(conflict_path)).unwrap().replace('\r', "\\r"), @r"
+    <<<<<<< Conflict 1 of 1\r
+    %%%%%%% Changes from base to side #1\r
+    -base\r
+    +left\r
+    +++++++ Contents of side #2\r
+    right\r
+    >>>>>>> Conflict 1 of 1 ends\r
+    ");
+
+    // Snapshotting the unchanged files doesn't change anything
+    let snapshot_options = SnapshotOptions {
+        eol_settings,
+        ..SnapshotOptions::empty_for_test()
+    };
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&snapshot_options)
+        .unwrap();
+    assert_eq!(tree.id(), merged_tree.id());
+
+    // CRLF line endings are converted to LF when snapshotting
+    testutils::write_working_copy_file(&workspace_root, text_path, "c\r\nd\r\n");
+    testutils::write_working_copy_file(&workspace_root, script_path, "c\r\nd\r\n");
+    testutils::write_working_copy_file(&workspace_root, conflict_path, "resolved\r\n");
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&snapshot_options)
+        .unwrap();
+    let expected_tree = create_tree(
+        repo,
+        &[
+            (text_path, "c\nd\n"),
+            (script_path, "c\r\nd\r\n"),
+            (conflict_path, "resolved\n"),
+        ],
+    );
+    assert_eq!(tree.id(), expected_tree.id());
+}
+
+#[test]
+fn test_lfs_conversion() {
+    let mut test_workspace = TestWorkspace::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let attributes = FileAttributes::empty()
+        .with_rule(
+            "*.bin",
+            AttributeValues {
+                lfs: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let lfs_settings = LfsSettings::default().with_attributes(attributes);
+    let snapshot_options = SnapshotOptions {
+        lfs_settings: lfs_settings.clone(),
+        ..SnapshotOptions::empty_for_test()
+    };
+    let checkout_options = CheckoutOptions {
+        lfs_settings,
+        ..CheckoutOptions::empty_for_test()
+    };
+
+    // Files with the lfs attribute are replaced by pointers when snapshotting
+    let large_path = RepoPath::from_internal_string("large.bin");
+    let text_path = RepoPath::from_internal_string("file.txt");
+    testutils::write_working_copy_file(&workspace_root, large_path, "large contents\n");
+    testutils::write_working_copy_file(&workspace_root, text_path, "text\n");
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&snapshot_options)
+        .unwrap();
+    let pointer = LfsPointer {
+        oid: "7004f11b91b38ea3219d0b2fb175e673cee200839aa75c1c8434e8693ebfab0b".to_owned(),
+        size: 15,
+    };
+    let pointer_text = String::from_utf8(pointer.to_bytes()).unwrap();
+    let expected_tree = create_tree(repo, &[(large_path, &pointer_text), (text_path, "text\n")]);
+    assert_eq!(tree.id(), expected_tree.id());
+
+    // Pointers are replaced by the contents when checking out
+    let empty_commit = commit_with_tree(repo.store(), repo.store().empty_merged_tree_id());
+    let commit = commit_with_tree(repo.store(), tree.id());
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(repo.op_id().clone(), None, &empty_commit, &checkout_options)
+        .unwrap();
+    assert!(!large_path.to_fs_path_unchecked(&workspace_root).exists());
+    ws.check_out(repo.op_id().clone(), None, &commit, &checkout_options)
+        .unwrap();
+    let read = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    assert_eq!(read(large_path), b"large contents\n");
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&snapshot_options)
+        .unwrap();
+    assert_eq!(tree.id(), expected_tree.id());
+
+    // Pointers to contents missing from the LFS storage are checked out as
+    // they are
+    let missing_pointer = LfsPointer {
+        oid: "0".repeat(64),
+        size: 1,
+    };
+    let missing_text = String::from_utf8(missing_pointer.to_bytes()).unwrap();
+    let missing_tree = create_tree(repo, &[(large_path, &missing_text)]);
+    let missing_commit = commit_with_tree(repo.store(), missing_tree.id());
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &missing_commit,
+        &checkout_options,
+    )
+    .unwrap();
+    assert_eq!(read(large_path), missing_text.as_bytes());
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&snapshot_options)
+        .unwrap();
+    assert_eq!(tree.id(), missing_tree.id());
+}
+
 #[test]
 fn test_snapshot_racy_timestamps() {
     // Tests that file modifications are detected even if they happen the same
@@ -983,6 +1327,126 @@ fn test_snapshot_racy_timestamps() {
     }
 }
 
+#[test]
+fn test_snapshot_file_cache() {
+    // Tests that unchanged files are found by their recorded metadata, and that
+    // racily clean files are read only until the state is saved again.
+    let mut test_workspace = TestWorkspace::init();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let wc: &LocalWorkingCopy = test_workspace
+        .workspace
+        .working_copy()
+        .as_any()
+        .downcast_ref()
+        .unwrap();
+    let tree_state_path = wc.state_path().join("tree_state");
+    let past_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let set_mtime = |path: &Path, time: SystemTime| {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(time).unwrap();
+    };
+
+    let file1_path = workspace_root.join("file1");
+    let file2_path = workspace_root.join("file2");
+    std::fs::write(&file1_path, "contents 1").unwrap();
+    std::fs::write(&file2_path, "contents 2").unwrap();
+    set_mtime(&file1_path, past_time);
+    set_mtime(&file2_path, past_time);
+    let (tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        stats.file_cache,
+        FileCacheStats {
+            hits: 0,
+            misses: 2,
+            racily_clean: 0,
+        }
+    );
+
+    // Unchanged files aren't read again
+    let (new_tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(new_tree.id(), tree.id());
+    assert_eq!(
+        stats.file_cache,
+        FileCacheStats {
+            hits: 2,
+            misses: 0,
+            racily_clean: 0,
+        }
+    );
+
+    // If the files may have been modified at the time the state was saved, they
+    // have to be read
+    set_mtime(&tree_state_path, past_time);
+    let (new_tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(new_tree.id(), tree.id());
+    assert_eq!(
+        stats.file_cache,
+        FileCacheStats {
+            hits: 0,
+            misses: 2,
+            racily_clean: 2,
+        }
+    );
+
+    // The state was saved again, so the files are no longer racily clean
+    let (new_tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(new_tree.id(), tree.id());
+    assert_eq!(
+        stats.file_cache,
+        FileCacheStats {
+            hits: 2,
+            misses: 0,
+            racily_clean: 0,
+        }
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_snapshot_replaced_file() {
+    // Tests that a file replaced by another file of the same size and mtime is
+    // detected by its inode number.
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let file_path = RepoPath::from_internal_string("file");
+    let past_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+    let disk_path = file_path.to_fs_path_unchecked(&workspace_root);
+    std::fs::write(&disk_path, "contents 1").unwrap();
+    File::options()
+        .write(true)
+        .open(&disk_path)
+        .unwrap()
+        .set_modified(past_time)
+        .unwrap();
+    test_workspace.snapshot().unwrap();
+
+    let temp_path = workspace_root.join(".jj").join("replacement");
+    std::fs::write(&temp_path, "contents 2").unwrap();
+    File::options()
+        .write(true)
+        .open(&temp_path)
+        .unwrap()
+        .set_modified(past_time)
+        .unwrap();
+    std::fs::rename(&temp_path, &disk_path).unwrap();
+    let (new_tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    let expected_tree = create_tree(&repo, &[(file_path, "contents 2")]);
+    assert_eq!(new_tree.id(), expected_tree.id());
+    assert_eq!(stats.file_cache.misses, 1);
+}
+
 #[cfg(unix)]
 #[test]
 fn test_snapshot_special_file() {
@@ -1328,7 +1792,8 @@ fn test_dotgit_ignored() {
 #[test_case(""; "ignore nothing")]
 #[test_case("/*\n"; "ignore all")]
 fn test_git_submodule(gitignore_content: &str) {
-    // Tests that git submodules are ignored.
+    // Tests that git submodules are materialized as directories and their
+    // contents are ignored.
 
     let mut test_workspace = TestWorkspace::init_with_backend(TestRepoBackend::Git);
     let repo = test_workspace.repo.clone();
@@ -1387,7 +1852,10 @@ fn test_git_submodule(gitignore_content: &str) {
     )
     .unwrap();
 
-    std::fs::create_dir(submodule_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    // The submodule is materialized as an empty directory
+    let submodule_disk_path = submodule_path.to_fs_path_unchecked(&workspace_root);
+    assert!(submodule_disk_path.is_dir());
+    assert_eq!(std::fs::read_dir(&submodule_disk_path).unwrap().count(), 0);
 
     testutils::write_working_copy_file(
         &workspace_root,
@@ -1445,6 +1913,30 @@ fn test_git_submodule(gitignore_content: &str) {
         )
         .unwrap();
     assert_eq!(stats.skipped_files, 1);
+    // The submodule directory is kept since it isn't empty
+    assert!(submodule_disk_path.is_dir());
+
+    // Empty submodule directory is removed
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    std::fs::remove_file(&file_in_submodule_path).unwrap();
+    let ws = &mut test_workspace.workspace;
+    let stats = ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &store.root_commit(),
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    assert_eq!(stats.skipped_files, 0);
+    assert!(!submodule_disk_path.exists());
 }
 
 #[test]
@@ -1492,14 +1984,30 @@ fn test_check_out_existing_file_cannot_be_removed() {
 }
 
 #[test]
-fn test_check_out_existing_file_replaced_with_directory() {
+fn test_check_out_changed_files() {
     let mut test_workspace = TestWorkspace::init();
     let repo = &test_workspace.repo;
     let workspace_root = test_workspace.workspace.workspace_root().to_owned();
 
-    let file_path = RepoPath::from_internal_string("file");
-    let tree1 = create_tree(repo, &[(file_path, "0")]);
-    let tree2 = create_tree(repo, &[(file_path, "1")]);
+    let modified_path = RepoPath::from_internal_string("modified");
+    let deleted_path = RepoPath::from_internal_string("deleted");
+    let unchanged_path = RepoPath::from_internal_string("unchanged");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (modified_path, "0"),
+            (deleted_path, "0"),
+            (unchanged_path, "0"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (modified_path, "1"),
+            (deleted_path, "1"),
+            (unchanged_path, "1"),
+        ],
+    );
     let commit1 = commit_with_tree(repo.store(), tree1.id());
     let commit2 = commit_with_tree(repo.store(), tree2.id());
 
@@ -1512,15 +2020,303 @@ fn test_check_out_existing_file_replaced_with_directory() {
     )
     .unwrap();
 
-    std::fs::remove_file(file_path.to_fs_path_unchecked(&workspace_root)).unwrap();
-    std::fs::create_dir(file_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    // Change files without snapshotting
+    write_working_copy_file(&workspace_root, modified_path, "changed on disk");
+    std::fs::remove_file(deleted_path.to_fs_path_unchecked(&workspace_root)).unwrap();
 
-    // Checkout doesn't fail, but the file should be skipped.
+    // Nothing is written if any file would be overwritten
+    let result = ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit2,
+        &CheckoutOptions::empty_for_test(),
+    );
+    assert_matches!(
+        result,
+        Err(CheckoutError::ChangedFiles { paths }) if paths == [modified_path.to_owned()]
+    );
+    let read_file = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root));
+    assert_eq!(read_file(modified_path).unwrap(), b"changed on disk");
+    assert!(read_file(deleted_path).is_err());
+    assert_eq!(read_file(unchanged_path).unwrap(), b"0");
+
+    // The changes are overwritten with force
+    let options = CheckoutOptions {
+        force: true,
+        ..CheckoutOptions::empty_for_test()
+    };
     let stats = ws
-        .check_out(
-            repo.op_id().clone(),
-            None,
-            &commit2,
+        .check_out(repo.op_id().clone(), None, &commit2, &options)
+        .unwrap();
+    assert_eq!(stats.updated_files, 3);
+    assert_eq!(read_file(modified_path).unwrap(), b"1");
+    assert_eq!(read_file(deleted_path).unwrap(), b"1");
+    assert_eq!(read_file(unchanged_path).unwrap(), b"1");
+}
+
+#[test]
+fn test_check_out_interrupted() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let modified_path = RepoPath::from_internal_string("modified");
+    let added_path = RepoPath::from_internal_string("added");
+    let removed_path = RepoPath::from_internal_string("removed");
+    let tree1 = create_tree(repo, &[(modified_path, "0"), (removed_path, "0")]);
+    let tree2 = create_tree(repo, &[(modified_path, "1"), (added_path, "1")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    let journal_path = wc.state_path().join("checkout_journal");
+    let read_file = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root));
+
+    // Check out commit2 without saving the tree state, and undo one of the
+    // file updates as if the checkout had been interrupted halfway
+    let modified_disk_path = modified_path.to_fs_path_unchecked(&workspace_root);
+    let backup_path = workspace_root.join("backup");
+    std::fs::rename(&modified_disk_path, &backup_path).unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    drop(locked_ws);
+    std::fs::rename(&backup_path, &modified_disk_path).unwrap();
+    assert!(journal_path.exists());
+
+    // The working copy is considered to be at commit2, and the checkout is
+    // finished instead of snapshotting the partially updated files
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    assert_eq!(*locked_ws.locked_wc().old_tree_id(), tree2.id());
+    let (tree_id, _stats) = locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree2.id());
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    assert!(!journal_path.exists());
+    assert_eq!(read_file(modified_path).unwrap(), b"1");
+    assert_eq!(read_file(added_path).unwrap(), b"1");
+    assert!(read_file(removed_path).is_err());
+
+    // Interrupt a checkout back to commit1, then check out commit2 again
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .check_out(&commit1, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    drop(locked_ws);
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    assert_eq!(*locked_ws.locked_wc().old_tree_id(), tree1.id());
+    locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    assert!(!journal_path.exists());
+    assert_eq!(*ws.working_copy().tree_id().unwrap(), tree2.id());
+    assert_eq!(read_file(modified_path).unwrap(), b"1");
+    assert_eq!(read_file(added_path).unwrap(), b"1");
+    assert!(read_file(removed_path).is_err());
+}
+
+#[test]
+fn test_check_out_interrupted_added_files() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let added_path = RepoPath::from_internal_string("added");
+    let untracked_path = RepoPath::from_internal_string("untracked");
+    let tree = create_tree(repo, &[(added_path, "1"), (untracked_path, "1")]);
+    let commit = commit_with_tree(repo.store(), tree.id());
+    let read_file = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root));
+
+    // An untracked file written before the checkout started
+    let untracked_disk_path = untracked_path.to_fs_path_unchecked(&workspace_root);
+    std::fs::write(&untracked_disk_path, "untracked").unwrap();
+    File::options()
+        .write(true)
+        .open(&untracked_disk_path)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .unwrap();
+
+    // Interrupt the checkout while the added file is being written
+    let ws = &mut test_workspace.workspace;
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let stats = locked_ws
+        .locked_wc()
+        .check_out(&commit, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(stats.skipped_files, 1);
+    drop(locked_ws);
+    write_working_copy_file(&workspace_root, added_path, "partial");
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    let load_tree_state = || {
+        TreeState::load(
+            repo.store().clone(),
+            workspace_root.clone(),
+            wc.state_path().to_owned(),
+        )
+        .unwrap()
+    };
+
+    // The partially written file can't be told apart from a change made by
+    // the user, so it isn't overwritten by default
+    let mut tree_state = load_tree_state();
+    assert_eq!(tree_state.pending_checkout(), Some(&tree.id()));
+    let result = tree_state.finish_interrupted_checkout(&CheckoutOptions::empty_for_test());
+    assert_matches!(
+        result,
+        Err(CheckoutError::ChangedFiles { paths }) if paths == [added_path.to_owned()]
+    );
+    assert_eq!(read_file(added_path).unwrap(), b"partial");
+
+    // The file is overwritten if forced, but the untracked file is still
+    // skipped
+    let mut tree_state = load_tree_state();
+    assert_eq!(tree_state.pending_checkout(), Some(&tree.id()));
+    let options = CheckoutOptions {
+        force: true,
+        ..CheckoutOptions::empty_for_test()
+    };
+    let stats = tree_state.finish_interrupted_checkout(&options).unwrap();
+    assert_eq!(
+        stats,
+        Some(CheckoutStats {
+            updated_files: 0,
+            added_files: 2,
+            removed_files: 0,
+            skipped_files: 1,
+        })
+    );
+    assert_eq!(read_file(added_path).unwrap(), b"1");
+    assert_eq!(read_file(untracked_path).unwrap(), b"untracked");
+}
+
+#[test]
+fn test_check_out_interrupted_changed_files() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let modified_path = RepoPath::from_internal_string("modified");
+    let unreached_path = RepoPath::from_internal_string("unreached");
+    let removed_path = RepoPath::from_internal_string("removed");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (modified_path, "0"),
+            (unreached_path, "0"),
+            (removed_path, "0"),
+        ],
+    );
+    let tree2 = create_tree(repo, &[(modified_path, "1"), (unreached_path, "1")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    let read_file = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&workspace_root));
+
+    // Interrupt a checkout of commit2 before one of the files is updated,
+    // then change the updated files
+    let unreached_disk_path = unreached_path.to_fs_path_unchecked(&workspace_root);
+    let backup_path = workspace_root.join("backup");
+    std::fs::rename(&unreached_disk_path, &backup_path).unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    drop(locked_ws);
+    std::fs::rename(&backup_path, &unreached_disk_path).unwrap();
+    write_working_copy_file(&workspace_root, modified_path, "changed");
+    write_working_copy_file(&workspace_root, removed_path, "changed");
+
+    // Checking out another commit refuses to overwrite the changed files
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let result = locked_ws
+        .locked_wc()
+        .check_out(&commit1, &CheckoutOptions::empty_for_test());
+    assert_matches!(
+        result,
+        Err(CheckoutError::ChangedFiles { paths })
+            if paths == [modified_path.to_owned(), removed_path.to_owned()]
+    );
+    drop(locked_ws);
+    assert_eq!(read_file(modified_path).unwrap(), b"changed");
+    assert_eq!(read_file(removed_path).unwrap(), b"changed");
+
+    // Snapshotting finishes the checkout and records the changed files as
+    // changes to commit2
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let (tree_id, _stats) = locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    let expected_tree = create_tree(
+        repo,
+        &[
+            (modified_path, "changed"),
+            (unreached_path, "1"),
+            (removed_path, "changed"),
+        ],
+    );
+    assert_eq!(tree_id, expected_tree.id());
+    assert_eq!(read_file(modified_path).unwrap(), b"changed");
+    assert_eq!(read_file(unreached_path).unwrap(), b"1");
+    assert_eq!(read_file(removed_path).unwrap(), b"changed");
+}
+
+#[test]
+fn test_check_out_existing_file_replaced_with_directory() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let file_path = RepoPath::from_internal_string("file");
+    let tree1 = create_tree(repo, &[(file_path, "0")]);
+    let tree2 = create_tree(repo, &[(file_path, "1")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    std::fs::remove_file(file_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    std::fs::create_dir(file_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+
+    // Checkout doesn't fail, but the file should be skipped.
+    let stats = ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit2,
             &CheckoutOptions::empty_for_test(),
         )
         .unwrap();
@@ -1879,13 +2675,13 @@ fn test_check_out_reserved_file_path_icase_fs(file_path_str: &str) {
     std::fs::create_dir_all(disk_path.parent().unwrap()).unwrap();
     std::fs::write(&disk_path, "").unwrap();
 
-    // Check out empty tree, which tries to remove the file.
-    let result = ws.check_out(
-        repo.op_id().clone(),
-        None,
-        &commit2,
-        &CheckoutOptions::empty_for_test(),
-    );
+    // Check out empty tree, which tries to remove the file. The file was
+    // rewritten since the last checkout, so force the removal.
+    let options = CheckoutOptions {
+        force: true,
+        ..CheckoutOptions::empty_for_test()
+    };
+    let result = ws.check_out(repo.op_id().clone(), None, &commit2, &options);
     if is_icase_fs {
         assert_matches!(result, Err(CheckoutError::ReservedPathComponent { .. }));
     } else {
@@ -1950,13 +2746,13 @@ fn test_check_out_reserved_file_path_hfs_plus(file_path_str: &str) {
     std::fs::create_dir_all(disk_path.parent().unwrap()).unwrap();
     std::fs::write(&disk_path, "").unwrap();
 
-    // Check out empty tree, which tries to remove the file.
-    let result = ws.check_out(
-        repo.op_id().clone(),
-        None,
-        &commit2,
-        &CheckoutOptions::empty_for_test(),
-    );
+    // Check out empty tree, which tries to remove the file. The file was
+    // rewritten since the last checkout, so force the removal.
+    let options = CheckoutOptions {
+        force: true,
+        ..CheckoutOptions::empty_for_test()
+    };
+    let result = ws.check_out(repo.op_id().clone(), None, &commit2, &options);
     if is_hfs_plus {
         assert_matches!(result, Err(CheckoutError::ReservedPathComponent { .. }));
     } else {
@@ -2231,3 +3027,263 @@ fn test_snapshot_max_new_file_size() {
         UntrackedReason::FileTooLarge { .. }
     );
 }
+
+#[test]
+fn test_snapshot_tracking_patterns() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let dir_path = RepoPath::from_internal_string("dir");
+    let kept_dir_path = RepoPath::from_internal_string("dir/kept");
+    let untracked_path = RepoPath::from_internal_string("dir/file");
+    let kept_path = RepoPath::from_internal_string("dir/kept/file");
+    let other_path = RepoPath::from_internal_string("other");
+    let no_auto_track = SnapshotOptions {
+        start_tracking_matcher: &NothingMatcher,
+        ..SnapshotOptions::empty_for_test()
+    };
+
+    let ws = &mut test_workspace.workspace;
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .untrack(&[dir_path.to_owned()])
+        .unwrap();
+    locked_ws
+        .locked_wc()
+        .track(&[kept_dir_path.to_owned()])
+        .unwrap();
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    let expected_patterns =
+        TrackingPatterns::new(vec![kept_dir_path.to_owned()], vec![dir_path.to_owned()]);
+    assert_eq!(
+        ws.working_copy().tracking_patterns().unwrap(),
+        &expected_patterns
+    );
+
+    // The patterns take precedence over the start-tracking matcher
+    for path in [untracked_path, kept_path, other_path] {
+        testutils::write_working_copy_file(&workspace_root, path, "contents");
+    }
+    let (tree, stats) = test_workspace
+        .snapshot_with_options(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        tree.entries().map(|(path, _value)| path).collect_vec(),
+        vec![kept_path.to_owned(), other_path.to_owned()]
+    );
+    assert_eq!(
+        stats.untracked_paths.keys().collect_vec(),
+        vec![&untracked_path.to_owned()]
+    );
+
+    // Files which are already tracked aren't affected
+    let ws = &mut test_workspace.workspace;
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .untrack(&[kept_path.to_owned(), other_path.to_owned()])
+        .unwrap();
+    locked_ws
+        .locked_wc()
+        .track(&[untracked_path.to_owned()])
+        .unwrap();
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    let (tree, _stats) = test_workspace
+        .snapshot_with_options(&no_auto_track)
+        .unwrap();
+    assert_eq!(
+        tree.entries().map(|(path, _value)| path).collect_vec(),
+        vec![
+            untracked_path.to_owned(),
+            kept_path.to_owned(),
+            other_path.to_owned()
+        ]
+    );
+
+    // The patterns are persisted
+    let wc: &LocalWorkingCopy = test_workspace
+        .workspace
+        .working_copy()
+        .as_any()
+        .downcast_ref()
+        .unwrap();
+    let reloaded_wc = LocalWorkingCopy::load(
+        repo.store().clone(),
+        workspace_root,
+        wc.state_path().to_owned(),
+    );
+    let expected_patterns = TrackingPatterns::new(
+        vec![untracked_path.to_owned(), kept_dir_path.to_owned()],
+        vec![
+            dir_path.to_owned(),
+            kept_path.to_owned(),
+            other_path.to_owned(),
+        ],
+    );
+    assert_eq!(reloaded_wc.tracking_patterns().unwrap(), &expected_patterns);
+    assert_eq!(expected_patterns.is_tracked(kept_dir_path), Some(true));
+    assert_eq!(
+        expected_patterns.is_tracked(RepoPath::from_internal_string("dir/kept/file/new")),
+        Some(false)
+    );
+    assert_eq!(
+        expected_patterns.is_tracked(RepoPath::from_internal_string("dir/new")),
+        Some(false)
+    );
+    assert_eq!(
+        expected_patterns.is_tracked(RepoPath::from_internal_string("new")),
+        None
+    );
+
+    // Tracking a parent directory overrides the patterns under it
+    let mut patterns = expected_patterns;
+    patterns.track(&[RepoPathBuf::root()]);
+    assert_eq!(
+        patterns,
+        TrackingPatterns::new(vec![RepoPathBuf::root()], vec![])
+    );
+}
+
+#[test]
+fn test_snapshot_path_normalization() {
+    let mut test_workspace = TestWorkspace::init();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let options = SnapshotOptions {
+        path_normalization: PathNormalization {
+            unicode_nfc: true,
+            case_insensitive: true,
+        },
+        ..SnapshotOptions::empty_for_test()
+    };
+    let tree_paths = |tree: &MergedTree| tree.entries().map(|(path, _value)| path).collect_vec();
+    let nfc_path = RepoPath::from_internal_string("dir/caf\u{e9}");
+    let nfd_path = RepoPath::from_internal_string("dir/cafe\u{301}");
+    let upper_path = RepoPath::from_internal_string("Foo");
+    let lower_path = RepoPath::from_internal_string("foo");
+
+    // New files are recorded with NFC-normalized names, but case is preserved
+    std::fs::create_dir(workspace_root.join("dir")).unwrap();
+    std::fs::write(nfd_path.to_fs_path_unchecked(&workspace_root), "cafe\n").unwrap();
+    std::fs::write(upper_path.to_fs_path_unchecked(&workspace_root), "foo\n").unwrap();
+    let (tree1, _stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert_eq!(
+        tree_paths(&tree1),
+        vec![upper_path.to_owned(), nfc_path.to_owned()]
+    );
+
+    // Differently-spelled names on disk are matched with the tracked names
+    std::fs::rename(
+        nfd_path.to_fs_path_unchecked(&workspace_root),
+        nfc_path.to_fs_path_unchecked(&workspace_root),
+    )
+    .unwrap();
+    std::fs::rename(
+        upper_path.to_fs_path_unchecked(&workspace_root),
+        lower_path.to_fs_path_unchecked(&workspace_root),
+    )
+    .unwrap();
+    let (tree2, _stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert_eq!(tree2.id(), tree1.id());
+
+    // Content changes are recorded at the tracked paths
+    std::fs::write(lower_path.to_fs_path_unchecked(&workspace_root), "bar\n").unwrap();
+    let (tree3, _stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert_eq!(
+        tree_paths(&tree3),
+        vec![upper_path.to_owned(), nfc_path.to_owned()]
+    );
+    assert_ne!(tree3.id(), tree1.id());
+
+    // Entries which both exist on disk aren't merged
+    std::fs::write(upper_path.to_fs_path_unchecked(&workspace_root), "foo\n").unwrap();
+    let (tree4, _stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert_eq!(
+        tree_paths(&tree4),
+        vec![
+            upper_path.to_owned(),
+            nfc_path.to_owned(),
+            lower_path.to_owned()
+        ]
+    );
+
+    // Without normalization, the names on disk are used as is
+    std::fs::rename(
+        nfc_path.to_fs_path_unchecked(&workspace_root),
+        nfd_path.to_fs_path_unchecked(&workspace_root),
+    )
+    .unwrap();
+    let tree5 = test_workspace.snapshot().unwrap();
+    assert_eq!(
+        tree_paths(&tree5),
+        vec![
+            upper_path.to_owned(),
+            nfd_path.to_owned(),
+            lower_path.to_owned()
+        ]
+    );
+}
+
+#[test]
+fn test_tree_state_format_version() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let file_path = RepoPath::from_internal_string("file");
+    let other_path = RepoPath::from_internal_string("other");
+
+    let tree = create_tree(&repo, &[(file_path, "contents")]);
+    let commit = commit_with_tree(repo.store(), tree.id());
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    let state_path = wc.state_path().to_path_buf();
+    let tree_state_path = state_path.join("tree_state");
+    let read_proto = || {
+        let buf = std::fs::read(&tree_state_path).unwrap();
+        jj_lib::protos::working_copy::TreeState::decode(&*buf).unwrap()
+    };
+    let write_proto = |proto: &jj_lib::protos::working_copy::TreeState| {
+        std::fs::write(&tree_state_path, proto.encode_to_vec()).unwrap();
+    };
+    assert_eq!(read_proto().format_version, 1);
+
+    // Files without a version are read as before
+    let mut proto = read_proto();
+    proto.format_version = 0;
+    write_proto(&proto);
+    let reloaded_wc = LocalWorkingCopy::load(
+        repo.store().clone(),
+        workspace_root.clone(),
+        state_path.clone(),
+    );
+    assert_eq!(reloaded_wc.tree_id().unwrap(), &tree.id());
+    assert!(reloaded_wc.file_states().unwrap().contains_path(file_path));
+
+    // The file states written by a newer version are rebuilt from the tree
+    let mut proto = read_proto();
+    proto.format_version = 999;
+    proto.file_states[0].path = other_path.as_internal_file_string().to_owned();
+    write_proto(&proto);
+    let reloaded_wc = LocalWorkingCopy::load(
+        repo.store().clone(),
+        workspace_root.clone(),
+        state_path.clone(),
+    );
+    assert_eq!(reloaded_wc.tree_id().unwrap(), &tree.id());
+    assert!(reloaded_wc.file_states().unwrap().contains_path(file_path));
+    assert!(!reloaded_wc.file_states().unwrap().contains_path(other_path));
+    let mut locked_wc = reloaded_wc.start_mutation().unwrap();
+    let (tree_id, _stats) = locked_wc
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree.id());
+    locked_wc.finish(repo.op_id().clone()).unwrap();
+    assert_eq!(read_proto().format_version, 1);
+}
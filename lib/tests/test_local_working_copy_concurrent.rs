@@ -190,16 +190,16 @@ fn test_racy_checkout() {
     let tree = create_tree(repo, &[(path, "1")]);
     let commit = commit_with_tree(repo.store(), tree.id());
 
+    // The file reset at the end of each round is overwritten
+    let options = CheckoutOptions {
+        force: true,
+        ..CheckoutOptions::empty_for_test()
+    };
     let mut num_matches = 0;
     for _ in 0..100 {
         let ws = &mut test_workspace.workspace;
-        ws.check_out(
-            op_id.clone(),
-            None,
-            &commit,
-            &CheckoutOptions::empty_for_test(),
-        )
-        .unwrap();
+        ws.check_out(op_id.clone(), None, &commit, &options)
+            .unwrap();
         assert_eq!(
             std::fs::read(path.to_fs_path_unchecked(&workspace_root)).unwrap(),
             b"1".to_vec()
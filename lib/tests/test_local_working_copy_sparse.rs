@@ -21,6 +21,7 @@ use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::SnapshotOptions;
 use jj_lib::working_copy::WorkingCopy;
 use pollster::FutureExt as _;
 use testutils::commit_with_tree;
@@ -174,6 +175,103 @@ fn test_sparse_checkout() {
 }
 
 /// Test that sparse patterns are respected on commit
+#[test]
+fn test_sparse_checkout_interrupted() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let working_copy_path = test_workspace.workspace.workspace_root().to_owned();
+
+    let root_file1_path = RepoPath::from_internal_string("file1");
+    let dir1_path = RepoPath::from_internal_string("dir1");
+    let dir1_file1_path = RepoPath::from_internal_string("dir1/file1");
+    let dir2_file1_path = RepoPath::from_internal_string("dir2/file1");
+
+    let tree = create_tree(
+        repo,
+        &[
+            (root_file1_path, "contents"),
+            (dir1_file1_path, "contents"),
+            (dir2_file1_path, "contents"),
+        ],
+    );
+    let commit = commit_with_tree(repo.store(), tree.id());
+
+    test_workspace
+        .workspace
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    let ws = &mut test_workspace.workspace;
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    let journal_path = wc.state_path().join("checkout_journal");
+    let read_file = |path: &RepoPath| std::fs::read(path.to_fs_path_unchecked(&working_copy_path));
+
+    // Set sparse patterns to only dir1/ without saving the tree state, and
+    // keep one of the removed files as if the update had been interrupted
+    // halfway
+    let root_file1_disk_path = root_file1_path.to_fs_path_unchecked(&working_copy_path);
+    let backup_path = working_copy_path.join("backup");
+    std::fs::rename(&root_file1_disk_path, &backup_path).unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .set_sparse_patterns(
+            to_owned_path_vec(&[dir1_path]),
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    drop(locked_ws);
+    std::fs::rename(&backup_path, &root_file1_disk_path).unwrap();
+    assert!(journal_path.exists());
+
+    // The update is finished instead of snapshotting the restored file
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let (tree_id, _stats) = locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree.id());
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    assert!(!journal_path.exists());
+    assert_eq!(
+        ws.working_copy().sparse_patterns().unwrap(),
+        to_owned_path_vec(&[dir1_path])
+    );
+    assert!(read_file(root_file1_path).is_err());
+    assert!(read_file(dir2_file1_path).is_err());
+
+    // Interrupt setting the sparse patterns back to everything before one of
+    // the added files is written, then finish it
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .set_sparse_patterns(
+            vec![RepoPathBuf::root()],
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    drop(locked_ws);
+    std::fs::remove_file(dir2_file1_path.to_fs_path_unchecked(&working_copy_path)).unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let (tree_id, _stats) = locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree.id());
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    assert!(!journal_path.exists());
+    assert_eq!(
+        ws.working_copy().sparse_patterns().unwrap(),
+        vec![RepoPathBuf::root()]
+    );
+    assert_eq!(read_file(root_file1_path).unwrap(), b"contents");
+    assert_eq!(read_file(dir2_file1_path).unwrap(), b"contents");
+}
+
 #[test]
 fn test_sparse_commit() {
     let mut test_workspace = TestWorkspace::init();
@@ -0,0 +1,87 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::repo::Repo as _;
+use jj_lib::testing::invariants::arb_commit_dag;
+use jj_lib::testing::invariants::arb_tree_edits;
+use jj_lib::testing::invariants::check_conflict_round_trip;
+use jj_lib::testing::invariants::check_merge_associative;
+use jj_lib::testing::invariants::check_merge_commutative;
+use jj_lib::testing::invariants::check_rebase_preserves_change_ids;
+use proptest::prelude::*;
+use proptest::sample::Index;
+use testutils::user_settings;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+const BACKENDS: [TestRepoBackend; 3] = [
+    TestRepoBackend::Git,
+    TestRepoBackend::Local,
+    TestRepoBackend::Test,
+];
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_rebase_preserves_change_ids(dag in arb_commit_dag(8), rewritten: Index) {
+        for backend in BACKENDS {
+            let test_repo = TestRepo::init_with_backend(backend);
+            check_rebase_preserves_change_ids(&test_repo.repo, &dag, rewritten)?;
+        }
+    }
+
+    #[test]
+    fn test_merge_commutative(
+        base in arb_tree_edits(),
+        side1 in arb_tree_edits(),
+        side2 in arb_tree_edits(),
+    ) {
+        for backend in BACKENDS {
+            let test_repo = TestRepo::init_with_backend(backend);
+            check_merge_commutative(&test_repo.repo, &base, &side1, &side2)?;
+        }
+    }
+
+    #[test]
+    fn test_merge_associative(
+        base in arb_tree_edits(),
+        side1 in arb_tree_edits(),
+        side2 in arb_tree_edits(),
+        side3 in arb_tree_edits(),
+    ) {
+        for backend in BACKENDS {
+            let test_repo = TestRepo::init_with_backend(backend);
+            check_merge_associative(&test_repo.repo, &base, &side1, &side2, &side3)?;
+        }
+    }
+
+    #[test]
+    fn test_conflict_round_trip(
+        base in arb_tree_edits(),
+        side1 in arb_tree_edits(),
+        side2 in arb_tree_edits(),
+    ) {
+        for backend in BACKENDS {
+            let test_repo = TestRepo::init_with_backend(backend);
+            let reload_store = || {
+                let repo = test_repo
+                    .env
+                    .load_repo_at_head(&user_settings(), test_repo.repo_path());
+                repo.store().clone()
+            };
+            check_conflict_round_trip(&test_repo.repo, reload_store, &base, &side1, &side2)?;
+        }
+    }
+}
@@ -12,14 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::slice;
+
 use itertools::Itertools;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::TreeValue;
+use jj_lib::commit::Commit;
+use jj_lib::merge::ConflictKind;
 use jj_lib::merge::Merge;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::RepoPathComponent;
+use jj_lib::rewrite::merge_commit_trees;
 use jj_lib::rewrite::rebase_commit;
 use jj_lib::tree::merge_trees;
 use jj_lib::tree::Tree;
@@ -365,6 +370,45 @@ fn test_subtree_one_missing() {
     assert_eq!(reverse_merged_tree.id(), merged_tree.id());
 }
 
+#[test]
+fn test_modify_delete() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // Merging the contents with the deleted side as an empty file would resolve
+    // to "line 2\n", but the modify/delete conflict must be kept instead.
+    let path = RepoPath::from_internal_string("file");
+    let other_path = RepoPath::from_internal_string("other");
+    let base_tree = create_tree(repo, &[(path, "line 1\n"), (other_path, "")]);
+    let modified_tree = create_tree(repo, &[(path, "line 1\nline 2\n"), (other_path, "")]);
+    let deleted_tree = create_tree(repo, &[(other_path, "")]);
+
+    for (side1_tree, side2_tree) in [
+        (&modified_tree, &deleted_tree),
+        (&deleted_tree, &modified_tree),
+    ] {
+        let merged_tree = side1_tree.merge(&base_tree, side2_tree).unwrap();
+        let value = merged_tree.path_value(path).unwrap();
+        assert_eq!(
+            value,
+            Merge::from_vec(vec![
+                side1_tree
+                    .path_value(path)
+                    .unwrap()
+                    .into_resolved()
+                    .unwrap(),
+                base_tree.path_value(path).unwrap().into_resolved().unwrap(),
+                side2_tree
+                    .path_value(path)
+                    .unwrap()
+                    .into_resolved()
+                    .unwrap(),
+            ])
+        );
+        assert_eq!(value.conflict_kind(), Some(ConflictKind::ModifyDelete));
+    }
+}
+
 #[test]
 fn test_types() {
     // Tests conflicts between different types. This is mostly to test that the
@@ -745,3 +789,105 @@ fn test_rebase_on_lossy_merge() {
     .flatten();
     assert_eq!(*commit_d2.tree_id(), MergedTreeId::Merge(expected_tree_id));
 }
+
+#[test]
+fn test_merge_criss_cross() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // Test this merge:
+    // D   E    D: a=A X C, E: a=A b C Y
+    // |\ /|
+    // | X |
+    // |/ \|
+    // B   C    B: a=A b c, C: a=a b C
+    //  \ /
+    //   A      A: a=a b c
+    //
+    // D and E have two merge bases, B and C. Merging them as a single 5-way
+    // merge conflicts because no line is unchanged between all terms. The merge
+    // bases resolve cleanly to "A b C", which is used as the base instead.
+    let path = RepoPath::from_internal_string("a");
+    let mut tx = repo.start_transaction();
+    let repo_mut = tx.repo_mut();
+    let mut new_commit = |parents: &[&Commit], content: &str| {
+        repo_mut
+            .new_commit(
+                parents.iter().map(|commit| commit.id().clone()).collect(),
+                create_tree(repo, &[(path, content)]).id(),
+            )
+            .write()
+            .unwrap()
+    };
+    let commit_a = new_commit(&[&repo.store().root_commit()], "a\nb\nc\n");
+    let commit_b = new_commit(&[&commit_a], "A\nb\nc\n");
+    let commit_c = new_commit(&[&commit_a], "a\nb\nC\n");
+    let commit_d = new_commit(&[&commit_b, &commit_c], "A\nX\nC\n");
+    let commit_e = new_commit(&[&commit_c, &commit_b], "A\nb\nC\nY\n");
+
+    assert_eq!(
+        tx.repo()
+            .common_ancestors(&[commit_d.id().clone(), commit_e.id().clone()])
+            .into_iter()
+            .sorted()
+            .collect_vec(),
+        [commit_b.id().clone(), commit_c.id().clone()]
+            .into_iter()
+            .sorted()
+            .collect_vec()
+    );
+    assert_eq!(
+        tx.repo().common_ancestors(&[
+            commit_b.id().clone(),
+            commit_d.id().clone(),
+            commit_e.id().clone()
+        ]),
+        vec![commit_b.id().clone()]
+    );
+
+    let merged_tree = merge_commit_trees(tx.repo(), &[commit_d, commit_e]).unwrap();
+    let expected_tree = create_tree(repo, &[(path, "A\nX\nC\nY\n")]);
+    assert_eq!(merged_tree.id(), expected_tree.id());
+}
+
+#[test]
+fn test_merge_commit_trees() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    //   C   D    C: a=c, D: a=d b=d
+    //    \ /
+    //     B      B: a=b
+    //     |
+    //     A      A: a=a
+    let path_a = RepoPath::from_internal_string("a");
+    let path_b = RepoPath::from_internal_string("b");
+    let mut tx = repo.start_transaction();
+    let repo_mut = tx.repo_mut();
+    let mut new_commit = |parent: &Commit, contents: &[(&RepoPath, &str)]| {
+        repo_mut
+            .new_commit(vec![parent.id().clone()], create_tree(repo, contents).id())
+            .write()
+            .unwrap()
+    };
+    let commit_a = new_commit(&store.root_commit(), &[(path_a, "a\n")]);
+    let commit_b = new_commit(&commit_a, &[(path_a, "b\n")]);
+    let commit_c = new_commit(&commit_b, &[(path_a, "c\n")]);
+    let commit_d = new_commit(&commit_b, &[(path_a, "d\n"), (path_b, "d\n")]);
+
+    // No commits merge to the empty tree, and a single commit to its tree
+    let merged_tree = merge_commit_trees(tx.repo(), &[]).unwrap();
+    assert_eq!(merged_tree.id(), store.empty_merged_tree_id());
+    let merged_tree = merge_commit_trees(tx.repo(), slice::from_ref(&commit_c)).unwrap();
+    assert_eq!(merged_tree.id(), *commit_c.tree_id());
+
+    // The conflict in "a" is recorded in the tree, and "b" is resolved
+    let merged_tree = merge_commit_trees(tx.repo(), &[commit_c, commit_d]).unwrap();
+    assert!(!merged_tree.path_value(path_a).unwrap().is_resolved());
+    assert!(merged_tree.path_value(path_b).unwrap().is_resolved());
+    assert_eq!(
+        merged_tree.conflicts().map(|(path, _)| path).collect_vec(),
+        [path_a.to_owned()]
+    );
+}
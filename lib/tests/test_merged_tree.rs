@@ -24,6 +24,7 @@ use jj_lib::copies::CopyOperation;
 use jj_lib::copies::CopyRecords;
 use jj_lib::files::MergeResult;
 use jj_lib::matchers::EverythingMatcher;
+use jj_lib::matchers::FileGlobsMatcher;
 use jj_lib::matchers::FilesMatcher;
 use jj_lib::matchers::Matcher;
 use jj_lib::matchers::PrefixMatcher;
@@ -41,9 +42,13 @@ use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::RepoPathComponent;
 use pollster::FutureExt as _;
 use pretty_assertions::assert_eq;
+use test_case::test_case;
 use testutils::create_single_tree;
+use testutils::create_tree;
 use testutils::write_file;
+use testutils::write_normal_file;
 use testutils::TestRepo;
+use testutils::TestRepoBackend;
 
 fn file_value(file_id: &FileId) -> TreeValue {
     TreeValue::File {
@@ -277,6 +282,38 @@ fn test_from_legacy_tree() {
     assert_eq!(recreated_merged_id, merged_tree.id());
 }
 
+/// Test that tree ids don't depend on the order the entries were added in,
+/// including names which sort differently in Git trees ("a.txt" < "a/").
+#[test_case(TestRepoBackend::Local ; "local backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn test_tree_id_independent_of_insertion_order(backend: TestRepoBackend) {
+    let test_repo = TestRepo::init_with_backend(backend);
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    let paths = ["a/b", "a.txt", "a-b", "b", "a/a"].map(RepoPath::from_internal_string);
+    let write_tree = |paths: &[&RepoPath]| {
+        let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+        for path in paths {
+            write_normal_file(&mut tree_builder, path, path.as_internal_file_string());
+        }
+        tree_builder.write_tree().unwrap()
+    };
+    let tree_id = write_tree(&paths);
+    let reversed_paths = paths.iter().copied().rev().collect_vec();
+    assert_eq!(write_tree(&reversed_paths), tree_id);
+    let sorted_paths = paths.iter().copied().sorted().collect_vec();
+    assert_eq!(write_tree(&sorted_paths), tree_id);
+
+    // Rewriting the tree as read from the store produces the same id
+    let tree = store.get_tree(RepoPathBuf::root(), &tree_id).unwrap();
+    let new_tree_id = store
+        .write_tree(RepoPath::root(), tree.data().clone())
+        .block_on()
+        .unwrap();
+    assert_eq!(*new_tree_id.id(), tree_id);
+}
+
 /// Test that a tree built with no changes on top of an add/add conflict gets
 /// resolved.
 #[test]
@@ -429,6 +466,56 @@ fn test_path_value_and_entries() {
         .map(|&path| (path.to_owned(), merged_tree.path_value(path).unwrap()))
         .collect_vec();
     assert_eq!(actual_entries, expected_entries);
+
+    let actual_entries = merged_tree
+        .entries_matching(&PrefixMatcher::new([*resolved_dir_path, missing_path]))
+        .map(|(path, result)| (path, result.unwrap()))
+        .collect_vec();
+    let expected_entries = vec![(
+        resolved_file_path.to_owned(),
+        merged_tree.path_value(resolved_file_path).unwrap(),
+    )];
+    assert_eq!(actual_entries, expected_entries);
+
+    let glob_matcher = FileGlobsMatcher::new([(
+        RepoPath::from_internal_string("dir2"),
+        glob::Pattern::new("*ed").unwrap(),
+    )]);
+    let actual_entries = merged_tree
+        .entries_matching(&glob_matcher)
+        .map(|(path, result)| (path, result.unwrap()))
+        .collect_vec();
+    let expected_entries = vec![(
+        conflicted_file_path.to_owned(),
+        merged_tree.path_value(conflicted_file_path).unwrap(),
+    )];
+    assert_eq!(actual_entries, expected_entries);
+
+    // Test values_for_paths()
+    let paths = [
+        RepoPath::root(),
+        resolved_file_path,
+        *resolved_dir_path,
+        conflicted_file_path,
+        missing_path,
+        modify_delete_path,
+        file_dir_conflict_path,
+        file_dir_conflict_sub_path,
+    ];
+    assert_eq!(
+        merged_tree.values_for_paths(&paths).unwrap(),
+        paths
+            .iter()
+            .map(|path| merged_tree.path_value(path).unwrap())
+            .collect_vec()
+    );
+    assert_eq!(
+        tree3.values_for_paths(&paths).unwrap(),
+        paths
+            .iter()
+            .map(|path| tree3.path_value(path).unwrap())
+            .collect_vec()
+    );
 }
 
 #[test]
@@ -1567,3 +1654,47 @@ fn test_merge_simplify_file_conflict_with_absent() {
     let merged = child1_merged.merge(&parent_merged, &child2_merged).unwrap();
     assert_eq!(merged, expected_merged);
 }
+
+/// Merge a tree where a file was renamed with a tree where it was modified
+#[test]
+fn test_merge_with_renames() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old_path = RepoPath::from_internal_string("dir/old");
+    let new_path = RepoPath::from_internal_string("new");
+    let other_new_path = RepoPath::from_internal_string("other_new");
+    let other_path = RepoPath::from_internal_string("other");
+    let base = create_tree(repo, &[(old_path, "1\n2\n"), (other_path, "base")]);
+    let renamed = create_tree(repo, &[(new_path, "1\n2\n"), (other_path, "base")]);
+    let modified = create_tree(repo, &[(old_path, "1\n2\n3\n"), (other_path, "modified")]);
+    let expected = create_tree(repo, &[(new_path, "1\n2\n3\n"), (other_path, "modified")]);
+
+    // The modification follows the rename regardless of the side it's on
+    let merged = renamed.merge_with_renames(&base, &modified).unwrap();
+    assert_eq!(merged, expected);
+    let merged = modified.merge_with_renames(&base, &renamed).unwrap();
+    assert_eq!(merged, expected);
+
+    // Without following the rename, it's a modify/delete conflict
+    let merged = renamed.merge(&base, &modified).unwrap();
+    assert!(!merged.path_value(old_path).unwrap().is_resolved());
+    assert!(merged.path_value(new_path).unwrap().is_present());
+
+    // If both sides renamed the file, the renames aren't followed
+    let renamed2 = create_tree(repo, &[(other_new_path, "1\n2\n"), (other_path, "base")]);
+    assert_eq!(
+        renamed.merge_with_renames(&base, &renamed2).unwrap(),
+        renamed.merge(&base, &renamed2).unwrap()
+    );
+
+    // If the contents don't identify the source uniquely, the rename isn't
+    // followed
+    let base = create_tree(repo, &[(old_path, "1\n2\n"), (other_path, "1\n2\n")]);
+    let renamed = create_tree(repo, &[(new_path, "1\n2\n")]);
+    let modified = create_tree(repo, &[(old_path, "1\n2\n3\n"), (other_path, "1\n2\n")]);
+    assert_eq!(
+        renamed.merge_with_renames(&base, &modified).unwrap(),
+        renamed.merge(&base, &modified).unwrap()
+    );
+}
@@ -73,6 +73,43 @@ fn test_checkout() {
     assert_eq!(repo.view().get_wc_commit_id(&ws_id), Some(wc_commit.id()));
 }
 
+#[test]
+fn test_check_out_new_commit() {
+    // Test that MutableRepo::check_out_new_commit() creates a commit with the
+    // given parents and tree, and abandons the previous empty working copy
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let ws_id = WorkspaceId::default();
+    let parent1 = write_random_commit(tx.repo_mut());
+    let parent2 = write_random_commit(tx.repo_mut());
+    let old_wc_commit = tx.repo_mut().check_out(ws_id.clone(), &parent1).unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let mut tx = repo.start_transaction();
+    let tree = create_random_tree(&repo);
+    let wc_commit = tx
+        .repo_mut()
+        .check_out_new_commit(
+            ws_id.clone(),
+            vec![parent1.id().clone(), parent2.id().clone()],
+            tree.clone(),
+        )
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    assert_eq!(wc_commit.tree_id(), &tree);
+    assert_eq!(
+        wc_commit.parent_ids(),
+        [parent1.id().clone(), parent2.id().clone()]
+    );
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(repo.view().get_wc_commit_id(&ws_id), Some(wc_commit.id()));
+    // The old working-copy commit was abandoned
+    assert_ne!(old_wc_commit.id(), wc_commit.id());
+    assert_eq!(*repo.view().heads(), hashset! {wc_commit.id().clone()});
+}
+
 #[test]
 fn test_edit_previous_not_empty() {
     // Test that MutableRepo::edit() does not usually abandon the previous
@@ -0,0 +1,261 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+use assert_matches::assert_matches;
+use jj_lib::backend::CommitId;
+use jj_lib::backend::MillisSinceEpoch;
+use jj_lib::backend::Timestamp;
+use jj_lib::local_backend::LocalBackend;
+use jj_lib::merge::Merge;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store::BisectState;
+use jj_lib::op_store::PendingRewrite;
+use jj_lib::op_store::RefTarget;
+use jj_lib::op_store::RemoteConfig;
+use jj_lib::op_store::RemoteRef;
+use jj_lib::op_store::RemoteRefState;
+use jj_lib::op_store::RemoteView;
+use jj_lib::op_store::StoppedRebase;
+use jj_lib::op_store::View;
+use jj_lib::op_store::WorkspaceId;
+use jj_lib::op_store_json::operation_from_json;
+use jj_lib::op_store_json::operation_to_json;
+use jj_lib::op_store_json::view_from_json;
+use jj_lib::op_store_json::view_to_json;
+use jj_lib::op_store_json::JsonDecodeError;
+use jj_lib::op_store_json::JsonOpStoreEncoding;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo::RepoLoader;
+use jj_lib::repo::StoreFactories;
+use jj_lib::signing::Signer;
+use maplit::btreemap;
+use maplit::hashset;
+use testutils::write_random_commit;
+use testutils::TestRepo;
+
+#[test]
+fn test_view_json() {
+    let id = |hex: &'static str| CommitId::from_hex(hex);
+    let view = View {
+        head_ids: hashset! {id("bbbb"), id("aaaa")},
+        local_bookmarks: btreemap! {
+            "main".to_owned() => RefTarget::normal(id("aaaa")),
+            "conflicted".to_owned() => RefTarget::from_merge(Merge::from_vec(vec![
+                Some(id("aaaa")),
+                None,
+                Some(id("bbbb")),
+            ])),
+        },
+        tags: btreemap! {
+            "v1".to_owned() => RefTarget::normal(id("aaaa")),
+        },
+        remote_views: btreemap! {
+            "origin".to_owned() => RemoteView {
+                bookmarks: btreemap! {
+                    "main".to_owned() => RemoteRef {
+                        target: RefTarget::normal(id("bbbb")),
+                        state: RemoteRefState::Tracking,
+                    },
+                },
+            },
+        },
+        git_refs: btreemap! {},
+        git_head: RefTarget::absent(),
+        wc_commit_ids: btreemap! {
+            WorkspaceId::default() => id("aaaa"),
+        },
+        anonymous_heads: btreemap! {},
+        bisect_state: BisectState::default(),
+        remotes: btreemap! {},
+    };
+    let text = view_to_json(&view);
+    insta::assert_snapshot!(text, @r#"
+    {
+      "head_ids": [
+        "aaaa",
+        "bbbb"
+      ],
+      "local_bookmarks": {
+        "conflicted": [
+          "aaaa",
+          null,
+          "bbbb"
+        ],
+        "main": [
+          "aaaa"
+        ]
+      },
+      "tags": {
+        "v1": [
+          "aaaa"
+        ]
+      },
+      "remote_views": {
+        "origin": {
+          "main": {
+            "target": [
+              "bbbb"
+            ],
+            "state": "tracking"
+          }
+        }
+      },
+      "git_refs": {},
+      "git_head": [
+        null
+      ],
+      "wc_commit_ids": {
+        "default": "aaaa"
+      },
+      "anonymous_heads": {}
+    }
+    "#);
+    assert_eq!(view_from_json(&text).unwrap(), view);
+
+    // The bisection state is only included while a bisection is ongoing
+    let view = View {
+        bisect_state: BisectState {
+            good_commits: vec![id("aaaa")],
+            bad_commits: vec![id("bbbb")],
+            skipped_commits: vec![],
+        },
+        ..view
+    };
+    let text = view_to_json(&view);
+    assert!(text.contains(r#""bisect_state": {"#));
+    assert_eq!(view_from_json(&text).unwrap(), view);
+
+    // Remotes are only included if there are any
+    let view = View {
+        remotes: btreemap! {
+            "origin".to_owned() => RemoteConfig {
+                url: "https://example.com/repo.git".to_owned(),
+            },
+        },
+        ..view
+    };
+    let text = view_to_json(&view);
+    assert!(text.contains(r#""remotes": {"#));
+    assert_eq!(view_from_json(&text).unwrap(), view);
+}
+
+#[test]
+fn test_operation_json_round_trip() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    tx.repo_mut()
+        .set_local_bookmark_target("main", RefTarget::normal(commit.id().clone()));
+    tx.set_tag("tag".to_owned(), "value".to_owned());
+    tx.set_working_copy_tree_id(commit.tree_id());
+    tx.set_includes_snapshot(true);
+    tx.set_stopped_rebase(Some(StoppedRebase {
+        conflicted_commit_id: commit.id().clone(),
+        rewrites: btreemap! {
+            commit.id().clone() => PendingRewrite::Abandoned(vec![repo.store().root_commit_id().clone()]),
+        },
+    }));
+    let repo = tx.commit("test").unwrap();
+
+    let operation = repo.operation().store_operation();
+    let text = operation_to_json(operation);
+    assert_eq!(&operation_from_json(&text).unwrap(), operation);
+
+    // Timestamps keep their milliseconds and time zone offset
+    let mut operation = operation.clone();
+    operation.metadata.start_time = Timestamp {
+        timestamp: MillisSinceEpoch(981_173_106_789),
+        tz_offset: -570,
+    };
+    let text = operation_to_json(&operation);
+    assert!(text.contains(r#""start_time": "2001-02-02T18:35:06.789-09:30""#));
+    assert_eq!(operation_from_json(&text).unwrap(), operation);
+
+    let view = repo.view().store_view();
+    let text = view_to_json(view);
+    assert_eq!(&view_from_json(&text).unwrap(), view);
+}
+
+#[test]
+fn test_json_decode_error() {
+    assert_matches!(view_from_json("{"), Err(JsonDecodeError::Json(_)));
+    let text = view_to_json(&View::empty()).replace(
+        r#""git_head": [
+    null
+  ]"#,
+        r#""git_head": []"#,
+    );
+    assert_matches!(
+        view_from_json(&text),
+        Err(JsonDecodeError::InvalidRefTarget(0))
+    );
+    let mut view = View::empty();
+    view.head_ids.insert(CommitId::from_hex("aaaa"));
+    let text = view_to_json(&view).replace("aaaa", "xyz");
+    assert_matches!(
+        view_from_json(&text),
+        Err(JsonDecodeError::InvalidId { kind: "commit", value }) if value == "xyz"
+    );
+}
+
+#[test]
+fn test_json_op_store_init_and_reload() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo = ReadonlyRepo::init(
+        &settings,
+        temp_dir.path(),
+        &|_settings, store_path| Ok(Box::new(LocalBackend::init(store_path))),
+        Signer::from_settings(&settings).unwrap(),
+        ReadonlyRepo::json_op_store_initializer(),
+        ReadonlyRepo::default_op_heads_store_initializer(),
+        ReadonlyRepo::default_index_store_initializer(),
+        ReadonlyRepo::default_submodule_store_initializer(),
+    )
+    .unwrap();
+    assert_eq!(repo.op_store().name(), JsonOpStoreEncoding::store_name());
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    // Objects are stored as JSON
+    let op_path = temp_dir
+        .path()
+        .join("op_store")
+        .join("operations")
+        .join(repo.op_id().hex());
+    let op_json = fs::read_to_string(op_path).unwrap();
+    assert_eq!(
+        operation_from_json(&op_json).unwrap(),
+        *repo.operation().store_operation()
+    );
+
+    let loaded_repo =
+        RepoLoader::init_from_file_system(&settings, temp_dir.path(), &StoreFactories::default())
+            .unwrap()
+            .load_at_head()
+            .unwrap();
+    assert_eq!(
+        loaded_repo.op_store().name(),
+        JsonOpStoreEncoding::store_name()
+    );
+    assert_eq!(loaded_repo.op_id(), repo.op_id());
+    assert!(loaded_repo.view().heads().contains(commit.id()));
+}
@@ -23,14 +23,21 @@ use jj_lib::backend::CommitId;
 use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigSource;
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_export::OperationEvent;
+use jj_lib::op_export::ViewDiffSummary;
+use jj_lib::op_store::RefTarget;
+use jj_lib::op_store::WorkspaceId;
 use jj_lib::op_walk;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
 use jj_lib::operation::Operation;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::settings::UserSettings;
+use maplit::hashset;
 use testutils::create_random_commit;
+use testutils::create_tree;
 use testutils::write_random_commit;
 use testutils::TestRepo;
 
@@ -54,7 +61,7 @@ fn test_unpublished_operation() {
 
     let mut tx1 = repo.start_transaction();
     write_random_commit(tx1.repo_mut());
-    let unpublished_op = tx1.write("transaction 1");
+    let unpublished_op = tx1.write("transaction 1").unwrap();
     let op_id1 = unpublished_op.operation().id().clone();
     assert_ne!(op_id1, op_id0);
     assert_eq!(list_dir(&op_heads_dir), vec![op_id0.hex()]);
@@ -681,3 +688,236 @@ fn test_gc() {
     assert_eq!(expected_op_entries.len(), 1);
     assert_eq!(expected_view_entries.len(), 1);
 }
+
+#[test]
+fn test_operation_event() {
+    let test_repo = TestRepo::init();
+    let repo = test_repo.repo;
+    let base_op_id = repo.op_id().clone();
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    tx.repo_mut()
+        .set_local_bookmark_target("main", RefTarget::normal(commit.id().clone()));
+    tx.set_tag("args".to_owned(), "jj new".to_owned());
+    let repo = tx.commit("create commit").unwrap();
+
+    let event = OperationEvent::from_operation(repo.operation()).unwrap();
+    assert_eq!(event.id, *repo.op_id());
+    assert_eq!(event.operation.parents, vec![base_op_id.clone()]);
+    assert_eq!(event.operation.metadata.description, "create commit");
+    assert_eq!(event.operation.metadata.tags["args"], "jj new");
+    assert_eq!(
+        event.view_diff,
+        ViewDiffSummary {
+            num_added_heads: 1,
+            num_removed_heads: 1,
+            changed_bookmarks: ["main".to_owned()].into(),
+        }
+    );
+
+    // The JSON has the same schema as the serialized operation, plus the view
+    // diff
+    let json = event.to_json();
+    let mut op_json = serde_json::to_value(repo.operation()).unwrap();
+    op_json["view_diff"] = json["view_diff"].clone();
+    assert_eq!(json, op_json);
+    assert_eq!(json["id"], repo.op_id().hex());
+    assert_eq!(json["view_id"], repo.operation().view_id().hex());
+    assert_eq!(json["parents"], serde_json::json!([base_op_id.hex()]));
+    assert_eq!(
+        json["metadata"]["tags"],
+        serde_json::json!({"args": "jj new"})
+    );
+    assert_eq!(
+        json["view_diff"],
+        serde_json::json!({
+            "num_added_heads": 1,
+            "num_removed_heads": 1,
+            "changed_bookmarks": ["main"],
+        })
+    );
+    assert_eq!(
+        json["metadata"]["end_time"],
+        serde_json::to_value(event.operation.metadata.end_time).unwrap()
+    );
+
+    // The root operation has no parent to compare with
+    let root_op = repo.loader().root_operation();
+    let event = OperationEvent::from_operation(&root_op).unwrap();
+    assert_eq!(event.view_diff, ViewDiffSummary::default());
+}
+
+#[test]
+fn test_operation_working_copy_snapshot_metadata() {
+    let test_repo = TestRepo::init();
+    let repo = test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    tx.set_is_snapshot(true);
+    tx.set_working_copy_tree_id(commit.tree_id());
+    let snapshot_repo = tx.commit("snapshot working copy").unwrap();
+
+    let mut tx = snapshot_repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    tx.set_working_copy_tree_id(commit.tree_id());
+    tx.set_includes_snapshot(true);
+    let command_repo = tx.commit("create commit").unwrap();
+
+    let mut tx = command_repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let plain_repo = tx.commit("create another commit").unwrap();
+
+    // Metadata is read back from the store
+    let loader = repo.loader();
+    let load_metadata = |repo: &ReadonlyRepo| {
+        loader
+            .load_operation(repo.op_id())
+            .unwrap()
+            .metadata()
+            .clone()
+    };
+    let metadata = load_metadata(&snapshot_repo);
+    assert!(metadata.is_snapshot);
+    assert_eq!(
+        metadata.working_copy_tree_ids,
+        Some(commit.tree_id().to_merge())
+    );
+    assert!(!metadata.includes_snapshot);
+    let metadata = load_metadata(&command_repo);
+    assert!(!metadata.is_snapshot);
+    assert_eq!(
+        metadata.working_copy_tree_ids,
+        Some(commit.tree_id().to_merge())
+    );
+    assert!(metadata.includes_snapshot);
+    let metadata = load_metadata(&plain_repo);
+    assert_eq!(metadata.working_copy_tree_ids, None);
+    assert!(!metadata.includes_snapshot);
+
+    let event = OperationEvent::from_operation(command_repo.operation()).unwrap();
+    let json = event.to_json();
+    assert_eq!(json["metadata"]["includes_snapshot"], true);
+    assert_eq!(
+        json["metadata"]["working_copy_tree_ids"],
+        serde_json::json!([commit.tree_id().to_merge().first().hex()])
+    );
+}
+
+#[test]
+fn test_restore_view_preserving_working_copies() {
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+    let ws_id = WorkspaceId::default();
+    let root_commit_id = repo_0.store().root_commit_id().clone();
+    let path_a = RepoPath::from_internal_string("a");
+    let path_b = RepoPath::from_internal_string("b");
+    let path_wc = RepoPath::from_internal_string("wc");
+    let path_new = RepoPath::from_internal_string("new");
+
+    // Working-copy commit C on top of A
+    let mut tx = repo_0.start_transaction();
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(
+            vec![root_commit_id.clone()],
+            create_tree(&repo_0, &[(path_a, "a")]).id(),
+        )
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(
+            vec![root_commit_id.clone()],
+            create_tree(&repo_0, &[(path_b, "b")]).id(),
+        )
+        .write()
+        .unwrap();
+    let commit_c = tx
+        .repo_mut()
+        .new_commit(
+            vec![commit_a.id().clone()],
+            create_tree(&repo_0, &[(path_a, "a"), (path_wc, "wc")]).id(),
+        )
+        .write()
+        .unwrap();
+    tx.repo_mut()
+        .set_wc_commit(ws_id.clone(), commit_c.id().clone())
+        .unwrap();
+    let repo_1 = tx.commit("create commits").unwrap();
+
+    // Bad rebase of C onto B
+    let mut tx = repo_1.start_transaction();
+    tx.repo_mut()
+        .rewrite_commit(&commit_c)
+        .set_parents(vec![commit_b.id().clone()])
+        .set_tree_id(create_tree(&repo_1, &[(path_b, "b"), (path_wc, "wc")]).id())
+        .write()
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo_2 = tx.commit("rebase").unwrap();
+
+    // More changes in the working copy
+    let mut tx = repo_2.start_transaction();
+    let commit_c2 = repo_2
+        .store()
+        .get_commit(repo_2.view().get_wc_commit_id(&ws_id).unwrap())
+        .unwrap();
+    tx.repo_mut()
+        .rewrite_commit(&commit_c2)
+        .set_tree_id(
+            create_tree(
+                &repo_2,
+                &[(path_b, "b"), (path_wc, "wc"), (path_new, "new")],
+            )
+            .id(),
+        )
+        .set_description("described later")
+        .write()
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo_3 = tx.commit("snapshot working copy").unwrap();
+
+    // Undo the rebase, keeping the changes made since. The view merge keeps
+    // the current working-copy commit, so the old one is restored explicitly.
+    let mut tx = repo_3.start_transaction();
+    tx.repo_mut().merge(&repo_2, &repo_1).unwrap();
+    let mut view = tx.repo().view().store_view().clone();
+    view.wc_commit_ids
+        .insert(ws_id.clone(), commit_c.id().clone());
+    tx.restore_view_preserving_working_copies(view, repo_2.view().store_view())
+        .unwrap();
+    let repo_4 = tx.commit("undo rebase").unwrap();
+    let wc_commit = repo_4
+        .store()
+        .get_commit(repo_4.view().get_wc_commit_id(&ws_id).unwrap())
+        .unwrap();
+    assert_eq!(wc_commit.change_id(), commit_c.change_id());
+    assert_eq!(wc_commit.parent_ids(), [commit_a.id().clone()]);
+    assert_eq!(wc_commit.description(), "described later");
+    assert_eq!(
+        *wc_commit.tree_id(),
+        create_tree(
+            &repo_4,
+            &[(path_a, "a"), (path_wc, "wc"), (path_new, "new")]
+        )
+        .id()
+    );
+    assert_eq!(
+        *repo_4.view().heads(),
+        hashset! {commit_b.id().clone(), wc_commit.id().clone()}
+    );
+
+    // Undoing the snapshot itself reverts the changes
+    let mut tx = repo_3.start_transaction();
+    tx.repo_mut().merge(&repo_3, &repo_2).unwrap();
+    let view = tx.repo().view().store_view().clone();
+    tx.restore_view_preserving_working_copies(view, repo_3.view().store_view())
+        .unwrap();
+    let repo_5 = tx.commit("undo snapshot").unwrap();
+    assert_eq!(
+        repo_5.view().get_wc_commit_id(&ws_id),
+        repo_2.view().get_wc_commit_id(&ws_id)
+    );
+}
@@ -0,0 +1,701 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use indoc::indoc;
+use itertools::Itertools as _;
+use jj_lib::backend::MillisSinceEpoch;
+use jj_lib::backend::Signature;
+use jj_lib::backend::Timestamp;
+use jj_lib::backend::TreeValue;
+use jj_lib::commit::Commit;
+use jj_lib::merge::Merge;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::merged_tree::MergedTreeBuilder;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::patch::apply;
+use jj_lib::patch::format_mbox;
+use jj_lib::patch::format_patches;
+use jj_lib::patch::ApplyPatchConflict;
+use jj_lib::patch::ApplyPatchConflictReason;
+use jj_lib::patch::ApplyPatchResult;
+use jj_lib::patch::FormatPatchOptions;
+use jj_lib::patch::PatchError;
+use jj_lib::repo::MutableRepo;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::revset::ResolvedRevsetExpression;
+use jj_lib::revset::RevsetExpression;
+use pollster::FutureExt as _;
+use testutils::create_tree;
+use testutils::read_file;
+use testutils::TestRepo;
+
+fn signature(name: &str) -> Signature {
+    Signature {
+        name: name.to_owned(),
+        email: "someone@example.com".to_owned(),
+        timestamp: Timestamp {
+            timestamp: MillisSinceEpoch(1_700_000_000_000),
+            tz_offset: 60,
+        },
+    }
+}
+
+fn write_commit(
+    mut_repo: &mut MutableRepo,
+    parents: &[&Commit],
+    files: &[(&RepoPath, &str)],
+    description: &str,
+) -> Commit {
+    let tree_id = create_tree(mut_repo.base_repo(), files).id();
+    let parent_ids = parents.iter().map(|commit| commit.id().clone()).collect();
+    mut_repo
+        .new_commit(parent_ids, tree_id)
+        .set_description(description)
+        .set_author(signature("Some One"))
+        .set_committer(signature("Some One"))
+        .write()
+        .unwrap()
+}
+
+fn options() -> FormatPatchOptions {
+    FormatPatchOptions {
+        signature: Some("jj test".to_owned()),
+        ..Default::default()
+    }
+}
+
+fn commits_expression(commits: &[&Commit]) -> Rc<ResolvedRevsetExpression> {
+    RevsetExpression::commits(commits.iter().map(|commit| commit.id().clone()).collect())
+}
+
+#[test]
+fn test_format_patches_series() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file1 = RepoPath::from_internal_string("file1");
+    let file2 = RepoPath::from_internal_string("file2");
+    let mut tx = repo.start_transaction();
+    let commit_a = write_commit(
+        tx.repo_mut(),
+        &[&repo.store().root_commit()],
+        &[(file1, "1\n2\n3\n")],
+        "base\n",
+    );
+    let commit_b = write_commit(
+        tx.repo_mut(),
+        &[&commit_a],
+        &[(file1, "1\nb\n3\n"), (file2, "new\n")],
+        "first: change file1\n\nAdd file2 too.\n",
+    );
+    let commit_c = write_commit(
+        tx.repo_mut(),
+        &[&commit_b],
+        &[(file1, "1\nb\n3\n")],
+        "second (remove file2)\n",
+    );
+
+    // Patches are in topological order regardless of the input order
+    let expression = commits_expression(&[&commit_c, &commit_b]);
+    let patches = format_patches(tx.repo(), &expression, &options()).unwrap();
+    assert_eq!(
+        patches
+            .iter()
+            .map(|patch| (&patch.commit_id, patch.file_name.as_str()))
+            .collect_vec(),
+        vec![
+            (commit_b.id(), "0001-first-change-file1.patch"),
+            (commit_c.id(), "0002-second-remove-file2.patch"),
+        ]
+    );
+    let texts = patches
+        .iter()
+        .map(|patch| String::from_utf8(patch.text.clone()).unwrap())
+        .collect_vec();
+    let texts = texts
+        .iter()
+        .map(|text| text.replace(&commit_b.id().hex(), "<B>"))
+        .map(|text| text.replace(&commit_c.id().hex(), "<C>"))
+        .map(|text| text.replace(&commit_a.id().hex(), "<A>"))
+        .collect_vec();
+    insta::assert_snapshot!(texts[0], @r"
+        From <B> Mon Sep 17 00:00:00 2001
+        From: Some One <someone@example.com>
+        Date: Tue, 14 Nov 2023 23:13:20 +0100
+        Subject: [PATCH 1/2] first: change file1
+
+        Add file2 too.
+        ---
+        diff --git a/file1 b/file1
+        index 2247ac019d..fcaa984cd8 100644
+        --- a/file1
+        +++ b/file1
+        @@ -1,3 +1,3 @@
+         1
+        -2
+        +b
+         3
+        diff --git a/file2 b/file2
+        new file mode 100644
+        index 0000000000..c2bcc6eb15
+        --- /dev/null
+        +++ b/file2
+        @@ -0,0 +1,1 @@
+        +new
+
+        base-commit: <A>
+        -- 
+        jj test
+    ");
+    insta::assert_snapshot!(texts[1], @r"
+        From <C> Mon Sep 17 00:00:00 2001
+        From: Some One <someone@example.com>
+        Date: Tue, 14 Nov 2023 23:13:20 +0100
+        Subject: [PATCH 2/2] second (remove file2)
+
+        ---
+        diff --git a/file2 b/file2
+        deleted file mode 100644
+        index c2bcc6eb15..0000000000
+        --- a/file2
+        +++ /dev/null
+        @@ -1,1 +0,0 @@
+        -new
+        -- 
+        jj test
+    ");
+
+    // The mbox is the concatenation of the patches
+    let mbox = format_mbox(tx.repo(), &expression, &options()).unwrap();
+    assert_eq!(
+        mbox,
+        patches
+            .iter()
+            .flat_map(|patch| &patch.text)
+            .copied()
+            .collect_vec()
+    );
+}
+
+#[test]
+fn test_format_patches_single() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file = RepoPath::from_internal_string("dir/file");
+    let mut tx = repo.start_transaction();
+    let tree_id = create_tree(repo, &[(file, "no newline")]).id();
+    let commit = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_id)
+        .set_description("Añadir un archivo\n")
+        .set_author(signature("Jöran"))
+        .set_committer(signature("Jöran"))
+        .write()
+        .unwrap();
+
+    // No base-commit is recorded for commits based on the root commit
+    let options = FormatPatchOptions {
+        subject_prefix: "RFC".to_owned(),
+        signature: None,
+        ..Default::default()
+    };
+    let patches = format_patches(tx.repo(), &commits_expression(&[&commit]), &options).unwrap();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].file_name, "0001-A-adir-un-archivo.patch");
+    let text = String::from_utf8(patches[0].text.clone()).unwrap();
+    let text = text.replace(&commit.id().hex(), "<COMMIT>");
+    insta::assert_snapshot!(text, @r"
+        From <COMMIT> Mon Sep 17 00:00:00 2001
+        From: =?UTF-8?q?J=C3=B6ran?= <someone@example.com>
+        Date: Tue, 14 Nov 2023 23:13:20 +0100
+        Subject: [RFC] =?UTF-8?q?A=C3=B1adir_un_archivo?=
+
+        ---
+        diff --git a/dir/file b/dir/file
+        new file mode 100644
+        index 0000000000..03eeb79db8
+        --- /dev/null
+        +++ b/dir/file
+        @@ -0,0 +1,1 @@
+        +no newline
+        \ No newline at end of file
+    ");
+}
+
+#[test]
+fn test_format_patches_merge() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file = RepoPath::from_internal_string("file");
+    let mut tx = repo.start_transaction();
+    let root_commit = repo.store().root_commit();
+    let commit_a = write_commit(tx.repo_mut(), &[&root_commit], &[(file, "a\n")], "a\n");
+    let commit_b = write_commit(tx.repo_mut(), &[&root_commit], &[(file, "b\n")], "b\n");
+    let commit_c = write_commit(
+        tx.repo_mut(),
+        &[&commit_a, &commit_b],
+        &[(file, "c\n")],
+        "merge\n",
+    );
+
+    let result = format_patches(
+        tx.repo(),
+        &commits_expression(&[&commit_a, &commit_c]),
+        &options(),
+    );
+    assert_matches!(result, Err(PatchError::MergeCommit(id)) if id == *commit_c.id());
+    // Patches of the parents are still fine
+    let patches = format_patches(
+        tx.repo(),
+        &commits_expression(&[&commit_a, &commit_b]),
+        &options(),
+    )
+    .unwrap();
+    assert_eq!(patches.len(), 2);
+}
+
+/// Writes a tree of regular files with arbitrary contents.
+fn create_binary_tree(
+    repo: &Arc<ReadonlyRepo>,
+    path_contents: &[(&RepoPath, &[u8])],
+) -> MergedTree {
+    let store = repo.store();
+    let mut tree_builder = MergedTreeBuilder::new(store.empty_merged_tree_id());
+    for (path, contents) in path_contents {
+        let id = store
+            .write_file(path, &mut &contents[..])
+            .block_on()
+            .unwrap();
+        let value = TreeValue::File {
+            id,
+            executable: false,
+        };
+        tree_builder.set_or_remove((*path).to_owned(), Merge::normal(value));
+    }
+    let tree_id = tree_builder.write_tree(store).unwrap();
+    store.get_root_tree(&tree_id).unwrap()
+}
+
+fn apply_to_tree(repo: &Arc<ReadonlyRepo>, tree: &MergedTree, patch_text: &str) -> MergedTree {
+    match apply(repo.as_ref(), tree, patch_text.as_bytes()).unwrap() {
+        ApplyPatchResult::Applied(tree_id) => repo.store().get_root_tree(&tree_id).unwrap(),
+        ApplyPatchResult::Conflicts(conflicts) => panic!("unexpected conflicts: {conflicts:?}"),
+    }
+}
+
+/// Returns the contents and the executable bit of the file at `path`.
+fn file_value(
+    repo: &Arc<ReadonlyRepo>,
+    tree: &MergedTree,
+    path: &RepoPath,
+) -> Option<(Vec<u8>, bool)> {
+    match tree.path_value(path).unwrap().into_resolved().unwrap() {
+        Some(TreeValue::File { id, executable }) => {
+            Some((read_file(repo.store(), path, &id), executable))
+        }
+        None => None,
+        value => panic!("unexpected value {value:?}"),
+    }
+}
+
+#[test]
+fn test_apply_format_patches_round_trip() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file1 = RepoPath::from_internal_string("file1");
+    let file2 = RepoPath::from_internal_string("dir/file2");
+    let file3 = RepoPath::from_internal_string("file3");
+    let mut tx = repo.start_transaction();
+    let commit_a = write_commit(
+        tx.repo_mut(),
+        &[&repo.store().root_commit()],
+        &[(file1, "1\n2\n3\n4\n5\n6\n7\n8\n9\n"), (file2, "a\n")],
+        "base\n",
+    );
+    let commit_b = write_commit(
+        tx.repo_mut(),
+        &[&commit_a],
+        &[
+            (file1, "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n"),
+            (file3, "no newline"),
+        ],
+        "b\n",
+    );
+    let commit_c = write_commit(
+        tx.repo_mut(),
+        &[&commit_b],
+        &[
+            (file1, "0\n1\n2\n3\n4\n5\n6\n7\n8\nnine\n"),
+            (file3, "newline\n"),
+        ],
+        "c\n",
+    );
+
+    // The whole series applies on top of the base commit
+    let expression = commits_expression(&[&commit_b, &commit_c]);
+    let mbox = format_mbox(tx.repo(), &expression, &options()).unwrap();
+    let result = apply(tx.repo(), &commit_a.tree().unwrap(), &mbox).unwrap();
+    assert_eq!(
+        result,
+        ApplyPatchResult::Applied(commit_c.tree_id().clone())
+    );
+}
+
+#[test]
+fn test_apply_format_patches_binary_round_trip() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let modified_path = RepoPath::from_internal_string("modified");
+    let added_path = RepoPath::from_internal_string("added");
+    let large_contents = (0..600).map(|i| (i * 7 % 256) as u8).collect_vec();
+    let base_tree = create_binary_tree(repo, &[(modified_path, b"abc\0def")]);
+    let tree = create_binary_tree(
+        repo,
+        &[(modified_path, b"abc\0dXf"), (added_path, &large_contents)],
+    );
+    let mut tx = repo.start_transaction();
+    let commit_base = tx
+        .repo_mut()
+        .new_commit(
+            vec![repo.store().root_commit_id().clone()],
+            base_tree.id().clone(),
+        )
+        .write()
+        .unwrap();
+    let commit = tx
+        .repo_mut()
+        .new_commit(vec![commit_base.id().clone()], tree.id().clone())
+        .set_description("binary\n\nFrom the start of a line.\n")
+        .set_author(signature("Some One"))
+        .write()
+        .unwrap();
+
+    let expression = commits_expression(&[&commit]);
+    let mbox = format_mbox(tx.repo(), &expression, &options()).unwrap();
+    let text = String::from_utf8(mbox.clone()).unwrap();
+    assert!(text.contains("\n>From the start of a line.\n"));
+    assert_eq!(text.matches("GIT binary patch\n").count(), 2);
+    assert!(!text.contains("Binary files"));
+    let result = apply(tx.repo(), &base_tree, &mbox).unwrap();
+    assert_eq!(result, ApplyPatchResult::Applied(tree.id().clone()));
+}
+
+#[test]
+fn test_apply_text_hunks() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let path = RepoPath::from_internal_string("file");
+    let tree = create_tree(repo, &[(path, "a\nb\nc\nd\ne\nf\n")]);
+
+    // Hunks are found at an offset, and plain unified diffs with timestamps
+    // and without the a/ b/ prefixes are accepted
+    let patch = indoc! {"
+        Some explanation.
+
+        --- file\t2025-01-01 00:00:00.000000000 +0000
+        +++ file\t2025-01-02 00:00:00.000000000 +0000
+        @@ -1,2 +1,2 @@
+        -a
+        +A
+         b
+        @@ -6,2 +6,3 @@
+         d
+        +D
+         e
+    "};
+    let new_tree = apply_to_tree(repo, &tree, patch);
+    assert_eq!(
+        file_value(repo, &new_tree, path),
+        Some((b"A\nb\nc\nd\nD\ne\nf\n".to_vec(), false))
+    );
+
+    // Missing newline at end of file
+    let patch = indoc! {r"
+        diff --git a/file b/file
+        --- a/file
+        +++ b/file
+        @@ -5,2 +5,2 @@
+         e
+        -f
+        +F
+        \ No newline at end of file
+    "};
+    let new_tree = apply_to_tree(repo, &tree, patch);
+    assert_eq!(
+        file_value(repo, &new_tree, path),
+        Some((b"a\nb\nc\nd\ne\nF".to_vec(), false))
+    );
+}
+
+#[test]
+fn test_apply_git_extended_headers() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("dir/new");
+    let copied_path = RepoPath::from_internal_string("copied");
+    let deleted_path = RepoPath::from_internal_string("deleted");
+    let added_path = RepoPath::from_internal_string("added file");
+    let tree = create_tree(repo, &[(old_path, "1\n2\n3\n"), (deleted_path, "gone\n")]);
+
+    let patch = indoc! {r#"
+        diff --git a/old "b/copied"
+        similarity index 100%
+        copy from old
+        copy to copied
+        diff --git a/old b/dir/new
+        old mode 100644
+        new mode 100755
+        similarity index 66%
+        rename from old
+        rename to dir/new
+        index 01e79c32a8..6ebb5d9a6e
+        --- a/old
+        +++ b/dir/new
+        @@ -1,3 +1,3 @@
+         1
+        -2
+        +two
+         3
+        diff --git a/deleted b/deleted
+        deleted file mode 100644
+        index 7e3c8a3c4c..0000000000
+        --- a/deleted
+        +++ /dev/null
+        @@ -1 +0,0 @@
+        -gone
+        diff --git a/added file b/added file
+        new file mode 100644
+        index 0000000000..e69de29bb2
+    "#};
+    let new_tree = apply_to_tree(repo, &tree, patch);
+    assert_eq!(file_value(repo, &new_tree, old_path), None);
+    assert_eq!(
+        file_value(repo, &new_tree, new_path),
+        Some((b"1\ntwo\n3\n".to_vec(), true))
+    );
+    assert_eq!(
+        file_value(repo, &new_tree, copied_path),
+        Some((b"1\n2\n3\n".to_vec(), false))
+    );
+    assert_eq!(file_value(repo, &new_tree, deleted_path), None);
+    assert_eq!(
+        file_value(repo, &new_tree, added_path),
+        Some((vec![], false))
+    );
+}
+
+#[test]
+fn test_apply_git_binary_patch() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let small_path = RepoPath::from_internal_string("bin");
+    let large_path = RepoPath::from_internal_string("big");
+    let large_contents = (0..600).map(|i| (i * 7 % 256) as u8).collect_vec();
+    let tree = create_binary_tree(
+        repo,
+        &[(small_path, b"abc\0def"), (large_path, &large_contents)],
+    );
+
+    // Generated by `git diff --binary`
+    let patch = indoc! {"
+        diff --git a/bin b/bin
+        index 96db3e1c616a9650209b6a2491a6a663261c7edf..2d9e3ca3a5a619d53e379f0d213d2085280d5d70 100644
+        GIT binary patch
+        literal 14
+        VcmYdHN@hrjNHa7tHZe6b2LK+v1GNAE
+
+        literal 7
+        OcmYdHN@hq&O#=W4MFLg;
+
+        diff --git a/big b/big
+        index 080523df4b001f7fffcd991cd619bff56c947c02..30afeedd7705c86fe4eb3452c834d2941a3cf33c 100644
+        GIT binary patch
+        delta 18
+        Zcmcb?a))Ju4kIJOMqO=2mXgHG8~`?s1ycY3
+
+        delta 13
+        Ucmcb^a)V`q4&y={#)&#Q03%HVQ2+n{
+
+    "};
+    let new_tree = apply_to_tree(repo, &tree, patch);
+    assert_eq!(
+        file_value(repo, &new_tree, small_path),
+        Some((b"abc\0dXf1234567".to_vec(), false))
+    );
+    let mut expected_large_contents = large_contents.clone();
+    expected_large_contents[300] = 0;
+    expected_large_contents.extend(b"tail");
+    assert_eq!(
+        file_value(repo, &new_tree, large_path),
+        Some((expected_large_contents, false))
+    );
+
+    // The delta doesn't apply to different contents
+    let tree = create_binary_tree(repo, &[(small_path, b"abc\0def"), (large_path, b"other")]);
+    let result = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap();
+    assert_eq!(
+        result,
+        ApplyPatchResult::Conflicts(vec![ApplyPatchConflict {
+            path: large_path.to_owned(),
+            reason: ApplyPatchConflictReason::ContentMismatch,
+        }])
+    );
+}
+
+#[test]
+fn test_apply_conflicts() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let path = RepoPath::from_internal_string("file");
+    let tree = create_tree(repo, &[(path, "a\nb\nc\n")]);
+
+    let patch = indoc! {"
+        diff --git a/file b/file
+        --- a/file
+        +++ b/file
+        @@ -1,2 +1,2 @@
+         a
+        -x
+        +y
+        diff --git a/missing b/missing
+        --- a/missing
+        +++ b/missing
+        @@ -1 +1 @@
+        -a
+        +b
+        diff --git a/file b/file
+        new file mode 100644
+        --- /dev/null
+        +++ b/file
+        @@ -0,0 +1 @@
+        +new
+        diff --git a/file b/file
+        Binary files a/file and b/file differ
+    "};
+    let result = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap();
+    let conflict = |path: &str, reason| ApplyPatchConflict {
+        path: RepoPathBuf::from_internal_string(path),
+        reason,
+    };
+    assert_eq!(
+        result,
+        ApplyPatchResult::Conflicts(vec![
+            conflict("file", ApplyPatchConflictReason::HunkMismatch(1)),
+            conflict("missing", ApplyPatchConflictReason::FileNotFound),
+            conflict("file", ApplyPatchConflictReason::FileExists),
+            conflict("file", ApplyPatchConflictReason::MissingBinaryData),
+        ])
+    );
+
+    // Hunks starting past the end of the file don't apply, even if the start
+    // would overflow or is too far away to search for the hunk
+    let patch = indoc! {"
+        --- a/file
+        +++ b/file
+        @@ -18446744073709551615,3 +1,3 @@
+         a
+         b
+        -c
+        +x
+    "};
+    let result = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap();
+    assert_eq!(
+        result,
+        ApplyPatchResult::Conflicts(vec![conflict(
+            "file",
+            ApplyPatchConflictReason::HunkMismatch(18446744073709551615)
+        )])
+    );
+    let patch = indoc! {"
+        --- a/file
+        +++ b/file
+        @@ -1000000000000,1 +1000000000000,1 @@
+        -a
+        +x
+    "};
+    let result = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap();
+    assert_eq!(
+        result,
+        ApplyPatchResult::Conflicts(vec![conflict(
+            "file",
+            ApplyPatchConflictReason::HunkMismatch(1000000000000)
+        )])
+    );
+
+    // Malformed patches are errors
+    let patch = indoc! {"
+        --- a/file
+        +++ b/file
+        @@ -1,2 +1,2 @@
+         a
+    "};
+    let err = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap_err();
+    insta::assert_snapshot!(err, @"Invalid patch at line 4: Truncated hunk");
+    let patch = indoc! {"
+        diff --git a/file b/file
+        deleted file mode 100644
+        new file mode 100644
+    "};
+    let err = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap_err();
+    insta::assert_snapshot!(err, @r"
+    Invalid patch at line 3: File is both deleted and new
+    ");
+    let patch = indoc! {"
+        diff --git a/file b/file
+        index 96db3e1c616a9650209b6a2491a6a663261c7edf..2d9e3ca3a5a619d53e379f0d213d2085280d5d70 100644
+        GIT binary patch
+        literal 1000000000000000000
+        VcmYdHN@hrjNHa7tHZe6b2LK+v1GNAE
+
+    "};
+    let err = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap_err();
+    insta::assert_snapshot!(err, @r"
+    Invalid patch at line 6: Binary hunk size mismatch
+    ");
+
+    // Delta sizes from the patch aren't trusted either
+    let tree = create_binary_tree(repo, &[(path, b"abc\0def")]);
+    let patch = indoc! {"
+        diff --git a/file b/file
+        index 96db3e1c616a9650209b6a2491a6a663261c7edf..2d9e3ca3a5a619d53e379f0d213d2085280d5d70 100644
+        GIT binary patch
+        delta 12
+        Nc${NzfC2}`3IHFP1i=6R
+
+    "};
+    let result = apply(repo.as_ref(), &tree, patch.as_bytes()).unwrap();
+    assert_eq!(
+        result,
+        ApplyPatchResult::Conflicts(vec![conflict(
+            "file",
+            ApplyPatchConflictReason::ContentMismatch
+        )])
+    );
+}
@@ -0,0 +1,237 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::backend::FileId;
+use jj_lib::backend::TreeValue;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::merge::Merge;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::resolution_cache::ResolutionCache;
+use jj_lib::rewrite::rebase_commit;
+use jj_lib::settings::UserSettings;
+use test_case::test_case;
+use testutils::create_tree;
+use testutils::write_file;
+use testutils::TestRepo;
+
+#[test]
+fn test_resolution_cache_insert_get() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+    let temp_dir = testutils::new_temp_dir();
+    let cache = ResolutionCache::load(&temp_dir.path().join("cache"));
+
+    let path = RepoPath::from_internal_string("file");
+    let file = |contents: &str| {
+        Some(TreeValue::File {
+            id: write_file(store, path, contents),
+            executable: false,
+        })
+    };
+    let conflict = Merge::from_removes_adds(vec![file("base\n")], vec![file("a\n"), file("b\n")]);
+    let other_conflict =
+        Merge::from_removes_adds(vec![file("base\n")], vec![file("a\n"), file("c\n")]);
+    assert_eq!(cache.get(&conflict).unwrap(), None);
+
+    let resolution = file("resolved\n").unwrap();
+    cache.insert(path, &conflict, &resolution).unwrap();
+    assert_eq!(cache.get(&conflict).unwrap(), Some(resolution.clone()));
+    assert_eq!(cache.get(&other_conflict).unwrap(), None);
+
+    // A later resolution replaces the earlier one
+    let new_resolution = TreeValue::File {
+        id: write_file(store, path, "resolved again\n"),
+        executable: true,
+    };
+    cache.insert(path, &conflict, &new_resolution).unwrap();
+    assert_eq!(cache.get(&conflict).unwrap(), Some(new_resolution));
+
+    // Equivalent conflicts share the resolution
+    let nested = Merge::from_removes_adds(
+        vec![file("base\n"), file("x\n")],
+        vec![file("a\n"), file("x\n"), file("b\n")],
+    );
+    assert!(cache.get(&nested).unwrap().is_some());
+}
+
+#[test]
+fn test_resolution_cache_missing_file() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let store = repo.store();
+    let temp_dir = testutils::new_temp_dir();
+    let cache = ResolutionCache::load(&temp_dir.path().join("cache"));
+
+    let path = RepoPath::from_internal_string("file");
+    let tree_base = create_tree(
+        repo,
+        &[(
+            path, "base
+",
+        )],
+    );
+    let tree_a = create_tree(
+        repo,
+        &[(
+            path, "a
+",
+        )],
+    );
+    let tree_b = create_tree(
+        repo,
+        &[(
+            path, "b
+",
+        )],
+    );
+    let other_path = RepoPath::from_internal_string("other");
+    let other_tree_a = create_tree(
+        repo,
+        &[(
+            other_path, "a
+",
+        )],
+    );
+    let other_tree_b = create_tree(
+        repo,
+        &[(
+            other_path, "b
+",
+        )],
+    );
+    let conflicted_tree = tree_a.merge(&tree_base, &tree_b).unwrap();
+    let conflict = conflicted_tree.path_value(path).unwrap();
+    let other_conflict = other_tree_a
+        .merge(&tree_base, &other_tree_b)
+        .unwrap()
+        .path_value(other_path)
+        .unwrap();
+    assert!(!conflict.is_resolved());
+    assert!(!other_conflict.is_resolved());
+
+    // The resolution refers to a file which doesn't exist in the store (e.g.
+    // because it was garbage-collected), so it isn't used, and it's removed.
+    let missing_resolution = TreeValue::File {
+        id: FileId::from_hex("0123456789abcdef0123456789abcdef01234567"),
+        executable: false,
+    };
+    cache.insert(path, &conflict, &missing_resolution).unwrap();
+    let resolved_tree = cache.resolve_tree(&conflicted_tree).unwrap();
+    assert_eq!(resolved_tree.id(), conflicted_tree.id());
+    assert_eq!(cache.get(&conflict).unwrap(), None);
+
+    // GC removes the entries which can't be used anymore
+    let resolution = TreeValue::File {
+        id: write_file(
+            store,
+            path,
+            "resolved
+",
+        ),
+        executable: false,
+    };
+    cache.insert(path, &conflict, &resolution).unwrap();
+    cache
+        .insert(other_path, &other_conflict, &missing_resolution)
+        .unwrap();
+    std::fs::write(
+        cache.dir().join("garbage"),
+        "garbage
+",
+    )
+    .unwrap();
+    cache.gc(store).unwrap();
+    assert_eq!(cache.get(&conflict).unwrap(), Some(resolution));
+    assert_eq!(cache.get(&other_conflict).unwrap(), None);
+    assert!(!cache.dir().join("garbage").exists());
+}
+
+#[test_case(false; "disabled")]
+#[test_case(true; "enabled")]
+fn test_rebase_reuses_resolution(reuse_resolutions: bool) {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(
+            ConfigSource::User,
+            &format!("merge.reuse-resolutions = {reuse_resolutions}"),
+        )
+        .unwrap(),
+    );
+    let settings = UserSettings::from_config(config).unwrap();
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    // B and C modify the same line
+    // B C
+    // |/
+    // A
+    let path = RepoPath::from_internal_string("file");
+    let tree_a = create_tree(repo, &[(path, "a\n")]);
+    let tree_b = create_tree(repo, &[(path, "b\n")]);
+    let tree_c = create_tree(repo, &[(path, "c\n")]);
+    let mut tx = repo.start_transaction();
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![store.root_commit_id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_b.id())
+        .write()
+        .unwrap();
+    let commit_c = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_c.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    // Rebasing C onto B conflicts, and the user resolves the conflict
+    let mut tx = repo.start_transaction();
+    let rebased_c =
+        rebase_commit(tx.repo_mut(), commit_c.clone(), vec![commit_b.id().clone()]).unwrap();
+    assert!(rebased_c.has_conflict().unwrap());
+    let tree_resolved = create_tree(&repo, &[(path, "b\nc\n")]);
+    tx.repo_mut()
+        .rewrite_commit(&rebased_c)
+        .set_tree_id(tree_resolved.id())
+        .write()
+        .unwrap();
+    tx.repo_mut().rebase_descendants().unwrap();
+
+    // Nothing is recorded until the transaction is committed
+    let mut tx2 = repo.start_transaction();
+    let rebased_c2 = rebase_commit(
+        tx2.repo_mut(),
+        commit_c.clone(),
+        vec![commit_b.id().clone()],
+    )
+    .unwrap();
+    assert!(rebased_c2.has_conflict().unwrap());
+    drop(tx2);
+    tx.commit("resolve").unwrap();
+
+    // Rebasing C onto B again produces the same conflict
+    let mut tx = repo.start_transaction();
+    let rebased_c = rebase_commit(tx.repo_mut(), commit_c, vec![commit_b.id().clone()]).unwrap();
+    if reuse_resolutions {
+        assert_eq!(*rebased_c.tree_id(), tree_resolved.id());
+    } else {
+        assert!(rebased_c.has_conflict().unwrap());
+    }
+}
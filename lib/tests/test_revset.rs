@@ -884,6 +884,45 @@ fn test_resolve_symbol_git_refs() {
     );
 }
 
+#[test]
+fn test_resolve_symbol_anonymous_heads() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = create_random_commit(mut_repo)
+        .set_change_id(ChangeId::from_hex("0123456789abcdef0123456789abcdef"))
+        .write()
+        .unwrap();
+    let commit2 = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("head-zyxwvuts", RefTarget::normal(commit2.id().clone()));
+    let repo = tx.commit("test").unwrap();
+
+    // Bookmarks take precedence over anonymous heads
+    assert_eq!(
+        repo.view().anonymous_head_name(commit1.id()),
+        Some("head-zyxwvuts")
+    );
+    assert_eq!(
+        resolve_symbol(repo.as_ref(), "head-zyxwvuts").unwrap(),
+        vec![commit2.id().clone()]
+    );
+
+    let mut tx = repo.start_transaction();
+    tx.repo_mut()
+        .set_local_bookmark_target("head-zyxwvuts", RefTarget::absent());
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        resolve_symbol(repo.as_ref(), "head-zyxwvuts").unwrap(),
+        vec![commit1.id().clone()]
+    );
+    assert_matches!(
+        resolve_symbol(repo.as_ref(), "head-zyxwvutsr"),
+        Err(RevsetResolutionError::NoSuchRevision { .. })
+    );
+}
+
 fn resolve_commit_ids(repo: &dyn Repo, revset_str: &str) -> Vec<CommitId> {
     try_resolve_commit_ids(repo, revset_str).unwrap()
 }
@@ -2703,6 +2742,74 @@ fn test_evaluate_expression_description() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_description_contains_trailer() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let commit1 = create_random_commit(mut_repo)
+        .set_description("commit 1\n\nSigned-off-by: Some One <some.one@example.com>\n")
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_description(
+            "commit 2\n\nbody\n\nFixes: #123\nsigned-off-by: Other <other@example.com>\n",
+        )
+        .write()
+        .unwrap();
+    // Not a trailer paragraph
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_description("Fixes: #456\n")
+        .write()
+        .unwrap();
+
+    // Keys are compared case-insensitively
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description_contains_trailer('Signed-off-by')"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description_contains_trailer(fixes)"),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description_contains_trailer(Fix)"),
+        vec![]
+    );
+    // Values are matched by the string pattern
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "description_contains_trailer(signed-off-by, 'some.one@')"
+        ),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "description_contains_trailer(Fixes, exact:'#456')"
+        ),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "description_contains_trailer(Fixes, exact:'#123')"
+        ),
+        vec![commit2.id().clone()]
+    );
+    // Unlike description(), only the trailer paragraph is searched
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description('Fixes:')"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_author() {
     let test_repo = TestRepo::init();
@@ -16,8 +16,10 @@ use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
+use jj_lib::rewrite::duplicate_commit;
 use jj_lib::rewrite::duplicate_commits;
 use jj_lib::transaction::Transaction;
+use maplit::hashset;
 use testutils::create_tree;
 use testutils::TestRepo;
 
@@ -177,3 +179,53 @@ fn test_duplicate_linear_contents() {
         &tree_1_2.id()
     );
 }
+
+#[test]
+fn test_duplicate_commit() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let path_1 = RepoPath::from_internal_string("file1");
+    let path_2 = RepoPath::from_internal_string("file2");
+    let tree_1 = create_tree(repo, &[(path_1, "content1")]);
+    let tree_2 = create_tree(repo, &[(path_2, "content2")]);
+    let tree_1_2 = create_tree(repo, &[(path_1, "content1"), (path_2, "content2")]);
+
+    // C [=file1]
+    // B [+file2]   A [+file1]
+    //  \          /
+    //   root
+    let mut tx = repo.start_transaction();
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_1.id())
+        .set_description("a")
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_2.id())
+        .write()
+        .unwrap();
+    let commit_c = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_1.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let mut tx = repo.start_transaction();
+    let new_commit =
+        duplicate_commit(tx.repo_mut(), &commit_a, vec![commit_b.id().clone()]).unwrap();
+    assert_ne!(new_commit.change_id(), commit_a.change_id());
+    assert_eq!(new_commit.parent_ids(), [commit_b.id().clone()]);
+    assert_eq!(new_commit.tree_id(), &tree_1_2.id());
+    assert_eq!(new_commit.description(), "a");
+
+    // The original commit isn't rewritten, so its descendants stay in place
+    assert!(!tx.repo().has_rewrites());
+    assert_eq!(
+        *tx.repo().view().heads(),
+        hashset! {commit_c.id().clone(), new_commit.id().clone()}
+    );
+}
@@ -0,0 +1,125 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::matchers::PrefixMatcher;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::revset::RevsetExpression;
+use jj_lib::search::grep;
+use jj_lib::search::GrepMatch;
+use jj_lib::search::GrepScope;
+use regex::bytes::Regex;
+use testutils::create_tree;
+use testutils::TestRepo;
+
+fn format_matches(matches: &[GrepMatch], commits: &[&Commit]) -> Vec<String> {
+    matches
+        .iter()
+        .map(|m| {
+            let commit_index = commits.iter().position(|c| **c == m.commit).unwrap();
+            format!(
+                "{commit_index} {}:{}{} {}",
+                m.path.as_internal_file_string(),
+                if m.removed { "-" } else { "+" },
+                m.line_number,
+                m.line
+            )
+        })
+        .collect_vec()
+}
+
+#[test]
+fn test_grep() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    let file1 = RepoPath::from_internal_string("dir/file1");
+    let file2 = RepoPath::from_internal_string("file2");
+    let binary = RepoPath::from_internal_string("binary");
+
+    let mut tx = repo.start_transaction();
+    let mut new_commit = |parent: &Commit, files: &[(&RepoPath, &str)]| {
+        tx.repo_mut()
+            .new_commit(vec![parent.id().clone()], create_tree(repo, files).id())
+            .write()
+            .unwrap()
+    };
+    let commit_a = new_commit(
+        &store.root_commit(),
+        &[
+            (file1, "foo 1\nbar\nfoo 2\n"),
+            (file2, "foo 3\n"),
+            (binary, "foo\0"),
+        ],
+    );
+    let commit_b = new_commit(
+        &commit_a,
+        &[(file1, "foo 1\nbaz\nfoo 4\n"), (file2, "foo 3\n")],
+    );
+    let commits = [&commit_a, &commit_b];
+    let revset = RevsetExpression::commits(vec![commit_a.id().clone(), commit_b.id().clone()]);
+    let pattern = Regex::new("^foo").unwrap();
+
+    let matches = grep(
+        tx.repo(),
+        &revset,
+        &pattern,
+        &EverythingMatcher,
+        GrepScope::Tree,
+    )
+    .unwrap();
+    insta::assert_debug_snapshot!(format_matches(&matches, &commits), @r#"
+    [
+        "1 dir/file1:+1 foo 1",
+        "1 dir/file1:+3 foo 4",
+        "1 file2:+1 foo 3",
+        "0 dir/file1:+1 foo 1",
+        "0 dir/file1:+3 foo 2",
+        "0 file2:+1 foo 3",
+    ]
+    "#);
+
+    let matches = grep(
+        tx.repo(),
+        &revset,
+        &pattern,
+        &PrefixMatcher::new([RepoPath::from_internal_string("dir")]),
+        GrepScope::Tree,
+    )
+    .unwrap();
+    assert!(matches.iter().all(|m| m.path.as_ref() == file1));
+
+    // Only the lines changed by each commit are searched
+    let matches = grep(
+        tx.repo(),
+        &revset,
+        &pattern,
+        &EverythingMatcher,
+        GrepScope::Diff,
+    )
+    .unwrap();
+    insta::assert_debug_snapshot!(format_matches(&matches, &commits), @r#"
+    [
+        "1 dir/file1:-3 foo 2",
+        "1 dir/file1:+3 foo 4",
+        "0 dir/file1:+1 foo 1",
+        "0 dir/file1:+3 foo 2",
+        "0 file2:+1 foo 3",
+    ]
+    "#);
+}
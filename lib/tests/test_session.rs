@@ -0,0 +1,187 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use indoc::indoc;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::git;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::session::FileChange;
+use jj_lib::session::FileChangeKind;
+use jj_lib::session::Session;
+use jj_lib::settings::UserSettings;
+use testutils::write_working_copy_file;
+use testutils::TestRepoBackend;
+use testutils::TestWorkspace;
+
+fn file_change(path: &str, kind: FileChangeKind) -> FileChange {
+    FileChange {
+        path: RepoPath::from_internal_string(path).to_owned(),
+        kind,
+    }
+}
+
+#[test]
+fn test_session_load() {
+    let settings = testutils::user_settings();
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Git, &settings);
+    let repo = &test_workspace.repo;
+    let workspace = &test_workspace.workspace;
+
+    let session = Session::load(&settings, workspace.workspace_root()).unwrap();
+    assert_eq!(session.repo().op_id(), repo.op_id());
+    assert_eq!(
+        session.working_copy_commit().unwrap().id(),
+        repo.view()
+            .get_wc_commit_id(workspace.workspace_id())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_session_status() {
+    let test_workspace = TestWorkspace::init();
+    let root_commit = test_workspace.repo.store().root_commit();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let mut session = Session::from_workspace(test_workspace.workspace).unwrap();
+
+    // Nothing has changed
+    let status = session.status().unwrap();
+    assert_eq!(status.parents, vec![root_commit]);
+    assert_eq!(status.changed_files, vec![]);
+    let old_op_id = session.repo().op_id().clone();
+
+    // New files are snapshotted in a new operation
+    let file_path = RepoPath::from_internal_string("file");
+    let dir_file_path = RepoPath::from_internal_string("dir/file");
+    write_working_copy_file(&workspace_root, file_path, "contents");
+    write_working_copy_file(&workspace_root, dir_file_path, "contents");
+    let status = session.status().unwrap();
+    assert_eq!(
+        status.changed_files,
+        vec![
+            file_change("dir/file", FileChangeKind::Added),
+            file_change("file", FileChangeKind::Added),
+        ]
+    );
+    assert_eq!(status.untracked_paths, vec![]);
+    assert_eq!(session.repo().operation().parent_ids(), [old_op_id]);
+    assert!(session.repo().operation().metadata().is_snapshot);
+
+    // Changes are relative to the new working-copy commit
+    let parent = session.working_copy_commit().unwrap();
+    session.new_child().unwrap();
+    write_working_copy_file(&workspace_root, dir_file_path, "modified");
+    std::fs::remove_file(file_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    let status = session.status().unwrap();
+    assert_eq!(status.parents, vec![parent]);
+    assert_eq!(
+        status.changed_files,
+        vec![
+            file_change("dir/file", FileChangeKind::Modified),
+            file_change("file", FileChangeKind::Removed),
+        ]
+    );
+}
+
+#[test]
+fn test_session_snapshot_settings() {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(
+            ConfigSource::User,
+            indoc! {r#"
+                snapshot.auto-track = "glob:*.rs"
+                snapshot.max-new-file-size = 10
+            "#},
+        )
+        .unwrap(),
+    );
+    let settings = UserSettings::from_config(config).unwrap();
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Git, &settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let git_repo_path = git::get_git_backend(test_workspace.repo.store())
+        .unwrap()
+        .git_repo_path()
+        .to_owned();
+    std::fs::create_dir_all(git_repo_path.join("info")).unwrap();
+    std::fs::write(git_repo_path.join("info").join("exclude"), "excluded.rs\n").unwrap();
+    let mut session = Session::from_workspace(test_workspace.workspace).unwrap();
+
+    // Only small files matching `snapshot.auto-track` are tracked, and files
+    // excluded by the backing Git repo are ignored
+    for (path, contents) in [
+        ("tracked.rs", "small"),
+        ("large.rs", "larger than the limit"),
+        ("not-auto-tracked.txt", "small"),
+        ("excluded.rs", "small"),
+    ] {
+        write_working_copy_file(
+            &workspace_root,
+            RepoPath::from_internal_string(path),
+            contents,
+        );
+    }
+    let status = session.status().unwrap();
+    assert_eq!(
+        status.changed_files,
+        vec![file_change("tracked.rs", FileChangeKind::Added)]
+    );
+    assert_eq!(
+        status.untracked_paths,
+        ["large.rs", "not-auto-tracked.txt"]
+            .map(|path| RepoPath::from_internal_string(path).to_owned())
+    );
+}
+
+#[test]
+fn test_session_describe() {
+    let test_workspace = TestWorkspace::init();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let mut session = Session::from_workspace(test_workspace.workspace).unwrap();
+
+    // Pending changes are snapshotted before the commit is rewritten
+    let file_path = RepoPath::from_internal_string("file");
+    write_working_copy_file(&workspace_root, file_path, "contents");
+    let commit = session.describe("first").unwrap();
+    assert_eq!(commit.description(), "first");
+    assert!(commit
+        .tree()
+        .unwrap()
+        .path_value(file_path)
+        .unwrap()
+        .is_present());
+    assert_eq!(session.working_copy_commit().unwrap(), commit);
+    assert_eq!(
+        session.repo().operation().metadata().description,
+        "describe working copy"
+    );
+
+    // The working copy was updated to the rewritten commit, so there's nothing
+    // to snapshot
+    let op_id = session.repo().op_id().clone();
+    let status = session.status().unwrap();
+    assert_eq!(status.working_copy_commit, commit);
+    assert_eq!(session.repo().op_id(), &op_id);
+
+    let child = session.new_child().unwrap();
+    assert_eq!(child.parent_ids(), [commit.id().clone()]);
+    assert_eq!(child.tree_id(), commit.tree_id());
+    let child = session.describe("second").unwrap();
+    assert_eq!(child.description(), "second");
+    assert_eq!(child.parent_ids(), [commit.id().clone()]);
+}
@@ -0,0 +1,130 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::backend::TreeValue;
+use jj_lib::op_store::RefTarget;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::speculative_repo::SpeculativeRepo;
+use maplit::hashset;
+use testutils::create_tree;
+use testutils::read_file;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+
+#[test]
+fn test_speculative_rewrite_is_discarded() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    let path = RepoPath::from_internal_string("dir/file");
+
+    let mut tx = repo.start_transaction();
+    let tree_a = create_tree(repo, &[(path, "a")]);
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let mut speculative = SpeculativeRepo::new(&repo).unwrap();
+    let tree_a2 = create_tree(speculative.repo().base_repo(), &[(path, "a2")]);
+    let mut_repo = speculative.repo_mut();
+    let commit_a2 = mut_repo
+        .rewrite_commit(&commit_a)
+        .set_tree_id(tree_a2.id())
+        .write()
+        .unwrap();
+    assert_eq!(mut_repo.rebase_descendants().unwrap(), 1);
+    let [head_id] = mut_repo.view().heads().iter().collect::<Vec<_>>()[..] else {
+        panic!("expected a single head");
+    };
+    assert_ne!(head_id, commit_b.id());
+    let head = mut_repo.store().get_commit(head_id).unwrap();
+    assert_eq!(head.parent_ids(), [commit_a2.id().clone()]);
+    assert_eq!(speculative.num_new_commits(), 2);
+
+    // Nothing was written to the real store.
+    assert!(repo.store().get_commit(commit_a2.id()).is_err());
+    assert!(repo.store().get_commit(head.id()).is_err());
+    assert!(repo.store().get_root_tree(&tree_a2.id()).is_err());
+    drop(speculative);
+    assert_eq!(*repo.view().heads(), hashset! {commit_b.id().clone()});
+}
+
+#[test]
+fn test_speculative_rewrite_promote() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+    let path = RepoPath::from_internal_string("dir/file");
+
+    let mut tx = repo.start_transaction();
+    let tree_a = create_tree(repo, &[(path, "a")]);
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    tx.repo_mut()
+        .set_local_bookmark_target("main", RefTarget::normal(commit_b.id().clone()));
+    let repo = tx.commit("test").unwrap();
+
+    let mut speculative = SpeculativeRepo::new(&repo).unwrap();
+    let tree_a2 = create_tree(speculative.repo().base_repo(), &[(path, "a2")]);
+    let mut_repo = speculative.repo_mut();
+    mut_repo
+        .rewrite_commit(&commit_a)
+        .set_tree_id(tree_a2.id())
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    mut_repo.rebase_descendants().unwrap();
+
+    let (tx, commit_ids) = speculative.promote().unwrap();
+    assert_eq!(commit_ids.len(), 2);
+    // The Git backend computes different ids than the in-memory overlay
+    assert!(commit_ids
+        .iter()
+        .all(|(speculative_id, new_id)| speculative_id != new_id));
+    let repo = tx.commit("promote").unwrap();
+
+    let heads = repo.view().heads().iter().collect::<Vec<_>>();
+    assert_eq!(heads.len(), 1);
+    let new_b = repo.store().get_commit(heads[0]).unwrap();
+    assert_eq!(new_b.change_id(), commit_b.change_id());
+    assert_eq!(
+        repo.view().get_local_bookmark("main").as_normal(),
+        Some(new_b.id())
+    );
+    let new_a = new_b.parents().next().unwrap().unwrap();
+    assert_eq!(new_a.change_id(), commit_a.change_id());
+    assert_eq!(new_a.description(), "rewritten");
+    assert_eq!(new_a.predecessor_ids(), [commit_a.id().clone()]);
+    assert_eq!(new_b.tree_id(), new_a.tree_id());
+    let value = new_a.tree().unwrap().path_value(path).unwrap();
+    let TreeValue::File { id, .. } = value.into_resolved().unwrap().unwrap() else {
+        panic!("expected a file");
+    };
+    assert_eq!(read_file(repo.store(), path, &id), b"a2");
+}
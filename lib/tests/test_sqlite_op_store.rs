@@ -0,0 +1,266 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::path::Path;
+use std::slice;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use assert_matches::assert_matches;
+use jj_lib::local_backend::LocalBackend;
+use jj_lib::lock::FileLockError;
+use jj_lib::lock::LockKind;
+use jj_lib::lock::LockManager;
+use jj_lib::object_id::HexPrefix;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::object_id::PrefixResolution;
+use jj_lib::op_heads_store::OpHeadsStore as _;
+use jj_lib::op_heads_store::OpHeadsStoreError;
+use jj_lib::op_store::OpStoreError;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo::RepoLoader;
+use jj_lib::repo::StoreFactories;
+use jj_lib::settings::UserSettings;
+use jj_lib::signing::Signer;
+use jj_lib::sqlite_op_heads_store::SqliteOpHeadsStore;
+use jj_lib::sqlite_op_store::SqliteOpStore;
+use testutils::write_random_commit;
+
+fn init_sqlite_repo(settings: &UserSettings, repo_path: &Path) -> Arc<ReadonlyRepo> {
+    ReadonlyRepo::init(
+        settings,
+        repo_path,
+        &|_settings, store_path| Ok(Box::new(LocalBackend::init(store_path))),
+        Signer::from_settings(settings).unwrap(),
+        ReadonlyRepo::sqlite_op_store_initializer(),
+        ReadonlyRepo::sqlite_op_heads_store_initializer(),
+        ReadonlyRepo::default_index_store_initializer(),
+        ReadonlyRepo::default_submodule_store_initializer(),
+    )
+    .unwrap()
+}
+
+fn load_repo_loader(settings: &UserSettings, repo_path: &Path) -> RepoLoader {
+    RepoLoader::init_from_file_system(settings, repo_path, &StoreFactories::default()).unwrap()
+}
+
+#[test]
+fn test_init_and_reload() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo = init_sqlite_repo(&settings, temp_dir.path());
+    assert_eq!(repo.op_store().name(), SqliteOpStore::name());
+    assert_eq!(repo.op_heads_store().name(), SqliteOpHeadsStore::name());
+
+    let mut tx = repo.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    let loaded_repo = load_repo_loader(&settings, temp_dir.path())
+        .load_at_head()
+        .unwrap();
+    assert_eq!(loaded_repo.op_store().name(), SqliteOpStore::name());
+    assert_eq!(loaded_repo.op_id(), repo.op_id());
+    assert_eq!(loaded_repo.operation(), repo.operation());
+    assert!(loaded_repo.view().heads().contains(commit.id()));
+}
+
+#[test]
+fn test_concurrent_operations() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo = init_sqlite_repo(&settings, temp_dir.path());
+
+    let mut tx1 = repo.start_transaction();
+    let commit1 = write_random_commit(tx1.repo_mut());
+    let mut tx2 = repo.start_transaction();
+    let commit2 = write_random_commit(tx2.repo_mut());
+    let op_id1 = tx1.commit("op 1").unwrap().op_id().clone();
+    let op_id2 = tx2.commit("op 2").unwrap().op_id().clone();
+
+    // Both operations are recorded as heads until the next load merges them.
+    let mut op_heads = repo.op_heads_store().get_op_heads().unwrap();
+    op_heads.sort();
+    let mut expected_op_heads = vec![op_id1.clone(), op_id2.clone()];
+    expected_op_heads.sort();
+    assert_eq!(op_heads, expected_op_heads);
+
+    let merged_repo = load_repo_loader(&settings, temp_dir.path())
+        .load_at_head()
+        .unwrap();
+    assert_eq!(*merged_repo.operation().parent_ids(), [op_id1, op_id2]);
+    assert!(merged_repo.view().heads().contains(commit1.id()));
+    assert!(merged_repo.view().heads().contains(commit2.id()));
+    assert_eq!(
+        repo.op_heads_store().get_op_heads().unwrap(),
+        [merged_repo.op_id().clone()]
+    );
+}
+
+#[test]
+fn test_lock() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo = init_sqlite_repo(&settings, temp_dir.path());
+    let op_heads_store = repo.op_heads_store().clone();
+
+    // Another process can't take the lock while it's held.
+    let lock = op_heads_store.lock().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let other_op_heads_store = op_heads_store.clone();
+    let handle = thread::spawn(move || {
+        let _lock = other_op_heads_store.lock().unwrap();
+        sender.send(()).unwrap();
+    });
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+    drop(lock);
+    receiver.recv().unwrap();
+    handle.join().unwrap();
+
+    // Transactions can be published once the lock is released.
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        repo.op_heads_store().get_op_heads().unwrap(),
+        [repo.op_id().clone()]
+    );
+}
+
+#[test]
+fn test_lock_timeout() {
+    let temp_dir = testutils::new_temp_dir();
+    let lock_manager = LockManager::new().with_timeout(Some(Duration::from_millis(100)));
+    let op_heads_store = SqliteOpHeadsStore::init(temp_dir.path(), lock_manager.clone()).unwrap();
+    let other_op_heads_store = SqliteOpHeadsStore::load(temp_dir.path(), lock_manager).unwrap();
+
+    // Another process gives up waiting for the lock after the timeout.
+    let lock = op_heads_store.lock().unwrap();
+    let err = thread::spawn(move || other_op_heads_store.lock().map(drop).unwrap_err())
+        .join()
+        .unwrap();
+    assert_matches!(
+        err,
+        OpHeadsStoreError::Lock(err)
+            if err.downcast_ref::<FileLockError>().unwrap().err.kind() == io::ErrorKind::TimedOut
+    );
+    drop(lock);
+}
+
+#[test]
+fn test_lock_order() {
+    let temp_dir = testutils::new_temp_dir();
+    let lock_manager = LockManager::new();
+    let op_heads_store = SqliteOpHeadsStore::init(temp_dir.path(), lock_manager.clone()).unwrap();
+
+    // The op-heads lock can't be taken while holding the Git repo lock.
+    let order = lock_manager.enter(LockKind::GitRepo).unwrap();
+    assert_matches!(
+        op_heads_store.lock().map(drop),
+        Err(OpHeadsStoreError::Lock(_))
+    );
+    drop(order);
+    op_heads_store.lock().unwrap();
+}
+
+#[test]
+fn test_resolve_operation_id_prefix() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo = init_sqlite_repo(&settings, temp_dir.path());
+    let op_store = repo.op_store();
+    let root_op_id = op_store.root_operation_id().clone();
+
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    let op_id = repo.op_id();
+
+    let resolve = |hex: &str| {
+        op_store
+            .resolve_operation_id_prefix(&HexPrefix::new(hex).unwrap())
+            .unwrap()
+    };
+    assert_eq!(
+        resolve(&op_id.hex()),
+        PrefixResolution::SingleMatch(op_id.clone())
+    );
+    assert_eq!(
+        resolve(&op_id.hex()[..6]),
+        PrefixResolution::SingleMatch(op_id.clone())
+    );
+    assert_eq!(
+        resolve(&root_op_id.hex()),
+        PrefixResolution::SingleMatch(root_op_id.clone())
+    );
+    // The empty prefix matches both the root and the new operation.
+    assert_eq!(resolve(""), PrefixResolution::AmbiguousMatch);
+    let other_hex = if op_id.hex().starts_with('f') {
+        "e"
+    } else {
+        "f"
+    };
+    assert_eq!(resolve(other_hex), PrefixResolution::NoMatch);
+}
+
+#[test]
+fn test_gc() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let repo_0 = init_sqlite_repo(&settings, temp_dir.path());
+    let op_store = repo_0.op_store();
+
+    // Set up operation graph:
+    //
+    // C
+    // | B
+    // |/
+    // A
+    // 0 (root)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction();
+        write_random_commit(tx.repo_mut());
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    let repo_c = random_tx(&repo_a).commit("op C").unwrap();
+
+    // No heads, but all kept by modification time
+    op_store.gc(&[], SystemTime::UNIX_EPOCH).unwrap();
+    for repo in [&repo_a, &repo_b, &repo_c] {
+        op_store.read_operation(repo.op_id()).unwrap();
+    }
+
+    // B is no longer reachable
+    op_store
+        .gc(slice::from_ref(repo_c.op_id()), SystemTime::now())
+        .unwrap();
+    op_store.read_operation(repo_a.op_id()).unwrap();
+    op_store.read_operation(repo_c.op_id()).unwrap();
+    op_store.read_view(repo_c.operation().view_id()).unwrap();
+    assert_matches!(
+        op_store.read_operation(repo_b.op_id()),
+        Err(OpStoreError::ObjectNotFound { .. })
+    );
+    assert_matches!(
+        op_store.read_view(repo_b.operation().view_id()),
+        Err(OpStoreError::ObjectNotFound { .. })
+    );
+}
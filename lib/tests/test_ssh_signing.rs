@@ -126,6 +126,10 @@ fn ssh_signing_roundtrip() {
 
     let check = backend.verify(data, &signature).unwrap();
     assert_eq!(check.status, SigStatus::Good);
+    assert_eq!(
+        check.key.as_deref(),
+        Some("SHA256:CaeelDOMvTqGZPjAS9fdbnACrLg68N1Bb9ux5y6GjGw")
+    );
 
     assert_eq!(check.display.unwrap(), "test@example.com");
 
@@ -0,0 +1,59 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::testing::create_tree;
+use jj_lib::testing::CommitGraphBuilder;
+use jj_lib::testing::TestRepo;
+use maplit::hashset;
+
+#[test]
+fn test_test_repo_commit_graph() {
+    let test_repo = TestRepo::init();
+    assert!(test_repo.repo_path().join("store").is_dir());
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut graph_builder = CommitGraphBuilder::new(tx.repo_mut());
+    let commit_a = graph_builder.initial_commit();
+    let commit_b = graph_builder.commit_with_parents(&[&commit_a]);
+    let commit_c = graph_builder.commit_with_parents(&[&commit_a]);
+    let commit_d = graph_builder.commit_with_parents(&[&commit_b, &commit_c]);
+    let repo = tx.commit("test").unwrap();
+
+    assert_eq!(*repo.view().heads(), hashset! {commit_d.id().clone()});
+    assert_eq!(
+        commit_d.parent_ids(),
+        [commit_b.id().clone(), commit_c.id().clone()]
+    );
+    assert_eq!(
+        commit_a.parent_ids(),
+        [repo.store().root_commit_id().clone()]
+    );
+}
+
+#[test]
+fn test_create_tree() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let path = RepoPath::from_internal_string("dir/file");
+    let tree = create_tree(repo, &[(path, "contents")]);
+    assert!(tree.path_value(path).unwrap().is_present());
+    assert!(tree
+        .path_value(RepoPath::from_internal_string("other"))
+        .unwrap()
+        .is_absent());
+}
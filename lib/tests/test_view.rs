@@ -14,13 +14,16 @@
 
 use std::collections::BTreeMap;
 
+use jj_lib::backend::ChangeId;
 use jj_lib::op_store::BookmarkTarget;
 use jj_lib::op_store::RefTarget;
+use jj_lib::op_store::RemoteConfig;
 use jj_lib::op_store::RemoteRef;
 use jj_lib::op_store::RemoteRefState;
 use jj_lib::op_store::WorkspaceId;
 use jj_lib::refs::RemoteRefSymbol;
 use jj_lib::repo::Repo;
+use jj_lib::view;
 use maplit::btreemap;
 use maplit::hashset;
 use test_case::test_case;
@@ -82,6 +85,85 @@ fn test_heads_merge() {
     assert_eq!(*repo.view().heads(), hashset! {merge.id().clone()});
 }
 
+#[test]
+fn test_view_diff() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = write_random_commit(mut_repo);
+    let commit2 = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("moved", RefTarget::normal(commit1.id().clone()));
+    mut_repo.set_local_bookmark_target("removed", RefTarget::normal(commit1.id().clone()));
+    mut_repo.set_tag_target("v1", RefTarget::normal(commit1.id().clone()));
+    let ws_id = WorkspaceId::default();
+    mut_repo.edit(ws_id.clone(), &commit1).unwrap();
+    let repo1 = tx.commit("test").unwrap();
+
+    let mut tx = repo1.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit3 = write_random_commit(mut_repo);
+    mut_repo.remove_head(commit2.id());
+    mut_repo.set_local_bookmark_target("moved", RefTarget::normal(commit3.id().clone()));
+    mut_repo.set_local_bookmark_target("removed", RefTarget::absent());
+    mut_repo.set_local_bookmark_target("added", RefTarget::normal(commit1.id().clone()));
+    let remote_ref = RemoteRef {
+        target: RefTarget::normal(commit3.id().clone()),
+        state: RemoteRefState::Tracking,
+    };
+    mut_repo.set_remote_bookmark(remote_symbol("moved", "origin"), remote_ref.clone());
+    mut_repo.edit(ws_id.clone(), &commit3).unwrap();
+    let repo2 = tx.commit("test").unwrap();
+
+    let diff = view::diff(repo1.view(), repo2.view());
+    assert_eq!(
+        diff.wc_commits,
+        vec![(&ws_id, (Some(commit1.id()), Some(commit3.id())))]
+    );
+    assert_eq!(diff.added_heads, vec![commit3.id()]);
+    assert_eq!(diff.removed_heads, vec![commit2.id()]);
+    assert_eq!(
+        diff.local_bookmarks,
+        vec![
+            (
+                "added",
+                (
+                    RefTarget::absent_ref(),
+                    &RefTarget::normal(commit1.id().clone())
+                )
+            ),
+            (
+                "moved",
+                (
+                    &RefTarget::normal(commit1.id().clone()),
+                    &RefTarget::normal(commit3.id().clone())
+                )
+            ),
+            (
+                "removed",
+                (
+                    &RefTarget::normal(commit1.id().clone()),
+                    RefTarget::absent_ref()
+                )
+            ),
+        ]
+    );
+    assert_eq!(
+        diff.remote_bookmarks,
+        vec![(
+            remote_symbol("moved", "origin"),
+            (RemoteRef::absent_ref(), &remote_ref)
+        )]
+    );
+    assert!(diff.tags.is_empty());
+    assert!(diff.git_refs.is_empty());
+    assert_eq!(diff.git_head, None);
+    assert!(!diff.is_empty());
+
+    assert!(view::diff(repo2.view(), repo2.view()).is_empty());
+}
+
 #[test]
 fn test_merge_views_heads() {
     // Tests merging of the view's heads (by performing divergent operations).
@@ -429,6 +511,49 @@ fn test_merge_views_git_heads() {
     assert_eq!(repo.view().git_head(), &expected_git_head);
 }
 
+#[test]
+fn test_merge_views_remote_configs() {
+    // Tests merging of remote configs (by performing divergent operations).
+    // If both sides changed a remote, the first one wins.
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let remote_config = |url: &str| RemoteConfig {
+        url: url.to_owned(),
+    };
+
+    let mut tx0 = repo.start_transaction();
+    tx0.repo_mut()
+        .set_remote_config("origin", remote_config("https://example.com/origin0"));
+    tx0.repo_mut()
+        .set_remote_config("upstream", remote_config("https://example.com/upstream0"));
+    tx0.repo_mut()
+        .set_remote_config("fork", remote_config("https://example.com/fork0"));
+    let repo = tx0.commit("test").unwrap();
+
+    let mut tx1 = repo.start_transaction();
+    tx1.repo_mut()
+        .set_remote_config("origin", remote_config("https://example.com/origin1"));
+    tx1.repo_mut()
+        .set_remote_config("upstream", remote_config("https://example.com/upstream1"));
+
+    let mut tx2 = repo.start_transaction();
+    tx2.repo_mut()
+        .set_remote_config("origin", remote_config("https://example.com/origin2"));
+    tx2.repo_mut().remove_remote("fork");
+    tx2.repo_mut()
+        .set_remote_config("new", remote_config("https://example.com/new2"));
+
+    let repo = commit_transactions(vec![tx1, tx2]);
+    assert_eq!(
+        *repo.view().remote_configs(),
+        btreemap! {
+            "new".to_owned() => remote_config("https://example.com/new2"),
+            "origin".to_owned() => remote_config("https://example.com/origin1"),
+            "upstream".to_owned() => remote_config("https://example.com/upstream1"),
+        }
+    );
+}
+
 #[test]
 fn test_merge_views_divergent() {
     // We start with just commit A. Operation 1 rewrites it as A2. Operation 2
@@ -605,3 +730,135 @@ fn test_merge_views_child_on_abandoned(child_first: bool) {
     assert_eq!(commit_c2.change_id(), commit_c.change_id());
     assert_eq!(commit_c2.parent_ids(), vec![commit_a.id().clone()]);
 }
+
+#[test]
+fn test_anonymous_heads() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let workspace_id = WorkspaceId::default();
+    let change_id = |hex| ChangeId::from_hex(hex);
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_anonymous = create_random_commit(mut_repo)
+        .set_change_id(change_id("0123456789abcdef0123456789abcdef"))
+        .write()
+        .unwrap();
+    let commit_similar = create_random_commit(mut_repo)
+        .set_change_id(change_id("0123456789ab00000000000000000000"))
+        .write()
+        .unwrap();
+    let commit_bookmarked = write_random_commit(mut_repo);
+    let commit_wc = write_random_commit(mut_repo);
+    mut_repo.set_local_bookmark_target("main", RefTarget::normal(commit_bookmarked.id().clone()));
+    mut_repo
+        .set_wc_commit(workspace_id.clone(), commit_wc.id().clone())
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    // Names are long enough to tell similar change ids apart
+    assert_eq!(
+        *repo.view().anonymous_heads(),
+        btreemap! {
+            "head-zyxwvutsrqpon".to_owned() => commit_anonymous.id().clone(),
+            "head-zyxwvutsrqpoz".to_owned() => commit_similar.id().clone(),
+        }
+    );
+    assert_eq!(
+        repo.view().anonymous_head_name(commit_anonymous.id()),
+        Some("head-zyxwvutsrqpon")
+    );
+    assert_eq!(
+        repo.view().anonymous_head_name(commit_bookmarked.id()),
+        None
+    );
+
+    // Rewritten heads keep their names, and abandoned ones lose them
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_rewritten = mut_repo
+        .rewrite_commit(&commit_anonymous)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    mut_repo.record_abandoned_commit(&commit_similar);
+    mut_repo.rebase_descendants().unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        *repo.view().anonymous_heads(),
+        btreemap! {
+            "head-zyxwvutsrqpon".to_owned() => commit_rewritten.id().clone(),
+        }
+    );
+
+    // Heads that get a bookmark lose their name
+    let mut tx = repo.start_transaction();
+    tx.repo_mut()
+        .set_local_bookmark_target("feature", RefTarget::normal(commit_rewritten.id().clone()));
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(*repo.view().anonymous_heads(), btreemap! {});
+
+    // Divergent heads aren't named
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit_divergent1 = write_random_commit(mut_repo);
+    let commit_divergent2 = create_random_commit(mut_repo)
+        .set_change_id(commit_divergent1.change_id().clone())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert!(repo.view().heads().contains(commit_divergent1.id()));
+    assert!(repo.view().heads().contains(commit_divergent2.id()));
+    assert_eq!(*repo.view().anonymous_heads(), btreemap! {});
+}
+
+#[test]
+fn test_anonymous_heads_extended_when_ambiguous() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let change_id = |hex| ChangeId::from_hex(hex);
+
+    let mut tx = repo.start_transaction();
+    let commit1 = create_random_commit(tx.repo_mut())
+        .set_change_id(change_id("0123456789abcdef0123456789abcdef"))
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        *repo.view().anonymous_heads(),
+        btreemap! {
+            "head-zyxwvuts".to_owned() => commit1.id().clone(),
+        }
+    );
+
+    // Unrelated new heads don't change the existing names
+    let mut tx = repo.start_transaction();
+    let commit2 = create_random_commit(tx.repo_mut())
+        .set_change_id(change_id("fedcba9876543210fedcba9876543210"))
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        *repo.view().anonymous_heads(),
+        btreemap! {
+            "head-zyxwvuts".to_owned() => commit1.id().clone(),
+            "head-klmnopqr".to_owned() => commit2.id().clone(),
+        }
+    );
+
+    // A new head sharing the prefix of an existing name extends both names
+    let mut tx = repo.start_transaction();
+    let commit3 = create_random_commit(tx.repo_mut())
+        .set_change_id(change_id("01234567ffffffffffffffffffffffff"))
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(
+        *repo.view().anonymous_heads(),
+        btreemap! {
+            "head-zyxwvutsr".to_owned() => commit1.id().clone(),
+            "head-zyxwvutsk".to_owned() => commit3.id().clone(),
+            "head-klmnopqr".to_owned() => commit2.id().clone(),
+        }
+    );
+}
@@ -12,15 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
 use std::thread;
 
 use assert_matches::assert_matches;
 use jj_lib::op_store::WorkspaceId;
+use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::working_copy::CheckoutOptions;
+use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::UpdateStaleError;
 use jj_lib::workspace::default_working_copy_factories;
 use jj_lib::workspace::default_working_copy_factory;
 use jj_lib::workspace::Workspace;
 use jj_lib::workspace::WorkspaceLoadError;
+use testutils::create_tree;
 use testutils::TestEnvironment;
 use testutils::TestWorkspace;
 
@@ -92,6 +99,59 @@ fn test_init_additional_workspace() {
     assert_eq!(same_workspace.workspace_root(), ws2.workspace_root());
 }
 
+#[test]
+fn test_update_stale() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let ws_id = test_workspace.workspace.workspace_id().clone();
+    let file_path = RepoPath::from_internal_string("file");
+    let options = CheckoutOptions::empty_for_test();
+
+    // Check out another commit in the repo, but not in the working copy
+    let edit_file = |repo: &Arc<ReadonlyRepo>, contents: &str| {
+        let tree = create_tree(repo, &[(file_path, contents)]);
+        let mut tx = repo.start_transaction();
+        let commit = tx
+            .repo_mut()
+            .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+            .write()
+            .unwrap();
+        tx.repo_mut().edit(ws_id.clone(), &commit).unwrap();
+        tx.repo_mut().rebase_descendants().unwrap();
+        tx.commit("test").unwrap()
+    };
+    let repo = edit_file(&repo, "contents1");
+
+    // The stale working copy is updated
+    let ws = &mut test_workspace.workspace;
+    let stats = ws.update_stale(&repo, &options).unwrap().unwrap();
+    assert_eq!(stats.added_files, 1);
+    assert_eq!(ws.working_copy().operation_id(), repo.op_id());
+    let disk_path = file_path.to_fs_path_unchecked(&workspace_root);
+    assert_eq!(std::fs::read_to_string(&disk_path).unwrap(), "contents1");
+
+    // The fresh working copy isn't touched
+    assert_matches!(ws.update_stale(&repo, &options), Ok(None));
+
+    // The stale working copy isn't updated if it was modified since its
+    // operation was recorded
+    let old_op_id = ws.working_copy().operation_id().clone();
+    let repo = edit_file(&repo, "contents2");
+    std::fs::write(&disk_path, "modified").unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    locked_ws.finish(old_op_id).unwrap();
+    assert_matches!(
+        ws.update_stale(&repo, &options),
+        Err(UpdateStaleError::ConcurrentOperation)
+    );
+    assert_eq!(std::fs::read_to_string(&disk_path).unwrap(), "modified");
+}
+
 /// Test cross-thread access to a workspace, which requires it to be Send
 #[test]
 fn test_sendable() {
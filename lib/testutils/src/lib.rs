@@ -36,10 +36,6 @@ use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::backend::TreeValue;
 use jj_lib::commit::Commit;
-use jj_lib::commit_builder::CommitBuilder;
-use jj_lib::config::ConfigLayer;
-use jj_lib::config::ConfigSource;
-use jj_lib::config::StackedConfig;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::local_backend::LocalBackend;
 use jj_lib::merged_tree::MergedTree;
@@ -50,7 +46,6 @@ use jj_lib::repo::Repo;
 use jj_lib::repo::RepoLoader;
 use jj_lib::repo::StoreFactories;
 use jj_lib::repo_path::RepoPath;
-use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::rewrite::RebaseOptions;
 use jj_lib::rewrite::RebasedCommit;
 use jj_lib::secret_backend::SecretBackend;
@@ -58,7 +53,6 @@ use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
 use jj_lib::store::Store;
 use jj_lib::transaction::Transaction;
-use jj_lib::tree::Tree;
 use jj_lib::tree_builder::TreeBuilder;
 use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::SnapshotOptions;
@@ -72,6 +66,17 @@ use crate::test_backend::TestBackendFactory;
 pub mod git;
 pub mod test_backend;
 
+pub use jj_lib::testing::base_user_config;
+pub use jj_lib::testing::create_random_commit;
+pub use jj_lib::testing::create_random_tree;
+pub use jj_lib::testing::create_single_tree;
+pub use jj_lib::testing::create_tree;
+pub use jj_lib::testing::user_settings;
+pub use jj_lib::testing::write_file;
+pub use jj_lib::testing::write_normal_file;
+pub use jj_lib::testing::write_random_commit;
+pub use jj_lib::testing::CommitGraphBuilder;
+
 pub fn hermetic_libgit2() {
     // libgit2 respects init.defaultBranch (and possibly other config
     // variables) in the user's config files. Disable access to them to make
@@ -107,27 +112,6 @@ pub fn new_temp_dir() -> TempDir {
         .unwrap()
 }
 
-/// Returns new low-level config object that includes fake user configuration
-/// needed to run basic operations.
-pub fn base_user_config() -> StackedConfig {
-    let config_text = r#"
-        user.name = "Test User"
-        user.email = "test.user@example.com"
-        operation.username = "test-username"
-        operation.hostname = "host.example.com"
-        debug.randomness-seed = 42
-    "#;
-    let mut config = StackedConfig::with_defaults();
-    config.add_layer(ConfigLayer::parse(ConfigSource::User, config_text).unwrap());
-    config
-}
-
-/// Returns new immutable settings object that includes fake user configuration
-/// needed to run basic operations.
-pub fn user_settings() -> UserSettings {
-    UserSettings::from_config(base_user_config()).unwrap()
-}
-
 #[derive(Debug)]
 pub struct TestEnvironment {
     temp_dir: TempDir,
@@ -352,29 +336,6 @@ pub fn read_file(store: &Store, path: &RepoPath, id: &FileId) -> Vec<u8> {
     content
 }
 
-pub fn write_file(store: &Store, path: &RepoPath, contents: &str) -> FileId {
-    store
-        .write_file(path, &mut contents.as_bytes())
-        .block_on()
-        .unwrap()
-}
-
-pub fn write_normal_file(
-    tree_builder: &mut TreeBuilder,
-    path: &RepoPath,
-    contents: &str,
-) -> FileId {
-    let id = write_file(tree_builder.store(), path, contents);
-    tree_builder.set(
-        path.to_owned(),
-        TreeValue::File {
-            id: id.clone(),
-            executable: false,
-        },
-    );
-    id
-}
-
 pub fn write_executable_file(tree_builder: &mut TreeBuilder, path: &RepoPath, contents: &str) {
     let id = write_file(tree_builder.store(), path, contents);
     tree_builder.set(
@@ -395,35 +356,6 @@ pub fn write_symlink(tree_builder: &mut TreeBuilder, path: &RepoPath, target: &s
     tree_builder.set(path.to_owned(), TreeValue::Symlink(id));
 }
 
-pub fn create_single_tree(repo: &Arc<ReadonlyRepo>, path_contents: &[(&RepoPath, &str)]) -> Tree {
-    let store = repo.store();
-    let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
-    for (path, contents) in path_contents {
-        write_normal_file(&mut tree_builder, path, contents);
-    }
-    let id = tree_builder.write_tree().unwrap();
-    store.get_tree(RepoPathBuf::root(), &id).unwrap()
-}
-
-pub fn create_tree(repo: &Arc<ReadonlyRepo>, path_contents: &[(&RepoPath, &str)]) -> MergedTree {
-    MergedTree::resolved(create_single_tree(repo, path_contents))
-}
-
-#[must_use]
-pub fn create_random_tree(repo: &Arc<ReadonlyRepo>) -> MergedTreeId {
-    let number = rand::random::<u32>();
-    let path = RepoPathBuf::from_internal_string(format!("file{number}"));
-    create_tree(repo, &[(&path, "contents")]).id()
-}
-
-pub fn create_random_commit(mut_repo: &mut MutableRepo) -> CommitBuilder<'_> {
-    let tree_id = create_random_tree(mut_repo.base_repo());
-    let number = rand::random::<u32>();
-    mut_repo
-        .new_commit(vec![mut_repo.store().root_commit_id().clone()], tree_id)
-        .set_description(format!("random commit {number}"))
-}
-
 pub fn commit_with_tree(store: &Arc<Store>, tree_id: MergedTreeId) -> Commit {
     let signature = Signature {
         name: "Some One".to_string(),
@@ -481,10 +413,6 @@ pub fn dump_tree(store: &Arc<Store>, tree_id: &MergedTreeId) -> String {
     buf
 }
 
-pub fn write_random_commit(mut_repo: &mut MutableRepo) -> Commit {
-    create_random_commit(mut_repo).write().unwrap()
-}
-
 pub fn write_working_copy_file(workspace_root: &Path, path: &RepoPath, contents: &str) {
     let path = path.to_fs_path(workspace_root).unwrap();
     if let Some(parent) = path.parent() {
@@ -499,31 +427,6 @@ pub fn write_working_copy_file(workspace_root: &Path, path: &RepoPath, contents:
     file.write_all(contents.as_bytes()).unwrap();
 }
 
-pub struct CommitGraphBuilder<'repo> {
-    mut_repo: &'repo mut MutableRepo,
-}
-
-impl<'repo> CommitGraphBuilder<'repo> {
-    pub fn new(mut_repo: &'repo mut MutableRepo) -> Self {
-        CommitGraphBuilder { mut_repo }
-    }
-
-    pub fn initial_commit(&mut self) -> Commit {
-        write_random_commit(self.mut_repo)
-    }
-
-    pub fn commit_with_parents(&mut self, parents: &[&Commit]) -> Commit {
-        let parent_ids = parents
-            .iter()
-            .map(|commit| commit.id().clone())
-            .collect_vec();
-        create_random_commit(self.mut_repo)
-            .set_parents(parent_ids)
-            .write()
-            .unwrap()
-    }
-}
-
 /// Rebase descendants of the rewritten commits. Returns map of original commit
 /// ID to rebased (or abandoned parent) commit ID.
 pub fn rebase_descendants_with_options_return_map(
@@ -536,6 +439,7 @@ pub fn rebase_descendants_with_options_return_map(
         let new_commit_id = match rebased_commit {
             RebasedCommit::Rewritten(new_commit) => new_commit.id().clone(),
             RebasedCommit::Abandoned { parent_id } => parent_id,
+            RebasedCommit::Skipped => old_commit_id.clone(),
         };
         rebased.insert(old_commit_id, new_commit_id);
     })